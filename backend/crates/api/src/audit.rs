@@ -0,0 +1,232 @@
+use axum::http::HeaderMap;
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::db::DatabaseKind;
+use crate::state::AppState;
+
+/// 一条待写入的审计事件；actor/IP/User-Agent 由 [`record`] 从请求头补全。
+#[derive(Debug, Clone, Default)]
+pub struct AuditEvent {
+    action: String,
+    target_type: String,
+    target_id: Option<String>,
+    before: Option<Value>,
+    after: Option<Value>,
+    actor_user_id: Option<i64>,
+}
+
+impl AuditEvent {
+    /// action 形如 `accounts.delete`、`crawl_config.update`；target_type 为被操作对象的类别。
+    pub fn new(action: &str, target_type: &str) -> Self {
+        Self {
+            action: action.to_string(),
+            target_type: target_type.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn target(mut self, id: impl ToString) -> Self {
+        self.target_id = Some(id.to_string());
+        self
+    }
+
+    pub fn before(mut self, v: Value) -> Self {
+        self.before = Some(v);
+        self
+    }
+
+    pub fn after(mut self, v: Value) -> Self {
+        self.after = Some(v);
+        self
+    }
+
+    /// 请求本身不带登录态时（如初始化管理员）显式指定操作者。
+    pub fn actor(mut self, user_id: i64) -> Self {
+        self.actor_user_id = Some(user_id);
+        self
+    }
+}
+
+/// 客户端 IP：由 [`crate::client_ip::stamp`] 中间件按连接地址解析，只在请求来自受信任代理时采信转发头。
+pub fn client_ip(headers: &HeaderMap) -> Option<String> {
+    crate::client_ip::from_headers(headers)
+}
+
+/// 追加一条审计记录。写入失败只记日志，不影响业务请求的结果。
+pub async fn record(state: &AppState, headers: &HeaderMap, event: AuditEvent) {
+    let Some(pool) = state.pool() else {
+        return;
+    };
+    let actor = event.actor_user_id.or_else(|| {
+        crate::routes::auth::authenticate(state, headers)
+            .ok()
+            .and_then(|id| id.parse::<i64>().ok())
+    });
+    let user_agent = headers
+        .get(axum::http::header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.chars().take(512).collect::<String>());
+
+    let sql = if crate::db::database_kind_from_pool(pool) == DatabaseKind::Postgres {
+        r#"
+        INSERT INTO audit_event (
+          id, actor_user_id, action, target_type, target_id, before_json, after_json, ip, user_agent, created_at
+        )
+        VALUES (($1)::uuid,$2,$3,$4,$5,$6,$7,$8,$9,CURRENT_TIMESTAMP)
+        "#
+    } else {
+        r#"
+        INSERT INTO audit_event (
+          id, actor_user_id, action, target_type, target_id, before_json, after_json, ip, user_agent, created_at
+        )
+        VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,CURRENT_TIMESTAMP)
+        "#
+    };
+    let r = sqlx::query(sql)
+        .bind(Uuid::new_v4().to_string())
+        .bind(actor)
+        .bind(&event.action)
+        .bind(&event.target_type)
+        .bind(event.target_id.as_deref())
+        .bind(event.before.as_ref().map(|v| v.to_string()))
+        .bind(event.after.as_ref().map(|v| v.to_string()))
+        .bind(client_ip(headers))
+        .bind(user_agent)
+        .execute(pool)
+        .await;
+    if let Err(e) = r {
+        tracing::warn!(error = %e, action = %event.action, "audit_event insert failed");
+    }
+}
+
+/// 审计查询条件；action 以 `*` 结尾时按前缀匹配（如 `accounts.*`）。
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub actor_user_id: Option<i64>,
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    /// 起止时间（UTC，`%Y-%m-%d %H:%M:%S`），含端点。
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEventRow {
+    pub id: String,
+    pub actor_user_id: Option<i64>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: Option<String>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: String,
+}
+
+/// 按条件倒序查询审计记录。
+pub async fn query_events(
+    pool: &sqlx::AnyPool,
+    f: &AuditFilter,
+) -> Result<Vec<AuditEventRow>, String> {
+    let pg = crate::db::database_kind_from_pool(pool) == DatabaseKind::Postgres;
+    let ts = |n: usize| {
+        if pg {
+            format!("(${n})::timestamptz")
+        } else {
+            format!("${n}")
+        }
+    };
+
+    let mut conds: Vec<String> = Vec::new();
+    let mut binds: Vec<String> = Vec::new();
+    if let Some(actor) = f.actor_user_id {
+        // 数字内联：已解析为 i64，无注入风险，且避免 Any 驱动下 bind 类型混用。
+        conds.push(format!("actor_user_id = {actor}"));
+    }
+    if let Some(action) = f.action.as_deref() {
+        if let Some(prefix) = action.strip_suffix('*') {
+            binds.push(format!("{prefix}%"));
+            conds.push(format!("action LIKE ${}", binds.len()));
+        } else {
+            binds.push(action.to_string());
+            conds.push(format!("action = ${}", binds.len()));
+        }
+    }
+    if let Some(v) = f.target_type.as_deref() {
+        binds.push(v.to_string());
+        conds.push(format!("target_type = ${}", binds.len()));
+    }
+    if let Some(v) = f.target_id.as_deref() {
+        binds.push(v.to_string());
+        conds.push(format!("target_id = ${}", binds.len()));
+    }
+    if let Some(v) = f.since.as_deref() {
+        binds.push(v.to_string());
+        conds.push(format!("created_at >= {}", ts(binds.len())));
+    }
+    if let Some(v) = f.until.as_deref() {
+        binds.push(v.to_string());
+        conds.push(format!("created_at <= {}", ts(binds.len())));
+    }
+    let where_sql = if conds.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conds.join(" AND "))
+    };
+
+    let sql = format!(
+        r#"
+        SELECT
+          CAST(id AS TEXT) as id,
+          actor_user_id,
+          action,
+          target_type,
+          target_id,
+          before_json,
+          after_json,
+          ip,
+          user_agent,
+          CAST(created_at AS TEXT) as created_at
+        FROM audit_event
+        {where_sql}
+        ORDER BY created_at DESC, id DESC
+        LIMIT {} OFFSET {}
+        "#,
+        f.limit.clamp(1, 500),
+        f.offset.max(0)
+    );
+    let mut q = sqlx::query(&sql);
+    for b in &binds {
+        q = q.bind(b);
+    }
+    let rows = q.fetch_all(pool).await.map_err(|e| e.to_string())?;
+
+    let json_col = |r: &sqlx::any::AnyRow, col: &str| {
+        r.try_get::<Option<String>, _>(col)
+            .ok()
+            .flatten()
+            .and_then(|s| serde_json::from_str::<Value>(&s).ok())
+    };
+    Ok(rows
+        .iter()
+        .map(|r| AuditEventRow {
+            id: r.get("id"),
+            actor_user_id: r.try_get("actor_user_id").ok().flatten(),
+            action: r.get("action"),
+            target_type: r.get("target_type"),
+            target_id: r.try_get("target_id").ok().flatten(),
+            before: json_col(r, "before_json"),
+            after: json_col(r, "after_json"),
+            ip: r.try_get("ip").ok().flatten(),
+            user_agent: r.try_get("user_agent").ok().flatten(),
+            created_at: crate::dbfmt::datetime_to_rfc3339(&r.get::<String, _>("created_at")),
+        })
+        .collect())
+}
@@ -0,0 +1,425 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::db::DatabaseKind;
+use crate::dbfmt::format_utc;
+use crate::jwt::{ACCESS_TOKEN_TTL_SECONDS, Claims, REFRESH_TOKEN_TTL_SECONDS};
+use crate::state::AppState;
+
+/// 吊销原因（写入 auth_session.revoke_reason）。
+pub const REASON_LOGOUT: &str = "logout";
+pub const REASON_USER_REVOKED: &str = "user_revoked";
+pub const REASON_REUSE: &str = "reuse_detected";
+pub const REASON_PASSWORD_CHANGE: &str = "password_change";
+
+/// 多副本部署时，其他副本的吊销记录同步到本进程的间隔。
+const SYNC_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 访问令牌校验是同步的，不查库；这里缓存“仍可能持有有效访问令牌”的吊销信息：
+/// - 被吊销的会话 id（保留到吊销后一个访问令牌有效期）；
+/// - 用户的令牌失效时间（不晚于它签发的、不绑定会话的令牌一律拒绝）。
+#[derive(Debug, Default)]
+pub struct SessionRevocations {
+    inner: RwLock<RevocationState>,
+}
+
+#[derive(Debug, Default)]
+struct RevocationState {
+    /// sid -> 可以遗忘的时间（unix 秒）。
+    sessions: HashMap<String, i64>,
+    /// user_id -> 令牌失效时间（unix 秒）。
+    users: HashMap<String, i64>,
+}
+
+impl SessionRevocations {
+    pub fn revoke_session(&self, sid: &str, revoked_at: DateTime<Utc>) {
+        let forget_at = revoked_at.timestamp() + ACCESS_TOKEN_TTL_SECONDS;
+        let mut st = self.inner.write().expect("session revocations lock");
+        st.sessions.insert(sid.to_string(), forget_at);
+    }
+
+    pub fn set_tokens_valid_after(&self, user_id: &str, at: DateTime<Utc>) {
+        let mut st = self.inner.write().expect("session revocations lock");
+        let v = st.users.entry(user_id.to_string()).or_insert(0);
+        *v = (*v).max(at.timestamp());
+    }
+
+    /// 访问令牌是否已被吊销：绑定会话的看会话是否已吊销（修改密码会吊销全部会话）；
+    /// 不绑定会话的看签发时间是否不晚于用户的令牌失效时间（秒级精度，同一秒内签发的也拒绝）。
+    pub fn is_revoked(&self, claims: &Claims) -> bool {
+        let st = self.inner.read().expect("session revocations lock");
+        match claims.sid.as_deref() {
+            Some(sid) => st.sessions.contains_key(sid),
+            None => match st.users.get(&claims.sub) {
+                Some(valid_after) => (claims.iat.unwrap_or(0) as i64) <= *valid_after,
+                None => false,
+            },
+        }
+    }
+
+    /// 丢弃已过访问令牌有效期的记录。
+    fn prune(&self, now: DateTime<Utc>) {
+        let now = now.timestamp();
+        let mut st = self.inner.write().expect("session revocations lock");
+        st.sessions.retain(|_, forget_at| *forget_at > now);
+        st.users
+            .retain(|_, valid_after| *valid_after + ACCESS_TOKEN_TTL_SECONDS > now);
+    }
+}
+
+fn is_pg(pool: &sqlx::AnyPool) -> bool {
+    crate::db::database_kind_from_pool(pool) == DatabaseKind::Postgres
+}
+
+/// 新建会话并返回 `(sid, refresh_jti)`。
+pub async fn create_session(
+    pool: &sqlx::AnyPool,
+    user_id: i64,
+    ip: Option<String>,
+    user_agent: Option<String>,
+) -> Result<(String, String), String> {
+    let sid = Uuid::new_v4().to_string();
+    let jti = Uuid::new_v4().to_string();
+    let now = Utc::now();
+    let expires_at = now + chrono::Duration::seconds(REFRESH_TOKEN_TTL_SECONDS);
+    let sql = if is_pg(pool) {
+        r#"
+        INSERT INTO auth_session (id, user_id, refresh_jti, ip, user_agent, created_at, last_used_at, expires_at)
+        VALUES (($1)::uuid,$2,$3,$4,$5,($6)::timestamptz,($6)::timestamptz,($7)::timestamptz)
+        "#
+    } else {
+        r#"
+        INSERT INTO auth_session (id, user_id, refresh_jti, ip, user_agent, created_at, last_used_at, expires_at)
+        VALUES ($1,$2,$3,$4,$5,$6,$6,$7)
+        "#
+    };
+    sqlx::query(sql)
+        .bind(&sid)
+        .bind(user_id)
+        .bind(&jti)
+        .bind(ip)
+        .bind(user_agent)
+        .bind(format_utc(now))
+        .bind(format_utc(expires_at))
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok((sid, jti))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RotateOutcome {
+    /// 轮换成功，返回新的 refresh_jti。
+    Rotated(String),
+    /// 出示的是已被轮换掉的令牌：会话已整体吊销。
+    Reused,
+    /// 会话不存在、已吊销、已过期或不属于该用户。
+    Invalid,
+}
+
+/// 用出示的刷新令牌轮换会话；旧令牌从此作废，再次出示即触发重放检测。
+pub async fn rotate(
+    pool: &sqlx::AnyPool,
+    sid: &str,
+    user_id: i64,
+    jti: &str,
+) -> Result<RotateOutcome, String> {
+    if Uuid::parse_str(sid).is_err() {
+        return Ok(RotateOutcome::Invalid);
+    }
+    let pg = is_pg(pool);
+    let now = Utc::now();
+    let sql = if pg {
+        r#"
+        SELECT refresh_jti FROM auth_session
+        WHERE id = ($1)::uuid AND user_id = $2 AND revoked_at IS NULL AND expires_at > ($3)::timestamptz
+        "#
+    } else {
+        r#"
+        SELECT refresh_jti FROM auth_session
+        WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL AND expires_at > $3
+        "#
+    };
+    let row = sqlx::query(sql)
+        .bind(sid)
+        .bind(user_id)
+        .bind(format_utc(now))
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    let Some(row) = row else {
+        return Ok(RotateOutcome::Invalid);
+    };
+    if row.get::<String, _>("refresh_jti") != jti {
+        revoke(pool, sid, None, REASON_REUSE).await?;
+        return Ok(RotateOutcome::Reused);
+    }
+
+    // 条件更新：并发的两次刷新只有一次能成功，另一次按重放处理。
+    let new_jti = Uuid::new_v4().to_string();
+    let expires_at = now + chrono::Duration::seconds(REFRESH_TOKEN_TTL_SECONDS);
+    let sql = if pg {
+        r#"
+        UPDATE auth_session
+        SET refresh_jti = $3, last_used_at = ($4)::timestamptz, expires_at = ($5)::timestamptz
+        WHERE id = ($1)::uuid AND refresh_jti = $2 AND revoked_at IS NULL
+        "#
+    } else {
+        r#"
+        UPDATE auth_session
+        SET refresh_jti = $3, last_used_at = $4, expires_at = $5
+        WHERE id = $1 AND refresh_jti = $2 AND revoked_at IS NULL
+        "#
+    };
+    let updated = sqlx::query(sql)
+        .bind(sid)
+        .bind(jti)
+        .bind(&new_jti)
+        .bind(format_utc(now))
+        .bind(format_utc(expires_at))
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    if updated.rows_affected() == 0 {
+        revoke(pool, sid, None, REASON_REUSE).await?;
+        return Ok(RotateOutcome::Reused);
+    }
+    Ok(RotateOutcome::Rotated(new_jti))
+}
+
+/// 吊销单个会话；给定 user_id 时只吊销该用户自己的会话。返回是否有会话被吊销。
+pub async fn revoke(
+    pool: &sqlx::AnyPool,
+    sid: &str,
+    user_id: Option<i64>,
+    reason: &str,
+) -> Result<bool, String> {
+    if Uuid::parse_str(sid).is_err() {
+        return Ok(false);
+    }
+    let sql = if is_pg(pool) {
+        r#"
+        UPDATE auth_session SET revoked_at = ($2)::timestamptz, revoke_reason = $3
+        WHERE id = ($1)::uuid AND revoked_at IS NULL AND (($4)::bigint IS NULL OR user_id = $4)
+        "#
+    } else {
+        r#"
+        UPDATE auth_session SET revoked_at = $2, revoke_reason = $3
+        WHERE id = $1 AND revoked_at IS NULL AND ($4 IS NULL OR user_id = $4)
+        "#
+    };
+    let r = sqlx::query(sql)
+        .bind(sid)
+        .bind(format_utc(Utc::now()))
+        .bind(reason)
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(r.rows_affected() > 0)
+}
+
+/// 吊销用户的全部会话（可保留一个，如“退出其他设备”时的当前会话）；返回被吊销的会话 id。
+pub async fn revoke_all_for_user(
+    pool: &sqlx::AnyPool,
+    user_id: i64,
+    except_sid: Option<&str>,
+    reason: &str,
+) -> Result<Vec<String>, String> {
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let revoked = revoke_all_for_user_in(&mut tx, is_pg(pool), user_id, except_sid, reason).await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(revoked)
+}
+
+/// 同 [`revoke_all_for_user`]，但在调用方的事务内执行（如修改密码时与密码更新一并提交）。
+pub async fn revoke_all_for_user_in(
+    tx: &mut sqlx::Transaction<'_, sqlx::Any>,
+    pg: bool,
+    user_id: i64,
+    except_sid: Option<&str>,
+    reason: &str,
+) -> Result<Vec<String>, String> {
+    let rows = sqlx::query(
+        "SELECT CAST(id AS TEXT) as id FROM auth_session WHERE user_id = $1 AND revoked_at IS NULL",
+    )
+    .bind(user_id)
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    let sql = if pg {
+        "UPDATE auth_session SET revoked_at = ($2)::timestamptz, revoke_reason = $3 WHERE id = ($1)::uuid AND revoked_at IS NULL"
+    } else {
+        "UPDATE auth_session SET revoked_at = $2, revoke_reason = $3 WHERE id = $1 AND revoked_at IS NULL"
+    };
+    let now = format_utc(Utc::now());
+    let mut revoked = Vec::new();
+    for row in rows {
+        let sid: String = row.get("id");
+        if Some(sid.as_str()) == except_sid {
+            continue;
+        }
+        let r = sqlx::query(sql)
+            .bind(&sid)
+            .bind(&now)
+            .bind(reason)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        if r.rows_affected() > 0 {
+            revoked.push(sid);
+        }
+    }
+    Ok(revoked)
+}
+
+/// 记录用户的令牌失效时间：此前签发的不绑定会话的访问令牌全部作废。
+pub async fn set_tokens_valid_after(
+    tx: &mut sqlx::Transaction<'_, sqlx::Any>,
+    pg: bool,
+    user_id: i64,
+    at: DateTime<Utc>,
+) -> Result<(), String> {
+    let sql = if pg {
+        "UPDATE auth_user SET tokens_valid_after = ($2)::timestamptz WHERE id = $1"
+    } else {
+        "UPDATE auth_user SET tokens_valid_after = $2 WHERE id = $1"
+    };
+    sqlx::query(sql)
+        .bind(user_id)
+        .bind(format_utc(at))
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionRow {
+    pub id: String,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: String,
+    pub last_used_at: String,
+    pub expires_at: String,
+    /// 是否为发起本次请求的会话。
+    pub current: bool,
+}
+
+/// 用户未吊销、未过期的会话，最近使用的在前。
+pub async fn list_active(
+    pool: &sqlx::AnyPool,
+    user_id: i64,
+    current_sid: Option<&str>,
+) -> Result<Vec<SessionRow>, String> {
+    let sql = if is_pg(pool) {
+        r#"
+        SELECT CAST(id AS TEXT) as id, ip, user_agent,
+          CAST(created_at AS TEXT) as created_at,
+          CAST(last_used_at AS TEXT) as last_used_at,
+          CAST(expires_at AS TEXT) as expires_at
+        FROM auth_session
+        WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > ($2)::timestamptz
+        ORDER BY last_used_at DESC, created_at DESC
+        "#
+    } else {
+        r#"
+        SELECT CAST(id AS TEXT) as id, ip, user_agent,
+          CAST(created_at AS TEXT) as created_at,
+          CAST(last_used_at AS TEXT) as last_used_at,
+          CAST(expires_at AS TEXT) as expires_at
+        FROM auth_session
+        WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > $2
+        ORDER BY last_used_at DESC, created_at DESC
+        "#
+    };
+    let rows = sqlx::query(sql)
+        .bind(user_id)
+        .bind(format_utc(Utc::now()))
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(rows
+        .iter()
+        .map(|r| {
+            let id: String = r.get("id");
+            SessionRow {
+                current: Some(id.as_str()) == current_sid,
+                id,
+                ip: r.try_get::<Option<String>, _>("ip").ok().flatten(),
+                user_agent: r.try_get::<Option<String>, _>("user_agent").ok().flatten(),
+                created_at: crate::dbfmt::datetime_to_rfc3339(&r.get::<String, _>("created_at")),
+                last_used_at: crate::dbfmt::datetime_to_rfc3339(
+                    &r.get::<String, _>("last_used_at"),
+                ),
+                expires_at: crate::dbfmt::datetime_to_rfc3339(&r.get::<String, _>("expires_at")),
+            }
+        })
+        .collect())
+}
+
+/// 从库中加载近一个访问令牌有效期内的吊销记录（含其他副本写入的），合并进本进程缓存。
+pub async fn sync_revocations(state: &AppState) -> Result<(), String> {
+    let Some(pool) = state.pool() else {
+        return Ok(());
+    };
+    let pg = is_pg(pool);
+    let now = Utc::now();
+    let cutoff = format_utc(now - chrono::Duration::seconds(ACCESS_TOKEN_TTL_SECONDS));
+    let registry = state.session_revocations();
+
+    let sql = if pg {
+        "SELECT CAST(id AS TEXT) as id, CAST(revoked_at AS TEXT) as revoked_at \
+         FROM auth_session WHERE revoked_at >= ($1)::timestamptz"
+    } else {
+        "SELECT CAST(id AS TEXT) as id, CAST(revoked_at AS TEXT) as revoked_at \
+         FROM auth_session WHERE revoked_at >= $1"
+    };
+    let rows = sqlx::query(sql)
+        .bind(&cutoff)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    for r in rows {
+        let at = crate::dbfmt::parse_datetime_utc(&r.get::<String, _>("revoked_at")).unwrap_or(now);
+        registry.revoke_session(&r.get::<String, _>("id"), at);
+    }
+
+    let sql = if pg {
+        "SELECT CAST(id AS TEXT) as id, CAST(tokens_valid_after AS TEXT) as valid_after \
+         FROM auth_user WHERE tokens_valid_after >= ($1)::timestamptz"
+    } else {
+        "SELECT CAST(id AS TEXT) as id, CAST(tokens_valid_after AS TEXT) as valid_after \
+         FROM auth_user WHERE tokens_valid_after >= $1"
+    };
+    let rows = sqlx::query(sql)
+        .bind(&cutoff)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    for r in rows {
+        if let Some(at) = crate::dbfmt::parse_datetime_utc(&r.get::<String, _>("valid_after")) {
+            registry.set_tokens_valid_after(&r.get::<String, _>("id"), at);
+        }
+    }
+
+    registry.prune(now);
+    Ok(())
+}
+
+/// 周期性同步吊销记录；单副本部署时本进程的吊销已即时生效，这里只兜底跨副本的情况。
+pub async fn background_task(state: AppState) {
+    let mut interval = tokio::time::interval(SYNC_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Err(e) = sync_revocations(&state).await {
+            tracing::warn!(error = %e, "auth session revocation sync failed");
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::net::{IpAddr, SocketAddr};
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{HeaderMap, HeaderValue};
+use axum::middleware::Next;
+use axum::response::Response;
+
+use crate::state::AppState;
+
+/// 中间件解析出的客户端 IP 写入该请求头；外部传入的同名头一律丢弃，下游只信任这里的值。
+pub const RESOLVED_HEADER: &str = "x-fundval-client-ip";
+
+/// 受信任代理列表：单个地址或 CIDR 网段。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrustedProxies(Vec<(IpAddr, u8)>);
+
+impl TrustedProxies {
+    /// 解析逗号分隔的 `10.0.0.1,10.1.0.0/16,::1`；无法识别的条目忽略。
+    pub fn parse(raw: &str) -> Self {
+        let nets = raw
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .filter_map(|item| {
+                let (addr, prefix) = match item.split_once('/') {
+                    Some((a, p)) => (a.trim(), Some(p.trim().parse::<u8>().ok()?)),
+                    None => (item, None),
+                };
+                let ip: IpAddr = addr.parse().ok()?;
+                let max = if ip.is_ipv4() { 32 } else { 128 };
+                let prefix = prefix.unwrap_or(max);
+                (prefix <= max).then_some((ip, prefix))
+            })
+            .collect();
+        Self(nets)
+    }
+
+    pub fn from_config(config: &crate::config::ConfigStore) -> Self {
+        Self::parse(&config.get_string("trusted_proxies").unwrap_or_default())
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let ip = canonical(ip);
+        self.0
+            .iter()
+            .any(|&(net, prefix)| match (canonical(net), ip) {
+                (IpAddr::V4(n), IpAddr::V4(a)) => prefix_eq(&n.octets(), &a.octets(), prefix),
+                (IpAddr::V6(n), IpAddr::V6(a)) => prefix_eq(&n.octets(), &a.octets(), prefix),
+                _ => false,
+            })
+    }
+}
+
+/// IPv4-mapped IPv6（双栈监听时常见）按 IPv4 比较。
+fn canonical(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+        v4 => v4,
+    }
+}
+
+fn prefix_eq(a: &[u8], b: &[u8], prefix: u8) -> bool {
+    let full = (prefix / 8) as usize;
+    let rem = prefix % 8;
+    if a[..full] != b[..full] {
+        return false;
+    }
+    rem == 0 || {
+        let mask = 0xff_u8 << (8 - rem);
+        a[full] & mask == b[full] & mask
+    }
+}
+
+/// 由对端地址与转发头确定客户端 IP。
+///
+/// 对端不是受信任代理时直接使用对端地址；否则从 `X-Forwarded-For` 右侧向左跳过受信任代理，
+/// 取第一个不受信任的地址（整条链都受信任时取最左侧），没有该头时退回 `X-Real-IP`。
+pub fn resolve(
+    peer: Option<IpAddr>,
+    headers: &HeaderMap,
+    trusted: &TrustedProxies,
+) -> Option<IpAddr> {
+    let peer = canonical(peer?);
+    if !trusted.contains(peer) {
+        return Some(peer);
+    }
+    let chain: Vec<IpAddr> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|v| v.trim().parse::<IpAddr>().ok())
+        .map(canonical)
+        .collect();
+    if let Some(first) = chain.first() {
+        return Some(
+            chain
+                .iter()
+                .rev()
+                .copied()
+                .find(|ip| !trusted.contains(*ip))
+                .unwrap_or(*first),
+        );
+    }
+    headers
+        .get("x-real-ip")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<IpAddr>().ok())
+        .map(canonical)
+        .or(Some(peer))
+}
+
+/// 中间件：按连接地址与受信任代理配置解析客户端 IP，写入 [`RESOLVED_HEADER`]。
+pub async fn stamp(State(state): State<AppState>, mut req: Request, next: Next) -> Response {
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|c| c.0.ip());
+    let trusted = TrustedProxies::from_config(state.config());
+    let ip = resolve(peer, req.headers(), &trusted);
+    let headers = req.headers_mut();
+    headers.remove(RESOLVED_HEADER);
+    if let Some(v) = ip.and_then(|ip| HeaderValue::from_str(&ip.to_string()).ok()) {
+        headers.insert(RESOLVED_HEADER, v);
+    }
+    next.run(req).await
+}
+
+/// 读取 [`stamp`] 解析出的客户端 IP；未经过中间件（或无法确定连接地址）时为 None。
+pub fn from_headers(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(RESOLVED_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
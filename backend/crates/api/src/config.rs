@@ -124,6 +124,30 @@ impl ConfigStore {
                 Value::Number(serde_json::Number::from(v)),
             );
         }
+        if let Some(v) = std::env::var("TASK_WORKER_CRAWL_CONCURRENCY")
+            .ok()
+            .and_then(|s| s.parse::<i64>().ok())
+        {
+            data.insert(
+                "task_worker_crawl_concurrency".into(),
+                Value::Number(serde_json::Number::from(v)),
+            );
+        }
+        if let Some(v) = std::env::var("TASK_WORKER_COMPUTE_CONCURRENCY")
+            .ok()
+            .and_then(|s| s.parse::<i64>().ok())
+        {
+            data.insert(
+                "task_worker_compute_concurrency".into(),
+                Value::Number(serde_json::Number::from(v)),
+            );
+        }
+        if let Ok(v) = std::env::var("METRICS_TOKEN") {
+            data.insert("metrics_token".into(), Value::String(v.trim().to_string()));
+        }
+        if let Ok(v) = std::env::var("TRUSTED_PROXIES") {
+            data.insert("trusted_proxies".into(), Value::String(v.trim().to_string()));
+        }
         if let Ok(v) = std::env::var("QUANT_SERVICE_URL") {
             let v = v.trim().to_string();
             if !v.is_empty() {
@@ -272,6 +296,15 @@ fn default_config() -> BTreeMap<String, Value> {
     m.insert("estimate_async_enabled".into(), Value::Bool(true));
     // 估值入队：每 tick 最多入队多少 estimate_sync（自选/持仓优先，其余全市场慢速播种）。
     m.insert("estimate_enqueue_max_jobs".into(), Value::Number(50.into()));
+    // 日内估值样本保留天数（fund_estimate_intraday），超期由后台 tick 清理。
+    m.insert(
+        "estimate_intraday_retention_days".into(),
+        Value::Number(7.into()),
+    );
+    // 基金资料（公司/经理/规模/费率）入队：变化很慢，每 tick 少量播种即可。
+    m.insert("profile_enqueue_max_jobs".into(), Value::Number(20.into()));
+    // 季报持仓（前十大股票/债券）与行业配置入队：同上，按报告期低频刷新。
+    m.insert("holdings_enqueue_max_jobs".into(), Value::Number(20.into()));
     m.insert("sources_health_probe".into(), Value::Bool(true));
     m.insert("tushare_token".into(), Value::Null);
     // crawl / cache: 自选/持仓优先，分批播种全量，避免触发数据源封锁
@@ -285,6 +318,8 @@ fn default_config() -> BTreeMap<String, Value> {
     // 防封锁：每日执行上限（0=不限）。默认给一个相对保守的上限，避免后台持续打点把数据源打挂。
     m.insert("crawl_daily_run_limit".into(), Value::Number(3000.into()));
     m.insert("crawl_run_max_jobs".into(), Value::Number(20.into()));
+    // 每轮按关注度/访问/净值滞后/剩余预算重算优先级的任务数上限（0=关闭重算）。
+    m.insert("crawl_rescore_max_jobs".into(), Value::Number(500.into()));
     m.insert("crawl_per_job_delay_ms".into(), Value::Number(250.into()));
     // 在固定 delay 上叠加一个小抖动，避免请求节奏过于规律（0=无抖动）。
     m.insert("crawl_per_job_jitter_ms".into(), Value::Number(200.into()));
@@ -293,8 +328,45 @@ fn default_config() -> BTreeMap<String, Value> {
         "crawl_source_fallbacks".into(),
         Value::String("danjuan,ths".into()),
     );
-    // task_job：每轮最多执行多少个异步任务（signals_batch 等计算类任务）。
+    // task_job：独立 worker 池每轮最多派发多少个异步任务（signals_batch 等计算类任务）。
     m.insert("task_run_max_jobs".into(), Value::Number(5.into()));
+    // task_job worker 池：轮询间隔（秒），入队时也会立即唤醒。
+    m.insert("task_worker_enabled".into(), Value::Bool(true));
+    m.insert("task_poll_interval_seconds".into(), Value::Number(5.into()));
+    // 每个副本的并发上限：抓取类（净值同步/行情刷新/嗅探）与计算类分开，互不阻塞（0=本副本不执行）。
+    m.insert("task_worker_crawl_concurrency".into(), Value::Number(2.into()));
+    m.insert("task_worker_compute_concurrency".into(), Value::Number(2.into()));
+    // 按 task_type 的并发上限（逗号分隔 type=n），优先于通道上限；训练类任务默认串行。
+    m.insert(
+        "task_type_concurrency".into(),
+        Value::String("forecast_model_train=1,fund_analysis_v2_compute=1,ml_sector_model_train=1".into()),
+    );
+    // task_job 租约时长（秒）：执行中按 1/3 间隔心跳续约，过期视为进程中断并回收重试。
+    m.insert("task_lease_seconds".into(), Value::Number(300.into()));
+    // task_schedule：worker 每轮检查到期的周期性计划并投递到 task_job（多副本只会投递一次）。
+    m.insert("task_schedule_enabled".into(), Value::Bool(true));
+    // 每个用户最多保留的模拟运行数（0=不限）；持有 sim.unlimited 能力的用户不受限。
+    m.insert("sim_max_runs_per_user".into(), Value::Number(20.into()));
+    // 登录防爆破：同一用户名/同一 IP 连续失败达到上限后锁定（秒）；此前从第 3 次失败起按 1,2,4... 秒渐进延迟。
+    // 失败计数在最后一次失败后 window 秒内无新失败时清零。
+    m.insert("login_max_failures_per_user".into(), Value::Number(5.into()));
+    m.insert("login_max_failures_per_ip".into(), Value::Number(20.into()));
+    m.insert("login_lockout_seconds".into(), Value::Number(900.into()));
+    m.insert("login_failure_window_seconds".into(), Value::Number(900.into()));
+    // 模型类型（linear / gbdt）：板块信号分类按任务分别选择，全市场净值预测单独选择。
+    m.insert("ml_model_type_dip_buy".into(), Value::String("linear".into()));
+    m.insert(
+        "ml_model_type_magic_rebound".into(),
+        Value::String("linear".into()),
+    );
+    m.insert("forecast_model_type".into(), Value::String("linear".into()));
+    // /metrics（Prometheus）访问令牌：为空时不开放该端点。
+    m.insert("metrics_token".into(), Value::String(String::new()));
+    // 受信任的反向代理（逗号分隔的 IP 或 CIDR）：只有来自这些地址的请求才采信 X-Forwarded-For / X-Real-IP。
+    m.insert(
+        "trusted_proxies".into(),
+        Value::String("127.0.0.1,::1".into()),
+    );
     // 独立量化服务（Python/FastAPI）。
     m.insert(
         "quant_service_url".into(),
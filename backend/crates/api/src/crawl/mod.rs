@@ -1,2 +1,3 @@
+pub mod priority;
 pub mod scheduler;
 pub mod worker;
@@ -0,0 +1,601 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Timelike, Utc};
+use serde::Serialize;
+use sqlx::Row;
+
+use crate::db::DatabaseKind;
+use crate::trading_calendar::{self, Market};
+
+/// 北京时间几点之后认为当日净值“应该”已经公布（多数基金 19:00~22:00 陆续披露）。
+const NAV_PUBLISH_HOUR_CN: u32 = 20;
+
+/// 净值滞后天数的上限：超过后不再继续加分，避免长期停更的基金霸占队列。
+const MAX_STALE_TRADING_DAYS: i64 = 3;
+
+/// 成功后的下次间隔上限（预算吃紧时放大间隔也不超过一周）。
+const MAX_DELAY_SECONDS: i64 = 7 * 24 * 60 * 60;
+
+/// “最近被访问”的有效窗口：超过 7 天的访问不再影响评分，也不再进入重算候选。
+const VIEW_WINDOW_DAYS: i64 = 7;
+
+/// 评分所需的基金侧信号。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DemandSignals {
+    /// 持有该基金（份额 > 0）的用户数。
+    pub holders: i64,
+    /// 自选中包含该基金的用户数。
+    pub watchers: i64,
+    /// 最近一次通过 API 查看该基金详情/估值的时间。
+    pub last_viewed_at: Option<DateTime<Utc>>,
+    pub latest_nav_date: Option<NaiveDate>,
+    /// QDII 净值通常晚一个交易日公布，计算滞后时给一天宽限。
+    pub is_qdii: bool,
+}
+
+/// 数据源当日调用预算（daily_limit=0 表示不限）。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Budget {
+    pub daily_limit: i64,
+    pub used: i64,
+}
+
+impl Budget {
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    pub fn remaining(&self) -> Option<i64> {
+        (self.daily_limit > 0).then(|| (self.daily_limit - self.used).max(0))
+    }
+
+    /// 预算越紧，成功后的下次间隔放得越大：剩余 ≥50% 不放大，其后依次 ×2/×4/×8。
+    pub fn delay_factor(&self) -> i64 {
+        let Some(remaining) = self.remaining() else {
+            return 1;
+        };
+        let ratio = remaining as f64 / self.daily_limit as f64;
+        if ratio >= 0.5 {
+            1
+        } else if ratio >= 0.25 {
+            2
+        } else if ratio >= 0.1 {
+            4
+        } else {
+            8
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ScoreComponent {
+    pub name: &'static str,
+    pub points: i64,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JobScore {
+    pub job_type: String,
+    pub priority: i64,
+    /// 本次成功后到下次执行的间隔（已乘预算系数）。
+    pub delay_seconds: i64,
+    pub budget_factor: i64,
+    pub components: Vec<ScoreComponent>,
+}
+
+/// 截至 now 应该能拿到的最新净值日期（北京时间）：交易日 20 点后为当天，否则为上一交易日。
+pub fn expected_nav_date(now_utc: DateTime<Utc>, is_qdii: bool) -> NaiveDate {
+    let cal = trading_calendar::bundled();
+    let now_cn = now_utc + Duration::hours(8);
+    let today = now_cn.date_naive();
+    let expected = if cal.is_trading_day(Market::Cn, today) && now_cn.hour() >= NAV_PUBLISH_HOUR_CN
+    {
+        today
+    } else {
+        cal.prev_trading_day(Market::Cn, today)
+    };
+    if is_qdii {
+        cal.prev_trading_day(Market::Cn, expected)
+    } else {
+        expected
+    }
+}
+
+/// 最新净值落后预期多少个 A 股交易日（无净值按上限计）。
+pub fn stale_trading_days(signals: &DemandSignals, now_utc: DateTime<Utc>) -> i64 {
+    let expected = expected_nav_date(now_utc, signals.is_qdii);
+    let Some(latest) = signals.latest_nav_date else {
+        return MAX_STALE_TRADING_DAYS;
+    };
+    if latest >= expected {
+        return 0;
+    }
+    let Some(from) = latest.succ_opt() else {
+        return 0;
+    };
+    let n = trading_calendar::bundled()
+        .trading_days_between(Market::Cn, from, expected)
+        .len() as i64;
+    n.min(MAX_STALE_TRADING_DAYS)
+}
+
+/// 各任务类型的 (无人关注时的底分, 自选分, 持仓分)：与原先按层级入队的优先级保持一致。
+fn type_weights(job_type: &str) -> (i64, i64, i64) {
+    match job_type {
+        "nav_history_sync" => (10, 100, 80),
+        "estimate_sync" => (5, 100, 80),
+        _ => (5, 90, 70),
+    }
+}
+
+/// 综合关注度、最近访问、净值滞后与数据源预算，给出任务优先级与成功后的下次间隔。
+pub fn score_job(
+    job_type: &str,
+    signals: &DemandSignals,
+    budget: &Budget,
+    now_utc: DateTime<Utc>,
+) -> JobScore {
+    let (base, watch_points, hold_points) = type_weights(job_type);
+    let mut components = vec![ScoreComponent {
+        name: "base",
+        points: base,
+        detail: format!("{job_type} 底分"),
+    }];
+
+    let demand = [
+        (signals.watchers > 0).then_some(watch_points),
+        (signals.holders > 0).then_some(hold_points),
+    ]
+    .into_iter()
+    .flatten()
+    .max()
+    .unwrap_or(0);
+    if demand > base {
+        components.push(ScoreComponent {
+            name: "demand",
+            points: demand - base,
+            detail: format!("{} 人自选、{} 人持有", signals.watchers, signals.holders),
+        });
+    }
+
+    let audience = signals.watchers + signals.holders;
+    if audience > 1 {
+        components.push(ScoreComponent {
+            name: "audience",
+            points: (5 * (audience - 1)).min(20),
+            detail: format!("共 {audience} 人次关注"),
+        });
+    }
+
+    if let Some(viewed) = signals.last_viewed_at {
+        let ago = now_utc - viewed;
+        let points = if ago <= Duration::hours(1) {
+            20
+        } else if ago <= Duration::hours(24) {
+            10
+        } else if ago <= Duration::days(VIEW_WINDOW_DAYS) {
+            5
+        } else {
+            0
+        };
+        if points > 0 {
+            components.push(ScoreComponent {
+                name: "recent_view",
+                points,
+                detail: format!("{} 分钟前被查看", ago.num_minutes().max(0)),
+            });
+        }
+    }
+
+    // 只有净值任务关心“该出的净值还没拿到”。
+    if job_type == "nav_history_sync" {
+        let stale = stale_trading_days(signals, now_utc);
+        if stale > 0 {
+            components.push(ScoreComponent {
+                name: "staleness",
+                points: 15 * stale,
+                detail: format!(
+                    "最新净值 {} 落后预期 {} 共 {stale} 个交易日",
+                    signals
+                        .latest_nav_date
+                        .map(|d| d.to_string())
+                        .unwrap_or_else(|| "无".to_string()),
+                    expected_nav_date(now_utc, signals.is_qdii)
+                ),
+            });
+        }
+    }
+
+    let priority = components
+        .iter()
+        .map(|c| c.points)
+        .sum::<i64>()
+        .clamp(0, 200);
+    let budget_factor = budget.delay_factor();
+    if budget_factor > 1 {
+        components.push(ScoreComponent {
+            name: "budget",
+            points: 0,
+            detail: format!(
+                "今日预算剩余 {}/{}，间隔 ×{budget_factor}",
+                budget.remaining().unwrap_or(0),
+                budget.daily_limit
+            ),
+        });
+    }
+    let delay_seconds = (super::scheduler::success_delay_seconds(job_type, priority)
+        * budget_factor)
+        .min(MAX_DELAY_SECONDS);
+
+    JobScore {
+        job_type: job_type.to_string(),
+        priority,
+        delay_seconds,
+        budget_factor,
+        components,
+    }
+}
+
+fn parse_ts(raw: &str) -> Option<DateTime<Utc>> {
+    let s = raw.trim();
+    if let Ok(dt) = DateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f%#z") {
+        return Some(dt.with_timezone(&Utc));
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    let head = s.get(..19)?.replace('T', " ");
+    NaiveDateTime::parse_from_str(&head, "%Y-%m-%d %H:%M:%S")
+        .ok()
+        .map(|dt| dt.and_utc())
+}
+
+fn parse_date(raw: Option<String>) -> Option<NaiveDate> {
+    let raw = raw?;
+    NaiveDate::parse_from_str(raw.trim().get(..10)?, "%Y-%m-%d").ok()
+}
+
+fn is_qdii_type(fund_type: Option<&str>) -> bool {
+    fund_type.is_some_and(|t| t.to_ascii_uppercase().contains("QDII"))
+}
+
+/// 记录一次基金详情/估值访问（尽力而为，失败不影响接口）。
+pub async fn record_fund_view(pool: &sqlx::AnyPool, fund_code: &str) -> Result<(), String> {
+    let code = fund_code.trim();
+    if code.is_empty() {
+        return Ok(());
+    }
+    sqlx::query(
+        r#"
+        INSERT INTO fund_view_stat (fund_code, view_count, last_viewed_at)
+        VALUES ($1, 1, CURRENT_TIMESTAMP)
+        ON CONFLICT (fund_code) DO UPDATE
+          SET view_count = fund_view_stat.view_count + 1,
+              last_viewed_at = CURRENT_TIMESTAMP
+        "#,
+    )
+    .bind(code)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 按基金汇总持有/自选人数与最近访问时间；fund_code 为空时返回全部有信号的基金。
+/// latest_nav_date / is_qdii 不在此填充（由调用方从 fund 表补齐）。
+pub async fn load_demand(
+    pool: &sqlx::AnyPool,
+    fund_code: Option<&str>,
+) -> Result<HashMap<String, DemandSignals>, String> {
+    let filter = fund_code.map(|s| s.trim()).unwrap_or("");
+    let mut out: HashMap<String, DemandSignals> = HashMap::new();
+
+    let holders = sqlx::query(
+        r#"
+        SELECT f.fund_code as fund_code, COUNT(DISTINCT a.user_id) as n
+        FROM position p
+        JOIN account a ON a.id = p.account_id
+        JOIN fund f ON f.id = p.fund_id
+        WHERE p.holding_share > 0 AND ($1 = '' OR f.fund_code = $1)
+        GROUP BY f.fund_code
+        "#,
+    )
+    .bind(filter)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    for r in holders {
+        let code: String = r.get("fund_code");
+        out.entry(code).or_default().holders = r.get::<i64, _>("n");
+    }
+
+    let watchers = sqlx::query(
+        r#"
+        SELECT f.fund_code as fund_code, COUNT(DISTINCT w.user_id) as n
+        FROM watchlist_item wi
+        JOIN watchlist w ON w.id = wi.watchlist_id
+        JOIN fund f ON f.id = wi.fund_id
+        WHERE ($1 = '' OR f.fund_code = $1)
+        GROUP BY f.fund_code
+        "#,
+    )
+    .bind(filter)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    for r in watchers {
+        let code: String = r.get("fund_code");
+        out.entry(code).or_default().watchers = r.get::<i64, _>("n");
+    }
+
+    let views = sqlx::query(
+        r#"
+        SELECT fund_code, CAST(last_viewed_at AS TEXT) as last_viewed_at
+        FROM fund_view_stat
+        WHERE ($1 = '' OR fund_code = $1)
+        "#,
+    )
+    .bind(filter)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    for r in views {
+        let code: String = r.get("fund_code");
+        let viewed = r
+            .try_get::<Option<String>, _>("last_viewed_at")
+            .ok()
+            .flatten()
+            .and_then(|s| parse_ts(&s));
+        out.entry(code).or_default().last_viewed_at = viewed;
+    }
+
+    Ok(out)
+}
+
+/// 单只基金的完整评分信号（含净值日期/QDII 标记）。
+pub async fn load_fund_signals(
+    pool: &sqlx::AnyPool,
+    fund_code: &str,
+) -> Result<DemandSignals, String> {
+    let code = fund_code.trim();
+    let mut signals = load_demand(pool, Some(code))
+        .await?
+        .remove(code)
+        .unwrap_or_default();
+
+    let row = sqlx::query(
+        "SELECT CAST(latest_nav_date AS TEXT) as latest_nav_date, fund_type FROM fund WHERE fund_code = $1",
+    )
+    .bind(code)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    if let Some(r) = row {
+        signals.latest_nav_date = parse_date(r.try_get("latest_nav_date").ok().flatten());
+        signals.is_qdii = is_qdii_type(
+            r.try_get::<Option<String>, _>("fund_type")
+                .ok()
+                .flatten()
+                .as_deref(),
+        );
+    }
+    Ok(signals)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduledJob {
+    pub id: String,
+    pub job_type: String,
+    pub fund_code: String,
+    pub status: String,
+    pub priority: i64,
+    pub attempt: i64,
+    pub not_before: Option<DateTime<Utc>>,
+    pub last_ok_at: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    latest_nav_date: Option<NaiveDate>,
+    #[serde(skip)]
+    is_qdii: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct JobFilter<'a> {
+    pub fund_code: Option<&'a str>,
+    pub job_type: Option<&'a str>,
+    /// 只取需要重算的任务：排队中，且当前有关注/访问信号或优先级高于底分。
+    pub rescore_candidates: bool,
+}
+
+pub async fn load_jobs(
+    pool: &sqlx::AnyPool,
+    source_name: &str,
+    filter: &JobFilter<'_>,
+    limit: i64,
+) -> Result<Vec<ScheduledJob>, String> {
+    let limit = limit.clamp(1, 5000);
+    let is_postgres = crate::db::database_kind_from_pool(pool) == DatabaseKind::Postgres;
+    let viewed_since = if is_postgres {
+        "($4)::timestamptz"
+    } else {
+        "$4"
+    };
+    let extra = if filter.rescore_candidates {
+        format!(
+            r#"
+          AND cj.status = 'queued'
+          AND (
+            cj.priority > 10
+            OR cj.fund_code IN (
+              SELECT f2.fund_code FROM position p JOIN fund f2 ON f2.id = p.fund_id
+              WHERE p.holding_share > 0
+              UNION
+              SELECT f3.fund_code FROM watchlist_item wi JOIN fund f3 ON f3.id = wi.fund_id
+              UNION
+              SELECT fund_code FROM fund_view_stat WHERE last_viewed_at >= {viewed_since}
+            )
+          )
+        "#
+        )
+    } else {
+        String::new()
+    };
+    // LIMIT 内联（已 clamp），与 run_due_jobs 相同，规避 Any + SQLite 的占位符差异。
+    let sql = format!(
+        r#"
+        SELECT
+          CAST(cj.id AS TEXT) as id,
+          cj.job_type as job_type,
+          cj.fund_code as fund_code,
+          cj.status as status,
+          cj.priority as priority,
+          cj.attempt as attempt,
+          CAST(cj.not_before AS TEXT) as not_before,
+          CAST(cj.last_ok_at AS TEXT) as last_ok_at,
+          CAST(f.latest_nav_date AS TEXT) as latest_nav_date,
+          f.fund_type as fund_type
+        FROM crawl_job cj
+        LEFT JOIN fund f ON f.fund_code = cj.fund_code
+        WHERE cj.source_name = $1
+          AND cj.fund_code IS NOT NULL
+          AND ($2 = '' OR cj.fund_code = $2)
+          AND ($3 = '' OR cj.job_type = $3)
+          {extra}
+        ORDER BY cj.priority DESC, cj.not_before ASC
+        LIMIT {limit}
+        "#
+    );
+
+    let mut query = sqlx::query(&sql)
+        .bind(source_name)
+        .bind(filter.fund_code.map(|s| s.trim()).unwrap_or(""))
+        .bind(filter.job_type.map(|s| s.trim()).unwrap_or(""));
+    if filter.rescore_candidates {
+        let cutoff = Utc::now() - Duration::days(VIEW_WINDOW_DAYS);
+        query = query.bind(cutoff.format("%Y-%m-%d %H:%M:%S").to_string());
+    }
+    let rows = query.fetch_all(pool).await.map_err(|e| e.to_string())?;
+
+    let ts = |r: &sqlx::any::AnyRow, col: &str| {
+        r.try_get::<Option<String>, _>(col)
+            .ok()
+            .flatten()
+            .and_then(|s| parse_ts(&s))
+    };
+    Ok(rows
+        .iter()
+        .map(|r| ScheduledJob {
+            id: r.get("id"),
+            job_type: r.get("job_type"),
+            fund_code: r.get("fund_code"),
+            status: r.get("status"),
+            priority: r.get("priority"),
+            attempt: r.get("attempt"),
+            not_before: ts(r, "not_before"),
+            last_ok_at: ts(r, "last_ok_at"),
+            latest_nav_date: parse_date(r.try_get("latest_nav_date").ok().flatten()),
+            is_qdii: is_qdii_type(
+                r.try_get::<Option<String>, _>("fund_type")
+                    .ok()
+                    .flatten()
+                    .as_deref(),
+            ),
+        })
+        .collect())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobExplain {
+    #[serde(flatten)]
+    pub job: ScheduledJob,
+    pub score: JobScore,
+    /// 按当前评分推算的下次执行时间：有成功记录且未处于失败退避时为 last_ok_at + delay，否则沿用 not_before。
+    pub next_due_at: Option<DateTime<Utc>>,
+}
+
+fn next_due(job: &ScheduledJob, score: &JobScore) -> Option<DateTime<Utc>> {
+    match job.last_ok_at {
+        Some(ok) if job.attempt == 0 => Some(ok + Duration::seconds(score.delay_seconds)),
+        _ => job.not_before,
+    }
+}
+
+/// 对一批任务评分并给出调度解释（只读）。
+pub async fn explain_jobs(
+    pool: &sqlx::AnyPool,
+    source_name: &str,
+    filter: &JobFilter<'_>,
+    limit: i64,
+    budget: &Budget,
+    now_utc: DateTime<Utc>,
+) -> Result<Vec<JobExplain>, String> {
+    let jobs = load_jobs(pool, source_name, filter, limit).await?;
+    let demand = load_demand(pool, filter.fund_code).await?;
+
+    Ok(jobs
+        .into_iter()
+        .map(|job| {
+            let mut signals = demand.get(&job.fund_code).cloned().unwrap_or_default();
+            signals.latest_nav_date = job.latest_nav_date;
+            signals.is_qdii = job.is_qdii;
+            let score = score_job(&job.job_type, &signals, budget, now_utc);
+            let next_due_at = next_due(&job, &score);
+            JobExplain {
+                job,
+                score,
+                next_due_at,
+            }
+        })
+        .collect())
+}
+
+/// 按最新信号重算排队任务的优先级与下次执行时间，返回实际更新的条数。
+/// 失败退避中的任务（attempt>0）只更新优先级，不改动 not_before。
+pub async fn rescore_jobs(
+    pool: &sqlx::AnyPool,
+    source_name: &str,
+    budget: &Budget,
+    now_utc: DateTime<Utc>,
+    max_jobs: i64,
+) -> Result<i64, String> {
+    if max_jobs <= 0 {
+        return Ok(0);
+    }
+    let filter = JobFilter {
+        rescore_candidates: true,
+        ..Default::default()
+    };
+    let explained = explain_jobs(pool, source_name, &filter, max_jobs, budget, now_utc).await?;
+    let is_postgres = crate::db::database_kind_from_pool(pool) == DatabaseKind::Postgres;
+    let sql = if is_postgres {
+        "UPDATE crawl_job SET priority = $2, not_before = ($3)::timestamptz, updated_at = CURRENT_TIMESTAMP WHERE id = ($1)::uuid"
+    } else {
+        "UPDATE crawl_job SET priority = $2, not_before = $3, updated_at = CURRENT_TIMESTAMP WHERE id = $1"
+    };
+
+    let mut updated = 0_i64;
+    for e in explained {
+        let due = e.next_due_at.or(e.job.not_before);
+        // 一分钟内的差异视为未变，避免每轮都重写整批任务。
+        let due_changed = match (due, e.job.not_before) {
+            (Some(a), Some(b)) => (a - b).num_seconds().abs() >= 60,
+            _ => false,
+        };
+        if e.score.priority == e.job.priority && !due_changed {
+            continue;
+        }
+        let due = due
+            .unwrap_or(now_utc)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string();
+        sqlx::query(sql)
+            .bind(&e.job.id)
+            .bind(e.score.priority)
+            .bind(&due)
+            .execute(pool)
+            .await
+            .map_err(|err| err.to_string())?;
+        updated += 1;
+    }
+    Ok(updated)
+}
@@ -2,6 +2,8 @@ use chrono::{DateTime, Duration, Utc};
 use sqlx::Row;
 use uuid::Uuid;
 
+use super::priority;
+
 pub fn daily_counter_key(job_type: &str, source_name: &str, kind: &str) -> String {
     let day = Utc::now().format("%Y%m%d").to_string();
     format!("crawl_{job_type}_{source_name}_{kind}_{day}")
@@ -132,6 +134,85 @@ pub async fn enqueue_estimate_tick(
     Ok(max_jobs - remaining)
 }
 
+/// 基金资料（公司/经理/规模/费率）入队：只播种还没有 profile_sync job 的基金，
+/// 自选/持仓优先；之后由 success_delay 控制低频刷新。
+pub async fn enqueue_profile_tick(
+    pool: &sqlx::AnyPool,
+    max_jobs: i64,
+    source_name: &str,
+) -> Result<i64, String> {
+    enqueue_seed_tick(pool, "profile_sync", max_jobs, source_name).await
+}
+
+/// 季报持仓/行业配置入队：与 profile_sync 相同的播种策略。
+pub async fn enqueue_holdings_tick(
+    pool: &sqlx::AnyPool,
+    max_jobs: i64,
+    source_name: &str,
+) -> Result<i64, String> {
+    enqueue_seed_tick(pool, "holdings_sync", max_jobs, source_name).await
+}
+
+async fn enqueue_seed_tick(
+    pool: &sqlx::AnyPool,
+    job_type: &str,
+    max_jobs: i64,
+    source_name: &str,
+) -> Result<i64, String> {
+    let max_jobs = max_jobs.clamp(0, 5000);
+    if max_jobs == 0 {
+        return Ok(0);
+    }
+
+    let scopes: [(&str, i64); 3] = [
+        (
+            "SELECT DISTINCT f.fund_code as fund_code FROM watchlist_item wi JOIN fund f ON f.id = wi.fund_id",
+            90,
+        ),
+        (
+            "SELECT DISTINCT f.fund_code as fund_code FROM position p JOIN fund f ON f.id = p.fund_id",
+            70,
+        ),
+        ("SELECT f.fund_code as fund_code FROM fund f", 5),
+    ];
+
+    let mut remaining = max_jobs;
+    for (scope_sql, priority) in scopes {
+        if remaining <= 0 {
+            break;
+        }
+        let sql = format!(
+            r#"
+            SELECT s.fund_code as fund_code
+            FROM ({scope_sql}) s
+            LEFT JOIN crawl_job cj
+              ON cj.job_type = $1
+             AND cj.fund_code = s.fund_code
+             AND cj.source_name = $2
+            WHERE cj.id IS NULL
+            ORDER BY s.fund_code ASC
+            LIMIT {remaining}
+            "#
+        );
+        let rows = sqlx::query(&sql)
+            .bind(job_type)
+            .bind(source_name)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        for r in rows {
+            let code: String = r.get("fund_code");
+            if code.trim().is_empty() {
+                continue;
+            }
+            upsert_job(pool, job_type, code.trim(), source_name, priority).await?;
+            remaining -= 1;
+        }
+    }
+
+    Ok(max_jobs - remaining)
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CrawlJob {
     pub id: String,
@@ -147,6 +228,20 @@ pub async fn run_due_jobs<F, Fut>(
     max_run: i64,
     exec: F,
 ) -> Result<i64, String>
+where
+    F: Fn(CrawlJob, String) -> Fut,
+    Fut: std::future::Future<Output = Result<(), String>>,
+{
+    run_due_jobs_with_budget(pool, max_run, &priority::Budget::unlimited(), exec).await
+}
+
+/// 同 run_due_jobs，但成功后的下次执行时间按评分模型（关注度/访问/净值滞后/当日预算）计算。
+pub async fn run_due_jobs_with_budget<F, Fut>(
+    pool: &sqlx::AnyPool,
+    max_run: i64,
+    budget: &priority::Budget,
+    exec: F,
+) -> Result<i64, String>
 where
     F: Fn(CrawlJob, String) -> Fut,
     Fut: std::future::Future<Output = Result<(), String>>,
@@ -210,12 +305,17 @@ where
         let attempt_now = job.attempt + 1;
         match exec(job.clone(), run_id.clone()).await {
             Ok(()) => {
-                mark_ok(
-                    pool,
-                    &job.id,
-                    next_at(success_delay_seconds(&job.job_type, job.priority)),
-                )
-                .await?;
+                let delay = match job.fund_code.as_deref() {
+                    Some(code) => match priority::load_fund_signals(pool, code).await {
+                        Ok(signals) => {
+                            priority::score_job(&job.job_type, &signals, budget, Utc::now())
+                                .delay_seconds
+                        }
+                        Err(_) => success_delay_seconds(&job.job_type, job.priority),
+                    },
+                    None => success_delay_seconds(&job.job_type, job.priority),
+                };
+                mark_ok(pool, &job.id, next_at(delay)).await?;
                 let _ = crate::tasks::finish_task_run_ok(pool, &run_id).await;
                 let _ =
                     bump_counter(pool, &daily_counter_key(&job.job_type, source, "ok"), 1).await;
@@ -249,7 +349,11 @@ fn backoff_seconds(attempt: i64) -> i64 {
     (10_i64.saturating_mul(pow)).clamp(10, 3600)
 }
 
-fn success_delay_seconds(job_type: &str, priority: i64) -> i64 {
+pub(crate) fn success_delay_seconds(job_type: &str, priority: i64) -> i64 {
+    // 基金资料/季报持仓按季度或不定期变化：每天刷新一次足够。
+    if job_type == "profile_sync" || job_type == "holdings_sync" {
+        return 24 * 60 * 60;
+    }
     // 估值相对更“实时”，但仍需节流以避免上游封锁。
     if job_type == "estimate_sync" {
         if priority >= 100 {
@@ -853,6 +957,82 @@ pub async fn upsert_estimate_job(
     Ok(())
 }
 
+/// 通用 crawl_job upsert（同 upsert_nav_job：仅在优先级提升时更新并提前 not_before）。
+async fn upsert_job(
+    pool: &sqlx::AnyPool,
+    job_type: &str,
+    fund_code: &str,
+    source_name: &str,
+    priority: i64,
+) -> Result<(), String> {
+    let id = Uuid::new_v4().to_string();
+    let code = fund_code.trim();
+    let source = source_name.trim();
+    if code.is_empty() || source.is_empty() {
+        return Ok(());
+    }
+
+    let sql_pg = r#"
+        INSERT INTO crawl_job (id, job_type, fund_code, source_name, priority, not_before, status, attempt, created_at, updated_at)
+        VALUES (($1)::uuid, $2, $3, $4, $5, CURRENT_TIMESTAMP, 'queued', 0, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        ON CONFLICT (job_type, fund_code, source_name) DO UPDATE
+          SET priority = EXCLUDED.priority,
+              not_before = CASE
+                WHEN crawl_job.not_before > CURRENT_TIMESTAMP THEN CURRENT_TIMESTAMP
+                ELSE crawl_job.not_before
+              END,
+              updated_at = CURRENT_TIMESTAMP
+          WHERE EXCLUDED.priority > crawl_job.priority
+    "#;
+
+    let sql_any = r#"
+        INSERT INTO crawl_job (id, job_type, fund_code, source_name, priority, not_before, status, attempt, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP, 'queued', 0, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        ON CONFLICT (job_type, fund_code, source_name) DO UPDATE
+          SET priority = EXCLUDED.priority,
+              not_before = CASE
+                WHEN crawl_job.not_before > CURRENT_TIMESTAMP THEN CURRENT_TIMESTAMP
+                ELSE crawl_job.not_before
+              END,
+              updated_at = CURRENT_TIMESTAMP
+          WHERE EXCLUDED.priority > crawl_job.priority
+    "#;
+
+    let r = sqlx::query(sql_pg)
+        .bind(&id)
+        .bind(job_type)
+        .bind(code)
+        .bind(source)
+        .bind(priority)
+        .execute(pool)
+        .await;
+
+    if r.is_ok() {
+        return Ok(());
+    }
+
+    sqlx::query(sql_any)
+        .bind(&id)
+        .bind(job_type)
+        .bind(code)
+        .bind(source)
+        .bind(priority)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+pub async fn upsert_profile_job(
+    pool: &sqlx::AnyPool,
+    fund_code: &str,
+    source_name: &str,
+    priority: i64,
+) -> Result<(), String> {
+    upsert_job(pool, "profile_sync", fund_code, source_name, priority).await
+}
+
 async fn upsert_relate_theme_job(
     pool: &sqlx::AnyPool,
     fund_code: &str,
@@ -6,14 +6,18 @@ use rust_decimal::Decimal;
 use sqlx::Row;
 use uuid::Uuid;
 
+use crate::crawl::priority;
 use crate::crawl::scheduler::{self, CrawlJob};
 use crate::eastmoney;
+use crate::fund_profile;
+use crate::holdings;
+use crate::intraday;
 use crate::ml;
 use crate::routes::nav_history;
 use crate::sources;
 use crate::state::AppState;
 use crate::tiantian_h5;
-use crate::tasks;
+use crate::trading_calendar;
 
 fn format_dt(dt: DateTime<Utc>) -> String {
     dt.to_rfc3339_opts(SecondsFormat::AutoSi, false)
@@ -153,6 +157,7 @@ pub async fn run_due_jobs_with_nav_sync(
     pool: &sqlx::AnyPool,
     config: &crate::config::ConfigStore,
     max_run: i64,
+    budget: &priority::Budget,
     per_job_delay_ms: u64,
     per_job_jitter_ms: u64,
     source_fallbacks: Arc<Vec<String>>,
@@ -160,7 +165,7 @@ pub async fn run_due_jobs_with_nav_sync(
     let client = eastmoney::build_client()?;
     let tushare_token = config.get_string("tushare_token").unwrap_or_default();
 
-    scheduler::run_due_jobs(pool, max_run, |job, run_id| {
+    scheduler::run_due_jobs_with_budget(pool, max_run, budget, |job, run_id| {
         let client = client.clone();
         let tushare_token = tushare_token.clone();
         let source_fallbacks = source_fallbacks.clone();
@@ -222,11 +227,16 @@ pub async fn background_task(state: AppState) {
         }
 
         // 估值：自选/持仓优先，全市场慢速覆盖（可通过 estimate_enqueue_max_jobs 控制）。
+        // A 股休市日没有盘中估值，不入队以节省上游配额。
         let estimate_enqueue_max = state
             .config()
             .get_i64("estimate_enqueue_max_jobs", 50)
             .clamp(0, 5000);
-        if estimate_enqueue_max > 0 {
+        let cn_trading_day = trading_calendar::is_trading_day(
+            trading_calendar::Market::Cn,
+            trading_calendar::cn_today(Utc::now()),
+        );
+        if estimate_enqueue_max > 0 && cn_trading_day {
             if let Err(e) =
                 scheduler::enqueue_estimate_tick(&pool, estimate_enqueue_max, source_name).await
             {
@@ -234,6 +244,44 @@ pub async fn background_task(state: AppState) {
             }
         }
 
+        // 基金资料/季报持仓：变化慢，只做少量播种。
+        let profile_enqueue_max = state
+            .config()
+            .get_i64("profile_enqueue_max_jobs", 20)
+            .clamp(0, 5000);
+        if profile_enqueue_max > 0
+            && let Err(e) =
+                scheduler::enqueue_profile_tick(&pool, profile_enqueue_max, source_name).await
+        {
+            tracing::warn!(error = %e, "crawl enqueue_profile_tick failed");
+        }
+        let holdings_enqueue_max = state
+            .config()
+            .get_i64("holdings_enqueue_max_jobs", 20)
+            .clamp(0, 5000);
+        if holdings_enqueue_max > 0
+            && let Err(e) =
+                scheduler::enqueue_holdings_tick(&pool, holdings_enqueue_max, source_name).await
+        {
+            tracing::warn!(error = %e, "crawl enqueue_holdings_tick failed");
+        }
+
+        // 日内估值样本：按保留天数清理（0=不清理）。
+        let intraday_retention_days = state
+            .config()
+            .get_i64("estimate_intraday_retention_days", 7)
+            .clamp(0, 3650);
+        if intraday_retention_days > 0
+            && let Err(e) = intraday::prune_samples(
+                &pool,
+                Utc::now().date_naive(),
+                intraday_retention_days,
+            )
+            .await
+        {
+            tracing::warn!(error = %e, "estimate intraday prune failed");
+        }
+
         let mut run_max = state
             .config()
             .get_i64("crawl_run_max_jobs", 20)
@@ -243,15 +291,34 @@ pub async fn background_task(state: AppState) {
             .config()
             .get_i64("crawl_daily_run_limit", 3000)
             .clamp(0, 1_000_000);
-        if daily_limit > 0 && run_max > 0 {
+        let mut budget = priority::Budget {
+            daily_limit,
+            used: 0,
+        };
+        if daily_limit > 0 {
             let key = scheduler::daily_counter_key_all(source_name, "run");
             if let Ok(used) = scheduler::get_counter(&pool, &key).await {
-                let remaining = (daily_limit - used).max(0);
-                if remaining <= 0 {
-                    continue;
-                }
-                run_max = run_max.min(remaining);
+                budget.used = used;
+            }
+        }
+
+        // 按关注度/访问/净值滞后/剩余预算重算排队任务的优先级与下次执行时间。
+        let rescore_max = state
+            .config()
+            .get_i64("crawl_rescore_max_jobs", 500)
+            .clamp(0, 5000);
+        if rescore_max > 0
+            && let Err(e) =
+                priority::rescore_jobs(&pool, source_name, &budget, Utc::now(), rescore_max).await
+        {
+            tracing::warn!(error = %e, "crawl rescore_jobs failed");
+        }
+
+        if let Some(remaining) = budget.remaining() {
+            if remaining <= 0 {
+                continue;
             }
+            run_max = run_max.min(remaining);
         }
         if run_max <= 0 {
             continue;
@@ -278,6 +345,7 @@ pub async fn background_task(state: AppState) {
             &pool,
             state.config(),
             run_max,
+            &budget,
             per_job_delay_ms,
             per_job_jitter_ms,
             fallbacks,
@@ -286,16 +354,6 @@ pub async fn background_task(state: AppState) {
         {
             tracing::warn!(error = %e, "crawl run_due_jobs failed");
         }
-
-        let task_run_max = state
-            .config()
-            .get_i64("task_run_max_jobs", 5)
-            .clamp(0, 200);
-        if task_run_max > 0 {
-            if let Err(e) = tasks::run_due_task_jobs(&pool, task_run_max).await {
-                tracing::warn!(error = %e, "task queue run_due_task_jobs failed");
-            }
-        }
     }
 }
 
@@ -403,23 +461,10 @@ async fn exec_one(
                 )
                 .await;
 
-                let peer_rows = sqlx::query(
-                    r#"
-                    SELECT sec_code
-                    FROM fund_relate_theme
-                    WHERE fund_code = $1
-                    GROUP BY sec_code
-                    ORDER BY sec_code ASC
-                    LIMIT 2
-                    "#,
-                )
-                .bind(&fund_code)
-                .fetch_all(pool)
-                .await;
+                let peer_rows = ml::cluster::fund_peers(pool, &fund_code, source_used, 2).await;
 
                 if let Ok(rows) = peer_rows {
-                    for r in rows {
-                        let peer_code: String = r.get("sec_code");
+                    for (peer_code, _) in rows {
                         let _ = crate::tasks::append_task_log(
                             pool,
                             run_id,
@@ -455,6 +500,48 @@ async fn exec_one(
                 tiantian_h5::upsert_fund_relate_themes(pool, &fund_code, "tiantian_h5", &themes)
                     .await?;
         }
+        "profile_sync" => {
+            let _ = crate::tasks::append_task_log(pool, run_id, "INFO", "拉取基金资料").await;
+            let bundle = fund_profile::fetch_fund_profile_bundle(client, &fund_code).await?;
+            if bundle.profile.is_none() {
+                return Err("fund profile empty".to_string());
+            }
+            let _ = crate::tasks::append_task_log(
+                pool,
+                run_id,
+                "INFO",
+                &format!(
+                    "基金资料：经理任期 {} 条，规模 {} 期",
+                    bundle.managers.len(),
+                    bundle.scale_history.len()
+                ),
+            )
+            .await;
+            fund_profile::upsert_fund_profile_bundle(
+                pool,
+                &fund_code,
+                fund_profile::SOURCE_EASTMONEY,
+                &bundle,
+            )
+            .await?;
+        }
+        "holdings_sync" => {
+            let _ = crate::tasks::append_task_log(pool, run_id, "INFO", "拉取季报持仓/行业配置").await;
+            let bundle = holdings::fetch_holdings_bundle(client, &fund_code).await?;
+            let _ = crate::tasks::append_task_log(
+                pool,
+                run_id,
+                "INFO",
+                &format!(
+                    "持仓 {} 条，行业配置 {} 条",
+                    bundle.holdings.len(),
+                    bundle.industries.len()
+                ),
+            )
+            .await;
+            holdings::upsert_holdings_bundle(pool, &fund_code, holdings::SOURCE_EASTMONEY, &bundle)
+                .await?;
+        }
         "estimate_sync" => {
             let source_raw = job
                 .source_name
@@ -464,6 +551,19 @@ async fn exec_one(
                 return Err(format!("unknown source: {source_raw}"));
             };
 
+            // 休市日（含节假日前已入队的任务）：不请求上游，按成功处理等待下次调度。
+            let today = trading_calendar::cn_today(Utc::now());
+            if !trading_calendar::is_trading_day(trading_calendar::Market::Cn, today) {
+                let _ = crate::tasks::append_task_log(
+                    pool,
+                    run_id,
+                    "INFO",
+                    &format!("{today} 非 A 股交易日，跳过估值"),
+                )
+                .await;
+                return Ok(());
+            }
+
             // 估值只对 tiantian（eastmoney fundgz）走实时接口；其他源退化为“最新净值”近似，避免额外上游请求。
             if source_name == sources::SOURCE_TIANTIAN {
                 let _ = crate::tasks::append_task_log(pool, run_id, "INFO", "请求实时估值（fundgz）").await;
@@ -514,6 +614,19 @@ async fn exec_one(
                 )
                 .await;
 
+                // best-effort：追加日内估值样本（fund 表只保留最新一条）
+                if let Some(et) = estimate_time {
+                    let _ = intraday::record_sample(
+                        pool,
+                        &fund_code,
+                        sources::SOURCE_TIANTIAN,
+                        et,
+                        estimate_nav,
+                        estimate_growth,
+                    )
+                    .await;
+                }
+
                 // best-effort：记录估值准确度（用于后续评估数据源质量）
                 if let Ok(Some(row)) = sqlx::query("SELECT CAST(id AS TEXT) as id FROM fund WHERE fund_code = $1")
                     .bind(&fund_code)
@@ -50,3 +50,8 @@ pub fn datetime_to_rfc3339(raw: &str) -> String {
         None => raw.trim().to_string(),
     }
 }
+
+/// 写库用的时间格式（与 task_job.not_before 一致，按 UTC）。
+pub fn format_utc(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d %H:%M:%S").to_string()
+}
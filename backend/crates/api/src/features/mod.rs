@@ -0,0 +1,177 @@
+//! 特征库：具名特征定义 + 按基金/交易日物化的特征表。
+//!
+//! 训练（`ml::dataset`、预测模型）、推理（`ml::compute`、analysis_v2）与模拟盘观测都从
+//! `fund_feature_row` 读取同一份特征，保证训练与线上不漂移。定义或算法变化时递增
+//! [`FEATURE_SET_VERSION`]，旧版本的行会在下次物化时整体重算。
+
+use std::collections::BTreeMap;
+
+pub mod series;
+pub mod store;
+
+/// 特征集版本；任何定义/算法变化都需要递增。
+pub const FEATURE_SET_VERSION: i64 = 2;
+
+/// 指数相对特征使用的参考指数（上证指数，东方财富日线）。
+pub const REFER_INDEX_CODE: &str = "1.000001";
+pub const REFER_INDEX_SOURCE: &str = "eastmoney";
+
+/// 某基金某日的特征值；缺失（窗口不足、无指数数据等）的特征不出现。
+pub type FeatureValues = BTreeMap<String, f64>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeatureKind {
+    /// 仅依赖基金自身净值。
+    Nav,
+    /// 相对参考指数（`index_daily_price`）。
+    IndexRelative,
+    /// 净值 MACD（按当日净值归一化，跨基金可比）。
+    Macd,
+    /// 同板块横截面分位，按板块存放（scope=板块代码）。
+    PeerPercentile,
+    /// 参考指数的市场状态概率（`index_regime`）。
+    Regime,
+}
+
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct FeatureDef {
+    pub name: &'static str,
+    pub kind: FeatureKind,
+    pub description: &'static str,
+}
+
+pub const REGISTRY: &[FeatureDef] = &[
+    FeatureDef {
+        name: "dd_mag",
+        kind: FeatureKind::Nav,
+        description: "近 252 个交易日（不足取全部）的最大回撤幅度",
+    },
+    FeatureDef {
+        name: "ret5",
+        kind: FeatureKind::Nav,
+        description: "5 日收益率（历史不足时为 0）",
+    },
+    FeatureDef {
+        name: "ret20",
+        kind: FeatureKind::Nav,
+        description: "20 日收益率（历史不足时退化为 ret5）",
+    },
+    FeatureDef {
+        name: "ret60",
+        kind: FeatureKind::Nav,
+        description: "60 日收益率",
+    },
+    FeatureDef {
+        name: "vol20",
+        kind: FeatureKind::Nav,
+        description: "20 日日收益率标准差（历史不足时为 0）",
+    },
+    FeatureDef {
+        name: "logret_1",
+        kind: FeatureKind::Nav,
+        description: "单日对数收益率（预测模型的滞后特征取自此列）",
+    },
+    FeatureDef {
+        name: "hist_days",
+        kind: FeatureKind::Nav,
+        description: "截至当日的净值点数（上限 252）",
+    },
+    FeatureDef {
+        name: "idx_excess_ret5",
+        kind: FeatureKind::IndexRelative,
+        description: "5 日收益率减去同期上证指数收益率",
+    },
+    FeatureDef {
+        name: "idx_excess_ret20",
+        kind: FeatureKind::IndexRelative,
+        description: "20 日收益率减去同期上证指数收益率",
+    },
+    FeatureDef {
+        name: "macd_dif",
+        kind: FeatureKind::Macd,
+        description: "MACD DIF（EMA12-EMA26）/ 净值",
+    },
+    FeatureDef {
+        name: "macd_dea",
+        kind: FeatureKind::Macd,
+        description: "MACD DEA（DIF 的 EMA9）/ 净值",
+    },
+    FeatureDef {
+        name: "macd_hist",
+        kind: FeatureKind::Macd,
+        description: "MACD 柱（2×(DIF-DEA)）/ 净值",
+    },
+    FeatureDef {
+        name: "peer_pos_pct",
+        kind: FeatureKind::PeerPercentile,
+        description: "同板块内 (1-dd_mag) 的分位（0..100，越高越接近前高）",
+    },
+    FeatureDef {
+        name: "regime_bull_p",
+        kind: FeatureKind::Regime,
+        description: "上证指数当日（或之前最近一日）处于牛市状态的滤波概率",
+    },
+    FeatureDef {
+        name: "regime_bear_p",
+        kind: FeatureKind::Regime,
+        description: "上证指数当日（或之前最近一日）处于熊市状态的滤波概率",
+    },
+];
+
+/// 板块信号模型（抄底/神奇反弹）使用的特征。
+pub const ML_SIGNAL_FEATURES: &[&str] = &["dd_mag", "ret5", "ret20", "vol20"];
+
+/// 市场状态特征；配置 `ml_signal_regime_features` 开启后追加到板块信号模型的输入。
+pub const REGIME_FEATURES: &[&str] = &["regime_bull_p", "regime_bear_p"];
+
+/// 板块信号模型本次训练使用的特征。开启市场状态特征后，缺少状态概率的样本会被跳过，
+/// 因此需要先运行 `index_regime_compute`。
+pub fn ml_signal_features(config: &crate::config::ConfigStore) -> Vec<&'static str> {
+    let mut names = ML_SIGNAL_FEATURES.to_vec();
+    if config.get_bool("ml_signal_regime_features", false) {
+        names.extend_from_slice(REGIME_FEATURES);
+    }
+    names
+}
+
+/// 预测模型滞后特征所用的列。
+pub const LAGGED_RETURN_FEATURE: &str = "logret_1";
+
+pub fn def(name: &str) -> Option<&'static FeatureDef> {
+    REGISTRY.iter().find(|d| d.name == name)
+}
+
+/// 按名称取特征向量；任一特征缺失返回 None。
+pub fn vector<S: AsRef<str>>(values: &FeatureValues, names: &[S]) -> Option<Vec<f64>> {
+    names
+        .iter()
+        .map(|n| values.get(n.as_ref()).copied())
+        .collect()
+}
+
+/// 由单列序列构造 (前 k 个值, 下一个值) 的滞后样本。
+pub fn lag_samples(series: &[f64], k: usize) -> (Vec<Vec<f64>>, Vec<f64>) {
+    let mut x = Vec::new();
+    let mut y = Vec::new();
+    if k == 0 {
+        return (x, y);
+    }
+    for i in k..series.len() {
+        x.push(series[i - k..i].to_vec());
+        y.push(series[i]);
+    }
+    (x, y)
+}
+
+/// 取序列最后 k 个值作为推理输入；不足时左侧补 0。
+pub fn last_lags(series: &[f64], k: usize) -> Vec<f64> {
+    let mut out = Vec::with_capacity(k);
+    if series.len() >= k {
+        out.extend_from_slice(&series[series.len() - k..]);
+    } else {
+        out.extend(std::iter::repeat_n(0.0, k - series.len()));
+        out.extend_from_slice(series);
+    }
+    out
+}
@@ -0,0 +1,191 @@
+//! 特征的纯计算函数（输入均为按日期升序的 `(日期, 值)` 序列）。
+
+use super::FeatureValues;
+
+/// dd_mag 的回看窗口（交易日）。
+pub const DRAWDOWN_LOOKBACK: usize = 252;
+const MACD_FAST: f64 = 12.0;
+const MACD_SLOW: f64 = 26.0;
+const MACD_SIGNAL: f64 = 9.0;
+/// MACD 预热期：慢线需要的点数。
+const MACD_WARMUP: usize = 26;
+
+pub fn drawdown_mag(navs: &[(String, f64)], idx: usize, lookback: usize) -> f64 {
+    if navs.is_empty() || idx >= navs.len() {
+        return 0.0;
+    }
+    let lookback = lookback.max(1).min(idx + 1);
+    let start = idx + 1 - lookback;
+    let mut max_v = f64::MIN;
+    for &(_, v) in navs.iter().take(idx + 1).skip(start) {
+        if v > max_v {
+            max_v = v;
+        }
+    }
+    let now = navs[idx].1;
+    if max_v <= 0.0 || now <= 0.0 {
+        return 0.0;
+    }
+    ((max_v - now) / max_v).max(0.0)
+}
+
+pub fn simple_return(navs: &[(String, f64)], idx: usize, lookback: usize) -> Option<f64> {
+    if idx < lookback || idx >= navs.len() {
+        return None;
+    }
+    let base = navs[idx - lookback].1;
+    let now = navs[idx].1;
+    if base <= 0.0 {
+        return None;
+    }
+    Some(now / base - 1.0)
+}
+
+pub fn vol(navs: &[(String, f64)], idx: usize, lookback: usize) -> Option<f64> {
+    if idx < lookback || idx >= navs.len() {
+        return None;
+    }
+    let start = idx + 1 - lookback;
+    let mut rets: Vec<f64> = Vec::with_capacity(lookback);
+    for i in (start + 1)..=idx {
+        let prev = navs[i - 1].1;
+        let now = navs[i].1;
+        if prev <= 0.0 || now <= 0.0 {
+            continue;
+        }
+        rets.push(now / prev - 1.0);
+    }
+    if rets.len() < 2 {
+        return None;
+    }
+    let mean = rets.iter().sum::<f64>() / (rets.len() as f64);
+    let var = rets
+        .iter()
+        .map(|r| {
+            let d = r - mean;
+            d * d
+        })
+        .sum::<f64>()
+        / (rets.len() as f64);
+    Some(var.sqrt())
+}
+
+pub fn log_return(navs: &[(String, f64)], idx: usize) -> Option<f64> {
+    if idx == 0 || idx >= navs.len() {
+        return None;
+    }
+    let (prev, now) = (navs[idx - 1].1, navs[idx].1);
+    if prev <= 0.0 || now <= 0.0 {
+        return None;
+    }
+    Some((now / prev).ln())
+}
+
+/// 逐点 MACD：(DIF, DEA, 柱)，首点作为 EMA 初值。
+pub fn macd(values: &[f64]) -> Vec<(f64, f64, f64)> {
+    let alpha = |n: f64| 2.0 / (n + 1.0);
+    let (af, as_, ad) = (alpha(MACD_FAST), alpha(MACD_SLOW), alpha(MACD_SIGNAL));
+    let mut out = Vec::with_capacity(values.len());
+    let Some(&first) = values.first() else {
+        return out;
+    };
+    let (mut fast, mut slow, mut dea) = (first, first, 0.0);
+    for (i, &v) in values.iter().enumerate() {
+        if i > 0 {
+            fast += af * (v - fast);
+            slow += as_ * (v - slow);
+        }
+        let dif = fast - slow;
+        dea += ad * (dif - dea);
+        out.push((dif, dea, 2.0 * (dif - dea)));
+    }
+    out
+}
+
+/// 指数在 `date` 当日或之前最近一个收盘价。
+fn index_close_on_or_before(index: &[(String, f64)], date: &str) -> Option<f64> {
+    let pos = index.partition_point(|(d, _)| d.as_str() <= date);
+    if pos == 0 {
+        return None;
+    }
+    let close = index[pos - 1].1;
+    (close > 0.0).then_some(close)
+}
+
+/// 基金在 [idx-lookback, idx] 的收益率减去同一日期区间的指数收益率。
+pub fn index_excess_return(
+    navs: &[(String, f64)],
+    index: &[(String, f64)],
+    idx: usize,
+    lookback: usize,
+) -> Option<f64> {
+    let fund = simple_return(navs, idx, lookback)?;
+    let start = index_close_on_or_before(index, &navs[idx - lookback].0)?;
+    let end = index_close_on_or_before(index, &navs[idx].0)?;
+    Some(fund - (end / start - 1.0))
+}
+
+/// 计算单只基金从 `from` 下标起每个净值日期的全部基金级特征（不含同板块分位）。
+/// 窗口特征仍回看 `from` 之前的历史，结果与全量计算一致。
+pub fn compute_fund_rows(
+    navs: &[(String, f64)],
+    index: &[(String, f64)],
+    from: usize,
+) -> Vec<(String, FeatureValues)> {
+    let nav_values: Vec<f64> = navs.iter().map(|(_, v)| *v).collect();
+    let macd_points = macd(&nav_values);
+    let mut out = Vec::with_capacity(navs.len().saturating_sub(from));
+    for (idx, (date, nav)) in navs.iter().enumerate().skip(from) {
+        let mut v = FeatureValues::new();
+        let ret5 = simple_return(navs, idx, 5).unwrap_or(0.0);
+        v.insert("dd_mag".into(), drawdown_mag(navs, idx, DRAWDOWN_LOOKBACK));
+        v.insert("ret5".into(), ret5);
+        v.insert("ret20".into(), simple_return(navs, idx, 20).unwrap_or(ret5));
+        v.insert("vol20".into(), vol(navs, idx, 20).unwrap_or(0.0));
+        v.insert("hist_days".into(), (idx + 1).min(DRAWDOWN_LOOKBACK) as f64);
+        if let Some(r) = simple_return(navs, idx, 60) {
+            v.insert("ret60".into(), r);
+        }
+        if let Some(r) = log_return(navs, idx) {
+            v.insert("logret_1".into(), r);
+        }
+        for (name, lookback) in [("idx_excess_ret5", 5), ("idx_excess_ret20", 20)] {
+            if let Some(r) = index_excess_return(navs, index, idx, lookback) {
+                v.insert(name.into(), r);
+            }
+        }
+        if idx + 1 >= MACD_WARMUP && *nav > 0.0 {
+            let (dif, dea, hist) = macd_points[idx];
+            v.insert("macd_dif".into(), dif / nav);
+            v.insert("macd_dea".into(), dea / nav);
+            v.insert("macd_hist".into(), hist / nav);
+        }
+        out.push((date.clone(), v));
+    }
+    out
+}
+
+/// 按日期附加参考指数的市场状态概率；状态数据早于当日超过 `max_gap_days` 天则不附加。
+pub fn attach_regime(
+    rows: &mut [(String, FeatureValues)],
+    regime: &[(String, [f64; 3])],
+    max_gap_days: i64,
+) {
+    for (date, values) in rows.iter_mut() {
+        if let Some(p) = crate::ml::regime::proba_on_or_before(regime, date, max_gap_days) {
+            values.insert("regime_bull_p".into(), p[0]);
+            values.insert("regime_bear_p".into(), p[1]);
+        }
+    }
+}
+
+/// 同板块分位：`sorted` 为组内得分升序，返回 `target` 的分位（0..100）；组内少于 3 只返回 None。
+/// 平局取最后一个不超过目标得分的位置，与原信号快照口径一致。
+pub fn percentile_rank(sorted: &[f64], target: f64) -> Option<f64> {
+    let n = sorted.len();
+    if n < 3 {
+        return None;
+    }
+    let rank = sorted.partition_point(|s| *s <= target).saturating_sub(1);
+    Some((rank as f64) * 100.0 / ((n - 1) as f64))
+}
@@ -0,0 +1,485 @@
+//! `fund_feature_row` 的物化与读取。
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::NaiveDate;
+use sqlx::Row;
+
+use super::series::{attach_regime, compute_fund_rows, percentile_rank};
+use super::{FEATURE_SET_VERSION, FeatureValues, REFER_INDEX_CODE, REFER_INDEX_SOURCE};
+
+/// 基金级特征的 scope。
+pub const FUND_SCOPE: &str = "";
+/// 同板块分位：成员最近净值早于当日这么多自然日则不参与排名。
+const PEER_STALE_DAYS: i64 = 31;
+/// 同板块分位：成员至少需要的净值点数。
+const PEER_MIN_HIST_DAYS: f64 = 10.0;
+/// 市场状态概率：早于净值日期这么多自然日则视为缺失。
+const REGIME_STALE_DAYS: i64 = 10;
+/// 同板块成员上限（与信号快照一致）。
+const PEER_MEMBER_LIMIT: i64 = 300;
+
+fn is_pg(pool: &sqlx::AnyPool) -> bool {
+    crate::db::database_kind_from_pool(pool) == crate::db::DatabaseKind::Postgres
+}
+
+fn parse_date(s: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(s.get(0..10).unwrap_or(s), "%Y-%m-%d").ok()
+}
+
+async fn load_navs(
+    pool: &sqlx::AnyPool,
+    fund_code: &str,
+    source_name: &str,
+) -> Result<Vec<(String, f64)>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+          CAST(h.nav_date AS TEXT) as nav_date,
+          CAST(h.unit_nav AS TEXT) as unit_nav
+        FROM fund_nav_history h
+        JOIN fund f ON f.id = h.fund_id
+        WHERE f.fund_code = $1 AND h.source_name = $2
+        ORDER BY h.nav_date ASC
+        "#,
+    )
+    .bind(fund_code)
+    .bind(source_name)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut navs: Vec<(String, f64)> = Vec::with_capacity(rows.len());
+    for r in rows {
+        let d: String = r.get("nav_date");
+        let s: String = r.get("unit_nav");
+        if let Ok(v) = s.trim().parse::<f64>() {
+            navs.push((d.get(0..10).unwrap_or(&d).to_string(), v));
+        }
+    }
+    Ok(navs)
+}
+
+async fn load_index(pool: &sqlx::AnyPool, end_date: &str) -> Result<Vec<(String, f64)>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+          CAST(trade_date AS TEXT) as trade_date,
+          CAST(close AS TEXT) as close
+        FROM index_daily_price
+        WHERE index_code = $1 AND source_name = $2 AND CAST(trade_date AS TEXT) <= $3
+        ORDER BY trade_date ASC
+        "#,
+    )
+    .bind(REFER_INDEX_CODE)
+    .bind(REFER_INDEX_SOURCE)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut out = Vec::with_capacity(rows.len());
+    for r in rows {
+        let d: String = r.get("trade_date");
+        let c: String = r.get("close");
+        if let Ok(v) = c.trim().parse::<f64>() {
+            out.push((d.get(0..10).unwrap_or(&d).to_string(), v));
+        }
+    }
+    Ok(out)
+}
+
+/// 已物化行的 (行数, 最新日期, 最低版本)。
+async fn watermark(
+    pool: &sqlx::AnyPool,
+    fund_code: Option<&str>,
+    source_name: &str,
+    scope: &str,
+) -> Result<(i64, Option<String>, Option<i64>), String> {
+    let row = sqlx::query(
+        r#"
+        SELECT
+          COUNT(*) as n,
+          MAX(CAST(as_of_date AS TEXT)) as last_date,
+          MIN(feature_set_version) as min_version
+        FROM fund_feature_row
+        WHERE ($1 = '' OR fund_code = $1) AND source_name = $2 AND scope = $3
+        "#,
+    )
+    .bind(fund_code.unwrap_or("").to_string())
+    .bind(source_name)
+    .bind(scope)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok((
+        row.get::<i64, _>("n"),
+        row.try_get::<Option<String>, _>("last_date").ok().flatten(),
+        row.try_get::<Option<i64>, _>("min_version").ok().flatten(),
+    ))
+}
+
+async fn delete_scope(
+    pool: &sqlx::AnyPool,
+    fund_code: Option<&str>,
+    source_name: &str,
+    scope: &str,
+) -> Result<(), String> {
+    sqlx::query(
+        "DELETE FROM fund_feature_row WHERE ($1 = '' OR fund_code = $1) AND source_name = $2 AND scope = $3",
+    )
+    .bind(fund_code.unwrap_or("").to_string())
+    .bind(source_name)
+    .bind(scope)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+async fn write_rows(
+    pool: &sqlx::AnyPool,
+    fund_code: &str,
+    source_name: &str,
+    scope: &str,
+    rows: &[(String, FeatureValues)],
+) -> Result<usize, String> {
+    if rows.is_empty() {
+        return Ok(0);
+    }
+    let sql = if is_pg(pool) {
+        r#"
+        INSERT INTO fund_feature_row (fund_code, source_name, scope, as_of_date, feature_set_version, values_json, computed_at)
+        VALUES ($1, $2, $3, ($4)::date, $5, $6, CURRENT_TIMESTAMP)
+        ON CONFLICT (fund_code, source_name, scope, as_of_date) DO UPDATE SET
+          feature_set_version = EXCLUDED.feature_set_version,
+          values_json = EXCLUDED.values_json,
+          computed_at = CURRENT_TIMESTAMP
+        "#
+    } else {
+        r#"
+        INSERT INTO fund_feature_row (fund_code, source_name, scope, as_of_date, feature_set_version, values_json, computed_at)
+        VALUES ($1, $2, $3, $4, $5, $6, CURRENT_TIMESTAMP)
+        ON CONFLICT (fund_code, source_name, scope, as_of_date) DO UPDATE SET
+          feature_set_version = excluded.feature_set_version,
+          values_json = excluded.values_json,
+          computed_at = CURRENT_TIMESTAMP
+        "#
+    };
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    for (date, values) in rows {
+        let values_json = serde_json::to_string(values).map_err(|e| e.to_string())?;
+        sqlx::query(sql)
+            .bind(fund_code)
+            .bind(source_name)
+            .bind(scope)
+            .bind(date)
+            .bind(FEATURE_SET_VERSION)
+            .bind(values_json)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(rows.len())
+}
+
+/// 物化单只基金的基金级特征；返回写入行数。
+///
+/// 增量：只重算最新已物化日期（含）之后的行；版本变化或历史被回补（行数对不上）时整体重算。
+pub async fn materialize_fund(
+    pool: &sqlx::AnyPool,
+    fund_code: &str,
+    source_name: &str,
+) -> Result<usize, String> {
+    let navs = load_navs(pool, fund_code, source_name).await?;
+    let Some((last_nav_date, _)) = navs.last() else {
+        return Ok(0);
+    };
+    let index = load_index(pool, last_nav_date).await?;
+
+    let (count, last, min_version) =
+        watermark(pool, Some(fund_code), source_name, FUND_SCOPE).await?;
+    let since = match (last, min_version) {
+        (Some(last), Some(v)) if v == FEATURE_SET_VERSION => {
+            let expected = navs.iter().filter(|(d, _)| *d <= last).count() as i64;
+            (expected == count).then_some(last)
+        }
+        _ => None,
+    };
+    if since.is_none() && count > 0 {
+        delete_scope(pool, Some(fund_code), source_name, FUND_SCOPE).await?;
+    }
+
+    let from = since
+        .as_deref()
+        .map_or(0, |s| navs.partition_point(|(d, _)| d.as_str() < s));
+    let mut rows = compute_fund_rows(&navs, &index, from);
+    let regime =
+        crate::ml::regime::load_probas(pool, REFER_INDEX_CODE, REFER_INDEX_SOURCE, last_nav_date)
+            .await?;
+    attach_regime(&mut rows, &regime, REGIME_STALE_DAYS);
+    write_rows(pool, fund_code, source_name, FUND_SCOPE, &rows).await
+}
+
+/// 板块成员（与信号快照取同一批）；聚类同类组取 `source_name` 下的 `fund_cluster_member`。
+pub async fn peer_members(
+    pool: &sqlx::AnyPool,
+    peer_code: &str,
+    source_name: &str,
+) -> Result<Vec<String>, String> {
+    if crate::ml::cluster::is_cluster_peer(peer_code) {
+        return crate::ml::cluster::cluster_members(pool, peer_code, source_name, PEER_MEMBER_LIMIT)
+            .await;
+    }
+    let rows = sqlx::query(
+        r#"
+        SELECT DISTINCT t.fund_code as fund_code
+        FROM fund_relate_theme t
+        WHERE t.sec_code = $1
+        ORDER BY t.fund_code ASC
+        LIMIT $2
+        "#,
+    )
+    .bind(peer_code)
+    .bind(PEER_MEMBER_LIMIT)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(rows
+        .into_iter()
+        .map(|r| r.get::<String, _>("fund_code").trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect())
+}
+
+/// 物化板块内全部成员的基金级特征，以及同板块分位（scope=板块代码）；返回写入的分位行数。
+///
+/// 分位按日期横截面计算：每个成员取当日或之前最近一行（不早于 [`PEER_STALE_DAYS`] 天、
+/// 至少 [`PEER_MIN_HIST_DAYS`] 个净值点），得分为 1-dd_mag。
+/// 增量时重算最新已物化日期（含）之后的行，以吸收晚到的成员净值。
+pub async fn materialize_peer(
+    pool: &sqlx::AnyPool,
+    peer_code: &str,
+    source_name: &str,
+) -> Result<usize, String> {
+    let members = peer_members(pool, peer_code, source_name).await?;
+    for code in &members {
+        materialize_fund(pool, code, source_name).await?;
+    }
+    if members.len() < 3 {
+        return Ok(0);
+    }
+
+    let (count, last, min_version) = watermark(pool, None, source_name, peer_code).await?;
+    let since = match (last, min_version) {
+        (Some(last), Some(v)) if v == FEATURE_SET_VERSION => Some(last),
+        _ => None,
+    };
+    if since.is_none() && count > 0 {
+        delete_scope(pool, None, source_name, peer_code).await?;
+    }
+    let load_from = since.as_deref().and_then(parse_date).map(|d| {
+        (d - chrono::Duration::days(PEER_STALE_DAYS))
+            .format("%Y-%m-%d")
+            .to_string()
+    });
+
+    // 每个成员：(日期, 得分, 净值点数)，按日期升序。
+    let mut series: Vec<Vec<(NaiveDate, f64, f64)>> = Vec::with_capacity(members.len());
+    let mut all_dates: BTreeSet<NaiveDate> = BTreeSet::new();
+    for code in &members {
+        let rows =
+            load_rows_from(pool, code, source_name, FUND_SCOPE, load_from.as_deref()).await?;
+        let mut points = Vec::with_capacity(rows.len());
+        for (date, values) in rows {
+            let (Some(d), Some(dd), Some(hist)) = (
+                parse_date(&date),
+                values.get("dd_mag"),
+                values.get("hist_days"),
+            ) else {
+                continue;
+            };
+            all_dates.insert(d);
+            points.push((d, (1.0 - dd).clamp(0.0, 1.0), *hist));
+        }
+        series.push(points);
+    }
+
+    let since_date = since.as_deref().and_then(parse_date);
+    let mut cursor = vec![0_usize; members.len()];
+    let mut out: Vec<Vec<(String, FeatureValues)>> = vec![Vec::new(); members.len()];
+    for d in all_dates {
+        let mut current: Vec<(usize, f64, bool)> = Vec::new();
+        for (m, points) in series.iter().enumerate() {
+            while cursor[m] < points.len() && points[cursor[m]].0 <= d {
+                cursor[m] += 1;
+            }
+            if cursor[m] == 0 {
+                continue;
+            }
+            let (pd, score, hist) = points[cursor[m] - 1];
+            if hist < PEER_MIN_HIST_DAYS || (d - pd).num_days() > PEER_STALE_DAYS {
+                continue;
+            }
+            current.push((m, score, pd == d));
+        }
+        if since_date.is_some_and(|s| d < s) || current.len() < 3 {
+            continue;
+        }
+        let mut sorted: Vec<f64> = current.iter().map(|(_, s, _)| *s).collect();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        for (m, score, own_row) in current {
+            if !own_row {
+                continue;
+            }
+            if let Some(pct) = percentile_rank(&sorted, score) {
+                let mut v = FeatureValues::new();
+                v.insert("peer_pos_pct".into(), pct);
+                out[m].push((d.format("%Y-%m-%d").to_string(), v));
+            }
+        }
+    }
+
+    let mut written = 0;
+    for (code, rows) in members.iter().zip(out) {
+        written += write_rows(pool, code, source_name, peer_code, &rows).await?;
+    }
+    Ok(written)
+}
+
+async fn load_rows_from(
+    pool: &sqlx::AnyPool,
+    fund_code: &str,
+    source_name: &str,
+    scope: &str,
+    from_date: Option<&str>,
+) -> Result<Vec<(String, FeatureValues)>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT CAST(as_of_date AS TEXT) as as_of_date, values_json
+        FROM fund_feature_row
+        WHERE fund_code = $1 AND source_name = $2 AND scope = $3
+          AND CAST(as_of_date AS TEXT) >= $4
+        ORDER BY as_of_date ASC
+        "#,
+    )
+    .bind(fund_code)
+    .bind(source_name)
+    .bind(scope)
+    .bind(from_date.unwrap_or("").to_string())
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    decode_rows(rows)
+}
+
+fn decode_rows(rows: Vec<sqlx::any::AnyRow>) -> Result<Vec<(String, FeatureValues)>, String> {
+    let mut out = Vec::with_capacity(rows.len());
+    for r in rows {
+        let date: String = r.get("as_of_date");
+        let values_json: String = r.get("values_json");
+        let values: FeatureValues =
+            serde_json::from_str(&values_json).map_err(|e| e.to_string())?;
+        out.push((date.get(0..10).unwrap_or(&date).to_string(), values));
+    }
+    Ok(out)
+}
+
+/// 读取基金截至 `until`（含，None 表示全部）最近 `limit` 行特征，按日期升序；
+/// 给出 `peer_code` 时合并该板块的横截面特征。
+pub async fn load_rows(
+    pool: &sqlx::AnyPool,
+    fund_code: &str,
+    source_name: &str,
+    peer_code: Option<&str>,
+    until: Option<&str>,
+    limit: Option<i64>,
+) -> Result<Vec<(String, FeatureValues)>, String> {
+    let sql = r#"
+        SELECT CAST(as_of_date AS TEXT) as as_of_date, values_json
+        FROM fund_feature_row
+        WHERE fund_code = $1 AND source_name = $2 AND scope = $3
+          AND ($4 = '' OR CAST(as_of_date AS TEXT) <= $4)
+        ORDER BY as_of_date DESC
+        LIMIT $5
+    "#;
+    let until = until.unwrap_or("").to_string();
+    let limit = limit.unwrap_or(i64::MAX).max(0);
+    let rows = sqlx::query(sql)
+        .bind(fund_code)
+        .bind(source_name)
+        .bind(FUND_SCOPE)
+        .bind(&until)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut out = decode_rows(rows)?;
+    out.reverse();
+
+    if let Some(peer) = peer_code.filter(|p| !p.is_empty())
+        && let Some((first, _)) = out.first()
+    {
+        let peer_rows: BTreeMap<String, FeatureValues> =
+            load_rows_from(pool, fund_code, source_name, peer, Some(first))
+                .await?
+                .into_iter()
+                .collect();
+        for (date, values) in &mut out {
+            if let Some(extra) = peer_rows.get(date) {
+                values.extend(extra.iter().map(|(k, v)| (k.clone(), *v)));
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// 基金在 `date` 当日或之前最近一行特征。
+pub async fn row_on_or_before(
+    pool: &sqlx::AnyPool,
+    fund_code: &str,
+    source_name: &str,
+    peer_code: Option<&str>,
+    date: &str,
+) -> Result<Option<(String, FeatureValues)>, String> {
+    Ok(
+        load_rows(pool, fund_code, source_name, peer_code, Some(date), Some(1))
+            .await?
+            .pop(),
+    )
+}
+
+/// 单个特征最近 `limit` 个非缺失值，按日期升序。
+pub async fn load_series(
+    pool: &sqlx::AnyPool,
+    fund_code: &str,
+    source_name: &str,
+    name: &str,
+    until: Option<&str>,
+    limit: Option<i64>,
+) -> Result<Vec<f64>, String> {
+    Ok(
+        load_dated_series(pool, fund_code, source_name, name, until, limit)
+            .await?
+            .into_iter()
+            .map(|(_, v)| v)
+            .collect(),
+    )
+}
+
+/// 同 [`load_series`]，附带日期。
+pub async fn load_dated_series(
+    pool: &sqlx::AnyPool,
+    fund_code: &str,
+    source_name: &str,
+    name: &str,
+    until: Option<&str>,
+    limit: Option<i64>,
+) -> Result<Vec<(String, f64)>, String> {
+    Ok(load_rows(pool, fund_code, source_name, None, until, limit)
+        .await?
+        .into_iter()
+        .filter_map(|(d, v)| v.get(name).map(|x| (d, *x)))
+        .collect())
+}
@@ -1,2 +1,3 @@
+pub mod model;
 pub mod ols_sgd;
-
+pub mod quantile;
@@ -0,0 +1,394 @@
+//! 全市场净值预测模型：OLS（SGD）或 GBDT 回归，按 `forecast_model` 表的列存取。
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::ml::gbdt::{GbdtModel, GbdtObjective, GbdtTrainConfig, train_gbdt};
+use crate::ml::model::ModelKind;
+use crate::ml::registry::{self, ModelVersion, NewModelVersion, ShadowReport};
+
+use super::ols_sgd::{OlsModel, OlsTrainConfig, train_ols_sgd};
+
+/// 选择预测模型类型的配置项（`linear` / `gbdt`）。
+pub const MODEL_TYPE_CONFIG_KEY: &str = "forecast_model_type";
+
+const OLS_TRAIN: OlsTrainConfig = OlsTrainConfig {
+    learning_rate: 0.01,
+    epochs: 3,
+    l2: 1e-4,
+};
+
+const GBDT_TRAIN: GbdtTrainConfig = GbdtTrainConfig {
+    n_trees: 100,
+    learning_rate: 0.05,
+    max_depth: 4,
+    max_leaves: 16,
+    min_samples_leaf: 50,
+    max_bins: 64,
+    l2: 1.0,
+    validation_fraction: 0.1,
+    early_stopping_rounds: 10,
+};
+
+#[derive(Debug, Clone)]
+pub enum ForecastModel {
+    Ols(OlsModel),
+    Gbdt {
+        model: GbdtModel,
+        residual_sigma: f64,
+    },
+}
+
+/// `forecast_model` 表中的模型列。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForecastModelColumns {
+    pub weights_json: String,
+    pub bias: f64,
+    pub mean_json: String,
+    pub std_json: String,
+    pub residual_sigma: f64,
+}
+
+impl ForecastModel {
+    /// 各模型类型的默认 `model_name`。
+    pub fn default_name(kind: ModelKind) -> &'static str {
+        match kind {
+            ModelKind::Linear => "global_ols_v1",
+            ModelKind::Gbdt => "global_gbdt_v1",
+        }
+    }
+
+    /// 样本过少时的退化模型（mu=0）。
+    pub fn degenerate(lag_k: usize) -> Self {
+        ForecastModel::Ols(OlsModel {
+            weights: vec![0.0; lag_k],
+            bias: 0.0,
+            mean: vec![0.0; lag_k],
+            std: vec![1.0; lag_k],
+            residual_sigma: 0.0,
+        })
+    }
+
+    pub fn kind(&self) -> ModelKind {
+        match self {
+            ForecastModel::Ols(_) => ModelKind::Linear,
+            ForecastModel::Gbdt { .. } => ModelKind::Gbdt,
+        }
+    }
+
+    pub fn predict(&self, x: &[f64]) -> Option<f64> {
+        match self {
+            ForecastModel::Ols(m) => m.predict(x),
+            ForecastModel::Gbdt { model, .. } => model.predict_raw(x),
+        }
+    }
+
+    pub fn residual_sigma(&self) -> f64 {
+        match self {
+            ForecastModel::Ols(m) => m.residual_sigma,
+            ForecastModel::Gbdt { residual_sigma, .. } => *residual_sigma,
+        }
+    }
+
+    /// OLS 的 `weights_json` 为权重数组；GBDT 为整棵模型对象（bias/mean/std 列留空）。
+    pub fn to_columns(&self) -> Result<ForecastModelColumns, String> {
+        let json = |v: &Vec<f64>| serde_json::to_string(v).map_err(|e| e.to_string());
+        match self {
+            ForecastModel::Ols(m) => Ok(ForecastModelColumns {
+                weights_json: json(&m.weights)?,
+                bias: m.bias,
+                mean_json: json(&m.mean)?,
+                std_json: json(&m.std)?,
+                residual_sigma: m.residual_sigma,
+            }),
+            ForecastModel::Gbdt {
+                model,
+                residual_sigma,
+            } => Ok(ForecastModelColumns {
+                weights_json: serde_json::to_string(model).map_err(|e| e.to_string())?,
+                bias: 0.0,
+                mean_json: "[]".to_string(),
+                std_json: "[]".to_string(),
+                residual_sigma: *residual_sigma,
+            }),
+        }
+    }
+
+    pub fn from_columns(cols: &ForecastModelColumns) -> Result<Self, String> {
+        let weights: serde_json::Value =
+            serde_json::from_str(&cols.weights_json).map_err(|e| e.to_string())?;
+        if weights.is_object() {
+            let model: GbdtModel = serde_json::from_value(weights).map_err(|e| e.to_string())?;
+            return Ok(ForecastModel::Gbdt {
+                model,
+                residual_sigma: cols.residual_sigma,
+            });
+        }
+        Ok(ForecastModel::Ols(OlsModel {
+            weights: serde_json::from_value(weights).map_err(|e| e.to_string())?,
+            bias: cols.bias,
+            mean: serde_json::from_str(&cols.mean_json).map_err(|e| e.to_string())?,
+            std: serde_json::from_str(&cols.std_json).map_err(|e| e.to_string())?,
+            residual_sigma: cols.residual_sigma,
+        }))
+    }
+}
+
+pub fn train_forecast_model(kind: ModelKind, x: &[Vec<f64>], y: &[f64]) -> Option<ForecastModel> {
+    match kind {
+        ModelKind::Linear => train_ols_sgd(x, y, &OLS_TRAIN).map(ForecastModel::Ols),
+        ModelKind::Gbdt => {
+            let model = train_gbdt(x, y, GbdtObjective::SquaredError, &GBDT_TRAIN)?;
+            // residual sigma (RMSE)，与 OLS 口径一致。
+            let sse: f64 = x
+                .iter()
+                .zip(y.iter())
+                .map(|(row, &yy)| {
+                    let err = model.predict_raw(row).unwrap_or(0.0) - yy;
+                    err * err
+                })
+                .sum();
+            let residual_sigma = (sse / (x.len() as f64).max(1.0)).sqrt();
+            Some(ForecastModel::Gbdt {
+                model,
+                residual_sigma,
+            })
+        }
+    }
+}
+
+/// 全市场训练样本：每行 `lag_k` 个滞后收益，目标为下一日收益；`dates` 为目标日期。
+#[derive(Debug, Clone, Default)]
+pub struct ForecastTrainingSet {
+    pub x: Vec<Vec<f64>>,
+    pub y: Vec<f64>,
+    pub dates: Vec<String>,
+}
+
+/// 预测模型的定位键（与 `forecast_model` 唯一约束一致）。
+#[derive(Debug, Clone)]
+pub struct ForecastModelKey {
+    pub model_name: String,
+    pub source: String,
+    pub horizon: i64,
+    pub lag_k: i64,
+}
+
+impl ForecastModelKey {
+    pub fn registry_key(&self) -> String {
+        registry::forecast_key(&self.model_name, &self.source, self.horizon, self.lag_k)
+    }
+
+    fn feature_names(&self) -> Vec<String> {
+        (1..=self.lag_k)
+            .map(|i| format!("{}_lag{i}", crate::features::LAGGED_RETURN_FEATURE))
+            .collect()
+    }
+}
+
+/// 用全部样本训练并登记为新版本；样本过少时登记退化模型（mu=0）。
+/// 无冠军时直接生效，否则在冠军训练截止日之后的样本上比较 RMSE 决定是否晋升。
+pub async fn train_and_register(
+    pool: &sqlx::AnyPool,
+    key: &ForecastModelKey,
+    kind: ModelKind,
+    set: &ForecastTrainingSet,
+) -> Result<ModelVersion, String> {
+    let lag_k = key.lag_k as usize;
+    let model = if set.x.len() < 10 {
+        ForecastModel::degenerate(lag_k)
+    } else {
+        train_forecast_model(kind, &set.x, &set.y)
+            .ok_or_else(|| "模型训练失败（样本不足或数据异常）".to_string())?
+    };
+
+    let shadow =
+        match registry::active_version(pool, registry::FAMILY_FORECAST, &key.registry_key()).await?
+        {
+            None => ShadowReport::no_champion("rmse"),
+            Some(champion) => shadow_score(&champion, kind, set)?,
+        };
+
+    let cols = model.to_columns()?;
+    registry::register(
+        pool,
+        NewModelVersion {
+            family: registry::FAMILY_FORECAST,
+            model_key: key.registry_key(),
+            model_type: model.kind(),
+            feature_names: key.feature_names(),
+            payload: json!({
+                "model_name": key.model_name,
+                "source": key.source,
+                "horizon": key.horizon,
+                "lag_k": key.lag_k,
+                "columns": cols,
+            }),
+            metrics: json!({
+                "sample_count": set.x.len(),
+                "residual_sigma": model.residual_sigma(),
+            }),
+            train_start_date: set.dates.iter().min().cloned(),
+            train_end_date: set.dates.iter().max().cloned(),
+            sample_count: set.x.len() as i64,
+        },
+        &shadow,
+    )
+    .await
+}
+
+fn shadow_score(
+    champion: &ModelVersion,
+    kind: ModelKind,
+    set: &ForecastTrainingSet,
+) -> Result<ShadowReport, String> {
+    let cols: ForecastModelColumns =
+        serde_json::from_value(champion.payload["columns"].clone()).map_err(|e| e.to_string())?;
+    let champion_model = ForecastModel::from_columns(&cols)?;
+    let after = champion.train_end_date.as_deref().unwrap_or("");
+
+    let (mut seen_x, mut seen_y) = (Vec::new(), Vec::new());
+    let (mut new_x, mut new_y) = (Vec::new(), Vec::new());
+    for ((row, target), d) in set.x.iter().zip(&set.y).zip(&set.dates) {
+        if d.as_str() > after {
+            new_x.push(row.clone());
+            new_y.push(*target);
+        } else {
+            seen_x.push(row.clone());
+            seen_y.push(*target);
+        }
+    }
+    let predict = |m: &ForecastModel| -> Vec<f64> {
+        new_x.iter().map(|r| m.predict(r).unwrap_or(0.0)).collect()
+    };
+    let challenger_pred = if seen_x.len() >= 10 {
+        train_forecast_model(kind, &seen_x, &seen_y)
+            .map(|m| predict(&m))
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    Ok(ShadowReport::compare(
+        "rmse",
+        champion,
+        &new_y,
+        &predict(&champion_model),
+        &challenger_pred,
+    ))
+}
+
+/// 读取服务中的模型及其 trained_at。
+pub async fn load_serving_model(
+    pool: &sqlx::AnyPool,
+    key: &ForecastModelKey,
+) -> Result<Option<(ForecastModel, String)>, String> {
+    let row = sqlx::query(
+        r#"
+        SELECT
+          weights_json,
+          bias,
+          mean_json,
+          std_json,
+          residual_sigma,
+          CAST(trained_at AS TEXT) as trained_at
+        FROM forecast_model
+        WHERE model_name = $1 AND source = $2 AND horizon = $3 AND lag_k = $4
+        ORDER BY trained_at DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(&key.model_name)
+    .bind(&key.source)
+    .bind(key.horizon)
+    .bind(key.lag_k)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    let model = ForecastModel::from_columns(&ForecastModelColumns {
+        weights_json: row.get("weights_json"),
+        bias: row.get::<f64, _>("bias"),
+        mean_json: row.get("mean_json"),
+        std_json: row.get("std_json"),
+        residual_sigma: row.get::<f64, _>("residual_sigma"),
+    })?;
+
+    Ok(Some((model, row.get::<String, _>("trained_at"))))
+}
+
+/// 把冠军版本写回 `forecast_model`（在 [`registry::promote`] 的事务内调用）；trained_at 记为生效时间。
+pub async fn store_serving_model(
+    tx: &mut sqlx::Transaction<'_, sqlx::Any>,
+    pg: bool,
+    version: &ModelVersion,
+) -> Result<(), String> {
+    let payload = &version.payload;
+    let model_name = payload["model_name"]
+        .as_str()
+        .ok_or("payload missing model_name")?;
+    let source = payload["source"].as_str().ok_or("payload missing source")?;
+    let horizon = payload["horizon"]
+        .as_i64()
+        .ok_or("payload missing horizon")?;
+    let lag_k = payload["lag_k"].as_i64().ok_or("payload missing lag_k")?;
+    let cols: ForecastModelColumns =
+        serde_json::from_value(payload["columns"].clone()).map_err(|e| e.to_string())?;
+    let id = Uuid::new_v4().to_string();
+
+    let sql_pg = r#"
+        INSERT INTO forecast_model (
+          id, model_name, source, horizon, lag_k, weights_json, bias, mean_json, std_json, residual_sigma,
+          sample_count, trained_at, created_at, updated_at
+        )
+        VALUES (($1)::uuid,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,CURRENT_TIMESTAMP,CURRENT_TIMESTAMP,CURRENT_TIMESTAMP)
+        ON CONFLICT (model_name, source, horizon, lag_k) DO UPDATE SET
+          weights_json = excluded.weights_json,
+          bias = excluded.bias,
+          mean_json = excluded.mean_json,
+          std_json = excluded.std_json,
+          residual_sigma = excluded.residual_sigma,
+          sample_count = excluded.sample_count,
+          trained_at = excluded.trained_at,
+          updated_at = CURRENT_TIMESTAMP
+    "#;
+    let sql_any = r#"
+        INSERT INTO forecast_model (
+          id, model_name, source, horizon, lag_k, weights_json, bias, mean_json, std_json, residual_sigma,
+          sample_count, trained_at, created_at, updated_at
+        )
+        VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,CURRENT_TIMESTAMP,CURRENT_TIMESTAMP,CURRENT_TIMESTAMP)
+        ON CONFLICT (model_name, source, horizon, lag_k) DO UPDATE SET
+          weights_json = excluded.weights_json,
+          bias = excluded.bias,
+          mean_json = excluded.mean_json,
+          std_json = excluded.std_json,
+          residual_sigma = excluded.residual_sigma,
+          sample_count = excluded.sample_count,
+          trained_at = excluded.trained_at,
+          updated_at = CURRENT_TIMESTAMP
+    "#;
+
+    // 在事务内执行：Postgres 语句失败会中止整个事务，不能先试再退，须按库类型选 SQL。
+    sqlx::query(if pg { sql_pg } else { sql_any })
+        .bind(id)
+        .bind(model_name)
+        .bind(source)
+        .bind(horizon)
+        .bind(lag_k)
+        .bind(cols.weights_json)
+        .bind(cols.bias)
+        .bind(cols.mean_json)
+        .bind(cols.std_json)
+        .bind(cols.residual_sigma)
+        .bind(version.sample_count)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
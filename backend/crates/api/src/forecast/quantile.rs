@@ -0,0 +1,415 @@
+//! 多期限净值分位数预测（预测扇形）。
+//!
+//! 每个期限 h 单独建一个直接预测模型：输入为最近 `lag_k` 个日对数收益，目标为其后 h 个交易日的
+//! 累计对数收益。分位数用经验残差分位：按日期做前移（walk-forward）回测，每折只用目标区间已结束的
+//! 样本训练，收集样本外残差；上线模型用全部样本训练，P10/P50/P90 偏移取全部样本外残差的分位数。
+//!
+//! 覆盖率回测：第 f 折的区间只用前 f 折的残差构造，统计落入 [P10, P90] 的比例（名义 80%）。
+//! 基金自身样本不足时改用同类组（见 [`crate::ml::cluster::fund_peers`]）成员的合并样本。
+
+use serde::Serialize;
+use sqlx::Row;
+
+use crate::features;
+use crate::ml::model::ModelKind;
+
+use super::model::{ForecastModel, train_forecast_model};
+
+pub const DEFAULT_HORIZONS: [usize; 4] = [5, 20, 60, 120];
+/// 输出的分位点。
+pub const QUANTILES: [f64; 3] = [0.1, 0.5, 0.9];
+/// 单只基金建模所需的最少样本数（每个期限）。
+pub const MIN_FUND_SAMPLES: usize = 120;
+const WALK_FORWARD_FOLDS: usize = 5;
+/// 每折训练所需的最少样本数。
+const MIN_FOLD_TRAIN: usize = 30;
+/// 校准分位偏移所需的最少样本外残差数。
+const MIN_RESIDUALS: usize = 20;
+/// 每只基金读取的最长收益序列。
+const MAX_HISTORY: i64 = 1500;
+/// 同类组合并建模时最多取的成员数。
+const PEER_POOL_LIMIT: usize = 50;
+
+/// 某个期限的训练样本，按 `as_of` 升序。
+#[derive(Debug, Clone, Default)]
+pub struct HorizonSet {
+    pub x: Vec<Vec<f64>>,
+    pub y: Vec<f64>,
+    /// 特征截止日（最后一个滞后收益的日期）。
+    pub as_of: Vec<String>,
+    /// 目标区间最后一天。
+    pub target_end: Vec<String>,
+}
+
+impl HorizonSet {
+    pub fn len(&self) -> usize {
+        self.x.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty()
+    }
+
+    fn extend(&mut self, other: HorizonSet) {
+        self.x.extend(other.x);
+        self.y.extend(other.y);
+        self.as_of.extend(other.as_of);
+        self.target_end.extend(other.target_end);
+    }
+
+    fn sort_by_as_of(&mut self) {
+        let mut idx: Vec<usize> = (0..self.len()).collect();
+        idx.sort_by(|&a, &b| self.as_of[a].cmp(&self.as_of[b]));
+        let take = |v: &[String]| idx.iter().map(|&i| v[i].clone()).collect::<Vec<_>>();
+        *self = HorizonSet {
+            x: idx.iter().map(|&i| self.x[i].clone()).collect(),
+            y: idx.iter().map(|&i| self.y[i]).collect(),
+            as_of: take(&self.as_of),
+            target_end: take(&self.target_end),
+        };
+    }
+}
+
+/// 由日对数收益序列（日期升序）构建期限 h 的样本。
+pub fn horizon_samples(returns: &[(String, f64)], lag_k: usize, horizon: usize) -> HorizonSet {
+    let mut set = HorizonSet::default();
+    if lag_k == 0 || horizon == 0 {
+        return set;
+    }
+    for t in lag_k..=returns.len().saturating_sub(horizon) {
+        set.x
+            .push(returns[t - lag_k..t].iter().map(|(_, v)| *v).collect());
+        set.y
+            .push(returns[t..t + horizon].iter().map(|(_, v)| *v).sum());
+        set.as_of.push(returns[t - 1].0.clone());
+        set.target_end.push(returns[t + horizon - 1].0.clone());
+    }
+    set
+}
+
+/// 样本分位数（线性插值）；`sorted` 须升序。
+pub fn quantile(sorted: &[f64], q: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let pos = q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let lo = pos.floor() as usize;
+    let hi = pos.ceil() as usize;
+    Some(sorted[lo] + (sorted[hi] - sorted[lo]) * (pos - lo as f64))
+}
+
+fn quantile_offsets(residuals: &[f64]) -> Option<[f64; 3]> {
+    let mut sorted = residuals.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    Some([
+        quantile(&sorted, QUANTILES[0])?,
+        quantile(&sorted, QUANTILES[1])?,
+        quantile(&sorted, QUANTILES[2])?,
+    ])
+}
+
+fn pinball(residual: f64, offset: f64, q: f64) -> f64 {
+    let u = residual - offset;
+    if u >= 0.0 { q * u } else { (q - 1.0) * u }
+}
+
+/// 前移回测结果（对数收益尺度）。
+#[derive(Debug, Clone, Serialize)]
+pub struct Backtest {
+    /// [P10, P90] 的名义覆盖率。
+    pub nominal_coverage: f64,
+    /// 实际覆盖率；只有一折有残差时为 None。
+    pub coverage: Option<f64>,
+    /// 参与覆盖率统计的样本数。
+    pub evaluated: usize,
+    /// 平均区间宽度。
+    pub mean_width: Option<f64>,
+    /// 三个分位点的平均 pinball 损失。
+    pub pinball_loss: Option<f64>,
+    /// 样本外残差总数（用于上线模型的分位偏移）。
+    pub residuals: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct QuantileForecaster {
+    pub horizon: usize,
+    pub model: ForecastModel,
+    /// 相对点预测的 P10/P50/P90 偏移。
+    pub offsets: [f64; 3],
+    pub sample_count: usize,
+    pub backtest: Backtest,
+}
+
+impl QuantileForecaster {
+    /// 拟合并回测；样本不足以完成回测校准时为 None。`set` 须按 `as_of` 升序。
+    pub fn fit(set: &HorizonSet, horizon: usize, kind: ModelKind) -> Option<Self> {
+        let n = set.len();
+        if n < 2 * MIN_FOLD_TRAIN {
+            return None;
+        }
+        // 后一半样本均分为若干折。
+        let start = n / 2;
+        let fold_len = (n - start).div_ceil(WALK_FORWARD_FOLDS);
+        let mut folds: Vec<Vec<f64>> = Vec::new();
+        for f in 0..WALK_FORWARD_FOLDS {
+            let a = start + f * fold_len;
+            let b = (a + fold_len).min(n);
+            if a >= b {
+                break;
+            }
+            // 只用目标区间在本折第一天之前结束的样本，避免重叠目标泄漏。
+            let cutoff = &set.as_of[a];
+            let (tx, ty): (Vec<Vec<f64>>, Vec<f64>) = (0..a)
+                .filter(|&i| set.target_end[i] <= *cutoff)
+                .map(|i| (set.x[i].clone(), set.y[i]))
+                .unzip();
+            if tx.len() < MIN_FOLD_TRAIN {
+                continue;
+            }
+            let Some(m) = train_forecast_model(kind, &tx, &ty) else {
+                continue;
+            };
+            folds.push(
+                (a..b)
+                    .map(|i| set.y[i] - m.predict(&set.x[i]).unwrap_or(0.0))
+                    .collect(),
+            );
+        }
+
+        let all: Vec<f64> = folds.iter().flatten().copied().collect();
+        if all.len() < MIN_RESIDUALS {
+            return None;
+        }
+        let (mut hits, mut evaluated, mut width, mut loss) = (0_usize, 0_usize, 0.0, 0.0);
+        for f in 1..folds.len() {
+            let prior: Vec<f64> = folds[..f].iter().flatten().copied().collect();
+            let Some(o) = quantile_offsets(&prior) else {
+                continue;
+            };
+            for &r in &folds[f] {
+                evaluated += 1;
+                hits += (r >= o[0] && r <= o[2]) as usize;
+                width += o[2] - o[0];
+                loss += QUANTILES
+                    .iter()
+                    .zip(o.iter())
+                    .map(|(&q, &off)| pinball(r, off, q))
+                    .sum::<f64>()
+                    / QUANTILES.len() as f64;
+            }
+        }
+        let per = |v: f64| (evaluated > 0).then(|| v / evaluated as f64);
+
+        Some(Self {
+            horizon,
+            model: train_forecast_model(kind, &set.x, &set.y)?,
+            offsets: quantile_offsets(&all)?,
+            sample_count: n,
+            backtest: Backtest {
+                nominal_coverage: QUANTILES[2] - QUANTILES[0],
+                coverage: per(hits as f64),
+                evaluated,
+                mean_width: per(width),
+                pinball_loss: per(loss),
+                residuals: all.len(),
+            },
+        })
+    }
+
+    /// 累计对数收益的 [P10, P50, P90]（保证单调）。
+    pub fn predict(&self, x: &[f64]) -> Option<[f64; 3]> {
+        let mu = self.model.predict(x)?;
+        let mut q = self.offsets.map(|o| mu + o);
+        q.sort_by(|a, b| a.total_cmp(b));
+        Some(q)
+    }
+}
+
+/// 分位三元组（P10/P50/P90）。
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Band {
+    pub p10: f64,
+    pub p50: f64,
+    pub p90: f64,
+}
+
+impl Band {
+    fn new(q: [f64; 3]) -> Self {
+        Self {
+            p10: q[0],
+            p50: q[1],
+            p90: q[2],
+        }
+    }
+}
+
+/// 某期限的预测。
+#[derive(Debug, Clone, Serialize)]
+pub struct HorizonForecast {
+    /// 交易日数。
+    pub horizon: usize,
+    /// `fund` 或 `peer`。
+    pub scope: String,
+    pub scope_code: String,
+    pub model_type: String,
+    pub sample_count: usize,
+    /// 累计对数收益分位。
+    pub log_return: Band,
+    /// 净值分位。
+    pub nav: Band,
+    pub backtest: Backtest,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ForecastFan {
+    pub as_of_date: String,
+    pub base_nav: f64,
+    pub lag_k: usize,
+    pub horizons: Vec<HorizonForecast>,
+    /// 基金与同类组样本都不足、未能预测的期限。
+    pub skipped: Vec<usize>,
+}
+
+async fn latest_nav(
+    pool: &sqlx::AnyPool,
+    fund_code: &str,
+    source_name: &str,
+) -> Result<Option<f64>, String> {
+    let row = sqlx::query(
+        r#"
+        SELECT CAST(h.unit_nav AS TEXT) as unit_nav
+        FROM fund_nav_history h
+        JOIN fund f ON f.id = h.fund_id
+        WHERE f.fund_code = $1 AND h.source_name = $2
+        ORDER BY h.nav_date DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(fund_code)
+    .bind(source_name)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(row.and_then(|r| r.get::<String, _>("unit_nav").trim().parse::<f64>().ok()))
+}
+
+async fn load_returns(
+    pool: &sqlx::AnyPool,
+    fund_code: &str,
+    source_name: &str,
+) -> Result<Vec<(String, f64)>, String> {
+    features::store::load_dated_series(
+        pool,
+        fund_code,
+        source_name,
+        features::LAGGED_RETURN_FEATURE,
+        None,
+        Some(MAX_HISTORY),
+    )
+    .await
+}
+
+/// 同类组成员的合并样本（不含空成员）；返回 (peer_code, 样本)。
+async fn peer_sets(
+    pool: &sqlx::AnyPool,
+    fund_code: &str,
+    source_name: &str,
+    lag_k: usize,
+    horizons: &[usize],
+) -> Result<Option<(String, Vec<HorizonSet>)>, String> {
+    let Some((peer_code, _)) = crate::ml::cluster::fund_peers(pool, fund_code, source_name, 1)
+        .await?
+        .into_iter()
+        .next()
+    else {
+        return Ok(None);
+    };
+    let members = features::store::peer_members(pool, &peer_code, source_name).await?;
+    let mut sets = vec![HorizonSet::default(); horizons.len()];
+    for code in members.iter().take(PEER_POOL_LIMIT) {
+        features::store::materialize_fund(pool, code, source_name).await?;
+        let returns = load_returns(pool, code, source_name).await?;
+        for (set, &h) in sets.iter_mut().zip(horizons) {
+            set.extend(horizon_samples(&returns, lag_k, h));
+        }
+    }
+    for set in &mut sets {
+        set.sort_by_as_of();
+    }
+    Ok(Some((peer_code, sets)))
+}
+
+/// 基金的多期限预测扇形；样本足够的期限用基金自身模型，否则用同类组合并模型。
+/// 基金特征需已物化（[`features::store::materialize_fund`]）。
+pub async fn forecast_fan(
+    pool: &sqlx::AnyPool,
+    fund_code: &str,
+    source_name: &str,
+    horizons: &[usize],
+    lag_k: usize,
+    kind: ModelKind,
+) -> Result<Option<ForecastFan>, String> {
+    let returns = load_returns(pool, fund_code, source_name).await?;
+    let (Some((as_of_date, _)), Some(base_nav)) = (
+        returns.last(),
+        latest_nav(pool, fund_code, source_name).await?,
+    ) else {
+        return Ok(None);
+    };
+    let x = features::last_lags(&returns.iter().map(|(_, v)| *v).collect::<Vec<_>>(), lag_k);
+
+    let mut fitted: Vec<Option<(&str, QuantileForecaster)>> = horizons
+        .iter()
+        .map(|&h| {
+            let set = horizon_samples(&returns, lag_k, h);
+            (set.len() >= MIN_FUND_SAMPLES)
+                .then(|| QuantileForecaster::fit(&set, h, kind))
+                .flatten()
+                .map(|f| ("fund", f))
+        })
+        .collect();
+    let mut peer_code = None;
+    if fitted.iter().any(Option::is_none)
+        && let Some((code, sets)) = peer_sets(pool, fund_code, source_name, lag_k, horizons).await?
+    {
+        for ((slot, set), &h) in fitted.iter_mut().zip(sets.iter()).zip(horizons) {
+            if slot.is_none() {
+                *slot = QuantileForecaster::fit(set, h, kind).map(|f| ("peer", f));
+            }
+        }
+        peer_code = Some(code);
+    }
+
+    let mut fan = ForecastFan {
+        as_of_date: as_of_date.clone(),
+        base_nav,
+        lag_k,
+        horizons: Vec::new(),
+        skipped: Vec::new(),
+    };
+    for (slot, &h) in fitted.into_iter().zip(horizons) {
+        let Some((scope, f)) = slot else {
+            fan.skipped.push(h);
+            continue;
+        };
+        let Some(q) = f.predict(&x) else {
+            fan.skipped.push(h);
+            continue;
+        };
+        fan.horizons.push(HorizonForecast {
+            horizon: h,
+            scope: scope.to_string(),
+            scope_code: if scope == "fund" {
+                fund_code.to_string()
+            } else {
+                peer_code.clone().unwrap_or_default()
+            },
+            model_type: f.model.kind().as_str().to_string(),
+            sample_count: f.sample_count,
+            log_return: Band::new(q),
+            nav: Band::new(q.map(|v| base_nav * v.exp())),
+            backtest: f.backtest,
+        });
+    }
+    Ok(Some(fan))
+}
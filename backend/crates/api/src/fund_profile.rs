@@ -0,0 +1,592 @@
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+use chrono::NaiveDate;
+use regex::Regex;
+use serde_json::Value;
+use sqlx::Row;
+
+use crate::db::DatabaseKind;
+
+pub const SOURCE_EASTMONEY: &str = "eastmoney";
+
+static F10_ROW_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<tr>(.*?)</tr>").expect("valid regex"));
+static F10_CELL_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<td[^>]*>(.*?)</td>").expect("valid regex"));
+static F10_MANAGER_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?s)<a[^>]*manager/(\d+)\.html[^>]*>(.*?)</a>"#).expect("valid regex")
+});
+static HTML_TAG_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<[^>]+>").expect("valid regex"));
+static SCALE_HISTORY_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)var\s+Data_fluctuationScale\s*=\s*(\{.*?\})\s*;").expect("valid regex")
+});
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FundProfile {
+    pub fund_code: String,
+    pub fund_name: Option<String>,
+    pub company_id: Option<String>,
+    pub company_name: Option<String>,
+    pub inception_date: Option<NaiveDate>,
+    pub benchmark: Option<String>,
+    pub management_fee_pct: Option<f64>,
+    pub custody_fee_pct: Option<f64>,
+    pub share_class: Option<String>,
+    pub purchase_status: Option<String>,
+    pub redeem_status: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManagerTenure {
+    pub manager_id: String,
+    pub manager_name: String,
+    pub start_date: NaiveDate,
+    /// None 表示“至今”仍在任。
+    pub end_date: Option<NaiveDate>,
+    pub tenure_return_pct: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScalePoint {
+    pub report_date: NaiveDate,
+    pub aum_cny: f64,
+}
+
+fn non_empty(v: Option<&Value>) -> Option<String> {
+    let s = match v? {
+        Value::String(s) => s.trim().to_string(),
+        Value::Number(n) => n.to_string(),
+        _ => return None,
+    };
+    if s.is_empty() || s == "--" { None } else { Some(s) }
+}
+
+fn parse_pct(raw: &str) -> Option<f64> {
+    let s = raw.trim().trim_end_matches('%').trim();
+    if s.is_empty() || s == "--" {
+        return None;
+    }
+    s.parse::<f64>().ok()
+}
+
+fn parse_date(raw: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(raw.trim(), "%Y-%m-%d").ok()
+}
+
+/// 从基金简称推断份额类别（A/C 等）；ETF/LOF/QDII 这类以大写字母结尾的名称不算份额后缀。
+pub fn share_class_from_name(name: &str) -> Option<String> {
+    let chars: Vec<char> = name.trim().chars().collect();
+    let last = *chars.last()?;
+    if !matches!(last, 'A' | 'B' | 'C' | 'D' | 'E' | 'I' | 'Y') {
+        return None;
+    }
+    if chars.len() >= 2 && chars[chars.len() - 2].is_ascii_alphabetic() {
+        return None;
+    }
+    Some(last.to_string())
+}
+
+/// 解析 FundMApi/FundBaseTypeInformation.ashx 的 JSON。
+pub fn parse_fund_base_info(fund_code: &str, payload: &str) -> Result<Option<FundProfile>, String> {
+    let root: Value =
+        serde_json::from_str(payload).map_err(|e| format!("基金基础信息 JSON 解析失败: {e}"))?;
+    let Some(d) = root.get("Datas").filter(|v| v.is_object()) else {
+        return Ok(None);
+    };
+
+    let fund_name = non_empty(d.get("SHORTNAME"));
+    let share_class = fund_name.as_deref().and_then(share_class_from_name);
+
+    Ok(Some(FundProfile {
+        fund_code: non_empty(d.get("FCODE")).unwrap_or_else(|| fund_code.trim().to_string()),
+        fund_name,
+        company_id: non_empty(d.get("JJGSID")),
+        company_name: non_empty(d.get("JJGS")),
+        inception_date: non_empty(d.get("ESTABDATE")).and_then(|s| parse_date(&s)),
+        benchmark: non_empty(d.get("BENCH")),
+        management_fee_pct: non_empty(d.get("MGREXP")).and_then(|s| parse_pct(&s)),
+        custody_fee_pct: non_empty(d.get("TRUSTEXP")).and_then(|s| parse_pct(&s)),
+        share_class,
+        purchase_status: non_empty(d.get("SGZT")),
+        redeem_status: non_empty(d.get("SHZT")),
+    }))
+}
+
+/// 解析 F10 “基金经理变动一览”（jjjl 页面第一张表）。
+pub fn parse_manager_tenures_from_f10_html(html: &str) -> Vec<ManagerTenure> {
+    let Some(start) = html.find("基金经理变动一览") else {
+        return Vec::new();
+    };
+    let rest = &html[start..];
+    let table_end = rest.find("</table>").unwrap_or(rest.len());
+    let table = &rest[..table_end];
+
+    let mut out = Vec::new();
+    for row in F10_ROW_RE.captures_iter(table) {
+        let cells: Vec<&str> = F10_CELL_RE
+            .captures_iter(&row[1])
+            .filter_map(|c| c.get(1).map(|m| m.as_str()))
+            .collect();
+        if cells.len() < 5 {
+            continue;
+        }
+        let Some(start_date) = parse_date(&HTML_TAG_RE.replace_all(cells[0], "")) else {
+            continue;
+        };
+        let end_date = parse_date(&HTML_TAG_RE.replace_all(cells[1], ""));
+        let tenure_return_pct = parse_pct(&HTML_TAG_RE.replace_all(cells[4], ""));
+
+        // 同一任期可能有多位共同管理的基金经理。
+        for m in F10_MANAGER_RE.captures_iter(cells[2]) {
+            let name = HTML_TAG_RE.replace_all(&m[2], "").trim().to_string();
+            if name.is_empty() {
+                continue;
+            }
+            out.push(ManagerTenure {
+                manager_id: m[1].to_string(),
+                manager_name: name,
+                start_date,
+                end_date,
+                tenure_return_pct,
+            });
+        }
+    }
+    out
+}
+
+/// 解析 pingzhongdata JS 中的 `Data_fluctuationScale`（单位：亿元）。
+pub fn parse_scale_history_from_pingzhongdata(js: &str) -> Vec<ScalePoint> {
+    let Some(cap) = SCALE_HISTORY_RE.captures(js) else {
+        return Vec::new();
+    };
+    let Ok(v) = serde_json::from_str::<Value>(&cap[1]) else {
+        return Vec::new();
+    };
+    let categories = v.get("categories").and_then(|x| x.as_array());
+    let series = v.get("series").and_then(|x| x.as_array());
+    let (Some(categories), Some(series)) = (categories, series) else {
+        return Vec::new();
+    };
+
+    let mut out = Vec::new();
+    for (c, s) in categories.iter().zip(series.iter()) {
+        let Some(report_date) = c.as_str().and_then(parse_date) else {
+            continue;
+        };
+        let Some(y) = s.get("y").and_then(|y| y.as_f64()) else {
+            continue;
+        };
+        out.push(ScalePoint {
+            report_date,
+            aum_cny: y * 100_000_000.0,
+        });
+    }
+    out
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FundProfileBundle {
+    pub profile: Option<FundProfile>,
+    pub managers: Vec<ManagerTenure>,
+    pub scale_history: Vec<ScalePoint>,
+}
+
+async fn fetch_text(client: &reqwest::Client, url: &str) -> Result<String, String> {
+    let resp = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {url}: {e}"))?;
+    resp.text()
+        .await
+        .map_err(|e| format!("读取响应失败: {url}: {e}"))
+}
+
+pub async fn fetch_fund_profile_bundle(
+    client: &reqwest::Client,
+    fund_code: &str,
+) -> Result<FundProfileBundle, String> {
+    let code = fund_code.trim();
+    if code.is_empty() {
+        return Ok(FundProfileBundle::default());
+    }
+
+    let base_url = format!(
+        "https://fundmobapi.eastmoney.com/FundMApi/FundBaseTypeInformation.ashx?FCODE={code}&deviceid=Wap&plat=Wap&product=EFund&version=2.0.0"
+    );
+    let base_text = fetch_text(client, &base_url).await?;
+    let profile = parse_fund_base_info(code, &base_text)?;
+
+    // 经理/规模属于补充信息：失败不影响基础资料落库。
+    let managers = match fetch_text(
+        client,
+        &format!("https://fundf10.eastmoney.com/jjjl_{code}.html"),
+    )
+    .await
+    {
+        Ok(html) => parse_manager_tenures_from_f10_html(&html),
+        Err(_) => Vec::new(),
+    };
+    let scale_history = match fetch_text(
+        client,
+        &format!("https://fund.eastmoney.com/pingzhongdata/{code}.js"),
+    )
+    .await
+    {
+        Ok(js) => parse_scale_history_from_pingzhongdata(&js),
+        Err(_) => Vec::new(),
+    };
+
+    Ok(FundProfileBundle {
+        profile,
+        managers,
+        scale_history,
+    })
+}
+
+pub async fn upsert_fund_profile_bundle(
+    pool: &sqlx::AnyPool,
+    fund_code: &str,
+    source: &str,
+    bundle: &FundProfileBundle,
+) -> Result<(), String> {
+    let code = fund_code.trim();
+    if code.is_empty() {
+        return Ok(());
+    }
+    let is_postgres = crate::db::database_kind_from_pool(pool) == DatabaseKind::Postgres;
+
+    if let Some(p) = &bundle.profile {
+        if let (Some(cid), Some(cname)) = (p.company_id.as_deref(), p.company_name.as_deref()) {
+            sqlx::query(
+                r#"
+                INSERT INTO fund_company (company_id, company_name, created_at, updated_at)
+                VALUES ($1, $2, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+                ON CONFLICT (company_id) DO UPDATE
+                  SET company_name = excluded.company_name,
+                      updated_at = CURRENT_TIMESTAMP
+                "#,
+            )
+            .bind(cid)
+            .bind(cname)
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        }
+
+        let sql = if is_postgres {
+            r#"
+            INSERT INTO fund_profile (
+              fund_code, company_id, inception_date, benchmark, management_fee_pct, custody_fee_pct,
+              share_class, purchase_status, redeem_status, source, fetched_at, created_at, updated_at
+            ) VALUES (
+              $1, $2, ($3)::date, $4, $5, $6, $7, $8, $9, $10, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP
+            )
+            ON CONFLICT (fund_code) DO UPDATE
+              SET company_id = EXCLUDED.company_id,
+                  inception_date = EXCLUDED.inception_date,
+                  benchmark = EXCLUDED.benchmark,
+                  management_fee_pct = EXCLUDED.management_fee_pct,
+                  custody_fee_pct = EXCLUDED.custody_fee_pct,
+                  share_class = EXCLUDED.share_class,
+                  purchase_status = EXCLUDED.purchase_status,
+                  redeem_status = EXCLUDED.redeem_status,
+                  source = EXCLUDED.source,
+                  fetched_at = CURRENT_TIMESTAMP,
+                  updated_at = CURRENT_TIMESTAMP
+            "#
+        } else {
+            r#"
+            INSERT INTO fund_profile (
+              fund_code, company_id, inception_date, benchmark, management_fee_pct, custody_fee_pct,
+              share_class, purchase_status, redeem_status, source, fetched_at, created_at, updated_at
+            ) VALUES (
+              $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP
+            )
+            ON CONFLICT (fund_code) DO UPDATE
+              SET company_id = excluded.company_id,
+                  inception_date = excluded.inception_date,
+                  benchmark = excluded.benchmark,
+                  management_fee_pct = excluded.management_fee_pct,
+                  custody_fee_pct = excluded.custody_fee_pct,
+                  share_class = excluded.share_class,
+                  purchase_status = excluded.purchase_status,
+                  redeem_status = excluded.redeem_status,
+                  source = excluded.source,
+                  fetched_at = CURRENT_TIMESTAMP,
+                  updated_at = CURRENT_TIMESTAMP
+            "#
+        };
+
+        sqlx::query(sql)
+            .bind(code)
+            .bind(p.company_id.as_deref())
+            .bind(p.inception_date.map(|d| d.to_string()))
+            .bind(p.benchmark.as_deref())
+            .bind(p.management_fee_pct)
+            .bind(p.custody_fee_pct)
+            .bind(p.share_class.as_deref())
+            .bind(p.purchase_status.as_deref())
+            .bind(p.redeem_status.as_deref())
+            .bind(source)
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    if !bundle.managers.is_empty() {
+        let sql = if is_postgres {
+            r#"
+            INSERT INTO fund_manager_tenure (
+              fund_code, manager_id, manager_name, start_date, end_date, tenure_return_pct, source, created_at, updated_at
+            ) VALUES ($1, $2, $3, ($4)::date, ($5)::date, $6, $7, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            ON CONFLICT (fund_code, manager_id, start_date) DO UPDATE
+              SET manager_name = EXCLUDED.manager_name,
+                  end_date = EXCLUDED.end_date,
+                  tenure_return_pct = EXCLUDED.tenure_return_pct,
+                  source = EXCLUDED.source,
+                  updated_at = CURRENT_TIMESTAMP
+            "#
+        } else {
+            r#"
+            INSERT INTO fund_manager_tenure (
+              fund_code, manager_id, manager_name, start_date, end_date, tenure_return_pct, source, created_at, updated_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            ON CONFLICT (fund_code, manager_id, start_date) DO UPDATE
+              SET manager_name = excluded.manager_name,
+                  end_date = excluded.end_date,
+                  tenure_return_pct = excluded.tenure_return_pct,
+                  source = excluded.source,
+                  updated_at = CURRENT_TIMESTAMP
+            "#
+        };
+        for m in &bundle.managers {
+            sqlx::query(sql)
+                .bind(code)
+                .bind(&m.manager_id)
+                .bind(&m.manager_name)
+                .bind(m.start_date.to_string())
+                .bind(m.end_date.map(|d| d.to_string()))
+                .bind(m.tenure_return_pct)
+                .bind(source)
+                .execute(pool)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+
+        // 以本次抓到的完整经理表为准：删除该来源下已不在表中的任期（如被更正的任职日期）。
+        // 抓取失败时 managers 为空，不会走到这里，避免误删。
+        let fresh: HashSet<(&str, String)> = bundle
+            .managers
+            .iter()
+            .map(|m| (m.manager_id.as_str(), m.start_date.to_string()))
+            .collect();
+        let existing = sqlx::query(
+            r#"
+            SELECT manager_id, CAST(start_date AS TEXT) as start_date
+            FROM fund_manager_tenure
+            WHERE fund_code = $1 AND source = $2
+            "#,
+        )
+        .bind(code)
+        .bind(source)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        let delete_sql = if is_postgres {
+            "DELETE FROM fund_manager_tenure WHERE fund_code = $1 AND manager_id = $2 AND start_date = ($3)::date AND source = $4"
+        } else {
+            "DELETE FROM fund_manager_tenure WHERE fund_code = $1 AND manager_id = $2 AND start_date = $3 AND source = $4"
+        };
+        for r in existing {
+            let manager_id: String = r.get("manager_id");
+            let start_date: String = r.get("start_date");
+            if fresh.contains(&(manager_id.as_str(), start_date.clone())) {
+                continue;
+            }
+            sqlx::query(delete_sql)
+                .bind(code)
+                .bind(&manager_id)
+                .bind(&start_date)
+                .bind(source)
+                .execute(pool)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    if !bundle.scale_history.is_empty() {
+        let sql = if is_postgres {
+            r#"
+            INSERT INTO fund_scale_history (fund_code, report_date, aum_cny, source, created_at, updated_at)
+            VALUES ($1, ($2)::date, $3, $4, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            ON CONFLICT (fund_code, report_date, source) DO UPDATE
+              SET aum_cny = EXCLUDED.aum_cny,
+                  updated_at = CURRENT_TIMESTAMP
+            "#
+        } else {
+            r#"
+            INSERT INTO fund_scale_history (fund_code, report_date, aum_cny, source, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            ON CONFLICT (fund_code, report_date, source) DO UPDATE
+              SET aum_cny = excluded.aum_cny,
+                  updated_at = CURRENT_TIMESTAMP
+            "#
+        };
+        for s in &bundle.scale_history {
+            sqlx::query(sql)
+                .bind(code)
+                .bind(s.report_date.to_string())
+                .bind(s.aum_cny)
+                .bind(source)
+                .execute(pool)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FundProfileOut {
+    pub company_id: Option<String>,
+    pub company_name: Option<String>,
+    pub inception_date: Option<String>,
+    pub benchmark: Option<String>,
+    pub management_fee_pct: Option<f64>,
+    pub custody_fee_pct: Option<f64>,
+    pub share_class: Option<String>,
+    pub purchase_status: Option<String>,
+    pub redeem_status: Option<String>,
+    pub source: String,
+    pub fetched_at: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ManagerTenureOut {
+    pub manager_id: String,
+    pub manager_name: String,
+    pub start_date: String,
+    pub end_date: Option<String>,
+    pub tenure_days: i64,
+    pub tenure_return_pct: Option<f64>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ScalePointOut {
+    pub report_date: String,
+    pub aum_cny: f64,
+}
+
+pub async fn load_fund_profile(
+    pool: &sqlx::AnyPool,
+    fund_code: &str,
+) -> Result<Option<FundProfileOut>, String> {
+    let row = sqlx::query(
+        r#"
+        SELECT
+          p.company_id as company_id,
+          c.company_name as company_name,
+          CAST(p.inception_date AS TEXT) as inception_date,
+          p.benchmark as benchmark,
+          p.management_fee_pct as management_fee_pct,
+          p.custody_fee_pct as custody_fee_pct,
+          p.share_class as share_class,
+          p.purchase_status as purchase_status,
+          p.redeem_status as redeem_status,
+          p.source as source,
+          CAST(p.fetched_at AS TEXT) as fetched_at
+        FROM fund_profile p
+        LEFT JOIN fund_company c ON c.company_id = p.company_id
+        WHERE p.fund_code = $1
+        "#,
+    )
+    .bind(fund_code.trim())
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(row.map(|r| FundProfileOut {
+        company_id: r.try_get::<Option<String>, _>("company_id").ok().flatten(),
+        company_name: r.try_get::<Option<String>, _>("company_name").ok().flatten(),
+        inception_date: r.try_get::<Option<String>, _>("inception_date").ok().flatten(),
+        benchmark: r.try_get::<Option<String>, _>("benchmark").ok().flatten(),
+        management_fee_pct: r.try_get::<Option<f64>, _>("management_fee_pct").ok().flatten(),
+        custody_fee_pct: r.try_get::<Option<f64>, _>("custody_fee_pct").ok().flatten(),
+        share_class: r.try_get::<Option<String>, _>("share_class").ok().flatten(),
+        purchase_status: r.try_get::<Option<String>, _>("purchase_status").ok().flatten(),
+        redeem_status: r.try_get::<Option<String>, _>("redeem_status").ok().flatten(),
+        source: r.get::<String, _>("source"),
+        fetched_at: crate::dbfmt::datetime_to_rfc3339(&r.get::<String, _>("fetched_at")),
+    }))
+}
+
+pub async fn load_manager_tenures(
+    pool: &sqlx::AnyPool,
+    fund_code: &str,
+    today: NaiveDate,
+) -> Result<Vec<ManagerTenureOut>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+          manager_id,
+          manager_name,
+          CAST(start_date AS TEXT) as start_date,
+          CAST(end_date AS TEXT) as end_date,
+          tenure_return_pct
+        FROM fund_manager_tenure
+        WHERE fund_code = $1
+        ORDER BY start_date DESC, manager_id ASC
+        "#,
+    )
+    .bind(fund_code.trim())
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut out = Vec::with_capacity(rows.len());
+    for r in rows {
+        let start_date: String = r.get("start_date");
+        let end_date = r.try_get::<Option<String>, _>("end_date").ok().flatten();
+        let start = parse_date(&start_date);
+        let end = end_date.as_deref().and_then(parse_date).unwrap_or(today);
+        out.push(ManagerTenureOut {
+            manager_id: r.get("manager_id"),
+            manager_name: r.get("manager_name"),
+            start_date,
+            end_date,
+            tenure_days: start.map(|s| (end - s).num_days().max(0)).unwrap_or(0),
+            tenure_return_pct: r.try_get::<Option<f64>, _>("tenure_return_pct").ok().flatten(),
+        });
+    }
+    Ok(out)
+}
+
+pub async fn load_scale_history(
+    pool: &sqlx::AnyPool,
+    fund_code: &str,
+) -> Result<Vec<ScalePointOut>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT CAST(report_date AS TEXT) as report_date, aum_cny
+        FROM fund_scale_history
+        WHERE fund_code = $1
+        ORDER BY report_date ASC
+        "#,
+    )
+    .bind(fund_code.trim())
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| ScalePointOut {
+            report_date: r.get("report_date"),
+            aum_cny: r.try_get::<f64, _>("aum_cny").unwrap_or(0.0),
+        })
+        .collect())
+}
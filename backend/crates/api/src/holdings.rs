@@ -0,0 +1,554 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::NaiveDate;
+use regex::Regex;
+use serde::Serialize;
+use sqlx::Row;
+
+use crate::db::DatabaseKind;
+
+pub const SOURCE_EASTMONEY: &str = "eastmoney";
+
+pub const ASSET_STOCK: &str = "stock";
+pub const ASSET_BOND: &str = "bond";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Holding {
+    pub report_date: NaiveDate,
+    pub asset_type: String,
+    pub sec_code: String,
+    pub sec_name: String,
+    /// 占净值比例（%）。
+    pub weight_pct: f64,
+    pub shares: Option<f64>,
+    pub market_value_cny: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndustryAllocation {
+    pub report_date: NaiveDate,
+    pub industry_name: String,
+    pub weight_pct: f64,
+    pub market_value_cny: Option<f64>,
+}
+
+fn cell_text(tag_re: &Regex, raw: &str) -> String {
+    tag_re
+        .replace_all(raw, "")
+        .replace("&nbsp;", "")
+        .replace(',', "")
+        .trim()
+        .to_string()
+}
+
+/// 把 FundArchivesDatas.aspx 的 content 按“报告期”切分：(report_date, 该报告期的 HTML)。
+fn split_report_sections(html: &str) -> Vec<(NaiveDate, &str)> {
+    let date_re =
+        Regex::new(r"截止至：\s*(?:<[^>]+>\s*)*(\d{4}-\d{2}-\d{2})").expect("valid regex");
+    let marks: Vec<(usize, NaiveDate)> = date_re
+        .captures_iter(html)
+        .filter_map(|c| {
+            let m = c.get(0)?;
+            let d = NaiveDate::parse_from_str(&c[1], "%Y-%m-%d").ok()?;
+            Some((m.start(), d))
+        })
+        .collect();
+
+    let mut out = Vec::with_capacity(marks.len());
+    for (i, (start, d)) in marks.iter().enumerate() {
+        let end = marks.get(i + 1).map(|(s, _)| *s).unwrap_or(html.len());
+        out.push((*d, &html[*start..end]));
+    }
+    out
+}
+
+fn table_rows(section: &str) -> Vec<Vec<String>> {
+    let row_re = Regex::new(r"(?s)<tr[^>]*>(.*?)</tr>").expect("valid regex");
+    let cell_re = Regex::new(r"(?s)<td[^>]*>(.*?)</td>").expect("valid regex");
+    let tag_re = Regex::new(r"<[^>]+>").expect("valid regex");
+    row_re
+        .captures_iter(section)
+        .map(|row| {
+            cell_re
+                .captures_iter(&row[1])
+                .map(|c| cell_text(&tag_re, &c[1]))
+                .collect::<Vec<_>>()
+        })
+        .filter(|cells| !cells.is_empty())
+        .collect()
+}
+
+fn parse_pct(s: &str) -> Option<f64> {
+    let t = s.trim();
+    let t = t.strip_suffix('%')?;
+    t.trim().parse::<f64>().ok()
+}
+
+/// 解析 F10 “持仓明细”（type=jjcc 股票 / type=zqcc 债券）。金额/份额单位为“万”，这里换算成元/股。
+pub fn parse_holdings_from_f10(content: &str, asset_type: &str) -> Vec<Holding> {
+    let mut out = Vec::new();
+    for (report_date, section) in split_report_sections(content) {
+        for cells in table_rows(section) {
+            if cells.len() < 4 {
+                continue;
+            }
+            let sec_code = cells[1].clone();
+            let sec_name = cells[2].clone();
+            if sec_code.is_empty() || sec_name.is_empty() {
+                continue;
+            }
+            let Some((pct_idx, weight_pct)) = cells
+                .iter()
+                .enumerate()
+                .skip(3)
+                .find_map(|(i, c)| parse_pct(c).map(|v| (i, v)))
+            else {
+                continue;
+            };
+
+            // 股票：占比后依次为 持股数（万股）、持仓市值（万元）；债券：占比前为 公允价值（万元）。
+            let numbers_after: Vec<f64> = cells[pct_idx + 1..]
+                .iter()
+                .filter_map(|c| c.parse::<f64>().ok())
+                .collect();
+            let (shares, market_value) = if asset_type == ASSET_STOCK {
+                match numbers_after.as_slice() {
+                    [s, mv, ..] => (Some(*s), Some(*mv)),
+                    [mv] => (None, Some(*mv)),
+                    [] => (None, None),
+                }
+            } else {
+                let before = cells[3..pct_idx]
+                    .iter()
+                    .rev()
+                    .find_map(|c| c.parse::<f64>().ok());
+                (None, before.or(numbers_after.first().copied()))
+            };
+
+            out.push(Holding {
+                report_date,
+                asset_type: asset_type.to_string(),
+                sec_code,
+                sec_name,
+                weight_pct,
+                shares: shares.map(|v| v * 10_000.0),
+                market_value_cny: market_value.map(|v| v * 10_000.0),
+            });
+        }
+    }
+    out
+}
+
+/// 解析 F10 “行业配置”（type=hypz）。
+pub fn parse_industry_allocation_from_f10(content: &str) -> Vec<IndustryAllocation> {
+    let mut out = Vec::new();
+    for (report_date, section) in split_report_sections(content) {
+        for cells in table_rows(section) {
+            if cells.len() < 3 {
+                continue;
+            }
+            let Some((pct_idx, weight_pct)) = cells
+                .iter()
+                .enumerate()
+                .find_map(|(i, c)| parse_pct(c).map(|v| (i, v)))
+            else {
+                continue;
+            };
+            // 行业名称：占比之前第一个“非序号、非链接文字”的单元格。
+            let Some(industry_name) = cells[..pct_idx]
+                .iter()
+                .find(|c| !c.is_empty() && c.parse::<f64>().is_err() && !c.contains("变动详情"))
+                .cloned()
+            else {
+                continue;
+            };
+            if industry_name == "合计" {
+                continue;
+            }
+            let market_value = cells[pct_idx + 1..]
+                .iter()
+                .find_map(|c| c.parse::<f64>().ok());
+            out.push(IndustryAllocation {
+                report_date,
+                industry_name,
+                weight_pct,
+                market_value_cny: market_value.map(|v| v * 10_000.0),
+            });
+        }
+    }
+    out
+}
+
+/// FundArchivesDatas.aspx 返回 `var apidata={ content:"...", ... };`，取出 content。
+pub fn extract_apidata_content(text: &str) -> Option<String> {
+    let start = text.find("content:\"")? + "content:\"".len();
+    let rest = &text[start..];
+    let mut out = String::new();
+    let mut escaped = false;
+    for ch in rest.chars() {
+        if escaped {
+            out.push(ch);
+            escaped = false;
+            continue;
+        }
+        match ch {
+            '\\' => escaped = true,
+            '"' => return Some(out),
+            _ => out.push(ch),
+        }
+    }
+    None
+}
+
+async fn fetch_archives(
+    client: &reqwest::Client,
+    kind: &str,
+    fund_code: &str,
+) -> Result<String, String> {
+    let url = format!(
+        "https://fundf10.eastmoney.com/FundArchivesDatas.aspx?type={kind}&code={fund_code}&topline=10&year=&month="
+    );
+    let text = client
+        .get(&url)
+        .header("referer", "https://fundf10.eastmoney.com/")
+        .send()
+        .await
+        .map_err(|e| format!("F10 {kind} 请求失败: {e}"))?
+        .text()
+        .await
+        .map_err(|e| format!("F10 {kind} 读取响应失败: {e}"))?;
+    Ok(extract_apidata_content(&text).unwrap_or_default())
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HoldingsBundle {
+    pub holdings: Vec<Holding>,
+    pub industries: Vec<IndustryAllocation>,
+}
+
+pub async fn fetch_holdings_bundle(
+    client: &reqwest::Client,
+    fund_code: &str,
+) -> Result<HoldingsBundle, String> {
+    let code = fund_code.trim();
+    if code.is_empty() {
+        return Ok(HoldingsBundle::default());
+    }
+
+    let stock_html = fetch_archives(client, "jjcc", code).await?;
+    let mut holdings = parse_holdings_from_f10(&stock_html, ASSET_STOCK);
+
+    // 债券/行业属于补充信息：失败不影响股票持仓落库。
+    if let Ok(bond_html) = fetch_archives(client, "zqcc", code).await {
+        holdings.extend(parse_holdings_from_f10(&bond_html, ASSET_BOND));
+    }
+    let industries = match fetch_archives(client, "hypz", code).await {
+        Ok(html) => parse_industry_allocation_from_f10(&html),
+        Err(_) => Vec::new(),
+    };
+
+    Ok(HoldingsBundle {
+        holdings,
+        industries,
+    })
+}
+
+pub async fn upsert_holdings_bundle(
+    pool: &sqlx::AnyPool,
+    fund_code: &str,
+    source: &str,
+    bundle: &HoldingsBundle,
+) -> Result<i64, String> {
+    let code = fund_code.trim();
+    if code.is_empty() {
+        return Ok(0);
+    }
+    let is_postgres = crate::db::database_kind_from_pool(pool) == DatabaseKind::Postgres;
+
+    let holding_sql = if is_postgres {
+        r#"
+        INSERT INTO fund_holding (
+          fund_code, report_date, asset_type, sec_code, sec_name, weight_pct, shares, market_value_cny, source, created_at, updated_at
+        ) VALUES ($1, ($2)::date, $3, $4, $5, $6, $7, $8, $9, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        ON CONFLICT (fund_code, report_date, asset_type, sec_code, source) DO UPDATE
+          SET sec_name = EXCLUDED.sec_name,
+              weight_pct = EXCLUDED.weight_pct,
+              shares = EXCLUDED.shares,
+              market_value_cny = EXCLUDED.market_value_cny,
+              updated_at = CURRENT_TIMESTAMP
+        "#
+    } else {
+        r#"
+        INSERT INTO fund_holding (
+          fund_code, report_date, asset_type, sec_code, sec_name, weight_pct, shares, market_value_cny, source, created_at, updated_at
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        ON CONFLICT (fund_code, report_date, asset_type, sec_code, source) DO UPDATE
+          SET sec_name = excluded.sec_name,
+              weight_pct = excluded.weight_pct,
+              shares = excluded.shares,
+              market_value_cny = excluded.market_value_cny,
+              updated_at = CURRENT_TIMESTAMP
+        "#
+    };
+
+    let mut upserted = 0_i64;
+    for h in &bundle.holdings {
+        sqlx::query(holding_sql)
+            .bind(code)
+            .bind(h.report_date.to_string())
+            .bind(&h.asset_type)
+            .bind(&h.sec_code)
+            .bind(&h.sec_name)
+            .bind(h.weight_pct)
+            .bind(h.shares)
+            .bind(h.market_value_cny)
+            .bind(source)
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        upserted += 1;
+    }
+
+    let industry_sql = if is_postgres {
+        r#"
+        INSERT INTO fund_industry_allocation (
+          fund_code, report_date, industry_name, weight_pct, market_value_cny, source, created_at, updated_at
+        ) VALUES ($1, ($2)::date, $3, $4, $5, $6, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        ON CONFLICT (fund_code, report_date, industry_name, source) DO UPDATE
+          SET weight_pct = EXCLUDED.weight_pct,
+              market_value_cny = EXCLUDED.market_value_cny,
+              updated_at = CURRENT_TIMESTAMP
+        "#
+    } else {
+        r#"
+        INSERT INTO fund_industry_allocation (
+          fund_code, report_date, industry_name, weight_pct, market_value_cny, source, created_at, updated_at
+        ) VALUES ($1, $2, $3, $4, $5, $6, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        ON CONFLICT (fund_code, report_date, industry_name, source) DO UPDATE
+          SET weight_pct = excluded.weight_pct,
+              market_value_cny = excluded.market_value_cny,
+              updated_at = CURRENT_TIMESTAMP
+        "#
+    };
+    for a in &bundle.industries {
+        sqlx::query(industry_sql)
+            .bind(code)
+            .bind(a.report_date.to_string())
+            .bind(&a.industry_name)
+            .bind(a.weight_pct)
+            .bind(a.market_value_cny)
+            .bind(source)
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        upserted += 1;
+    }
+
+    Ok(upserted)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HoldingOut {
+    pub sec_code: String,
+    pub sec_name: String,
+    pub weight_pct: f64,
+    pub shares: Option<f64>,
+    pub market_value_cny: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IndustryOut {
+    pub industry_name: String,
+    pub weight_pct: f64,
+    pub market_value_cny: Option<f64>,
+}
+
+/// 取某基金最新报告期的持仓（asset_type = stock/bond）。
+pub async fn load_latest_holdings(
+    pool: &sqlx::AnyPool,
+    fund_code: &str,
+    asset_type: &str,
+) -> Result<(Option<String>, Vec<HoldingOut>), String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+          CAST(h.report_date AS TEXT) as report_date,
+          h.sec_code as sec_code,
+          h.sec_name as sec_name,
+          h.weight_pct as weight_pct,
+          h.shares as shares,
+          h.market_value_cny as market_value_cny
+        FROM fund_holding h
+        WHERE h.fund_code = $1
+          AND h.asset_type = $2
+          AND h.report_date = (
+            SELECT MAX(h2.report_date) FROM fund_holding h2
+            WHERE h2.fund_code = $1 AND h2.asset_type = $2
+          )
+        ORDER BY h.weight_pct DESC, h.sec_code ASC
+        "#,
+    )
+    .bind(fund_code.trim())
+    .bind(asset_type)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let report_date = rows.first().map(|r| r.get::<String, _>("report_date"));
+    let out = rows
+        .into_iter()
+        .map(|r| HoldingOut {
+            sec_code: r.get("sec_code"),
+            sec_name: r.get("sec_name"),
+            weight_pct: r.try_get::<f64, _>("weight_pct").unwrap_or(0.0),
+            shares: r.try_get::<Option<f64>, _>("shares").ok().flatten(),
+            market_value_cny: r
+                .try_get::<Option<f64>, _>("market_value_cny")
+                .ok()
+                .flatten(),
+        })
+        .collect();
+    Ok((report_date, out))
+}
+
+pub async fn load_latest_industries(
+    pool: &sqlx::AnyPool,
+    fund_code: &str,
+) -> Result<(Option<String>, Vec<IndustryOut>), String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+          CAST(a.report_date AS TEXT) as report_date,
+          a.industry_name as industry_name,
+          a.weight_pct as weight_pct,
+          a.market_value_cny as market_value_cny
+        FROM fund_industry_allocation a
+        WHERE a.fund_code = $1
+          AND a.report_date = (
+            SELECT MAX(a2.report_date) FROM fund_industry_allocation a2 WHERE a2.fund_code = $1
+          )
+        ORDER BY a.weight_pct DESC, a.industry_name ASC
+        "#,
+    )
+    .bind(fund_code.trim())
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let report_date = rows.first().map(|r| r.get::<String, _>("report_date"));
+    let out = rows
+        .into_iter()
+        .map(|r| IndustryOut {
+            industry_name: r.get("industry_name"),
+            weight_pct: r.try_get::<f64, _>("weight_pct").unwrap_or(0.0),
+            market_value_cny: r
+                .try_get::<Option<f64>, _>("market_value_cny")
+                .ok()
+                .flatten(),
+        })
+        .collect();
+    Ok((report_date, out))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommonHolding {
+    pub sec_code: String,
+    pub sec_name: String,
+    pub weight_a_pct: f64,
+    pub weight_b_pct: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OverlapOut {
+    pub fund_a: String,
+    pub fund_b: String,
+    /// Σ min(w_a, w_b)（%）：两只基金在前十大重仓上“重叠的净值占比”。
+    pub overlap_pct: f64,
+    pub common: Vec<CommonHolding>,
+}
+
+/// 计算两只基金重仓股重叠度（按 sec_code 对齐）。
+pub fn holdings_overlap(
+    fund_a: &str,
+    a: &[HoldingOut],
+    fund_b: &str,
+    b: &[HoldingOut],
+) -> OverlapOut {
+    let b_map: HashMap<&str, &HoldingOut> = b.iter().map(|h| (h.sec_code.as_str(), h)).collect();
+    let mut common = Vec::new();
+    let mut overlap = 0.0;
+    for ha in a {
+        let Some(hb) = b_map.get(ha.sec_code.as_str()) else {
+            continue;
+        };
+        overlap += ha.weight_pct.min(hb.weight_pct);
+        common.push(CommonHolding {
+            sec_code: ha.sec_code.clone(),
+            sec_name: ha.sec_name.clone(),
+            weight_a_pct: ha.weight_pct,
+            weight_b_pct: hb.weight_pct,
+        });
+    }
+    common.sort_by(|x, y| {
+        let kx = x.weight_a_pct.min(x.weight_b_pct);
+        let ky = y.weight_a_pct.min(y.weight_b_pct);
+        ky.partial_cmp(&kx).unwrap_or(std::cmp::Ordering::Equal)
+    });
+    OverlapOut {
+        fund_a: fund_a.to_string(),
+        fund_b: fund_b.to_string(),
+        overlap_pct: overlap,
+        common,
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExposureOut {
+    pub sec_code: String,
+    pub sec_name: String,
+    /// 穿透后占账户总市值比例（%）。
+    pub exposure_pct: f64,
+    pub exposure_value_cny: f64,
+    pub via_funds: Vec<String>,
+}
+
+/// 穿透暴露：Σ_f (基金市值_f × 持仓占比_f,s)。`funds` 为 (fund_code, 基金市值, 该基金持仓)。
+pub fn look_through_exposure(funds: &[(String, f64, Vec<HoldingOut>)]) -> Vec<ExposureOut> {
+    let total: f64 = funds.iter().map(|(_, v, _)| v.max(0.0)).sum();
+    let mut acc: BTreeMap<String, ExposureOut> = BTreeMap::new();
+    for (fund_code, value, holdings) in funds {
+        let value = value.max(0.0);
+        for h in holdings {
+            let e = acc
+                .entry(h.sec_code.clone())
+                .or_insert_with(|| ExposureOut {
+                    sec_code: h.sec_code.clone(),
+                    sec_name: h.sec_name.clone(),
+                    exposure_pct: 0.0,
+                    exposure_value_cny: 0.0,
+                    via_funds: Vec::new(),
+                });
+            e.exposure_value_cny += value * h.weight_pct / 100.0;
+            if !e.via_funds.contains(fund_code) {
+                e.via_funds.push(fund_code.clone());
+            }
+        }
+    }
+    let mut out: Vec<ExposureOut> = acc
+        .into_values()
+        .map(|mut e| {
+            e.exposure_pct = if total > 0.0 {
+                e.exposure_value_cny / total * 100.0
+            } else {
+                0.0
+            };
+            e
+        })
+        .collect();
+    out.sort_by(|a, b| {
+        b.exposure_value_cny
+            .partial_cmp(&a.exposure_value_cny)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    out
+}
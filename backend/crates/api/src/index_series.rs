@@ -151,3 +151,19 @@ pub async fn load_or_fetch_index_close_series(
     Ok(out)
 }
 
+
+/// 无条件从东方财富拉取区间日线并写入（已有日期覆盖收盘价）；返回拉到的点数。
+pub async fn refresh_index_close_series(
+    pool: &sqlx::AnyPool,
+    client: &reqwest::Client,
+    db_kind: DatabaseKind,
+    index_code: &str,
+    source_name: &str,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<usize, String> {
+    let list = eastmoney::fetch_index_kline_daily(client, index_code.trim(), start_date, end_date).await?;
+    let n = list.len();
+    upsert_index_close_series(pool, db_kind, index_code, source_name, list).await;
+    Ok(n)
+}
@@ -0,0 +1,271 @@
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::Row;
+
+use crate::db::DatabaseKind;
+
+const TIME_FMT: &str = "%Y-%m-%d %H:%M:%S";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntradayPoint {
+    pub estimate_time: NaiveDateTime,
+    pub estimate_nav: Decimal,
+    /// 相对上一交易日净值的估算涨幅（%）。
+    pub estimate_growth: Option<Decimal>,
+}
+
+fn parse_decimal(s: &str) -> Option<Decimal> {
+    Decimal::from_str(s.trim()).ok()
+}
+
+fn parse_time(s: &str) -> Option<NaiveDateTime> {
+    let t = s.trim();
+    NaiveDateTime::parse_from_str(t, TIME_FMT)
+        .or_else(|_| NaiveDateTime::parse_from_str(t, "%Y-%m-%d %H:%M:%S%.f"))
+        .or_else(|_| NaiveDateTime::parse_from_str(t, "%Y-%m-%dT%H:%M:%S"))
+        .ok()
+}
+
+/// 记录一条估值样本；同一 (fund_code, source, estimate_time) 重复抓取时覆盖。
+pub async fn record_sample(
+    pool: &sqlx::AnyPool,
+    fund_code: &str,
+    source_name: &str,
+    estimate_time: NaiveDateTime,
+    estimate_nav: Decimal,
+    estimate_growth: Option<Decimal>,
+) -> Result<(), String> {
+    let code = fund_code.trim();
+    if code.is_empty() {
+        return Ok(());
+    }
+    let is_postgres = crate::db::database_kind_from_pool(pool) == DatabaseKind::Postgres;
+    let sql = if is_postgres {
+        r#"
+        INSERT INTO fund_estimate_intraday (fund_code, source_name, estimate_time, estimate_nav, estimate_growth, created_at)
+        VALUES ($1, $2, CAST($3 AS TIMESTAMP), CAST($4 AS NUMERIC), CAST($5 AS NUMERIC), CURRENT_TIMESTAMP)
+        ON CONFLICT (fund_code, source_name, estimate_time) DO UPDATE
+          SET estimate_nav = EXCLUDED.estimate_nav,
+              estimate_growth = EXCLUDED.estimate_growth
+        "#
+    } else {
+        r#"
+        INSERT INTO fund_estimate_intraday (fund_code, source_name, estimate_time, estimate_nav, estimate_growth, created_at)
+        VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP)
+        ON CONFLICT (fund_code, source_name, estimate_time) DO UPDATE
+          SET estimate_nav = excluded.estimate_nav,
+              estimate_growth = excluded.estimate_growth
+        "#
+    };
+
+    sqlx::query(sql)
+        .bind(code)
+        .bind(source_name)
+        .bind(estimate_time.format(TIME_FMT).to_string())
+        .bind(estimate_nav.to_string())
+        .bind(estimate_growth.map(|g| g.to_string()))
+        .execute(pool)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// 保留策略：删除 `retention_days` 天之前（按 estimate_time 日期）的样本，返回删除条数。
+pub async fn prune_samples(
+    pool: &sqlx::AnyPool,
+    today: NaiveDate,
+    retention_days: i64,
+) -> Result<u64, String> {
+    let retention_days = retention_days.max(1);
+    let cutoff = (today - Duration::days(retention_days - 1))
+        .and_hms_opt(0, 0, 0)
+        .expect("valid time");
+    let is_postgres = crate::db::database_kind_from_pool(pool) == DatabaseKind::Postgres;
+    let sql = if is_postgres {
+        "DELETE FROM fund_estimate_intraday WHERE estimate_time < CAST($1 AS TIMESTAMP)"
+    } else {
+        "DELETE FROM fund_estimate_intraday WHERE estimate_time < $1"
+    };
+    sqlx::query(sql)
+        .bind(cutoff.format(TIME_FMT).to_string())
+        .execute(pool)
+        .await
+        .map(|r| r.rows_affected())
+        .map_err(|e| e.to_string())
+}
+
+/// 该基金（该数据源）最近一次有样本的日期。
+pub async fn latest_sample_date(
+    pool: &sqlx::AnyPool,
+    fund_code: &str,
+    source_name: &str,
+) -> Result<Option<NaiveDate>, String> {
+    let row = sqlx::query(
+        r#"
+        SELECT CAST(MAX(estimate_time) AS TEXT) as latest
+        FROM fund_estimate_intraday
+        WHERE fund_code = $1 AND source_name = $2
+        "#,
+    )
+    .bind(fund_code.trim())
+    .bind(source_name)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(row
+        .try_get::<Option<String>, _>("latest")
+        .ok()
+        .flatten()
+        .and_then(|s| parse_time(&s))
+        .map(|dt| dt.date()))
+}
+
+/// 某日的估值曲线（按时间升序）。
+pub async fn load_day_curve(
+    pool: &sqlx::AnyPool,
+    fund_code: &str,
+    source_name: &str,
+    date: NaiveDate,
+) -> Result<Vec<IntradayPoint>, String> {
+    let start = date.and_hms_opt(0, 0, 0).expect("valid time");
+    let end = start + Duration::days(1);
+    let is_postgres = crate::db::database_kind_from_pool(pool) == DatabaseKind::Postgres;
+    let sql = if is_postgres {
+        r#"
+        SELECT
+          CAST(estimate_time AS TEXT) as estimate_time,
+          CAST(estimate_nav AS TEXT) as estimate_nav,
+          CAST(estimate_growth AS TEXT) as estimate_growth
+        FROM fund_estimate_intraday
+        WHERE fund_code = $1
+          AND source_name = $2
+          AND estimate_time >= CAST($3 AS TIMESTAMP)
+          AND estimate_time < CAST($4 AS TIMESTAMP)
+        ORDER BY estimate_time ASC
+        "#
+    } else {
+        r#"
+        SELECT
+          CAST(estimate_time AS TEXT) as estimate_time,
+          CAST(estimate_nav AS TEXT) as estimate_nav,
+          CAST(estimate_growth AS TEXT) as estimate_growth
+        FROM fund_estimate_intraday
+        WHERE fund_code = $1
+          AND source_name = $2
+          AND estimate_time >= $3
+          AND estimate_time < $4
+        ORDER BY estimate_time ASC
+        "#
+    };
+
+    let rows = sqlx::query(sql)
+        .bind(fund_code.trim())
+        .bind(source_name)
+        .bind(start.format(TIME_FMT).to_string())
+        .bind(end.format(TIME_FMT).to_string())
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|r| {
+            let estimate_time = parse_time(&r.get::<String, _>("estimate_time"))?;
+            let estimate_nav = parse_decimal(&r.get::<String, _>("estimate_nav"))?;
+            let estimate_growth = r
+                .try_get::<Option<String>, _>("estimate_growth")
+                .ok()
+                .flatten()
+                .and_then(|s| parse_decimal(&s));
+            Some(IntradayPoint {
+                estimate_time,
+                estimate_nav,
+                estimate_growth,
+            })
+        })
+        .collect())
+}
+
+/// 账户内单只基金的日内输入：份额 + 当日估值曲线。
+#[derive(Debug, Clone)]
+pub struct FundIntraday {
+    pub fund_code: String,
+    pub holding_share: Decimal,
+    /// 上一交易日净值；样本缺少 growth 时用它作为当日盈亏基准。
+    pub base_nav: Option<Decimal>,
+    /// 需按 estimate_time 升序（`load_day_curve` 的返回顺序）。
+    pub points: Vec<IntradayPoint>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IntradayPnlPoint {
+    pub time: String,
+    pub estimate_value: String,
+    pub pnl: String,
+    pub pnl_rate: Option<String>,
+}
+
+fn point_base_nav(p: &IntradayPoint, fallback: Option<Decimal>) -> Option<Decimal> {
+    match p.estimate_growth {
+        Some(g) => {
+            let denom = Decimal::ONE + g / Decimal::from(100);
+            if denom.is_zero() {
+                fallback
+            } else {
+                Some(p.estimate_nav / denom)
+            }
+        }
+        None => fallback,
+    }
+}
+
+fn fmt_fixed(value: Decimal, dp: u32) -> String {
+    format!("{:.*}", dp as usize, value.round_dp(dp))
+}
+
+/// 账户日内盈亏曲线：在所有基金样本时间点的并集上，每只基金取“该时刻及之前最近一条”样本，
+/// 盈亏 = Σ 份额 × (估值 − 上一交易日净值)。尚未出现样本的基金不计入该时刻。
+pub fn aggregate_intraday_pnl(funds: &[FundIntraday]) -> Vec<IntradayPnlPoint> {
+    let times: BTreeSet<NaiveDateTime> = funds
+        .iter()
+        .flat_map(|f| f.points.iter().map(|p| p.estimate_time))
+        .collect();
+
+    let mut cursors = vec![0_usize; funds.len()];
+    let mut out = Vec::with_capacity(times.len());
+    for t in times {
+        let mut value = Decimal::ZERO;
+        let mut pnl = Decimal::ZERO;
+        for (f, cursor) in funds.iter().zip(cursors.iter_mut()) {
+            while *cursor < f.points.len() && f.points[*cursor].estimate_time <= t {
+                *cursor += 1;
+            }
+            if *cursor == 0 {
+                continue;
+            }
+            let p = &f.points[*cursor - 1];
+            let Some(base) = point_base_nav(p, f.base_nav) else {
+                continue;
+            };
+            value += f.holding_share * p.estimate_nav;
+            pnl += f.holding_share * (p.estimate_nav - base);
+        }
+        let cost = value - pnl;
+        out.push(IntradayPnlPoint {
+            time: t.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            estimate_value: fmt_fixed(value, 2),
+            pnl: fmt_fixed(pnl, 2),
+            pnl_rate: if cost > Decimal::ZERO {
+                Some(fmt_fixed(pnl / cost * Decimal::from(100), 2))
+            } else {
+                None
+            },
+        });
+    }
+    out
+}
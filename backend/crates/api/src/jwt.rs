@@ -8,11 +8,27 @@ pub struct JwtService {
     decoding: DecodingKey,
 }
 
+/// 访问令牌有效期；吊销记录至少要保留这么久。
+pub const ACCESS_TOKEN_TTL_SECONDS: i64 = 3600;
+/// 两步验证中间令牌有效期：密码校验通过后，需在此时间内提交第二因素。
+pub const MFA_TOKEN_TTL_SECONDS: i64 = 300;
+/// 刷新令牌有效期（每次轮换后重新计算）。
+pub const REFRESH_TOKEN_TTL_SECONDS: i64 = 7 * 24 * 3600;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,
     pub exp: usize,
     pub token_type: String,
+    /// 签发时间；用于“修改密码后旧令牌失效”。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat: Option<usize>,
+    /// 所属会话（auth_session.id）。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
+    /// 刷新令牌编号，与 auth_session.refresh_jti 比对以发现重放。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
 }
 
 impl JwtService {
@@ -23,26 +39,57 @@ impl JwtService {
         }
     }
 
+    /// 不绑定会话的访问令牌（脚本与测试使用）；仍受修改密码后的失效时间约束。
     pub fn issue_access_token(&self, user_id: &str) -> String {
-        let exp = (Utc::now() + Duration::hours(1)).timestamp() as usize;
+        self.encode_access(user_id, None)
+    }
+
+    /// 绑定会话的访问令牌：会话吊销后立即失效。
+    pub fn issue_session_access_token(&self, user_id: &str, sid: &str) -> String {
+        self.encode_access(user_id, Some(sid))
+    }
+
+    fn encode_access(&self, user_id: &str, sid: Option<&str>) -> String {
+        let now = Utc::now();
         let claims = Claims {
             sub: user_id.to_string(),
-            exp,
+            exp: (now + Duration::seconds(ACCESS_TOKEN_TTL_SECONDS)).timestamp() as usize,
             token_type: "access".to_string(),
+            iat: Some(now.timestamp() as usize),
+            sid: sid.map(str::to_string),
+            jti: None,
         };
         jsonwebtoken::encode(&Header::default(), &claims, &self.encoding).expect("encode access")
     }
 
-    pub fn issue_refresh_token(&self, user_id: &str) -> String {
-        let exp = (Utc::now() + Duration::days(7)).timestamp() as usize;
+    /// 会话的刷新令牌；jti 需同时写入 auth_session.refresh_jti。
+    pub fn issue_refresh_token(&self, user_id: &str, sid: &str, jti: &str) -> String {
+        let now = Utc::now();
         let claims = Claims {
             sub: user_id.to_string(),
-            exp,
+            exp: (now + Duration::seconds(REFRESH_TOKEN_TTL_SECONDS)).timestamp() as usize,
             token_type: "refresh".to_string(),
+            iat: Some(now.timestamp() as usize),
+            sid: Some(sid.to_string()),
+            jti: Some(jti.to_string()),
         };
         jsonwebtoken::encode(&Header::default(), &claims, &self.encoding).expect("encode refresh")
     }
 
+    /// 密码已校验、等待第二因素的中间令牌（token_type 为 `mfa`，不能当作访问令牌使用）。
+    pub fn issue_mfa_token(&self, user_id: &str) -> String {
+        let now = Utc::now();
+        let claims = Claims {
+            sub: user_id.to_string(),
+            exp: (now + Duration::seconds(MFA_TOKEN_TTL_SECONDS)).timestamp() as usize,
+            token_type: "mfa".to_string(),
+            iat: Some(now.timestamp() as usize),
+            sid: None,
+            jti: None,
+        };
+        jsonwebtoken::encode(&Header::default(), &claims, &self.encoding).expect("encode mfa")
+    }
+
     pub fn decode(&self, token: &str) -> Result<TokenData<Claims>, jsonwebtoken::errors::Error> {
         let mut validation = Validation::default();
         validation.validate_exp = true;
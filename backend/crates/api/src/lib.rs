@@ -1,15 +1,26 @@
 pub mod accuracy;
 pub mod analytics;
+pub mod audit;
+pub mod auth_session;
+pub mod client_ip;
 pub mod config;
 pub mod crawl;
 pub mod db;
 pub mod dbfmt;
 pub mod django_password;
 pub mod eastmoney;
+pub mod features;
 pub mod forecast;
+pub mod fund_profile;
+pub mod holdings;
 pub mod index_series;
+pub mod intraday;
 pub mod jwt;
+pub mod login_guard;
+pub mod metrics;
 pub mod ml;
+pub mod permissions;
+pub mod personal_token;
 pub mod position_history;
 pub mod rates;
 pub mod routes;
@@ -17,8 +28,12 @@ pub mod sim;
 pub mod sniffer;
 pub mod sources;
 pub mod state;
+pub mod task_schedule;
+pub mod task_worker;
 pub mod tasks;
 pub mod tiantian_h5;
+pub mod totp;
+pub mod trading_calendar;
 
 use axum::Router;
 use tower_http::normalize_path::NormalizePath;
@@ -26,6 +41,10 @@ use tower_http::normalize_path::NormalizePath;
 pub fn app(state: state::AppState) -> Router {
     Router::new()
         .merge(routes::router(state.clone()))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            client_ip::stamp,
+        ))
         .with_state(state)
 }
 
@@ -0,0 +1,160 @@
+use chrono::{DateTime, Duration, Utc};
+use sqlx::Row;
+
+use crate::config::ConfigStore;
+use crate::db::DatabaseKind;
+use crate::dbfmt::format_utc;
+
+/// 从第几次失败开始施加渐进延迟（1, 2, 4... 秒）。
+const DELAY_AFTER_FAILURES: i64 = 3;
+
+/// 防爆破策略（取自配置）。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuardPolicy {
+    pub max_failures_per_user: i64,
+    pub max_failures_per_ip: i64,
+    pub lockout_seconds: i64,
+    pub window_seconds: i64,
+}
+
+impl GuardPolicy {
+    pub fn from_config(config: &ConfigStore) -> Self {
+        Self {
+            max_failures_per_user: config.get_i64("login_max_failures_per_user", 5).max(1),
+            max_failures_per_ip: config.get_i64("login_max_failures_per_ip", 20).max(1),
+            lockout_seconds: config.get_i64("login_lockout_seconds", 900).max(1),
+            window_seconds: config.get_i64("login_failure_window_seconds", 900).max(1),
+        }
+    }
+
+    /// 第 `failures` 次失败之后需要等待的秒数（0 表示不限制）。
+    pub fn delay_after(&self, failures: i64, max_failures: i64) -> i64 {
+        if failures >= max_failures {
+            return self.lockout_seconds;
+        }
+        if failures < DELAY_AFTER_FAILURES {
+            return 0;
+        }
+        let exp = (failures - DELAY_AFTER_FAILURES).min(16) as u32;
+        2_i64.pow(exp).min(self.lockout_seconds)
+    }
+}
+
+pub fn user_key(username: &str) -> String {
+    format!("user:{}", username.trim().to_lowercase())
+}
+
+pub fn ip_key(ip: &str) -> String {
+    format!("ip:{ip}")
+}
+
+fn is_pg(pool: &sqlx::AnyPool) -> bool {
+    crate::db::database_kind_from_pool(pool) == DatabaseKind::Postgres
+}
+
+/// 若任一键仍处于延迟/锁定期，返回还需等待的秒数。
+pub async fn retry_after(pool: &sqlx::AnyPool, keys: &[String]) -> Result<Option<i64>, String> {
+    let now = Utc::now();
+    let mut wait: Option<i64> = None;
+    for key in keys {
+        let row = sqlx::query(
+            "SELECT CAST(locked_until AS TEXT) as locked_until FROM auth_login_failure WHERE key = $1",
+        )
+        .bind(key)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        let until = row
+            .and_then(|r| {
+                r.try_get::<Option<String>, _>("locked_until")
+                    .ok()
+                    .flatten()
+            })
+            .and_then(|s| crate::dbfmt::parse_datetime_utc(&s));
+        if let Some(until) = until
+            && until > now
+        {
+            let secs = (until - now).num_seconds().max(1);
+            wait = Some(wait.map_or(secs, |w| w.max(secs)));
+        }
+    }
+    Ok(wait)
+}
+
+/// 记录一次失败；返回该键的累计失败次数与新的锁定截止时间。
+///
+/// 计数在一条 upsert 内原子递增（窗口过期则从 1 重新计数），并发失败不会丢计数。
+pub async fn record_failure(
+    pool: &sqlx::AnyPool,
+    key: &str,
+    max_failures: i64,
+    policy: &GuardPolicy,
+) -> Result<(i64, Option<DateTime<Utc>>), String> {
+    let now = Utc::now();
+    let window_start = now - Duration::seconds(policy.window_seconds);
+    let pg = is_pg(pool);
+
+    let sql = if pg {
+        r#"
+        INSERT INTO auth_login_failure (key, failures, last_failed_at, locked_until)
+        VALUES ($1, 1, ($2)::timestamptz, NULL)
+        ON CONFLICT (key) DO UPDATE
+        SET failures = CASE
+              WHEN auth_login_failure.last_failed_at IS NULL
+                OR auth_login_failure.last_failed_at < ($3)::timestamptz THEN 1
+              ELSE auth_login_failure.failures + 1
+            END,
+            last_failed_at = EXCLUDED.last_failed_at
+        RETURNING failures
+        "#
+    } else {
+        r#"
+        INSERT INTO auth_login_failure (key, failures, last_failed_at, locked_until)
+        VALUES ($1, 1, $2, NULL)
+        ON CONFLICT (key) DO UPDATE
+        SET failures = CASE
+              WHEN auth_login_failure.last_failed_at IS NULL
+                OR auth_login_failure.last_failed_at < $3 THEN 1
+              ELSE auth_login_failure.failures + 1
+            END,
+            last_failed_at = excluded.last_failed_at
+        RETURNING failures
+        "#
+    };
+    let failures: i64 = sqlx::query(sql)
+        .bind(key)
+        .bind(format_utc(now))
+        .bind(format_utc(window_start))
+        .fetch_one(pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .get("failures");
+
+    let delay = policy.delay_after(failures, max_failures);
+    let locked_until = (delay > 0).then(|| now + Duration::seconds(delay));
+    if let Some(until) = locked_until {
+        // 只延长不缩短：并发失败各自算出的锁定期取最晚者。
+        let sql = if pg {
+            "UPDATE auth_login_failure SET locked_until = ($2)::timestamptz WHERE key = $1 AND (locked_until IS NULL OR locked_until < ($2)::timestamptz)"
+        } else {
+            "UPDATE auth_login_failure SET locked_until = $2 WHERE key = $1 AND (locked_until IS NULL OR locked_until < $2)"
+        };
+        sqlx::query(sql)
+            .bind(key)
+            .bind(format_utc(until))
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    Ok((failures, locked_until))
+}
+
+/// 登录成功后清除该用户名的失败计数（IP 计数按时间窗口自然清零）。
+pub async fn clear(pool: &sqlx::AnyPool, key: &str) -> Result<(), String> {
+    sqlx::query("DELETE FROM auth_login_failure WHERE key = $1")
+        .bind(key)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
@@ -7,7 +7,6 @@ use sqlx::Error as SqlxError;
 use sqlx::any::AnyPoolOptions;
 use sqlx::migrate::Migrator;
 use sqlx::postgres::PgPoolOptions;
-use tower::make::Shared;
 use tower_http::cors::{AllowOrigin, Any, CorsLayer};
 use tower_http::normalize_path::NormalizePath;
 use tower_http::trace::TraceLayer;
@@ -268,6 +267,8 @@ async fn main() {
 
     if state.pool().is_some() {
         tokio::spawn(api::crawl::worker::background_task(state.clone()));
+        tokio::spawn(api::task_worker::background_task(state.clone()));
+        tokio::spawn(api::auth_session::background_task(state.clone()));
     }
 
     let cors = build_cors_layer(state.config().get_bool("debug", false));
@@ -275,7 +276,10 @@ async fn main() {
     // 注意：NormalizePathLayer 作为普通 middleware 会在路由匹配之后才生效，
     // 不能用于“尾斜杠归一化后再匹配路由”。这里必须把整个 Router 包一层 NormalizePath。
     let router = app(state).layer(TraceLayer::new_for_http()).layer(cors);
-    let app = Shared::new(NormalizePath::trim_trailing_slash(router));
+    // 带上连接地址（ConnectInfo），供 client_ip 中间件判断请求是否来自受信任代理。
+    let app = axum::ServiceExt::<axum::extract::Request>::into_make_service_with_connect_info::<
+        SocketAddr,
+    >(NormalizePath::trim_trailing_slash(router));
 
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
     tracing::info!(%addr, "backend listening");
@@ -0,0 +1,437 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use axum::extract::{MatchedPath, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use chrono::Utc;
+use sqlx::Row;
+
+use crate::state::AppState;
+
+/// HTTP 延迟直方图的桶上界（秒）。
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Default)]
+struct RouteStats {
+    by_status: BTreeMap<u16, u64>,
+    count: u64,
+    sum_seconds: f64,
+    buckets: [u64; LATENCY_BUCKETS.len()],
+}
+
+/// 最近一次数据源健康探测的结果（由 `/api/sources/health` 写入）。
+struct SourceProbe {
+    ok: bool,
+    latency_ms: Option<u128>,
+    probed_at: i64,
+}
+
+/// 进程内指标：HTTP 请求按 (method, 路由模板) 聚合；数据库相关指标在抓取时现查。
+#[derive(Default)]
+pub struct Metrics {
+    http: Mutex<BTreeMap<(String, String), RouteStats>>,
+    sources: Mutex<BTreeMap<String, SourceProbe>>,
+}
+
+impl Metrics {
+    pub fn observe_http(&self, method: &str, route: &str, status: u16, seconds: f64) {
+        let Ok(mut http) = self.http.lock() else {
+            return;
+        };
+        let s = http
+            .entry((method.to_string(), route.to_string()))
+            .or_default();
+        *s.by_status.entry(status).or_default() += 1;
+        s.count += 1;
+        s.sum_seconds += seconds;
+        for (i, le) in LATENCY_BUCKETS.iter().enumerate() {
+            if seconds <= *le {
+                s.buckets[i] += 1;
+            }
+        }
+    }
+
+    pub fn record_source_probe(&self, name: &str, ok: bool, latency_ms: Option<u128>) {
+        if let Ok(mut sources) = self.sources.lock() {
+            sources.insert(
+                name.to_string(),
+                SourceProbe {
+                    ok,
+                    latency_ms,
+                    probed_at: Utc::now().timestamp(),
+                },
+            );
+        }
+    }
+
+    fn render_http(&self, out: &mut String) {
+        let Ok(http) = self.http.lock() else {
+            return;
+        };
+        header(
+            out,
+            "fundval_http_requests_total",
+            "counter",
+            "HTTP requests by route and status.",
+        );
+        for ((method, route), s) in http.iter() {
+            for (status, n) in &s.by_status {
+                let _ = writeln!(
+                    out,
+                    "fundval_http_requests_total{{method=\"{}\",route=\"{}\",status=\"{status}\"}} {n}",
+                    escape(method),
+                    escape(route)
+                );
+            }
+        }
+        header(
+            out,
+            "fundval_http_request_duration_seconds",
+            "histogram",
+            "HTTP request latency by route.",
+        );
+        for ((method, route), s) in http.iter() {
+            let labels = format!("method=\"{}\",route=\"{}\"", escape(method), escape(route));
+            for (i, le) in LATENCY_BUCKETS.iter().enumerate() {
+                let _ = writeln!(
+                    out,
+                    "fundval_http_request_duration_seconds_bucket{{{labels},le=\"{le}\"}} {}",
+                    s.buckets[i]
+                );
+            }
+            let _ = writeln!(
+                out,
+                "fundval_http_request_duration_seconds_bucket{{{labels},le=\"+Inf\"}} {}",
+                s.count
+            );
+            let _ = writeln!(
+                out,
+                "fundval_http_request_duration_seconds_sum{{{labels}}} {}",
+                s.sum_seconds
+            );
+            let _ = writeln!(
+                out,
+                "fundval_http_request_duration_seconds_count{{{labels}}} {}",
+                s.count
+            );
+        }
+    }
+
+    fn render_sources(&self, out: &mut String) {
+        let Ok(sources) = self.sources.lock() else {
+            return;
+        };
+        header(
+            out,
+            "fundval_source_up",
+            "gauge",
+            "Last source health probe result (1 = ok).",
+        );
+        for (name, p) in sources.iter() {
+            let _ = writeln!(
+                out,
+                "fundval_source_up{{source=\"{}\"}} {}",
+                escape(name),
+                p.ok as i32
+            );
+        }
+        header(
+            out,
+            "fundval_source_latency_ms",
+            "gauge",
+            "Last source health probe latency.",
+        );
+        for (name, p) in sources.iter() {
+            if let Some(ms) = p.latency_ms {
+                let _ = writeln!(
+                    out,
+                    "fundval_source_latency_ms{{source=\"{}\"}} {ms}",
+                    escape(name)
+                );
+            }
+        }
+        header(
+            out,
+            "fundval_source_probe_timestamp_seconds",
+            "gauge",
+            "Unix time of the last source health probe.",
+        );
+        for (name, p) in sources.iter() {
+            let _ = writeln!(
+                out,
+                "fundval_source_probe_timestamp_seconds{{source=\"{}\"}} {}",
+                escape(name),
+                p.probed_at
+            );
+        }
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+fn escape(v: &str) -> String {
+    v.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// 记录每个请求的路由模板（如 `/api/funds/{code}`）、状态码与耗时；未匹配路由归为 `unmatched`。
+pub async fn track_http(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let method = req.method().to_string();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let start = Instant::now();
+    let res = next.run(req).await;
+    state.metrics().observe_http(
+        &method,
+        &route,
+        res.status().as_u16(),
+        start.elapsed().as_secs_f64(),
+    );
+    res
+}
+
+/// 解析当日抓取计数键 `crawl_{job_type}_{source}_{kind}_{yyyymmdd}`（job_type 为 `all` 表示全部类型）。
+pub fn parse_crawl_counter_key(key: &str, day: &str) -> Option<(String, String, String)> {
+    let rest = key
+        .strip_prefix("crawl_")?
+        .strip_suffix(&format!("_{day}"))?;
+    let mut parts = rest.rsplitn(3, '_');
+    let kind = parts.next()?;
+    let source = parts.next()?;
+    let job_type = parts.next()?;
+    if job_type.is_empty() || source.is_empty() || kind.is_empty() {
+        return None;
+    }
+    Some((job_type.to_string(), source.to_string(), kind.to_string()))
+}
+
+async fn render_crawl(
+    state: &AppState,
+    pool: &sqlx::AnyPool,
+    out: &mut String,
+) -> Result<(), String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT job_type, COALESCE(source_name, '') as source_name, status, COUNT(*) as n
+        FROM crawl_job
+        GROUP BY job_type, source_name, status
+        ORDER BY job_type, source_name, status
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    header(
+        out,
+        "fundval_crawl_jobs",
+        "gauge",
+        "crawl_job rows by job_type, source and status.",
+    );
+    for r in rows {
+        let _ = writeln!(
+            out,
+            "fundval_crawl_jobs{{job_type=\"{}\",source=\"{}\",status=\"{}\"}} {}",
+            escape(&r.get::<String, _>("job_type")),
+            escape(&r.get::<String, _>("source_name")),
+            escape(&r.get::<String, _>("status")),
+            r.get::<i64, _>("n")
+        );
+    }
+
+    // 当日计数（UTC 日期，与 crawl::scheduler 的计数键一致）。
+    let day = Utc::now().format("%Y%m%d").to_string();
+    let rows = sqlx::query("SELECT key, value FROM crawl_state WHERE key LIKE $1 ORDER BY key")
+        .bind(format!("crawl_%_{day}"))
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut runs_by_source: BTreeMap<String, i64> = BTreeMap::new();
+    header(
+        out,
+        "fundval_crawl_runs_today",
+        "gauge",
+        "crawl jobs executed today by job_type, source and result (run/ok/err).",
+    );
+    for r in rows {
+        let key: String = r.get("key");
+        let Some((job_type, source, kind)) = parse_crawl_counter_key(&key, &day) else {
+            continue;
+        };
+        let value = r
+            .get::<String, _>("value")
+            .trim()
+            .parse::<i64>()
+            .unwrap_or(0);
+        if job_type == "all" {
+            if kind == "run" {
+                runs_by_source.insert(source, value);
+            }
+            continue;
+        }
+        let _ = writeln!(
+            out,
+            "fundval_crawl_runs_today{{job_type=\"{}\",source=\"{}\",result=\"{}\"}} {value}",
+            escape(&job_type),
+            escape(&source),
+            escape(&kind)
+        );
+    }
+
+    let limit = state
+        .config()
+        .get_i64("crawl_daily_run_limit", 3000)
+        .clamp(0, 1_000_000);
+    header(
+        out,
+        "fundval_crawl_daily_run_limit",
+        "gauge",
+        "Configured daily crawl run limit per source.",
+    );
+    let _ = writeln!(out, "fundval_crawl_daily_run_limit {limit}");
+    header(
+        out,
+        "fundval_crawl_daily_runs",
+        "gauge",
+        "crawl jobs executed today per source.",
+    );
+    for (source, n) in &runs_by_source {
+        let _ = writeln!(
+            out,
+            "fundval_crawl_daily_runs{{source=\"{}\"}} {n}",
+            escape(source)
+        );
+    }
+    header(
+        out,
+        "fundval_crawl_daily_limit_usage_ratio",
+        "gauge",
+        "Share of the daily crawl run limit used today per source.",
+    );
+    if limit > 0 {
+        for (source, n) in &runs_by_source {
+            let _ = writeln!(
+                out,
+                "fundval_crawl_daily_limit_usage_ratio{{source=\"{}\"}} {}",
+                escape(source),
+                *n as f64 / limit as f64
+            );
+        }
+    }
+    Ok(())
+}
+
+async fn render_tasks(pool: &sqlx::AnyPool, out: &mut String) -> Result<(), String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT task_type, status, COUNT(*) as n, CAST(MIN(created_at) AS TEXT) as oldest
+        FROM task_job
+        GROUP BY task_type, status
+        ORDER BY task_type, status
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let now = Utc::now();
+    let mut ages: Vec<(String, i64)> = Vec::new();
+    header(
+        out,
+        "fundval_task_jobs",
+        "gauge",
+        "task_job rows by task_type and status.",
+    );
+    for r in rows {
+        let task_type: String = r.get("task_type");
+        let status: String = r.get("status");
+        let _ = writeln!(
+            out,
+            "fundval_task_jobs{{task_type=\"{}\",status=\"{}\"}} {}",
+            escape(&task_type),
+            escape(&status),
+            r.get::<i64, _>("n")
+        );
+        if status == "queued"
+            && let Some(oldest) = r
+                .try_get::<Option<String>, _>("oldest")
+                .ok()
+                .flatten()
+                .and_then(|s| crate::dbfmt::parse_datetime_utc(&s))
+        {
+            ages.push((task_type, (now - oldest).num_seconds().max(0)));
+        }
+    }
+    header(
+        out,
+        "fundval_task_queue_oldest_age_seconds",
+        "gauge",
+        "Age of the oldest queued task_job by task_type.",
+    );
+    for (task_type, age) in ages {
+        let _ = writeln!(
+            out,
+            "fundval_task_queue_oldest_age_seconds{{task_type=\"{}\"}} {age}",
+            escape(&task_type)
+        );
+    }
+    Ok(())
+}
+
+fn render_pool(pool: &sqlx::AnyPool, out: &mut String) {
+    let size = pool.size() as usize;
+    let idle = pool.num_idle();
+    header(
+        out,
+        "fundval_db_pool_connections",
+        "gauge",
+        "Database pool connections by state.",
+    );
+    let _ = writeln!(out, "fundval_db_pool_connections{{state=\"idle\"}} {idle}");
+    let _ = writeln!(
+        out,
+        "fundval_db_pool_connections{{state=\"in_use\"}} {}",
+        size.saturating_sub(idle)
+    );
+    header(
+        out,
+        "fundval_db_pool_max_connections",
+        "gauge",
+        "Database pool size limit.",
+    );
+    let _ = writeln!(
+        out,
+        "fundval_db_pool_max_connections {}",
+        pool.options().get_max_connections()
+    );
+}
+
+/// 生成 Prometheus 文本格式（0.0.4）；某一组数据库指标查询失败时跳过该组，不影响其余输出。
+pub async fn render(state: &AppState) -> String {
+    let mut out = String::new();
+    state.metrics().render_http(&mut out);
+    state.metrics().render_sources(&mut out);
+
+    let Some(pool) = state.pool() else {
+        return out;
+    };
+    if let Err(e) = render_crawl(state, pool, &mut out).await {
+        tracing::warn!(error = %e, "metrics: crawl section failed");
+    }
+    if let Err(e) = render_tasks(pool, &mut out).await {
+        tracing::warn!(error = %e, "metrics: task section failed");
+    }
+    render_pool(pool, &mut out);
+    out
+}
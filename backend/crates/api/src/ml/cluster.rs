@@ -0,0 +1,679 @@
+//! 按日收益行为聚类基金，得到数据驱动的同类组。
+//!
+//! 距离为日对数收益相关系数的 `sqrt(2 * (1 - ρ))`（两两取共同日期，重叠不足按 ρ=0 处理），
+//! 用 k-medoids（BUILD 初始化 + 交替迭代）聚类；未指定 k 时按平均轮廓系数在区间内选取。
+//! 稳定性：多次随机抽取 80% 的日期重算距离并以原代表基金为起点重聚类，
+//! 每只基金取原同组成员与重聚类同组成员的 Jaccard 相似度的平均值。
+//!
+//! 同类组代码为 `cluster:<代表基金代码>`。关联板块（`fund_relate_theme`）缺失的基金
+//! 在解析 peer_code 时回退到所属同类组（稳定性不低于 [`MIN_PEER_STABILITY`]）。
+
+use std::collections::BTreeMap;
+
+use sqlx::Row;
+
+/// 同类组 peer_code 前缀。
+pub const CLUSTER_PEER_PREFIX: &str = "cluster:";
+/// 回退到同类组所需的最低稳定性。
+pub const MIN_PEER_STABILITY: f64 = 0.5;
+const MAX_KMEDOIDS_ITERS: usize = 50;
+const SUBSAMPLE_FRACTION: f64 = 0.8;
+
+pub fn is_cluster_peer(peer_code: &str) -> bool {
+    peer_code.trim().starts_with(CLUSTER_PEER_PREFIX)
+}
+
+#[derive(Debug, Clone)]
+pub struct ClusterConfig {
+    /// 固定簇数；None 时在 `k_min..=k_max` 中按轮廓系数选取。
+    pub k: Option<usize>,
+    pub k_min: usize,
+    pub k_max: usize,
+    /// 参与计算的最近交易日数。
+    pub window_days: usize,
+    /// 两只基金计算相关系数所需的最少共同日期；也是入选基金的最少收益点数。
+    pub min_overlap: usize,
+    /// 入选基金上限（按窗口内收益点数优先）。
+    pub max_funds: usize,
+    /// 稳定性评估的子样本轮数；0 表示不评估（稳定性记为 1）。
+    pub bootstrap_rounds: usize,
+}
+
+impl Default for ClusterConfig {
+    fn default() -> Self {
+        Self {
+            k: None,
+            k_min: 4,
+            k_max: 24,
+            window_days: 250,
+            min_overlap: 60,
+            max_funds: 1500,
+            bootstrap_rounds: 10,
+        }
+    }
+}
+
+/// 对齐到统一日期轴的日收益面板；`returns[i][t]` 为第 i 只基金在 `dates[t]` 的对数收益。
+#[derive(Debug, Clone, Default)]
+pub struct ReturnPanel {
+    pub codes: Vec<String>,
+    pub dates: Vec<String>,
+    pub returns: Vec<Vec<Option<f64>>>,
+}
+
+/// 由各基金净值序列（日期升序）构建收益面板：取所有基金收益日期并集的最近 `window_days` 天，
+/// 窗口内收益点数不足 `min_overlap` 的基金不入选，超过 `max_funds` 时保留收益点数最多的。
+pub fn return_panel(navs: &[(String, Vec<(String, f64)>)], cfg: &ClusterConfig) -> ReturnPanel {
+    let series: Vec<(&str, Vec<(&str, f64)>)> = navs
+        .iter()
+        .map(|(code, nav)| {
+            let rets = nav
+                .windows(2)
+                .filter(|w| w[0].1 > 0.0 && w[1].1 > 0.0)
+                .map(|w| (w[1].0.as_str(), (w[1].1 / w[0].1).ln()))
+                .collect();
+            (code.as_str(), rets)
+        })
+        .collect();
+
+    let mut all_dates: Vec<&str> = series
+        .iter()
+        .flat_map(|(_, r)| r.iter().map(|(d, _)| *d))
+        .collect();
+    all_dates.sort_unstable();
+    all_dates.dedup();
+    let dates: Vec<&str> = all_dates[all_dates.len().saturating_sub(cfg.window_days)..].to_vec();
+    let pos: BTreeMap<&str, usize> = dates.iter().enumerate().map(|(i, d)| (*d, i)).collect();
+
+    let mut rows: Vec<(usize, &str, Vec<Option<f64>>)> = series
+        .into_iter()
+        .filter_map(|(code, rets)| {
+            let mut row = vec![None; dates.len()];
+            let mut n = 0;
+            for (d, r) in rets {
+                if let Some(&t) = pos.get(d) {
+                    row[t] = Some(r);
+                    n += 1;
+                }
+            }
+            (n >= cfg.min_overlap.max(2)).then_some((n, code, row))
+        })
+        .collect();
+    rows.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+    rows.truncate(cfg.max_funds);
+    rows.sort_by(|a, b| a.1.cmp(b.1));
+
+    ReturnPanel {
+        codes: rows.iter().map(|(_, c, _)| c.to_string()).collect(),
+        dates: dates.iter().map(|d| d.to_string()).collect(),
+        returns: rows.into_iter().map(|(_, _, r)| r).collect(),
+    }
+}
+
+/// 对称距离矩阵（按上三角压缩存储）。
+#[derive(Debug, Clone)]
+pub struct Distances {
+    n: usize,
+    d: Vec<f32>,
+}
+
+impl Distances {
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> f64 {
+        if i == j {
+            return 0.0;
+        }
+        let (a, b) = if i < j { (i, j) } else { (j, i) };
+        self.d[a * self.n - a * (a + 1) / 2 + (b - a - 1)] as f64
+    }
+}
+
+/// 相关距离矩阵；`keep` 给出时只用被保留的日期。
+pub fn correlation_distances(
+    panel: &ReturnPanel,
+    keep: Option<&[bool]>,
+    min_overlap: usize,
+) -> Distances {
+    let n = panel.returns.len();
+    let uncorrelated = std::f64::consts::SQRT_2 as f32;
+    let mut d = Vec::with_capacity(n * n.saturating_sub(1) / 2);
+    for i in 0..n {
+        for j in (i + 1)..n {
+            let rho = pairwise_correlation(
+                &panel.returns[i],
+                &panel.returns[j],
+                keep,
+                min_overlap.max(3),
+            );
+            d.push(rho.map_or(uncorrelated, |r| (2.0 * (1.0 - r)).max(0.0).sqrt() as f32));
+        }
+    }
+    Distances { n, d }
+}
+
+fn pairwise_correlation(
+    a: &[Option<f64>],
+    b: &[Option<f64>],
+    keep: Option<&[bool]>,
+    min_overlap: usize,
+) -> Option<f64> {
+    let (mut n, mut sa, mut sb, mut saa, mut sbb, mut sab) = (0_usize, 0.0, 0.0, 0.0, 0.0, 0.0);
+    for (t, (x, y)) in a.iter().zip(b.iter()).enumerate() {
+        if keep.is_some_and(|k| !k[t]) {
+            continue;
+        }
+        let (Some(x), Some(y)) = (x, y) else {
+            continue;
+        };
+        n += 1;
+        sa += x;
+        sb += y;
+        saa += x * x;
+        sbb += y * y;
+        sab += x * y;
+    }
+    if n < min_overlap {
+        return None;
+    }
+    let nf = n as f64;
+    let cov = sab - sa * sb / nf;
+    let va = saa - sa * sa / nf;
+    let vb = sbb - sb * sb / nf;
+    if va <= 1e-18 || vb <= 1e-18 {
+        return None;
+    }
+    Some((cov / (va * vb).sqrt()).clamp(-1.0, 1.0))
+}
+
+/// 一次 k-medoids 的结果：`labels[i]` 为 `medoids` 中的下标。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Partition {
+    pub medoids: Vec<usize>,
+    pub labels: Vec<usize>,
+    pub cost: f64,
+}
+
+/// k-medoids：`init` 为空时用 BUILD 贪心初始化，随后交替“分配到最近代表 / 组内重选代表”直到不变。
+pub fn k_medoids(dist: &Distances, k: usize, init: Option<&[usize]>) -> Option<Partition> {
+    let n = dist.len();
+    if k == 0 || k > n {
+        return None;
+    }
+    let mut medoids = match init {
+        Some(m) if m.len() == k && m.iter().all(|&i| i < n) => m.to_vec(),
+        _ => build_medoids(dist, k),
+    };
+    let mut labels = assign(dist, &medoids);
+    for _ in 0..MAX_KMEDOIDS_ITERS {
+        let mut changed = false;
+        for (c, medoid) in medoids.iter_mut().enumerate() {
+            let members: Vec<usize> = (0..n).filter(|&i| labels[i] == c).collect();
+            let best = members
+                .iter()
+                .map(|&m| (m, members.iter().map(|&j| dist.get(m, j)).sum::<f64>()))
+                .min_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+            if let Some((m, _)) = best
+                && m != *medoid
+            {
+                *medoid = m;
+                changed = true;
+            }
+        }
+        let next = assign(dist, &medoids);
+        if !changed && next == labels {
+            break;
+        }
+        labels = next;
+    }
+    let cost = (0..n).map(|i| dist.get(i, medoids[labels[i]])).sum();
+    Some(Partition {
+        medoids,
+        labels,
+        cost,
+    })
+}
+
+fn build_medoids(dist: &Distances, k: usize) -> Vec<usize> {
+    let n = dist.len();
+    // 每个候选的得分只算一次；并列时取下标小的。
+    let pick = |score: &dyn Fn(usize) -> f64, medoids: &[usize]| {
+        (0..n)
+            .filter(|i| !medoids.contains(i))
+            .map(|i| (i, score(i)))
+            .fold(None, |best: Option<(usize, f64)>, (i, s)| match best {
+                Some((_, b)) if b >= s => best,
+                _ => Some((i, s)),
+            })
+            .map(|(i, _)| i)
+    };
+    let Some(first) = pick(&|i| -(0..n).map(|j| dist.get(i, j)).sum::<f64>(), &[]) else {
+        return Vec::new();
+    };
+    let mut medoids = vec![first];
+    let mut nearest: Vec<f64> = (0..n).map(|j| dist.get(first, j)).collect();
+    while medoids.len() < k {
+        let gain = |i: usize| {
+            (0..n)
+                .map(|j| (nearest[j] - dist.get(i, j)).max(0.0))
+                .sum::<f64>()
+        };
+        let Some(c) = pick(&gain, &medoids) else {
+            break;
+        };
+        for (j, v) in nearest.iter_mut().enumerate() {
+            *v = v.min(dist.get(c, j));
+        }
+        medoids.push(c);
+    }
+    medoids
+}
+
+fn assign(dist: &Distances, medoids: &[usize]) -> Vec<usize> {
+    (0..dist.len())
+        .map(|i| {
+            medoids
+                .iter()
+                .enumerate()
+                .min_by(|a, b| dist.get(i, *a.1).total_cmp(&dist.get(i, *b.1)))
+                .map_or(0, |(c, _)| c)
+        })
+        .collect()
+}
+
+/// 每个样本的轮廓系数；单成员簇记为 0。
+pub fn silhouettes(dist: &Distances, labels: &[usize], k: usize) -> Vec<f64> {
+    let n = dist.len();
+    let mut sizes = vec![0_usize; k];
+    for &l in labels {
+        sizes[l] += 1;
+    }
+    (0..n)
+        .map(|i| {
+            let own = labels[i];
+            if sizes[own] <= 1 {
+                return 0.0;
+            }
+            let mut sums = vec![0.0; k];
+            for j in 0..n {
+                if j != i {
+                    sums[labels[j]] += dist.get(i, j);
+                }
+            }
+            let a = sums[own] / (sizes[own] - 1) as f64;
+            let b = (0..k)
+                .filter(|&c| c != own && sizes[c] > 0)
+                .map(|c| sums[c] / sizes[c] as f64)
+                .fold(f64::INFINITY, f64::min);
+            if !b.is_finite() || a.max(b) <= 0.0 {
+                0.0
+            } else {
+                (b - a) / a.max(b)
+            }
+        })
+        .collect()
+}
+
+/// 聚类结果；簇按规模降序、代表基金代码升序编号。
+#[derive(Debug, Clone)]
+pub struct Clustering {
+    pub codes: Vec<String>,
+    pub k: usize,
+    pub medoids: Vec<usize>,
+    pub labels: Vec<usize>,
+    pub silhouette: Vec<f64>,
+    pub stability: Vec<f64>,
+    pub mean_silhouette: f64,
+}
+
+impl Clustering {
+    pub fn peer_code(&self, cluster: usize) -> String {
+        format!("{CLUSTER_PEER_PREFIX}{}", self.codes[self.medoids[cluster]])
+    }
+
+    pub fn members(&self, cluster: usize) -> Vec<usize> {
+        (0..self.labels.len())
+            .filter(|&i| self.labels[i] == cluster)
+            .collect()
+    }
+}
+
+/// 对收益面板聚类并评估稳定性；入选基金不足 `2 * k_min`（或固定 k 时不足 2k）时为 None。
+pub fn cluster_panel(panel: &ReturnPanel, cfg: &ClusterConfig) -> Option<Clustering> {
+    let n = panel.codes.len();
+    let dist = correlation_distances(panel, None, cfg.min_overlap);
+    let (k, partition, sil) = match cfg.k {
+        Some(k) => {
+            if k < 2 || n < 2 * k {
+                return None;
+            }
+            let p = k_medoids(&dist, k, None)?;
+            let sil = silhouettes(&dist, &p.labels, k);
+            (k, p, sil)
+        }
+        None => {
+            let k_min = cfg.k_min.max(2);
+            let k_max = cfg.k_max.min(n / 2);
+            if k_min > k_max {
+                return None;
+            }
+            let mut best: Option<(f64, usize, Partition, Vec<f64>)> = None;
+            for k in k_min..=k_max {
+                let Some(p) = k_medoids(&dist, k, None) else {
+                    continue;
+                };
+                let sil = silhouettes(&dist, &p.labels, k);
+                let mean = sil.iter().sum::<f64>() / n as f64;
+                if best.as_ref().is_none_or(|b| mean > b.0) {
+                    best = Some((mean, k, p, sil));
+                }
+            }
+            let (_, k, p, sil) = best?;
+            (k, p, sil)
+        }
+    };
+    let stability = stability_scores(panel, cfg, &partition);
+
+    // 规范化编号：规模降序，其次代表基金代码升序。
+    let mut order: Vec<usize> = (0..k).collect();
+    let size = |c: usize| partition.labels.iter().filter(|&&l| l == c).count();
+    order.sort_by(|&a, &b| {
+        size(b)
+            .cmp(&size(a))
+            .then_with(|| panel.codes[partition.medoids[a]].cmp(&panel.codes[partition.medoids[b]]))
+    });
+    let mut rank = vec![0; k];
+    for (new, &old) in order.iter().enumerate() {
+        rank[old] = new;
+    }
+    Some(Clustering {
+        codes: panel.codes.clone(),
+        k,
+        medoids: order.iter().map(|&c| partition.medoids[c]).collect(),
+        labels: partition.labels.iter().map(|&l| rank[l]).collect(),
+        mean_silhouette: sil.iter().sum::<f64>() / n as f64,
+        silhouette: sil,
+        stability,
+    })
+}
+
+/// 子样本重聚类的稳定性（见模块说明）；日期子样本由固定种子生成，结果可复现。
+fn stability_scores(panel: &ReturnPanel, cfg: &ClusterConfig, base: &Partition) -> Vec<f64> {
+    let n = base.labels.len();
+    if cfg.bootstrap_rounds == 0 {
+        return vec![1.0; n];
+    }
+    let k = base.medoids.len();
+    let min_overlap = ((cfg.min_overlap as f64) * SUBSAMPLE_FRACTION).ceil() as usize;
+    let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+    let mut total = vec![0.0; n];
+    for _ in 0..cfg.bootstrap_rounds {
+        let keep: Vec<bool> = (0..panel.dates.len())
+            .map(|_| {
+                seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                ((seed >> 11) as f64) / ((1_u64 << 53) as f64) < SUBSAMPLE_FRACTION
+            })
+            .collect();
+        let dist = correlation_distances(panel, Some(&keep), min_overlap);
+        let Some(p) = k_medoids(&dist, k, Some(&base.medoids)) else {
+            continue;
+        };
+        for (i, t) in total.iter_mut().enumerate() {
+            let (mut inter, mut union) = (0_usize, 0_usize);
+            for j in 0..n {
+                let a = base.labels[j] == base.labels[i];
+                let b = p.labels[j] == p.labels[i];
+                inter += (a && b) as usize;
+                union += (a || b) as usize;
+            }
+            *t += inter as f64 / union.max(1) as f64;
+        }
+    }
+    total
+        .into_iter()
+        .map(|t| t / cfg.bootstrap_rounds as f64)
+        .collect()
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ClusterRunStats {
+    pub funds: usize,
+    pub clusters: usize,
+    pub mean_silhouette: f64,
+    pub mean_stability: f64,
+}
+
+async fn load_all_navs(
+    pool: &sqlx::AnyPool,
+    source_name: &str,
+    since: &str,
+) -> Result<Vec<(String, Vec<(String, f64)>)>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+          f.fund_code as fund_code,
+          CAST(h.nav_date AS TEXT) as nav_date,
+          CAST(h.unit_nav AS TEXT) as unit_nav
+        FROM fund_nav_history h
+        JOIN fund f ON f.id = h.fund_id
+        WHERE h.source_name = $1 AND CAST(h.nav_date AS TEXT) >= $2
+        ORDER BY f.fund_code ASC, h.nav_date ASC
+        "#,
+    )
+    .bind(source_name)
+    .bind(since)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut out: Vec<(String, Vec<(String, f64)>)> = Vec::new();
+    for r in rows {
+        let code: String = r.get("fund_code");
+        let d: String = r.get("nav_date");
+        let s: String = r.get("unit_nav");
+        let Ok(v) = s.trim().parse::<f64>() else {
+            continue;
+        };
+        let code = code.trim().to_string();
+        if out.last().is_none_or(|(c, _)| *c != code) {
+            out.push((code, Vec::new()));
+        }
+        if let Some((_, navs)) = out.last_mut() {
+            navs.push((d.get(0..10).unwrap_or(&d).to_string(), v));
+        }
+    }
+    Ok(out)
+}
+
+/// 聚类 `source_name` 下全部基金并替换该数据源的 `fund_cluster` / `fund_cluster_member`（其他数据源不受影响）。
+pub async fn compute_and_store(
+    pool: &sqlx::AnyPool,
+    source_name: &str,
+    cfg: &ClusterConfig,
+) -> Result<ClusterRunStats, String> {
+    // 自然日约为交易日的 1.5 倍，多取一些保证窗口取满。
+    let since = (chrono::Utc::now().date_naive()
+        - chrono::Duration::days((cfg.window_days as i64) * 3 / 2 + 30))
+    .format("%Y-%m-%d")
+    .to_string();
+    let navs = load_all_navs(pool, source_name, &since).await?;
+    let panel = return_panel(&navs, cfg);
+    let Some(c) = cluster_panel(&panel, cfg) else {
+        return Err(format!(
+            "not enough funds to cluster: {} with >= {} returns",
+            panel.codes.len(),
+            cfg.min_overlap
+        ));
+    };
+
+    let names: BTreeMap<String, String> = sqlx::query("SELECT fund_code, fund_name FROM fund")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|r| {
+            (
+                r.get::<String, _>("fund_code").trim().to_string(),
+                r.get::<String, _>("fund_name"),
+            )
+        })
+        .collect();
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    for table in ["fund_cluster_member", "fund_cluster"] {
+        sqlx::query(&format!("DELETE FROM {table} WHERE source_name = $1"))
+            .bind(source_name)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    for cluster in 0..c.k {
+        let members = c.members(cluster);
+        let peer_code = c.peer_code(cluster);
+        let medoid = &c.codes[c.medoids[cluster]];
+        let peer_name = format!(
+            "走势相近：{}等{}只",
+            names.get(medoid).map_or(medoid.as_str(), |s| s.as_str()),
+            members.len()
+        );
+        let mean = |v: &[f64]| members.iter().map(|&i| v[i]).sum::<f64>() / members.len() as f64;
+        sqlx::query(
+            r#"
+            INSERT INTO fund_cluster (
+              peer_code, peer_name, medoid_fund_code, source_name, size, mean_stability, mean_silhouette, computed_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, CURRENT_TIMESTAMP)
+            "#,
+        )
+        .bind(&peer_code)
+        .bind(&peer_name)
+        .bind(medoid)
+        .bind(source_name)
+        .bind(members.len() as i64)
+        .bind(mean(&c.stability))
+        .bind(mean(&c.silhouette))
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+        for &i in &members {
+            sqlx::query(
+                r#"
+                INSERT INTO fund_cluster_member (source_name, fund_code, peer_code, stability, silhouette, computed_at)
+                VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP)
+                "#,
+            )
+            .bind(source_name)
+            .bind(&c.codes[i])
+            .bind(&peer_code)
+            .bind(c.stability[i])
+            .bind(c.silhouette[i])
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        }
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    let n = c.codes.len();
+    Ok(ClusterRunStats {
+        funds: n,
+        clusters: c.k,
+        mean_silhouette: c.mean_silhouette,
+        mean_stability: c.stability.iter().sum::<f64>() / n as f64,
+    })
+}
+
+/// 基金的同类组：优先关联板块（按代码升序，最多 `limit` 个）；没有关联板块时回退到
+/// `source_name` 下稳定性达标的聚类同类组。返回 (peer_code, peer_name)。
+pub async fn fund_peers(
+    pool: &sqlx::AnyPool,
+    fund_code: &str,
+    source_name: &str,
+    limit: i64,
+) -> Result<Vec<(String, String)>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT sec_code, sec_name
+        FROM fund_relate_theme
+        WHERE fund_code = $1
+        GROUP BY sec_code, sec_name
+        ORDER BY sec_code ASC
+        LIMIT $2
+        "#,
+    )
+    .bind(fund_code)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    let themes: Vec<(String, String)> = rows
+        .into_iter()
+        .map(|r| (r.get("sec_code"), r.get("sec_name")))
+        .collect();
+    if !themes.is_empty() {
+        return Ok(themes);
+    }
+    Ok(fund_cluster_peer(pool, fund_code, source_name)
+        .await?
+        .into_iter()
+        .collect())
+}
+
+/// 基金在 `source_name` 下所属的聚类同类组 (peer_code, peer_name)；稳定性低于 [`MIN_PEER_STABILITY`] 时为 None。
+pub async fn fund_cluster_peer(
+    pool: &sqlx::AnyPool,
+    fund_code: &str,
+    source_name: &str,
+) -> Result<Option<(String, String)>, String> {
+    let row = sqlx::query(
+        r#"
+        SELECT c.peer_code as peer_code, c.peer_name as peer_name
+        FROM fund_cluster_member m
+        JOIN fund_cluster c ON c.source_name = m.source_name AND c.peer_code = m.peer_code
+        WHERE m.fund_code = $1 AND m.source_name = $2 AND m.stability >= $3
+        "#,
+    )
+    .bind(fund_code)
+    .bind(source_name)
+    .bind(MIN_PEER_STABILITY)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(row.map(|r| (r.get("peer_code"), r.get("peer_name"))))
+}
+
+/// `source_name` 下的同类组成员（基金代码升序）。
+pub async fn cluster_members(
+    pool: &sqlx::AnyPool,
+    peer_code: &str,
+    source_name: &str,
+    limit: i64,
+) -> Result<Vec<String>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT fund_code
+        FROM fund_cluster_member
+        WHERE peer_code = $1 AND source_name = $2
+        ORDER BY fund_code ASC
+        LIMIT $3
+        "#,
+    )
+    .bind(peer_code.trim())
+    .bind(source_name)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(rows
+        .into_iter()
+        .map(|r| r.get::<String, _>("fund_code"))
+        .collect())
+}
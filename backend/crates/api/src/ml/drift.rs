@@ -0,0 +1,741 @@
+//! 漂移监控：比较冠军模型训练期与之后的数据分布、近期校准，并按模型年龄判断是否需要重训。
+//!
+//! - 特征漂移：样本按冠军训练截止日切分为参考段（训练期）与近期段，两段都用模型自带的
+//!   `mean`/`std`（逻辑回归 / OLS）标准化，逐特征计算 PSI 与两样本 KS；GBDT 没有标准化参数时用参考段估计。
+//! - 校准：板块模型比较近期已实现样本的 Brier 与训练时留出集的 Brier；预测模型比较近期 RMSE 与训练残差 σ。
+//! - 陈旧：该 key 最近一次登记版本（含未晋升的挑战者）距今天数。
+//!
+//! 任一项越过阈值即向 `task_job` 入队对应的重训任务（同参数任务已在排队或运行时不重复入队；
+//! 上次重训产出的挑战者仍待处理时也不再入队），
+//! 每次检查的结论写入 `ml_drift_check` 供人工复核。
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use serde_json::{Value, json};
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::db::DatabaseKind;
+use crate::features;
+use crate::forecast::model::{ForecastModel, ForecastModelColumns};
+use crate::ml::dataset::{
+    DatasetConfig, TriggerSample, build_trigger_samples_for_all_funds,
+    build_trigger_samples_for_peer,
+};
+use crate::ml::metrics;
+use crate::ml::model::Classifier;
+use crate::ml::registry::{self, ModelVersion};
+use crate::ml::train::{MlTask, PEER_CODE_ALL};
+
+/// PSI 分箱数（按参考段分位数切分）。
+pub const PSI_BINS: usize = 10;
+/// 空箱占比的下限，避免 ln(0)。
+const PSI_EPS: f64 = 1e-4;
+/// 预测模型每只基金读取的收益序列长度（与训练一致）。
+const FORECAST_HISTORY: i64 = 400;
+/// 重训任务的优先级。
+const RETRAIN_PRIORITY: i64 = 10;
+
+pub const DECISION_OK: &str = "ok";
+pub const DECISION_RETRAIN: &str = "retrain";
+pub const DECISION_RETRAIN_PENDING: &str = "retrain_pending";
+/// 上次重训登记的挑战者被影子评估保留（hold），等待人工晋升或下次评估。
+pub const DECISION_RETRAIN_HELD: &str = "retrain_held";
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct DriftConfig {
+    /// 任一特征 PSI 超过该值视为漂移（0.25 为常用的“显著变化”线）。
+    pub psi: f64,
+    /// 任一特征 KS 统计量超过该值视为漂移。
+    pub ks: f64,
+    /// 板块模型：近期 Brier 比训练留出集高出该值视为校准恶化。
+    pub brier_delta: f64,
+    /// 预测模型：近期 RMSE 与训练残差 σ 之比超过该值视为校准恶化。
+    pub rmse_ratio: f64,
+    /// 最近一次登记版本超过该天数视为陈旧。
+    pub max_age_days: i64,
+    /// 近期样本少于该数时不判断特征漂移与校准。
+    pub min_samples: usize,
+    /// 预测模型最多扫描的基金数。
+    pub max_funds: usize,
+}
+
+impl Default for DriftConfig {
+    fn default() -> Self {
+        Self {
+            psi: 0.25,
+            ks: 0.2,
+            brier_delta: 0.05,
+            rmse_ratio: 1.5,
+            max_age_days: 30,
+            min_samples: 30,
+            max_funds: 300,
+        }
+    }
+}
+
+fn is_pg(pool: &sqlx::AnyPool) -> bool {
+    crate::db::database_kind_from_pool(pool) == DatabaseKind::Postgres
+}
+
+/// 群体稳定性指数：按参考段分位数分箱，Σ (a - e)·ln(a / e)。
+pub fn psi(reference: &[f64], recent: &[f64], bins: usize) -> Option<f64> {
+    if reference.is_empty() || recent.is_empty() || bins < 2 {
+        return None;
+    }
+    let mut sorted = reference.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let mut cuts: Vec<f64> = (1..bins).map(|i| sorted[i * sorted.len() / bins]).collect();
+    cuts.dedup();
+    let shares = |v: &[f64]| {
+        let mut counts = vec![0.0; cuts.len() + 1];
+        for &x in v {
+            counts[cuts.partition_point(|&c| c <= x)] += 1.0;
+        }
+        counts
+            .into_iter()
+            .map(|n| (n / v.len() as f64).max(PSI_EPS))
+            .collect::<Vec<_>>()
+    };
+    let (e, a) = (shares(reference), shares(recent));
+    Some(e.iter().zip(&a).map(|(e, a)| (a - e) * (a / e).ln()).sum())
+}
+
+/// 两样本 Kolmogorov–Smirnov 统计量（经验分布函数的最大差）。
+pub fn ks_statistic(a: &[f64], b: &[f64]) -> Option<f64> {
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+    let sort = |v: &[f64]| {
+        let mut s = v.to_vec();
+        s.sort_by(|x, y| x.total_cmp(y));
+        s
+    };
+    let (a, b) = (sort(a), sort(b));
+    let (mut i, mut j, mut d) = (0, 0, 0.0_f64);
+    while i < a.len() && j < b.len() {
+        let x = a[i].min(b[j]);
+        while i < a.len() && a[i] <= x {
+            i += 1;
+        }
+        while j < b.len() && b[j] <= x {
+            j += 1;
+        }
+        d = d.max((i as f64 / a.len() as f64 - j as f64 / b.len() as f64).abs());
+    }
+    Some(d)
+}
+
+fn mean_std(v: &[f64]) -> (f64, f64) {
+    let n = v.len().max(1) as f64;
+    let mean = v.iter().sum::<f64>() / n;
+    let var = v.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / n;
+    (mean, var.sqrt())
+}
+
+/// 单个特征的漂移（标准化尺度）。
+#[derive(Debug, Clone, Serialize)]
+pub struct FeatureDrift {
+    pub name: String,
+    pub psi: Option<f64>,
+    pub ks: Option<f64>,
+    /// 近期段标准化后的均值与标准差（训练期约为 0 / 1）。
+    pub recent_mean_z: f64,
+    pub recent_std_z: f64,
+}
+
+/// 逐特征比较参考段与近期段；`scale` 为模型的标准化参数，缺失时用参考段估计。
+pub fn feature_drift(
+    names: &[String],
+    reference: &[Vec<f64>],
+    recent: &[Vec<f64>],
+    scale: Option<(&[f64], &[f64])>,
+) -> Vec<FeatureDrift> {
+    names
+        .iter()
+        .enumerate()
+        .map(|(j, name)| {
+            let column = |rows: &[Vec<f64>]| -> Vec<f64> {
+                rows.iter().filter_map(|r| r.get(j).copied()).collect()
+            };
+            let (ref_col, recent_col) = (column(reference), column(recent));
+            let (mean, std) = match scale {
+                Some((m, s)) if j < m.len() && j < s.len() => (m[j], s[j]),
+                _ => mean_std(&ref_col),
+            };
+            let z = |v: Vec<f64>| -> Vec<f64> {
+                v.into_iter()
+                    .map(|x| {
+                        if std.abs() < 1e-12 {
+                            x - mean
+                        } else {
+                            (x - mean) / std
+                        }
+                    })
+                    .collect()
+            };
+            let (ref_z, recent_z) = (z(ref_col), z(recent_col));
+            let (recent_mean_z, recent_std_z) = mean_std(&recent_z);
+            FeatureDrift {
+                name: name.clone(),
+                psi: psi(&ref_z, &recent_z, PSI_BINS),
+                ks: ks_statistic(&ref_z, &recent_z),
+                recent_mean_z,
+                recent_std_z,
+            }
+        })
+        .collect()
+}
+
+/// 单个冠军版本的检查结果（写入 `report_json`）。
+#[derive(Debug, Clone, Serialize)]
+pub struct DriftReport {
+    pub model_type: String,
+    pub train_end_date: Option<String>,
+    /// 最近一次登记版本距今天数。
+    pub age_days: Option<i64>,
+    pub reference_samples: usize,
+    pub recent_samples: usize,
+    pub features: Vec<FeatureDrift>,
+    pub max_psi: Option<f64>,
+    pub max_ks: Option<f64>,
+    pub calibration: Value,
+    pub thresholds: DriftConfig,
+}
+
+/// 一次检查记录（`ml_drift_check` 的一行）。
+#[derive(Debug, Clone, Serialize)]
+pub struct DriftCheck {
+    pub id: String,
+    pub family: String,
+    pub model_key: String,
+    pub model_version_id: String,
+    pub version: i64,
+    pub decision: String,
+    /// 触发原因：`stale` / `feature_set_changed` / `feature_psi` / `feature_ks` / `calibration`。
+    pub reasons: Vec<String>,
+    pub report: Value,
+    pub task_job_id: Option<String>,
+    pub checked_at: Option<String>,
+}
+
+/// 同一次运行内共享的样本：同一板块与持有期的两个任务只构建一次。
+#[derive(Default)]
+pub struct SampleCache {
+    sector: HashMap<(String, usize), Vec<TriggerSample>>,
+    forecast: HashMap<(String, usize), LagSamples>,
+}
+
+#[derive(Default)]
+struct LagSamples {
+    x: Vec<Vec<f64>>,
+    y: Vec<f64>,
+    dates: Vec<String>,
+}
+
+fn date_prefix(s: &str) -> &str {
+    s.get(0..10).unwrap_or(s)
+}
+
+fn age_days(created_at: Option<&str>) -> Option<i64> {
+    let created = chrono::NaiveDate::parse_from_str(date_prefix(created_at?), "%Y-%m-%d").ok()?;
+    Some((chrono::Utc::now().date_naive() - created).num_days())
+}
+
+/// 训练截止日之后为近期段，其余为参考段；返回 (参考下标, 近期下标)。
+fn split_by_date(dates: &[String], train_end: Option<&str>) -> (Vec<usize>, Vec<usize>) {
+    let end = train_end.map(date_prefix).unwrap_or("");
+    (0..dates.len()).partition(|&i| date_prefix(&dates[i]) <= end)
+}
+
+fn max_of(items: &[FeatureDrift], f: fn(&FeatureDrift) -> Option<f64>) -> Option<f64> {
+    items.iter().filter_map(f).reduce(f64::max)
+}
+
+struct Evaluation {
+    report: DriftReport,
+    reasons: Vec<String>,
+    retrain_task: &'static str,
+    retrain_payload: Value,
+}
+
+fn evaluate(
+    version: &ModelVersion,
+    age_days: Option<i64>,
+    cfg: &DriftConfig,
+    reference: &[Vec<f64>],
+    recent: &[Vec<f64>],
+    scale: Option<(&[f64], &[f64])>,
+    calibration: Value,
+) -> (DriftReport, Vec<String>) {
+    let features = if recent.len() >= cfg.min_samples {
+        feature_drift(&version.feature_names, reference, recent, scale)
+    } else {
+        Vec::new()
+    };
+    let report = DriftReport {
+        model_type: version.model_type.clone(),
+        train_end_date: version.train_end_date.clone(),
+        age_days,
+        reference_samples: reference.len(),
+        recent_samples: recent.len(),
+        max_psi: max_of(&features, |f| f.psi),
+        max_ks: max_of(&features, |f| f.ks),
+        features,
+        calibration,
+        thresholds: *cfg,
+    };
+    let mut reasons = Vec::new();
+    if report.age_days.is_some_and(|d| d > cfg.max_age_days) {
+        reasons.push("stale".to_string());
+    }
+    if report.max_psi.is_some_and(|v| v > cfg.psi) {
+        reasons.push("feature_psi".to_string());
+    }
+    if report.max_ks.is_some_and(|v| v > cfg.ks) {
+        reasons.push("feature_ks".to_string());
+    }
+    if report.calibration["drifted"].as_bool() == Some(true) {
+        reasons.push("calibration".to_string());
+    }
+    (report, reasons)
+}
+
+async fn evaluate_sector(
+    pool: &sqlx::AnyPool,
+    version: &ModelVersion,
+    age_days: Option<i64>,
+    source_name: &str,
+    cfg: &DriftConfig,
+    cache: &mut SampleCache,
+) -> Result<Evaluation, String> {
+    let payload = &version.payload;
+    let peer_code = payload["peer_code"]
+        .as_str()
+        .ok_or("payload missing peer_code")?;
+    let task = match payload["task"].as_str() {
+        Some(t) if t == MlTask::DipBuy.as_str() => MlTask::DipBuy,
+        Some(t) if t == MlTask::MagicRebound.as_str() => MlTask::MagicRebound,
+        other => return Err(format!("unknown task: {other:?}")),
+    };
+    let horizon_days = payload["horizon_days"]
+        .as_u64()
+        .ok_or("payload missing horizon_days")? as usize;
+    // 重训两个任务一起跑，同板块同持有期的两份检查会合并到同一个任务。
+    let retrain_payload = json!({
+        "source": source_name,
+        "peer_codes": [peer_code],
+        "horizons": [horizon_days],
+    });
+
+    let current: Vec<String> = features::ml_signal_features(&crate::config::ConfigStore::load())
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect();
+    if current != version.feature_names {
+        let (report, mut reasons) = evaluate(version, age_days, cfg, &[], &[], None, Value::Null);
+        reasons.push("feature_set_changed".to_string());
+        return Ok(Evaluation {
+            report,
+            reasons,
+            retrain_task: "ml_sector_model_train",
+            retrain_payload,
+        });
+    }
+
+    let key = (peer_code.to_string(), horizon_days);
+    if !cache.sector.contains_key(&key) {
+        let ds = DatasetConfig {
+            lookback_days: 252,
+            horizon_days,
+            stride_days: 5,
+        };
+        let samples = if peer_code == PEER_CODE_ALL {
+            build_trigger_samples_for_all_funds(pool, source_name, &ds).await?
+        } else {
+            build_trigger_samples_for_peer(pool, peer_code, source_name, &ds).await?
+        };
+        cache.sector.insert(key.clone(), samples);
+    }
+    let samples = &cache.sector[&key];
+    let dates: Vec<String> = samples.iter().map(|s| s.as_of_date.clone()).collect();
+    let (ref_idx, recent_idx) = split_by_date(&dates, version.train_end_date.as_deref());
+    let rows = |idx: &[usize]| -> Vec<Vec<f64>> {
+        idx.iter().map(|&i| samples[i].features.clone()).collect()
+    };
+    let (reference, recent) = (rows(&ref_idx), rows(&recent_idx));
+
+    let model = Classifier::from_json(&payload["model"].to_string())?;
+    let y: Vec<f64> = recent_idx
+        .iter()
+        .map(|&i| {
+            let s = &samples[i];
+            let label = match task {
+                MlTask::DipBuy => s.dip_buy_success,
+                MlTask::MagicRebound => s.magic_rebound,
+            };
+            if label { 1.0 } else { 0.0 }
+        })
+        .collect();
+    let p: Vec<f64> = recent
+        .iter()
+        .map(|r| model.predict_proba(r).unwrap_or(0.5))
+        .collect();
+    let train_brier = version.metrics["validation"]["brier"].as_f64();
+    let recent_brier = metrics::brier_score(&y, &p);
+    let delta = match (recent_brier, train_brier) {
+        (Some(r), Some(t)) if y.len() >= cfg.min_samples => Some(r - t),
+        _ => None,
+    };
+    let calibration = json!({
+        "metric": "brier",
+        "train": train_brier,
+        "recent": recent_brier,
+        "recent_samples": y.len(),
+        "recent_positive_rate": (!y.is_empty()).then(|| y.iter().sum::<f64>() / y.len() as f64),
+        "recent_mean_proba": (!p.is_empty()).then(|| p.iter().sum::<f64>() / p.len() as f64),
+        "delta": delta,
+        "drifted": delta.is_some_and(|d| d > cfg.brier_delta),
+    });
+
+    let scale = match &model {
+        Classifier::LogReg(m) => Some((m.mean.as_slice(), m.std.as_slice())),
+        Classifier::Gbdt(_) => None,
+    };
+    let (report, reasons) = evaluate(
+        version,
+        age_days,
+        cfg,
+        &reference,
+        &recent,
+        scale,
+        calibration,
+    );
+    Ok(Evaluation {
+        report,
+        reasons,
+        retrain_task: "ml_sector_model_train",
+        retrain_payload,
+    })
+}
+
+/// 与训练同口径的滞后收益样本：每只基金最近 [`FORECAST_HISTORY`] 个收益，日期为目标日。
+async fn forecast_samples(
+    pool: &sqlx::AnyPool,
+    source_name: &str,
+    lag_k: usize,
+    max_funds: usize,
+) -> Result<LagSamples, String> {
+    let rows = sqlx::query("SELECT fund_code FROM fund ORDER BY fund_code ASC LIMIT $1")
+        .bind(max_funds as i64)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut out = LagSamples::default();
+    for r in rows {
+        let code: String = r.get("fund_code");
+        features::store::materialize_fund(pool, code.trim(), source_name).await?;
+        let dated = features::store::load_dated_series(
+            pool,
+            code.trim(),
+            source_name,
+            features::LAGGED_RETURN_FEATURE,
+            None,
+            Some(FORECAST_HISTORY),
+        )
+        .await?;
+        if dated.len() < lag_k + 2 {
+            continue;
+        }
+        let series: Vec<f64> = dated.iter().map(|(_, v)| *v).collect();
+        let (x, y) = features::lag_samples(&series, lag_k);
+        out.x.extend(x);
+        out.y.extend(y);
+        out.dates
+            .extend(dated.into_iter().skip(lag_k).map(|(d, _)| d));
+    }
+    Ok(out)
+}
+
+async fn evaluate_forecast(
+    pool: &sqlx::AnyPool,
+    version: &ModelVersion,
+    age_days: Option<i64>,
+    cfg: &DriftConfig,
+    cache: &mut SampleCache,
+) -> Result<Evaluation, String> {
+    let payload = &version.payload;
+    let source_name = payload["source"].as_str().ok_or("payload missing source")?;
+    let lag_k = payload["lag_k"].as_u64().ok_or("payload missing lag_k")? as usize;
+    let retrain_payload = json!({
+        "source": source_name,
+        "model_name": payload["model_name"],
+        "model_type": version.model_type,
+        "horizon": payload["horizon"],
+        "lag_k": lag_k,
+    });
+
+    let key = (source_name.to_string(), lag_k);
+    if !cache.forecast.contains_key(&key) {
+        let samples = forecast_samples(pool, source_name, lag_k, cfg.max_funds).await?;
+        cache.forecast.insert(key.clone(), samples);
+    }
+    let samples = &cache.forecast[&key];
+    let (ref_idx, recent_idx) = split_by_date(&samples.dates, version.train_end_date.as_deref());
+    let rows =
+        |idx: &[usize]| -> Vec<Vec<f64>> { idx.iter().map(|&i| samples.x[i].clone()).collect() };
+    let (reference, recent) = (rows(&ref_idx), rows(&recent_idx));
+
+    let cols: ForecastModelColumns =
+        serde_json::from_value(payload["columns"].clone()).map_err(|e| e.to_string())?;
+    let model = ForecastModel::from_columns(&cols)?;
+    let y: Vec<f64> = recent_idx.iter().map(|&i| samples.y[i]).collect();
+    let p: Vec<f64> = recent
+        .iter()
+        .map(|r| model.predict(r).unwrap_or(0.0))
+        .collect();
+    let sigma = version.metrics["residual_sigma"]
+        .as_f64()
+        .filter(|s| *s > 0.0);
+    let recent_rmse = registry::rmse(&y, &p);
+    let ratio = match (recent_rmse, sigma) {
+        (Some(r), Some(s)) if y.len() >= cfg.min_samples => Some(r / s),
+        _ => None,
+    };
+    let calibration = json!({
+        "metric": "rmse",
+        "train_residual_sigma": sigma,
+        "recent": recent_rmse,
+        "recent_samples": y.len(),
+        "ratio": ratio,
+        "drifted": ratio.is_some_and(|r| r > cfg.rmse_ratio),
+    });
+
+    let scale = match &model {
+        ForecastModel::Ols(m) => Some((m.mean.as_slice(), m.std.as_slice())),
+        ForecastModel::Gbdt { .. } => None,
+    };
+    let (report, reasons) = evaluate(
+        version,
+        age_days,
+        cfg,
+        &reference,
+        &recent,
+        scale,
+        calibration,
+    );
+    Ok(Evaluation {
+        report,
+        reasons,
+        retrain_task: "forecast_model_train",
+        retrain_payload,
+    })
+}
+
+/// 同参数且仍在排队或运行的重训任务。
+async fn pending_job(
+    pool: &sqlx::AnyPool,
+    task_type: &str,
+    payload: &Value,
+) -> Result<Option<String>, String> {
+    let payload_json = serde_json::to_string(payload).map_err(|e| e.to_string())?;
+    let row = sqlx::query(
+        r#"
+        SELECT CAST(id AS TEXT) as id
+        FROM task_job
+        WHERE task_type = $1 AND payload_json = $2 AND status IN ('queued', 'running')
+        ORDER BY created_at ASC
+        LIMIT 1
+        "#,
+    )
+    .bind(task_type)
+    .bind(payload_json)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(row.map(|r| r.get("id")))
+}
+
+/// 最近一次 `retrain` 决定之后登记、仍处于 challenger 状态的版本（影子评估保留）。
+async fn held_challenger(
+    pool: &sqlx::AnyPool,
+    family: &str,
+    model_key: &str,
+) -> Result<Option<String>, String> {
+    let row = sqlx::query(
+        r#"
+        SELECT CAST(v.id AS TEXT) as id
+        FROM ml_model_version v
+        WHERE v.family = $1 AND v.model_key = $2 AND v.status = 'challenger'
+          AND v.created_at >= (
+            SELECT MAX(c.checked_at)
+            FROM ml_drift_check c
+            WHERE c.family = $1 AND c.model_key = $2 AND c.decision = 'retrain'
+          )
+        ORDER BY v.version DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(family)
+    .bind(model_key)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(row.map(|r| r.get("id")))
+}
+
+async fn insert_check(pool: &sqlx::AnyPool, check: &DriftCheck) -> Result<(), String> {
+    let sql = if is_pg(pool) {
+        r#"
+        INSERT INTO ml_drift_check (
+          id, family, model_key, model_version_id, version, decision,
+          reasons_json, report_json, task_job_id, checked_at
+        )
+        VALUES (($1)::uuid, $2, $3, ($4)::uuid, $5, $6, $7, $8, ($9)::uuid, CURRENT_TIMESTAMP)
+        "#
+    } else {
+        r#"
+        INSERT INTO ml_drift_check (
+          id, family, model_key, model_version_id, version, decision,
+          reasons_json, report_json, task_job_id, checked_at
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, CURRENT_TIMESTAMP)
+        "#
+    };
+    sqlx::query(sql)
+        .bind(&check.id)
+        .bind(&check.family)
+        .bind(&check.model_key)
+        .bind(&check.model_version_id)
+        .bind(check.version)
+        .bind(&check.decision)
+        .bind(serde_json::to_string(&check.reasons).map_err(|e| e.to_string())?)
+        .bind(check.report.to_string())
+        .bind(check.task_job_id.clone())
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 检查一个冠军版本：越阈值时入队重训，并记录结论。
+/// 板块模型的样本取自 `source_name`；预测模型用其 key 中的数据源。
+pub async fn check_version(
+    pool: &sqlx::AnyPool,
+    version: &ModelVersion,
+    source_name: &str,
+    cfg: &DriftConfig,
+    cache: &mut SampleCache,
+) -> Result<DriftCheck, String> {
+    // 年龄按该 key 最近一次登记计：重训产出的挑战者即使未晋升，也说明模型刚刚重新拟合过。
+    let latest = registry::latest_created_at(pool, &version.family, &version.model_key).await?;
+    let age = age_days(latest.as_deref().or(version.created_at.as_deref()));
+    let eval = match version.family.as_str() {
+        registry::FAMILY_SECTOR => {
+            evaluate_sector(pool, version, age, source_name, cfg, cache).await?
+        }
+        registry::FAMILY_FORECAST => evaluate_forecast(pool, version, age, cfg, cache).await?,
+        other => return Err(format!("unknown family: {other}")),
+    };
+
+    let mut held = None;
+    let (decision, task_job_id) = if eval.reasons.is_empty() {
+        (DECISION_OK, None)
+    } else if let Some(id) = pending_job(pool, eval.retrain_task, &eval.retrain_payload).await? {
+        (DECISION_RETRAIN_PENDING, Some(id))
+    } else if let Some(id) = held_challenger(pool, &version.family, &version.model_key).await? {
+        held = Some(id);
+        (DECISION_RETRAIN_HELD, None)
+    } else {
+        let id = crate::tasks::enqueue_task_job(
+            pool,
+            eval.retrain_task,
+            &eval.retrain_payload,
+            RETRAIN_PRIORITY,
+            None,
+        )
+        .await?;
+        (DECISION_RETRAIN, Some(id))
+    };
+
+    let mut report = serde_json::to_value(&eval.report).map_err(|e| e.to_string())?;
+    report["retrain"] = json!({ "task_type": eval.retrain_task, "payload": eval.retrain_payload });
+    if let Some(id) = held {
+        report["held_challenger_id"] = json!(id);
+    }
+    let check = DriftCheck {
+        id: Uuid::new_v4().to_string(),
+        family: version.family.clone(),
+        model_key: version.model_key.clone(),
+        model_version_id: version.id.clone(),
+        version: version.version,
+        decision: decision.to_string(),
+        reasons: eval.reasons,
+        report,
+        task_job_id,
+        checked_at: None,
+    };
+    insert_check(pool, &check).await?;
+    Ok(check)
+}
+
+/// 检查记录，按时间倒序。
+pub async fn list_checks(
+    pool: &sqlx::AnyPool,
+    family: Option<&str>,
+    model_key: Option<&str>,
+    decision: Option<&str>,
+    limit: i64,
+) -> Result<Vec<DriftCheck>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+          CAST(id AS TEXT) as id,
+          family,
+          model_key,
+          CAST(model_version_id AS TEXT) as model_version_id,
+          version,
+          decision,
+          reasons_json,
+          report_json,
+          CAST(task_job_id AS TEXT) as task_job_id,
+          CAST(checked_at AS TEXT) as checked_at
+        FROM ml_drift_check
+        WHERE ($1 = '' OR family = $1) AND ($2 = '' OR model_key = $2) AND ($3 = '' OR decision = $3)
+        ORDER BY checked_at DESC, model_key ASC
+        LIMIT $4
+        "#,
+    )
+    .bind(family.unwrap_or("").to_string())
+    .bind(model_key.unwrap_or("").to_string())
+    .bind(decision.unwrap_or("").to_string())
+    .bind(limit.max(1))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .iter()
+        .map(|r| {
+            let reasons: String = r.get("reasons_json");
+            let report: String = r.get("report_json");
+            DriftCheck {
+                id: r.get("id"),
+                family: r.get("family"),
+                model_key: r.get("model_key"),
+                model_version_id: r.get("model_version_id"),
+                version: r.get("version"),
+                decision: r.get("decision"),
+                reasons: serde_json::from_str(&reasons).unwrap_or_default(),
+                report: serde_json::from_str(&report).unwrap_or(Value::Null),
+                task_job_id: r.try_get::<Option<String>, _>("task_job_id").ok().flatten(),
+                checked_at: r
+                    .try_get::<Option<String>, _>("checked_at")
+                    .ok()
+                    .flatten()
+                    .map(|s| crate::dbfmt::datetime_to_rfc3339(&s)),
+            }
+        })
+        .collect())
+}
@@ -0,0 +1,200 @@
+//! 信号归因：单次预测中每个特征的贡献（log-odds 尺度），基准点为模型训练样本的特征均值，
+//! 即该板块的“平均样本”。
+//!
+//! 逻辑回归按 `w_i * (x_i - mean_i) / std_i` 精确分解；其他模型用固定种子的排列采样 Shapley 值
+//! （正反排列成对采样）。两种方法下 `logit(baseline_proba) + Σ贡献 = logit(proba)` 都严格成立。
+
+use serde::{Deserialize, Serialize};
+
+use super::gbdt::GbdtModel;
+use super::logreg::LogRegModel;
+use super::model::Classifier;
+
+/// 每条信号返回的主要驱动特征数。
+pub const TOP_DRIVERS: usize = 5;
+/// 排列采样的排列对数（每对为一条随机排列及其逆序）。
+const PERMUTATION_PAIRS: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeatureContribution {
+    pub feature: String,
+    pub value: f64,
+    /// 板块基准值（训练样本均值）。
+    pub baseline: f64,
+    /// 对 log-odds 的贡献；正值推高概率。
+    pub contribution: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignalExplanation {
+    /// `linear_exact` 或 `permutation_shapley`。
+    pub method: String,
+    /// 板块基准概率：所有特征取基准值时的预测。
+    pub baseline_proba: f64,
+    pub proba: f64,
+    /// 按贡献绝对值降序的前 [`TOP_DRIVERS`] 个特征。
+    pub drivers: Vec<FeatureContribution>,
+    /// 其余特征的贡献之和。
+    pub other_contribution: f64,
+}
+
+/// 快照上四个概率对应的归因，序列化后存于 `fund_signal_snapshot.explain_json`。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotExplanations {
+    #[serde(default)]
+    pub dip_buy_5t: Option<SignalExplanation>,
+    #[serde(default)]
+    pub dip_buy_20t: Option<SignalExplanation>,
+    #[serde(default)]
+    pub magic_rebound_5t: Option<SignalExplanation>,
+    #[serde(default)]
+    pub magic_rebound_20t: Option<SignalExplanation>,
+}
+
+impl SnapshotExplanations {
+    /// 缺失或无法解析时为空。
+    pub fn from_json(raw: Option<&str>) -> Self {
+        raw.and_then(|s| serde_json::from_str(s).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// 解释一次预测；特征数不匹配或模型缺少基准（旧版 GBDT）时为 None。
+pub fn explain(
+    model: &Classifier,
+    feature_names: &[String],
+    x: &[f64],
+) -> Option<SignalExplanation> {
+    if x.len() != feature_names.len() {
+        return None;
+    }
+    let (method, baseline, base_raw, contributions) = match model {
+        Classifier::LogReg(m) => {
+            let (base_raw, c) = linear_contributions(m, x)?;
+            ("linear_exact", m.mean.clone(), base_raw, c)
+        }
+        Classifier::Gbdt(m) => {
+            let baseline = m.feature_means.clone();
+            let (base_raw, c) = gbdt_contributions(m, x, &baseline)?;
+            ("permutation_shapley", baseline, base_raw, c)
+        }
+    };
+
+    let raw = base_raw + contributions.iter().sum::<f64>();
+    let mut all: Vec<FeatureContribution> = feature_names
+        .iter()
+        .zip(x.iter().zip(baseline.iter()))
+        .zip(contributions.iter())
+        .map(|((name, (&value, &base)), &c)| FeatureContribution {
+            feature: name.clone(),
+            value,
+            baseline: base,
+            contribution: c,
+        })
+        .collect();
+    all.sort_by(|a, b| {
+        b.contribution
+            .abs()
+            .partial_cmp(&a.contribution.abs())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let rest = all.split_off(all.len().min(TOP_DRIVERS));
+
+    Some(SignalExplanation {
+        method: method.to_string(),
+        baseline_proba: sigmoid(base_raw),
+        proba: sigmoid(raw),
+        drivers: all,
+        other_contribution: rest.iter().map(|c| c.contribution).sum(),
+    })
+}
+
+/// 逻辑回归的精确分解：(bias, 各特征的 `w_i * 标准化值`)；标准化方式与 `predict_proba` 一致。
+pub fn linear_contributions(m: &LogRegModel, x: &[f64]) -> Option<(f64, Vec<f64>)> {
+    if x.len() != m.weights.len() || x.len() != m.mean.len() || x.len() != m.std.len() {
+        return None;
+    }
+    let c = x
+        .iter()
+        .zip(m.mean.iter().zip(m.std.iter()).zip(m.weights.iter()))
+        .map(|(xi, ((mean_i, std_i), w_i))| {
+            let s = if std_i.abs() < 1e-12 {
+                xi - mean_i
+            } else {
+                (xi - mean_i) / std_i
+            };
+            w_i * s
+        })
+        .collect();
+    Some((m.bias, c))
+}
+
+/// GBDT 相对基准点的排列 Shapley 贡献：(基准原始得分, 各特征贡献)。
+pub fn gbdt_contributions(m: &GbdtModel, x: &[f64], baseline: &[f64]) -> Option<(f64, Vec<f64>)> {
+    if baseline.len() != m.n_features {
+        return None;
+    }
+    let base_raw = m.predict_raw(baseline)?;
+    let c = permutation_shapley(|v| m.predict_raw(v), x, baseline)?;
+    Some((base_raw, c))
+}
+
+/// 通用排列 Shapley：按排列依次把特征从基准值换成实际值，累计每一步的得分增量。
+/// 每条排列上增量之和都等于 `f(x) - f(baseline)`，所以平均后仍严格可加。
+pub fn permutation_shapley<F>(f: F, x: &[f64], baseline: &[f64]) -> Option<Vec<f64>>
+where
+    F: Fn(&[f64]) -> Option<f64>,
+{
+    let d = x.len();
+    if d != baseline.len() {
+        return None;
+    }
+    let base = f(baseline)?;
+    let mut phi = vec![0.0; d];
+    if d == 0 {
+        return Some(phi);
+    }
+
+    let mut seed = 0x9e37_79b9_7f4a_7c15_u64;
+    let mut order: Vec<usize> = (0..d).collect();
+    for _ in 0..PERMUTATION_PAIRS {
+        // Fisher–Yates（固定种子，结果可复现）。
+        for i in (1..d).rev() {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let j = ((seed >> 33) as usize) % (i + 1);
+            order.swap(i, j);
+        }
+        for forward in [true, false] {
+            let mut z = baseline.to_vec();
+            let mut prev = base;
+            let mut step = |j: usize| -> Option<()> {
+                z[j] = x[j];
+                let cur = f(&z)?;
+                phi[j] += cur - prev;
+                prev = cur;
+                Some(())
+            };
+            if forward {
+                order.iter().try_for_each(|&j| step(j))?;
+            } else {
+                order.iter().rev().try_for_each(|&j| step(j))?;
+            }
+        }
+    }
+    let n = (2 * PERMUTATION_PAIRS) as f64;
+    for p in &mut phi {
+        *p /= n;
+    }
+    Some(phi)
+}
+
+fn sigmoid(z: f64) -> f64 {
+    if z >= 0.0 {
+        1.0 / (1.0 + (-z).exp())
+    } else {
+        let ez = z.exp();
+        ez / (1.0 + ez)
+    }
+}
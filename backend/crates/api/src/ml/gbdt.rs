@@ -0,0 +1,421 @@
+//! 纯 Rust 梯度提升决策树（二阶梯度、直方图分裂、按增益逐叶生长、验证集早停）。
+
+use serde::{Deserialize, Serialize};
+
+/// 早停验证集的最少样本数，不足时不做早停（全部用于训练）。
+const MIN_VALIDATION_SAMPLES: usize = 5;
+/// 二阶梯度下限，避免叶子值发散。
+const MIN_HESSIAN: f64 = 1e-6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GbdtObjective {
+    /// 二分类（log-loss），输出经 sigmoid 为概率。
+    Logistic,
+    /// 回归（平方误差）。
+    SquaredError,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GbdtTrainConfig {
+    pub n_trees: usize,
+    pub learning_rate: f64,
+    pub max_depth: usize,
+    pub max_leaves: usize,
+    pub min_samples_leaf: usize,
+    /// 每个特征的直方图分箱数上限（2..=256）。
+    pub max_bins: usize,
+    pub l2: f64,
+    /// 末尾多少比例的样本作为早停验证集（调用方应按时间顺序传入样本）。
+    pub validation_fraction: f64,
+    /// 验证损失连续多少棵树未改善即停止；0 表示不早停。
+    pub early_stopping_rounds: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum GbdtNode {
+    /// `x[feature] <= threshold` 走左子树（NaN 也走左）。
+    Split {
+        feature: usize,
+        threshold: f64,
+        left: usize,
+        right: usize,
+    },
+    /// 叶子值已乘学习率。
+    Leaf { value: f64 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GbdtModel {
+    pub objective: GbdtObjective,
+    pub n_features: usize,
+    pub base_score: f64,
+    /// 每棵树为扁平节点数组，下标 0 为根。
+    pub trees: Vec<Vec<GbdtNode>>,
+    /// 训练样本的特征均值，作为归因基准点；旧模型没有该字段时为空。
+    #[serde(default)]
+    pub feature_means: Vec<f64>,
+}
+
+impl GbdtModel {
+    /// 原始得分（logistic 为 log-odds）。
+    pub fn predict_raw(&self, x: &[f64]) -> Option<f64> {
+        if x.len() != self.n_features {
+            return None;
+        }
+        Some(self.base_score + self.trees.iter().map(|t| tree_value(t, x)).sum::<f64>())
+    }
+
+    /// 正类概率；仅 logistic 目标有定义。
+    pub fn predict_proba(&self, x: &[f64]) -> Option<f64> {
+        match self.objective {
+            GbdtObjective::Logistic => self.predict_raw(x).map(sigmoid),
+            GbdtObjective::SquaredError => None,
+        }
+    }
+}
+
+fn tree_value(nodes: &[GbdtNode], x: &[f64]) -> f64 {
+    let mut i = 0;
+    loop {
+        match nodes.get(i) {
+            Some(GbdtNode::Split {
+                feature,
+                threshold,
+                left,
+                right,
+            }) => {
+                let v = x.get(*feature).copied().unwrap_or(f64::NAN);
+                i = if v > *threshold { *right } else { *left };
+            }
+            Some(GbdtNode::Leaf { value }) => return *value,
+            None => return 0.0,
+        }
+    }
+}
+
+fn sigmoid(z: f64) -> f64 {
+    if z >= 0.0 {
+        1.0 / (1.0 + (-z).exp())
+    } else {
+        let ez = z.exp();
+        ez / (1.0 + ez)
+    }
+}
+
+/// 分箱边界：不同取值不多时取相邻取值中点，否则取分位点；`v <= edges[b]` 即落在前 b+1 个箱。
+fn bin_edges(values: &mut [f64], max_bins: usize) -> Vec<f64> {
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let mut uniq: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    uniq.dedup();
+    if uniq.len() <= max_bins {
+        return uniq.windows(2).map(|w| (w[0] + w[1]) / 2.0).collect();
+    }
+    let finite: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    let n = finite.len();
+    let mut edges: Vec<f64> = (1..max_bins).map(|k| finite[k * n / max_bins]).collect();
+    edges.dedup();
+    if edges.last() == finite.last() {
+        edges.pop();
+    }
+    edges
+}
+
+fn bin_of(edges: &[f64], v: f64) -> usize {
+    if v.is_nan() {
+        return 0;
+    }
+    edges.partition_point(|e| *e < v)
+}
+
+struct SplitCandidate {
+    gain: f64,
+    feature: usize,
+    bin: usize,
+}
+
+struct Binned<'a> {
+    /// 列优先：bins[特征][样本]。
+    bins: Vec<Vec<u16>>,
+    edges: &'a [Vec<f64>],
+}
+
+fn leaf_weight(g: f64, h: f64, l2: f64) -> f64 {
+    -g / (h + l2)
+}
+
+fn score(g: f64, h: f64, l2: f64) -> f64 {
+    g * g / (h + l2)
+}
+
+fn best_split(
+    data: &Binned,
+    idx: &[usize],
+    grad: &[f64],
+    hess: &[f64],
+    cfg: &GbdtTrainConfig,
+) -> Option<SplitCandidate> {
+    let min_leaf = cfg.min_samples_leaf.max(1);
+    if idx.len() < 2 * min_leaf {
+        return None;
+    }
+    let (g_total, h_total) = idx
+        .iter()
+        .fold((0.0, 0.0), |(g, h), &i| (g + grad[i], h + hess[i]));
+    let parent = score(g_total, h_total, cfg.l2);
+
+    let mut best: Option<SplitCandidate> = None;
+    for (f, col) in data.bins.iter().enumerate() {
+        let n_bins = data.edges[f].len() + 1;
+        if n_bins < 2 {
+            continue;
+        }
+        let mut hist = vec![(0.0_f64, 0.0_f64, 0_usize); n_bins];
+        for &i in idx {
+            let h = &mut hist[col[i] as usize];
+            h.0 += grad[i];
+            h.1 += hess[i];
+            h.2 += 1;
+        }
+        let (mut gl, mut hl, mut nl) = (0.0, 0.0, 0);
+        for (b, &(g, h, n)) in hist.iter().enumerate().take(n_bins - 1) {
+            gl += g;
+            hl += h;
+            nl += n;
+            let nr = idx.len() - nl;
+            if nl < min_leaf || n == 0 {
+                continue;
+            }
+            if nr < min_leaf {
+                break;
+            }
+            let gain = score(gl, hl, cfg.l2) + score(g_total - gl, h_total - hl, cfg.l2) - parent;
+            if gain > 1e-12 && best.as_ref().is_none_or(|c| gain > c.gain) {
+                best = Some(SplitCandidate {
+                    gain,
+                    feature: f,
+                    bin: b,
+                });
+            }
+        }
+    }
+    best
+}
+
+/// 按增益逐叶生长一棵树；返回节点数组与每个训练样本落入的叶子值。
+fn grow_tree(
+    data: &Binned,
+    rows: &[usize],
+    grad: &[f64],
+    hess: &[f64],
+    cfg: &GbdtTrainConfig,
+) -> (Vec<GbdtNode>, Vec<(Vec<usize>, f64)>) {
+    struct Open {
+        node: usize,
+        idx: Vec<usize>,
+        depth: usize,
+        split: Option<SplitCandidate>,
+    }
+    let can_split = |depth: usize| depth < cfg.max_depth.max(1);
+    let mut nodes = vec![GbdtNode::Leaf { value: 0.0 }];
+    let root_split = best_split(data, rows, grad, hess, cfg);
+    let mut open = vec![Open {
+        node: 0,
+        idx: rows.to_vec(),
+        depth: 0,
+        split: root_split,
+    }];
+    let mut leaves = 1;
+
+    while leaves < cfg.max_leaves.max(2) {
+        let Some(pos) = open
+            .iter()
+            .enumerate()
+            .filter(|(_, o)| o.split.is_some() && can_split(o.depth))
+            .max_by(|a, b| {
+                let ga = a.1.split.as_ref().map_or(0.0, |s| s.gain);
+                let gb = b.1.split.as_ref().map_or(0.0, |s| s.gain);
+                ga.partial_cmp(&gb).unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .map(|(i, _)| i)
+        else {
+            break;
+        };
+        let leaf = open.swap_remove(pos);
+        let split = leaf.split.expect("filtered on split");
+        let col = &data.bins[split.feature];
+        let (left_idx, right_idx): (Vec<usize>, Vec<usize>) = leaf
+            .idx
+            .iter()
+            .partition(|&&i| (col[i] as usize) <= split.bin);
+
+        let (left, right) = (nodes.len(), nodes.len() + 1);
+        nodes.push(GbdtNode::Leaf { value: 0.0 });
+        nodes.push(GbdtNode::Leaf { value: 0.0 });
+        nodes[leaf.node] = GbdtNode::Split {
+            feature: split.feature,
+            threshold: data.edges[split.feature][split.bin],
+            left,
+            right,
+        };
+        leaves += 1;
+        for (node, idx) in [(left, left_idx), (right, right_idx)] {
+            let depth = leaf.depth + 1;
+            let split = if can_split(depth) {
+                best_split(data, &idx, grad, hess, cfg)
+            } else {
+                None
+            };
+            open.push(Open {
+                node,
+                idx,
+                depth,
+                split,
+            });
+        }
+    }
+
+    let lr = cfg.learning_rate.clamp(1e-6, 1.0);
+    let mut assigned = Vec::with_capacity(open.len());
+    for o in open {
+        let (g, h) = o
+            .idx
+            .iter()
+            .fold((0.0, 0.0), |(g, h), &i| (g + grad[i], h + hess[i]));
+        let value = lr * leaf_weight(g, h, cfg.l2);
+        nodes[o.node] = GbdtNode::Leaf { value };
+        assigned.push((o.idx, value));
+    }
+    (nodes, assigned)
+}
+
+fn gradients(objective: GbdtObjective, raw: f64, y: f64) -> (f64, f64) {
+    match objective {
+        GbdtObjective::Logistic => {
+            let p = sigmoid(raw);
+            let y = if y >= 0.5 { 1.0 } else { 0.0 };
+            (p - y, (p * (1.0 - p)).max(MIN_HESSIAN))
+        }
+        GbdtObjective::SquaredError => (raw - y, 1.0),
+    }
+}
+
+fn loss(objective: GbdtObjective, raw: f64, y: f64) -> f64 {
+    match objective {
+        GbdtObjective::Logistic => {
+            let p = sigmoid(raw).clamp(1e-15, 1.0 - 1e-15);
+            if y >= 0.5 { -p.ln() } else { -(1.0 - p).ln() }
+        }
+        GbdtObjective::SquaredError => (raw - y) * (raw - y),
+    }
+}
+
+pub fn train_gbdt(
+    x: &[Vec<f64>],
+    y: &[f64],
+    objective: GbdtObjective,
+    cfg: &GbdtTrainConfig,
+) -> Option<GbdtModel> {
+    if x.is_empty() || x.len() != y.len() {
+        return None;
+    }
+    let d = x[0].len();
+    if d == 0 || x.iter().any(|row| row.len() != d) {
+        return None;
+    }
+    let n = x.len();
+
+    let n_valid = if cfg.early_stopping_rounds > 0 {
+        ((n as f64) * cfg.validation_fraction.clamp(0.0, 0.5)).floor() as usize
+    } else {
+        0
+    };
+    let n_valid = if n_valid >= MIN_VALIDATION_SAMPLES && n - n_valid >= 2 {
+        n_valid
+    } else {
+        0
+    };
+    let n_train = n - n_valid;
+
+    let max_bins = cfg.max_bins.clamp(2, 256);
+    let edges: Vec<Vec<f64>> = (0..d)
+        .map(|j| {
+            let mut col: Vec<f64> = x[..n_train].iter().map(|r| r[j]).collect();
+            bin_edges(&mut col, max_bins)
+        })
+        .collect();
+    let data = Binned {
+        bins: (0..d)
+            .map(|j| {
+                x[..n_train]
+                    .iter()
+                    .map(|r| bin_of(&edges[j], r[j]) as u16)
+                    .collect()
+            })
+            .collect(),
+        edges: &edges,
+    };
+
+    let base_score = match objective {
+        GbdtObjective::Logistic => {
+            let pos = y[..n_train].iter().filter(|v| **v >= 0.5).count() as f64;
+            let p = (pos / n_train as f64).clamp(1e-6, 1.0 - 1e-6);
+            (p / (1.0 - p)).ln()
+        }
+        GbdtObjective::SquaredError => y[..n_train].iter().sum::<f64>() / n_train as f64,
+    };
+
+    let mut model = GbdtModel {
+        objective,
+        n_features: d,
+        base_score,
+        trees: Vec::new(),
+        feature_means: (0..d)
+            .map(|j| x.iter().map(|r| r[j]).sum::<f64>() / n as f64)
+            .collect(),
+    };
+    let rows: Vec<usize> = (0..n_train).collect();
+    let mut raw_train = vec![base_score; n_train];
+    let mut raw_valid = vec![base_score; n_valid];
+    let mut grad = vec![0.0; n_train];
+    let mut hess = vec![0.0; n_train];
+    let mut best_loss = f64::INFINITY;
+    let mut best_trees = 0;
+
+    for _ in 0..cfg.n_trees.max(1) {
+        for (i, (g, h)) in grad.iter_mut().zip(hess.iter_mut()).enumerate() {
+            (*g, *h) = gradients(objective, raw_train[i], y[i]);
+        }
+        let (tree, assigned) = grow_tree(&data, &rows, &grad, &hess, cfg);
+        for (idx, value) in assigned {
+            for i in idx {
+                raw_train[i] += value;
+            }
+        }
+        for (k, raw) in raw_valid.iter_mut().enumerate() {
+            *raw += tree_value(&tree, &x[n_train + k]);
+        }
+        model.trees.push(tree);
+
+        if n_valid == 0 {
+            continue;
+        }
+        let valid_loss = raw_valid
+            .iter()
+            .enumerate()
+            .map(|(k, &raw)| loss(objective, raw, y[n_train + k]))
+            .sum::<f64>()
+            / n_valid as f64;
+        if valid_loss < best_loss - 1e-12 {
+            best_loss = valid_loss;
+            best_trees = model.trees.len();
+        } else if model.trees.len() - best_trees >= cfg.early_stopping_rounds {
+            break;
+        }
+    }
+    if n_valid > 0 {
+        model.trees.truncate(best_trees);
+    }
+    Some(model)
+}
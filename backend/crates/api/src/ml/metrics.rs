@@ -0,0 +1,116 @@
+use serde::Serialize;
+
+/// 概率裁剪下限，避免 log(0)。
+const EPS: f64 = 1e-15;
+
+/// ROC AUC（Mann-Whitney U，平局取平均秩）。只有一类标签时无定义，返回 None。
+pub fn roc_auc(y: &[f64], p: &[f64]) -> Option<f64> {
+    if y.len() != p.len() || y.is_empty() {
+        return None;
+    }
+    let mut idx: Vec<usize> = (0..p.len()).collect();
+    idx.sort_by(|&a, &b| p[a].partial_cmp(&p[b]).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut rank_sum_pos = 0.0_f64;
+    let mut i = 0;
+    while i < idx.len() {
+        let mut j = i;
+        while j + 1 < idx.len() && p[idx[j + 1]] == p[idx[i]] {
+            j += 1;
+        }
+        // 秩从 1 开始；[i, j] 为一组平局。
+        let avg_rank = (i + j) as f64 / 2.0 + 1.0;
+        for &k in &idx[i..=j] {
+            if y[k] >= 0.5 {
+                rank_sum_pos += avg_rank;
+            }
+        }
+        i = j + 1;
+    }
+
+    let n_pos = y.iter().filter(|v| **v >= 0.5).count() as f64;
+    let n_neg = y.len() as f64 - n_pos;
+    if n_pos == 0.0 || n_neg == 0.0 {
+        return None;
+    }
+    Some((rank_sum_pos - n_pos * (n_pos + 1.0) / 2.0) / (n_pos * n_neg))
+}
+
+pub fn log_loss(y: &[f64], p: &[f64]) -> Option<f64> {
+    if y.len() != p.len() || y.is_empty() {
+        return None;
+    }
+    let sum: f64 = y
+        .iter()
+        .zip(p.iter())
+        .map(|(&yy, &pp)| {
+            let pp = pp.clamp(EPS, 1.0 - EPS);
+            if yy >= 0.5 {
+                -pp.ln()
+            } else {
+                -(1.0 - pp).ln()
+            }
+        })
+        .sum();
+    Some(sum / y.len() as f64)
+}
+
+pub fn brier_score(y: &[f64], p: &[f64]) -> Option<f64> {
+    if y.len() != p.len() || y.is_empty() {
+        return None;
+    }
+    let sum: f64 = y
+        .iter()
+        .zip(p.iter())
+        .map(|(&yy, &pp)| {
+            let yy = if yy >= 0.5 { 1.0 } else { 0.0 };
+            (pp - yy) * (pp - yy)
+        })
+        .sum();
+    Some(sum / y.len() as f64)
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CalibrationBucket {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: usize,
+    pub mean_predicted: f64,
+    pub observed_rate: f64,
+}
+
+/// 按预测概率等宽分桶（[0,1] 切成 `buckets` 段），空桶不输出。
+pub fn calibration_buckets(y: &[f64], p: &[f64], buckets: usize) -> Vec<CalibrationBucket> {
+    let buckets = buckets.max(1);
+    let mut count = vec![0_usize; buckets];
+    let mut sum_p = vec![0.0_f64; buckets];
+    let mut sum_y = vec![0.0_f64; buckets];
+    for (&yy, &pp) in y.iter().zip(p.iter()) {
+        let b = ((pp.clamp(0.0, 1.0) * buckets as f64) as usize).min(buckets - 1);
+        count[b] += 1;
+        sum_p[b] += pp;
+        sum_y[b] += if yy >= 0.5 { 1.0 } else { 0.0 };
+    }
+    (0..buckets)
+        .filter(|&b| count[b] > 0)
+        .map(|b| CalibrationBucket {
+            lower: b as f64 / buckets as f64,
+            upper: (b + 1) as f64 / buckets as f64,
+            count: count[b],
+            mean_predicted: sum_p[b] / count[b] as f64,
+            observed_rate: sum_y[b] / count[b] as f64,
+        })
+        .collect()
+}
+
+/// 预测概率最高的 k 个样本中的正例比例。
+pub fn precision_at_k(y: &[f64], p: &[f64], k: usize) -> Option<f64> {
+    if y.len() != p.len() || y.is_empty() || k == 0 {
+        return None;
+    }
+    let mut idx: Vec<usize> = (0..p.len()).collect();
+    idx.sort_by(|&a, &b| p[b].partial_cmp(&p[a]).unwrap_or(std::cmp::Ordering::Equal));
+    let k = k.min(idx.len());
+    let hits = idx[..k].iter().filter(|&&i| y[i] >= 0.5).count();
+    Some(hits as f64 / k as f64)
+}
@@ -1,5 +1,14 @@
+pub mod cluster;
 pub mod compute;
 pub mod dataset;
+pub mod drift;
+pub mod explain;
+pub mod gbdt;
 pub mod logreg;
+pub mod metrics;
+pub mod model;
+pub mod outcomes;
+pub mod regime;
+pub mod registry;
 pub mod signals;
 pub mod train;
@@ -0,0 +1,124 @@
+//! 模型类型选择与可序列化的模型包装（分类：逻辑回归 / GBDT）。
+
+use serde::{Deserialize, Serialize};
+
+use super::gbdt::{GbdtModel, GbdtObjective, GbdtTrainConfig, train_gbdt};
+use super::logreg::{LogRegModel, LogRegTrainConfig, train_logreg};
+use crate::config::ConfigStore;
+
+/// 模型类型：线性（分类为逻辑回归、回归为 OLS）或梯度提升树。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelKind {
+    Linear,
+    Gbdt,
+}
+
+impl ModelKind {
+    /// 接受 `linear`/`logreg`/`ols` 与 `gbdt`（大小写不敏感）。
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "linear" | "logreg" | "ols" => Some(ModelKind::Linear),
+            "gbdt" => Some(ModelKind::Gbdt),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModelKind::Linear => "linear",
+            ModelKind::Gbdt => "gbdt",
+        }
+    }
+
+    /// 读取配置项；缺失或非法时为线性模型。
+    pub fn from_config(config: &ConfigStore, key: &str) -> Self {
+        config
+            .get_string(key)
+            .and_then(|s| Self::parse(&s))
+            .unwrap_or(ModelKind::Linear)
+    }
+}
+
+/// 训练参数：与 [`ModelKind`] 一一对应。
+#[derive(Debug, Clone, Copy)]
+pub enum ClassifierTrainConfig {
+    LogReg(LogRegTrainConfig),
+    Gbdt(GbdtTrainConfig),
+}
+
+impl ClassifierTrainConfig {
+    pub fn kind(&self) -> ModelKind {
+        match self {
+            ClassifierTrainConfig::LogReg(_) => ModelKind::Linear,
+            ClassifierTrainConfig::Gbdt(_) => ModelKind::Gbdt,
+        }
+    }
+
+    pub fn train(&self, x: &[Vec<f64>], y: &[f64]) -> Option<Classifier> {
+        match self {
+            ClassifierTrainConfig::LogReg(cfg) => train_logreg(x, y, cfg).map(Classifier::LogReg),
+            ClassifierTrainConfig::Gbdt(cfg) => {
+                train_gbdt(x, y, GbdtObjective::Logistic, cfg).map(Classifier::Gbdt)
+            }
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            ClassifierTrainConfig::LogReg(c) => serde_json::json!({
+                "learning_rate": c.learning_rate,
+                "epochs": c.epochs,
+                "l2": c.l2,
+            }),
+            ClassifierTrainConfig::Gbdt(c) => serde_json::json!({
+                "n_trees": c.n_trees,
+                "learning_rate": c.learning_rate,
+                "max_depth": c.max_depth,
+                "max_leaves": c.max_leaves,
+                "min_samples_leaf": c.min_samples_leaf,
+                "max_bins": c.max_bins,
+                "l2": c.l2,
+                "validation_fraction": c.validation_fraction,
+                "early_stopping_rounds": c.early_stopping_rounds,
+            }),
+        }
+    }
+}
+
+/// 二分类模型；序列化为带 `model_type` 标签的 JSON（存于 `ml_sector_model.model_json`）。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "model_type", rename_all = "snake_case")]
+pub enum Classifier {
+    #[serde(rename = "logreg")]
+    LogReg(LogRegModel),
+    Gbdt(GbdtModel),
+}
+
+impl Classifier {
+    pub fn kind(&self) -> ModelKind {
+        match self {
+            Classifier::LogReg(_) => ModelKind::Linear,
+            Classifier::Gbdt(_) => ModelKind::Gbdt,
+        }
+    }
+
+    pub fn predict_proba(&self, x: &[f64]) -> Option<f64> {
+        match self {
+            Classifier::LogReg(m) => m.predict_proba(x),
+            Classifier::Gbdt(m) => m.predict_proba(x),
+        }
+    }
+
+    /// 兼容旧数据：不带 `model_type` 的 JSON 按逻辑回归解析。
+    pub fn from_json(s: &str) -> Result<Self, String> {
+        let value: serde_json::Value = serde_json::from_str(s).map_err(|e| e.to_string())?;
+        if value.get("model_type").is_some() {
+            serde_json::from_value(value).map_err(|e| e.to_string())
+        } else {
+            serde_json::from_value(value)
+                .map(Classifier::LogReg)
+                .map_err(|e| e.to_string())
+        }
+    }
+}
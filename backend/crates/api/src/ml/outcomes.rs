@@ -0,0 +1,338 @@
+//! 信号事后评估：信号日之后满 5T / 20T 时把实现结果回填到 `fund_signal_snapshot`，
+//! 并按板块、位置分桶与月份汇总线上命中率、校准与衰减。
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::{Value, json};
+use sqlx::Row;
+
+use super::metrics;
+use super::signals::realized_outcome;
+use super::train::MlTask;
+
+/// 回填的持有期（交易日），与快照里的 5T / 20T 概率对应。
+pub const HORIZONS: [usize; 2] = [5, 20];
+/// 概率不低于该值视为发出信号，命中率以此为分母。
+pub const SIGNAL_PROBA_THRESHOLD: f64 = 0.5;
+const CALIBRATION_BUCKETS: usize = 10;
+
+fn horizon_suffix(h: usize) -> &'static str {
+    if h <= 5 { "5t" } else { "20t" }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct ResolveStats {
+    pub scanned: usize,
+    pub resolved_5t: usize,
+    pub resolved_20t: usize,
+    /// 本批中仍未满 20T 的快照数（只回填了 5T）。
+    pub pending: usize,
+}
+
+struct PendingRow {
+    fund_code: String,
+    peer_code: String,
+    as_of_date: String,
+    has_5t: bool,
+}
+
+/// 回填尚未满 20T 结果的快照（最早的 `limit` 行）；快照没记录数据源时用 `default_source`。
+///
+/// 只选取现有净值已能覆盖下一个待回填持有期的快照：有不晚于信号日的净值点，且之后的净值点
+/// 不少于 5 个（5T 未回填）或 20 个（只差 20T）。缺净值或尚未满期的旧快照不会占满批次、
+/// 挡住后面可回填的行。
+pub async fn resolve_pending_outcomes(
+    pool: &sqlx::AnyPool,
+    default_source: &str,
+    limit: i64,
+) -> Result<ResolveStats, String> {
+    let sql = format!(
+        r#"
+        SELECT
+          s.fund_code,
+          s.peer_code,
+          CAST(s.as_of_date AS TEXT) as as_of_date,
+          s.source_name,
+          CASE WHEN s.outcome_5t_at IS NULL THEN 0 ELSE 1 END as has_5t
+        FROM fund_signal_snapshot s
+        WHERE s.outcome_20t_at IS NULL
+          AND EXISTS (
+            SELECT 1
+            FROM fund_nav_history h
+            JOIN fund f ON f.id = h.fund_id
+            WHERE f.fund_code = s.fund_code
+              AND h.source_name = COALESCE(NULLIF(TRIM(s.source_name), ''), $2)
+              AND SUBSTR(CAST(h.nav_date AS TEXT), 1, 10) <= SUBSTR(CAST(s.as_of_date AS TEXT), 1, 10)
+          )
+          AND (
+            SELECT COUNT(*)
+            FROM fund_nav_history h
+            JOIN fund f ON f.id = h.fund_id
+            WHERE f.fund_code = s.fund_code
+              AND h.source_name = COALESCE(NULLIF(TRIM(s.source_name), ''), $2)
+              AND SUBSTR(CAST(h.nav_date AS TEXT), 1, 10) > SUBSTR(CAST(s.as_of_date AS TEXT), 1, 10)
+          ) >= CASE WHEN s.outcome_5t_at IS NULL THEN {short} ELSE {long} END
+        ORDER BY s.as_of_date ASC, s.fund_code ASC
+        LIMIT $1
+        "#,
+        short = HORIZONS[0],
+        long = HORIZONS[1],
+    );
+    let rows = sqlx::query(&sql)
+        .bind(limit.max(1))
+        .bind(default_source)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut by_series: BTreeMap<(String, String), Vec<PendingRow>> = BTreeMap::new();
+    for r in &rows {
+        let source = r
+            .try_get::<Option<String>, _>("source_name")
+            .ok()
+            .flatten()
+            .filter(|s| !s.trim().is_empty())
+            .unwrap_or_else(|| default_source.to_string());
+        let fund_code: String = r.get("fund_code");
+        let as_of_date: String = r.get("as_of_date");
+        by_series
+            .entry((fund_code.clone(), source))
+            .or_default()
+            .push(PendingRow {
+                fund_code,
+                peer_code: r.get("peer_code"),
+                as_of_date: as_of_date.get(0..10).unwrap_or(&as_of_date).to_string(),
+                has_5t: r.get::<i64, _>("has_5t") != 0,
+            });
+    }
+
+    let mut stats = ResolveStats {
+        scanned: rows.len(),
+        ..ResolveStats::default()
+    };
+    for ((fund_code, source), pending) in by_series {
+        let navs = load_navs(pool, &fund_code, &source).await?;
+        for row in pending {
+            // 信号日取不晚于 as_of_date 的最后一个净值点。
+            let idx = navs.partition_point(|(d, _)| d.as_str() <= row.as_of_date.as_str());
+            let Some(idx) = idx.checked_sub(1) else {
+                stats.pending += 1;
+                continue;
+            };
+            let mut done_20t = false;
+            for h in HORIZONS {
+                if h <= 5 && row.has_5t {
+                    continue;
+                }
+                if idx + h >= navs.len() {
+                    continue;
+                }
+                let window: Vec<f64> = navs[idx..=idx + h].iter().map(|(_, v)| *v).collect();
+                let Some(outcome) = realized_outcome(&window, h) else {
+                    continue;
+                };
+                let s = horizon_suffix(h);
+                let sql = format!(
+                    r#"
+                    UPDATE fund_signal_snapshot SET
+                      realized_return_{s} = $1,
+                      realized_max_return_{s} = $2,
+                      dip_buy_outcome_{s} = $3,
+                      magic_rebound_outcome_{s} = $4,
+                      outcome_{s}_at = CURRENT_TIMESTAMP,
+                      updated_at = CURRENT_TIMESTAMP
+                    WHERE fund_code = $5 AND peer_code = $6 AND SUBSTR(CAST(as_of_date AS TEXT), 1, 10) = $7
+                    "#
+                );
+                sqlx::query(&sql)
+                    .bind(outcome.ret)
+                    .bind(outcome.max_ret)
+                    .bind(outcome.dip_buy_success as i64)
+                    .bind(outcome.magic_rebound as i64)
+                    .bind(&row.fund_code)
+                    .bind(&row.peer_code)
+                    .bind(&row.as_of_date)
+                    .execute(pool)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if h <= 5 {
+                    stats.resolved_5t += 1;
+                } else {
+                    stats.resolved_20t += 1;
+                    done_20t = true;
+                }
+            }
+            if !done_20t {
+                stats.pending += 1;
+            }
+        }
+    }
+    Ok(stats)
+}
+
+async fn load_navs(
+    pool: &sqlx::AnyPool,
+    fund_code: &str,
+    source_name: &str,
+) -> Result<Vec<(String, f64)>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT CAST(h.nav_date AS TEXT) as nav_date, CAST(h.unit_nav AS TEXT) as unit_nav
+        FROM fund_nav_history h
+        JOIN fund f ON f.id = h.fund_id
+        WHERE f.fund_code = $1 AND h.source_name = $2
+        ORDER BY h.nav_date ASC
+        "#,
+    )
+    .bind(fund_code)
+    .bind(source_name)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut out = Vec::with_capacity(rows.len());
+    for r in rows {
+        let d: String = r.get("nav_date");
+        let s: String = r.get("unit_nav");
+        if let Ok(v) = s.trim().parse::<f64>() {
+            out.push((d.get(0..10).unwrap_or(&d).to_string(), v));
+        }
+    }
+    Ok(out)
+}
+
+/// 汇总条件：按板块与信号日期范围（含端点，YYYY-MM-DD）过滤。
+#[derive(Debug, Clone, Default)]
+pub struct OutcomeFilter {
+    pub peer_code: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+}
+
+struct Resolved {
+    peer_code: String,
+    bucket: String,
+    month: String,
+    proba: f64,
+    outcome: f64,
+}
+
+/// 各（任务, 持有期）的线上表现：整体、按板块、按位置分桶、按月（衰减）。
+pub async fn outcome_report(pool: &sqlx::AnyPool, filter: &OutcomeFilter) -> Result<Value, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+          peer_code,
+          CAST(as_of_date AS TEXT) as as_of_date,
+          position_bucket,
+          dip_buy_proba_5t, dip_buy_proba_20t,
+          magic_rebound_proba_5t, magic_rebound_proba_20t,
+          dip_buy_outcome_5t, dip_buy_outcome_20t,
+          magic_rebound_outcome_5t, magic_rebound_outcome_20t
+        FROM fund_signal_snapshot
+        WHERE outcome_5t_at IS NOT NULL
+          AND ($1 = '' OR peer_code = $1)
+          AND ($2 = '' OR CAST(as_of_date AS TEXT) >= $2)
+          AND ($3 = '' OR SUBSTR(CAST(as_of_date AS TEXT), 1, 10) <= $3)
+        ORDER BY as_of_date ASC
+        "#,
+    )
+    .bind(filter.peer_code.clone().unwrap_or_default())
+    .bind(filter.since.clone().unwrap_or_default())
+    .bind(filter.until.clone().unwrap_or_default())
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut items = Vec::new();
+    for task in [MlTask::DipBuy, MlTask::MagicRebound] {
+        for h in HORIZONS {
+            let s = horizon_suffix(h);
+            let proba_col = format!("{}_proba_{s}", task.as_str());
+            let outcome_col = format!("{}_outcome_{s}", task.as_str());
+            let resolved: Vec<Resolved> = rows
+                .iter()
+                .filter_map(|r| {
+                    let proba = r.try_get::<Option<f64>, _>(proba_col.as_str()).ok()??;
+                    let outcome = r.try_get::<Option<i64>, _>(outcome_col.as_str()).ok()??;
+                    let date: String = r.get("as_of_date");
+                    Some(Resolved {
+                        peer_code: r.get("peer_code"),
+                        bucket: r
+                            .try_get::<Option<String>, _>("position_bucket")
+                            .ok()
+                            .flatten()
+                            .unwrap_or_else(|| "unknown".to_string()),
+                        month: date.get(0..7).unwrap_or(&date).to_string(),
+                        proba,
+                        outcome: outcome as f64,
+                    })
+                })
+                .collect();
+
+            let group = |key: fn(&Resolved) -> &str, name: &str, calibration: bool| {
+                let mut groups: BTreeMap<&str, Vec<&Resolved>> = BTreeMap::new();
+                for r in &resolved {
+                    groups.entry(key(r)).or_default().push(r);
+                }
+                groups
+                    .into_iter()
+                    .map(|(k, rs)| {
+                        let mut v = summarize(&rs, calibration);
+                        v[name] = json!(k);
+                        v
+                    })
+                    .collect::<Vec<_>>()
+            };
+            let all: Vec<&Resolved> = resolved.iter().collect();
+            items.push(json!({
+                "task": task.as_str(),
+                "horizon_days": h,
+                "overall": summarize(&all, true),
+                "by_peer": group(|r| r.peer_code.as_str(), "peer_code", true),
+                "by_bucket": group(|r| r.bucket.as_str(), "position_bucket", true),
+                "decay": group(|r| r.month.as_str(), "month", false),
+            }));
+        }
+    }
+
+    Ok(json!({
+        "signal_threshold": SIGNAL_PROBA_THRESHOLD,
+        "items": items,
+    }))
+}
+
+/// 样本数、基准率、平均概率、命中率（概率达阈值的信号中实现为正的比例）、Brier / log-loss / AUC。
+fn summarize(rows: &[&Resolved], calibration: bool) -> Value {
+    let y: Vec<f64> = rows.iter().map(|r| r.outcome).collect();
+    let p: Vec<f64> = rows.iter().map(|r| r.proba).collect();
+    let n = y.len();
+    let mean = |v: &[f64]| {
+        if v.is_empty() {
+            None
+        } else {
+            Some(v.iter().sum::<f64>() / v.len() as f64)
+        }
+    };
+    let hits: Vec<f64> = rows
+        .iter()
+        .filter(|r| r.proba >= SIGNAL_PROBA_THRESHOLD)
+        .map(|r| r.outcome)
+        .collect();
+
+    let mut out = json!({
+        "n": n,
+        "base_rate": mean(&y),
+        "mean_proba": mean(&p),
+        "signals": hits.len(),
+        "hit_rate": mean(&hits),
+        "brier": metrics::brier_score(&y, &p),
+        "log_loss": metrics::log_loss(&y, &p),
+        "auc": metrics::roc_auc(&y, &p),
+    });
+    if calibration {
+        out["calibration"] = json!(metrics::calibration_buckets(&y, &p, CALIBRATION_BUCKETS));
+    }
+    out
+}
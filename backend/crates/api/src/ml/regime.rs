@@ -0,0 +1,584 @@
+//! 指数市场状态识别：三状态高斯隐马尔可夫模型（对角协方差），观测为 20 日对数收益（趋势）
+//! 与 20 日日收益标准差（波动）。
+//!
+//! 每次运行用全部历史重新拟合（Baum–Welch），再做前向滤波：某日的概率只依赖截至当日的观测。
+//! 已写入的日期默认不再改写，所以日常增量运行得到的历史概率不含未来信息；首次回补（或 `full`）
+//! 时参数来自全部历史。状态按趋势均值排序后标记为熊 / 震荡 / 牛。
+
+use chrono::NaiveDate;
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+
+/// 趋势与波动的滚动窗口（交易日）。
+pub const REGIME_WINDOW: usize = 20;
+/// 拟合所需的最少观测数（约半年）。
+pub const MIN_OBSERVATIONS: usize = 120;
+const MAX_EM_ITERS: usize = 200;
+const MIN_VARIANCE: f64 = 1e-3;
+const K: usize = 3;
+
+/// 前向算法每步的 (缩放系数, 缩放后的发射密度)。
+type Scaled = (f64, [f64; K]);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Regime {
+    Bull,
+    Bear,
+    Sideways,
+}
+
+impl Regime {
+    /// 概率数组的顺序。
+    pub const ALL: [Regime; K] = [Regime::Bull, Regime::Bear, Regime::Sideways];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Regime::Bull => "bull",
+            Regime::Bear => "bear",
+            Regime::Sideways => "sideways",
+        }
+    }
+}
+
+/// 已拟合的模型；状态顺序与 [`Regime::ALL`] 一致，均值/方差为标准化后的观测。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegimeModel {
+    pub feature_mean: [f64; 2],
+    pub feature_std: [f64; 2],
+    pub means: [[f64; 2]; K],
+    pub vars: [[f64; 2]; K],
+    pub transition: [[f64; K]; K],
+    pub initial: [f64; K],
+    pub log_likelihood: f64,
+    pub sample_count: usize,
+}
+
+/// 每个交易日的观测 (日期, [趋势, 波动])，从第 [`REGIME_WINDOW`] 个收盘价开始。
+pub fn observations(closes: &[(String, f64)]) -> Vec<(String, [f64; 2])> {
+    let logret: Vec<f64> = closes
+        .windows(2)
+        .map(|w| {
+            if w[0].1 > 0.0 && w[1].1 > 0.0 {
+                (w[1].1 / w[0].1).ln()
+            } else {
+                0.0
+            }
+        })
+        .collect();
+    let mut out = Vec::new();
+    for t in REGIME_WINDOW..closes.len() {
+        let window = &logret[t - REGIME_WINDOW..t];
+        let trend: f64 = window.iter().sum();
+        let mean = trend / REGIME_WINDOW as f64;
+        let var = window.iter().map(|r| (r - mean) * (r - mean)).sum::<f64>()
+            / (REGIME_WINDOW - 1) as f64;
+        if trend.is_finite() && var.is_finite() {
+            out.push((closes[t].0.clone(), [trend, var.sqrt()]));
+        }
+    }
+    out
+}
+
+impl RegimeModel {
+    /// Baum–Welch 拟合；观测不足 [`MIN_OBSERVATIONS`] 时为 None。初值按趋势三分位给出，结果可复现。
+    pub fn fit(obs: &[[f64; 2]]) -> Option<Self> {
+        let n = obs.len();
+        if n < MIN_OBSERVATIONS {
+            return None;
+        }
+        let mut feature_mean = [0.0; 2];
+        let mut feature_std = [0.0; 2];
+        for f in 0..2 {
+            let m = obs.iter().map(|o| o[f]).sum::<f64>() / n as f64;
+            let v = obs.iter().map(|o| (o[f] - m).powi(2)).sum::<f64>() / n as f64;
+            feature_mean[f] = m;
+            feature_std[f] = if v.sqrt() < 1e-12 { 1.0 } else { v.sqrt() };
+        }
+        let z: Vec<[f64; 2]> = obs
+            .iter()
+            .map(|o| {
+                [
+                    (o[0] - feature_mean[0]) / feature_std[0],
+                    (o[1] - feature_mean[1]) / feature_std[1],
+                ]
+            })
+            .collect();
+
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by(|&a, &b| {
+            z[a][0]
+                .partial_cmp(&z[b][0])
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        let mut means = [[0.0; 2]; K];
+        let mut vars = [[1.0; 2]; K];
+        for k in 0..K {
+            let part = &order[k * n / K..(k + 1) * n / K];
+            for f in 0..2 {
+                let m = part.iter().map(|&i| z[i][f]).sum::<f64>() / part.len() as f64;
+                let v =
+                    part.iter().map(|&i| (z[i][f] - m).powi(2)).sum::<f64>() / part.len() as f64;
+                means[k][f] = m;
+                vars[k][f] = v.max(MIN_VARIANCE);
+            }
+        }
+        let mut model = RegimeModel {
+            feature_mean,
+            feature_std,
+            means,
+            vars,
+            transition: [[0.05; K]; K],
+            initial: [1.0 / K as f64; K],
+            log_likelihood: f64::NEG_INFINITY,
+            sample_count: n,
+        };
+        for k in 0..K {
+            model.transition[k][k] = 0.9;
+        }
+
+        for _ in 0..MAX_EM_ITERS {
+            let (alpha, scale, ll) = model.forward(&z);
+            let improved = ll - model.log_likelihood;
+            model.log_likelihood = ll;
+            model.em_step(&z, &alpha, &scale);
+            if improved.abs() < 1e-6 * n as f64 {
+                break;
+            }
+        }
+        model.log_likelihood = model.forward(&z).2;
+        model.canonicalize();
+        Some(model)
+    }
+
+    fn standardize(&self, o: &[f64; 2]) -> [f64; 2] {
+        [
+            (o[0] - self.feature_mean[0]) / self.feature_std[0],
+            (o[1] - self.feature_mean[1]) / self.feature_std[1],
+        ]
+    }
+
+    /// 各状态的发射密度（减去当步最大对数密度以防下溢），以及该最大值。
+    fn emission(&self, z: &[f64; 2]) -> ([f64; K], f64) {
+        let logp: [f64; K] = std::array::from_fn(|k| {
+            (0..2)
+                .map(|f| {
+                    let v = self.vars[k][f];
+                    -0.5 * ((z[f] - self.means[k][f]).powi(2) / v
+                        + v.ln()
+                        + std::f64::consts::TAU.ln())
+                })
+                .sum()
+        });
+        let max = logp.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let mut b = [0.0; K];
+        for k in 0..K {
+            b[k] = (logp[k] - max).exp();
+        }
+        (b, max)
+    }
+
+    /// 带缩放的前向算法：(归一化 alpha, 每步缩放系数与发射, 对数似然)。
+    fn forward(&self, z: &[[f64; 2]]) -> (Vec<[f64; K]>, Vec<Scaled>, f64) {
+        let mut alpha = Vec::with_capacity(z.len());
+        let mut scale = Vec::with_capacity(z.len());
+        let mut ll = 0.0;
+        let mut prev: Option<[f64; K]> = None;
+        for o in z {
+            let (b, max) = self.emission(o);
+            let mut a = [0.0; K];
+            for j in 0..K {
+                let prior = match prev {
+                    None => self.initial[j],
+                    Some(p) => (0..K).map(|i| p[i] * self.transition[i][j]).sum(),
+                };
+                a[j] = prior * b[j];
+            }
+            let c: f64 = a.iter().sum::<f64>().max(1e-300);
+            for v in &mut a {
+                *v /= c;
+            }
+            ll += c.ln() + max;
+            alpha.push(a);
+            scale.push((c, b));
+            prev = Some(a);
+        }
+        (alpha, scale, ll)
+    }
+
+    fn em_step(&mut self, z: &[[f64; 2]], alpha: &[[f64; K]], scale: &[Scaled]) {
+        let n = z.len();
+        let mut beta = vec![[1.0; K]; n];
+        for t in (0..n - 1).rev() {
+            let (c, b) = scale[t + 1];
+            for i in 0..K {
+                beta[t][i] = (0..K)
+                    .map(|j| self.transition[i][j] * b[j] * beta[t + 1][j])
+                    .sum::<f64>()
+                    / c;
+            }
+        }
+
+        let mut gamma_sum = [0.0; K];
+        let mut gamma_sum_head = [0.0; K];
+        let mut xi_sum = [[0.0; K]; K];
+        let mut mean_acc = [[0.0; 2]; K];
+        let mut gammas = Vec::with_capacity(n);
+        for t in 0..n {
+            let mut g = [0.0; K];
+            for k in 0..K {
+                g[k] = alpha[t][k] * beta[t][k];
+            }
+            let s: f64 = g.iter().sum::<f64>().max(1e-300);
+            for k in 0..K {
+                g[k] /= s;
+                gamma_sum[k] += g[k];
+                if t + 1 < n {
+                    gamma_sum_head[k] += g[k];
+                }
+                for f in 0..2 {
+                    mean_acc[k][f] += g[k] * z[t][f];
+                }
+            }
+            gammas.push(g);
+
+            if t + 1 < n {
+                let (c, b) = scale[t + 1];
+                for i in 0..K {
+                    for j in 0..K {
+                        xi_sum[i][j] +=
+                            alpha[t][i] * self.transition[i][j] * b[j] * beta[t + 1][j] / c;
+                    }
+                }
+            }
+        }
+
+        self.initial = gammas[0];
+        for i in 0..K {
+            let row: f64 = xi_sum[i].iter().sum();
+            if gamma_sum_head[i] > 1e-12 && row > 1e-12 {
+                for (p, x) in self.transition[i].iter_mut().zip(xi_sum[i].iter()) {
+                    *p = x / row;
+                }
+            }
+        }
+        for k in 0..K {
+            if gamma_sum[k] < 1e-9 {
+                continue;
+            }
+            for (m, acc) in self.means[k].iter_mut().zip(mean_acc[k].iter()) {
+                *m = acc / gamma_sum[k];
+            }
+            for f in 0..2 {
+                let v = gammas
+                    .iter()
+                    .zip(z.iter())
+                    .map(|(g, o)| g[k] * (o[f] - self.means[k][f]).powi(2))
+                    .sum::<f64>()
+                    / gamma_sum[k];
+                self.vars[k][f] = v.max(MIN_VARIANCE);
+            }
+        }
+    }
+
+    /// 按趋势均值把状态重排为 [牛, 熊, 震荡]。
+    fn canonicalize(&mut self) {
+        let mut by_trend: Vec<usize> = (0..K).collect();
+        by_trend.sort_by(|&a, &b| {
+            self.means[a][0]
+                .partial_cmp(&self.means[b][0])
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        let perm = [by_trend[2], by_trend[0], by_trend[1]];
+        let old = self.clone();
+        for (new_i, &old_i) in perm.iter().enumerate() {
+            self.means[new_i] = old.means[old_i];
+            self.vars[new_i] = old.vars[old_i];
+            self.initial[new_i] = old.initial[old_i];
+            for (new_j, &old_j) in perm.iter().enumerate() {
+                self.transition[new_i][new_j] = old.transition[old_i][old_j];
+            }
+        }
+    }
+
+    /// 前向滤波概率，顺序同 [`Regime::ALL`]。
+    pub fn filter(&self, obs: &[[f64; 2]]) -> Vec<[f64; K]> {
+        let z: Vec<[f64; 2]> = obs.iter().map(|o| self.standardize(o)).collect();
+        self.forward(&z).0
+    }
+
+    /// 各状态在原始单位下的 (20 日趋势, 20 日波动) 均值。
+    pub fn state_summary(&self) -> Vec<serde_json::Value> {
+        Regime::ALL
+            .iter()
+            .enumerate()
+            .map(|(k, r)| {
+                serde_json::json!({
+                    "regime": r.as_str(),
+                    "trend_20d": self.means[k][0] * self.feature_std[0] + self.feature_mean[0],
+                    "vol_20d": self.means[k][1] * self.feature_std[1] + self.feature_mean[1],
+                    "persistence": self.transition[k][k],
+                })
+            })
+            .collect()
+    }
+}
+
+/// 概率最大的状态。
+pub fn most_likely(p: &[f64; K]) -> Regime {
+    let mut best = 0;
+    for k in 1..K {
+        if p[k] > p[best] {
+            best = k;
+        }
+    }
+    Regime::ALL[best]
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RegimePoint {
+    pub date: String,
+    pub regime: String,
+    pub p_bull: f64,
+    pub p_bear: f64,
+    pub p_sideways: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RegimeRunStats {
+    pub observations: usize,
+    pub written: usize,
+    pub last_date: Option<String>,
+}
+
+fn is_pg(pool: &sqlx::AnyPool) -> bool {
+    crate::db::database_kind_from_pool(pool) == crate::db::DatabaseKind::Postgres
+}
+
+async fn load_closes(
+    pool: &sqlx::AnyPool,
+    index_code: &str,
+    source_name: &str,
+) -> Result<Vec<(String, f64)>, String> {
+    let start = NaiveDate::from_ymd_opt(1990, 1, 1).expect("valid date");
+    let end = chrono::Utc::now().date_naive();
+    let series =
+        crate::index_series::load_index_close_series(pool, index_code, source_name, start, end)
+            .await?;
+    Ok(series
+        .into_iter()
+        .filter_map(|(d, c)| Some((d.format("%Y-%m-%d").to_string(), c.to_f64()?)))
+        .collect())
+}
+
+/// 重新拟合并写入尚未存储的日期（`full` 时清空后全部重写），同时保存模型参数。
+pub async fn compute_and_store(
+    pool: &sqlx::AnyPool,
+    index_code: &str,
+    source_name: &str,
+    full: bool,
+) -> Result<RegimeRunStats, String> {
+    let closes = load_closes(pool, index_code, source_name).await?;
+    let obs = observations(&closes);
+    let values: Vec<[f64; 2]> = obs.iter().map(|(_, o)| *o).collect();
+    let Some(model) = RegimeModel::fit(&values) else {
+        return Err(format!(
+            "not enough index history: {} observations (need {MIN_OBSERVATIONS})",
+            obs.len()
+        ));
+    };
+    let probas = model.filter(&values);
+
+    if full {
+        sqlx::query("DELETE FROM index_regime WHERE index_code = $1 AND source_name = $2")
+            .bind(index_code)
+            .bind(source_name)
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let last_stored: Option<String> = sqlx::query(
+        r#"
+        SELECT MAX(CAST(trade_date AS TEXT)) as last_date
+        FROM index_regime
+        WHERE index_code = $1 AND source_name = $2
+        "#,
+    )
+    .bind(index_code)
+    .bind(source_name)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .try_get::<Option<String>, _>("last_date")
+    .ok()
+    .flatten();
+
+    let sql = if is_pg(pool) {
+        r#"
+        INSERT INTO index_regime (index_code, source_name, trade_date, regime, p_bull, p_bear, p_sideways, computed_at)
+        VALUES ($1, $2, ($3)::date, $4, $5, $6, $7, CURRENT_TIMESTAMP)
+        ON CONFLICT (index_code, source_name, trade_date) DO NOTHING
+        "#
+    } else {
+        r#"
+        INSERT INTO index_regime (index_code, source_name, trade_date, regime, p_bull, p_bear, p_sideways, computed_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, CURRENT_TIMESTAMP)
+        ON CONFLICT (index_code, source_name, trade_date) DO NOTHING
+        "#
+    };
+    let mut stats = RegimeRunStats {
+        observations: obs.len(),
+        ..RegimeRunStats::default()
+    };
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    for ((date, _), p) in obs.iter().zip(probas.iter()) {
+        if last_stored
+            .as_deref()
+            .is_some_and(|last| date.as_str() <= last.get(0..10).unwrap_or(last))
+        {
+            continue;
+        }
+        sqlx::query(sql)
+            .bind(index_code)
+            .bind(source_name)
+            .bind(date)
+            .bind(most_likely(p).as_str())
+            .bind(p[0])
+            .bind(p[1])
+            .bind(p[2])
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        stats.written += 1;
+    }
+
+    let model_json = serde_json::to_string(&model).map_err(|e| e.to_string())?;
+    let sql = if is_pg(pool) {
+        r#"
+        INSERT INTO index_regime_model (index_code, source_name, model_json, sample_count, log_likelihood, fitted_at)
+        VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP)
+        ON CONFLICT (index_code, source_name) DO UPDATE SET
+          model_json = EXCLUDED.model_json,
+          sample_count = EXCLUDED.sample_count,
+          log_likelihood = EXCLUDED.log_likelihood,
+          fitted_at = CURRENT_TIMESTAMP
+        "#
+    } else {
+        r#"
+        INSERT INTO index_regime_model (index_code, source_name, model_json, sample_count, log_likelihood, fitted_at)
+        VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP)
+        ON CONFLICT (index_code, source_name) DO UPDATE SET
+          model_json = excluded.model_json,
+          sample_count = excluded.sample_count,
+          log_likelihood = excluded.log_likelihood,
+          fitted_at = CURRENT_TIMESTAMP
+        "#
+    };
+    sqlx::query(sql)
+        .bind(index_code)
+        .bind(source_name)
+        .bind(model_json)
+        .bind(model.sample_count as i64)
+        .bind(model.log_likelihood)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    stats.last_date = obs.last().map(|(d, _)| d.clone());
+    Ok(stats)
+}
+
+/// 已存储的每日概率（日期含端点，YYYY-MM-DD）。
+pub async fn load_points(
+    pool: &sqlx::AnyPool,
+    index_code: &str,
+    source_name: &str,
+    start_date: &str,
+    end_date: &str,
+) -> Result<Vec<RegimePoint>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT CAST(trade_date AS TEXT) as trade_date, regime, p_bull, p_bear, p_sideways
+        FROM index_regime
+        WHERE index_code = $1 AND source_name = $2
+          AND CAST(trade_date AS TEXT) >= $3 AND SUBSTR(CAST(trade_date AS TEXT), 1, 10) <= $4
+        ORDER BY trade_date ASC
+        "#,
+    )
+    .bind(index_code)
+    .bind(source_name)
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| {
+            let d: String = r.get("trade_date");
+            RegimePoint {
+                date: d.get(0..10).unwrap_or(&d).to_string(),
+                regime: r.get("regime"),
+                p_bull: r.get("p_bull"),
+                p_bear: r.get("p_bear"),
+                p_sideways: r.get("p_sideways"),
+            }
+        })
+        .collect())
+}
+
+/// 截至 `end_date` 的全部每日概率 (日期, [牛, 熊, 震荡])，供特征库与模拟盘按日查找。
+pub async fn load_probas(
+    pool: &sqlx::AnyPool,
+    index_code: &str,
+    source_name: &str,
+    end_date: &str,
+) -> Result<Vec<(String, [f64; K])>, String> {
+    Ok(load_points(pool, index_code, source_name, "", end_date)
+        .await?
+        .into_iter()
+        .map(|p| (p.date, [p.p_bull, p.p_bear, p.p_sideways]))
+        .collect())
+}
+
+/// 当日或之前最近一日的概率（不超过 `max_gap_days` 个自然日）。
+pub fn proba_on_or_before(
+    series: &[(String, [f64; K])],
+    date: &str,
+    max_gap_days: i64,
+) -> Option<[f64; K]> {
+    let pos = series.partition_point(|(d, _)| d.as_str() <= date);
+    let (d, p) = series.get(pos.checked_sub(1)?)?;
+    let gap = NaiveDate::parse_from_str(date.get(0..10).unwrap_or(date), "%Y-%m-%d").ok()?
+        - NaiveDate::parse_from_str(d, "%Y-%m-%d").ok()?;
+    (gap.num_days() <= max_gap_days).then_some(*p)
+}
+
+/// 最近一次拟合的模型与拟合时间。
+pub async fn load_model(
+    pool: &sqlx::AnyPool,
+    index_code: &str,
+    source_name: &str,
+) -> Result<Option<(RegimeModel, String)>, String> {
+    let row = sqlx::query(
+        r#"
+        SELECT model_json, CAST(fitted_at AS TEXT) as fitted_at
+        FROM index_regime_model
+        WHERE index_code = $1 AND source_name = $2
+        "#,
+    )
+    .bind(index_code)
+    .bind(source_name)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    let Some(row) = row else {
+        return Ok(None);
+    };
+    let model: RegimeModel =
+        serde_json::from_str(&row.get::<String, _>("model_json")).map_err(|e| e.to_string())?;
+    Ok(Some((model, row.get("fitted_at"))))
+}
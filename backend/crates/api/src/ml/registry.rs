@@ -0,0 +1,537 @@
+//! 模型版本库：训练结果登记为不可变版本，经影子评估后晋升为冠军。
+//!
+//! 每次训练都会插入一行 `ml_model_version`（带训练区间、特征集哈希、指标）。
+//! `ml_model_active` 指向当前冠军；服务表 `ml_sector_model` / `forecast_model`
+//! 始终是冠军版本的物化副本，推理侧读法不变。
+//!
+//! 影子评估：挑战者与冠军在“冠军训练截止日之后”的样本上比较同一指标
+//! （冠军从未见过这些样本；挑战者用剔除这些样本后的数据另行拟合一个评估模型）。
+//! 挑战者不明显差于冠军才晋升，否则标记为 rejected；样本不足时保持 challenger，待人工处理或下次训练。
+
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::db::DatabaseKind;
+use crate::ml::metrics;
+use crate::ml::model::ModelKind;
+
+pub const FAMILY_SECTOR: &str = "sector";
+pub const FAMILY_FORECAST: &str = "forecast";
+
+/// 影子评估所需的最少样本数。
+pub const MIN_SHADOW_SAMPLES: usize = 20;
+/// 挑战者指标允许比冠军差的相对幅度：同配置在新数据上重训的结果应能替换旧模型。
+pub const PROMOTE_TOLERANCE: f64 = 0.02;
+/// 登记版本遇到版本号冲突时的最多尝试次数。
+const REGISTER_ATTEMPTS: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VersionStatus {
+    Challenger,
+    Champion,
+    Retired,
+    Rejected,
+}
+
+impl VersionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VersionStatus::Challenger => "challenger",
+            VersionStatus::Champion => "champion",
+            VersionStatus::Retired => "retired",
+            VersionStatus::Rejected => "rejected",
+        }
+    }
+}
+
+pub fn is_known_family(family: &str) -> bool {
+    family == FAMILY_SECTOR || family == FAMILY_FORECAST
+}
+
+pub fn sector_key(peer_code: &str, task: &str, horizon_days: i64) -> String {
+    format!("{peer_code}/{task}/{horizon_days}")
+}
+
+pub fn forecast_key(model_name: &str, source: &str, horizon: i64, lag_k: i64) -> String {
+    format!("{model_name}/{source}/{horizon}/{lag_k}")
+}
+
+/// 特征集哈希：特征集版本 + 有序特征名（sha256 十六进制）。
+pub fn feature_set_hash(feature_names: &[String]) -> String {
+    let mut h = Sha256::new();
+    h.update(crate::features::FEATURE_SET_VERSION.to_string().as_bytes());
+    for name in feature_names {
+        h.update(b"\n");
+        h.update(name.as_bytes());
+    }
+    h.finalize().iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// 待登记的新版本。`payload` 需包含写回服务表所需的全部字段。
+#[derive(Debug, Clone)]
+pub struct NewModelVersion {
+    pub family: &'static str,
+    pub model_key: String,
+    pub model_type: ModelKind,
+    pub feature_names: Vec<String>,
+    pub payload: Value,
+    pub metrics: Value,
+    pub train_start_date: Option<String>,
+    pub train_end_date: Option<String>,
+    pub sample_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelVersion {
+    pub id: String,
+    pub family: String,
+    pub model_key: String,
+    pub version: i64,
+    pub model_type: String,
+    pub feature_names: Vec<String>,
+    pub feature_set_hash: String,
+    pub metrics: Value,
+    pub train_start_date: Option<String>,
+    pub train_end_date: Option<String>,
+    pub sample_count: i64,
+    pub status: String,
+    pub shadow: Value,
+    pub created_at: Option<String>,
+    pub promoted_at: Option<String>,
+    #[serde(skip)]
+    pub payload: Value,
+}
+
+/// 影子评估结论。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShadowDecision {
+    Promote,
+    Hold,
+    Reject,
+}
+
+/// 影子评估记录（写入 `shadow_json`）；指标越小越好（log_loss / rmse）。
+#[derive(Debug, Clone, Serialize)]
+pub struct ShadowReport {
+    pub metric: String,
+    pub champion_version: Option<i64>,
+    /// 评估样本起点：冠军训练截止日之后。
+    pub after_date: Option<String>,
+    pub samples: usize,
+    pub champion: Option<f64>,
+    pub challenger: Option<f64>,
+    pub decision: ShadowDecision,
+    pub reason: String,
+}
+
+impl ShadowReport {
+    /// 没有冠军（或只有未登记版本的旧模型）：直接晋升。
+    pub fn no_champion(metric: &str) -> Self {
+        ShadowReport {
+            metric: metric.to_string(),
+            champion_version: None,
+            after_date: None,
+            samples: 0,
+            champion: None,
+            challenger: None,
+            decision: ShadowDecision::Promote,
+            reason: "no_champion".to_string(),
+        }
+    }
+
+    /// 冠军特征集与当前不同，无法在同一批样本上打分：直接晋升。
+    pub fn feature_set_changed(metric: &str, champion: &ModelVersion) -> Self {
+        ShadowReport {
+            champion_version: Some(champion.version),
+            reason: "feature_set_changed".to_string(),
+            ..Self::no_champion(metric)
+        }
+    }
+
+    /// 由两组预测得出结论：挑战者指标不超过冠军的 `1 + PROMOTE_TOLERANCE` 倍即晋升。
+    pub fn compare(
+        metric: &str,
+        champion: &ModelVersion,
+        y: &[f64],
+        champion_pred: &[f64],
+        challenger_pred: &[f64],
+    ) -> Self {
+        let score = |p: &[f64]| match metric {
+            "log_loss" => metrics::log_loss(y, p),
+            _ => rmse(y, p),
+        };
+        let (c, n) = if y.len() < MIN_SHADOW_SAMPLES {
+            (None, None)
+        } else {
+            (score(champion_pred), score(challenger_pred))
+        };
+        let (decision, reason) = match (c, n) {
+            (Some(c), Some(n)) if n <= c * (1.0 + PROMOTE_TOLERANCE) => {
+                (ShadowDecision::Promote, "not_worse_than_champion")
+            }
+            (Some(_), Some(_)) => (ShadowDecision::Reject, "worse_than_champion"),
+            _ => (ShadowDecision::Hold, "insufficient_shadow_samples"),
+        };
+        ShadowReport {
+            metric: metric.to_string(),
+            champion_version: Some(champion.version),
+            after_date: champion.train_end_date.clone(),
+            samples: y.len(),
+            champion: c,
+            challenger: n,
+            decision,
+            reason: reason.to_string(),
+        }
+    }
+}
+
+pub fn rmse(y: &[f64], p: &[f64]) -> Option<f64> {
+    if y.len() != p.len() || y.is_empty() {
+        return None;
+    }
+    let sse: f64 = y.iter().zip(p).map(|(a, b)| (a - b) * (a - b)).sum();
+    Some((sse / y.len() as f64).sqrt())
+}
+
+fn is_pg(pool: &sqlx::AnyPool) -> bool {
+    crate::db::database_kind_from_pool(pool) == DatabaseKind::Postgres
+}
+
+const SELECT_VERSION: &str = r#"
+    SELECT
+      CAST(v.id AS TEXT) as id,
+      v.family,
+      v.model_key,
+      v.version,
+      v.model_type,
+      v.feature_names_json,
+      v.feature_set_hash,
+      v.payload_json,
+      v.metrics_json,
+      CAST(v.train_start_date AS TEXT) as train_start_date,
+      CAST(v.train_end_date AS TEXT) as train_end_date,
+      v.sample_count,
+      v.status,
+      v.shadow_json,
+      CAST(v.created_at AS TEXT) as created_at,
+      CAST(v.promoted_at AS TEXT) as promoted_at
+    FROM ml_model_version v
+"#;
+
+fn version_from_row(row: &sqlx::any::AnyRow) -> ModelVersion {
+    let json_col = |name: &str| -> Value {
+        row.try_get::<Option<String>, _>(name)
+            .ok()
+            .flatten()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or(Value::Null)
+    };
+    let text_col = |name: &str| row.try_get::<Option<String>, _>(name).ok().flatten();
+    ModelVersion {
+        id: row.get("id"),
+        family: row.get("family"),
+        model_key: row.get("model_key"),
+        version: row.get("version"),
+        model_type: row.get("model_type"),
+        feature_names: serde_json::from_value(json_col("feature_names_json")).unwrap_or_default(),
+        feature_set_hash: row.get("feature_set_hash"),
+        metrics: json_col("metrics_json"),
+        train_start_date: text_col("train_start_date"),
+        train_end_date: text_col("train_end_date"),
+        sample_count: row.get("sample_count"),
+        status: row.get("status"),
+        shadow: json_col("shadow_json"),
+        created_at: text_col("created_at").map(|s| crate::dbfmt::datetime_to_rfc3339(&s)),
+        promoted_at: text_col("promoted_at").map(|s| crate::dbfmt::datetime_to_rfc3339(&s)),
+        payload: json_col("payload_json"),
+    }
+}
+
+pub async fn get_version(pool: &sqlx::AnyPool, id: &str) -> Result<Option<ModelVersion>, String> {
+    if Uuid::parse_str(id).is_err() {
+        return Ok(None);
+    }
+    let sql = if is_pg(pool) {
+        format!("{SELECT_VERSION} WHERE v.id = ($1)::uuid")
+    } else {
+        format!("{SELECT_VERSION} WHERE v.id = $1")
+    };
+    let row = sqlx::query(&sql)
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(row.as_ref().map(version_from_row))
+}
+
+/// 当前冠军（`ml_model_active` 指向的版本）。
+pub async fn active_version(
+    pool: &sqlx::AnyPool,
+    family: &str,
+    model_key: &str,
+) -> Result<Option<ModelVersion>, String> {
+    let sql = format!(
+        "{SELECT_VERSION} JOIN ml_model_active a ON a.version_id = v.id
+         WHERE a.family = $1 AND a.model_key = $2"
+    );
+    let row = sqlx::query(&sql)
+        .bind(family)
+        .bind(model_key)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(row.as_ref().map(version_from_row))
+}
+
+/// 全部冠军版本（`family` 为空时不过滤），按 family、key 排序。
+pub async fn active_versions(
+    pool: &sqlx::AnyPool,
+    family: Option<&str>,
+) -> Result<Vec<ModelVersion>, String> {
+    let sql = format!(
+        "{SELECT_VERSION} JOIN ml_model_active a ON a.version_id = v.id
+         WHERE ($1 = '' OR a.family = $1)
+         ORDER BY a.family ASC, a.model_key ASC"
+    );
+    let rows = sqlx::query(&sql)
+        .bind(family.unwrap_or("").to_string())
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(rows.iter().map(version_from_row).collect())
+}
+
+/// 最近一次登记版本的时间（无论是否晋升），用于判断是否需要重训。
+pub async fn latest_created_at(
+    pool: &sqlx::AnyPool,
+    family: &str,
+    model_key: &str,
+) -> Result<Option<String>, String> {
+    let row = sqlx::query(
+        r#"
+        SELECT CAST(MAX(created_at) AS TEXT) as created_at
+        FROM ml_model_version
+        WHERE family = $1 AND model_key = $2
+        "#,
+    )
+    .bind(family)
+    .bind(model_key)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(row
+        .try_get::<Option<String>, _>("created_at")
+        .ok()
+        .flatten())
+}
+
+/// 版本列表（不含模型本体），按 key 与版本号倒序。
+pub async fn list_versions(
+    pool: &sqlx::AnyPool,
+    family: Option<&str>,
+    model_key: Option<&str>,
+) -> Result<Vec<ModelVersion>, String> {
+    let sql = format!(
+        "{SELECT_VERSION} WHERE ($1 = '' OR v.family = $1) AND ($2 = '' OR v.model_key = $2)
+         ORDER BY v.family ASC, v.model_key ASC, v.version DESC"
+    );
+    let rows = sqlx::query(&sql)
+        .bind(family.unwrap_or("").to_string())
+        .bind(model_key.unwrap_or("").to_string())
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(rows.iter().map(version_from_row).collect())
+}
+
+/// 登记新版本并按影子评估结论处理：Promote 立即晋升，Reject 标记 rejected，Hold 保持 challenger。
+pub async fn register(
+    pool: &sqlx::AnyPool,
+    new: NewModelVersion,
+    shadow: &ShadowReport,
+) -> Result<ModelVersion, String> {
+    let id = Uuid::new_v4().to_string();
+    let status = match shadow.decision {
+        ShadowDecision::Reject => VersionStatus::Rejected,
+        ShadowDecision::Promote | ShadowDecision::Hold => VersionStatus::Challenger,
+    };
+    let feature_names_json =
+        serde_json::to_string(&new.feature_names).map_err(|e| e.to_string())?;
+    let payload_json = serde_json::to_string(&new.payload).map_err(|e| e.to_string())?;
+    let metrics_json = serde_json::to_string(&new.metrics).map_err(|e| e.to_string())?;
+    let shadow_json = serde_json::to_string(shadow).map_err(|e| e.to_string())?;
+
+    let sql = if is_pg(pool) {
+        r#"
+        INSERT INTO ml_model_version (
+          id, family, model_key, version, model_type, feature_names_json, feature_set_hash,
+          payload_json, metrics_json, train_start_date, train_end_date, sample_count,
+          status, shadow_json, created_at
+        )
+        SELECT ($1)::uuid, $2, $3, COALESCE(MAX(version), 0) + 1, $4, $5, $6,
+               $7, $8, ($9)::date, ($10)::date, $11, $12, $13, CURRENT_TIMESTAMP
+        FROM ml_model_version WHERE family = $2 AND model_key = $3
+        "#
+    } else {
+        r#"
+        INSERT INTO ml_model_version (
+          id, family, model_key, version, model_type, feature_names_json, feature_set_hash,
+          payload_json, metrics_json, train_start_date, train_end_date, sample_count,
+          status, shadow_json, created_at
+        )
+        SELECT $1, $2, $3, COALESCE(MAX(version), 0) + 1, $4, $5, $6,
+               $7, $8, $9, $10, $11, $12, $13, CURRENT_TIMESTAMP
+        FROM ml_model_version WHERE family = $2 AND model_key = $3
+        "#
+    };
+    // 版本号取 MAX+1：并发登记同一 key 时可能撞上唯一约束，重新取号即可。
+    let hash = feature_set_hash(&new.feature_names);
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let res = sqlx::query(sql)
+            .bind(&id)
+            .bind(new.family)
+            .bind(&new.model_key)
+            .bind(new.model_type.as_str())
+            .bind(&feature_names_json)
+            .bind(&hash)
+            .bind(&payload_json)
+            .bind(&metrics_json)
+            .bind(new.train_start_date.clone())
+            .bind(new.train_end_date.clone())
+            .bind(new.sample_count)
+            .bind(status.as_str())
+            .bind(&shadow_json)
+            .execute(pool)
+            .await;
+        match res {
+            Ok(_) => break,
+            Err(sqlx::Error::Database(e))
+                if e.is_unique_violation() && attempt < REGISTER_ATTEMPTS => {}
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+
+    if shadow.decision == ShadowDecision::Promote {
+        return promote(pool, &id).await;
+    }
+    get_version(pool, &id)
+        .await?
+        .ok_or_else(|| "model version vanished after insert".to_string())
+}
+
+/// 将指定版本设为冠军：原冠军转为 retired，更新生效指针并写回服务表。
+pub async fn promote(pool: &sqlx::AnyPool, id: &str) -> Result<ModelVersion, String> {
+    let Some(version) = get_version(pool, id).await? else {
+        return Err(format!("model version not found: {id}"));
+    };
+    let pg = is_pg(pool);
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    sqlx::query(
+        r#"
+        UPDATE ml_model_version SET status = 'retired'
+        WHERE family = $1 AND model_key = $2 AND status = 'champion'
+        "#,
+    )
+    .bind(&version.family)
+    .bind(&version.model_key)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let sql = if pg {
+        "UPDATE ml_model_version SET status = 'champion', promoted_at = CURRENT_TIMESTAMP WHERE id = ($1)::uuid"
+    } else {
+        "UPDATE ml_model_version SET status = 'champion', promoted_at = CURRENT_TIMESTAMP WHERE id = $1"
+    };
+    sqlx::query(sql)
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let sql = if pg {
+        r#"
+        INSERT INTO ml_model_active (family, model_key, version_id, updated_at)
+        VALUES ($1, $2, ($3)::uuid, CURRENT_TIMESTAMP)
+        ON CONFLICT (family, model_key) DO UPDATE SET
+          version_id = excluded.version_id,
+          updated_at = CURRENT_TIMESTAMP
+        "#
+    } else {
+        r#"
+        INSERT INTO ml_model_active (family, model_key, version_id, updated_at)
+        VALUES ($1, $2, $3, CURRENT_TIMESTAMP)
+        ON CONFLICT (family, model_key) DO UPDATE SET
+          version_id = excluded.version_id,
+          updated_at = CURRENT_TIMESTAMP
+        "#
+    };
+    sqlx::query(sql)
+        .bind(&version.family)
+        .bind(&version.model_key)
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // 服务表与生效指针一并提交：写回失败时整个晋升回滚，不会出现指针与服务表不一致。
+    materialize(&mut tx, pg, &version).await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    get_version(pool, id)
+        .await?
+        .ok_or_else(|| format!("model version not found: {id}"))
+}
+
+/// 回滚到上一个曾经生效的版本：版本号低于当前冠军的 retired 版本中最高者。
+///
+/// 按版本号而非 `promoted_at` 选取：回滚本身会刷新晋升时间，连续回滚仍能逐级向前。
+pub async fn rollback(
+    pool: &sqlx::AnyPool,
+    family: &str,
+    model_key: &str,
+) -> Result<Option<ModelVersion>, String> {
+    let below = active_version(pool, family, model_key)
+        .await?
+        .map(|v| v.version)
+        .unwrap_or(i64::MAX);
+    let sql = format!(
+        "{SELECT_VERSION} WHERE v.family = $1 AND v.model_key = $2
+           AND v.status = 'retired' AND v.promoted_at IS NOT NULL
+           AND v.version < $3
+         ORDER BY v.version DESC
+         LIMIT 1"
+    );
+    let row = sqlx::query(&sql)
+        .bind(family)
+        .bind(model_key)
+        .bind(below)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    let Some(target) = row.as_ref().map(version_from_row) else {
+        return Ok(None);
+    };
+    promote(pool, &target.id).await.map(Some)
+}
+
+/// 把版本写回对应的服务表。
+async fn materialize(
+    tx: &mut sqlx::Transaction<'_, sqlx::Any>,
+    pg: bool,
+    version: &ModelVersion,
+) -> Result<(), String> {
+    match version.family.as_str() {
+        FAMILY_SECTOR => crate::ml::train::store_serving_sector_model(tx, version).await,
+        FAMILY_FORECAST => crate::forecast::model::store_serving_model(tx, pg, version).await,
+        other => Err(format!("unknown model family: {other}")),
+    }
+}
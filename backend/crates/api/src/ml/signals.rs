@@ -28,3 +28,41 @@ pub fn bucket_for_percentile(percentile_0_100: f64) -> PositionBucket {
 
 pub const MAGIC_REBOUND_THRESHOLD_5T: f64 = 0.03;
 pub const MAGIC_REBOUND_THRESHOLD_20T: f64 = 0.08;
+
+/// 神奇反弹阈值：5T 及以内用 5T 阈值，否则用 20T（训练标签与事后评估同一口径）。
+pub fn magic_rebound_threshold(horizon_days: usize) -> f64 {
+    if horizon_days <= 5 {
+        MAGIC_REBOUND_THRESHOLD_5T
+    } else {
+        MAGIC_REBOUND_THRESHOLD_20T
+    }
+}
+
+/// 信号日之后 h 个交易日的实现结果。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RealizedOutcome {
+    /// 第 h 日相对信号日的收益。
+    pub ret: f64,
+    /// 1..=h 日内最高净值相对信号日的收益。
+    pub max_ret: f64,
+    pub dip_buy_success: bool,
+    pub magic_rebound: bool,
+}
+
+/// `navs[0]` 为信号日净值，`navs[1..=h]` 为其后 h 个交易日；不足 h 日或净值非正返回 None。
+pub fn realized_outcome(navs: &[f64], horizon_days: usize) -> Option<RealizedOutcome> {
+    let h = horizon_days.max(1);
+    let nav_now = *navs.first()?;
+    if nav_now <= 0.0 || navs.len() <= h {
+        return None;
+    }
+    let ret = navs[h] / nav_now - 1.0;
+    let max_future = navs[1..=h].iter().copied().fold(f64::MIN, f64::max);
+    let max_ret = max_future / nav_now - 1.0;
+    Some(RealizedOutcome {
+        ret,
+        max_ret,
+        dip_buy_success: ret > 0.0,
+        magic_rebound: max_ret >= magic_rebound_threshold(h),
+    })
+}
@@ -2,10 +2,149 @@ use serde_json::json;
 use sqlx::Row;
 
 use super::dataset::{DatasetConfig, build_trigger_samples_for_peer};
-use super::logreg::{LogRegModel, LogRegTrainConfig, train_logreg};
+use super::gbdt::GbdtTrainConfig;
+use super::logreg::LogRegTrainConfig;
+use super::metrics;
+use super::model::{Classifier, ClassifierTrainConfig, ModelKind};
+use super::registry::{self, ModelVersion, NewModelVersion, ShadowReport};
 
 pub const PEER_CODE_ALL: &str = "__all__";
 
+/// 留出集占样本日期数的比例（取时间上最后的部分）。
+pub const HOLDOUT_FRACTION: f64 = 0.2;
+/// 训练/留出集的最小样本数，不足时只记录原因、不出指标。
+pub const MIN_TRAIN_SAMPLES: usize = 10;
+pub const MIN_TEST_SAMPLES: usize = 5;
+const CALIBRATION_BUCKETS: usize = 10;
+const PRECISION_TOP_FRACTIONS: [f64; 3] = [0.05, 0.1, 0.2];
+
+/// 按时间切分的样本下标。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeSplit {
+    pub train: Vec<usize>,
+    pub test: Vec<usize>,
+}
+
+/// 时间顺序切分：按样本中出现的不同日期计数，最后 `test_fraction` 的日期作为留出集，
+/// 其前 `embargo` 个不同样本日期的样本丢弃（标签要向后看 horizon 天，避免与留出集重叠泄漏）。
+/// 注意 embargo 按样本日期而非交易日历计数：样本日期稀疏时，实际隔离的交易日会多于 `embargo`。
+pub fn time_holdout_split(
+    dates: &[String],
+    test_fraction: f64,
+    embargo: usize,
+) -> Option<TimeSplit> {
+    let mut distinct: Vec<&str> = dates.iter().map(|d| d.as_str()).collect();
+    distinct.sort_unstable();
+    distinct.dedup();
+    let n = distinct.len();
+    let test_dates = ((n as f64) * test_fraction.clamp(0.0, 1.0)).ceil() as usize;
+    if test_dates == 0 || n < test_dates + embargo + 1 {
+        return None;
+    }
+    let test_start = distinct[n - test_dates];
+    let train_end = distinct[n - test_dates - embargo - 1];
+
+    let mut split = TimeSplit {
+        train: Vec::new(),
+        test: Vec::new(),
+    };
+    for (i, d) in dates.iter().enumerate() {
+        if d.as_str() >= test_start {
+            split.test.push(i);
+        } else if d.as_str() <= train_end {
+            split.train.push(i);
+        }
+    }
+    Some(split)
+}
+
+/// 在留出集上评估：AUC、log-loss、Brier、校准分桶、precision@top-k，
+/// 并给出以训练集正例率作常数预测的基线，便于判断是否优于基准率。
+pub fn evaluate_holdout(
+    x: &[Vec<f64>],
+    y: &[f64],
+    dates: &[String],
+    embargo: usize,
+    train_cfg: &ClassifierTrainConfig,
+) -> serde_json::Value {
+    let insufficient = |reason: &str| {
+        json!({
+            "method": "time_holdout",
+            "status": "insufficient_data",
+            "reason": reason,
+            "embargo_days": embargo,
+        })
+    };
+    let Some(split) = time_holdout_split(dates, HOLDOUT_FRACTION, embargo) else {
+        return insufficient("not enough distinct dates for holdout + embargo");
+    };
+    if split.train.len() < MIN_TRAIN_SAMPLES || split.test.len() < MIN_TEST_SAMPLES {
+        return insufficient("not enough samples in train or test split");
+    }
+
+    let pick_x = |idx: &[usize]| idx.iter().map(|&i| x[i].clone()).collect::<Vec<_>>();
+    let pick_y = |idx: &[usize]| idx.iter().map(|&i| y[i]).collect::<Vec<_>>();
+    let (train_x, train_y) = (pick_x(&split.train), pick_y(&split.train));
+    let test_y = pick_y(&split.test);
+    let Some(model) = train_cfg.train(&train_x, &train_y) else {
+        return insufficient("model training failed");
+    };
+    let test_p: Vec<f64> = split
+        .test
+        .iter()
+        .map(|&i| model.predict_proba(&x[i]).unwrap_or(0.5))
+        .collect();
+
+    let base_rate = train_y.iter().filter(|v| **v >= 0.5).count() as f64 / train_y.len() as f64;
+    let base_p = vec![base_rate; test_y.len()];
+    let test_positive_rate =
+        test_y.iter().filter(|v| **v >= 0.5).count() as f64 / test_y.len() as f64;
+
+    let brier = metrics::brier_score(&test_y, &test_p);
+    let base_brier = metrics::brier_score(&test_y, &base_p);
+    let brier_skill = match (brier, base_brier) {
+        (Some(b), Some(r)) if r > 0.0 => Some(1.0 - b / r),
+        _ => None,
+    };
+    let precision_at_k: Vec<serde_json::Value> = PRECISION_TOP_FRACTIONS
+        .iter()
+        .map(|&f| {
+            let k = ((test_y.len() as f64) * f).ceil().max(1.0) as usize;
+            json!({
+                "fraction": f,
+                "k": k,
+                "precision": metrics::precision_at_k(&test_y, &test_p, k),
+            })
+        })
+        .collect();
+    let date_of = |i: usize| dates[i].clone();
+
+    json!({
+        "method": "time_holdout",
+        "status": "ok",
+        "holdout_fraction": HOLDOUT_FRACTION,
+        "embargo_days": embargo,
+        "train_size": split.train.len(),
+        "test_size": split.test.len(),
+        "train_end_date": split.train.iter().map(|&i| date_of(i)).max(),
+        "test_start_date": split.test.iter().map(|&i| date_of(i)).min(),
+        "test_end_date": split.test.iter().map(|&i| date_of(i)).max(),
+        "train_positive_rate": base_rate,
+        "test_positive_rate": test_positive_rate,
+        "auc": metrics::roc_auc(&test_y, &test_p),
+        "log_loss": metrics::log_loss(&test_y, &test_p),
+        "brier": brier,
+        "brier_skill": brier_skill,
+        "baseline": {
+            "auc": 0.5,
+            "log_loss": metrics::log_loss(&test_y, &base_p),
+            "brier": base_brier,
+        },
+        "calibration": metrics::calibration_buckets(&test_y, &test_p, CALIBRATION_BUCKETS),
+        "precision_at_k": precision_at_k,
+    })
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MlTask {
     DipBuy,
@@ -19,6 +158,36 @@ impl MlTask {
             MlTask::MagicRebound => "magic_rebound",
         }
     }
+
+    /// 选择该任务模型类型的配置项（`linear` / `gbdt`）。
+    pub fn model_type_config_key(&self) -> &'static str {
+        match self {
+            MlTask::DipBuy => "ml_model_type_dip_buy",
+            MlTask::MagicRebound => "ml_model_type_magic_rebound",
+        }
+    }
+}
+
+/// 各模型类型的训练参数。
+pub fn classifier_train_config(kind: ModelKind) -> ClassifierTrainConfig {
+    match kind {
+        ModelKind::Linear => ClassifierTrainConfig::LogReg(LogRegTrainConfig {
+            learning_rate: 0.5,
+            epochs: 600,
+            l2: 0.1,
+        }),
+        ModelKind::Gbdt => ClassifierTrainConfig::Gbdt(GbdtTrainConfig {
+            n_trees: 200,
+            learning_rate: 0.1,
+            max_depth: 3,
+            max_leaves: 8,
+            min_samples_leaf: 20,
+            max_bins: 64,
+            l2: 1.0,
+            validation_fraction: 0.2,
+            early_stopping_rounds: 20,
+        }),
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -27,37 +196,64 @@ pub struct SectorModelRecord {
     pub task: MlTask,
     pub horizon_days: i64,
     pub feature_names: Vec<String>,
-    pub model: LogRegModel,
+    pub model: Classifier,
     pub metrics: serde_json::Value,
 }
 
+/// 按配置（[`MlTask::model_type_config_key`]）选择模型类型训练并保存。
 pub async fn train_and_store_sector_model(
     pool: &sqlx::AnyPool,
     peer_code: &str,
     source_name: &str,
     task: MlTask,
     cfg: &DatasetConfig,
-) -> Result<(), String> {
-    let samples = if peer_code.trim() == PEER_CODE_ALL {
+) -> Result<Option<ModelVersion>, String> {
+    let kind = ModelKind::from_config(
+        &crate::config::ConfigStore::load(),
+        task.model_type_config_key(),
+    );
+    train_and_store_sector_model_with_kind(pool, peer_code, source_name, task, cfg, kind).await
+}
+
+/// 训练并登记为新版本（见 [`registry`]）：无冠军时直接生效，否则经影子评估决定是否晋升。
+/// 无样本时返回 `None`。
+pub async fn train_and_store_sector_model_with_kind(
+    pool: &sqlx::AnyPool,
+    peer_code: &str,
+    source_name: &str,
+    task: MlTask,
+    cfg: &DatasetConfig,
+    kind: ModelKind,
+) -> Result<Option<ModelVersion>, String> {
+    let mut samples = if peer_code.trim() == PEER_CODE_ALL {
         super::dataset::build_trigger_samples_for_all_funds(pool, source_name, cfg).await?
     } else {
         build_trigger_samples_for_peer(pool, peer_code, source_name, cfg).await?
     };
     if samples.is_empty() {
-        return Ok(());
+        return Ok(None);
     }
+    // 按时间排序：GBDT 早停取末尾样本作验证集。
+    samples.sort_by(|a, b| a.as_of_date.cmp(&b.as_of_date));
 
-    let feature_names = vec![
-        "dd_mag".to_string(),
-        "ret5".to_string(),
-        "ret20".to_string(),
-        "vol20".to_string(),
-    ];
+    let feature_names: Vec<String> =
+        crate::features::ml_signal_features(&crate::config::ConfigStore::load())
+            .into_iter()
+            .map(|s| s.to_string())
+            .collect();
+    if samples
+        .iter()
+        .any(|s| s.features.len() != feature_names.len())
+    {
+        return Err("feature set changed while building samples".to_string());
+    }
 
     let mut x: Vec<Vec<f64>> = Vec::with_capacity(samples.len());
     let mut y: Vec<f64> = Vec::with_capacity(samples.len());
+    let mut dates: Vec<String> = Vec::with_capacity(samples.len());
     for s in &samples {
         x.push(s.features.clone());
+        dates.push(s.as_of_date.clone());
         let label = match task {
             MlTask::DipBuy => s.dip_buy_success,
             MlTask::MagicRebound => s.magic_rebound,
@@ -65,12 +261,12 @@ pub async fn train_and_store_sector_model(
         y.push(if label { 1.0 } else { 0.0 });
     }
 
-    let train_cfg = LogRegTrainConfig {
-        learning_rate: 0.5,
-        epochs: 600,
-        l2: 0.1,
-    };
-    let model = train_logreg(&x, &y, &train_cfg).ok_or("train_logreg failed")?;
+    let train_cfg = classifier_train_config(kind);
+    // 指标来自时间留出集；对外服务的模型仍用全部样本重新拟合。
+    let validation = evaluate_holdout(&x, &y, &dates, cfg.horizon_days.max(1), &train_cfg);
+    let model = train_cfg
+        .train(&x, &y)
+        .ok_or_else(|| format!("{} training failed", kind.as_str()))?;
 
     let positives = y.iter().filter(|v| **v >= 0.5).count() as i64;
     let total = y.len() as i64;
@@ -78,16 +274,109 @@ pub async fn train_and_store_sector_model(
         "sample_size": total,
         "positive": positives,
         "positive_rate": if total > 0 { (positives as f64) / (total as f64) } else { 0.0 },
-        "train": {
-            "learning_rate": train_cfg.learning_rate,
-            "epochs": train_cfg.epochs,
-            "l2": train_cfg.l2,
-        }
+        "model_type": kind.as_str(),
+        "train": train_cfg.to_json(),
+        "validation": validation,
     });
 
-    let feature_names_json = serde_json::to_string(&feature_names).map_err(|e| e.to_string())?;
-    let model_json = serde_json::to_string(&model).map_err(|e| e.to_string())?;
-    let metrics_json = serde_json::to_string(&metrics).map_err(|e| e.to_string())?;
+    let model_key = registry::sector_key(peer_code, task.as_str(), cfg.horizon_days as i64);
+    let shadow = match registry::active_version(pool, registry::FAMILY_SECTOR, &model_key).await? {
+        None => ShadowReport::no_champion("log_loss"),
+        Some(champion)
+            if champion.feature_set_hash != registry::feature_set_hash(&feature_names) =>
+        {
+            ShadowReport::feature_set_changed("log_loss", &champion)
+        }
+        Some(champion) => shadow_score(&champion, &x, &y, &dates, &train_cfg)?,
+    };
+
+    let version = registry::register(
+        pool,
+        NewModelVersion {
+            family: registry::FAMILY_SECTOR,
+            model_key,
+            model_type: kind,
+            feature_names: feature_names.clone(),
+            payload: json!({
+                "peer_code": peer_code,
+                "task": task.as_str(),
+                "horizon_days": cfg.horizon_days,
+                "model": model,
+            }),
+            metrics,
+            train_start_date: dates.first().cloned(),
+            train_end_date: dates.last().cloned(),
+            sample_count: total,
+        },
+        &shadow,
+    )
+    .await?;
+    Ok(Some(version))
+}
+
+/// 影子评估：在冠军训练截止日之后的样本上比较冠军与挑战者（后者用其余样本拟合）的 log-loss。
+fn shadow_score(
+    champion: &ModelVersion,
+    x: &[Vec<f64>],
+    y: &[f64],
+    dates: &[String],
+    train_cfg: &ClassifierTrainConfig,
+) -> Result<ShadowReport, String> {
+    let champion_model = Classifier::from_json(&champion.payload["model"].to_string())?;
+    let after = champion.train_end_date.as_deref().unwrap_or("");
+    let (mut seen_x, mut seen_y) = (Vec::new(), Vec::new());
+    let (mut new_x, mut new_y) = (Vec::new(), Vec::new());
+    for ((row, label), d) in x.iter().zip(y).zip(dates) {
+        if d.as_str() > after {
+            new_x.push(row.clone());
+            new_y.push(*label);
+        } else {
+            seen_x.push(row.clone());
+            seen_y.push(*label);
+        }
+    }
+    let challenger_model = if seen_x.len() >= MIN_TRAIN_SAMPLES {
+        train_cfg.train(&seen_x, &seen_y)
+    } else {
+        None
+    };
+    let predict = |m: &Classifier| -> Vec<f64> {
+        new_x
+            .iter()
+            .map(|r| m.predict_proba(r).unwrap_or(0.5))
+            .collect()
+    };
+    let champion_pred = predict(&champion_model);
+    let challenger_pred = match &challenger_model {
+        Some(m) => predict(m),
+        None => Vec::new(),
+    };
+    Ok(ShadowReport::compare(
+        "log_loss",
+        champion,
+        &new_y,
+        &champion_pred,
+        &challenger_pred,
+    ))
+}
+
+/// 把冠军版本写回 `ml_sector_model`（在 [`registry::promote`] 的事务内调用）；trained_at 记为生效时间。
+pub async fn store_serving_sector_model(
+    tx: &mut sqlx::Transaction<'_, sqlx::Any>,
+    version: &ModelVersion,
+) -> Result<(), String> {
+    let payload = &version.payload;
+    let peer_code = payload["peer_code"]
+        .as_str()
+        .ok_or("payload missing peer_code")?;
+    let task = payload["task"].as_str().ok_or("payload missing task")?;
+    let horizon_days = payload["horizon_days"]
+        .as_i64()
+        .ok_or("payload missing horizon_days")?;
+    let feature_names_json =
+        serde_json::to_string(&version.feature_names).map_err(|e| e.to_string())?;
+    let model_json = payload["model"].to_string();
+    let metrics_json = serde_json::to_string(&version.metrics).map_err(|e| e.to_string())?;
 
     sqlx::query(
         r#"
@@ -106,12 +395,12 @@ pub async fn train_and_store_sector_model(
         "#,
     )
     .bind(peer_code)
-    .bind(task.as_str())
-    .bind(cfg.horizon_days as i64)
+    .bind(task)
+    .bind(horizon_days)
     .bind(feature_names_json)
     .bind(model_json)
     .bind(metrics_json)
-    .execute(pool)
+    .execute(&mut **tx)
     .await
     .map_err(|e| e.to_string())?;
 
@@ -154,7 +443,7 @@ pub async fn get_sector_model(
 
     let feature_names: Vec<String> =
         serde_json::from_str(&feature_names_json).map_err(|e| e.to_string())?;
-    let model: LogRegModel = serde_json::from_str(&model_json).map_err(|e| e.to_string())?;
+    let model = Classifier::from_json(&model_json)?;
     let metrics: serde_json::Value =
         serde_json::from_str(&metrics_json).map_err(|e| e.to_string())?;
 
@@ -167,3 +456,56 @@ pub async fn get_sector_model(
         metrics,
     }))
 }
+
+/// 模型列表条目（不含权重）。
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SectorModelSummary {
+    pub peer_code: String,
+    pub task: String,
+    pub horizon_days: i64,
+    pub feature_names: Vec<String>,
+    pub metrics: serde_json::Value,
+    pub trained_at: Option<String>,
+}
+
+pub async fn list_sector_models(
+    pool: &sqlx::AnyPool,
+    peer_code: Option<&str>,
+    task: Option<&str>,
+) -> Result<Vec<SectorModelSummary>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+          peer_code,
+          task,
+          horizon_days,
+          feature_names_json,
+          metrics_json,
+          CAST(trained_at AS TEXT) as trained_at
+        FROM ml_sector_model
+        WHERE ($1 = '' OR peer_code = $1) AND ($2 = '' OR task = $2)
+        ORDER BY peer_code ASC, task ASC, horizon_days ASC
+        "#,
+    )
+    .bind(peer_code.unwrap_or("").to_string())
+    .bind(task.unwrap_or("").to_string())
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut out = Vec::with_capacity(rows.len());
+    for row in rows {
+        let feature_names_json: String = row.get("feature_names_json");
+        let metrics_json: String = row.get("metrics_json");
+        let trained_at: Option<String> = row.try_get("trained_at").ok();
+        out.push(SectorModelSummary {
+            peer_code: row.get("peer_code"),
+            task: row.get("task"),
+            horizon_days: row.get("horizon_days"),
+            feature_names: serde_json::from_str(&feature_names_json).unwrap_or_default(),
+            metrics: serde_json::from_str(&metrics_json).unwrap_or(serde_json::Value::Null),
+            trained_at: trained_at.map(|s| crate::dbfmt::datetime_to_rfc3339(&s)),
+        });
+    }
+    Ok(out)
+}
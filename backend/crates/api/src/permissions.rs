@@ -0,0 +1,456 @@
+use std::collections::BTreeSet;
+use std::marker::PhantomData;
+
+use axum::{
+    Json,
+    extract::FromRequestParts,
+    http::{StatusCode, request::Parts},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use serde_json::json;
+use sqlx::Row;
+
+use crate::state::AppState;
+
+/// 能力标记类型：配合 [`Require`] 在 handler 签名里声明所需能力。
+pub trait Capability {
+    const NAME: &'static str;
+}
+
+macro_rules! capabilities {
+    ($( $(#[$doc:meta])* $ty:ident => $name:literal ),* $(,)?) => {
+        $(
+            $(#[$doc])*
+            pub struct $ty;
+            impl Capability for $ty {
+                const NAME: &'static str = $name;
+            }
+        )*
+        /// 全部已定义的能力名。
+        pub const CAPABILITIES: &[&str] = &[$($name),*];
+    };
+}
+
+capabilities! {
+    /// 爬虫配置与调度、基金列表/嗅探/利率等全量同步。
+    CrawlAdmin => "crawl.admin",
+    /// 数据源令牌与准确率重算。
+    SourcesConfigure => "sources.configure",
+    /// 查看、取消、重排他人创建的任务。
+    TasksViewAll => "tasks.view_all",
+    /// 管理定时任务计划。
+    TasksSchedule => "tasks.schedule",
+    /// 不受每用户模拟运行数上限约束。
+    SimUnlimited => "sim.unlimited",
+    /// 单次同步超过 15 只基金的净值历史。
+    NavHistoryBulkSync => "nav_history.bulk_sync",
+    /// 查看、代录他人的持仓操作。
+    PositionsViewAll => "positions.view_all",
+    /// 全量重算持仓、删除操作记录。
+    PositionsAdmin => "positions.admin",
+    /// 查询审计日志。
+    AuditView => "audit.view",
+    /// 管理角色及用户角色。
+    UsersManageRoles => "users.manage_roles",
+    /// 查看模型版本、手动晋升与回滚。
+    MlManageModels => "ml.manage_models",
+}
+
+/// 角色能力中的通配符：拥有全部能力。
+pub const WILDCARD: &str = "*";
+
+/// is_staff 用户默认拥有的能力（与原先 is_staff 检查覆盖的范围一致；持仓管理与角色管理仍限 is_superuser）。
+pub const STAFF_CAPABILITIES: &[&str] = &[
+    CrawlAdmin::NAME,
+    SourcesConfigure::NAME,
+    TasksViewAll::NAME,
+    TasksSchedule::NAME,
+    SimUnlimited::NAME,
+    NavHistoryBulkSync::NAME,
+    PositionsViewAll::NAME,
+    AuditView::NAME,
+    MlManageModels::NAME,
+];
+
+/// 只有超级用户才能授予的能力：通配符与角色管理本身（否则持有角色管理者可自我提权）。
+pub const SUPERUSER_ONLY_GRANTS: &[&str] = &[WILDCARD, UsersManageRoles::NAME];
+
+pub fn grants_superuser_only(caps: &[String]) -> bool {
+    caps.iter()
+        .any(|c| SUPERUSER_ONLY_GRANTS.contains(&c.as_str()))
+}
+
+pub fn is_known_capability(cap: &str) -> bool {
+    cap == WILDCARD || CAPABILITIES.contains(&cap)
+}
+
+/// 已登录用户及其生效能力。
+#[derive(Debug, Clone)]
+pub struct Principal {
+    pub user_id: i64,
+    pub is_superuser: bool,
+    pub is_staff: bool,
+    capabilities: BTreeSet<String>,
+}
+
+impl Principal {
+    pub fn has(&self, cap: &str) -> bool {
+        self.is_superuser || self.capabilities.contains(WILDCARD) || self.capabilities.contains(cap)
+    }
+
+    /// 生效能力列表（通配符展开为全部能力）。
+    pub fn capabilities(&self) -> Vec<String> {
+        CAPABILITIES
+            .iter()
+            .filter(|c| self.has(c))
+            .map(|c| c.to_string())
+            .collect()
+    }
+}
+
+/// 读取用户的 is_superuser / is_staff 与角色能力；用户不存在返回 None。
+pub async fn load_principal(
+    pool: &sqlx::AnyPool,
+    user_id: i64,
+) -> Result<Option<Principal>, String> {
+    let row = sqlx::query(
+        r#"
+        SELECT
+          CASE WHEN is_superuser THEN 1 ELSE 0 END as is_superuser,
+          CASE WHEN is_staff THEN 1 ELSE 0 END as is_staff
+        FROM auth_user
+        WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    let Some(row) = row else {
+        return Ok(None);
+    };
+    let is_superuser = row.get::<i64, _>("is_superuser") != 0;
+    let is_staff = row.get::<i64, _>("is_staff") != 0;
+
+    let mut capabilities: BTreeSet<String> = BTreeSet::new();
+    if is_staff {
+        capabilities.extend(STAFF_CAPABILITIES.iter().map(|c| c.to_string()));
+    }
+    let rows = sqlx::query(
+        r#"
+        SELECT DISTINCT c.capability
+        FROM auth_user_role ur
+        JOIN auth_role_capability c ON c.role_name = ur.role_name
+        WHERE ur.user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    capabilities.extend(rows.iter().map(|r| r.get::<String, _>("capability")));
+
+    Ok(Some(Principal {
+        user_id,
+        is_superuser,
+        is_staff,
+        capabilities,
+    }))
+}
+
+/// 在 handler 内部按需检查能力（需要先完成其他校验时使用，语义同 [`Require`]）。
+pub async fn require(
+    state: &AppState,
+    headers: &axum::http::HeaderMap,
+    cap: &str,
+) -> Result<Principal, Response> {
+    let principal = principal_from_headers(state, headers).await?;
+    if !principal.has(cap) {
+        return Err(forbidden());
+    }
+    Ok(principal)
+}
+
+/// 软检查：用户是否持有某能力；查询失败按“无权限”处理。
+pub async fn user_has(pool: &sqlx::AnyPool, user_id: i64, cap: &str) -> bool {
+    matches!(load_principal(pool, user_id).await, Ok(Some(p)) if p.has(cap))
+}
+
+pub fn forbidden() -> Response {
+    (
+        StatusCode::FORBIDDEN,
+        Json(json!({ "detail": "You do not have permission to perform this action." })),
+    )
+        .into_response()
+}
+
+/// 从请求头解析登录用户：未登录/令牌无效返回 401，数据库不可用返回 503。
+pub async fn principal_from_headers(
+    state: &AppState,
+    headers: &axum::http::HeaderMap,
+) -> Result<Principal, Response> {
+    let user_id = crate::routes::auth::authenticate(state, headers)?;
+    let user_id = user_id
+        .parse::<i64>()
+        .map_err(|_| crate::routes::auth::invalid_token_response())?;
+    let Some(pool) = state.pool() else {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({ "error": "数据库未连接" })),
+        )
+            .into_response());
+    };
+    match load_principal(pool, user_id).await {
+        Ok(Some(p)) => Ok(p),
+        Ok(None) => Err(crate::routes::auth::invalid_token_response()),
+        Err(e) => Err(crate::routes::errors::internal_response(state, e)),
+    }
+}
+
+impl FromRequestParts<AppState> for Principal {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        principal_from_headers(state, &parts.headers).await
+    }
+}
+
+/// 要求登录用户持有能力 `C`，否则 403。
+pub struct Require<C>(pub Principal, PhantomData<C>);
+
+impl<C> std::ops::Deref for Require<C> {
+    type Target = Principal;
+
+    fn deref(&self) -> &Principal {
+        &self.0
+    }
+}
+
+impl<C: Capability + Send + Sync> FromRequestParts<AppState> for Require<C> {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let principal = principal_from_headers(state, &parts.headers).await?;
+        if !principal.has(C::NAME) {
+            return Err(forbidden());
+        }
+        Ok(Require(principal, PhantomData))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoleRow {
+    pub name: String,
+    pub description: String,
+    pub is_system: bool,
+    pub capabilities: Vec<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+async fn role_capabilities(pool: &sqlx::AnyPool, name: &str) -> Result<Vec<String>, String> {
+    let rows = sqlx::query(
+        "SELECT capability FROM auth_role_capability WHERE role_name = $1 ORDER BY capability",
+    )
+    .bind(name)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(rows
+        .iter()
+        .map(|r| r.get::<String, _>("capability"))
+        .collect())
+}
+
+const ROLE_SELECT: &str = r#"
+    SELECT
+      name,
+      description,
+      CASE WHEN is_system THEN 1 ELSE 0 END as is_system,
+      CAST(created_at AS TEXT) as created_at,
+      CAST(updated_at AS TEXT) as updated_at
+    FROM auth_role
+"#;
+
+async fn role_from_row(pool: &sqlx::AnyPool, r: &sqlx::any::AnyRow) -> Result<RoleRow, String> {
+    let name: String = r.get("name");
+    Ok(RoleRow {
+        capabilities: role_capabilities(pool, &name).await?,
+        name,
+        description: r.get("description"),
+        is_system: r.get::<i64, _>("is_system") != 0,
+        created_at: crate::dbfmt::datetime_to_rfc3339(&r.get::<String, _>("created_at")),
+        updated_at: crate::dbfmt::datetime_to_rfc3339(&r.get::<String, _>("updated_at")),
+    })
+}
+
+pub async fn list_roles(pool: &sqlx::AnyPool) -> Result<Vec<RoleRow>, String> {
+    let rows = sqlx::query(&format!("{ROLE_SELECT} ORDER BY name"))
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut out = Vec::with_capacity(rows.len());
+    for r in &rows {
+        out.push(role_from_row(pool, r).await?);
+    }
+    Ok(out)
+}
+
+pub async fn get_role(pool: &sqlx::AnyPool, name: &str) -> Result<Option<RoleRow>, String> {
+    let row = sqlx::query(&format!("{ROLE_SELECT} WHERE name = $1"))
+        .bind(name)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    match row {
+        Some(r) => Ok(Some(role_from_row(pool, &r).await?)),
+        None => Ok(None),
+    }
+}
+
+async fn replace_role_capabilities(
+    tx: &mut sqlx::Transaction<'_, sqlx::Any>,
+    name: &str,
+    caps: &[String],
+) -> Result<(), String> {
+    sqlx::query("DELETE FROM auth_role_capability WHERE role_name = $1")
+        .bind(name)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    for cap in caps.iter().collect::<BTreeSet<_>>() {
+        sqlx::query("INSERT INTO auth_role_capability (role_name, capability) VALUES ($1, $2)")
+            .bind(name)
+            .bind(cap)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+pub async fn create_role(
+    pool: &sqlx::AnyPool,
+    name: &str,
+    description: &str,
+    caps: &[String],
+) -> Result<(), String> {
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    sqlx::query(
+        r#"
+        INSERT INTO auth_role (name, description, is_system, created_at, updated_at)
+        VALUES ($1, $2, FALSE, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        "#,
+    )
+    .bind(name)
+    .bind(description)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    replace_role_capabilities(&mut tx, name, caps).await?;
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+/// 只更新传入的字段；角色不存在返回 false。
+pub async fn update_role(
+    pool: &sqlx::AnyPool,
+    name: &str,
+    description: Option<&str>,
+    caps: Option<&[String]>,
+) -> Result<bool, String> {
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let res = sqlx::query(
+        r#"
+        UPDATE auth_role
+        SET description = COALESCE($1, description),
+            updated_at = CURRENT_TIMESTAMP
+        WHERE name = $2
+        "#,
+    )
+    .bind(description)
+    .bind(name)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    if res.rows_affected() == 0 {
+        let _ = tx.rollback().await;
+        return Ok(false);
+    }
+    if let Some(caps) = caps {
+        replace_role_capabilities(&mut tx, name, caps).await?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// 删除角色（连带用户授权）；角色不存在返回 false。
+pub async fn delete_role(pool: &sqlx::AnyPool, name: &str) -> Result<bool, String> {
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    for sql in [
+        "DELETE FROM auth_user_role WHERE role_name = $1",
+        "DELETE FROM auth_role_capability WHERE role_name = $1",
+    ] {
+        sqlx::query(sql)
+            .bind(name)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let res = sqlx::query("DELETE FROM auth_role WHERE name = $1")
+        .bind(name)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(res.rows_affected() > 0)
+}
+
+pub async fn user_role_names(pool: &sqlx::AnyPool, user_id: i64) -> Result<Vec<String>, String> {
+    let rows =
+        sqlx::query("SELECT role_name FROM auth_user_role WHERE user_id = $1 ORDER BY role_name")
+            .bind(user_id)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    Ok(rows
+        .iter()
+        .map(|r| r.get::<String, _>("role_name"))
+        .collect())
+}
+
+/// 用给定角色集合整体替换用户的角色。
+pub async fn set_user_roles(
+    pool: &sqlx::AnyPool,
+    user_id: i64,
+    roles: &[String],
+    granted_by: Option<i64>,
+) -> Result<(), String> {
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    sqlx::query("DELETE FROM auth_user_role WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    for role in roles.iter().collect::<BTreeSet<_>>() {
+        sqlx::query(
+            r#"
+            INSERT INTO auth_user_role (user_id, role_name, granted_by, created_at)
+            VALUES ($1, $2, $3, CURRENT_TIMESTAMP)
+            "#,
+        )
+        .bind(user_id)
+        .bind(role)
+        .bind(granted_by)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())
+}
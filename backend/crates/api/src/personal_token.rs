@@ -0,0 +1,448 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use axum::Json;
+use axum::extract::{Request, State};
+use axum::http::{Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, Utc};
+use rand::{Rng, distributions::Alphanumeric};
+use serde::Serialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::db::DatabaseKind;
+use crate::dbfmt::format_utc;
+use crate::state::AppState;
+
+/// 个人访问令牌的固定前缀，用于与 JWT 区分。
+pub const TOKEN_PREFIX: &str = "fvp_";
+
+/// 只读：任意路径的 GET/HEAD。
+pub const SCOPE_READ: &str = "read";
+/// 任务队列与定时任务（`/api/tasks*`、`/api/admin/task-schedules*`）的全部操作。
+pub const SCOPE_TASKS: &str = "tasks";
+/// 模拟盘/回测（`/api/sim*`）的全部操作。
+pub const SCOPE_SIM: &str = "sim";
+
+pub const SCOPES: &[&str] = &[SCOPE_READ, SCOPE_TASKS, SCOPE_SIM];
+
+/// last_used_at 的最小写库间隔，避免脚本高频调用时每个请求都写一次。
+const TOUCH_INTERVAL_SECONDS: i64 = 60;
+
+/// 令牌永远不能访问的路径：账号凭据与权限管理只接受交互式登录。
+const DENIED_PREFIXES: &[&str] = &[
+    "/api/auth/tokens",
+    "/api/auth/password",
+    "/api/auth/sessions",
+    "/api/auth/logout",
+    "/api/auth/refresh",
+    "/api/auth/2fa",
+    "/api/admin/users",
+    "/api/admin/roles",
+];
+
+fn path_under(path: &str, prefix: &str) -> bool {
+    path == prefix
+        || path
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// 请求所需的作用域（满足其一即可）；返回空表示令牌不可访问该路径。
+pub fn accepted_scopes(method: &Method, path: &str) -> Vec<&'static str> {
+    if DENIED_PREFIXES.iter().any(|p| path_under(path, p)) {
+        return Vec::new();
+    }
+    let mut out = Vec::new();
+    if *method == Method::GET || *method == Method::HEAD {
+        out.push(SCOPE_READ);
+    }
+    if path_under(path, "/api/tasks") || path_under(path, "/api/admin/task-schedules") {
+        out.push(SCOPE_TASKS);
+    }
+    if path_under(path, "/api/sim") {
+        out.push(SCOPE_SIM);
+    }
+    out
+}
+
+pub fn is_personal_token(token: &str) -> bool {
+    token.starts_with(TOKEN_PREFIX)
+}
+
+pub fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn generate_token() -> String {
+    let secret: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(40)
+        .map(char::from)
+        .collect();
+    format!("{TOKEN_PREFIX}{secret}")
+}
+
+/// 已通过中间件校验的令牌。
+#[derive(Debug, Clone)]
+struct CachedToken {
+    id: String,
+    user_id: String,
+    expires_at: Option<i64>,
+    touched_at: i64,
+}
+
+/// `authenticate` 是同步的：中间件先查库校验令牌（吊销/过期/作用域），再放入这里供其识别。
+#[derive(Debug, Default)]
+pub struct PersonalTokenCache {
+    inner: RwLock<HashMap<String, CachedToken>>,
+}
+
+impl PersonalTokenCache {
+    /// 明文令牌 -> (令牌 id, 用户 id)。
+    pub fn resolve(&self, token: &str) -> Option<(String, String)> {
+        let now = Utc::now().timestamp();
+        let map = self.inner.read().expect("personal token cache lock");
+        let t = map.get(&hash_token(token))?;
+        if t.expires_at.is_some_and(|exp| exp <= now) {
+            return None;
+        }
+        Some((t.id.clone(), t.user_id.clone()))
+    }
+
+    /// 写入缓存；返回是否需要刷新 last_used_at。
+    fn remember(&self, hash: &str, record: &TokenRecord, now: i64) -> bool {
+        let mut map = self.inner.write().expect("personal token cache lock");
+        let touched_at = map.get(hash).map(|t| t.touched_at).unwrap_or(0);
+        let due = now - touched_at >= TOUCH_INTERVAL_SECONDS;
+        map.insert(
+            hash.to_string(),
+            CachedToken {
+                id: record.id.clone(),
+                user_id: record.user_id.to_string(),
+                expires_at: record.expires_at.map(|d| d.timestamp()),
+                touched_at: if due { now } else { touched_at },
+            },
+        );
+        due
+    }
+
+    fn forget_hash(&self, hash: &str) {
+        self.inner
+            .write()
+            .expect("personal token cache lock")
+            .remove(hash);
+    }
+
+    pub fn forget_id(&self, id: &str) {
+        self.inner
+            .write()
+            .expect("personal token cache lock")
+            .retain(|_, t| t.id != id);
+    }
+}
+
+fn is_pg(pool: &sqlx::AnyPool) -> bool {
+    crate::db::database_kind_from_pool(pool) == DatabaseKind::Postgres
+}
+
+/// 有效（未吊销、未过期）令牌的校验信息。
+#[derive(Debug, Clone)]
+pub struct TokenRecord {
+    pub id: String,
+    pub user_id: i64,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+pub fn parse_scopes(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+pub async fn lookup(pool: &sqlx::AnyPool, token: &str) -> Result<Option<TokenRecord>, String> {
+    let sql = if is_pg(pool) {
+        r#"
+        SELECT CAST(id AS TEXT) as id, user_id, scopes, CAST(expires_at AS TEXT) as expires_at
+        FROM auth_personal_token
+        WHERE token_hash = $1 AND revoked_at IS NULL
+          AND (expires_at IS NULL OR expires_at > ($2)::timestamptz)
+        "#
+    } else {
+        r#"
+        SELECT CAST(id AS TEXT) as id, user_id, scopes, CAST(expires_at AS TEXT) as expires_at
+        FROM auth_personal_token
+        WHERE token_hash = $1 AND revoked_at IS NULL
+          AND (expires_at IS NULL OR expires_at > $2)
+        "#
+    };
+    let row = sqlx::query(sql)
+        .bind(hash_token(token))
+        .bind(format_utc(Utc::now()))
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(row.map(|r| TokenRecord {
+        id: r.get("id"),
+        user_id: r.get("user_id"),
+        scopes: parse_scopes(&r.get::<String, _>("scopes")),
+        expires_at: r
+            .try_get::<Option<String>, _>("expires_at")
+            .ok()
+            .flatten()
+            .and_then(|s| crate::dbfmt::parse_datetime_utc(&s)),
+    }))
+}
+
+async fn touch(pool: &sqlx::AnyPool, id: &str, ip: Option<String>) -> Result<(), String> {
+    let sql = if is_pg(pool) {
+        "UPDATE auth_personal_token SET last_used_at = ($2)::timestamptz, last_used_ip = $3 WHERE id = ($1)::uuid"
+    } else {
+        "UPDATE auth_personal_token SET last_used_at = $2, last_used_ip = $3 WHERE id = $1"
+    };
+    sqlx::query(sql)
+        .bind(id)
+        .bind(format_utc(Utc::now()))
+        .bind(ip)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenRow {
+    pub id: String,
+    pub name: String,
+    /// 明文开头的若干字符，便于用户辨认。
+    pub token_prefix: String,
+    pub scopes: Vec<String>,
+    pub created_at: String,
+    pub expires_at: Option<String>,
+    pub last_used_at: Option<String>,
+    pub last_used_ip: Option<String>,
+}
+
+const SELECT_COLUMNS: &str = "CAST(id AS TEXT) as id, name, token_prefix, scopes, \
+     CAST(created_at AS TEXT) as created_at, CAST(expires_at AS TEXT) as expires_at, \
+     CAST(last_used_at AS TEXT) as last_used_at, last_used_ip";
+
+fn row_to_token(r: &sqlx::any::AnyRow) -> TokenRow {
+    let opt_time = |col: &str| {
+        r.try_get::<Option<String>, _>(col)
+            .ok()
+            .flatten()
+            .map(|s| crate::dbfmt::datetime_to_rfc3339(&s))
+    };
+    TokenRow {
+        id: r.get("id"),
+        name: r.get("name"),
+        token_prefix: r.get("token_prefix"),
+        scopes: parse_scopes(&r.get::<String, _>("scopes")),
+        created_at: crate::dbfmt::datetime_to_rfc3339(&r.get::<String, _>("created_at")),
+        expires_at: opt_time("expires_at"),
+        last_used_at: opt_time("last_used_at"),
+        last_used_ip: r
+            .try_get::<Option<String>, _>("last_used_ip")
+            .ok()
+            .flatten(),
+    }
+}
+
+/// 用户未吊销的令牌（含已过期的，便于识别后清理），新建的在前。
+pub async fn list(pool: &sqlx::AnyPool, user_id: i64) -> Result<Vec<TokenRow>, String> {
+    let rows = sqlx::query(&format!(
+        "SELECT {SELECT_COLUMNS} FROM auth_personal_token \
+         WHERE user_id = $1 AND revoked_at IS NULL ORDER BY created_at DESC, name ASC"
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(rows.iter().map(row_to_token).collect())
+}
+
+/// 新建令牌，返回元数据与明文（仅此一次）。
+pub async fn create(
+    pool: &sqlx::AnyPool,
+    user_id: i64,
+    name: &str,
+    scopes: &[String],
+    expires_at: Option<DateTime<Utc>>,
+) -> Result<(TokenRow, String), String> {
+    let id = Uuid::new_v4().to_string();
+    let token = generate_token();
+    let prefix: String = token.chars().take(TOKEN_PREFIX.len() + 4).collect();
+    let sql = if is_pg(pool) {
+        r#"
+        INSERT INTO auth_personal_token (id, user_id, name, token_prefix, token_hash, scopes, created_at, expires_at)
+        VALUES (($1)::uuid,$2,$3,$4,$5,$6,($7)::timestamptz,($8)::timestamptz)
+        "#
+    } else {
+        r#"
+        INSERT INTO auth_personal_token (id, user_id, name, token_prefix, token_hash, scopes, created_at, expires_at)
+        VALUES ($1,$2,$3,$4,$5,$6,$7,$8)
+        "#
+    };
+    sqlx::query(sql)
+        .bind(&id)
+        .bind(user_id)
+        .bind(name)
+        .bind(&prefix)
+        .bind(hash_token(&token))
+        .bind(scopes.join(","))
+        .bind(format_utc(Utc::now()))
+        .bind(expires_at.map(format_utc))
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let sql = if is_pg(pool) {
+        format!("SELECT {SELECT_COLUMNS} FROM auth_personal_token WHERE id = ($1)::uuid")
+    } else {
+        format!("SELECT {SELECT_COLUMNS} FROM auth_personal_token WHERE id = $1")
+    };
+    let row = sqlx::query(&sql)
+        .bind(&id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok((row_to_token(&row), token))
+}
+
+/// 吊销用户自己的令牌；返回被吊销令牌的元数据（不存在或已吊销时为 None）。
+pub async fn revoke(
+    pool: &sqlx::AnyPool,
+    id: &str,
+    user_id: i64,
+) -> Result<Option<TokenRow>, String> {
+    if Uuid::parse_str(id).is_err() {
+        return Ok(None);
+    }
+    let pg = is_pg(pool);
+    let sql = if pg {
+        format!(
+            "SELECT {SELECT_COLUMNS} FROM auth_personal_token \
+             WHERE id = ($1)::uuid AND user_id = $2 AND revoked_at IS NULL"
+        )
+    } else {
+        format!(
+            "SELECT {SELECT_COLUMNS} FROM auth_personal_token \
+             WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL"
+        )
+    };
+    let Some(row) = sqlx::query(&sql)
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?
+    else {
+        return Ok(None);
+    };
+    let sql = if pg {
+        "UPDATE auth_personal_token SET revoked_at = ($2)::timestamptz WHERE id = ($1)::uuid AND revoked_at IS NULL"
+    } else {
+        "UPDATE auth_personal_token SET revoked_at = $2 WHERE id = $1 AND revoked_at IS NULL"
+    };
+    let r = sqlx::query(sql)
+        .bind(id)
+        .bind(format_utc(Utc::now()))
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    if r.rows_affected() == 0 {
+        return Ok(None);
+    }
+    Ok(Some(row_to_token(&row)))
+}
+
+/// 在调用方的事务内吊销用户的全部令牌（修改密码时与密码更新一并提交）；返回被吊销的令牌 id。
+/// 提交后需对每个 id 调用 [`PersonalTokenCache::forget_id`]。
+pub async fn revoke_all_for_user(
+    tx: &mut sqlx::Transaction<'_, sqlx::Any>,
+    pg: bool,
+    user_id: i64,
+) -> Result<Vec<String>, String> {
+    let rows = sqlx::query(
+        "SELECT CAST(id AS TEXT) as id FROM auth_personal_token WHERE user_id = $1 AND revoked_at IS NULL",
+    )
+    .bind(user_id)
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    let sql = if pg {
+        "UPDATE auth_personal_token SET revoked_at = ($2)::timestamptz WHERE user_id = $1 AND revoked_at IS NULL"
+    } else {
+        "UPDATE auth_personal_token SET revoked_at = $2 WHERE user_id = $1 AND revoked_at IS NULL"
+    };
+    sqlx::query(sql)
+        .bind(user_id)
+        .bind(format_utc(Utc::now()))
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(rows.iter().map(|r| r.get::<String, _>("id")).collect())
+}
+
+fn bearer(req: &Request) -> Option<&str> {
+    req.headers()
+        .get(axum::http::header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+/// 路由中间件：个人访问令牌在进入处理函数前查库校验（吊销、过期、作用域），
+/// 通过后放入缓存，由 `authenticate` 识别为对应用户；JWT 请求原样放行。
+pub async fn authorize(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let Some(token) = bearer(&req).filter(|t| is_personal_token(t)) else {
+        return next.run(req).await;
+    };
+    let token = token.to_string();
+    let Some(pool) = state.pool() else {
+        return crate::routes::errors::internal_response(&state, "database not configured");
+    };
+    let hash = hash_token(&token);
+    let record = match lookup(pool, &token).await {
+        Ok(Some(r)) => r,
+        Ok(None) => {
+            state.personal_tokens().forget_hash(&hash);
+            return crate::routes::auth::invalid_token_response();
+        }
+        Err(e) => return crate::routes::errors::internal_response(&state, e),
+    };
+
+    let accepted = accepted_scopes(req.method(), req.uri().path());
+    if !accepted
+        .iter()
+        .any(|s| record.scopes.iter().any(|have| have == s))
+    {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "detail": "该令牌没有执行此操作的权限。",
+                "required_scopes": accepted,
+            })),
+        )
+            .into_response();
+    }
+
+    if state
+        .personal_tokens()
+        .remember(&hash, &record, Utc::now().timestamp())
+        && let Err(e) = touch(pool, &record.id, crate::audit::client_ip(req.headers())).await
+    {
+        tracing::warn!(error = %e, token_id = %record.id, "personal token touch failed");
+    }
+    next.run(req).await
+}
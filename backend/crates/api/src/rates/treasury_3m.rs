@@ -72,6 +72,27 @@ pub async fn fetch_chinabond_3m(
     parse_chinabond_curve_json(&text)
 }
 
+/// 中债收益率曲线地址；可用环境变量 `RISK_FREE_CHINABOND_URL` 覆盖。
+pub fn chinabond_curve_url() -> String {
+    std::env::var("RISK_FREE_CHINABOND_URL")
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| DEFAULT_CHINABOND_CURVE_URL.to_string())
+}
+
+/// 抓取中债 3M 收益率并写入 risk_free_rate_daily（定时任务使用）。
+pub async fn sync_chinabond_3m(pool: &sqlx::AnyPool) -> Result<Treasury3mRate, String> {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(20))
+        .user_agent("Fundval-re rates/1.0")
+        .build()
+        .map_err(|e| format!("创建 HTTP 客户端失败: {e}"))?;
+    let got = fetch_chinabond_3m(&client, &chinabond_curve_url()).await?;
+    upsert_risk_free_rate_3m(pool, &got, "chinabond").await?;
+    Ok(got)
+}
+
 pub async fn upsert_risk_free_rate_3m(
     pool: &sqlx::AnyPool,
     rate: &Treasury3mRate,
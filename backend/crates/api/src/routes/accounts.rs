@@ -441,6 +441,19 @@ pub async fn create(
         updated_at: format_now(Utc::now()),
     };
 
+    crate::audit::record(
+        &state,
+        &headers,
+        crate::audit::AuditEvent::new("accounts.create", "account")
+            .target(&row.id)
+            .after(json!({
+                "name": row.name,
+                "parent": row.parent_id,
+                "is_default": row.is_default,
+            })),
+    )
+    .await;
+
     let summary = compute_child_summary(&[]);
     let mut resp = to_account_response(&row, &summary);
     if row.parent_id.is_none() {
@@ -873,6 +886,24 @@ async fn update_internal(
             .into_response();
     }
 
+    crate::audit::record(
+        &state,
+        &headers,
+        crate::audit::AuditEvent::new("accounts.update", "account")
+            .target(&existing_row.id)
+            .before(json!({
+                "name": existing_row.name,
+                "parent": existing_row.parent_id,
+                "is_default": existing_row.is_default,
+            }))
+            .after(json!({
+                "name": next_name,
+                "parent": next_parent,
+                "is_default": next_is_default,
+            })),
+    )
+    .await;
+
     let id_uuid = Uuid::parse_str(&existing_row.id).unwrap_or(id);
     retrieve(
         axum::extract::State(state),
@@ -909,6 +940,24 @@ pub async fn destroy(
         Some(p) => p,
     };
 
+    // 删除前的快照，供审计记录使用。
+    let before = sqlx::query(
+        "SELECT name, CAST(parent_id AS TEXT) as parent_id, is_default FROM account WHERE CAST(id AS TEXT) = $1 AND user_id = $2",
+    )
+    .bind(id.to_string())
+    .bind(user_id_i64)
+    .fetch_optional(pool)
+    .await
+    .ok()
+    .flatten()
+    .map(|row| {
+        json!({
+            "name": row.get::<String, _>("name"),
+            "parent": row.get::<Option<String>, _>("parent_id"),
+            "is_default": row_bool(&row, "is_default"),
+        })
+    });
+
     let res = match sqlx::query("DELETE FROM account WHERE CAST(id AS TEXT) = $1 AND user_id = $2")
         .bind(id.to_string())
         .bind(user_id_i64)
@@ -933,6 +982,12 @@ pub async fn destroy(
             .into_response();
     }
 
+    let mut event = crate::audit::AuditEvent::new("accounts.delete", "account").target(id);
+    if let Some(v) = before {
+        event = event.before(v);
+    }
+    crate::audit::record(&state, &headers, event).await;
+
     StatusCode::NO_CONTENT.into_response()
 }
 
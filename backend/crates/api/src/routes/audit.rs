@@ -0,0 +1,97 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::audit::{self, AuditFilter};
+use crate::dbfmt::format_utc;
+use crate::permissions::{AuditView, Require};
+use crate::routes::errors;
+use crate::state::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct AuditQuery {
+    pub actor: Option<i64>,
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// 时间参数：RFC3339 / 数据库时间文本，或纯日期（since 取当日零点，until 取当日末秒）。
+fn parse_bound(raw: &str, end_of_day: bool) -> Option<DateTime<Utc>> {
+    if let Some(dt) = crate::dbfmt::parse_datetime_utc(raw) {
+        return Some(dt);
+    }
+    let d = NaiveDate::parse_from_str(raw.trim(), "%Y-%m-%d").ok()?;
+    let t = if end_of_day {
+        d.and_hms_opt(23, 59, 59)?
+    } else {
+        d.and_hms_opt(0, 0, 0)?
+    };
+    Some(DateTime::<Utc>::from_naive_utc_and_offset(t, Utc))
+}
+
+fn non_empty(v: Option<String>) -> Option<String> {
+    v.map(|s| s.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+pub async fn admin_list(
+    _: Require<AuditView>,
+    State(state): State<AppState>,
+    Query(q): Query<AuditQuery>,
+) -> axum::response::Response {
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+
+    let mut bounds = [None, None];
+    for (i, raw) in [q.since, q.until].into_iter().enumerate() {
+        let Some(raw) = non_empty(raw) else {
+            continue;
+        };
+        match parse_bound(&raw, i == 1) {
+            Some(dt) => bounds[i] = Some(format_utc(dt)),
+            None => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({ "error": format!("时间格式不正确: {raw}") })),
+                )
+                    .into_response();
+            }
+        }
+    }
+    let [since, until] = bounds;
+
+    let filter = AuditFilter {
+        actor_user_id: q.actor,
+        action: non_empty(q.action),
+        target_type: non_empty(q.target_type),
+        target_id: non_empty(q.target_id),
+        since,
+        until,
+        limit: q.limit.unwrap_or(50).clamp(1, 500),
+        offset: q.offset.unwrap_or(0).max(0),
+    };
+
+    match audit::query_events(pool, &filter).await {
+        Ok(items) => (
+            StatusCode::OK,
+            Json(json!({
+                "items": items,
+                "limit": filter.limit,
+                "offset": filter.offset,
+            })),
+        )
+            .into_response(),
+        Err(e) => errors::internal_response(&state, e),
+    }
+}
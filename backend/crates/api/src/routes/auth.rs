@@ -1,10 +1,15 @@
 use axum::{Json, http::StatusCode, response::IntoResponse};
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use sqlx::Row;
 
+use crate::auth_session::{self, RotateOutcome};
 use crate::django_password;
+use crate::login_guard;
+use crate::jwt::{Claims, JwtService};
+use crate::personal_token;
 use crate::routes::errors;
-use crate::{jwt::JwtService, state::AppState};
+use crate::state::AppState;
 
 #[derive(Debug, Deserialize)]
 pub struct LoginRequest {
@@ -19,6 +24,19 @@ pub struct LoginResponse {
     pub user: LoginUser,
 }
 
+/// 开启两步验证时，密码校验通过后的响应（此时不签发访问令牌）。
+#[derive(Debug, Serialize)]
+pub struct MfaRequiredResponse {
+    pub mfa_required: bool,
+    pub mfa_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoginTwoFactorRequest {
+    pub mfa_token: String,
+    pub code: String,
+}
+
 #[derive(Debug, Serialize)]
 pub struct LoginUser {
     pub id: String,
@@ -34,6 +52,13 @@ pub struct RefreshRequest {
 #[derive(Debug, Serialize)]
 pub struct RefreshResponse {
     pub access_token: String,
+    /// 轮换后的刷新令牌；旧的刷新令牌随即失效。
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct LogoutRequest {
+    pub refresh_token: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -66,6 +91,8 @@ pub struct MeResponse {
     pub username: String,
     pub email: String,
     pub role: String,
+    /// 生效能力（is_staff 默认能力 + 角色能力；超级用户为全部）。
+    pub capabilities: Vec<String>,
     pub created_at: String,
 }
 
@@ -80,8 +107,76 @@ pub struct MessageResponse {
     pub message: &'static str,
 }
 
+fn login_failed_response() -> axum::response::Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        Json(ErrorResponse {
+            error: "用户名或密码错误".to_string(),
+        }),
+    )
+        .into_response()
+}
+
+pub(crate) fn too_many_attempts_response(retry_after: i64) -> axum::response::Response {
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(axum::http::header::RETRY_AFTER, retry_after.to_string())],
+        Json(serde_json::json!({
+            "error": format!("登录失败次数过多，请 {retry_after} 秒后重试"),
+            "retry_after": retry_after,
+        })),
+    )
+        .into_response()
+}
+
+/// 参与限流的键：用户名，以及可识别时的来源 IP。
+pub(crate) fn guard_keys(username: &str, headers: &axum::http::HeaderMap) -> Vec<String> {
+    let mut keys = vec![login_guard::user_key(username)];
+    if let Some(ip) = crate::client_ip::from_headers(headers) {
+        keys.push(login_guard::ip_key(&ip));
+    }
+    keys
+}
+
+/// 记录一次登录失败（密码或第二因素错误）；用户名刚被锁定时写审计。
+pub(crate) async fn register_login_failure(
+    state: &AppState,
+    pool: &sqlx::AnyPool,
+    headers: &axum::http::HeaderMap,
+    username: &str,
+) -> Result<(), String> {
+    let policy = login_guard::GuardPolicy::from_config(state.config());
+    let key = login_guard::user_key(username);
+    let (failures, _) =
+        login_guard::record_failure(pool, &key, policy.max_failures_per_user, &policy).await?;
+    if failures == policy.max_failures_per_user {
+        tracing::warn!(username, failures, "login locked after repeated failures");
+        crate::audit::record(
+            state,
+            headers,
+            crate::audit::AuditEvent::new("auth.login.locked", "user")
+                .target(username)
+                .after(serde_json::json!({ "failures": failures, "lockout_seconds": policy.lockout_seconds })),
+        )
+        .await;
+    }
+    if let Some(ip) = crate::client_ip::from_headers(headers) {
+        login_guard::record_failure(
+            pool,
+            &login_guard::ip_key(&ip),
+            policy.max_failures_per_ip,
+            &policy,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// 登录：先检查限流（锁定期内不做密码校验），失败累计渐进延迟直至锁定。
+/// 开启两步验证的用户只拿到中间令牌，需再调用 `/api/auth/login/2fa`。
 pub async fn login(
     axum::extract::State(state): axum::extract::State<AppState>,
+    headers: axum::http::HeaderMap,
     Json(body): Json<LoginRequest>,
 ) -> axum::response::Response {
     let pool = match state.pool() {
@@ -97,6 +192,12 @@ pub async fn login(
         Some(p) => p,
     };
 
+    match login_guard::retry_after(pool, &guard_keys(&body.username, &headers)).await {
+        Ok(Some(secs)) => return too_many_attempts_response(secs),
+        Ok(None) => {}
+        Err(e) => return errors::internal_response(&state, e),
+    }
+
     let row = sqlx::query(
         r#"
         SELECT
@@ -114,7 +215,7 @@ pub async fn login(
     .fetch_optional(pool)
     .await;
 
-    let Some(row) = (match row {
+    let row = match row {
         Ok(v) => v,
         Err(e) => {
             return (
@@ -125,48 +226,69 @@ pub async fn login(
             )
                 .into_response();
         }
-    }) else {
-        return (
-            StatusCode::UNAUTHORIZED,
-            Json(ErrorResponse {
-                error: "用户名或密码错误".to_string(),
-            }),
-        )
-            .into_response();
     };
 
-    let password_hash = match row.try_get::<String, _>("password") {
-        Ok(v) => v,
-        Err(_) => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(ErrorResponse {
-                    error: "用户名或密码错误".to_string(),
-                }),
-            )
-                .into_response();
+    let verified = row.as_ref().is_some_and(|r| {
+        r.try_get::<String, _>("password")
+            .is_ok_and(|hash| django_password::verify_password(&body.password, &hash))
+    });
+    let Some(row) = row.filter(|_| verified) else {
+        if let Err(e) = register_login_failure(&state, pool, &headers, &body.username).await {
+            return errors::internal_response(&state, e);
         }
+        return login_failed_response();
     };
 
-    if !django_password::verify_password(&body.password, &password_hash) {
-        return (
-            StatusCode::UNAUTHORIZED,
-            Json(ErrorResponse {
-                error: "用户名或密码错误".to_string(),
-            }),
-        )
-            .into_response();
-    }
-
     let user_id = row.get::<String, _>("id");
     let username = row.get::<String, _>("username");
     let is_superuser = row
         .try_get::<bool, _>("is_superuser")
         .unwrap_or_else(|_| row.try_get::<i64, _>("is_superuser").unwrap_or(0) != 0);
 
-    let jwt = state.jwt();
-    let access_token = jwt.issue_access_token(&user_id);
-    let refresh_token = jwt.issue_refresh_token(&user_id);
+    let uid = user_id.parse::<i64>().unwrap_or_default();
+    match crate::totp::is_enabled(pool, uid).await {
+        Ok(true) => {
+            return (
+                StatusCode::OK,
+                Json(MfaRequiredResponse {
+                    mfa_required: true,
+                    mfa_token: state.jwt().issue_mfa_token(&user_id),
+                }),
+            )
+                .into_response();
+        }
+        Ok(false) => {}
+        Err(e) => return errors::internal_response(&state, e),
+    }
+
+    login_success(&state, pool, &headers, user_id, username, is_superuser).await
+}
+
+/// 认证完成：清除该用户名的失败计数并签发令牌。
+async fn login_success(
+    state: &AppState,
+    pool: &sqlx::AnyPool,
+    headers: &axum::http::HeaderMap,
+    user_id: String,
+    username: String,
+    is_superuser: bool,
+) -> axum::response::Response {
+    if let Err(e) = login_guard::clear(pool, &login_guard::user_key(&username)).await {
+        return errors::internal_response(state, e);
+    }
+    let (access_token, refresh_token) =
+        match issue_session_tokens(state, pool, &user_id, headers).await {
+            Ok(v) => v,
+            Err(e) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse {
+                        error: errors::internal_message(state, e),
+                    }),
+                )
+                    .into_response();
+            }
+        };
 
     (
         StatusCode::OK,
@@ -183,34 +305,358 @@ pub async fn login(
         .into_response()
 }
 
-pub async fn refresh(
+/// 两步验证登录的第二步：中间令牌 + 验证码（TOTP 或一次性恢复码）。
+pub async fn login_two_factor(
     axum::extract::State(state): axum::extract::State<AppState>,
-    Json(body): Json<RefreshRequest>,
+    headers: axum::http::HeaderMap,
+    Json(body): Json<LoginTwoFactorRequest>,
 ) -> axum::response::Response {
-    let jwt = state.jwt();
-    let decoded = jwt.decode(&body.refresh_token);
-    let Ok(decoded) = decoded else {
-        return (
+    let invalid = || {
+        (
             StatusCode::BAD_REQUEST,
             Json(ErrorResponse {
-                error: "Invalid refresh token".to_string(),
+                error: "Invalid mfa token".to_string(),
             }),
         )
-            .into_response();
+            .into_response()
+    };
+    let Ok(decoded) = state.jwt().decode(&body.mfa_token) else {
+        return invalid();
+    };
+    if decoded.claims.token_type != "mfa" {
+        return invalid();
+    }
+    let Ok(uid) = decoded.claims.sub.parse::<i64>() else {
+        return invalid();
+    };
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
     };
 
-    if decoded.claims.token_type != "refresh" {
-        return (
-            StatusCode::BAD_REQUEST,
+    let row = sqlx::query(
+        r#"
+        SELECT username, CASE WHEN is_superuser THEN 1 ELSE 0 END as is_superuser
+        FROM auth_user WHERE id = $1
+        "#,
+    )
+    .bind(uid)
+    .fetch_optional(pool)
+    .await;
+    let (username, is_superuser) = match row {
+        Ok(Some(r)) => (
+            r.get::<String, _>("username"),
+            r.get::<i64, _>("is_superuser") != 0,
+        ),
+        Ok(None) => return invalid(),
+        Err(e) => return errors::internal_response(&state, e),
+    };
+
+    match login_guard::retry_after(pool, &guard_keys(&username, &headers)).await {
+        Ok(Some(secs)) => return too_many_attempts_response(secs),
+        Ok(None) => {}
+        Err(e) => return errors::internal_response(&state, e),
+    }
+
+    match crate::totp::verify_second_factor(pool, uid, &body.code).await {
+        Ok(Some(factor)) => {
+            if factor == crate::totp::SecondFactor::RecoveryCode {
+                crate::audit::record(
+                    &state,
+                    &headers,
+                    crate::audit::AuditEvent::new("auth.2fa.recovery_code_used", "user")
+                        .target(uid)
+                        .actor(uid),
+                )
+                .await;
+            }
+            login_success(
+                &state,
+                pool,
+                &headers,
+                uid.to_string(),
+                username,
+                is_superuser,
+            )
+            .await
+        }
+        Ok(None) => {
+            if let Err(e) = register_login_failure(&state, pool, &headers, &username).await {
+                return errors::internal_response(&state, e);
+            }
+            (
+                StatusCode::UNAUTHORIZED,
+                Json(ErrorResponse {
+                    error: "验证码错误".to_string(),
+                }),
+            )
+                .into_response()
+        }
+        Err(e) => errors::internal_response(&state, e),
+    }
+}
+
+/// 新建会话并签发一对绑定该会话的令牌（登录、注册共用）。
+pub(crate) async fn issue_session_tokens(
+    state: &AppState,
+    pool: &sqlx::AnyPool,
+    user_id: &str,
+    headers: &axum::http::HeaderMap,
+) -> Result<(String, String), String> {
+    let uid = user_id.parse::<i64>().map_err(|e| e.to_string())?;
+    let user_agent = headers
+        .get(axum::http::header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.chars().take(512).collect::<String>());
+    let (sid, jti) =
+        auth_session::create_session(pool, uid, crate::audit::client_ip(headers), user_agent)
+            .await?;
+    let jwt = state.jwt();
+    Ok((
+        jwt.issue_session_access_token(user_id, &sid),
+        jwt.issue_refresh_token(user_id, &sid, &jti),
+    ))
+}
+
+fn invalid_refresh_response() -> axum::response::Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse {
+            error: "Invalid refresh token".to_string(),
+        }),
+    )
+        .into_response()
+}
+
+/// 刷新令牌轮换：每次刷新都签发新的刷新令牌；已轮换掉的令牌被再次出示时吊销整个会话。
+pub async fn refresh(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    headers: axum::http::HeaderMap,
+    Json(body): Json<RefreshRequest>,
+) -> axum::response::Response {
+    let jwt = state.jwt();
+    let Ok(decoded) = jwt.decode(&body.refresh_token) else {
+        return invalid_refresh_response();
+    };
+    let claims = decoded.claims;
+    if claims.token_type != "refresh" {
+        return invalid_refresh_response();
+    }
+    // 未绑定会话的旧版刷新令牌不再接受，需重新登录。
+    let (Some(sid), Some(jti), Ok(user_id)) =
+        (claims.sid.as_deref(), claims.jti.as_deref(), claims.sub.parse::<i64>())
+    else {
+        return invalid_refresh_response();
+    };
+
+    let pool = match state.pool() {
+        None => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: "database not configured".to_string(),
+                }),
+            )
+                .into_response();
+        }
+        Some(p) => p,
+    };
+
+    match auth_session::rotate(pool, sid, user_id, jti).await {
+        Ok(RotateOutcome::Rotated(new_jti)) => (
+            StatusCode::OK,
+            Json(RefreshResponse {
+                access_token: jwt.issue_session_access_token(&claims.sub, sid),
+                refresh_token: jwt.issue_refresh_token(&claims.sub, sid, &new_jti),
+            }),
+        )
+            .into_response(),
+        Ok(RotateOutcome::Reused) => {
+            state
+                .session_revocations()
+                .revoke_session(sid, Utc::now());
+            tracing::warn!(user_id, sid, "refresh token reuse detected, session revoked");
+            crate::audit::record(
+                &state,
+                &headers,
+                crate::audit::AuditEvent::new("auth.session.reuse_detected", "session")
+                    .target(sid)
+                    .actor(user_id),
+            )
+            .await;
+            invalid_refresh_response()
+        }
+        Ok(RotateOutcome::Invalid) => invalid_refresh_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse {
-                error: "Invalid refresh token".to_string(),
+                error: errors::internal_message(&state, e),
             }),
         )
-            .into_response();
+            .into_response(),
+    }
+}
+
+/// 退出登录：吊销请求所用访问令牌的会话，以及请求体中刷新令牌所属的会话。
+/// 只带刷新令牌也可以退出（访问令牌可能已过期）。
+pub async fn logout(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    headers: axum::http::HeaderMap,
+    body: Option<Json<LogoutRequest>>,
+) -> axum::response::Response {
+    let body = body.map(|Json(b)| b).unwrap_or_default();
+    let access = authenticate_claims(&state, &headers);
+    let refresh = body
+        .refresh_token
+        .as_deref()
+        .and_then(|t| state.jwt().decode(t).ok())
+        .map(|d| d.claims)
+        .filter(|c| c.token_type == "refresh");
+
+    let mut targets: Vec<(String, i64)> = Vec::new();
+    let claims = match (access, refresh) {
+        (Ok(a), Some(r)) => vec![a, r],
+        (Ok(a), None) => vec![a],
+        (Err(_), Some(r)) => vec![r],
+        (Err(resp), None) => return resp,
+    };
+    for c in claims {
+        if let (Some(sid), Ok(uid)) = (c.sid, c.sub.parse::<i64>())
+            && !targets.iter().any(|(s, _)| *s == sid)
+        {
+            targets.push((sid, uid));
+        }
     }
 
-    let access_token = jwt.issue_access_token(&decoded.claims.sub);
-    (StatusCode::OK, Json(RefreshResponse { access_token })).into_response()
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+    for (sid, uid) in targets {
+        match auth_session::revoke(pool, &sid, Some(uid), auth_session::REASON_LOGOUT).await {
+            Ok(true) => {
+                state
+                    .session_revocations()
+                    .revoke_session(&sid, Utc::now());
+                crate::audit::record(
+                    &state,
+                    &headers,
+                    crate::audit::AuditEvent::new("auth.logout", "session")
+                        .target(&sid)
+                        .actor(uid),
+                )
+                .await;
+            }
+            Ok(false) => {}
+            Err(e) => return errors::internal_response(&state, e),
+        }
+    }
+    StatusCode::NO_CONTENT.into_response()
+}
+
+pub async fn sessions_list(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    headers: axum::http::HeaderMap,
+) -> axum::response::Response {
+    let claims = match authenticate_claims(&state, &headers) {
+        Ok(c) => c,
+        Err(resp) => return resp,
+    };
+    let Ok(user_id) = claims.sub.parse::<i64>() else {
+        return invalid_token_response();
+    };
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+    match auth_session::list_active(pool, user_id, claims.sid.as_deref()).await {
+        Ok(items) => (StatusCode::OK, Json(serde_json::json!({ "items": items }))).into_response(),
+        Err(e) => errors::internal_response(&state, e),
+    }
+}
+
+/// 吊销自己的某个会话（如另一台设备）；不属于自己或已吊销的返回 404。
+pub async fn sessions_revoke(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Path(session_id): axum::extract::Path<String>,
+) -> axum::response::Response {
+    let user_id = match authenticate(&state, &headers) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+    let Ok(uid) = user_id.parse::<i64>() else {
+        return invalid_token_response();
+    };
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+    match auth_session::revoke(pool, &session_id, Some(uid), auth_session::REASON_USER_REVOKED)
+        .await
+    {
+        Ok(true) => {
+            state
+                .session_revocations()
+                .revoke_session(&session_id, Utc::now());
+            crate::audit::record(
+                &state,
+                &headers,
+                crate::audit::AuditEvent::new("auth.session.revoke", "session").target(&session_id),
+            )
+            .await;
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "detail": "Not found." })),
+        )
+            .into_response(),
+        Err(e) => errors::internal_response(&state, e),
+    }
+}
+
+/// 退出其他设备：吊销除当前会话外的全部会话。
+pub async fn sessions_revoke_others(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    headers: axum::http::HeaderMap,
+) -> axum::response::Response {
+    let claims = match authenticate_claims(&state, &headers) {
+        Ok(c) => c,
+        Err(resp) => return resp,
+    };
+    let Ok(uid) = claims.sub.parse::<i64>() else {
+        return invalid_token_response();
+    };
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+    let revoked = match auth_session::revoke_all_for_user(
+        pool,
+        uid,
+        claims.sid.as_deref(),
+        auth_session::REASON_USER_REVOKED,
+    )
+    .await
+    {
+        Ok(v) => v,
+        Err(e) => return errors::internal_response(&state, e),
+    };
+    let now = Utc::now();
+    for sid in &revoked {
+        state.session_revocations().revoke_session(sid, now);
+    }
+    if !revoked.is_empty() {
+        crate::audit::record(
+            &state,
+            &headers,
+            crate::audit::AuditEvent::new("auth.session.revoke_others", "user")
+                .target(uid)
+                .after(serde_json::json!({ "revoked": revoked })),
+        )
+        .await;
+    }
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({ "revoked": revoked.len() })),
+    )
+        .into_response()
 }
 
 pub async fn me(
@@ -267,6 +713,15 @@ pub async fn me(
     };
 
     let created_at_raw: String = row.get("date_joined");
+    let capabilities = match user_id.parse::<i64>() {
+        Ok(id) => crate::permissions::load_principal(pool, id)
+            .await
+            .ok()
+            .flatten()
+            .map(|p| p.capabilities())
+            .unwrap_or_default(),
+        Err(_) => Vec::new(),
+    };
     (
         StatusCode::OK,
         Json(MeResponse {
@@ -281,6 +736,7 @@ pub async fn me(
             } else {
                 "user".to_string()
             },
+            capabilities,
             created_at: crate::dbfmt::datetime_to_rfc3339(&created_at_raw),
         }),
     )
@@ -347,24 +803,41 @@ pub async fn change_password(
             .into_response();
     }
 
+    let Ok(uid) = user_id.parse::<i64>() else {
+        return invalid_token_response();
+    };
     let new_hash = django_password::hash_password(&body.new_password);
 
-    let updated = sqlx::query("UPDATE auth_user SET password = $1 WHERE CAST(id AS TEXT) = $2")
-        .bind(new_hash)
-        .bind(&user_id)
-        .execute(pool)
-        .await;
-
-    if let Err(e) = updated {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: errors::internal_message(&state, e),
-            }),
-        )
-            .into_response();
+    // 修改密码后所有已签发的令牌（含当前请求所用的会话与个人访问令牌）全部作废，需重新登录。
+    let now = Utc::now();
+    let (sessions, tokens) = match replace_password(pool, uid, &new_hash, now).await {
+        Ok(v) => v,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: errors::internal_message(&state, e),
+                }),
+            )
+                .into_response();
+        }
+    };
+    let registry = state.session_revocations();
+    for sid in &sessions {
+        registry.revoke_session(sid, now);
+    }
+    registry.set_tokens_valid_after(&user_id, now);
+    for id in &tokens {
+        state.personal_tokens().forget_id(id);
     }
 
+    crate::audit::record(
+        &state,
+        &headers,
+        crate::audit::AuditEvent::new("auth.password.change", "user").target(&user_id),
+    )
+    .await;
+
     (
         StatusCode::OK,
         Json(MessageResponse {
@@ -374,11 +847,50 @@ pub async fn change_password(
         .into_response()
 }
 
+/// 更新密码并在同一事务内吊销全部会话与个人访问令牌；返回被吊销的会话 id 与令牌 id。
+async fn replace_password(
+    pool: &sqlx::AnyPool,
+    uid: i64,
+    new_hash: &str,
+    now: chrono::DateTime<Utc>,
+) -> Result<(Vec<String>, Vec<String>), String> {
+    let pg = crate::db::database_kind_from_pool(pool) == crate::db::DatabaseKind::Postgres;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    sqlx::query("UPDATE auth_user SET password = $1 WHERE id = $2")
+        .bind(new_hash)
+        .bind(uid)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    let sessions = auth_session::revoke_all_for_user_in(
+        &mut tx,
+        pg,
+        uid,
+        None,
+        auth_session::REASON_PASSWORD_CHANGE,
+    )
+    .await?;
+    auth_session::set_tokens_valid_after(&mut tx, pg, uid, now).await?;
+    let tokens = personal_token::revoke_all_for_user(&mut tx, pg, uid).await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok((sessions, tokens))
+}
+
 #[allow(clippy::result_large_err)]
 pub(crate) fn authenticate(
     state: &AppState,
     headers: &axum::http::HeaderMap,
 ) -> Result<String, axum::response::Response> {
+    authenticate_claims(state, headers).map(|c| c.sub)
+}
+
+/// 校验访问令牌并返回完整声明（含会话 id）；已吊销的会话或修改密码前签发的令牌视为无效。
+/// 个人访问令牌返回 token_type 为 `personal`、jti 为令牌 id 的声明。
+#[allow(clippy::result_large_err)]
+pub(crate) fn authenticate_claims(
+    state: &AppState,
+    headers: &axum::http::HeaderMap,
+) -> Result<Claims, axum::response::Response> {
     let Some(auth) = headers.get(axum::http::header::AUTHORIZATION) else {
         return Err((
             StatusCode::UNAUTHORIZED,
@@ -398,12 +910,30 @@ pub(crate) fn authenticate(
         return Err(invalid_token_response());
     }
 
+    // 个人访问令牌已由 personal_token::authorize 中间件查库校验并缓存。
+    if crate::personal_token::is_personal_token(token) {
+        let Some((token_id, user_id)) = state.personal_tokens().resolve(token) else {
+            return Err(invalid_token_response());
+        };
+        return Ok(Claims {
+            sub: user_id,
+            exp: 0,
+            token_type: "personal".to_string(),
+            iat: None,
+            sid: None,
+            jti: Some(token_id),
+        });
+    }
+
     let jwt: &JwtService = state.jwt();
     let decoded = jwt.decode(token).map_err(|_| invalid_token_response())?;
     if decoded.claims.token_type != "access" {
         return Err(invalid_token_response());
     }
-    Ok(decoded.claims.sub)
+    if state.session_revocations().is_revoked(&decoded.claims) {
+        return Err(invalid_token_response());
+    }
+    Ok(decoded.claims)
 }
 
 pub(crate) fn invalid_token_response() -> axum::response::Response {
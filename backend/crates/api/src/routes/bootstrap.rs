@@ -1,5 +1,6 @@
 use axum::{Json, http::StatusCode, response::IntoResponse};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 
 use crate::django_password;
 use crate::routes::errors;
@@ -81,6 +82,7 @@ pub async fn verify(
 
 pub async fn initialize(
     axum::extract::State(state): axum::extract::State<AppState>,
+    headers: axum::http::HeaderMap,
     Json(body): Json<BootstrapInitializeRequest>,
 ) -> axum::response::Response {
     if state.config().system_initialized() {
@@ -176,6 +178,17 @@ pub async fn initialize(
         .set_allow_register(body.allow_register.unwrap_or(false));
     let _ = state.config().save();
 
+    // 初始化请求不带登录态，操作者留空；记录创建的管理员用户名。
+    crate::audit::record(
+        &state,
+        &headers,
+        crate::audit::AuditEvent::new("bootstrap.initialize", "system").after(json!({
+            "admin_username": admin_username,
+            "allow_register": body.allow_register.unwrap_or(false),
+        })),
+    )
+    .await;
+
     (
         StatusCode::OK,
         Json(BootstrapInitializeOk {
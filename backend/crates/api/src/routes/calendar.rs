@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use axum::{Json, extract::Query, http::StatusCode, response::IntoResponse};
+use chrono::{NaiveDate, Utc};
+use serde_json::json;
+
+use crate::routes::auth;
+use crate::routes::errors;
+use crate::state::AppState;
+use crate::trading_calendar::{self, Market};
+
+/// 区间查询最多返回的天数，避免一次生成过长的列表。
+const MAX_RANGE_DAYS: i64 = 3660;
+
+fn bad_request(msg: impl Into<String>) -> axum::response::Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({ "error": msg.into() })),
+    )
+        .into_response()
+}
+
+#[allow(clippy::result_large_err)]
+fn parse_market(raw: &str) -> Result<Market, axum::response::Response> {
+    Market::parse(raw).ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": format!("市场 {raw} 不存在（可选 cn/hk/us/qdii_hk/qdii_us）") })),
+        )
+            .into_response()
+    })
+}
+
+/// 参数缺省时返回 None，由调用方决定默认值（通常是北京时间的今天）。
+#[allow(clippy::result_large_err)]
+fn parse_date_param(
+    q: &HashMap<String, String>,
+    key: &str,
+) -> Result<Option<NaiveDate>, axum::response::Response> {
+    match q.get(key).map(|s| s.trim()).filter(|s| !s.is_empty()) {
+        None => Ok(None),
+        Some(raw) => NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+            .map(Some)
+            .map_err(|_| bad_request(format!("{key} 格式应为 YYYY-MM-DD"))),
+    }
+}
+
+pub async fn day(
+    axum::extract::Path(market): axum::extract::Path<String>,
+    Query(q): Query<HashMap<String, String>>,
+) -> axum::response::Response {
+    let market = match parse_market(&market) {
+        Ok(m) => m,
+        Err(resp) => return resp,
+    };
+    let date = match parse_date_param(&q, "date") {
+        Ok(d) => d.unwrap_or_else(|| trading_calendar::cn_today(Utc::now())),
+        Err(resp) => return resp,
+    };
+
+    let cal = trading_calendar::bundled();
+    Json(json!({
+        "market": market.as_str(),
+        "date": date.to_string(),
+        "is_trading_day": cal.is_trading_day(market, date),
+        "prev_trading_day": cal.prev_trading_day(market, date).to_string(),
+        "next_trading_day": cal.next_trading_day(market, date).to_string(),
+        "covered": cal.is_covered(market, date),
+    }))
+    .into_response()
+}
+
+pub async fn offset(
+    axum::extract::Path(market): axum::extract::Path<String>,
+    Query(q): Query<HashMap<String, String>>,
+) -> axum::response::Response {
+    let market = match parse_market(&market) {
+        Ok(m) => m,
+        Err(resp) => return resp,
+    };
+    let date = match parse_date_param(&q, "date") {
+        Ok(d) => d.unwrap_or_else(|| trading_calendar::cn_today(Utc::now())),
+        Err(resp) => return resp,
+    };
+    let n = match q.get("n").map(|s| s.trim().parse::<i64>()) {
+        None => 1,
+        Some(Ok(v)) if v.abs() <= MAX_RANGE_DAYS => v,
+        Some(_) => {
+            return bad_request(format!(
+                "n 应为 -{MAX_RANGE_DAYS}..={MAX_RANGE_DAYS} 的整数"
+            ));
+        }
+    };
+
+    let cal = trading_calendar::bundled();
+    let result = cal.add_trading_days(market, date, n);
+    Json(json!({
+        "market": market.as_str(),
+        "date": date.to_string(),
+        "n": n,
+        "result": result.to_string(),
+        "covered": cal.is_covered(market, date) && cal.is_covered(market, result),
+    }))
+    .into_response()
+}
+
+pub async fn days(
+    axum::extract::Path(market): axum::extract::Path<String>,
+    Query(q): Query<HashMap<String, String>>,
+) -> axum::response::Response {
+    let market = match parse_market(&market) {
+        Ok(m) => m,
+        Err(resp) => return resp,
+    };
+    let (start, end) = match (parse_date_param(&q, "start"), parse_date_param(&q, "end")) {
+        (Ok(Some(s)), Ok(Some(e))) => (s, e),
+        (Err(resp), _) | (_, Err(resp)) => return resp,
+        _ => return bad_request("start/end 不能为空"),
+    };
+    if end < start {
+        return bad_request("end 不能早于 start");
+    }
+    if (end - start).num_days() > MAX_RANGE_DAYS {
+        return bad_request(format!("区间不能超过 {MAX_RANGE_DAYS} 天"));
+    }
+
+    let cal = trading_calendar::bundled();
+    let list: Vec<String> = cal
+        .trading_days_between(market, start, end)
+        .into_iter()
+        .map(|d| d.to_string())
+        .collect();
+    Json(json!({
+        "market": market.as_str(),
+        "start": start.to_string(),
+        "end": end.to_string(),
+        "covered": cal.is_covered(market, start) && cal.is_covered(market, end),
+        "count": list.len(),
+        "days": list,
+    }))
+    .into_response()
+}
+
+pub async fn verify(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    headers: axum::http::HeaderMap,
+    Query(q): Query<HashMap<String, String>>,
+) -> axum::response::Response {
+    if let Err(resp) = auth::authenticate(&state, &headers) {
+        return resp;
+    }
+    let Some(pool) = state.pool() else {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "database not configured" })),
+        )
+            .into_response();
+    };
+
+    let market = match parse_market(q.get("market").map(|s| s.as_str()).unwrap_or("cn")) {
+        Ok(m) => m,
+        Err(resp) => return resp,
+    };
+    // 缺省用沪深300 校验 A 股日历。
+    let index_code = q
+        .get("index_code")
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .unwrap_or("1.000300");
+    // index_daily_price 目前只由 eastmoney K 线写入。
+    let source_name = q.get("source").map(|s| s.as_str()).unwrap_or("eastmoney");
+    let today = trading_calendar::cn_today(Utc::now());
+    let start = match parse_date_param(&q, "start") {
+        Ok(d) => d.unwrap_or(today - chrono::Duration::days(365)),
+        Err(resp) => return resp,
+    };
+    let end = match parse_date_param(&q, "end") {
+        Ok(d) => d.unwrap_or(today),
+        Err(resp) => return resp,
+    };
+
+    match trading_calendar::verify_against_index(
+        pool,
+        trading_calendar::bundled(),
+        market,
+        index_code,
+        source_name,
+        start,
+        end,
+    )
+    .await
+    {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => errors::internal_response(&state, e),
+    }
+}
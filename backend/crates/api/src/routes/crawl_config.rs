@@ -1,55 +1,13 @@
 use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use sqlx::Row;
 
+use crate::crawl::{priority, scheduler};
+use crate::permissions::{CrawlAdmin, Require};
 use crate::sources;
 use crate::state::AppState;
 
-async fn require_staff(
-    state: &AppState,
-    headers: &axum::http::HeaderMap,
-) -> Result<(), axum::response::Response> {
-    let pool = match state.pool() {
-        Some(p) => p,
-        None => {
-            return Err((
-                StatusCode::SERVICE_UNAVAILABLE,
-                Json(json!({ "error": "数据库未连接" })),
-            )
-                .into_response());
-        }
-    };
-
-    let user_id = match crate::routes::auth::authenticate(state, headers) {
-        Ok(v) => v,
-        Err(resp) => return Err(resp),
-    };
-    let user_id_i64 = user_id
-        .parse::<i64>()
-        .map_err(|_| crate::routes::auth::invalid_token_response())?;
-
-    let is_staff = match sqlx::query("SELECT is_staff FROM auth_user WHERE id = $1")
-        .bind(user_id_i64)
-        .fetch_optional(pool)
-        .await
-    {
-        Ok(Some(row)) => row
-            .try_get::<bool, _>("is_staff")
-            .unwrap_or_else(|_| row.try_get::<i64, _>("is_staff").unwrap_or(0) != 0),
-        _ => false,
-    };
-
-    if !is_staff {
-        return Err((
-            StatusCode::FORBIDDEN,
-            Json(json!({ "detail": "You do not have permission to perform this action." })),
-        )
-            .into_response());
-    }
-    Ok(())
-}
-
 #[derive(Debug, Serialize)]
 pub struct CrawlConfigResponse {
     pub crawl_enabled: bool,
@@ -64,13 +22,13 @@ pub struct CrawlConfigResponse {
 }
 
 pub async fn admin_get_config(
+    _: Require<CrawlAdmin>,
     State(state): State<AppState>,
-    headers: axum::http::HeaderMap,
 ) -> axum::response::Response {
-    if let Err(resp) = require_staff(&state, &headers).await {
-        return resp;
-    }
+    (StatusCode::OK, Json(current_config(&state))).into_response()
+}
 
+fn current_config(state: &AppState) -> CrawlConfigResponse {
     let cfg = state.config();
     let source = cfg
         .get_string("crawl_source")
@@ -79,21 +37,17 @@ pub async fn admin_get_config(
         .unwrap_or(sources::SOURCE_TIANTIAN)
         .to_string();
 
-    (
-        StatusCode::OK,
-        Json(CrawlConfigResponse {
-            crawl_enabled: cfg.get_bool("crawl_enabled", true),
-            crawl_source: source,
-            crawl_tick_interval_seconds: cfg.get_i64("crawl_tick_interval_seconds", 30),
-            crawl_enqueue_max_jobs: cfg.get_i64("crawl_enqueue_max_jobs", 200),
-            crawl_daily_run_limit: cfg.get_i64("crawl_daily_run_limit", 3000),
-            crawl_run_max_jobs: cfg.get_i64("crawl_run_max_jobs", 20),
-            crawl_per_job_delay_ms: cfg.get_i64("crawl_per_job_delay_ms", 250),
-            crawl_per_job_jitter_ms: cfg.get_i64("crawl_per_job_jitter_ms", 200),
-            crawl_source_fallbacks: cfg.get_string("crawl_source_fallbacks").unwrap_or_default(),
-        }),
-    )
-        .into_response()
+    CrawlConfigResponse {
+        crawl_enabled: cfg.get_bool("crawl_enabled", true),
+        crawl_source: source,
+        crawl_tick_interval_seconds: cfg.get_i64("crawl_tick_interval_seconds", 30),
+        crawl_enqueue_max_jobs: cfg.get_i64("crawl_enqueue_max_jobs", 200),
+        crawl_daily_run_limit: cfg.get_i64("crawl_daily_run_limit", 3000),
+        crawl_run_max_jobs: cfg.get_i64("crawl_run_max_jobs", 20),
+        crawl_per_job_delay_ms: cfg.get_i64("crawl_per_job_delay_ms", 250),
+        crawl_per_job_jitter_ms: cfg.get_i64("crawl_per_job_jitter_ms", 200),
+        crawl_source_fallbacks: cfg.get_string("crawl_source_fallbacks").unwrap_or_default(),
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -110,14 +64,12 @@ pub struct SetCrawlConfigRequest {
 }
 
 pub async fn admin_set_config(
+    _: Require<CrawlAdmin>,
     State(state): State<AppState>,
     headers: axum::http::HeaderMap,
     Json(body): Json<SetCrawlConfigRequest>,
 ) -> axum::response::Response {
-    if let Err(resp) = require_staff(&state, &headers).await {
-        return resp;
-    }
-
+    let before = json!(current_config(&state));
     let cfg = state.config();
 
     if let Some(v) = body.crawl_enabled {
@@ -168,5 +120,85 @@ pub async fn admin_set_config(
             .into_response();
     }
 
+    crate::audit::record(
+        &state,
+        &headers,
+        crate::audit::AuditEvent::new("crawl_config.update", "config")
+            .target("crawl")
+            .before(before)
+            .after(json!(current_config(&state))),
+    )
+    .await;
+
     (StatusCode::OK, Json(json!({ "message": "ok" }))).into_response()
 }
+
+/// 调度解释：列出任务的当前优先级、按评分模型重算后的优先级/间隔及各项得分来源。
+/// 支持 `?fund_code=&job_type=&source=&limit=`（limit 缺省 50，最大 500）。
+pub async fn admin_schedule_explain(
+    _: Require<CrawlAdmin>,
+    State(state): State<AppState>,
+    axum::extract::Query(q): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> axum::response::Response {
+    let Some(pool) = state.pool() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({ "error": "数据库未连接" })),
+        )
+            .into_response();
+    };
+
+    let cfg = state.config();
+    let source_raw = q
+        .get("source")
+        .cloned()
+        .or_else(|| cfg.get_string("crawl_source"))
+        .unwrap_or_else(|| sources::SOURCE_TIANTIAN.to_string());
+    let Some(source_name) = sources::normalize_source_name(&source_raw) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("未知数据源: {source_raw}") })),
+        )
+            .into_response();
+    };
+    let limit = q
+        .get("limit")
+        .and_then(|s| s.trim().parse::<i64>().ok())
+        .unwrap_or(50)
+        .clamp(1, 500);
+    let filter = priority::JobFilter {
+        fund_code: q.get("fund_code").map(|s| s.as_str()),
+        job_type: q.get("job_type").map(|s| s.as_str()),
+        rescore_candidates: false,
+    };
+
+    let daily_limit = cfg.get_i64("crawl_daily_run_limit", 3000).clamp(0, 1_000_000);
+    let used = if daily_limit > 0 {
+        let key = scheduler::daily_counter_key_all(source_name, "run");
+        match scheduler::get_counter(pool, &key).await {
+            Ok(v) => v,
+            Err(e) => return crate::routes::errors::internal_response(&state, e),
+        }
+    } else {
+        0
+    };
+    let budget = priority::Budget { daily_limit, used };
+
+    let now = Utc::now();
+    match priority::explain_jobs(pool, source_name, &filter, limit, &budget, now).await {
+        Ok(jobs) => Json(json!({
+            "source": source_name,
+            "now": now,
+            "expected_nav_date": priority::expected_nav_date(now, false).to_string(),
+            "budget": {
+                "daily_limit": budget.daily_limit,
+                "used": budget.used,
+                "remaining": budget.remaining(),
+                "delay_factor": budget.delay_factor(),
+            },
+            "jobs": jobs,
+        }))
+        .into_response(),
+        Err(e) => crate::routes::errors::internal_response(&state, e),
+    }
+}
@@ -7,6 +7,8 @@ use axum::{
 use serde::Deserialize;
 use serde_json::json;
 
+use crate::forecast::model::{ForecastModel, MODEL_TYPE_CONFIG_KEY};
+use crate::ml::model::ModelKind;
 use crate::routes::{auth, errors};
 use crate::sources;
 use crate::state::AppState;
@@ -16,6 +18,8 @@ use crate::tasks;
 pub struct TrainForecastModelBody {
     pub source: Option<String>,
     pub model_name: Option<String>,
+    /// `linear` / `gbdt`；缺省取配置 `forecast_model_type`。
+    pub model_type: Option<String>,
     pub horizon: Option<i64>,
     pub lag_k: Option<i64>,
     pub priority: Option<i64>,
@@ -60,10 +64,23 @@ pub async fn train_model(
         }
     };
 
+    let model_kind = match body.model_type.as_deref() {
+        Some(s) => match ModelKind::parse(s) {
+            Some(k) => k,
+            None => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({ "error": format!("unknown model_type: {s}") })),
+                )
+                    .into_response();
+            }
+        },
+        None => ModelKind::from_config(state.config(), MODEL_TYPE_CONFIG_KEY),
+    };
     let model_name = body
         .model_name
         .as_deref()
-        .unwrap_or("global_ols_v1")
+        .unwrap_or(ForecastModel::default_name(model_kind))
         .trim()
         .to_string();
     if model_name.is_empty() {
@@ -81,6 +98,7 @@ pub async fn train_model(
     let payload = json!({
       "source": source_name,
       "model_name": model_name,
+      "model_type": model_kind.as_str(),
       "horizon": horizon,
       "lag_k": lag_k
     });
@@ -96,17 +114,17 @@ pub async fn train_model(
         }
     };
 
-    // 立即唤醒后台 worker，避免等待 tick。
-    state.crawl_notify().notify_one();
+    // 立即唤醒任务 worker 池，避免等待轮询。
+    state.task_notify().notify_one();
 
-    let pool2 = pool.clone();
-    if !cfg!(test) {
-        tokio::spawn(async move {
-            if let Err(e) = tasks::run_due_task_jobs(&pool2, 1).await {
-                tracing::warn!(error = %e, "task queue run_due_task_jobs failed (route trigger)");
-            }
-        });
-    }
+    crate::audit::record(
+        &state,
+        &headers,
+        crate::audit::AuditEvent::new("forecast.model.train", "task_job")
+            .target(&task_id)
+            .after(payload),
+    )
+    .await;
 
     (StatusCode::ACCEPTED, Json(EnqueueTaskOut { task_id })).into_response()
 }
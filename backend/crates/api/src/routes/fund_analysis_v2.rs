@@ -168,6 +168,8 @@ pub struct FundAnalysisV2ComputeBody {
     pub every_n: Option<i64>,
     pub amount: Option<f64>,
     pub refer_index_code: Option<String>,
+    /// 预测扇形的期限（交易日），默认 5/20/60/120。
+    pub forecast_horizons: Option<Vec<i64>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -219,6 +221,10 @@ pub async fn compute(
     }
     windows.truncate(8);
 
+    let mut forecast_horizons = body.forecast_horizons.unwrap_or_default();
+    forecast_horizons.retain(|h| *h >= 1 && *h <= 250);
+    forecast_horizons.truncate(8);
+
     let payload = json!({
       "fund_code": fund_code.trim(),
       "source": source,
@@ -229,6 +235,7 @@ pub async fn compute(
       "every_n": body.every_n.unwrap_or(20),
       "amount": body.amount.unwrap_or(1.0),
       "refer_index_code": body.refer_index_code.as_deref().unwrap_or("1.000001").trim(),
+      "forecast_horizons": forecast_horizons,
       "quant_service_url": quant_base_url(&state),
     });
 
@@ -243,17 +250,8 @@ pub async fn compute(
         }
     };
 
-    // 立即唤醒后台 worker，避免等待 tick。
-    state.crawl_notify().notify_one();
-
-    let pool2 = pool.clone();
-    if !cfg!(test) {
-        tokio::spawn(async move {
-            if let Err(e) = tasks::run_due_task_jobs(&pool2, 1).await {
-                tracing::warn!(error = %e, "task queue run_due_task_jobs failed (route trigger)");
-            }
-        });
-    }
+    // 立即唤醒任务 worker 池，避免等待轮询。
+    state.task_notify().notify_one();
 
     (StatusCode::ACCEPTED, Json(EnqueueTaskOut { task_id })).into_response()
 }
@@ -6,6 +6,7 @@ use sqlx::Row;
 
 use crate::analytics;
 use crate::dbfmt;
+use crate::ml;
 use crate::routes::auth;
 use crate::routes::errors;
 use crate::sources;
@@ -354,7 +355,7 @@ pub async fn retrieve(
             },
         });
 
-    // Prefer "关联板块" (fund_relate_theme). Fallback to fund_type when missing.
+    // Prefer "关联板块" (fund_relate_theme). Fallback to the return cluster, then fund_type when missing.
     let themes_rows = sqlx::query(
         r#"
         SELECT sec_code, sec_name
@@ -403,6 +404,15 @@ pub async fn retrieve(
                 candidates.push((vs, ce));
             }
         }
+    } else if let Some((peer_code, peer_name)) = ml::cluster::fund_cluster_peer(pool, code, source_name)
+        .await
+        .ok()
+        .flatten()
+    {
+        let (vs, ce) = compute_value_score_and_ce_by_sector(pool, &peer_code, &peer_name, &ctx).await;
+        if let Some(vs) = vs {
+            candidates.push((vs, ce));
+        }
     } else {
         let fund_type_row = sqlx::query("SELECT fund_type FROM fund WHERE fund_code = $1 LIMIT 1")
             .bind(code)
@@ -763,7 +773,19 @@ async fn compute_value_score_and_ce_by_sector(
     sec_name: &str,
     ctx: &PeerComputeCtx<'_>,
 ) -> (Option<ValueScoreOut>, Option<CeOut>) {
-    let rows = sqlx::query(
+    let is_cluster = ml::cluster::is_cluster_peer(sec_code);
+    let sql = if is_cluster {
+        r#"
+        SELECT f.fund_code as fund_code
+        FROM fund f
+        JOIN fund_nav_history h ON h.fund_id = f.id
+        JOIN fund_cluster_member m ON m.fund_code = f.fund_code AND m.source_name = h.source_name
+        WHERE m.peer_code = $1 AND h.source_name = $2
+        GROUP BY f.fund_code
+        ORDER BY f.fund_code ASC
+        LIMIT 500
+        "#
+    } else {
         r#"
         SELECT f.fund_code as fund_code
         FROM fund f
@@ -773,8 +795,9 @@ async fn compute_value_score_and_ce_by_sector(
         GROUP BY f.fund_code
         ORDER BY f.fund_code ASC
         LIMIT 500
-        "#,
-    )
+        "#
+    };
+    let rows = sqlx::query(sql)
     .bind(sec_code)
     .bind(ctx.source_name)
     .fetch_all(pool)
@@ -863,7 +886,7 @@ async fn compute_value_score_and_ce_by_sector(
     let value_score =
         analytics::value_score::compute_value_score(&samples, ctx.target_code, &weights);
     let value_score_out = value_score.map(|vs| ValueScoreOut {
-        peer_kind: "sector".to_string(),
+        peer_kind: if is_cluster { "cluster" } else { "sector" }.to_string(),
         peer_name: sec_name.to_string(),
         peer_code: Some(sec_code.to_string()),
         fund_type: sec_name.to_string(),
@@ -5,6 +5,7 @@ use serde_json::json;
 use sqlx::Row;
 
 use crate::ml;
+use crate::ml::explain::{SignalExplanation, SnapshotExplanations};
 use crate::routes::auth;
 use crate::routes::errors;
 use crate::sources;
@@ -20,6 +21,11 @@ pub struct FundSignalsQuery {
 pub struct HorizonProbaOut {
     pub p_5t: Option<f64>,
     pub p_20t: Option<f64>,
+    /// 主要驱动特征与板块基准；旧快照或无法归因的模型为 null。
+    #[serde(default)]
+    pub explain_5t: Option<SignalExplanation>,
+    #[serde(default)]
+    pub explain_20t: Option<SignalExplanation>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -179,30 +185,16 @@ pub async fn retrieve(
             .into_response();
     };
 
-    // 取关联板块列表（最多 5 个 + 全市场兜底，共 6 个，避免一次请求过慢）
-    let peer_rows = sqlx::query(
-        r#"
-        SELECT sec_code, sec_name
-        FROM fund_relate_theme
-        WHERE fund_code = $1
-        GROUP BY sec_code, sec_name
-        ORDER BY sec_code ASC
-        LIMIT 6
-        "#,
-    )
-    .bind(code)
-    .fetch_all(pool)
-    .await;
-
-    let peer_rows = peer_rows.unwrap_or_default();
+    // 取关联板块列表（最多 5 个 + 全市场兜底，共 6 个，避免一次请求过慢）；无关联板块时回退到聚类同类组
+    let peer_rows = ml::cluster::fund_peers(pool, code, source_name, 6)
+        .await
+        .unwrap_or_default();
 
     // 始终包含“全市场”，确保 ML 能基于全量基金数据训练/推断（同时也作为关联板块缺失时的兜底）。
     let mut peers_list: Vec<(String, String)> = Vec::new();
     peers_list.push((ml::train::PEER_CODE_ALL.to_string(), "全市场".to_string()));
 
-    for r in peer_rows {
-        let peer_code: String = r.get("sec_code");
-        let peer_name: String = r.get("sec_name");
+    for (peer_code, peer_name) in peer_rows {
         if peer_code.trim().is_empty() || peer_name.trim().is_empty() {
             continue;
         }
@@ -234,6 +226,7 @@ pub async fn retrieve(
                   dip_buy_proba_20t,
                   magic_rebound_proba_5t,
                   magic_rebound_proba_20t,
+                  explain_json,
                   CAST(computed_at AS TEXT) as computed_at
                 FROM fund_signal_snapshot
                 WHERE fund_code = $1 AND peer_code = $2 AND CAST(as_of_date AS TEXT) = $3
@@ -252,6 +245,12 @@ pub async fn retrieve(
 
         let (position_percentile_0_100, position_bucket, dip_buy, magic_rebound, computed_at) =
             if let Some(s) = snap {
+                let explain = SnapshotExplanations::from_json(
+                    s.try_get::<Option<String>, _>("explain_json")
+                        .ok()
+                        .flatten()
+                        .as_deref(),
+                );
                 (
                     s.try_get::<Option<f64>, _>("position_percentile_0_100")
                         .ok()
@@ -268,6 +267,8 @@ pub async fn retrieve(
                             .try_get::<Option<f64>, _>("dip_buy_proba_20t")
                             .ok()
                             .flatten(),
+                        explain_5t: explain.dip_buy_5t,
+                        explain_20t: explain.dip_buy_20t,
                     },
                     HorizonProbaOut {
                         p_5t: s
@@ -278,6 +279,8 @@ pub async fn retrieve(
                             .try_get::<Option<f64>, _>("magic_rebound_proba_20t")
                             .ok()
                             .flatten(),
+                        explain_5t: explain.magic_rebound_5t,
+                        explain_20t: explain.magic_rebound_20t,
                     },
                     s.try_get::<Option<String>, _>("computed_at").ok().flatten(),
                 )
@@ -288,10 +291,14 @@ pub async fn retrieve(
                     HorizonProbaOut {
                         p_5t: None,
                         p_20t: None,
+                        explain_5t: None,
+                        explain_20t: None,
                     },
                     HorizonProbaOut {
                         p_5t: None,
                         p_20t: None,
+                        explain_5t: None,
+                        explain_20t: None,
                     },
                     None,
                 )
@@ -1,5 +1,5 @@
 use axum::{Json, extract::Query, http::StatusCode, response::IntoResponse};
-use chrono::{Datelike, Duration, NaiveDate, Utc};
+use chrono::{Duration, NaiveDate, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
@@ -10,11 +10,15 @@ use uuid::Uuid;
 
 use crate::db::DatabaseKind;
 use crate::eastmoney;
+use crate::fund_profile;
+use crate::intraday;
+use crate::permissions::{CrawlAdmin, Require};
 use crate::routes::auth;
 use crate::routes::errors;
 use crate::sources;
 use crate::state::AppState;
 use crate::tasks;
+use crate::trading_calendar;
 
 async fn upsert_basic_fund(
     pool: &sqlx::AnyPool,
@@ -135,6 +139,16 @@ pub struct FundItem {
     pub updated_at: String,
 }
 
+/// 单只基金详情：在 FundItem 基础上附带基金资料（公司/经理/规模/费率）。
+#[derive(Debug, Serialize)]
+pub struct FundDetail {
+    #[serde(flatten)]
+    pub fund: FundItem,
+    pub profile: Option<fund_profile::FundProfileOut>,
+    pub managers: Vec<fund_profile::ManagerTenureOut>,
+    pub aum_history: Vec<fund_profile::ScalePointOut>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct FundListResponse {
     pub count: i64,
@@ -568,6 +582,8 @@ pub async fn retrieve(
         )
             .into_response();
     };
+    // 访问记录供抓取优先级评分参考（尽力而为）。
+    let _ = crate::crawl::priority::record_fund_view(pool, &fund_code).await;
 
     let item = FundItem {
         id: row.get::<String, _>("id"),
@@ -585,7 +601,28 @@ pub async fn retrieve(
         updated_at: crate::dbfmt::datetime_to_rfc3339(&row.get::<String, _>("updated_at")),
     };
 
-    (StatusCode::OK, Json(item)).into_response()
+    // 基金资料由 profile_sync 后台任务补齐；缺失时返回空，不阻塞详情接口。
+    let profile = fund_profile::load_fund_profile(pool, &item.fund_code)
+        .await
+        .ok()
+        .flatten();
+    let managers = fund_profile::load_manager_tenures(pool, &item.fund_code, Utc::now().date_naive())
+        .await
+        .unwrap_or_default();
+    let aum_history = fund_profile::load_scale_history(pool, &item.fund_code)
+        .await
+        .unwrap_or_default();
+
+    (
+        StatusCode::OK,
+        Json(FundDetail {
+            fund: item,
+            profile,
+            managers,
+            aum_history,
+        }),
+    )
+        .into_response()
 }
 
 pub async fn estimate(
@@ -674,6 +711,7 @@ pub async fn estimate(
     };
     let fund_id: String = row.get("id");
     let fund_name: String = row.get("fund_name");
+    let _ = crate::crawl::priority::record_fund_view(pool, &fund_code).await;
 
     let source_name_raw = q
         .get("source")
@@ -711,6 +749,15 @@ pub async fn estimate(
                     estimate_nav,
                 )
                 .await;
+                let _ = intraday::record_sample(
+                    pool,
+                    &fund_code,
+                    sources::SOURCE_TIANTIAN,
+                    data.estimate_time,
+                    data.estimate_nav,
+                    Some(data.estimate_growth),
+                )
+                .await;
 
                 (
                     StatusCode::OK,
@@ -1191,6 +1238,15 @@ pub async fn batch_estimate(
                                 data.estimate_nav,
                             )
                             .await;
+                            let _ = intraday::record_sample(
+                                &pool,
+                                &code,
+                                sources::SOURCE_TIANTIAN,
+                                data.estimate_time,
+                                data.estimate_nav,
+                                Some(data.estimate_growth),
+                            )
+                            .await;
 
                             (
                                 code.clone(),
@@ -1368,6 +1424,7 @@ pub struct BatchUpdateNavRequest {
 
 pub async fn batch_update_nav(
     axum::extract::State(state): axum::extract::State<AppState>,
+    headers: axum::http::HeaderMap,
     Json(body): Json<BatchUpdateNavRequest>,
 ) -> axum::response::Response {
     let fund_codes = body.fund_codes.unwrap_or_default();
@@ -1525,6 +1582,21 @@ pub async fn batch_update_nav(
         }
     }
 
+    let updated = results
+        .values()
+        .filter(|v| v.get("latest_nav").is_some())
+        .count();
+    crate::audit::record(
+        &state,
+        &headers,
+        crate::audit::AuditEvent::new("funds.batch_update_nav", "fund").after(json!({
+            "source": source_name,
+            "fund_codes": results.keys().collect::<Vec<_>>(),
+            "updated": updated,
+        })),
+    )
+    .await;
+
     (StatusCode::OK, Json(serde_json::Value::Object(results))).into_response()
 }
 
@@ -1605,17 +1677,8 @@ pub async fn prices_refresh_batch_async(
             }
         };
 
-    // 唤醒后台 worker，避免等待 tick。
-    state.crawl_notify().notify_one();
-
-    let pool2 = pool.clone();
-    if !cfg!(test) {
-        tokio::spawn(async move {
-            if let Err(e) = tasks::run_due_task_jobs(&pool2, 1).await {
-                tracing::warn!(error = %e, "task queue run_due_task_jobs failed (route trigger)");
-            }
-        });
-    }
+    // 立即唤醒任务 worker 池，避免等待轮询。
+    state.task_notify().notify_one();
 
     (StatusCode::ACCEPTED, Json(json!({ "task_id": task_id }))).into_response()
 }
@@ -1839,18 +1902,10 @@ pub async fn query_nav(
 }
 
 pub async fn sync(
+    _: Require<CrawlAdmin>,
     axum::extract::State(state): axum::extract::State<AppState>,
     headers: axum::http::HeaderMap,
 ) -> axum::response::Response {
-    let user_id = match auth::authenticate(&state, &headers) {
-        Ok(v) => v,
-        Err(resp) => return resp,
-    };
-    let user_id_i64 = match user_id.parse::<i64>() {
-        Ok(v) => v,
-        Err(_) => return auth::invalid_token_response(),
-    };
-
     let pool = match state.pool() {
         None => {
             return (
@@ -1864,25 +1919,6 @@ pub async fn sync(
         Some(p) => p,
     };
 
-    // Django IsAdminUser：要求 is_staff（不要求 is_superuser）
-    let is_admin = match sqlx::query("SELECT is_staff FROM auth_user WHERE id = $1")
-        .bind(user_id_i64)
-        .fetch_optional(pool)
-        .await
-    {
-        Ok(Some(row)) => row
-            .try_get::<bool, _>("is_staff")
-            .unwrap_or_else(|_| row.try_get::<i64, _>("is_staff").unwrap_or(0) != 0),
-        _ => false,
-    };
-
-    if !is_admin {
-        return (
-            StatusCode::FORBIDDEN,
-            Json(json!({ "detail": "You do not have permission to perform this action." })),
-        )
-            .into_response();
-    }
 
     let client = match eastmoney::build_client() {
         Ok(c) => c,
@@ -1962,33 +1998,23 @@ pub async fn sync(
         }
     }
 
-    (
-        StatusCode::OK,
-        Json(json!({
-          "created": created,
-          "updated": updated,
-          "total": funds.len()
-        })),
+    let summary = json!({
+      "created": created,
+      "updated": updated,
+      "total": funds.len()
+    });
+    crate::audit::record(
+        &state,
+        &headers,
+        crate::audit::AuditEvent::new("funds.sync", "fund").after(summary.clone()),
     )
-        .into_response()
-}
+    .await;
 
-fn is_trading_day(d: NaiveDate) -> bool {
-    // 近似实现：周一至周五视为交易日。
-    // Python 版本使用 chinese_calendar 会考虑法定节假日与调休；若需要 100% 对齐，可在此处替换实现。
-    let wd = d.weekday().num_days_from_monday();
-    wd < 5
+    (StatusCode::OK, Json(summary)).into_response()
 }
 
-fn get_last_trading_day(mut d: NaiveDate) -> NaiveDate {
-    let original = d;
-    for _ in 0..30 {
-        if is_trading_day(d) {
-            return d;
-        }
-        d = d.pred_opt().unwrap_or(d);
-    }
-    original
+fn get_last_trading_day(d: NaiveDate) -> NaiveDate {
+    trading_calendar::bundled().last_trading_day_on_or_before(trading_calendar::Market::Cn, d)
 }
 
 async fn sync_nav_history_for_date(
@@ -0,0 +1,355 @@
+use axum::{Json, extract::Query, http::StatusCode, response::IntoResponse};
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::holdings::{self, HoldingOut};
+use crate::routes::auth;
+use crate::routes::errors;
+use crate::state::AppState;
+
+#[allow(clippy::result_large_err)]
+fn require_pool(state: &AppState) -> Result<&sqlx::AnyPool, axum::response::Response> {
+    state.pool().ok_or_else(|| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "database not configured" })),
+        )
+            .into_response()
+    })
+}
+
+fn not_found() -> axum::response::Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "detail": "Not found." })),
+    )
+        .into_response()
+}
+
+pub async fn fund_holdings(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Path(fund_code): axum::extract::Path<String>,
+) -> axum::response::Response {
+    if let Err(resp) = auth::authenticate(&state, &headers) {
+        return resp;
+    }
+    let pool = match require_pool(&state) {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
+
+    let code = fund_code.trim();
+    let (stock_date, stocks) =
+        match holdings::load_latest_holdings(pool, code, holdings::ASSET_STOCK).await {
+            Ok(v) => v,
+            Err(e) => return errors::internal_response(&state, e),
+        };
+    let (bond_date, bonds) =
+        match holdings::load_latest_holdings(pool, code, holdings::ASSET_BOND).await {
+            Ok(v) => v,
+            Err(e) => return errors::internal_response(&state, e),
+        };
+    let (industry_date, industries) = match holdings::load_latest_industries(pool, code).await {
+        Ok(v) => v,
+        Err(e) => return errors::internal_response(&state, e),
+    };
+
+    Json(json!({
+        "fund_code": code,
+        "stocks": { "report_date": stock_date, "items": stocks },
+        "bonds": { "report_date": bond_date, "items": bonds },
+        "industries": { "report_date": industry_date, "items": industries },
+    }))
+    .into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OverlapQuery {
+    pub fund_a: String,
+    pub fund_b: String,
+}
+
+pub async fn overlap(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    headers: axum::http::HeaderMap,
+    Query(q): Query<OverlapQuery>,
+) -> axum::response::Response {
+    if let Err(resp) = auth::authenticate(&state, &headers) {
+        return resp;
+    }
+    let pool = match require_pool(&state) {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
+
+    let a = q.fund_a.trim();
+    let b = q.fund_b.trim();
+    if a.is_empty() || b.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "fund_a/fund_b 不能为空" })),
+        )
+            .into_response();
+    }
+
+    let (_, ha) = match holdings::load_latest_holdings(pool, a, holdings::ASSET_STOCK).await {
+        Ok(v) => v,
+        Err(e) => return errors::internal_response(&state, e),
+    };
+    let (_, hb) = match holdings::load_latest_holdings(pool, b, holdings::ASSET_STOCK).await {
+        Ok(v) => v,
+        Err(e) => return errors::internal_response(&state, e),
+    };
+
+    Json(holdings::holdings_overlap(a, &ha, b, &hb)).into_response()
+}
+
+/// 账户（含子账户）下每只基金的市值：holding_share × 最新净值（缺失时回退持仓净值）。
+async fn load_account_fund_values(
+    pool: &sqlx::AnyPool,
+    account_id: &str,
+    user_id: i64,
+) -> Result<Option<Vec<(String, f64)>>, String> {
+    let owned =
+        sqlx::query("SELECT 1 as ok FROM account WHERE CAST(id AS TEXT) = $1 AND user_id = $2")
+            .bind(account_id)
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    if owned.is_none() {
+        return Ok(None);
+    }
+
+    let rows = sqlx::query(
+        r#"
+        SELECT
+          f.fund_code as fund_code,
+          CAST(p.holding_share AS TEXT) as holding_share,
+          CAST(p.holding_nav AS TEXT) as holding_nav,
+          CAST(f.latest_nav AS TEXT) as latest_nav
+        FROM position p
+        JOIN account a ON a.id = p.account_id
+        JOIN fund f ON f.id = p.fund_id
+        WHERE CAST(a.id AS TEXT) = $1 OR CAST(a.parent_id AS TEXT) = $1
+        ORDER BY f.fund_code ASC
+        "#,
+    )
+    .bind(account_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut out: Vec<(String, f64)> = Vec::new();
+    for r in rows {
+        let code: String = r.get("fund_code");
+        let parse = |k: &str| {
+            r.try_get::<Option<String>, _>(k)
+                .ok()
+                .flatten()
+                .and_then(|s| s.trim().parse::<f64>().ok())
+        };
+        let share = parse("holding_share").unwrap_or(0.0);
+        let nav = parse("latest_nav")
+            .or_else(|| parse("holding_nav"))
+            .unwrap_or(0.0);
+        let value = share * nav;
+        if value <= 0.0 {
+            continue;
+        }
+        match out.iter_mut().find(|(c, _)| *c == code) {
+            Some((_, v)) => *v += value,
+            None => out.push((code, value)),
+        }
+    }
+    Ok(Some(out))
+}
+
+pub async fn account_overlap(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> axum::response::Response {
+    let user_id = match auth::authenticate(&state, &headers) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+    let Ok(user_id_i64) = user_id.parse::<i64>() else {
+        return auth::invalid_token_response();
+    };
+    let pool = match require_pool(&state) {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
+
+    let funds = match load_account_fund_values(pool, &id.to_string(), user_id_i64).await {
+        Ok(Some(v)) => v,
+        Ok(None) => return not_found(),
+        Err(e) => return errors::internal_response(&state, e),
+    };
+
+    let mut loaded: Vec<(String, Vec<HoldingOut>)> = Vec::with_capacity(funds.len());
+    for (code, _) in &funds {
+        match holdings::load_latest_holdings(pool, code, holdings::ASSET_STOCK).await {
+            Ok((_, h)) => loaded.push((code.clone(), h)),
+            Err(e) => return errors::internal_response(&state, e),
+        }
+    }
+
+    let mut pairs = Vec::new();
+    for i in 0..loaded.len() {
+        for j in (i + 1)..loaded.len() {
+            let (ca, ha) = &loaded[i];
+            let (cb, hb) = &loaded[j];
+            pairs.push(holdings::holdings_overlap(ca, ha, cb, hb));
+        }
+    }
+    pairs.sort_by(|x, y| {
+        y.overlap_pct
+            .partial_cmp(&x.overlap_pct)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    Json(json!({
+        "account_id": id.to_string(),
+        "funds": funds.iter().map(|(c, _)| c).collect::<Vec<_>>(),
+        "pairs": pairs,
+    }))
+    .into_response()
+}
+
+pub async fn account_exposure(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+) -> axum::response::Response {
+    let user_id = match auth::authenticate(&state, &headers) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+    let Ok(user_id_i64) = user_id.parse::<i64>() else {
+        return auth::invalid_token_response();
+    };
+    let pool = match require_pool(&state) {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
+
+    let funds = match load_account_fund_values(pool, &id.to_string(), user_id_i64).await {
+        Ok(Some(v)) => v,
+        Ok(None) => return not_found(),
+        Err(e) => return errors::internal_response(&state, e),
+    };
+
+    let total_value: f64 = funds.iter().map(|(_, v)| *v).sum();
+    let mut input = Vec::with_capacity(funds.len());
+    let mut covered_value = 0.0;
+    for (code, value) in &funds {
+        let h = match holdings::load_latest_holdings(pool, code, holdings::ASSET_STOCK).await {
+            Ok((_, h)) => h,
+            Err(e) => return errors::internal_response(&state, e),
+        };
+        if !h.is_empty() {
+            covered_value += value;
+        }
+        input.push((code.clone(), *value, h));
+    }
+    let exposures = holdings::look_through_exposure(&input);
+
+    Json(json!({
+        "account_id": id.to_string(),
+        "total_value_cny": total_value,
+        // 有持仓披露数据的基金市值占比（%）：其余部分无法穿透。
+        "covered_pct": if total_value > 0.0 { covered_value / total_value * 100.0 } else { 0.0 },
+        "stocks": exposures,
+    }))
+    .into_response()
+}
+
+pub async fn stock_funds(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Path(sec_code): axum::extract::Path<String>,
+) -> axum::response::Response {
+    let user_id = match auth::authenticate(&state, &headers) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+    let Ok(user_id_i64) = user_id.parse::<i64>() else {
+        return auth::invalid_token_response();
+    };
+    let pool = match require_pool(&state) {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
+
+    // “我的基金”= 持仓 ∪ 自选；只看各基金最新报告期。
+    let rows = sqlx::query(
+        r#"
+        SELECT
+          h.fund_code as fund_code,
+          f.fund_name as fund_name,
+          CAST(h.report_date AS TEXT) as report_date,
+          h.sec_name as sec_name,
+          h.weight_pct as weight_pct,
+          h.market_value_cny as market_value_cny,
+          CASE WHEN EXISTS (
+            SELECT 1 FROM position p JOIN account a ON a.id = p.account_id
+            WHERE p.fund_id = f.id AND a.user_id = $2
+          ) THEN 1 ELSE 0 END as in_positions,
+          CASE WHEN EXISTS (
+            SELECT 1 FROM watchlist_item wi JOIN watchlist w ON w.id = wi.watchlist_id
+            WHERE wi.fund_id = f.id AND w.user_id = $2
+          ) THEN 1 ELSE 0 END as in_watchlists
+        FROM fund_holding h
+        JOIN fund f ON f.fund_code = h.fund_code
+        WHERE h.sec_code = $1
+          AND h.asset_type = 'stock'
+          AND h.report_date = (
+            SELECT MAX(h2.report_date) FROM fund_holding h2
+            WHERE h2.fund_code = h.fund_code AND h2.asset_type = 'stock'
+          )
+          AND (
+            EXISTS (
+              SELECT 1 FROM position p JOIN account a ON a.id = p.account_id
+              WHERE p.fund_id = f.id AND a.user_id = $2
+            )
+            OR EXISTS (
+              SELECT 1 FROM watchlist_item wi JOIN watchlist w ON w.id = wi.watchlist_id
+              WHERE wi.fund_id = f.id AND w.user_id = $2
+            )
+          )
+        ORDER BY h.weight_pct DESC, h.fund_code ASC
+        "#,
+    )
+    .bind(sec_code.trim())
+    .bind(user_id_i64)
+    .fetch_all(pool)
+    .await;
+
+    let rows = match rows {
+        Ok(v) => v,
+        Err(e) => return errors::internal_response(&state, e),
+    };
+
+    let items: Vec<serde_json::Value> = rows
+        .into_iter()
+        .map(|r| {
+            json!({
+                "fund_code": r.get::<String, _>("fund_code"),
+                "fund_name": r.get::<String, _>("fund_name"),
+                "report_date": r.get::<String, _>("report_date"),
+                "sec_name": r.get::<String, _>("sec_name"),
+                "weight_pct": r.try_get::<f64, _>("weight_pct").unwrap_or(0.0),
+                "market_value_cny": r.try_get::<Option<f64>, _>("market_value_cny").ok().flatten(),
+                "in_positions": r.try_get::<i64, _>("in_positions").unwrap_or(0) != 0,
+                "in_watchlists": r.try_get::<i64, _>("in_watchlists").unwrap_or(0) != 0,
+            })
+        })
+        .collect();
+
+    Json(json!({ "sec_code": sec_code.trim(), "funds": items })).into_response()
+}
@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::eastmoney;
 use crate::index_series;
+use crate::ml;
 use crate::routes::auth;
 use crate::routes::errors;
 use crate::state::AppState;
@@ -96,3 +97,90 @@ pub async fn daily(
         .collect();
     Json(serde_json::json!({ "index_code": index_code, "source_name": source_name, "points": points })).into_response()
 }
+
+#[derive(Debug, Deserialize)]
+pub struct IndexRegimeQuery {
+    pub index_code: Option<String>,
+    pub source_name: Option<String>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+}
+
+/// 指数每日市场状态概率；该指数尚未计算过时用已存储的日线即时拟合一次。
+pub async fn regime(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    headers: HeaderMap,
+    Query(q): Query<IndexRegimeQuery>,
+) -> axum::response::Response {
+    let _user_id = match auth::authenticate(&state, &headers) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+
+    let pool = match state.pool() {
+        None => return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({ "error": "database not configured" }))).into_response(),
+        Some(p) => p,
+    };
+
+    let index_code = q
+        .index_code
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .unwrap_or(crate::features::REFER_INDEX_CODE)
+        .to_string();
+    let source_name = q
+        .source_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .unwrap_or(crate::features::REFER_INDEX_SOURCE)
+        .to_string();
+
+    let end_date = q
+        .end_date
+        .as_deref()
+        .and_then(|s| NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d").ok())
+        .unwrap_or_else(|| Utc::now().date_naive());
+    let start_date = q
+        .start_date
+        .as_deref()
+        .and_then(|s| NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d").ok())
+        .unwrap_or_else(|| end_date - Duration::days(365));
+
+    let mut model = match ml::regime::load_model(pool, &index_code, &source_name).await {
+        Ok(v) => v,
+        Err(e) => return errors::internal_response(&state, e),
+    };
+    if model.is_none() {
+        if let Err(e) = ml::regime::compute_and_store(pool, &index_code, &source_name, false).await {
+            return (StatusCode::NOT_FOUND, Json(serde_json::json!({ "error": e }))).into_response();
+        }
+        model = match ml::regime::load_model(pool, &index_code, &source_name).await {
+            Ok(v) => v,
+            Err(e) => return errors::internal_response(&state, e),
+        };
+    }
+
+    let points = match ml::regime::load_points(pool, &index_code, &source_name, &fmt_date(start_date), &fmt_date(end_date)).await {
+        Ok(v) => v,
+        Err(e) => return errors::internal_response(&state, e),
+    };
+
+    let model = model.map(|(m, fitted_at)| {
+        serde_json::json!({
+            "fitted_at": fitted_at,
+            "sample_count": m.sample_count,
+            "log_likelihood": m.log_likelihood,
+            "states": m.state_summary(),
+        })
+    });
+    Json(serde_json::json!({
+        "index_code": index_code,
+        "source_name": source_name,
+        "model": model,
+        "latest": points.last(),
+        "points": points,
+    }))
+    .into_response()
+}
@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use axum::{Json, extract::Query, http::StatusCode, response::IntoResponse};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde_json::json;
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::intraday::{self, FundIntraday};
+use crate::routes::auth;
+use crate::routes::errors;
+use crate::sources;
+use crate::state::AppState;
+
+#[allow(clippy::result_large_err)]
+fn require_pool(state: &AppState) -> Result<&sqlx::AnyPool, axum::response::Response> {
+    state.pool().ok_or_else(|| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "database not configured" })),
+        )
+            .into_response()
+    })
+}
+
+fn not_found() -> axum::response::Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "detail": "Not found." })),
+    )
+        .into_response()
+}
+
+/// 解析 `?source=&date=`：source 缺省为 tiantian；date 缺省返回 None（由调用方取最近有样本的日期）。
+#[allow(clippy::result_large_err)]
+fn parse_query(
+    q: &HashMap<String, String>,
+) -> Result<(&'static str, Option<NaiveDate>), axum::response::Response> {
+    let source_raw = q
+        .get("source")
+        .map(|s| s.as_str())
+        .unwrap_or(sources::SOURCE_TIANTIAN);
+    let Some(source_name) = sources::normalize_source_name(source_raw) else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": format!("数据源 {source_raw} 不存在") })),
+        )
+            .into_response());
+    };
+
+    let date = match q.get("date").map(|s| s.trim()).filter(|s| !s.is_empty()) {
+        None => None,
+        Some(raw) => match NaiveDate::parse_from_str(raw, "%Y-%m-%d") {
+            Ok(d) => Some(d),
+            Err(_) => {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({ "error": "date 格式应为 YYYY-MM-DD" })),
+                )
+                    .into_response());
+            }
+        },
+    };
+    Ok((source_name, date))
+}
+
+pub async fn fund_intraday(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::extract::Path(fund_code): axum::extract::Path<String>,
+    Query(q): Query<HashMap<String, String>>,
+) -> axum::response::Response {
+    let pool = match require_pool(&state) {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
+    let (source_name, date) = match parse_query(&q) {
+        Ok(v) => v,
+        Err(resp) => return resp,
+    };
+
+    let code = fund_code.trim();
+    let date = match date {
+        Some(d) => Some(d),
+        None => match intraday::latest_sample_date(pool, code, source_name).await {
+            Ok(v) => v,
+            Err(e) => return errors::internal_response(&state, e),
+        },
+    };
+
+    let points = match date {
+        None => Vec::new(),
+        Some(d) => match intraday::load_day_curve(pool, code, source_name, d).await {
+            Ok(v) => v,
+            Err(e) => return errors::internal_response(&state, e),
+        },
+    };
+
+    let items: Vec<serde_json::Value> = points
+        .iter()
+        .map(|p| {
+            json!({
+                "time": p.estimate_time.format("%Y-%m-%dT%H:%M:%S").to_string(),
+                "estimate_nav": p.estimate_nav.to_string(),
+                "estimate_growth": p.estimate_growth.map(|g| g.to_string()),
+            })
+        })
+        .collect();
+
+    Json(json!({
+        "fund_code": code,
+        "source": source_name,
+        "date": date.map(|d| d.to_string()),
+        "points": items,
+    }))
+    .into_response()
+}
+
+/// 账户（含子账户）下每只基金的合计份额与最新净值。None 表示账户不存在或不属于该用户。
+async fn load_account_shares(
+    pool: &sqlx::AnyPool,
+    account_id: &str,
+    user_id: i64,
+) -> Result<Option<Vec<(String, Decimal, Option<Decimal>)>>, String> {
+    let owned =
+        sqlx::query("SELECT 1 as ok FROM account WHERE CAST(id AS TEXT) = $1 AND user_id = $2")
+            .bind(account_id)
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    if owned.is_none() {
+        return Ok(None);
+    }
+
+    let rows = sqlx::query(
+        r#"
+        SELECT
+          f.fund_code as fund_code,
+          CAST(p.holding_share AS TEXT) as holding_share,
+          CAST(f.latest_nav AS TEXT) as latest_nav
+        FROM position p
+        JOIN account a ON a.id = p.account_id
+        JOIN fund f ON f.id = p.fund_id
+        WHERE CAST(a.id AS TEXT) = $1 OR CAST(a.parent_id AS TEXT) = $1
+        ORDER BY f.fund_code ASC
+        "#,
+    )
+    .bind(account_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut out: Vec<(String, Decimal, Option<Decimal>)> = Vec::new();
+    for r in rows {
+        let code: String = r.get("fund_code");
+        let share = r
+            .try_get::<Option<String>, _>("holding_share")
+            .ok()
+            .flatten()
+            .and_then(|s| Decimal::from_str(s.trim()).ok())
+            .unwrap_or(Decimal::ZERO);
+        if share <= Decimal::ZERO {
+            continue;
+        }
+        let latest_nav = r
+            .try_get::<Option<String>, _>("latest_nav")
+            .ok()
+            .flatten()
+            .and_then(|s| Decimal::from_str(s.trim()).ok());
+        match out.iter_mut().find(|(c, _, _)| *c == code) {
+            Some((_, s, _)) => *s += share,
+            None => out.push((code, share, latest_nav)),
+        }
+    }
+    Ok(Some(out))
+}
+
+pub async fn account_intraday(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Path(id): axum::extract::Path<Uuid>,
+    Query(q): Query<HashMap<String, String>>,
+) -> axum::response::Response {
+    let user_id = match auth::authenticate(&state, &headers) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+    let Ok(user_id_i64) = user_id.parse::<i64>() else {
+        return auth::invalid_token_response();
+    };
+    let pool = match require_pool(&state) {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
+    let (source_name, date) = match parse_query(&q) {
+        Ok(v) => v,
+        Err(resp) => return resp,
+    };
+
+    let shares = match load_account_shares(pool, &id.to_string(), user_id_i64).await {
+        Ok(Some(v)) => v,
+        Ok(None) => return not_found(),
+        Err(e) => return errors::internal_response(&state, e),
+    };
+
+    // 未指定日期：取账户内各基金最近一次有样本的日期中最新的一天。
+    let date = match date {
+        Some(d) => Some(d),
+        None => {
+            let mut latest: Option<NaiveDate> = None;
+            for (code, _, _) in &shares {
+                match intraday::latest_sample_date(pool, code, source_name).await {
+                    Ok(Some(d)) => latest = Some(latest.map_or(d, |l| l.max(d))),
+                    Ok(None) => {}
+                    Err(e) => return errors::internal_response(&state, e),
+                }
+            }
+            latest
+        }
+    };
+
+    let mut funds: Vec<FundIntraday> = Vec::with_capacity(shares.len());
+    if let Some(d) = date {
+        for (code, share, latest_nav) in &shares {
+            let points = match intraday::load_day_curve(pool, code, source_name, d).await {
+                Ok(v) => v,
+                Err(e) => return errors::internal_response(&state, e),
+            };
+            funds.push(FundIntraday {
+                fund_code: code.clone(),
+                holding_share: *share,
+                base_nav: *latest_nav,
+                points,
+            });
+        }
+    }
+
+    let points = intraday::aggregate_intraday_pnl(&funds);
+    let covered: Vec<&str> = funds
+        .iter()
+        .filter(|f| !f.points.is_empty())
+        .map(|f| f.fund_code.as_str())
+        .collect();
+
+    Json(json!({
+        "account_id": id.to_string(),
+        "source": source_name,
+        "date": date.map(|d| d.to_string()),
+        "funds": shares.iter().map(|(c, _, _)| c).collect::<Vec<_>>(),
+        // 当日有估值样本的基金：其余基金不参与日内盈亏曲线。
+        "covered_funds": covered,
+        "points": points,
+    }))
+    .into_response()
+}
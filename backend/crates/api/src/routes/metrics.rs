@@ -0,0 +1,69 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::state::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct MetricsQuery {
+    pub token: Option<String>,
+}
+
+/// 逐字节比较，耗时与首个不同字节的位置无关。
+fn token_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+/// Prometheus 抓取端点：需配置 `metrics_token`，通过 `Authorization: Bearer <token>` 或 `?token=` 访问。
+pub async fn metrics(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Query(q): Query<MetricsQuery>,
+) -> axum::response::Response {
+    let expected = state
+        .config()
+        .get_string("metrics_token")
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+    if expected.is_empty() {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "detail": "Not found." })),
+        )
+            .into_response();
+    }
+
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(|v| v.trim().to_string())
+        .or(q.token);
+    if !provided.is_some_and(|t| token_eq(&t, &expected)) {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({ "detail": "Authentication credentials were not provided." })),
+        )
+            .into_response();
+    }
+
+    (
+        StatusCode::OK,
+        [(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        crate::metrics::render(&state).await,
+    )
+        .into_response()
+}
@@ -0,0 +1,284 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::audit::AuditEvent;
+use crate::ml;
+use crate::ml::registry;
+use crate::permissions::{MlManageModels, Require};
+use crate::routes::{auth, errors};
+use crate::state::AppState;
+
+#[derive(Debug, Deserialize, Default)]
+pub struct MlModelsQuery {
+    pub peer_code: Option<String>,
+    pub task: Option<String>,
+}
+
+/// 板块模型列表：附带时间留出集上的验证指标（AUC / log-loss / Brier / 校准 / precision@k）。
+pub async fn list(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    headers: axum::http::HeaderMap,
+    Query(q): Query<MlModelsQuery>,
+) -> axum::response::Response {
+    if let Err(resp) = auth::authenticate(&state, &headers) {
+        return resp;
+    }
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+
+    let peer_code = q
+        .peer_code
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty());
+    let task = q.task.as_deref().map(str::trim).filter(|s| !s.is_empty());
+    if let Some(t) = task
+        && ![ml::train::MlTask::DipBuy, ml::train::MlTask::MagicRebound]
+            .iter()
+            .any(|m| m.as_str() == t)
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("unknown task: {t}") })),
+        )
+            .into_response();
+    }
+
+    match ml::train::list_sector_models(pool, peer_code, task).await {
+        Ok(items) => (StatusCode::OK, Json(json!({ "items": items }))).into_response(),
+        Err(e) => errors::internal_response(&state, e),
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct SignalOutcomesQuery {
+    pub peer_code: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+}
+
+/// 已回填实现结果的信号的线上表现：命中率、校准与按月衰减，按板块与位置分桶拆分。
+pub async fn signal_outcomes(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Query(q): Query<SignalOutcomesQuery>,
+) -> axum::response::Response {
+    if let Err(resp) = auth::authenticate(&state, &headers) {
+        return resp;
+    }
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+
+    let trimmed = |v: Option<String>| v.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    let filter = ml::outcomes::OutcomeFilter {
+        peer_code: trimmed(q.peer_code),
+        since: trimmed(q.since),
+        until: trimmed(q.until),
+    };
+    for d in [&filter.since, &filter.until].into_iter().flatten() {
+        if chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").is_err() {
+            return bad_request(format!("invalid date: {d}"));
+        }
+    }
+
+    match ml::outcomes::outcome_report(pool, &filter).await {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err(e) => errors::internal_response(&state, e),
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct ModelVersionsQuery {
+    pub family: Option<String>,
+    pub model_key: Option<String>,
+}
+
+fn bad_request(msg: String) -> axum::response::Response {
+    (StatusCode::BAD_REQUEST, Json(json!({ "error": msg }))).into_response()
+}
+
+/// 模型版本列表（含训练区间、特征集哈希、指标与影子评估记录）。
+pub async fn admin_list_versions(
+    _: Require<MlManageModels>,
+    State(state): State<AppState>,
+    Query(q): Query<ModelVersionsQuery>,
+) -> axum::response::Response {
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+    let family = q.family.as_deref().map(str::trim).filter(|s| !s.is_empty());
+    if let Some(f) = family
+        && !registry::is_known_family(f)
+    {
+        return bad_request(format!("unknown family: {f}"));
+    }
+    let model_key = q
+        .model_key
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty());
+
+    match registry::list_versions(pool, family, model_key).await {
+        Ok(items) => (StatusCode::OK, Json(json!({ "items": items }))).into_response(),
+        Err(e) => errors::internal_response(&state, e),
+    }
+}
+
+/// 手动晋升指定版本（含被拒绝或待定的挑战者）。
+pub async fn admin_promote_version(
+    _: Require<MlManageModels>,
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Path(id): Path<String>,
+) -> axum::response::Response {
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+    let before = match registry::get_version(pool, &id).await {
+        Ok(Some(v)) => v,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": "model version not found" })),
+            )
+                .into_response();
+        }
+        Err(e) => return errors::internal_response(&state, e),
+    };
+    let previous = registry::active_version(pool, &before.family, &before.model_key)
+        .await
+        .ok()
+        .flatten();
+
+    match registry::promote(pool, &id).await {
+        Ok(v) => {
+            crate::audit::record(
+                &state,
+                &headers,
+                AuditEvent::new("ml.model.promote", "ml_model_version")
+                    .target(&v.id)
+                    .before(json!({ "active_version": previous.map(|p| p.version) }))
+                    .after(json!({ "model_key": v.model_key, "active_version": v.version })),
+            )
+            .await;
+            (StatusCode::OK, Json(json!(v))).into_response()
+        }
+        Err(e) => errors::internal_response(&state, e),
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct DriftChecksQuery {
+    pub family: Option<String>,
+    pub model_key: Option<String>,
+    pub decision: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// 漂移检查记录（特征 PSI/KS、校准、陈旧度与重训决定），按时间倒序。
+pub async fn admin_list_drift_checks(
+    _: Require<MlManageModels>,
+    State(state): State<AppState>,
+    Query(q): Query<DriftChecksQuery>,
+) -> axum::response::Response {
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+    let trimmed = |v: &Option<String>| {
+        v.as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+    let (family, model_key, decision) = (
+        trimmed(&q.family),
+        trimmed(&q.model_key),
+        trimmed(&q.decision),
+    );
+    if let Some(f) = family.as_deref()
+        && !registry::is_known_family(f)
+    {
+        return bad_request(format!("unknown family: {f}"));
+    }
+    if let Some(d) = decision.as_deref()
+        && ![
+            ml::drift::DECISION_OK,
+            ml::drift::DECISION_RETRAIN,
+            ml::drift::DECISION_RETRAIN_PENDING,
+            ml::drift::DECISION_RETRAIN_HELD,
+        ]
+        .contains(&d)
+    {
+        return bad_request(format!("unknown decision: {d}"));
+    }
+    let limit = q.limit.unwrap_or(100).clamp(1, 1000);
+
+    match ml::drift::list_checks(
+        pool,
+        family.as_deref(),
+        model_key.as_deref(),
+        decision.as_deref(),
+        limit,
+    )
+    .await
+    {
+        Ok(items) => (StatusCode::OK, Json(json!({ "items": items }))).into_response(),
+        Err(e) => errors::internal_response(&state, e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RollbackBody {
+    pub family: String,
+    pub model_key: String,
+}
+
+/// 回滚到上一个曾经生效的版本。
+pub async fn admin_rollback(
+    _: Require<MlManageModels>,
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Json(body): Json<RollbackBody>,
+) -> axum::response::Response {
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+    let family = body.family.trim();
+    let model_key = body.model_key.trim();
+    if !registry::is_known_family(family) {
+        return bad_request(format!("unknown family: {family}"));
+    }
+    let previous = match registry::active_version(pool, family, model_key).await {
+        Ok(v) => v,
+        Err(e) => return errors::internal_response(&state, e),
+    };
+
+    match registry::rollback(pool, family, model_key).await {
+        Ok(Some(v)) => {
+            crate::audit::record(
+                &state,
+                &headers,
+                AuditEvent::new("ml.model.rollback", "ml_model_version")
+                    .target(&v.id)
+                    .before(json!({ "active_version": previous.map(|p| p.version) }))
+                    .after(json!({ "model_key": v.model_key, "active_version": v.version })),
+            )
+            .await;
+            (StatusCode::OK, Json(json!(v))).into_response()
+        }
+        Ok(None) => (
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "no previous version to roll back to" })),
+        )
+            .into_response(),
+        Err(e) => errors::internal_response(&state, e),
+    }
+}
@@ -3,8 +3,10 @@ use axum::Router;
 use crate::state::AppState;
 
 pub mod accounts;
+pub mod audit;
 pub mod auth;
 pub mod bootstrap;
+pub mod calendar;
 pub mod crawl_config;
 pub mod errors;
 pub mod forecast;
@@ -13,16 +15,24 @@ pub mod fund_analysis_v2;
 pub mod fund_signals;
 pub mod funds;
 pub mod health;
+pub mod holdings;
 pub mod indexes;
+pub mod intraday;
+pub mod metrics;
+pub mod ml_models;
 pub mod nav_history;
 pub mod positions;
 pub mod rates;
+pub mod roles;
 pub mod settings;
 pub mod sim;
 pub mod quant;
 pub mod sniffer;
 pub mod sources;
+pub mod task_schedules;
 pub mod tasks;
+pub mod tokens;
+pub mod two_factor;
 pub mod users;
 pub mod watchlists;
 
@@ -40,8 +50,40 @@ pub fn router(state: AppState) -> Router<AppState> {
             axum::routing::post(bootstrap::initialize),
         )
         .route("/api/auth/login", axum::routing::post(auth::login))
+        .route(
+            "/api/auth/login/2fa",
+            axum::routing::post(auth::login_two_factor),
+        )
+        .route("/api/auth/2fa", axum::routing::get(two_factor::status))
+        .route("/api/auth/2fa/setup", axum::routing::post(two_factor::setup))
+        .route("/api/auth/2fa/enable", axum::routing::post(two_factor::enable))
+        .route(
+            "/api/auth/2fa/disable",
+            axum::routing::post(two_factor::disable),
+        )
+        .route(
+            "/api/auth/2fa/recovery-codes",
+            axum::routing::post(two_factor::regenerate_recovery_codes),
+        )
         .route("/api/auth/refresh", axum::routing::post(auth::refresh))
         .route("/api/auth/me", axum::routing::get(auth::me))
+        .route("/api/auth/logout", axum::routing::post(auth::logout))
+        .route(
+            "/api/auth/sessions",
+            axum::routing::get(auth::sessions_list).delete(auth::sessions_revoke_others),
+        )
+        .route(
+            "/api/auth/sessions/{id}",
+            axum::routing::delete(auth::sessions_revoke),
+        )
+        .route(
+            "/api/auth/tokens",
+            axum::routing::get(tokens::list).post(tokens::create),
+        )
+        .route(
+            "/api/auth/tokens/{id}",
+            axum::routing::delete(tokens::revoke),
+        )
         .route(
             "/api/auth/password",
             axum::routing::put(auth::change_password),
@@ -74,6 +116,10 @@ pub fn router(state: AppState) -> Router<AppState> {
             "/api/funds/{fund_code}/estimate",
             axum::routing::get(funds::estimate),
         )
+        .route(
+            "/api/funds/{fund_code}/estimate/intraday",
+            axum::routing::get(intraday::fund_intraday),
+        )
         .route(
             "/api/funds/{fund_code}/analytics",
             axum::routing::get(fund_analytics::retrieve),
@@ -90,6 +136,11 @@ pub fn router(state: AppState) -> Router<AppState> {
             "/api/funds/{fund_code}/signals",
             axum::routing::get(fund_signals::retrieve),
         )
+        .route("/api/ml/models", axum::routing::get(ml_models::list))
+        .route(
+            "/api/ml/signal-outcomes",
+            axum::routing::get(ml_models::signal_outcomes),
+        )
         .route(
             "/api/funds/signals/batch",
             axum::routing::post(fund_signals::batch),
@@ -106,6 +157,10 @@ pub fn router(state: AppState) -> Router<AppState> {
             "/api/funds/{fund_code}/accuracy",
             axum::routing::get(funds::accuracy),
         )
+        .route(
+            "/api/funds/{fund_code}/holdings",
+            axum::routing::get(holdings::fund_holdings),
+        )
         .route(
             "/api/funds/batch_estimate",
             axum::routing::post(funds::batch_estimate),
@@ -138,6 +193,23 @@ pub fn router(state: AppState) -> Router<AppState> {
             "/api/accounts/{id}/positions",
             axum::routing::get(accounts::positions),
         )
+        .route(
+            "/api/accounts/{id}/holdings/overlap",
+            axum::routing::get(holdings::account_overlap),
+        )
+        .route(
+            "/api/accounts/{id}/holdings/exposure",
+            axum::routing::get(holdings::account_exposure),
+        )
+        .route(
+            "/api/accounts/{id}/estimate/intraday",
+            axum::routing::get(intraday::account_intraday),
+        )
+        .route("/api/holdings/overlap", axum::routing::get(holdings::overlap))
+        .route(
+            "/api/holdings/stocks/{sec_code}/funds",
+            axum::routing::get(holdings::stock_funds),
+        )
         .route("/api/positions", axum::routing::get(positions::list))
         .route(
             "/api/positions/history",
@@ -197,6 +269,14 @@ pub fn router(state: AppState) -> Router<AppState> {
             axum::routing::post(nav_history::sync),
         )
         .route("/api/indexes/daily", axum::routing::get(indexes::daily))
+        .route("/api/indexes/regime", axum::routing::get(indexes::regime))
+        .route("/api/calendar/verify", axum::routing::get(calendar::verify))
+        .route("/api/calendar/{market}/day", axum::routing::get(calendar::day))
+        .route(
+            "/api/calendar/{market}/offset",
+            axum::routing::get(calendar::offset),
+        )
+        .route("/api/calendar/{market}/days", axum::routing::get(calendar::days))
         .route("/api/rates/risk-free", axum::routing::get(rates::risk_free))
         .route("/api/sniffer/status", axum::routing::get(sniffer::status))
         .route("/api/sniffer/items", axum::routing::get(sniffer::items))
@@ -255,7 +335,21 @@ pub fn router(state: AppState) -> Router<AppState> {
         .route("/api/tasks/jobs/{id}", axum::routing::get(tasks::job_detail))
         .route("/api/tasks/jobs/{id}/runs", axum::routing::get(tasks::job_runs))
         .route("/api/tasks/jobs/{id}/logs", axum::routing::get(tasks::job_logs))
+        .route(
+            "/api/tasks/jobs/{id}/cancel",
+            axum::routing::post(tasks::cancel_job),
+        )
+        .route(
+            "/api/tasks/jobs/{id}/requeue",
+            axum::routing::post(tasks::requeue_job),
+        )
+        .route("/api/tasks/dead-letter", axum::routing::get(tasks::dead_letter))
+        .route("/api/tasks/jobs/{id}/stream", axum::routing::get(tasks::job_stream))
         .route("/api/tasks/runs/{id}/logs", axum::routing::get(tasks::run_logs))
+        .route(
+            "/api/tasks/runs/{id}/logs/stream",
+            axum::routing::get(tasks::run_logs_stream),
+        )
         // sim (paper trading / RL env)
         .route(
             "/api/sim/runs",
@@ -295,5 +389,64 @@ pub fn router(state: AppState) -> Router<AppState> {
             "/api/admin/crawl/config",
             axum::routing::get(crawl_config::admin_get_config).put(crawl_config::admin_set_config),
         )
+        .route(
+            "/api/admin/crawl/schedule",
+            axum::routing::get(crawl_config::admin_schedule_explain),
+        )
+        .route(
+            "/api/admin/task-schedules",
+            axum::routing::get(task_schedules::admin_list).post(task_schedules::admin_create),
+        )
+        .route(
+            "/api/admin/task-schedules/{id}",
+            axum::routing::get(task_schedules::admin_get)
+                .patch(task_schedules::admin_update)
+                .delete(task_schedules::admin_delete),
+        )
+        .route(
+            "/api/admin/ml/model-versions",
+            axum::routing::get(ml_models::admin_list_versions),
+        )
+        .route(
+            "/api/admin/ml/model-versions/{id}/promote",
+            axum::routing::post(ml_models::admin_promote_version),
+        )
+        .route(
+            "/api/admin/ml/rollback",
+            axum::routing::post(ml_models::admin_rollback),
+        )
+        .route(
+            "/api/admin/ml/drift-checks",
+            axum::routing::get(ml_models::admin_list_drift_checks),
+        )
+        .route(
+            "/api/admin/audit-events",
+            axum::routing::get(audit::admin_list),
+        )
+        .route(
+            "/api/admin/capabilities",
+            axum::routing::get(roles::capabilities),
+        )
+        .route(
+            "/api/admin/roles",
+            axum::routing::get(roles::list_roles).post(roles::create_role),
+        )
+        .route(
+            "/api/admin/roles/{name}",
+            axum::routing::patch(roles::update_role).delete(roles::delete_role),
+        )
+        .route(
+            "/api/admin/users/{id}/roles",
+            axum::routing::get(roles::get_user_roles).put(roles::set_user_roles),
+        )
+        .route("/metrics", axum::routing::get(metrics::metrics))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            crate::personal_token::authorize,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            crate::metrics::track_http,
+        ))
         .with_state(state)
 }
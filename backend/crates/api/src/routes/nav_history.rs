@@ -8,6 +8,7 @@ use uuid::Uuid;
 
 use crate::eastmoney;
 use crate::ml;
+use crate::permissions::{self, Capability, NavHistoryBulkSync};
 use crate::routes::auth;
 use crate::routes::errors;
 use crate::sources;
@@ -377,13 +378,13 @@ pub async fn sync(
             .into_response();
     }
 
-    // 分级鉴权：>15 需要 is_staff
+    // 分级鉴权：>15 需要 nav_history.bulk_sync
     if fund_codes.len() > 15 {
-        let is_staff = match maybe_is_staff(&state, &headers).await {
+        let allowed = match maybe_can_bulk_sync(&state, &headers).await {
             Ok(v) => v.unwrap_or(false),
             Err(resp) => return resp,
         };
-        if !is_staff {
+        if !allowed {
             return (
                 StatusCode::FORBIDDEN,
                 Json(json!({ "error": "同步超过 15 个基金需要管理员权限" })),
@@ -455,17 +456,23 @@ pub async fn sync(
             }
         };
 
-        // 立即唤醒后台 worker，避免等待 tick。
-        state.crawl_notify().notify_one();
-
-        let pool2 = pool.clone();
-        if !cfg!(test) {
-            tokio::spawn(async move {
-                if let Err(e) = tasks::run_due_task_jobs(&pool2, 1).await {
-                    tracing::warn!(error = %e, "task queue run_due_task_jobs failed (route trigger)");
-                }
-            });
-        }
+        // 立即唤醒任务 worker 池，避免等待轮询。
+        state.task_notify().notify_one();
+
+        crate::audit::record(
+            &state,
+            &headers,
+            crate::audit::AuditEvent::new("nav_history.sync", "task_job")
+                .target(&task_id)
+                .after(json!({
+                    "mode": "enqueue",
+                    "source": source_name,
+                    "fund_codes": fund_codes,
+                    "start_date": body.start_date,
+                    "end_date": body.end_date,
+                })),
+        )
+        .await;
 
         return (StatusCode::ACCEPTED, Json(json!({ "task_id": task_id }))).into_response();
     }
@@ -520,23 +527,10 @@ pub async fn sync(
                         .await;
 
                         // 再补充 1-2 个板块 peer（可选，不阻塞同步接口）。
-                        let peer_rows = sqlx::query(
-                            r#"
-                            SELECT sec_code
-                            FROM fund_relate_theme
-                            WHERE fund_code = $1
-                            GROUP BY sec_code
-                            ORDER BY sec_code ASC
-                            LIMIT 2
-                            "#,
-                        )
-                        .bind(&fund_code)
-                        .fetch_all(&pool)
-                        .await;
+                        let peer_rows = ml::cluster::fund_peers(&pool, &fund_code, &source_used, 2).await;
 
                         if let Ok(rows) = peer_rows {
-                            for r in rows {
-                                let peer_code: String = r.get("sec_code");
+                            for (peer_code, _) in rows {
                                 let _ = ml::compute::compute_and_store_fund_snapshot_with_opts(
                                     &pool,
                                     &fund_code,
@@ -558,10 +552,25 @@ pub async fn sync(
         }
     }
 
+    crate::audit::record(
+        &state,
+        &headers,
+        crate::audit::AuditEvent::new("nav_history.sync", "fund_nav_history")
+            .target(source_name)
+            .after(json!({
+                "mode": "inline",
+                "source": source_name,
+                "start_date": body.start_date,
+                "end_date": body.end_date,
+                "results": results,
+            })),
+    )
+    .await;
+
     (StatusCode::OK, Json(serde_json::Value::Object(results))).into_response()
 }
 
-async fn maybe_is_staff(
+async fn maybe_can_bulk_sync(
     state: &AppState,
     headers: &axum::http::HeaderMap,
 ) -> Result<Option<bool>, axum::response::Response> {
@@ -588,12 +597,10 @@ async fn maybe_is_staff(
         return Ok(None);
     };
 
-    let row = sqlx::query("SELECT is_staff FROM auth_user WHERE id = $1")
-        .bind(user_id_i64)
-        .fetch_optional(pool)
+    let principal = permissions::load_principal(pool, user_id_i64)
         .await
         .map_err(|e| {
-            tracing::error!(error = %e, "nav_history.maybe_is_staff query failed");
+            tracing::error!(error = %e, "nav_history.maybe_can_bulk_sync query failed");
             (
                 axum::http::StatusCode::INTERNAL_SERVER_ERROR,
                 Json(json!({ "error": "服务器内部错误" })),
@@ -601,10 +608,7 @@ async fn maybe_is_staff(
                 .into_response()
         })?;
 
-    Ok(row.map(|r| {
-        r.try_get::<bool, _>("is_staff")
-            .unwrap_or_else(|_| r.try_get::<i64, _>("is_staff").unwrap_or(0) != 0)
-    }))
+    Ok(principal.map(|p| p.has(NavHistoryBulkSync::NAME)))
 }
 
 pub(crate) async fn sync_one(
@@ -10,6 +10,7 @@ use sqlx::Row;
 use uuid::Uuid;
 
 use crate::dbfmt;
+use crate::permissions::{self, Capability, PositionsAdmin, PositionsViewAll, Require};
 use crate::position_history;
 use crate::routes::auth;
 use crate::routes::errors;
@@ -541,6 +542,7 @@ pub async fn retrieve(
 }
 
 pub async fn recalculate(
+    _: Require<PositionsAdmin>,
     axum::extract::State(state): axum::extract::State<AppState>,
     headers: axum::http::HeaderMap,
     body: Bytes,
@@ -559,15 +561,6 @@ pub async fn recalculate(
             }
         }
     };
-    let user_id = match auth::authenticate(&state, &headers) {
-        Ok(id) => id,
-        Err(resp) => return resp,
-    };
-    let user_id_i64 = match user_id.parse::<i64>() {
-        Ok(v) => v,
-        Err(_) => return auth::invalid_token_response(),
-    };
-
     let pool = match state.pool() {
         None => {
             return (
@@ -581,28 +574,7 @@ pub async fn recalculate(
         Some(p) => p,
     };
 
-    // admin only
-    let is_admin = match sqlx::query("SELECT is_superuser FROM auth_user WHERE id = $1")
-        .bind(user_id_i64)
-        .fetch_optional(pool)
-        .await
-    {
-        Ok(Some(row)) => row
-            .try_get::<bool, _>("is_superuser")
-            .unwrap_or_else(|_| row.try_get::<i64, _>("is_superuser").unwrap_or(0) != 0),
-        Ok(None) => false,
-        Err(_) => false,
-    };
-
-    if !is_admin {
-        return (
-            StatusCode::FORBIDDEN,
-            Json(json!({ "detail": "You do not have permission to perform this action." })),
-        )
-            .into_response();
-    }
-
-    if let Err(e) = recalculate_all_positions(pool, body.account_id).await {
+    if let Err(e) = recalculate_all_positions(pool, body.account_id.clone()).await {
         return (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(json!({ "error": e })),
@@ -610,6 +582,12 @@ pub async fn recalculate(
             .into_response();
     }
 
+    let mut event = crate::audit::AuditEvent::new("positions.recalculate", "account");
+    if let Some(account_id) = body.account_id.as_deref() {
+        event = event.target(account_id);
+    }
+    crate::audit::record(&state, &headers, event).await;
+
     (
         StatusCode::OK,
         Json(MessageResponse {
@@ -685,16 +663,7 @@ pub async fn operations_list(
         Some(p) => p,
     };
 
-    let is_staff = match sqlx::query("SELECT is_staff FROM auth_user WHERE id = $1")
-        .bind(user_id_i64)
-        .fetch_optional(pool)
-        .await
-    {
-        Ok(Some(row)) => row
-            .try_get::<bool, _>("is_staff")
-            .unwrap_or_else(|_| row.try_get::<i64, _>("is_staff").unwrap_or(0) != 0),
-        _ => false,
-    };
+    let can_view_all = permissions::user_has(pool, user_id_i64, PositionsViewAll::NAME).await;
 
     let mut sql = String::from(
         r#"
@@ -717,14 +686,14 @@ pub async fn operations_list(
         "#,
     );
 
-    // filter by user unless staff
-    if !is_staff {
+    // filter by user unless positions.view_all
+    if !can_view_all {
         sql.push_str(" WHERE a.user_id = $1");
     } else {
         sql.push_str(" WHERE 1=1");
     }
 
-    let mut bind_idx = if !is_staff { 2 } else { 1 };
+    let mut bind_idx = if !can_view_all { 2 } else { 1 };
     if q.account.is_some() {
         sql.push_str(&format!(" AND CAST(o.account_id AS TEXT) = ${bind_idx}"));
         bind_idx += 1;
@@ -740,7 +709,7 @@ pub async fn operations_list(
     sql.push_str(" ORDER BY o.operation_date ASC, o.created_at ASC");
 
     let mut query = sqlx::query(&sql);
-    if !is_staff {
+    if !can_view_all {
         query = query.bind(user_id_i64);
     }
     if let Some(account_id) = q.account {
@@ -848,16 +817,7 @@ pub async fn operations_create(
     }
 
     // account 必须属于当前用户（admin/staff 可跳过）；且必须是子账户（parent_id 不能为 NULL）
-    let is_staff = match sqlx::query("SELECT is_staff FROM auth_user WHERE id = $1")
-        .bind(user_id_i64)
-        .fetch_optional(pool)
-        .await
-    {
-        Ok(Some(row)) => row
-            .try_get::<bool, _>("is_staff")
-            .unwrap_or_else(|_| row.try_get::<i64, _>("is_staff").unwrap_or(0) != 0),
-        _ => false,
-    };
+    let can_view_all = permissions::user_has(pool, user_id_i64, PositionsViewAll::NAME).await;
 
     let account_id_str = body.account.to_string();
     let account_row = match sqlx::query(
@@ -884,7 +844,7 @@ pub async fn operations_create(
             .into_response();
     };
     let owner_id: i64 = account_row.get("user_id");
-    if !is_staff && owner_id != user_id_i64 {
+    if !can_view_all && owner_id != user_id_i64 {
         return (
             StatusCode::BAD_REQUEST,
             Json(json!({ "account": ["Invalid pk - object does not exist."] })),
@@ -1042,24 +1002,31 @@ pub async fn operations_create(
             .into_response();
     }
 
-    (
-        StatusCode::CREATED,
-        Json(OperationResponse {
-            id: id.clone(),
-            account: account_id_str,
-            account_name,
-            fund: fund_id.clone(),
-            fund_name,
-            operation_type,
-            operation_date: operation_date.to_string(),
-            before_15: body.before_15,
-            amount: fmt_decimal_fixed(amount, 2),
-            share: fmt_decimal_fixed(share, 4),
-            nav: fmt_decimal_fixed(nav, 4),
-            created_at: format_dt(Utc::now()),
-        }),
+    let out = OperationResponse {
+        id: id.clone(),
+        account: account_id_str,
+        account_name,
+        fund: fund_id.clone(),
+        fund_name,
+        operation_type,
+        operation_date: operation_date.to_string(),
+        before_15: body.before_15,
+        amount: fmt_decimal_fixed(amount, 2),
+        share: fmt_decimal_fixed(share, 4),
+        nav: fmt_decimal_fixed(nav, 4),
+        created_at: format_dt(Utc::now()),
+    };
+
+    crate::audit::record(
+        &state,
+        &headers,
+        crate::audit::AuditEvent::new("positions.operation.create", "position_operation")
+            .target(&id)
+            .after(json!(out)),
     )
-        .into_response()
+    .await;
+
+    (StatusCode::CREATED, Json(out)).into_response()
 }
 
 pub async fn operations_retrieve(
@@ -1089,16 +1056,7 @@ pub async fn operations_retrieve(
         Some(p) => p,
     };
 
-    let is_staff = match sqlx::query("SELECT is_staff FROM auth_user WHERE id = $1")
-        .bind(user_id_i64)
-        .fetch_optional(pool)
-        .await
-    {
-        Ok(Some(row)) => row
-            .try_get::<bool, _>("is_staff")
-            .unwrap_or_else(|_| row.try_get::<i64, _>("is_staff").unwrap_or(0) != 0),
-        _ => false,
-    };
+    let can_view_all = permissions::user_has(pool, user_id_i64, PositionsViewAll::NAME).await;
 
     let row = sqlx::query(
         r#"
@@ -1143,7 +1101,7 @@ pub async fn operations_retrieve(
             .into_response();
     };
 
-    if !is_staff {
+    if !can_view_all {
         let account_id: String = row.get("account");
         let owner = sqlx::query("SELECT user_id FROM account WHERE CAST(id AS TEXT) = $1")
             .bind(&account_id)
@@ -1183,19 +1141,11 @@ pub async fn operations_retrieve(
 }
 
 pub async fn operations_destroy(
+    _: Require<PositionsAdmin>,
     axum::extract::State(state): axum::extract::State<AppState>,
     headers: axum::http::HeaderMap,
     axum::extract::Path(id): axum::extract::Path<Uuid>,
 ) -> axum::response::Response {
-    let user_id = match auth::authenticate(&state, &headers) {
-        Ok(id) => id,
-        Err(resp) => return resp,
-    };
-    let user_id_i64 = match user_id.parse::<i64>() {
-        Ok(v) => v,
-        Err(_) => return auth::invalid_token_response(),
-    };
-
     let pool = match state.pool() {
         None => {
             return (
@@ -1209,24 +1159,6 @@ pub async fn operations_destroy(
         Some(p) => p,
     };
 
-    let is_admin = match sqlx::query("SELECT is_superuser FROM auth_user WHERE id = $1")
-        .bind(user_id_i64)
-        .fetch_optional(pool)
-        .await
-    {
-        Ok(Some(row)) => row
-            .try_get::<bool, _>("is_superuser")
-            .unwrap_or_else(|_| row.try_get::<i64, _>("is_superuser").unwrap_or(0) != 0),
-        _ => false,
-    };
-    if !is_admin {
-        return (
-            StatusCode::FORBIDDEN,
-            Json(json!({ "detail": "You do not have permission to perform this action." })),
-        )
-            .into_response();
-    }
-
     let mut tx = match pool.begin().await {
         Ok(v) => v,
         Err(e) => {
@@ -1239,7 +1171,18 @@ pub async fn operations_destroy(
     };
 
     let row = match sqlx::query(
-        "SELECT CAST(account_id AS TEXT) as account_id, CAST(fund_id AS TEXT) as fund_id FROM position_operation WHERE CAST(id AS TEXT) = $1",
+        r#"
+        SELECT
+          CAST(account_id AS TEXT) as account_id,
+          CAST(fund_id AS TEXT) as fund_id,
+          operation_type,
+          CAST(operation_date AS TEXT) as operation_date,
+          CAST(amount AS TEXT) as amount,
+          CAST(share AS TEXT) as share,
+          CAST(nav AS TEXT) as nav
+        FROM position_operation
+        WHERE CAST(id AS TEXT) = $1
+        "#,
     )
         .bind(id.to_string())
         .fetch_optional(&mut *tx)
@@ -1267,6 +1210,15 @@ pub async fn operations_destroy(
 
     let account_id: String = row.get("account_id");
     let fund_id: String = row.get("fund_id");
+    let before = json!({
+        "account": account_id,
+        "fund": fund_id,
+        "operation_type": row.get::<String, _>("operation_type"),
+        "operation_date": row.get::<String, _>("operation_date"),
+        "amount": row.get::<String, _>("amount"),
+        "share": row.get::<String, _>("share"),
+        "nav": row.get::<String, _>("nav"),
+    });
 
     let res = match sqlx::query("DELETE FROM position_operation WHERE CAST(id AS TEXT) = $1")
         .bind(id.to_string())
@@ -1309,6 +1261,16 @@ pub async fn operations_destroy(
         )
             .into_response();
     }
+
+    crate::audit::record(
+        &state,
+        &headers,
+        crate::audit::AuditEvent::new("positions.operation.delete", "position_operation")
+            .target(id)
+            .before(before),
+    )
+    .await;
+
     StatusCode::NO_CONTENT.into_response()
 }
 
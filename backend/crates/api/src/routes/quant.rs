@@ -352,16 +352,8 @@ pub async fn xalpha_metrics_batch_async(
         }
     };
 
-    // best-effort：立即触发一次执行，减少等待下一轮 tick 的延迟。
-    state.crawl_notify().notify_one();
-    let pool2 = pool.clone();
-    if !cfg!(test) {
-        tokio::spawn(async move {
-            if let Err(e) = tasks::run_due_task_jobs(&pool2, 1).await {
-                tracing::warn!(error = %e, "task queue run_due_task_jobs failed (route trigger)");
-            }
-        });
-    }
+    // 立即唤醒任务 worker 池，避免等待轮询。
+    state.task_notify().notify_one();
 
     (StatusCode::ACCEPTED, Json(EnqueueTaskOut { task_id })).into_response()
 }
@@ -425,15 +417,7 @@ pub async fn xalpha_grid_batch_async(
         }
     };
 
-    state.crawl_notify().notify_one();
-    let pool2 = pool.clone();
-    if !cfg!(test) {
-        tokio::spawn(async move {
-            if let Err(e) = tasks::run_due_task_jobs(&pool2, 1).await {
-                tracing::warn!(error = %e, "task queue run_due_task_jobs failed (route trigger)");
-            }
-        });
-    }
+    state.task_notify().notify_one();
 
     (StatusCode::ACCEPTED, Json(EnqueueTaskOut { task_id })).into_response()
 }
@@ -499,15 +483,7 @@ pub async fn xalpha_scheduled_batch_async(
         }
     };
 
-    state.crawl_notify().notify_one();
-    let pool2 = pool.clone();
-    if !cfg!(test) {
-        tokio::spawn(async move {
-            if let Err(e) = tasks::run_due_task_jobs(&pool2, 1).await {
-                tracing::warn!(error = %e, "task queue run_due_task_jobs failed (route trigger)");
-            }
-        });
-    }
+    state.task_notify().notify_one();
 
     (StatusCode::ACCEPTED, Json(EnqueueTaskOut { task_id })).into_response()
 }
@@ -585,15 +561,7 @@ pub async fn xalpha_qdiipredict_batch_async(
         }
     };
 
-    state.crawl_notify().notify_one();
-    let pool2 = pool.clone();
-    if !cfg!(test) {
-        tokio::spawn(async move {
-            if let Err(e) = tasks::run_due_task_jobs(&pool2, 1).await {
-                tracing::warn!(error = %e, "task queue run_due_task_jobs failed (route trigger)");
-            }
-        });
-    }
+    state.task_notify().notify_one();
 
     (StatusCode::ACCEPTED, Json(EnqueueTaskOut { task_id })).into_response()
 }
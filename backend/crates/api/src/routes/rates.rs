@@ -5,6 +5,7 @@ use serde_json::json;
 use sqlx::Row;
 
 use crate::dbfmt;
+use crate::permissions::{CrawlAdmin, Require};
 use crate::rates::treasury_3m;
 use crate::routes::auth;
 use crate::routes::errors;
@@ -113,18 +114,10 @@ pub struct AdminSyncRiskFreeResponse {
 }
 
 pub async fn admin_sync_risk_free(
+    _: Require<CrawlAdmin>,
     axum::extract::State(state): axum::extract::State<AppState>,
     headers: axum::http::HeaderMap,
 ) -> axum::response::Response {
-    let user_id = match auth::authenticate(&state, &headers) {
-        Ok(id) => id,
-        Err(resp) => return resp,
-    };
-    let user_id_i64 = match user_id.parse::<i64>() {
-        Ok(v) => v,
-        Err(_) => return auth::invalid_token_response(),
-    };
-
     let pool = match state.pool() {
         None => {
             return (
@@ -136,74 +129,19 @@ pub async fn admin_sync_risk_free(
         Some(p) => p,
     };
 
-    let row = sqlx::query(
-        r#"
-        SELECT
-          CASE WHEN is_superuser THEN 1 ELSE 0 END as is_superuser,
-          CASE WHEN is_staff THEN 1 ELSE 0 END as is_staff
-        FROM auth_user
-        WHERE id = $1
-        "#,
-    )
-    .bind(user_id_i64)
-    .fetch_optional(pool)
-    .await;
-    let row = match row {
-        Ok(v) => v,
-        Err(e) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                errors::internal_json(&state, e),
-            )
-                .into_response();
-        }
-    };
-    let Some(row) = row else {
-        return auth::invalid_token_response();
-    };
-
-    let is_admin = row.get::<i64, _>("is_superuser") != 0 || row.get::<i64, _>("is_staff") != 0;
-    if !is_admin {
-        return (
-            StatusCode::FORBIDDEN,
-            Json(json!({ "error": "需要管理员权限" })),
-        )
-            .into_response();
-    }
-
-    let url = std::env::var("RISK_FREE_CHINABOND_URL")
-        .ok()
-        .map(|s| s.trim().to_string())
-        .filter(|s| !s.is_empty())
-        .unwrap_or_else(|| treasury_3m::DEFAULT_CHINABOND_CURVE_URL.to_string());
-
-    let client = match reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(20))
-        .user_agent("Fundval-re rates/1.0")
-        .build()
-    {
-        Ok(c) => c,
-        Err(e) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({ "error": format!("创建 HTTP 客户端失败: {e}") })),
-            )
-                .into_response();
-        }
-    };
-
-    let got = match treasury_3m::fetch_chinabond_3m(&client, &url).await {
+    let got = match treasury_3m::sync_chinabond_3m(pool).await {
         Ok(v) => v,
         Err(e) => return (StatusCode::BAD_GATEWAY, Json(json!({ "error": e }))).into_response(),
     };
 
-    if let Err(e) = treasury_3m::upsert_risk_free_rate_3m(pool, &got, "chinabond").await {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "error": e })),
-        )
-            .into_response();
-    }
+    crate::audit::record(
+        &state,
+        &headers,
+        crate::audit::AuditEvent::new("rates.risk_free.sync", "risk_free_rate")
+            .target(&got.rate_date)
+            .after(json!({ "tenor": "3M", "rate_percent": got.rate_percent, "source": "chinabond" })),
+    )
+    .await;
 
     (
         StatusCode::OK,
@@ -0,0 +1,322 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::audit::{self, AuditEvent};
+use crate::permissions::{self, CAPABILITIES, Require, UsersManageRoles};
+use crate::routes::errors;
+use crate::state::AppState;
+
+fn bad_request(msg: impl Into<String>) -> axum::response::Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({ "error": msg.into() })),
+    )
+        .into_response()
+}
+
+fn not_found() -> axum::response::Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "detail": "Not found." })),
+    )
+        .into_response()
+}
+
+fn superuser_required() -> axum::response::Response {
+    (
+        StatusCode::FORBIDDEN,
+        Json(json!({ "error": "只有超级用户才能授予 * 或 users.manage_roles" })),
+    )
+        .into_response()
+}
+
+/// 校验能力名并去重排序；未知能力返回错误。
+fn normalize_capabilities(caps: Vec<String>) -> Result<Vec<String>, String> {
+    let mut out: Vec<String> = Vec::with_capacity(caps.len());
+    for c in caps {
+        let c = c.trim().to_string();
+        if !permissions::is_known_capability(&c) {
+            return Err(format!("未知能力: {c}"));
+        }
+        if !out.contains(&c) {
+            out.push(c);
+        }
+    }
+    out.sort();
+    Ok(out)
+}
+
+/// 角色名：小写字母/数字/下划线/连字符，1..=64。
+fn valid_role_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+}
+
+pub async fn capabilities(_: Require<UsersManageRoles>) -> axum::response::Response {
+    (StatusCode::OK, Json(json!({ "items": CAPABILITIES }))).into_response()
+}
+
+pub async fn list_roles(
+    _: Require<UsersManageRoles>,
+    State(state): State<AppState>,
+) -> axum::response::Response {
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+    match permissions::list_roles(pool).await {
+        Ok(items) => (StatusCode::OK, Json(json!({ "items": items }))).into_response(),
+        Err(e) => errors::internal_response(&state, e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RoleRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub capabilities: Option<Vec<String>>,
+}
+
+pub async fn create_role(
+    admin: Require<UsersManageRoles>,
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Json(body): Json<RoleRequest>,
+) -> axum::response::Response {
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+    let name = body.name.unwrap_or_default().trim().to_string();
+    if !valid_role_name(&name) {
+        return bad_request("角色名只能包含小写字母、数字、下划线和连字符");
+    }
+    let caps = match normalize_capabilities(body.capabilities.unwrap_or_default()) {
+        Ok(v) => v,
+        Err(e) => return bad_request(e),
+    };
+    if permissions::grants_superuser_only(&caps) && !admin.is_superuser {
+        return superuser_required();
+    }
+
+    match permissions::get_role(pool, &name).await {
+        Ok(Some(_)) => {
+            return (
+                StatusCode::CONFLICT,
+                Json(json!({ "error": format!("角色 {name} 已存在") })),
+            )
+                .into_response();
+        }
+        Ok(None) => {}
+        Err(e) => return errors::internal_response(&state, e),
+    }
+    let description = body.description.unwrap_or_default();
+    if let Err(e) = permissions::create_role(pool, &name, description.trim(), &caps).await {
+        return errors::internal_response(&state, e);
+    }
+
+    match permissions::get_role(pool, &name).await {
+        Ok(Some(role)) => {
+            audit::record(
+                &state,
+                &headers,
+                AuditEvent::new("roles.create", "role")
+                    .target(&name)
+                    .after(json!(role)),
+            )
+            .await;
+            (StatusCode::CREATED, Json(role)).into_response()
+        }
+        Ok(None) => not_found(),
+        Err(e) => errors::internal_response(&state, e),
+    }
+}
+
+/// 部分更新描述或能力集；内置角色同样可改能力，但不能删除。
+pub async fn update_role(
+    admin: Require<UsersManageRoles>,
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Path(name): Path<String>,
+    Json(body): Json<RoleRequest>,
+) -> axum::response::Response {
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+    let before = match permissions::get_role(pool, &name).await {
+        Ok(Some(r)) => r,
+        Ok(None) => return not_found(),
+        Err(e) => return errors::internal_response(&state, e),
+    };
+    let caps = match body.capabilities.map(normalize_capabilities).transpose() {
+        Ok(v) => v,
+        Err(e) => return bad_request(e),
+    };
+    // 已分配的角色加上这些能力同样等于授予。
+    if caps
+        .as_deref()
+        .is_some_and(permissions::grants_superuser_only)
+        && !admin.is_superuser
+    {
+        return superuser_required();
+    }
+    let description = body.description.map(|s| s.trim().to_string());
+
+    match permissions::update_role(pool, &name, description.as_deref(), caps.as_deref()).await {
+        Ok(true) => {}
+        Ok(false) => return not_found(),
+        Err(e) => return errors::internal_response(&state, e),
+    }
+    match permissions::get_role(pool, &name).await {
+        Ok(Some(role)) => {
+            audit::record(
+                &state,
+                &headers,
+                AuditEvent::new("roles.update", "role")
+                    .target(&name)
+                    .before(json!(before))
+                    .after(json!(role)),
+            )
+            .await;
+            (StatusCode::OK, Json(role)).into_response()
+        }
+        Ok(None) => not_found(),
+        Err(e) => errors::internal_response(&state, e),
+    }
+}
+
+pub async fn delete_role(
+    _: Require<UsersManageRoles>,
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Path(name): Path<String>,
+) -> axum::response::Response {
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+    let before = match permissions::get_role(pool, &name).await {
+        Ok(Some(r)) => r,
+        Ok(None) => return not_found(),
+        Err(e) => return errors::internal_response(&state, e),
+    };
+    if before.is_system {
+        return bad_request("内置角色不能删除");
+    }
+    match permissions::delete_role(pool, &name).await {
+        Ok(true) => {
+            audit::record(
+                &state,
+                &headers,
+                AuditEvent::new("roles.delete", "role")
+                    .target(&name)
+                    .before(json!(before)),
+            )
+            .await;
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Ok(false) => not_found(),
+        Err(e) => errors::internal_response(&state, e),
+    }
+}
+
+async fn user_roles_response(
+    state: &AppState,
+    pool: &sqlx::AnyPool,
+    user_id: i64,
+) -> axum::response::Response {
+    let principal = match permissions::load_principal(pool, user_id).await {
+        Ok(Some(p)) => p,
+        Ok(None) => return not_found(),
+        Err(e) => return errors::internal_response(state, e),
+    };
+    match permissions::user_role_names(pool, user_id).await {
+        Ok(roles) => (
+            StatusCode::OK,
+            Json(json!({
+                "user_id": user_id,
+                "is_superuser": principal.is_superuser,
+                "is_staff": principal.is_staff,
+                "roles": roles,
+                "capabilities": principal.capabilities(),
+            })),
+        )
+            .into_response(),
+        Err(e) => errors::internal_response(state, e),
+    }
+}
+
+pub async fn get_user_roles(
+    _: Require<UsersManageRoles>,
+    State(state): State<AppState>,
+    Path(user_id): Path<i64>,
+) -> axum::response::Response {
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+    user_roles_response(&state, pool, user_id).await
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetUserRolesRequest {
+    pub roles: Vec<String>,
+}
+
+pub async fn set_user_roles(
+    admin: Require<UsersManageRoles>,
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Path(user_id): Path<i64>,
+    Json(body): Json<SetUserRolesRequest>,
+) -> axum::response::Response {
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+    match permissions::load_principal(pool, user_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return not_found(),
+        Err(e) => return errors::internal_response(&state, e),
+    }
+    let mut roles: Vec<String> = body.roles.iter().map(|r| r.trim().to_string()).collect();
+    roles.sort();
+    roles.dedup();
+    let before = match permissions::user_role_names(pool, user_id).await {
+        Ok(v) => v,
+        Err(e) => return errors::internal_response(&state, e),
+    };
+    for r in &roles {
+        match permissions::get_role(pool, r).await {
+            // 新增的角色含 * 或 users.manage_roles 时仅超级用户可授予（包括授予自己）。
+            Ok(Some(role))
+                if !admin.is_superuser
+                    && !before.contains(r)
+                    && permissions::grants_superuser_only(&role.capabilities) =>
+            {
+                return superuser_required();
+            }
+            Ok(Some(_)) => {}
+            Ok(None) => return bad_request(format!("角色 {r} 不存在")),
+            Err(e) => return errors::internal_response(&state, e),
+        }
+    }
+    if let Err(e) = permissions::set_user_roles(pool, user_id, &roles, Some(admin.user_id)).await {
+        return errors::internal_response(&state, e);
+    }
+    audit::record(
+        &state,
+        &headers,
+        AuditEvent::new("users.roles.update", "user")
+            .target(user_id)
+            .before(json!({ "roles": before }))
+            .after(json!({ "roles": roles })),
+    )
+    .await;
+
+    user_roles_response(&state, pool, user_id).await
+}
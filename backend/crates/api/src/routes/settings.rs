@@ -1,8 +1,8 @@
 use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use sqlx::Row;
 
+use crate::permissions::{Require, SourcesConfigure};
 use crate::state::AppState;
 
 fn mask_token(token: &str) -> Option<String> {
@@ -16,50 +16,6 @@ fn mask_token(token: &str) -> Option<String> {
     Some(format!("{}****{}", &t[..4], &t[t.len() - 4..]))
 }
 
-async fn require_staff(
-    state: &AppState,
-    headers: &axum::http::HeaderMap,
-) -> Result<(), axum::response::Response> {
-    let pool = match state.pool() {
-        Some(p) => p,
-        None => {
-            return Err((
-                StatusCode::SERVICE_UNAVAILABLE,
-                Json(json!({ "error": "数据库未连接" })),
-            )
-                .into_response());
-        }
-    };
-
-    let user_id = match crate::routes::auth::authenticate(state, headers) {
-        Ok(v) => v,
-        Err(resp) => return Err(resp),
-    };
-    let user_id_i64 = user_id
-        .parse::<i64>()
-        .map_err(|_| crate::routes::auth::invalid_token_response())?;
-
-    let is_staff = match sqlx::query("SELECT is_staff FROM auth_user WHERE id = $1")
-        .bind(user_id_i64)
-        .fetch_optional(pool)
-        .await
-    {
-        Ok(Some(row)) => row
-            .try_get::<bool, _>("is_staff")
-            .unwrap_or_else(|_| row.try_get::<i64, _>("is_staff").unwrap_or(0) != 0),
-        _ => false,
-    };
-
-    if !is_staff {
-        return Err((
-            StatusCode::FORBIDDEN,
-            Json(json!({ "detail": "You do not have permission to perform this action." })),
-        )
-            .into_response());
-    }
-    Ok(())
-}
-
 #[derive(Debug, Serialize)]
 pub struct TokenStatusResponse {
     pub configured: bool,
@@ -67,13 +23,9 @@ pub struct TokenStatusResponse {
 }
 
 pub async fn get_tushare_token_status(
+    _: Require<SourcesConfigure>,
     State(state): State<AppState>,
-    headers: axum::http::HeaderMap,
 ) -> axum::response::Response {
-    if let Err(resp) = require_staff(&state, &headers).await {
-        return resp;
-    }
-
     let token = state
         .config()
         .get_string("tushare_token")
@@ -95,14 +47,12 @@ pub struct SetTokenRequest {
 }
 
 pub async fn set_tushare_token(
+    _: Require<SourcesConfigure>,
     State(state): State<AppState>,
     headers: axum::http::HeaderMap,
     Json(body): Json<SetTokenRequest>,
 ) -> axum::response::Response {
-    if let Err(resp) = require_staff(&state, &headers).await {
-        return resp;
-    }
-
+    let before = mask_token(&state.config().get_string("tushare_token").unwrap_or_default());
     let token = body.token.and_then(|s| {
         let t = s.trim().to_string();
         if t.is_empty() { None } else { Some(t) }
@@ -117,5 +67,17 @@ pub async fn set_tushare_token(
             .into_response();
     }
 
+    // 审计只记录脱敏后的令牌。
+    let after = mask_token(&state.config().get_string("tushare_token").unwrap_or_default());
+    crate::audit::record(
+        &state,
+        &headers,
+        crate::audit::AuditEvent::new("settings.tushare_token.update", "config")
+            .target("tushare_token")
+            .before(json!({ "configured": before.is_some(), "token_hint": before }))
+            .after(json!({ "configured": after.is_some(), "token_hint": after })),
+    )
+    .await;
+
     (StatusCode::OK, Json(json!({ "message": "ok" }))).into_response()
 }
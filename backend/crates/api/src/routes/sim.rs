@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sqlx::Row;
 
+use crate::permissions::{self, Capability, SimUnlimited};
 use crate::routes::auth;
 use crate::routes::errors;
 use crate::sim::engine;
@@ -49,6 +50,9 @@ pub struct CreateSimRunBody {
     pub sell_unit: Option<String>, // "amount" | "fundPercent"
     pub profit_rate: Option<f64>,
     pub buy_amount_percent: Option<f64>,
+
+    // 两种 auto_topk 策略共用：市场状态过滤
+    pub regime_filter: Option<engine::RegimeFilter>,
 }
 
 #[derive(Debug, Serialize)]
@@ -249,6 +253,13 @@ pub async fn delete_run(
             .into_response();
     }
 
+    crate::audit::record(
+        &state,
+        &headers,
+        crate::audit::AuditEvent::new("sim.run.delete", "sim_run").target(run_id.trim()),
+    )
+    .await;
+
     (StatusCode::OK, Json(json!({ "deleted": true }))).into_response()
 }
 
@@ -277,6 +288,25 @@ pub async fn create_run(
         Some(p) => p,
     };
 
+    let max_runs = state.config().get_i64("sim_max_runs_per_user", 20);
+    if max_runs > 0 && !permissions::user_has(pool, user_id_i64, SimUnlimited::NAME).await {
+        let existing = match sqlx::query("SELECT COUNT(1) as n FROM sim_run WHERE user_id = $1")
+            .bind(user_id_i64)
+            .fetch_one(pool)
+            .await
+        {
+            Ok(row) => row.get::<i64, _>("n"),
+            Err(e) => return errors::internal_response(&state, e),
+        };
+        if existing >= max_runs {
+            return (
+                StatusCode::FORBIDDEN,
+                Json(json!({ "error": format!("模拟运行数已达上限（{max_runs}），请先删除旧的运行") })),
+            )
+                .into_response();
+        }
+    }
+
     let mode = body.mode.trim();
     if mode != "env" && mode != "backtest" {
         return (
@@ -403,6 +433,7 @@ pub async fn create_run(
                     top_k,
                     rebalance_every,
                     weights: body.weights.clone(),
+                    regime_filter: body.regime_filter.clone(),
                 },
             )
             .await
@@ -450,6 +481,7 @@ pub async fn create_run(
                     profit_rate: body.profit_rate.unwrap_or(10.0),
                     buy_amount_percent: body.buy_amount_percent.unwrap_or(20.0),
                     quant_service_url: quant_base_url(&state),
+                    regime_filter: body.regime_filter.clone(),
                 },
             )
             .await
@@ -4,6 +4,7 @@ use serde_json::json;
 use sqlx::Row;
 
 use crate::dbfmt;
+use crate::permissions::{CrawlAdmin, Require};
 use crate::routes::auth;
 use crate::routes::errors;
 use crate::sniffer;
@@ -376,18 +377,10 @@ pub async fn items(
 }
 
 pub async fn admin_sync(
+    admin: Require<CrawlAdmin>,
     axum::extract::State(state): axum::extract::State<AppState>,
     headers: axum::http::HeaderMap,
 ) -> axum::response::Response {
-    let user_id = match auth::authenticate(&state, &headers) {
-        Ok(v) => v,
-        Err(resp) => return resp,
-    };
-    let user_id_i64 = match user_id.parse::<i64>() {
-        Ok(v) => v,
-        Err(_) => return auth::invalid_token_response(),
-    };
-
     let pool = match state.pool() {
         None => {
             return (
@@ -399,28 +392,9 @@ pub async fn admin_sync(
         Some(p) => p,
     };
 
-    // Django IsAdminUser：要求 is_staff
-    let is_admin = match sqlx::query("SELECT is_staff FROM auth_user WHERE id = $1")
-        .bind(user_id_i64)
-        .fetch_optional(pool)
-        .await
-    {
-        Ok(Some(row)) => row
-            .try_get::<bool, _>("is_staff")
-            .unwrap_or_else(|_| row.try_get::<i64, _>("is_staff").unwrap_or(0) != 0),
-        _ => false,
-    };
-
-    if !is_admin {
-        return (
-            StatusCode::FORBIDDEN,
-            Json(json!({ "detail": "You do not have permission to perform this action." })),
-        )
-            .into_response();
-    }
 
     let payload = json!({});
-    let task_id = match tasks::enqueue_task_job(pool, "sniffer_sync", &payload, 200, Some(user_id_i64)).await {
+    let task_id = match tasks::enqueue_task_job(pool, "sniffer_sync", &payload, 200, Some(admin.user_id)).await {
         Ok(id) => id,
         Err(e) => {
             return (
@@ -431,17 +405,15 @@ pub async fn admin_sync(
         }
     };
 
-    // 立即唤醒后台 worker，避免等待 tick。
-    state.crawl_notify().notify_one();
+    // 立即唤醒任务 worker 池，避免等待轮询。
+    state.task_notify().notify_one();
 
-    let pool2 = pool.clone();
-    if !cfg!(test) {
-        tokio::spawn(async move {
-            if let Err(e) = tasks::run_due_task_jobs(&pool2, 1).await {
-                tracing::warn!(error = %e, "task queue run_due_task_jobs failed (route trigger)");
-            }
-        });
-    }
+    crate::audit::record(
+        &state,
+        &headers,
+        crate::audit::AuditEvent::new("sniffer.sync", "task_job").target(&task_id),
+    )
+    .await;
 
     (
         StatusCode::ACCEPTED,
@@ -12,6 +12,7 @@ use tokio::{sync::Semaphore, task::JoinSet};
 
 use crate::accuracy;
 use crate::eastmoney;
+use crate::permissions::{self, Capability, SourcesConfigure};
 use crate::sources;
 use crate::state::AppState;
 use sqlx::Row;
@@ -247,6 +248,12 @@ pub async fn health(State(state): State<AppState>) -> impl IntoResponse {
         }
     }
 
+    for item in &result {
+        state
+            .metrics()
+            .record_source_probe(&item.name, item.ok, item.latency_ms);
+    }
+
     (StatusCode::OK, Json(result))
 }
 
@@ -349,33 +356,8 @@ pub async fn calculate_accuracy(
         }
     };
 
-    // Django IsAdminUser：要求 is_staff
-    let user_id = match crate::routes::auth::authenticate(&state, &headers) {
-        Ok(v) => v,
-        Err(resp) => return resp,
-    };
-    let user_id_i64 = match user_id.parse::<i64>() {
-        Ok(v) => v,
-        Err(_) => return crate::routes::auth::invalid_token_response(),
-    };
-
-    let is_admin = match sqlx::query("SELECT is_staff FROM auth_user WHERE id = $1")
-        .bind(user_id_i64)
-        .fetch_optional(pool)
-        .await
-    {
-        Ok(Some(row)) => row
-            .try_get::<bool, _>("is_staff")
-            .unwrap_or_else(|_| row.try_get::<i64, _>("is_staff").unwrap_or(0) != 0),
-        _ => false,
-    };
-
-    if !is_admin {
-        return (
-            StatusCode::FORBIDDEN,
-            Json(json!({ "detail": "You do not have permission to perform this action." })),
-        )
-            .into_response();
+    if let Err(resp) = permissions::require(&state, &headers, SourcesConfigure::NAME).await {
+        return resp;
     }
 
     let Some(source) = sources::normalize_source_name(&source_name) else {
@@ -579,6 +561,20 @@ pub async fn calculate_accuracy(
         }
     }
 
+    crate::audit::record(
+        &state,
+        &headers,
+        crate::audit::AuditEvent::new("sources.accuracy.calculate", "estimate_accuracy")
+            .target(source)
+            .after(json!({
+                "date": target_date.to_string(),
+                "total": total,
+                "success": success,
+                "failed": failed,
+            })),
+    )
+    .await;
+
     (
         StatusCode::OK,
         Json(CalculateAccuracyResponse {
@@ -0,0 +1,359 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+use crate::dbfmt::format_utc;
+use crate::permissions::{TasksSchedule, Require};
+use crate::routes::errors;
+use crate::state::AppState;
+use crate::task_schedule::{self, Recurrence, TaskScheduleRow};
+
+#[derive(Debug, Serialize)]
+pub struct TaskScheduleOut {
+    pub id: String,
+    pub name: String,
+    pub task_type: String,
+    pub payload: Value,
+    pub cron_expr: Option<String>,
+    pub interval_seconds: Option<i64>,
+    pub priority: i64,
+    pub enabled: bool,
+    pub last_run_at: Option<String>,
+    pub next_run_at: Option<String>,
+    pub last_job_id: Option<String>,
+    pub created_by: Option<i64>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<TaskScheduleRow> for TaskScheduleOut {
+    fn from(r: TaskScheduleRow) -> Self {
+        Self {
+            payload: serde_json::from_str(&r.payload_json).unwrap_or_else(|_| json!({})),
+            id: r.id,
+            name: r.name,
+            task_type: r.task_type,
+            cron_expr: r.cron_expr,
+            interval_seconds: r.interval_seconds,
+            priority: r.priority,
+            enabled: r.enabled,
+            last_run_at: r.last_run_at.map(|s| crate::dbfmt::datetime_to_rfc3339(&s)),
+            next_run_at: r.next_run_at.map(|s| crate::dbfmt::datetime_to_rfc3339(&s)),
+            last_job_id: r.last_job_id,
+            created_by: r.created_by,
+            created_at: crate::dbfmt::datetime_to_rfc3339(&r.created_at),
+            updated_at: crate::dbfmt::datetime_to_rfc3339(&r.updated_at),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TaskScheduleRequest {
+    pub name: Option<String>,
+    pub task_type: Option<String>,
+    pub payload: Option<Value>,
+    pub cron_expr: Option<String>,
+    pub interval_seconds: Option<i64>,
+    pub priority: Option<i64>,
+    pub enabled: Option<bool>,
+}
+
+fn bad_request(msg: impl Into<String>) -> axum::response::Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({ "error": msg.into() })),
+    )
+        .into_response()
+}
+
+fn not_found() -> axum::response::Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "detail": "Not found." })),
+    )
+        .into_response()
+}
+
+fn name_conflict(name: &str) -> axum::response::Response {
+    (
+        StatusCode::CONFLICT,
+        Json(json!({ "error": format!("计划名称已存在: {name}") })),
+    )
+        .into_response()
+}
+
+/// 把请求体合并到计划上并校验；触发方式或启用状态变化时重算 next_run_at。
+fn apply_request(row: &mut TaskScheduleRow, body: TaskScheduleRequest) -> Result<(), String> {
+    let was_enabled = row.enabled;
+    let old_trigger = (row.cron_expr.clone(), row.interval_seconds);
+
+    if let Some(v) = body.name {
+        let v = v.trim();
+        if v.is_empty() {
+            return Err("name 不能为空".to_string());
+        }
+        row.name = v.to_string();
+    }
+    if let Some(v) = body.task_type {
+        let v = v.trim();
+        if !crate::tasks::TASK_TYPES.contains(&v) {
+            return Err(format!("未知 task_type: {v}"));
+        }
+        row.task_type = v.to_string();
+    }
+    if let Some(v) = body.payload {
+        if !v.is_object() {
+            return Err("payload 必须是 JSON 对象".to_string());
+        }
+        row.payload_json = v.to_string();
+    }
+    match (body.cron_expr, body.interval_seconds) {
+        (Some(_), Some(_)) => {
+            return Err("cron_expr 与 interval_seconds 必须且只能设置一个".to_string());
+        }
+        (Some(expr), None) => {
+            row.cron_expr = Some(expr.trim().to_string());
+            row.interval_seconds = None;
+        }
+        (None, Some(secs)) => {
+            row.cron_expr = None;
+            row.interval_seconds = Some(secs);
+        }
+        (None, None) => {}
+    }
+    if let Some(v) = body.priority {
+        row.priority = v.clamp(-1000, 1000);
+    }
+    if let Some(v) = body.enabled {
+        row.enabled = v;
+    }
+
+    let recurrence = Recurrence::from_parts(row.cron_expr.as_deref(), row.interval_seconds)?;
+    let trigger_changed = old_trigger != (row.cron_expr.clone(), row.interval_seconds);
+    if !row.enabled {
+        row.next_run_at = None;
+    } else if trigger_changed || !was_enabled || row.next_run_at.is_none() {
+        let next = recurrence
+            .next_after(Utc::now())
+            .ok_or_else(|| "cron 表达式永远不会触发".to_string())?;
+        row.next_run_at = Some(format_utc(next));
+    }
+    Ok(())
+}
+
+/// 周期性计划列表（按名称排序）。
+pub async fn admin_list(
+    _: Require<TasksSchedule>,
+    State(state): State<AppState>,
+) -> axum::response::Response {
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+
+    match task_schedule::list_task_schedules(pool).await {
+        Ok(rows) => {
+            let items: Vec<TaskScheduleOut> = rows.into_iter().map(TaskScheduleOut::from).collect();
+            (StatusCode::OK, Json(json!({ "items": items }))).into_response()
+        }
+        Err(e) => errors::internal_response(&state, e),
+    }
+}
+
+pub async fn admin_create(
+    admin: Require<TasksSchedule>,
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Json(body): Json<TaskScheduleRequest>,
+) -> axum::response::Response {
+    let created_by = Some(admin.user_id);
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+
+    if body.name.as_deref().is_none_or(|s| s.trim().is_empty()) {
+        return bad_request("缺少 name");
+    }
+    if body.task_type.is_none() {
+        return bad_request("缺少 task_type");
+    }
+
+    let mut row = TaskScheduleRow {
+        id: String::new(),
+        name: String::new(),
+        task_type: String::new(),
+        payload_json: "{}".to_string(),
+        cron_expr: None,
+        interval_seconds: None,
+        priority: 0,
+        enabled: true,
+        last_run_at: None,
+        next_run_at: None,
+        last_job_id: None,
+        created_by,
+        created_at: String::new(),
+        updated_at: String::new(),
+    };
+    if let Err(e) = apply_request(&mut row, body) {
+        return bad_request(e);
+    }
+
+    match task_schedule::find_task_schedule_by_name(pool, &row.name).await {
+        Ok(Some(_)) => return name_conflict(&row.name),
+        Ok(None) => {}
+        Err(e) => return errors::internal_response(&state, e),
+    }
+
+    let id = match task_schedule::insert_task_schedule(
+        pool,
+        &row.name,
+        &row.task_type,
+        &row.payload_json,
+        row.cron_expr.as_deref(),
+        row.interval_seconds,
+        row.priority,
+        row.enabled,
+        row.next_run_at.as_deref(),
+        created_by,
+    )
+    .await
+    {
+        Ok(id) => id,
+        Err(e) => return errors::internal_response(&state, e),
+    };
+
+    match task_schedule::get_task_schedule(pool, &id).await {
+        Ok(Some(r)) => {
+            let out = TaskScheduleOut::from(r);
+            crate::audit::record(
+                &state,
+                &headers,
+                crate::audit::AuditEvent::new("task_schedules.create", "task_schedule")
+                    .target(&out.id)
+                    .after(json!(out)),
+            )
+            .await;
+            (StatusCode::CREATED, Json(out)).into_response()
+        }
+        Ok(None) => not_found(),
+        Err(e) => errors::internal_response(&state, e),
+    }
+}
+
+pub async fn admin_get(
+    _: Require<TasksSchedule>,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> axum::response::Response {
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+    if Uuid::parse_str(id.trim()).is_err() {
+        return not_found();
+    }
+
+    match task_schedule::get_task_schedule(pool, id.trim()).await {
+        Ok(Some(r)) => (StatusCode::OK, Json(TaskScheduleOut::from(r))).into_response(),
+        Ok(None) => not_found(),
+        Err(e) => errors::internal_response(&state, e),
+    }
+}
+
+/// 部分更新：只改请求体中出现的字段；改触发方式或重新启用时重算下一次执行时间。
+pub async fn admin_update(
+    _: Require<TasksSchedule>,
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Path(id): Path<String>,
+    Json(body): Json<TaskScheduleRequest>,
+) -> axum::response::Response {
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+    if Uuid::parse_str(id.trim()).is_err() {
+        return not_found();
+    }
+
+    let mut row = match task_schedule::get_task_schedule(pool, id.trim()).await {
+        Ok(Some(r)) => r,
+        Ok(None) => return not_found(),
+        Err(e) => return errors::internal_response(&state, e),
+    };
+    let before = json!(TaskScheduleOut::from(row.clone()));
+    let old_name = row.name.clone();
+    if let Err(e) = apply_request(&mut row, body) {
+        return bad_request(e);
+    }
+    if row.name != old_name {
+        match task_schedule::find_task_schedule_by_name(pool, &row.name).await {
+            Ok(Some(_)) => return name_conflict(&row.name),
+            Ok(None) => {}
+            Err(e) => return errors::internal_response(&state, e),
+        }
+    }
+
+    match task_schedule::save_task_schedule(pool, &row).await {
+        Ok(true) => {}
+        Ok(false) => return not_found(),
+        Err(e) => return errors::internal_response(&state, e),
+    }
+    match task_schedule::get_task_schedule(pool, &row.id).await {
+        Ok(Some(r)) => {
+            let out = TaskScheduleOut::from(r);
+            crate::audit::record(
+                &state,
+                &headers,
+                crate::audit::AuditEvent::new("task_schedules.update", "task_schedule")
+                    .target(&out.id)
+                    .before(before)
+                    .after(json!(out)),
+            )
+            .await;
+            (StatusCode::OK, Json(out)).into_response()
+        }
+        Ok(None) => not_found(),
+        Err(e) => errors::internal_response(&state, e),
+    }
+}
+
+pub async fn admin_delete(
+    _: Require<TasksSchedule>,
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Path(id): Path<String>,
+) -> axum::response::Response {
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+    if Uuid::parse_str(id.trim()).is_err() {
+        return not_found();
+    }
+
+    let before = match task_schedule::get_task_schedule(pool, id.trim()).await {
+        Ok(Some(r)) => json!(TaskScheduleOut::from(r)),
+        Ok(None) => return not_found(),
+        Err(e) => return errors::internal_response(&state, e),
+    };
+
+    match task_schedule::delete_task_schedule(pool, id.trim()).await {
+        Ok(true) => {
+            crate::audit::record(
+                &state,
+                &headers,
+                crate::audit::AuditEvent::new("task_schedules.delete", "task_schedule")
+                    .target(id.trim())
+                    .before(before),
+            )
+            .await;
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Ok(false) => not_found(),
+        Err(e) => errors::internal_response(&state, e),
+    }
+}
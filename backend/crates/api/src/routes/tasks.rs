@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sqlx::Row;
 
+use crate::permissions::{self, Capability, TasksViewAll};
 use crate::routes::auth;
 use crate::routes::errors;
 use crate::state::AppState;
@@ -44,6 +45,28 @@ pub struct TaskJobOut {
     pub finished_at: Option<String>,
     pub created_at: String,
     pub updated_at: String,
+    pub progress: Option<TaskProgressOut>,
+}
+
+/// 批量任务上报的进度（尚未上报时为 null）。
+#[derive(Debug, Serialize)]
+pub struct TaskProgressOut {
+    pub done: i64,
+    pub total: i64,
+    pub item: Option<String>,
+    pub eta_seconds: Option<i64>,
+    pub updated_at: Option<String>,
+}
+
+fn task_progress_out(r: &sqlx::any::AnyRow) -> Option<TaskProgressOut> {
+    let total = r.try_get::<Option<i64>, _>("progress_total").ok().flatten()?;
+    Some(TaskProgressOut {
+        done: r.try_get::<Option<i64>, _>("progress_done").ok().flatten().unwrap_or(0),
+        total,
+        item: r.try_get::<Option<String>, _>("progress_item").ok().flatten(),
+        eta_seconds: r.try_get::<Option<i64>, _>("progress_eta_seconds").ok().flatten(),
+        updated_at: r.try_get::<Option<String>, _>("progress_updated_at").ok().flatten(),
+    })
 }
 
 #[derive(Debug, Serialize)]
@@ -74,7 +97,7 @@ pub async fn overview(
     headers: axum::http::HeaderMap,
     axum::extract::Query(q): axum::extract::Query<TaskOverviewQuery>,
 ) -> axum::response::Response {
-    let _user_id = match auth::authenticate(&state, &headers) {
+    let user_id = match auth::authenticate(&state, &headers) {
         Ok(id) => id,
         Err(resp) => return resp,
     };
@@ -90,6 +113,10 @@ pub async fn overview(
         Some(p) => p,
     };
 
+    // 没有 tasks.view_all 时只展示自己创建的任务与系统任务。
+    let user_id_i64 = user_id.parse::<i64>().unwrap_or(0);
+    let view_all = permissions::user_has(pool, user_id_i64, TasksViewAll::NAME).await;
+
     let queued_limit = q.queued_limit.unwrap_or(200).clamp(1, 2000);
     let running_limit = q.running_limit.unwrap_or(50).clamp(1, 500);
     let recent_limit = q.recent_limit.unwrap_or(20).clamp(1, 200);
@@ -112,14 +139,22 @@ pub async fn overview(
           CAST(started_at AS TEXT) as started_at,
           CAST(finished_at AS TEXT) as finished_at,
           CAST(created_at AS TEXT) as created_at,
-          CAST(updated_at AS TEXT) as updated_at
+          CAST(updated_at AS TEXT) as updated_at,
+          progress_done,
+          progress_total,
+          progress_item,
+          progress_eta_seconds,
+          CAST(progress_updated_at AS TEXT) as progress_updated_at
         FROM task_job
         WHERE status IN ('queued','running')
+          AND ($2 = 1 OR created_by IS NULL OR created_by = $3)
         ORDER BY priority DESC, not_before ASC
         LIMIT $1
         "#,
     )
     .bind(queued_limit)
+    .bind(i64::from(view_all))
+    .bind(user_id_i64)
     .fetch_all(pool)
     .await;
 
@@ -150,6 +185,7 @@ pub async fn overview(
             finished_at: r.try_get::<Option<String>, _>("finished_at").ok().flatten(),
             created_at: r.get("created_at"),
             updated_at: r.get("updated_at"),
+            progress: task_progress_out(&r),
         });
     }
 
@@ -168,14 +204,22 @@ pub async fn overview(
           CAST(started_at AS TEXT) as started_at,
           CAST(finished_at AS TEXT) as finished_at,
           CAST(created_at AS TEXT) as created_at,
-          CAST(updated_at AS TEXT) as updated_at
+          CAST(updated_at AS TEXT) as updated_at,
+          progress_done,
+          progress_total,
+          progress_item,
+          progress_eta_seconds,
+          CAST(progress_updated_at AS TEXT) as progress_updated_at
         FROM task_job
-        WHERE status IN ('done','error')
+        WHERE status IN ('done','error','dead','cancelled')
+          AND ($2 = 1 OR created_by IS NULL OR created_by = $3)
         ORDER BY finished_at DESC NULLS LAST, updated_at DESC, created_at DESC
         LIMIT $1
         "#,
     )
     .bind(recent_limit)
+    .bind(i64::from(view_all))
+    .bind(user_id_i64)
     .fetch_all(pool)
     .await;
 
@@ -206,6 +250,7 @@ pub async fn overview(
             finished_at: r.try_get::<Option<String>, _>("finished_at").ok().flatten(),
             created_at: r.get("created_at"),
             updated_at: r.get("updated_at"),
+            progress: task_progress_out(&r),
         });
     }
 
@@ -224,11 +269,18 @@ pub async fn overview(
           CAST(finished_at AS TEXT) as finished_at
         FROM task_run
         WHERE status = 'running' AND queue_type = 'task_job'
+          AND ($2 = 1 OR NOT EXISTS (
+            SELECT 1 FROM task_job j
+            WHERE CAST(j.id AS TEXT) = CAST(task_run.job_id AS TEXT)
+              AND j.created_by IS NOT NULL AND j.created_by <> $3
+          ))
         ORDER BY started_at DESC
         LIMIT $1
         "#,
     )
     .bind(running_limit)
+    .bind(i64::from(view_all))
+    .bind(user_id_i64)
     .fetch_all(pool)
     .await;
 
@@ -273,12 +325,19 @@ pub async fn overview(
           CAST(started_at AS TEXT) as started_at,
           CAST(finished_at AS TEXT) as finished_at
         FROM task_run
-        WHERE finished_at IS NOT NULL AND status IN ('ok','error') AND queue_type = 'task_job'
+        WHERE finished_at IS NOT NULL AND status IN ('ok','error','cancelled') AND queue_type = 'task_job'
+          AND ($2 = 1 OR NOT EXISTS (
+            SELECT 1 FROM task_job j
+            WHERE CAST(j.id AS TEXT) = CAST(task_run.job_id AS TEXT)
+              AND j.created_by IS NOT NULL AND j.created_by <> $3
+          ))
         ORDER BY finished_at DESC
         LIMIT $1
         "#,
     )
     .bind(recent_limit)
+    .bind(i64::from(view_all))
+    .bind(user_id_i64)
     .fetch_all(pool)
     .await;
 
@@ -340,7 +399,7 @@ pub async fn run_logs(
     axum::extract::Path(run_id): axum::extract::Path<String>,
     axum::extract::Query(q): axum::extract::Query<TaskLogsQuery>,
 ) -> axum::response::Response {
-    let _user_id = match auth::authenticate(&state, &headers) {
+    let user_id = match auth::authenticate(&state, &headers) {
         Ok(id) => id,
         Err(resp) => return resp,
     };
@@ -356,6 +415,11 @@ pub async fn run_logs(
         Some(p) => p,
     };
 
+    let created_by = run_created_by(pool, run_id.trim()).await;
+    if let Err(resp) = ensure_can_view(&state, pool, &user_id, created_by).await {
+        return resp;
+    }
+
     let limit = q.limit.unwrap_or(500).clamp(1, 2000);
     let rows = sqlx::query(
         r#"
@@ -408,7 +472,7 @@ pub async fn job_detail(
     headers: axum::http::HeaderMap,
     axum::extract::Path(job_id): axum::extract::Path<String>,
 ) -> axum::response::Response {
-    let _user_id = match auth::authenticate(&state, &headers) {
+    let user_id = match auth::authenticate(&state, &headers) {
         Ok(id) => id,
         Err(resp) => return resp,
     };
@@ -439,7 +503,12 @@ pub async fn job_detail(
           CAST(started_at AS TEXT) as started_at,
           CAST(finished_at AS TEXT) as finished_at,
           CAST(created_at AS TEXT) as created_at,
-          CAST(updated_at AS TEXT) as updated_at
+          CAST(updated_at AS TEXT) as updated_at,
+          progress_done,
+          progress_total,
+          progress_item,
+          progress_eta_seconds,
+          CAST(progress_updated_at AS TEXT) as progress_updated_at
         FROM task_job
         WHERE CAST(id AS TEXT) = $1
         "#,
@@ -475,7 +544,11 @@ pub async fn job_detail(
         finished_at: r.try_get::<Option<String>, _>("finished_at").ok().flatten(),
         created_at: r.get("created_at"),
         updated_at: r.get("updated_at"),
+        progress: task_progress_out(&r),
     };
+    if !can_view_job(pool, &user_id, job.created_by).await {
+        return (StatusCode::NOT_FOUND, Json(json!({ "detail": "Not found." }))).into_response();
+    }
 
     let last_run_row = sqlx::query(
         r#"
@@ -537,7 +610,7 @@ pub async fn job_runs(
     axum::extract::Path(job_id): axum::extract::Path<String>,
     axum::extract::Query(q): axum::extract::Query<TaskRunsQuery>,
 ) -> axum::response::Response {
-    let _user_id = match auth::authenticate(&state, &headers) {
+    let user_id = match auth::authenticate(&state, &headers) {
         Ok(id) => id,
         Err(resp) => return resp,
     };
@@ -553,6 +626,11 @@ pub async fn job_runs(
         Some(p) => p,
     };
 
+    let created_by = job_created_by(pool, job_id.trim()).await;
+    if let Err(resp) = ensure_can_view(&state, pool, &user_id, created_by).await {
+        return resp;
+    }
+
     let limit = q.limit.unwrap_or(50).clamp(1, 500);
     let rows = sqlx::query(
         r#"
@@ -614,7 +692,7 @@ pub async fn job_logs(
     axum::extract::Path(job_id): axum::extract::Path<String>,
     axum::extract::Query(q): axum::extract::Query<TaskLogsQuery>,
 ) -> axum::response::Response {
-    let _user_id = match auth::authenticate(&state, &headers) {
+    let user_id = match auth::authenticate(&state, &headers) {
         Ok(id) => id,
         Err(resp) => return resp,
     };
@@ -630,6 +708,11 @@ pub async fn job_logs(
         Some(p) => p,
     };
 
+    let created_by = job_created_by(pool, job_id.trim()).await;
+    if let Err(resp) = ensure_can_view(&state, pool, &user_id, created_by).await {
+        return resp;
+    }
+
     let run_row = sqlx::query(
         r#"
         SELECT CAST(id AS TEXT) as id
@@ -667,3 +750,610 @@ pub async fn job_logs(
     )
     .await
 }
+
+/// 他人创建的任务需要 tasks.view_all；系统任务（created_by 为空）对所有登录用户可见。
+async fn can_view_job(pool: &sqlx::AnyPool, user_id: &str, created_by: Option<i64>) -> bool {
+    created_by.is_none() || can_manage_job(pool, user_id, created_by).await
+}
+
+/// 只有任务创建者或持有 tasks.view_all 的用户可以取消/重排任务。
+async fn can_manage_job(pool: &sqlx::AnyPool, user_id: &str, created_by: Option<i64>) -> bool {
+    let Ok(user_id) = user_id.parse::<i64>() else {
+        return false;
+    };
+    created_by == Some(user_id) || permissions::user_has(pool, user_id, TasksViewAll::NAME).await
+}
+
+/// 任务的 created_by；任务不存在时返回 None。
+async fn job_created_by(
+    pool: &sqlx::AnyPool,
+    job_id: &str,
+) -> Result<Option<Option<i64>>, sqlx::Error> {
+    let row = sqlx::query("SELECT created_by FROM task_job WHERE CAST(id AS TEXT) = $1")
+        .bind(job_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.map(|r| r.try_get::<Option<i64>, _>("created_by").ok().flatten()))
+}
+
+/// run 所属任务的 created_by；run 不存在时返回 None，非 task_job 队列的 run 视为系统任务。
+async fn run_created_by(
+    pool: &sqlx::AnyPool,
+    run_id: &str,
+) -> Result<Option<Option<i64>>, sqlx::Error> {
+    let row = sqlx::query(
+        r#"
+        SELECT j.created_by as created_by
+        FROM task_run r
+        LEFT JOIN task_job j
+          ON r.queue_type = 'task_job' AND CAST(j.id AS TEXT) = CAST(r.job_id AS TEXT)
+        WHERE CAST(r.id AS TEXT) = $1
+        "#,
+    )
+    .bind(run_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|r| r.try_get::<Option<i64>, _>("created_by").ok().flatten()))
+}
+
+/// 读取任务/run 前校验可见性：他人任务返回 403，不存在时由调用方决定如何处理。
+async fn ensure_can_view(
+    state: &AppState,
+    pool: &sqlx::AnyPool,
+    user_id: &str,
+    created_by: Result<Option<Option<i64>>, sqlx::Error>,
+) -> Result<bool, axum::response::Response> {
+    match created_by {
+        Ok(None) => Ok(false),
+        Ok(Some(created_by)) if can_view_job(pool, user_id, created_by).await => Ok(true),
+        Ok(Some(_)) => Err((
+            StatusCode::FORBIDDEN,
+            Json(json!({ "detail": "You do not have permission to perform this action." })),
+        )
+            .into_response()),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            errors::internal_json(state, e),
+        )
+            .into_response()),
+    }
+}
+
+async fn change_job_state(
+    state: AppState,
+    headers: axum::http::HeaderMap,
+    job_id: String,
+    action: &str,
+) -> axum::response::Response {
+    let user_id = match auth::authenticate(&state, &headers) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+
+    let pool = match state.pool() {
+        None => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "database not configured" })),
+            )
+                .into_response();
+        }
+        Some(p) => p,
+    };
+
+    let job = match crate::tasks::get_task_job(pool, job_id.trim()).await {
+        Ok(Some(j)) => j,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json(json!({ "detail": "Not found." }))).into_response();
+        }
+        Err(e) => return errors::internal_response(&state, e),
+    };
+    if !can_manage_job(pool, &user_id, job.created_by).await {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(json!({ "detail": "You do not have permission to perform this action." })),
+        )
+            .into_response();
+    }
+
+    let changed = if action == "cancel" {
+        crate::tasks::cancel_task_job(pool, &job.id).await
+    } else {
+        crate::tasks::requeue_task_job(pool, &job.id).await
+    };
+    match changed {
+        Ok(true) => {}
+        Ok(false) => {
+            let verb = if action == "cancel" { "取消" } else { "重新排队" };
+            return (
+                StatusCode::CONFLICT,
+                Json(json!({ "error": format!("任务当前状态为 {}，无法{verb}", job.status) })),
+            )
+                .into_response();
+        }
+        Err(e) => return errors::internal_response(&state, e),
+    }
+
+    match crate::tasks::get_task_job(pool, &job.id).await {
+        Ok(Some(j)) => {
+            crate::audit::record(
+                &state,
+                &headers,
+                crate::audit::AuditEvent::new(&format!("tasks.job.{action}"), "task_job")
+                    .target(&j.id)
+                    .before(json!({ "status": job.status, "attempt": job.attempt }))
+                    .after(json!({ "status": j.status, "attempt": j.attempt })),
+            )
+            .await;
+            (
+                StatusCode::OK,
+                Json(json!({ "id": j.id, "status": j.status, "attempt": j.attempt })),
+            )
+                .into_response()
+        }
+        Ok(None) => (StatusCode::NOT_FOUND, Json(json!({ "detail": "Not found." }))).into_response(),
+        Err(e) => errors::internal_response(&state, e),
+    }
+}
+
+/// 取消排队中/执行中的任务（执行中的任务在下次心跳时停止）。
+pub async fn cancel_job(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Path(job_id): axum::extract::Path<String>,
+) -> axum::response::Response {
+    change_job_state(state, headers, job_id, "cancel").await
+}
+
+/// 把已结束的任务（含死信）重新排队，重试计数清零。
+pub async fn requeue_job(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Path(job_id): axum::extract::Path<String>,
+) -> axum::response::Response {
+    change_job_state(state, headers, job_id, "requeue").await
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeadLetterQuery {
+    pub task_type: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeadLetterJobOut {
+    #[serde(flatten)]
+    pub job: TaskJobOut,
+    pub max_attempts: i64,
+}
+
+/// 死信队列：重试耗尽（status=dead）的任务，按结束时间倒序。
+pub async fn dead_letter(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Query(q): axum::extract::Query<DeadLetterQuery>,
+) -> axum::response::Response {
+    let user_id = match auth::authenticate(&state, &headers) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+
+    let pool = match state.pool() {
+        None => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "database not configured" })),
+            )
+                .into_response();
+        }
+        Some(p) => p,
+    };
+
+    let limit = q.limit.unwrap_or(100).clamp(1, 1000);
+    let task_type = q.task_type.as_deref().map(|s| s.trim()).unwrap_or("");
+    let user_id_i64 = user_id.parse::<i64>().unwrap_or(0);
+    let view_all = permissions::user_has(pool, user_id_i64, TasksViewAll::NAME).await;
+    let rows = sqlx::query(
+        r#"
+        SELECT
+          CAST(id AS TEXT) as id,
+          task_type,
+          payload_json,
+          priority,
+          CAST(not_before AS TEXT) as not_before,
+          status,
+          attempt,
+          error,
+          created_by,
+          CAST(started_at AS TEXT) as started_at,
+          CAST(finished_at AS TEXT) as finished_at,
+          CAST(created_at AS TEXT) as created_at,
+          CAST(updated_at AS TEXT) as updated_at,
+          progress_done,
+          progress_total,
+          progress_item,
+          progress_eta_seconds,
+          CAST(progress_updated_at AS TEXT) as progress_updated_at
+        FROM task_job
+        WHERE status = 'dead' AND ($1 = '' OR task_type = $1)
+          AND ($3 = 1 OR created_by IS NULL OR created_by = $4)
+        ORDER BY finished_at DESC, updated_at DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(task_type)
+    .bind(limit)
+    .bind(i64::from(view_all))
+    .bind(user_id_i64)
+    .fetch_all(pool)
+    .await;
+
+    let rows = match rows {
+        Ok(v) => v,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                errors::internal_json(&state, e),
+            )
+                .into_response();
+        }
+    };
+
+    let mut out: Vec<DeadLetterJobOut> = Vec::with_capacity(rows.len());
+    for r in rows {
+        let task_type: String = r.get("task_type");
+        out.push(DeadLetterJobOut {
+            max_attempts: crate::tasks::retry_policy(&task_type).max_attempts,
+            job: TaskJobOut {
+                id: r.get("id"),
+                task_type,
+                payload_json: r.get::<String, _>("payload_json"),
+                priority: r.get("priority"),
+                not_before: r.get("not_before"),
+                status: r.get("status"),
+                attempt: r.get("attempt"),
+                error: r.try_get::<Option<String>, _>("error").ok().flatten(),
+                created_by: r.try_get::<Option<i64>, _>("created_by").ok().flatten(),
+                started_at: r.try_get::<Option<String>, _>("started_at").ok().flatten(),
+                finished_at: r.try_get::<Option<String>, _>("finished_at").ok().flatten(),
+                created_at: r.get("created_at"),
+                updated_at: r.get("updated_at"),
+                progress: task_progress_out(&r),
+            },
+        });
+    }
+
+    (StatusCode::OK, Json(out)).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TaskStreamQuery {
+    /// 浏览器 EventSource 无法携带 Authorization 头，允许用查询参数传 access token。
+    pub access_token: Option<String>,
+    /// 轮询间隔（毫秒），默认 1000。
+    pub poll_ms: Option<u64>,
+}
+
+const STREAM_LOG_BATCH: i64 = 500;
+
+fn stream_headers(headers: &axum::http::HeaderMap, q: &TaskStreamQuery) -> axum::http::HeaderMap {
+    let mut out = headers.clone();
+    if !out.contains_key(axum::http::header::AUTHORIZATION)
+        && let Some(token) = q.access_token.as_deref().map(str::trim).filter(|t| !t.is_empty())
+        && let Ok(v) = axum::http::HeaderValue::from_str(&format!("Bearer {token}"))
+    {
+        out.insert(axum::http::header::AUTHORIZATION, v);
+    }
+    out
+}
+
+enum StreamTarget {
+    /// 跟随任务的最新一次 run（重试会切换到新的 run），同时推送进度。
+    Job(String),
+    /// 只推送某一次 run 的日志。
+    Run(String),
+}
+
+struct TaskStream {
+    pool: sqlx::AnyPool,
+    target: StreamTarget,
+    poll: std::time::Duration,
+    run_id: Option<String>,
+    last_seq: i64,
+    last_progress: Option<serde_json::Value>,
+    pending: std::collections::VecDeque<axum::response::sse::Event>,
+    started: bool,
+    finished: bool,
+}
+
+impl TaskStream {
+    /// 推送 run 中 seq 之后的日志；返回本次读到的行数。
+    async fn push_logs(&mut self, run_id: &str) -> Result<i64, String> {
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT
+              seq,
+              level,
+              message,
+              CAST(created_at AS TEXT) as created_at
+            FROM task_run_log
+            WHERE CAST(run_id AS TEXT) = $1 AND seq > $2
+            ORDER BY seq ASC
+            LIMIT {STREAM_LOG_BATCH}
+            "#
+        ))
+        .bind(run_id)
+        .bind(self.last_seq)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        let n = rows.len() as i64;
+        for r in rows {
+            let seq: i64 = r.get("seq");
+            let data = json!({
+                "run_id": run_id,
+                "seq": seq,
+                "level": r.get::<String, _>("level"),
+                "message": r.get::<String, _>("message"),
+                "created_at": r.get::<String, _>("created_at"),
+            });
+            self.pending.push_back(
+                axum::response::sse::Event::default()
+                    .event("log")
+                    .id(format!("{run_id}:{seq}"))
+                    .data(data.to_string()),
+            );
+            self.last_seq = seq;
+        }
+        Ok(n)
+    }
+
+    fn push_end(&mut self, data: serde_json::Value) {
+        self.pending.push_back(
+            axum::response::sse::Event::default()
+                .event("end")
+                .data(data.to_string()),
+        );
+        self.finished = true;
+    }
+
+    async fn poll_job(&mut self, job_id: &str) -> Result<(), String> {
+        let Some(r) = sqlx::query(
+            r#"
+            SELECT
+              status,
+              attempt,
+              progress_done,
+              progress_total,
+              progress_item,
+              progress_eta_seconds,
+              CAST(progress_updated_at AS TEXT) as progress_updated_at
+            FROM task_job
+            WHERE CAST(id AS TEXT) = $1
+            "#,
+        )
+        .bind(job_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| e.to_string())?
+        else {
+            self.push_end(json!({ "id": job_id, "status": "missing" }));
+            return Ok(());
+        };
+        let status: String = r.get("status");
+        let progress = json!({
+            "id": job_id,
+            "status": status,
+            "attempt": r.get::<i64, _>("attempt"),
+            "progress": task_progress_out(&r),
+        });
+
+        let latest_run = sqlx::query(
+            r#"
+            SELECT CAST(id AS TEXT) as id
+            FROM task_run
+            WHERE queue_type = 'task_job' AND CAST(job_id AS TEXT) = $1
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(job_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .map(|r| r.get::<String, _>("id"));
+
+        if latest_run.is_some() && latest_run != self.run_id {
+            // 切换到新的 run 前，先把上一次 run 剩余的日志推完。
+            if let Some(old) = self.run_id.clone() {
+                while self.push_logs(&old).await? >= STREAM_LOG_BATCH {}
+            }
+            self.run_id = latest_run;
+            self.last_seq = 0;
+        }
+        let fetched = match self.run_id.clone() {
+            Some(run_id) => self.push_logs(&run_id).await?,
+            None => 0,
+        };
+
+        if self.last_progress.as_ref() != Some(&progress) {
+            self.pending.push_back(
+                axum::response::sse::Event::default()
+                    .event("progress")
+                    .data(progress.to_string()),
+            );
+            self.last_progress = Some(progress.clone());
+        }
+
+        let terminal = matches!(status.as_str(), "done" | "error" | "dead" | "cancelled");
+        if terminal && fetched < STREAM_LOG_BATCH {
+            self.push_end(progress);
+        }
+        Ok(())
+    }
+
+    async fn poll_run(&mut self, run_id: &str) -> Result<(), String> {
+        let status = sqlx::query("SELECT status FROM task_run WHERE CAST(id AS TEXT) = $1")
+            .bind(run_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| e.to_string())?
+            .map(|r| r.get::<String, _>("status"));
+        let Some(status) = status else {
+            self.push_end(json!({ "run_id": run_id, "status": "missing" }));
+            return Ok(());
+        };
+
+        let fetched = self.push_logs(run_id).await?;
+        if status != "running" && fetched < STREAM_LOG_BATCH {
+            self.push_end(json!({ "run_id": run_id, "status": status }));
+        }
+        Ok(())
+    }
+
+    async fn poll_once(&mut self) -> Result<(), String> {
+        match &self.target {
+            StreamTarget::Job(id) => {
+                let id = id.clone();
+                self.poll_job(&id).await
+            }
+            StreamTarget::Run(id) => {
+                let id = id.clone();
+                self.poll_run(&id).await
+            }
+        }
+    }
+
+    fn into_response(self) -> axum::response::Response {
+        let stream = futures_util::stream::unfold(self, |mut st| async move {
+            loop {
+                if let Some(ev) = st.pending.pop_front() {
+                    return Some((Ok::<_, std::convert::Infallible>(ev), st));
+                }
+                if st.finished {
+                    return None;
+                }
+                if st.started {
+                    tokio::time::sleep(st.poll).await;
+                }
+                st.started = true;
+                if let Err(e) = st.poll_once().await {
+                    // 数据库偶发错误：下一轮继续重试，不中断连接。
+                    tracing::warn!(error = %e, "task stream poll failed");
+                }
+            }
+        });
+        axum::response::sse::Sse::new(stream)
+            .keep_alive(axum::response::sse::KeepAlive::default())
+            .into_response()
+    }
+}
+
+/// SSE：推送任务进度（event: progress）与最新一次 run 的日志（event: log），任务结束后发送 end 并关闭。
+///
+/// 断线重连时浏览器会带上 Last-Event-ID（`{run_id}:{seq}`），从该位置继续推送日志。
+pub async fn job_stream(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Path(job_id): axum::extract::Path<String>,
+    axum::extract::Query(q): axum::extract::Query<TaskStreamQuery>,
+) -> axum::response::Response {
+    let user_id = match auth::authenticate(&state, &stream_headers(&headers, &q)) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+
+    let pool = match state.pool() {
+        None => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "database not configured" })),
+            )
+                .into_response();
+        }
+        Some(p) => p,
+    };
+
+    let job_id = job_id.trim().to_string();
+    match ensure_can_view(&state, pool, &user_id, job_created_by(pool, &job_id).await).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (StatusCode::NOT_FOUND, Json(json!({ "detail": "Not found." }))).into_response();
+        }
+        Err(resp) => return resp,
+    }
+
+    let (run_id, last_seq) = headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.rsplit_once(':'))
+        .and_then(|(run, seq)| Some((Some(run.to_string()), seq.parse::<i64>().ok()?)))
+        .unwrap_or((None, 0));
+
+    TaskStream {
+        pool: pool.clone(),
+        target: StreamTarget::Job(job_id),
+        poll: std::time::Duration::from_millis(q.poll_ms.unwrap_or(1000).clamp(200, 10_000)),
+        run_id,
+        last_seq,
+        last_progress: None,
+        pending: Default::default(),
+        started: false,
+        finished: false,
+    }
+    .into_response()
+}
+
+/// SSE：推送某一次 run 的日志（event: log），run 结束后发送 end 并关闭。
+pub async fn run_logs_stream(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Path(run_id): axum::extract::Path<String>,
+    axum::extract::Query(q): axum::extract::Query<TaskStreamQuery>,
+) -> axum::response::Response {
+    let user_id = match auth::authenticate(&state, &stream_headers(&headers, &q)) {
+        Ok(id) => id,
+        Err(resp) => return resp,
+    };
+
+    let pool = match state.pool() {
+        None => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "database not configured" })),
+            )
+                .into_response();
+        }
+        Some(p) => p,
+    };
+
+    let run_id = run_id.trim().to_string();
+    match ensure_can_view(&state, pool, &user_id, run_created_by(pool, &run_id).await).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (StatusCode::NOT_FOUND, Json(json!({ "detail": "Not found." }))).into_response();
+        }
+        Err(resp) => return resp,
+    }
+
+    let last_seq = headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.rsplit_once(':'))
+        .and_then(|(_, seq)| seq.parse::<i64>().ok())
+        .unwrap_or(0);
+
+    TaskStream {
+        pool: pool.clone(),
+        target: StreamTarget::Run(run_id.clone()),
+        poll: std::time::Duration::from_millis(q.poll_ms.unwrap_or(1000).clamp(200, 10_000)),
+        run_id: Some(run_id),
+        last_seq,
+        last_progress: None,
+        pending: Default::default(),
+        started: false,
+        finished: false,
+    }
+    .into_response()
+}
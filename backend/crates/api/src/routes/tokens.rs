@@ -0,0 +1,162 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::audit::{self, AuditEvent};
+use crate::personal_token::{self, SCOPES};
+use crate::routes::{auth, errors};
+use crate::state::AppState;
+
+/// 每个用户最多持有的有效令牌数。
+const MAX_TOKENS_PER_USER: usize = 50;
+
+fn bad_request(msg: impl Into<String>) -> axum::response::Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({ "error": msg.into() })),
+    )
+        .into_response()
+}
+
+#[allow(clippy::result_large_err)]
+fn user_id(
+    state: &AppState,
+    headers: &axum::http::HeaderMap,
+) -> Result<i64, axum::response::Response> {
+    let id = auth::authenticate(state, headers)?;
+    id.parse::<i64>()
+        .map_err(|_| auth::invalid_token_response())
+}
+
+pub async fn list(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+) -> axum::response::Response {
+    let uid = match user_id(&state, &headers) {
+        Ok(v) => v,
+        Err(resp) => return resp,
+    };
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+    match personal_token::list(pool, uid).await {
+        Ok(items) => (
+            StatusCode::OK,
+            Json(json!({ "items": items, "scopes": SCOPES })),
+        )
+            .into_response(),
+        Err(e) => errors::internal_response(&state, e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTokenRequest {
+    pub name: Option<String>,
+    pub scopes: Option<Vec<String>>,
+    /// 有效天数；省略表示永不过期。
+    pub expires_in_days: Option<i64>,
+}
+
+/// 新建令牌：明文只在本次响应中返回。
+pub async fn create(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Json(body): Json<CreateTokenRequest>,
+) -> axum::response::Response {
+    let uid = match user_id(&state, &headers) {
+        Ok(v) => v,
+        Err(resp) => return resp,
+    };
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+
+    let name = body.name.unwrap_or_default().trim().to_string();
+    if name.is_empty() || name.chars().count() > 100 {
+        return bad_request("名称不能为空且不超过 100 个字符");
+    }
+    let mut scopes: Vec<String> = Vec::new();
+    for s in body.scopes.unwrap_or_default() {
+        let s = s.trim().to_string();
+        if !SCOPES.contains(&s.as_str()) {
+            return bad_request(format!("未知作用域: {s}"));
+        }
+        if !scopes.contains(&s) {
+            scopes.push(s);
+        }
+    }
+    if scopes.is_empty() {
+        return bad_request("至少需要一个作用域");
+    }
+    scopes.sort();
+    let expires_at = match body.expires_in_days {
+        None => None,
+        Some(d) if (1..=3650).contains(&d) => Some(Utc::now() + Duration::days(d)),
+        Some(_) => return bad_request("expires_in_days 需在 1..=3650 之间"),
+    };
+
+    match personal_token::list(pool, uid).await {
+        Ok(v) if v.len() >= MAX_TOKENS_PER_USER => {
+            return bad_request(format!("最多只能保留 {MAX_TOKENS_PER_USER} 个令牌"));
+        }
+        Ok(_) => {}
+        Err(e) => return errors::internal_response(&state, e),
+    }
+
+    match personal_token::create(pool, uid, &name, &scopes, expires_at).await {
+        Ok((row, token)) => {
+            audit::record(
+                &state,
+                &headers,
+                AuditEvent::new("auth.token.create", "personal_token")
+                    .target(&row.id)
+                    .after(json!(row)),
+            )
+            .await;
+            let mut out = json!(row);
+            out["token"] = json!(token);
+            (StatusCode::CREATED, Json(out)).into_response()
+        }
+        Err(e) => errors::internal_response(&state, e),
+    }
+}
+
+pub async fn revoke(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Path(id): Path<String>,
+) -> axum::response::Response {
+    let uid = match user_id(&state, &headers) {
+        Ok(v) => v,
+        Err(resp) => return resp,
+    };
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+    match personal_token::revoke(pool, &id, uid).await {
+        Ok(Some(row)) => {
+            state.personal_tokens().forget_id(&id);
+            audit::record(
+                &state,
+                &headers,
+                AuditEvent::new("auth.token.revoke", "personal_token")
+                    .target(&id)
+                    .before(json!(row)),
+            )
+            .await;
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "detail": "Not found." })),
+        )
+            .into_response(),
+        Err(e) => errors::internal_response(&state, e),
+    }
+}
@@ -0,0 +1,297 @@
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::Row;
+
+use crate::audit::{self, AuditEvent};
+use crate::django_password;
+use crate::login_guard;
+use crate::routes::{auth, errors};
+use crate::state::AppState;
+use crate::totp;
+
+fn bad_request(msg: impl Into<String>) -> axum::response::Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({ "error": msg.into() })),
+    )
+        .into_response()
+}
+
+fn already_enabled_response() -> axum::response::Response {
+    (
+        StatusCode::CONFLICT,
+        Json(json!({ "error": "两步验证已启用，如需更换请先关闭" })),
+    )
+        .into_response()
+}
+
+#[allow(clippy::result_large_err)]
+fn user_id(
+    state: &AppState,
+    headers: &axum::http::HeaderMap,
+) -> Result<i64, axum::response::Response> {
+    let id = auth::authenticate(state, headers)?;
+    id.parse::<i64>()
+        .map_err(|_| auth::invalid_token_response())
+}
+
+#[allow(clippy::result_large_err)]
+async fn username(
+    state: &AppState,
+    pool: &sqlx::AnyPool,
+    uid: i64,
+) -> Result<String, axum::response::Response> {
+    match sqlx::query("SELECT username FROM auth_user WHERE id = $1")
+        .bind(uid)
+        .fetch_optional(pool)
+        .await
+    {
+        Ok(Some(r)) => Ok(r.get::<String, _>("username")),
+        Ok(None) => Err(auth::invalid_token_response()),
+        Err(e) => Err(errors::internal_response(state, e)),
+    }
+}
+
+/// 与登录共用防爆破计数：被盗会话不能借这些接口无限次猜测密码或验证码。
+async fn throttled(
+    state: &AppState,
+    pool: &sqlx::AnyPool,
+    headers: &axum::http::HeaderMap,
+    username: &str,
+) -> Option<axum::response::Response> {
+    match login_guard::retry_after(pool, &auth::guard_keys(username, headers)).await {
+        Ok(Some(secs)) => Some(auth::too_many_attempts_response(secs)),
+        Ok(None) => None,
+        Err(e) => Some(errors::internal_response(state, e)),
+    }
+}
+
+/// 记一次失败（计入锁定计数）后返回 400。
+async fn failed_attempt(
+    state: &AppState,
+    pool: &sqlx::AnyPool,
+    headers: &axum::http::HeaderMap,
+    username: &str,
+    msg: &str,
+) -> axum::response::Response {
+    if let Err(e) = auth::register_login_failure(state, pool, headers, username).await {
+        return errors::internal_response(state, e);
+    }
+    bad_request(msg)
+}
+
+/// 两步验证状态：是否启用、是否有待确认的密钥、剩余恢复码数量。
+pub async fn status(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+) -> axum::response::Response {
+    let uid = match user_id(&state, &headers) {
+        Ok(v) => v,
+        Err(resp) => return resp,
+    };
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+    let row = match totp::get(pool, uid).await {
+        Ok(v) => v,
+        Err(e) => return errors::internal_response(&state, e),
+    };
+    let remaining = match totp::remaining_recovery_codes(pool, uid).await {
+        Ok(v) => v,
+        Err(e) => return errors::internal_response(&state, e),
+    };
+    (
+        StatusCode::OK,
+        Json(json!({
+            "enabled": row.as_ref().is_some_and(|t| t.enabled),
+            "pending": row.as_ref().is_some_and(|t| !t.enabled),
+            "recovery_codes_remaining": remaining,
+        })),
+    )
+        .into_response()
+}
+
+/// 开始注册：生成新密钥并返回 otpauth URI；需再用验证码调用 enable 确认。
+pub async fn setup(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+) -> axum::response::Response {
+    let uid = match user_id(&state, &headers) {
+        Ok(v) => v,
+        Err(resp) => return resp,
+    };
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+    match totp::is_enabled(pool, uid).await {
+        Ok(true) => return already_enabled_response(),
+        Ok(false) => {}
+        Err(e) => return errors::internal_response(&state, e),
+    }
+    let username = match username(&state, pool, uid).await {
+        Ok(v) => v,
+        Err(resp) => return resp,
+    };
+    // 条件写入：与上面的检查并发时，已启用的密钥不会被覆盖。
+    match totp::begin_enrollment(pool, uid).await {
+        Ok(Some(secret)) => (
+            StatusCode::OK,
+            Json(json!({
+                "otpauth_uri": totp::otpauth_uri(&username, &secret),
+                "secret": secret,
+            })),
+        )
+            .into_response(),
+        Ok(None) => already_enabled_response(),
+        Err(e) => errors::internal_response(&state, e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CodeRequest {
+    pub code: String,
+}
+
+/// 确认注册：校验一次验证码后启用，并返回恢复码（仅此一次）。
+pub async fn enable(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Json(body): Json<CodeRequest>,
+) -> axum::response::Response {
+    let uid = match user_id(&state, &headers) {
+        Ok(v) => v,
+        Err(resp) => return resp,
+    };
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+    let username = match username(&state, pool, uid).await {
+        Ok(v) => v,
+        Err(resp) => return resp,
+    };
+    if let Some(resp) = throttled(&state, pool, &headers, &username).await {
+        return resp;
+    }
+    match totp::confirm_enrollment(pool, uid, &body.code).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return failed_attempt(&state, pool, &headers, &username, "验证码错误或未开始注册")
+                .await;
+        }
+        Err(e) => return errors::internal_response(&state, e),
+    }
+    let codes = match totp::regenerate_recovery_codes(pool, uid).await {
+        Ok(v) => v,
+        Err(e) => return errors::internal_response(&state, e),
+    };
+    audit::record(
+        &state,
+        &headers,
+        AuditEvent::new("auth.2fa.enable", "user").target(uid),
+    )
+    .await;
+    (
+        StatusCode::OK,
+        Json(json!({ "enabled": true, "recovery_codes": codes })),
+    )
+        .into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DisableRequest {
+    pub password: String,
+    pub code: String,
+}
+
+/// 关闭两步验证：需同时提供密码与当前验证码（或恢复码）。
+pub async fn disable(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Json(body): Json<DisableRequest>,
+) -> axum::response::Response {
+    let uid = match user_id(&state, &headers) {
+        Ok(v) => v,
+        Err(resp) => return resp,
+    };
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+    let (username, hash) =
+        match sqlx::query("SELECT username, password FROM auth_user WHERE id = $1")
+            .bind(uid)
+            .fetch_optional(pool)
+            .await
+        {
+            Ok(Some(r)) => (
+                r.get::<String, _>("username"),
+                r.get::<String, _>("password"),
+            ),
+            Ok(None) => return auth::invalid_token_response(),
+            Err(e) => return errors::internal_response(&state, e),
+        };
+    if let Some(resp) = throttled(&state, pool, &headers, &username).await {
+        return resp;
+    }
+    let failed = if !django_password::verify_password(&body.password, &hash) {
+        Some("密码错误")
+    } else {
+        match totp::verify_second_factor(pool, uid, &body.code).await {
+            Ok(Some(_)) => None,
+            Ok(None) => Some("验证码错误"),
+            Err(e) => return errors::internal_response(&state, e),
+        }
+    };
+    if let Some(msg) = failed {
+        return failed_attempt(&state, pool, &headers, &username, msg).await;
+    }
+    if let Err(e) = totp::disable(pool, uid).await {
+        return errors::internal_response(&state, e);
+    }
+    audit::record(
+        &state,
+        &headers,
+        AuditEvent::new("auth.2fa.disable", "user").target(uid),
+    )
+    .await;
+    (StatusCode::OK, Json(json!({ "enabled": false }))).into_response()
+}
+
+/// 重新生成恢复码（旧码全部作废）；需提供当前验证码。
+pub async fn regenerate_recovery_codes(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Json(body): Json<CodeRequest>,
+) -> axum::response::Response {
+    let uid = match user_id(&state, &headers) {
+        Ok(v) => v,
+        Err(resp) => return resp,
+    };
+    let Some(pool) = state.pool() else {
+        return errors::internal_response(&state, "database not configured");
+    };
+    let username = match username(&state, pool, uid).await {
+        Ok(v) => v,
+        Err(resp) => return resp,
+    };
+    if let Some(resp) = throttled(&state, pool, &headers, &username).await {
+        return resp;
+    }
+    match totp::verify_second_factor(pool, uid, &body.code).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return failed_attempt(&state, pool, &headers, &username, "验证码错误").await,
+        Err(e) => return errors::internal_response(&state, e),
+    }
+    match totp::regenerate_recovery_codes(pool, uid).await {
+        Ok(codes) => {
+            audit::record(
+                &state,
+                &headers,
+                AuditEvent::new("auth.2fa.recovery_codes.regenerate", "user").target(uid),
+            )
+            .await;
+            (StatusCode::OK, Json(json!({ "recovery_codes": codes }))).into_response()
+        }
+        Err(e) => errors::internal_response(&state, e),
+    }
+}
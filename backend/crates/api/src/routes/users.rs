@@ -53,6 +53,7 @@ pub struct SummaryResponse {
 
 pub async fn register(
     axum::extract::State(state): axum::extract::State<AppState>,
+    headers: axum::http::HeaderMap,
     Json(body): Json<RegisterRequest>,
 ) -> axum::response::Response {
     if !state.config().allow_register() {
@@ -235,10 +236,30 @@ pub async fn register(
         }
     };
 
-    // 生成 token
-    let jwt = state.jwt();
-    let access_token = jwt.issue_access_token(&id.to_string());
-    let refresh_token = jwt.issue_refresh_token(&id.to_string());
+    crate::audit::record(
+        &state,
+        &headers,
+        crate::audit::AuditEvent::new("users.register", "user")
+            .target(id)
+            .actor(id)
+            .after(serde_json::json!({ "username": username })),
+    )
+    .await;
+
+    // 生成 token（同时建立会话）
+    let (access_token, refresh_token) =
+        match auth::issue_session_tokens(&state, pool, &id.to_string(), &headers).await {
+            Ok(v) => v,
+            Err(e) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse {
+                        error: errors::internal_message(&state, e),
+                    }),
+                )
+                    .into_response();
+            }
+        };
 
     (
         StatusCode::CREATED,
@@ -229,6 +229,15 @@ pub async fn create(
             .into_response();
     }
 
+    crate::audit::record(
+        &state,
+        &headers,
+        crate::audit::AuditEvent::new("watchlists.create", "watchlist")
+            .target(&id)
+            .after(json!({ "name": name })),
+    )
+    .await;
+
     (
         StatusCode::CREATED,
         Json(WatchlistResponse {
@@ -454,6 +463,16 @@ async fn update_internal(
             .into_response();
     }
 
+    crate::audit::record(
+        &state,
+        &headers,
+        crate::audit::AuditEvent::new("watchlists.update", "watchlist")
+            .target(&id_str)
+            .before(json!({ "name": current_name }))
+            .after(json!({ "name": next_name })),
+    )
+    .await;
+
     let ids = [id_str.clone()];
     let items_by_watchlist = match load_items(&state, pool, &ids).await {
         Ok(v) => v,
@@ -525,6 +544,13 @@ pub async fn destroy(
             .into_response();
     }
 
+    crate::audit::record(
+        &state,
+        &headers,
+        crate::audit::AuditEvent::new("watchlists.delete", "watchlist").target(&id_str),
+    )
+    .await;
+
     StatusCode::NO_CONTENT.into_response()
 }
 
@@ -704,6 +730,15 @@ pub async fn items_add(
             .into_response();
     }
 
+    crate::audit::record(
+        &state,
+        &headers,
+        crate::audit::AuditEvent::new("watchlists.items.add", "watchlist")
+            .target(&id_str)
+            .after(json!({ "fund_code": fund_code, "order": next_order })),
+    )
+    .await;
+
     (
         StatusCode::CREATED,
         Json(json!({ "id": item_id, "fund_code": fund_code })),
@@ -812,6 +847,15 @@ pub async fn items_remove(
             .into_response();
     }
 
+    crate::audit::record(
+        &state,
+        &headers,
+        crate::audit::AuditEvent::new("watchlists.items.remove", "watchlist")
+            .target(&id_str)
+            .before(json!({ "fund_code": fund_code.trim() })),
+    )
+    .await;
+
     StatusCode::NO_CONTENT.into_response()
 }
 
@@ -907,6 +951,15 @@ pub async fn reorder(
             .await;
     }
 
+    crate::audit::record(
+        &state,
+        &headers,
+        crate::audit::AuditEvent::new("watchlists.items.reorder", "watchlist")
+            .target(&id_str)
+            .after(json!({ "fund_codes": fund_codes })),
+    )
+    .await;
+
     (
         StatusCode::OK,
         Json(MessageResponse {
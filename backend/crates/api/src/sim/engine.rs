@@ -6,6 +6,7 @@ use serde_json::{Value, json};
 use sqlx::Row;
 use uuid::Uuid;
 
+use crate::features::{self, FeatureValues};
 use crate::ml;
 use rand::SeedableRng;
 
@@ -33,6 +34,8 @@ pub struct PositionView {
     pub shares_frozen: String,
     pub nav: Option<String>,
     pub value: Option<String>,
+    /// 当日（或之前最近一日）的特征库取值，与训练/推理同源。
+    pub features: Option<FeatureValues>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -60,6 +63,9 @@ pub struct AutoTopkSnapshotParams {
     pub rebalance_every: i64,
     /// 线性打分权重：[pos, dip5, dip20, magic5, magic20]
     pub weights: Option<Vec<f64>>,
+    /// 市场状态过滤；None 表示不启用。
+    #[serde(default)]
+    pub regime_filter: Option<RegimeFilter>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -92,6 +98,59 @@ pub struct AutoTopkTsTimingParams {
 
     /// quant-service base url
     pub quant_service_url: String,
+
+    /// 市场状态过滤；None 表示不启用。
+    #[serde(default)]
+    pub regime_filter: Option<RegimeFilter>,
+}
+
+/// 市场状态过滤：指数当日熊市概率（`index_regime`）高于阈值时不建仓 / 加仓，卖出不受影响。
+/// 当日没有状态数据时不拦截。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegimeFilter {
+    pub index_code: String,
+    #[serde(default)]
+    pub source_name: Option<String>,
+    /// 熊市概率阈值（0..1）。
+    pub max_bear_proba: f64,
+}
+
+/// 状态概率早于交易日这么多自然日则视为缺失。
+const REGIME_STALE_DAYS: i64 = 10;
+
+struct RegimeGate {
+    probas: Vec<(String, [f64; 3])>,
+    max_bear_proba: f64,
+}
+
+impl RegimeGate {
+    async fn load(
+        pool: &sqlx::AnyPool,
+        filter: Option<&RegimeFilter>,
+        end_date: NaiveDate,
+    ) -> Result<Option<Self>, String> {
+        let Some(filter) = filter else {
+            return Ok(None);
+        };
+        let source = filter
+            .source_name
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .unwrap_or(crate::features::REFER_INDEX_SOURCE);
+        let probas =
+            ml::regime::load_probas(pool, filter.index_code.trim(), source, &fmt_date(end_date))
+                .await?;
+        Ok(Some(Self {
+            probas,
+            max_bear_proba: filter.max_bear_proba.clamp(0.0, 1.0),
+        }))
+    }
+
+    fn blocks_buy(&self, d: NaiveDate) -> bool {
+        ml::regime::proba_on_or_before(&self.probas, &fmt_date(d), REGIME_STALE_DAYS)
+            .is_some_and(|p| p[1] > self.max_bear_proba)
+    }
 }
 
 fn normalize_weights(raw: Option<Vec<f64>>) -> [f64; 5] {
@@ -760,12 +819,17 @@ async fn compute_observation(
         if let Some(v) = value {
             positions_value += v;
         }
+        features::store::materialize_fund(pool, &code, &run.source_name).await?;
+        let feature_row =
+            features::store::row_on_or_before(pool, &code, &run.source_name, None, &fmt_date(date))
+                .await?;
         views.push(PositionView {
             fund_code: code,
             shares_available: fmt_dec(avail),
             shares_frozen: fmt_dec(frozen),
             nav: nav.map(fmt_dec),
             value: value.map(fmt_dec),
+            features: feature_row.map(|(_, v)| v),
         });
     }
 
@@ -1210,6 +1274,8 @@ async fn simulate_auto_topk_snapshot_final_equity(
     let top_k = params.top_k.clamp(1, 200);
     let rebalance_every = params.rebalance_every.clamp(1, 60);
 
+    let regime_gate = RegimeGate::load(pool, params.regime_filter.as_ref(), end_date).await?;
+
     let mut cash = initial_cash;
     let mut holdings: std::collections::BTreeMap<String, Decimal> = std::collections::BTreeMap::new();
     let mut days_since_rebalance: i64 = 10_000;
@@ -1242,8 +1308,13 @@ async fn simulate_auto_topk_snapshot_final_equity(
                 holdings.clear();
             }
 
-            // 选出当日 topK
-            let picked = pick_topk_by_snapshot_score(pool, d, top_k, w).await?;
+            // 选出当日 topK；市场状态过滤拦截时只清仓、持有现金。
+            let blocked = regime_gate.as_ref().is_some_and(|g| g.blocks_buy(d));
+            let picked = if blocked {
+                Vec::new()
+            } else {
+                pick_topk_by_snapshot_score(pool, d, top_k, w).await?
+            };
             if !picked.is_empty() && cash > Decimal::ZERO {
                 let k = picked.len() as i64;
                 let amount_each = cash / Decimal::from(k);
@@ -1431,6 +1502,8 @@ async fn simulate_auto_topk_ts_timing_final_equity(
         }
     }
 
+    let regime_gate = RegimeGate::load(pool, params.regime_filter.as_ref(), end_date).await?;
+
     let sh_series = crate::index_series::load_or_fetch_index_close_series(
         pool,
         &client,
@@ -1612,9 +1685,12 @@ async fn simulate_auto_topk_ts_timing_final_equity(
         }
 
         let is_buy_signal_day = buy_days.contains(&d);
-        let can_trade_today = params.buy_macd_point.is_none() || is_buy_signal_day;
+        let regime_blocked = regime_gate.as_ref().is_some_and(|g| g.blocks_buy(d));
+        let can_trade_today =
+            (params.buy_macd_point.is_none() || is_buy_signal_day) && !regime_blocked;
         let wants_rebalance = holdings.is_empty() || days_since_rebalance >= rebalance_every;
-        let wants_add_on_buy = params.buy_macd_point.is_some() && is_buy_signal_day;
+        let wants_add_on_buy =
+            params.buy_macd_point.is_some() && is_buy_signal_day && !regime_blocked;
 
         // rebalance: only on allowed trade days when buy timing is enabled (matches “只在买点调仓/建仓”的 TS 直觉)
         if wants_rebalance && can_trade_today {
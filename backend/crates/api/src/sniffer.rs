@@ -2,7 +2,6 @@ use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 use std::time::Duration;
 
-use chrono::{Datelike, NaiveDate, Utc};
 use csv::StringRecord;
 use reqwest::Client;
 use rust_decimal::Decimal;
@@ -16,10 +15,6 @@ use crate::state::AppState;
 pub const DEEPQ_STAR_CSV_URL: &str = "https://sq.deepq.tech/star/api/data";
 pub const SNIFFER_WATCHLIST_NAME: &str = "嗅探（自动）";
 
-const TZ_OFFSET_SECONDS: i32 = 8 * 60 * 60; // Asia/Shanghai fixed offset (+08:00)
-const DAILY_HOUR: u32 = 3;
-const DAILY_MINUTE: u32 = 10;
-
 #[derive(Debug, Clone, PartialEq)]
 pub struct SnifferRow {
     pub sector: String,
@@ -237,48 +232,6 @@ fn parse_record(rec: &StringRecord, idx: &CsvIndices) -> Option<SnifferRow> {
     })
 }
 
-pub fn next_daily_run_utc(now_utc: chrono::DateTime<Utc>) -> chrono::DateTime<Utc> {
-    let local = now_utc + chrono::Duration::seconds(TZ_OFFSET_SECONDS as i64);
-    let today = NaiveDate::from_ymd_opt(local.year(), local.month(), local.day())
-        .unwrap_or_else(|| Utc::now().date_naive());
-    let target_today = today
-        .and_hms_opt(DAILY_HOUR, DAILY_MINUTE, 0)
-        .unwrap_or_else(|| today.and_hms_opt(3, 10, 0).unwrap());
-    let next_local = if local.naive_utc() < target_today {
-        target_today
-    } else {
-        (today.succ_opt().unwrap_or(today))
-            .and_hms_opt(DAILY_HOUR, DAILY_MINUTE, 0)
-            .unwrap()
-    };
-    chrono::DateTime::<Utc>::from_naive_utc_and_offset(
-        next_local - chrono::Duration::seconds(TZ_OFFSET_SECONDS as i64),
-        Utc,
-    )
-}
-
-pub async fn run_scheduler_forever(state: AppState) {
-    loop {
-        let now = Utc::now();
-        let next = next_daily_run_utc(now);
-        let dur = match (next - now).to_std() {
-            Ok(d) => d,
-            Err(_) => Duration::from_secs(0),
-        };
-        tracing::info!(next_run_utc=%next.to_rfc3339(), sleep_seconds=?dur.as_secs(), "sniffer scheduler sleeping");
-        tokio::time::sleep(dur).await;
-
-        match run_sync_once(state.clone()).await {
-            Ok(r) => {
-                tracing::info!(run_id=%r.run_id, snapshot_id=%r.snapshot_id, item_count=r.item_count, users_updated=r.users_updated, "sniffer sync ok");
-            }
-            Err(e) => {
-                tracing::warn!(error=%e, "sniffer sync failed");
-            }
-        }
-    }
-}
-
 async fn fetch_deepq_csv(client: &Client) -> Result<String, String> {
     let resp = client
         .get(DEEPQ_STAR_CSV_URL)
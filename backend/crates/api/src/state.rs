@@ -4,9 +4,12 @@ use serde::Serialize;
 use sqlx::AnyPool;
 use tokio::sync::{Mutex, Notify};
 
+use crate::auth_session::SessionRevocations;
 use crate::config::ConfigStore;
 use crate::db::DatabaseKind;
 use crate::jwt::JwtService;
+use crate::metrics::Metrics;
+use crate::personal_token::PersonalTokenCache;
 
 #[derive(Clone)]
 pub struct AppState {
@@ -21,6 +24,10 @@ struct InnerState {
     pub sniffer_lock: Mutex<()>,
     pub crawl_lock: Mutex<()>,
     pub crawl_notify: Notify,
+    pub task_notify: Notify,
+    pub metrics: Metrics,
+    pub session_revocations: SessionRevocations,
+    pub personal_tokens: PersonalTokenCache,
 }
 
 impl AppState {
@@ -34,6 +41,10 @@ impl AppState {
                 sniffer_lock: Mutex::new(()),
                 crawl_lock: Mutex::new(()),
                 crawl_notify: Notify::new(),
+                task_notify: Notify::new(),
+                metrics: Metrics::default(),
+                session_revocations: SessionRevocations::default(),
+                personal_tokens: PersonalTokenCache::default(),
             }),
         }
     }
@@ -64,6 +75,22 @@ impl AppState {
     pub fn crawl_notify(&self) -> &Notify {
         &self.inner.crawl_notify
     }
+
+    pub fn task_notify(&self) -> &Notify {
+        &self.inner.task_notify
+    }
+
+    pub fn metrics(&self) -> &Metrics {
+        &self.inner.metrics
+    }
+
+    pub fn session_revocations(&self) -> &SessionRevocations {
+        &self.inner.session_revocations
+    }
+
+    pub fn personal_tokens(&self) -> &PersonalTokenCache {
+        &self.inner.personal_tokens
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -0,0 +1,482 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDateTime, Timelike, Utc};
+use serde_json::Value;
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::db::DatabaseKind;
+use crate::dbfmt::format_utc;
+use crate::tasks;
+
+/// cron 表达式按北京时间（+08:00）解释。
+const TZ_OFFSET_SECONDS: i64 = 8 * 60 * 60;
+/// 固定间隔的下限，避免误配置成每秒投递。
+pub const MIN_INTERVAL_SECONDS: i64 = 60;
+/// 向后搜索下一次触发时间的上限（覆盖 2 月 29 日这类每 4 年一次的表达式）。
+const MAX_SEARCH_DAYS: i64 = 366 * 5;
+
+/// 5 段 cron 表达式：分 时 日 月 周。
+///
+/// 每段支持 `*`、`*/n`、`a`、`a-b`、`a-b/n`、`a/n` 以及逗号列表；周日可写 0 或 7。
+/// 日与周同时受限时按标准 cron 语义取“或”。另支持 `@hourly/@daily/@weekly/@monthly`。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronExpr {
+    minutes: u64,
+    hours: u32,
+    days: u32,
+    months: u16,
+    weekdays: u8,
+    days_any: bool,
+    weekdays_any: bool,
+}
+
+fn parse_field(field: &str, min: u32, max: u32, name: &str) -> Result<u64, String> {
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let part = part.trim();
+        let (range, step) = match part.split_once('/') {
+            Some((r, s)) => {
+                let step = s
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|v| *v >= 1)
+                    .ok_or_else(|| format!("invalid {name} step: {part}"))?;
+                (r, Some(step))
+            }
+            None => (part, None),
+        };
+        let parse_num = |s: &str| {
+            s.parse::<u32>()
+                .ok()
+                .filter(|v| (min..=max).contains(v))
+                .ok_or_else(|| format!("invalid {name} value: {part}"))
+        };
+        let (lo, hi) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            (parse_num(a)?, parse_num(b)?)
+        } else {
+            let a = parse_num(range)?;
+            if step.is_some() { (a, max) } else { (a, a) }
+        };
+        if lo > hi {
+            return Err(format!("invalid {name} range: {part}"));
+        }
+        let mut v = lo;
+        while v <= hi {
+            bits |= 1 << v;
+            v += step.unwrap_or(1);
+        }
+    }
+    Ok(bits)
+}
+
+impl CronExpr {
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let expr = match expr.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!("cron 表达式需要 5 段（分 时 日 月 周）：{expr}"));
+        }
+        let weekdays = parse_field(fields[4], 0, 7, "weekday")?;
+        Ok(Self {
+            minutes: parse_field(fields[0], 0, 59, "minute")?,
+            hours: parse_field(fields[1], 0, 23, "hour")? as u32,
+            days: parse_field(fields[2], 1, 31, "day")? as u32,
+            months: parse_field(fields[3], 1, 12, "month")? as u16,
+            // 7 与 0 都表示周日。
+            weekdays: ((weekdays | (weekdays >> 7)) & 0x7f) as u8,
+            days_any: fields[2].starts_with('*'),
+            weekdays_any: fields[4].starts_with('*'),
+        })
+    }
+
+    fn day_matches(&self, date: chrono::NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+        let dom = self.days & (1 << date.day()) != 0;
+        let dow = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        if self.days_any || self.weekdays_any {
+            dom && dow
+        } else {
+            dom || dow
+        }
+    }
+
+    /// 北京时间的本地时刻是否命中表达式（精确到分钟）。
+    pub fn matches(&self, local: NaiveDateTime) -> bool {
+        self.day_matches(local.date())
+            && self.hours & (1 << local.hour()) != 0
+            && self.minutes & (1 << local.minute()) != 0
+    }
+
+    /// 严格晚于 after 的下一次触发时间（UTC）；表达式永不触发（如 2 月 30 日）时返回 None。
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let local = after.naive_utc() + Duration::seconds(TZ_OFFSET_SECONDS);
+        let start = local.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        for offset in 0..MAX_SEARCH_DAYS {
+            let date = start.date() + Duration::days(offset);
+            if !self.day_matches(date) {
+                continue;
+            }
+            let first_day = offset == 0;
+            for hour in (if first_day { start.hour() } else { 0 })..24 {
+                if self.hours & (1 << hour) == 0 {
+                    continue;
+                }
+                let from_minute = if first_day && hour == start.hour() {
+                    start.minute()
+                } else {
+                    0
+                };
+                if let Some(minute) = (from_minute..60).find(|m| self.minutes & (1 << m) != 0) {
+                    let at = date.and_hms_opt(hour, minute, 0)?;
+                    return Some(DateTime::<Utc>::from_naive_utc_and_offset(
+                        at - Duration::seconds(TZ_OFFSET_SECONDS),
+                        Utc,
+                    ));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// 计划的触发方式：cron 表达式或固定间隔（二选一）。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Recurrence {
+    Cron(CronExpr),
+    Interval(i64),
+}
+
+impl Recurrence {
+    pub fn from_parts(
+        cron_expr: Option<&str>,
+        interval_seconds: Option<i64>,
+    ) -> Result<Self, String> {
+        let cron_expr = cron_expr.map(str::trim).filter(|s| !s.is_empty());
+        match (cron_expr, interval_seconds) {
+            (Some(expr), None) => Ok(Recurrence::Cron(CronExpr::parse(expr)?)),
+            (None, Some(secs)) if secs >= MIN_INTERVAL_SECONDS => Ok(Recurrence::Interval(secs)),
+            (None, Some(_)) => Err(format!("interval_seconds 不能小于 {MIN_INTERVAL_SECONDS}")),
+            _ => Err("cron_expr 与 interval_seconds 必须且只能设置一个".to_string()),
+        }
+    }
+
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Recurrence::Cron(c) => c.next_after(after),
+            Recurrence::Interval(secs) => Some(after + Duration::seconds(*secs)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TaskScheduleRow {
+    pub id: String,
+    pub name: String,
+    pub task_type: String,
+    pub payload_json: String,
+    pub cron_expr: Option<String>,
+    pub interval_seconds: Option<i64>,
+    pub priority: i64,
+    pub enabled: bool,
+    pub last_run_at: Option<String>,
+    pub next_run_at: Option<String>,
+    pub last_job_id: Option<String>,
+    pub created_by: Option<i64>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+const SELECT_COLUMNS: &str = r#"
+  CAST(id AS TEXT) as id,
+  name,
+  task_type,
+  payload_json,
+  cron_expr,
+  interval_seconds,
+  priority,
+  CASE WHEN enabled THEN 1 ELSE 0 END as enabled,
+  CAST(last_run_at AS TEXT) as last_run_at,
+  CAST(next_run_at AS TEXT) as next_run_at,
+  CAST(last_job_id AS TEXT) as last_job_id,
+  created_by,
+  CAST(created_at AS TEXT) as created_at,
+  CAST(updated_at AS TEXT) as updated_at
+"#;
+
+fn row_to_schedule(r: &sqlx::any::AnyRow) -> TaskScheduleRow {
+    TaskScheduleRow {
+        id: r.get("id"),
+        name: r.get("name"),
+        task_type: r.get("task_type"),
+        payload_json: r.get("payload_json"),
+        cron_expr: r.try_get("cron_expr").ok().flatten(),
+        interval_seconds: r.try_get("interval_seconds").ok().flatten(),
+        priority: r.try_get::<i64, _>("priority").unwrap_or(0),
+        enabled: r.try_get::<i64, _>("enabled").unwrap_or(0) != 0,
+        last_run_at: r.try_get("last_run_at").ok().flatten(),
+        next_run_at: r.try_get("next_run_at").ok().flatten(),
+        last_job_id: r.try_get("last_job_id").ok().flatten(),
+        created_by: r.try_get("created_by").ok().flatten(),
+        created_at: r.get("created_at"),
+        updated_at: r.get("updated_at"),
+    }
+}
+
+fn is_postgres(pool: &sqlx::AnyPool) -> bool {
+    crate::db::database_kind_from_pool(pool) == DatabaseKind::Postgres
+}
+
+pub async fn list_task_schedules(pool: &sqlx::AnyPool) -> Result<Vec<TaskScheduleRow>, String> {
+    let sql = format!("SELECT {SELECT_COLUMNS} FROM task_schedule ORDER BY name ASC");
+    let rows = sqlx::query(&sql)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(rows.iter().map(row_to_schedule).collect())
+}
+
+pub async fn get_task_schedule(
+    pool: &sqlx::AnyPool,
+    id: &str,
+) -> Result<Option<TaskScheduleRow>, String> {
+    let cond = if is_postgres(pool) {
+        "id = ($1)::uuid"
+    } else {
+        "id = $1"
+    };
+    let sql = format!("SELECT {SELECT_COLUMNS} FROM task_schedule WHERE {cond}");
+    let row = sqlx::query(&sql)
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(row.as_ref().map(row_to_schedule))
+}
+
+pub async fn find_task_schedule_by_name(
+    pool: &sqlx::AnyPool,
+    name: &str,
+) -> Result<Option<TaskScheduleRow>, String> {
+    let sql = format!("SELECT {SELECT_COLUMNS} FROM task_schedule WHERE name = $1");
+    let row = sqlx::query(&sql)
+        .bind(name)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(row.as_ref().map(row_to_schedule))
+}
+
+/// 新建计划；返回新 id。next_run_at 由调用方按触发方式算好传入（停用时为 None）。
+#[allow(clippy::too_many_arguments)]
+pub async fn insert_task_schedule(
+    pool: &sqlx::AnyPool,
+    name: &str,
+    task_type: &str,
+    payload_json: &str,
+    cron_expr: Option<&str>,
+    interval_seconds: Option<i64>,
+    priority: i64,
+    enabled: bool,
+    next_run_at: Option<&str>,
+    created_by: Option<i64>,
+) -> Result<String, String> {
+    let id = Uuid::new_v4().to_string();
+    let sql = if is_postgres(pool) {
+        r#"
+        INSERT INTO task_schedule (
+          id, name, task_type, payload_json, cron_expr, interval_seconds, priority, enabled,
+          next_run_at, created_by, created_at, updated_at
+        )
+        VALUES (($1)::uuid,$2,$3,$4,$5,$6,$7,$8,($9)::timestamptz,$10,CURRENT_TIMESTAMP,CURRENT_TIMESTAMP)
+        "#
+    } else {
+        r#"
+        INSERT INTO task_schedule (
+          id, name, task_type, payload_json, cron_expr, interval_seconds, priority, enabled,
+          next_run_at, created_by, created_at, updated_at
+        )
+        VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,CURRENT_TIMESTAMP,CURRENT_TIMESTAMP)
+        "#
+    };
+    sqlx::query(sql)
+        .bind(&id)
+        .bind(name)
+        .bind(task_type)
+        .bind(payload_json)
+        .bind(cron_expr)
+        .bind(interval_seconds)
+        .bind(priority)
+        .bind(enabled)
+        .bind(next_run_at)
+        .bind(created_by)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+/// 按行整体写回可编辑字段（name/task_type/payload/触发方式/优先级/启用/next_run_at）。
+pub async fn save_task_schedule(
+    pool: &sqlx::AnyPool,
+    row: &TaskScheduleRow,
+) -> Result<bool, String> {
+    let sql = if is_postgres(pool) {
+        r#"
+        UPDATE task_schedule
+        SET name = $2, task_type = $3, payload_json = $4, cron_expr = $5, interval_seconds = $6,
+            priority = $7, enabled = $8, next_run_at = ($9)::timestamptz, updated_at = CURRENT_TIMESTAMP
+        WHERE id = ($1)::uuid
+        "#
+    } else {
+        r#"
+        UPDATE task_schedule
+        SET name = $2, task_type = $3, payload_json = $4, cron_expr = $5, interval_seconds = $6,
+            priority = $7, enabled = $8, next_run_at = $9, updated_at = CURRENT_TIMESTAMP
+        WHERE id = $1
+        "#
+    };
+    let r = sqlx::query(sql)
+        .bind(&row.id)
+        .bind(&row.name)
+        .bind(&row.task_type)
+        .bind(&row.payload_json)
+        .bind(row.cron_expr.as_deref())
+        .bind(row.interval_seconds)
+        .bind(row.priority)
+        .bind(row.enabled)
+        .bind(row.next_run_at.as_deref())
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(r.rows_affected() == 1)
+}
+
+pub async fn delete_task_schedule(pool: &sqlx::AnyPool, id: &str) -> Result<bool, String> {
+    let sql = if is_postgres(pool) {
+        "DELETE FROM task_schedule WHERE id = ($1)::uuid"
+    } else {
+        "DELETE FROM task_schedule WHERE id = $1"
+    };
+    let r = sqlx::query(sql)
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(r.rows_affected() == 1)
+}
+
+/// 把到期的计划投递到 task_job，返回本轮投递的任务数。
+///
+/// - next_run_at 为 NULL（新建/内置计划）：只初始化下一次时间，不立即投递；
+/// - 停机期间错过的多次触发合并为一次；
+/// - 先用条件 UPDATE 推进 next_run_at 抢占本次触发，多副本同时检查时只有一个会投递。
+pub async fn enqueue_due_schedules(
+    pool: &sqlx::AnyPool,
+    now: DateTime<Utc>,
+) -> Result<i64, String> {
+    let pg = is_postgres(pool);
+    let now_s = format_utc(now);
+    let sql = if pg {
+        format!(
+            "SELECT {SELECT_COLUMNS} FROM task_schedule \
+             WHERE enabled AND (next_run_at IS NULL OR next_run_at <= ($1)::timestamptz) \
+             ORDER BY priority DESC, name ASC"
+        )
+    } else {
+        format!(
+            "SELECT {SELECT_COLUMNS} FROM task_schedule \
+             WHERE enabled AND (next_run_at IS NULL OR next_run_at <= $1) \
+             ORDER BY priority DESC, name ASC"
+        )
+    };
+    let rows = sqlx::query(&sql)
+        .bind(&now_s)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut enqueued = 0;
+    for r in rows.iter().map(row_to_schedule) {
+        let next = match Recurrence::from_parts(r.cron_expr.as_deref(), r.interval_seconds) {
+            Ok(rec) => rec.next_after(now),
+            Err(e) => {
+                tracing::warn!(error = %e, schedule = %r.name, "task_schedule invalid trigger");
+                continue;
+            }
+        };
+        let next_s = next.map(format_utc);
+
+        if r.next_run_at.is_none() {
+            let sql = if pg {
+                "UPDATE task_schedule SET next_run_at = ($2)::timestamptz, updated_at = CURRENT_TIMESTAMP \
+                 WHERE id = ($1)::uuid AND next_run_at IS NULL"
+            } else {
+                "UPDATE task_schedule SET next_run_at = $2, updated_at = CURRENT_TIMESTAMP \
+                 WHERE id = $1 AND next_run_at IS NULL"
+            };
+            sqlx::query(sql)
+                .bind(&r.id)
+                .bind(next_s.as_deref())
+                .execute(pool)
+                .await
+                .map_err(|e| e.to_string())?;
+            continue;
+        }
+
+        let claim_sql = if pg {
+            "UPDATE task_schedule \
+             SET next_run_at = ($2)::timestamptz, last_run_at = ($3)::timestamptz, updated_at = CURRENT_TIMESTAMP \
+             WHERE id = ($1)::uuid AND enabled AND next_run_at <= ($3)::timestamptz"
+        } else {
+            "UPDATE task_schedule \
+             SET next_run_at = $2, last_run_at = $3, updated_at = CURRENT_TIMESTAMP \
+             WHERE id = $1 AND enabled AND next_run_at <= $3"
+        };
+        let claimed = sqlx::query(claim_sql)
+            .bind(&r.id)
+            .bind(next_s.as_deref())
+            .bind(&now_s)
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?
+            .rows_affected()
+            == 1;
+        if !claimed {
+            continue;
+        }
+
+        let payload: Value = match serde_json::from_str(&r.payload_json) {
+            Ok(v) => v,
+            Err(e) => {
+                tracing::warn!(error = %e, schedule = %r.name, "task_schedule invalid payload_json");
+                continue;
+            }
+        };
+        let job_id =
+            tasks::enqueue_task_job(pool, &r.task_type, &payload, r.priority, r.created_by).await?;
+        enqueued += 1;
+        tracing::info!(schedule = %r.name, task_type = %r.task_type, job_id = %job_id, "task_schedule enqueued");
+
+        let sql = if pg {
+            "UPDATE task_schedule SET last_job_id = ($2)::uuid WHERE id = ($1)::uuid"
+        } else {
+            "UPDATE task_schedule SET last_job_id = $2 WHERE id = $1"
+        };
+        sqlx::query(sql)
+            .bind(&r.id)
+            .bind(&job_id)
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(enqueued)
+}
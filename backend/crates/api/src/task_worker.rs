@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::Notify;
+
+use crate::config::ConfigStore;
+use crate::state::AppState;
+use crate::task_schedule;
+use crate::tasks::{self, ClaimFilter, TaskLane};
+
+/// 每个副本的并发上限：按通道（抓取/计算）与按 task_type 两层限制，0 表示本副本不执行。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConcurrencyLimits {
+    pub crawl: usize,
+    pub compute: usize,
+    pub per_type: HashMap<String, usize>,
+}
+
+impl ConcurrencyLimits {
+    pub fn from_config(config: &ConfigStore) -> Self {
+        Self {
+            crawl: config
+                .get_i64("task_worker_crawl_concurrency", 2)
+                .clamp(0, 64) as usize,
+            compute: config
+                .get_i64("task_worker_compute_concurrency", 2)
+                .clamp(0, 64) as usize,
+            per_type: parse_per_type_limits(
+                &config
+                    .get_string("task_type_concurrency")
+                    .unwrap_or_default(),
+            ),
+        }
+    }
+
+    fn lane_limit(&self, lane: TaskLane) -> usize {
+        match lane {
+            TaskLane::Crawl => self.crawl,
+            TaskLane::Compute => self.compute,
+        }
+    }
+}
+
+/// 解析 `forecast_model_train=1,fund_analysis_v2_compute=1` 形式的按类型并发配置；非法项忽略。
+pub fn parse_per_type_limits(raw: &str) -> HashMap<String, usize> {
+    let mut out = HashMap::new();
+    for item in raw.split(',') {
+        let Some((k, v)) = item.split_once('=') else {
+            continue;
+        };
+        let k = k.trim();
+        let Ok(v) = v.trim().parse::<usize>() else {
+            continue;
+        };
+        if !k.is_empty() {
+            out.insert(k.to_string(), v.min(64));
+        }
+    }
+    out
+}
+
+/// 本副本正在执行的任务数（按通道、按类型）。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RunningCounts {
+    pub crawl: usize,
+    pub compute: usize,
+    pub per_type: HashMap<String, usize>,
+}
+
+impl RunningCounts {
+    fn lane(&self, lane: TaskLane) -> usize {
+        match lane {
+            TaskLane::Crawl => self.crawl,
+            TaskLane::Compute => self.compute,
+        }
+    }
+
+    fn add(&mut self, task_type: &str) {
+        match tasks::task_lane(task_type) {
+            TaskLane::Crawl => self.crawl += 1,
+            TaskLane::Compute => self.compute += 1,
+        }
+        *self.per_type.entry(task_type.to_string()).or_default() += 1;
+    }
+
+    fn remove(&mut self, task_type: &str) {
+        match tasks::task_lane(task_type) {
+            TaskLane::Crawl => self.crawl = self.crawl.saturating_sub(1),
+            TaskLane::Compute => self.compute = self.compute.saturating_sub(1),
+        }
+        if let Some(n) = self.per_type.get_mut(task_type) {
+            *n = n.saturating_sub(1);
+            if *n == 0 {
+                self.per_type.remove(task_type);
+            }
+        }
+    }
+}
+
+/// 根据当前占用计算下一次抢占的过滤条件；两个通道都已满时返回 None。
+pub fn claim_filter(limits: &ConcurrencyLimits, running: &RunningCounts) -> Option<ClaimFilter> {
+    let crawl_open = running.lane(TaskLane::Crawl) < limits.lane_limit(TaskLane::Crawl);
+    let compute_open = running.lane(TaskLane::Compute) < limits.lane_limit(TaskLane::Compute);
+    let crawl_types = || tasks::CRAWL_LANE_TASK_TYPES.iter().map(|t| t.to_string());
+
+    let mut filter = ClaimFilter::default();
+    match (crawl_open, compute_open) {
+        (false, false) => return None,
+        (true, false) => filter.only.extend(crawl_types()),
+        (false, true) => filter.exclude.extend(crawl_types()),
+        (true, true) => {}
+    }
+
+    let mut saturated: Vec<String> = limits
+        .per_type
+        .iter()
+        .filter(|(t, limit)| running.per_type.get(*t).copied().unwrap_or(0) >= **limit)
+        .map(|(t, _)| t.clone())
+        .collect();
+    saturated.sort();
+    if !filter.only.is_empty() {
+        filter.only.retain(|t| !saturated.contains(t));
+        if filter.only.is_empty() {
+            return None;
+        }
+    } else {
+        for t in saturated {
+            if !filter.exclude.contains(&t) {
+                filter.exclude.push(t);
+            }
+        }
+    }
+    Some(filter)
+}
+
+struct PoolInner {
+    running: Mutex<RunningCounts>,
+    released: Notify,
+}
+
+/// 占用的并发名额；任务结束（含 panic）时归还并唤醒调度循环。
+struct Slot {
+    inner: Arc<PoolInner>,
+    task_type: String,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        if let Ok(mut running) = self.inner.running.lock() {
+            running.remove(&self.task_type);
+        }
+        self.inner.released.notify_one();
+    }
+}
+
+/// task_job 并发执行池：按上限抢占任务并各自在独立的 tokio 任务中执行。
+///
+/// 多个副本共享同一个队列：抢占走 `tasks::claim_next_task_job`（行锁 + 租约），
+/// 并发上限只约束本副本。
+#[derive(Clone)]
+pub struct TaskWorkerPool {
+    pool: sqlx::AnyPool,
+    owner: String,
+    inner: Arc<PoolInner>,
+}
+
+impl TaskWorkerPool {
+    pub fn new(pool: sqlx::AnyPool) -> Self {
+        Self {
+            pool,
+            owner: tasks::worker_id().to_string(),
+            inner: Arc::new(PoolInner {
+                running: Mutex::new(RunningCounts::default()),
+                released: Notify::new(),
+            }),
+        }
+    }
+
+    pub fn running(&self) -> RunningCounts {
+        self.inner.running.lock().expect("task pool lock").clone()
+    }
+
+    /// 有任务执行结束（空出名额）时被唤醒。
+    pub async fn released(&self) {
+        self.inner.released.notified().await;
+    }
+
+    /// 在上限内抢占到期任务并派发执行（本轮最多 max_dispatch 个）；返回本次派发的任务数。
+    pub async fn fill(
+        &self,
+        limits: &ConcurrencyLimits,
+        max_dispatch: usize,
+        lease_seconds: i64,
+    ) -> Result<usize, String> {
+        let lease_seconds = lease_seconds.clamp(10, 24 * 60 * 60);
+        let mut dispatched = 0;
+        while dispatched < max_dispatch {
+            let filter = {
+                let running = self.inner.running.lock().expect("task pool lock");
+                claim_filter(limits, &running)
+            };
+            let Some(filter) = filter else {
+                break;
+            };
+            let Some(job) =
+                tasks::claim_next_task_job(&self.pool, &self.owner, lease_seconds, &filter).await?
+            else {
+                break;
+            };
+
+            self.inner
+                .running
+                .lock()
+                .expect("task pool lock")
+                .add(&job.task_type);
+            dispatched += 1;
+
+            let slot = Slot {
+                inner: self.inner.clone(),
+                task_type: job.task_type.clone(),
+            };
+            let pool = self.pool.clone();
+            let owner = self.owner.clone();
+            tokio::spawn(async move {
+                let _slot = slot;
+                if let Err(e) =
+                    tasks::run_claimed_task_job(&pool, &job, &owner, lease_seconds).await
+                {
+                    tracing::warn!(error = %e, job_id = %job.id, task_type = %job.task_type, "task_job run failed");
+                }
+            });
+        }
+        Ok(dispatched)
+    }
+}
+
+/// 独立于爬虫循环的 task_job 后台 worker：长耗时的计算任务不会阻塞估值/净值抓取。
+pub async fn background_task(state: AppState) {
+    let Some(pool) = state.pool().cloned() else {
+        return;
+    };
+
+    // 启动时先回收上次进程中断遗留的 running 任务（租约已过期）。
+    match tasks::reclaim_expired_task_jobs(&pool).await {
+        Ok(n) if n > 0 => tracing::info!(reclaimed = n, "task queue reclaimed expired jobs"),
+        Ok(_) => {}
+        Err(e) => tracing::warn!(error = %e, "task queue reclaim failed"),
+    }
+
+    let workers = TaskWorkerPool::new(pool.clone());
+    let poll_seconds = state
+        .config()
+        .get_i64("task_poll_interval_seconds", 5)
+        .max(1) as u64;
+    let mut interval = tokio::time::interval(Duration::from_secs(poll_seconds));
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {},
+            _ = state.task_notify().notified() => {},
+            _ = workers.released() => {},
+        }
+
+        if !state.config().get_bool("task_worker_enabled", true) {
+            continue;
+        }
+
+        // 其他副本崩溃遗留的任务也在这里回收。
+        if let Err(e) = tasks::reclaim_expired_task_jobs(&pool).await {
+            tracing::warn!(error = %e, "task queue reclaim failed");
+        }
+
+        // 到期的周期性计划先入队，本轮即可被派发。
+        if state.config().get_bool("task_schedule_enabled", true)
+            && let Err(e) = task_schedule::enqueue_due_schedules(&pool, chrono::Utc::now()).await
+        {
+            tracing::warn!(error = %e, "task schedule enqueue failed");
+        }
+
+        let limits = ConcurrencyLimits::from_config(state.config());
+        let max_dispatch = state.config().get_i64("task_run_max_jobs", 5).clamp(0, 200) as usize;
+        let lease_seconds = state
+            .config()
+            .get_i64("task_lease_seconds", tasks::DEFAULT_LEASE_SECONDS);
+        if let Err(e) = workers.fill(&limits, max_dispatch, lease_seconds).await {
+            tracing::warn!(error = %e, "task queue fill failed");
+        }
+    }
+}
@@ -1,7 +1,11 @@
+use std::sync::OnceLock;
+
 use serde_json::{Value, json};
 use sqlx::Row;
 use uuid::Uuid;
 
+use crate::features;
+
 #[derive(Debug, Clone)]
 pub struct TaskRunRow {
     pub id: String,
@@ -112,15 +116,24 @@ pub async fn finish_task_run_error(pool: &sqlx::AnyPool, run_id: &str, err: &str
     Ok(())
 }
 
+/// 追加一行执行日志；seq 为同一 run 内的递增序号（SSE 流以此为游标）。
 pub async fn append_task_log(pool: &sqlx::AnyPool, run_id: &str, level: &str, message: &str) -> Result<(), String> {
     let id = Uuid::new_v4().to_string();
     let sql_pg = r#"
-        INSERT INTO task_run_log (id, run_id, level, message, created_at)
-        VALUES (($1)::uuid,($2)::uuid,$3,$4,CURRENT_TIMESTAMP)
+        INSERT INTO task_run_log (id, run_id, level, message, seq, created_at)
+        VALUES (
+          ($1)::uuid,($2)::uuid,$3,$4,
+          (SELECT COALESCE(MAX(seq), 0) + 1 FROM task_run_log WHERE run_id = ($2)::uuid),
+          CURRENT_TIMESTAMP
+        )
     "#;
     let sql_any = r#"
-        INSERT INTO task_run_log (id, run_id, level, message, created_at)
-        VALUES ($1,$2,$3,$4,CURRENT_TIMESTAMP)
+        INSERT INTO task_run_log (id, run_id, level, message, seq, created_at)
+        VALUES (
+          $1,$2,$3,$4,
+          (SELECT COALESCE(MAX(seq), 0) + 1 FROM task_run_log WHERE run_id = $2),
+          CURRENT_TIMESTAMP
+        )
     "#;
 
     if sqlx::query(sql_pg)
@@ -272,182 +285,679 @@ pub async fn get_task_job(pool: &sqlx::AnyPool, id: &str) -> Result<Option<TaskJ
     }))
 }
 
-async fn mark_task_job_running(pool: &sqlx::AnyPool, id: &str) -> Result<(), String> {
-    let sql_pg = r#"
+/// 租约缺省时长（秒）；执行中每 1/3 租约时长心跳续约一次。
+pub const DEFAULT_LEASE_SECONDS: i64 = 300;
+
+/// 本进程的 worker 标识（写入 task_job.lease_owner），多副本共用一个队列时用于区分持有者。
+pub fn worker_id() -> &'static str {
+    static ID: OnceLock<String> = OnceLock::new();
+    ID.get_or_init(|| {
+        let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "local".to_string());
+        let suffix = Uuid::new_v4().simple().to_string();
+        format!("{host}-{}-{}", std::process::id(), &suffix[..8])
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// 含首次执行在内的最大尝试次数（1 = 不重试）。
+    pub max_attempts: i64,
+    pub base_delay_seconds: i64,
+    pub max_delay_seconds: i64,
+}
+
+impl RetryPolicy {
+    /// 第 attempt 次失败后的等待时间：base * 2^(attempt-1)，封顶 max_delay_seconds。
+    pub fn backoff_seconds(&self, attempt: i64) -> i64 {
+        let attempt = attempt.clamp(1, 30);
+        let pow = 1_i64.checked_shl((attempt - 1) as u32).unwrap_or(i64::MAX);
+        self.base_delay_seconds
+            .saturating_mul(pow)
+            .clamp(0, self.max_delay_seconds.max(0))
+    }
+}
+
+/// 各 task_type 的重试策略。
+pub fn retry_policy(task_type: &str) -> RetryPolicy {
+    let (max_attempts, base_delay_seconds, max_delay_seconds) = match task_type {
+        // 依赖上游数据源：网络抖动/限流较常见，多给几次机会。
+        "nav_history_sync_batch" | "prices_refresh_batch" | "sniffer_sync" | "rates_risk_free_sync" => {
+            (4, 60, 30 * 60)
+        }
+        // 依赖独立量化服务：服务重启期间失败，稍后重试即可。
+        "quant_xalpha_metrics_batch"
+        | "quant_xalpha_grid_batch"
+        | "quant_xalpha_scheduled_batch"
+        | "quant_xalpha_qdiipredict_batch" => (3, 60, 30 * 60),
+        "signals_batch" | "ml_signal_outcome_resolve" | "index_regime_compute" => (3, 30, 10 * 60),
+        // 训练/计算类耗时长，失败多为数据问题：只补一次。
+        "forecast_model_train"
+        | "fund_analysis_v2_compute"
+        | "ml_sector_model_train"
+        | "fund_cluster_compute"
+        | "ml_drift_monitor" => (2, 5 * 60, 60 * 60),
+        // 未知类型重试也不会成功。
+        _ => (1, 0, 0),
+    };
+    RetryPolicy {
+        max_attempts,
+        base_delay_seconds,
+        max_delay_seconds,
+    }
+}
+
+fn utc_after(seconds: i64) -> String {
+    crate::dbfmt::format_utc(chrono::Utc::now() + chrono::Duration::seconds(seconds.max(0)))
+}
+
+/// 任务所属的执行通道：抓取类（依赖上游数据源）与计算类分开限流，互不阻塞。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskLane {
+    Crawl,
+    Compute,
+}
+
+/// 属于抓取通道的 task_type；其余（含未知类型）都归入计算通道。
+pub const CRAWL_LANE_TASK_TYPES: &[&str] = &[
+    "nav_history_sync_batch",
+    "prices_refresh_batch",
+    "sniffer_sync",
+    "rates_risk_free_sync",
+];
+
+pub fn task_lane(task_type: &str) -> TaskLane {
+    if CRAWL_LANE_TASK_TYPES.contains(&task_type) {
+        TaskLane::Crawl
+    } else {
+        TaskLane::Compute
+    }
+}
+
+/// 抢占时的 task_type 过滤：only 非空时只抢这些类型；exclude 中的类型不抢。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClaimFilter {
+    pub only: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+/// 原子地抢占一个到期的排队任务并写入租约（attempt 已 +1）；没有可抢的任务时返回 None。
+///
+/// Postgres 用 `FOR UPDATE SKIP LOCKED`，多个副本并发抢占时互不阻塞、也不会抢到同一行；
+/// SQLite 写操作本身串行，单条 `UPDATE ... WHERE id = (SELECT ...)` 即可保证原子性。
+pub async fn claim_next_task_job(
+    pool: &sqlx::AnyPool,
+    owner: &str,
+    lease_seconds: i64,
+    filter: &ClaimFilter,
+) -> Result<Option<TaskJobRow>, String> {
+    let is_postgres = crate::db::database_kind_from_pool(pool) == crate::db::DatabaseKind::Postgres;
+
+    let mut next_param = 3;
+    let mut placeholders = |n: usize| {
+        let list: Vec<String> = (0..n).map(|i| format!("${}", next_param + i)).collect();
+        next_param += n;
+        list.join(", ")
+    };
+    let mut type_filter = String::new();
+    if !filter.only.is_empty() {
+        type_filter.push_str(&format!(" AND task_type IN ({})", placeholders(filter.only.len())));
+    }
+    if !filter.exclude.is_empty() {
+        type_filter.push_str(&format!(" AND task_type NOT IN ({})", placeholders(filter.exclude.len())));
+    }
+
+    let (lease_expr, lock_clause) = if is_postgres {
+        ("($2)::timestamptz", "FOR UPDATE SKIP LOCKED")
+    } else {
+        ("$2", "")
+    };
+    let sql = format!(
+        r#"
         UPDATE task_job
         SET status='running',
             attempt = attempt + 1,
             started_at = COALESCE(started_at, CURRENT_TIMESTAMP),
+            lease_owner = $1,
+            lease_expires_at = {lease_expr},
+            heartbeat_at = CURRENT_TIMESTAMP,
+            progress_done = NULL,
+            progress_total = NULL,
+            progress_item = NULL,
+            progress_eta_seconds = NULL,
+            progress_updated_at = NULL,
             updated_at = CURRENT_TIMESTAMP
-        WHERE id = ($1)::uuid
+        WHERE status = 'queued' AND id = (
+          SELECT id
+          FROM task_job
+          WHERE status = 'queued' AND not_before <= CURRENT_TIMESTAMP{type_filter}
+          ORDER BY priority DESC, not_before ASC
+          LIMIT 1
+          {lock_clause}
+        )
+        RETURNING
+          CAST(id AS TEXT) as id,
+          task_type,
+          payload_json,
+          priority,
+          CAST(not_before AS TEXT) as not_before,
+          status,
+          attempt,
+          error,
+          created_by
+        "#
+    );
+
+    let mut q = sqlx::query(&sql).bind(owner).bind(utc_after(lease_seconds));
+    for t in filter.only.iter().chain(filter.exclude.iter()) {
+        q = q.bind(t);
+    }
+    let Some(r) = q.fetch_optional(pool).await.map_err(|e| e.to_string())? else {
+        return Ok(None);
+    };
+
+    Ok(Some(TaskJobRow {
+        id: r.get("id"),
+        task_type: r.get("task_type"),
+        payload_json: r.get("payload_json"),
+        priority: r.get("priority"),
+        not_before: r.get("not_before"),
+        status: r.get("status"),
+        attempt: r.get("attempt"),
+        error: r.try_get::<Option<String>, _>("error").ok().flatten(),
+        created_by: r.try_get::<Option<i64>, _>("created_by").ok().flatten(),
+    }))
+}
+
+/// 续约；返回 false 表示任务已不归本 worker 持有（被取消或租约已被回收）。
+pub async fn heartbeat_task_job(pool: &sqlx::AnyPool, id: &str, owner: &str, lease_seconds: i64) -> Result<bool, String> {
+    let sql_pg = r#"
+        UPDATE task_job
+        SET lease_expires_at = ($3)::timestamptz,
+            heartbeat_at = CURRENT_TIMESTAMP
+        WHERE id = ($1)::uuid AND status = 'running' AND lease_owner = $2
     "#;
     let sql_any = r#"
         UPDATE task_job
-        SET status='running',
-            attempt = attempt + 1,
-            started_at = COALESCE(started_at, CURRENT_TIMESTAMP),
-            updated_at = CURRENT_TIMESTAMP
-        WHERE id = $1
+        SET lease_expires_at = $3,
+            heartbeat_at = CURRENT_TIMESTAMP
+        WHERE id = $1 AND status = 'running' AND lease_owner = $2
     "#;
 
     let is_postgres = crate::db::database_kind_from_pool(pool) == crate::db::DatabaseKind::Postgres;
     let sql = if is_postgres { sql_pg } else { sql_any };
-    sqlx::query(sql)
+    let r = sqlx::query(sql)
         .bind(id)
+        .bind(owner)
+        .bind(utc_after(lease_seconds))
         .execute(pool)
         .await
         .map_err(|e| e.to_string())?;
-    Ok(())
+    Ok(r.rows_affected() == 1)
 }
 
-async fn mark_task_job_done(pool: &sqlx::AnyPool, id: &str) -> Result<(), String> {
+/// 仅当任务仍由 owner 持有时才标记完成（已取消的任务不会被覆盖）。
+async fn mark_task_job_done(pool: &sqlx::AnyPool, id: &str, owner: &str) -> Result<bool, String> {
     let sql_pg = r#"
         UPDATE task_job
         SET status='done',
             error=NULL,
+            lease_owner = NULL,
+            lease_expires_at = NULL,
             finished_at = CURRENT_TIMESTAMP,
             updated_at = CURRENT_TIMESTAMP
-        WHERE id = ($1)::uuid
+        WHERE id = ($1)::uuid AND status = 'running' AND lease_owner = $2
     "#;
     let sql_any = r#"
         UPDATE task_job
         SET status='done',
             error=NULL,
+            lease_owner = NULL,
+            lease_expires_at = NULL,
             finished_at = CURRENT_TIMESTAMP,
             updated_at = CURRENT_TIMESTAMP
-        WHERE id = $1
+        WHERE id = $1 AND status = 'running' AND lease_owner = $2
     "#;
 
     let is_postgres = crate::db::database_kind_from_pool(pool) == crate::db::DatabaseKind::Postgres;
     let sql = if is_postgres { sql_pg } else { sql_any };
-    sqlx::query(sql)
+    let r = sqlx::query(sql)
         .bind(id)
+        .bind(owner)
         .execute(pool)
         .await
         .map_err(|e| e.to_string())?;
-    Ok(())
+    Ok(r.rows_affected() == 1)
 }
 
-async fn mark_task_job_error(pool: &sqlx::AnyPool, id: &str, err: &str) -> Result<(), String> {
+/// 失败处理：重试未耗尽则回到 queued 并按退避推迟，否则进入死信（dead）。
+/// owner 为 None 时匹配无租约的历史 running 任务。返回新状态；任务已不归 owner 持有时返回 None。
+async fn fail_task_job(
+    pool: &sqlx::AnyPool,
+    id: &str,
+    owner: Option<&str>,
+    task_type: &str,
+    attempt: i64,
+    err: &str,
+) -> Result<Option<&'static str>, String> {
+    let policy = retry_policy(task_type);
+    let retry = attempt < policy.max_attempts;
+    let (status, not_before) = if retry {
+        ("queued", utc_after(policy.backoff_seconds(attempt)))
+    } else {
+        ("dead", utc_after(0))
+    };
+
     let sql_pg = r#"
         UPDATE task_job
-        SET status='error',
-            error=$2,
-            finished_at = CURRENT_TIMESTAMP,
+        SET status = $3,
+            error = $4,
+            not_before = ($5)::timestamptz,
+            lease_owner = NULL,
+            lease_expires_at = NULL,
+            finished_at = CASE WHEN $3 = 'dead' THEN CURRENT_TIMESTAMP ELSE NULL END,
             updated_at = CURRENT_TIMESTAMP
-        WHERE id = ($1)::uuid
+        WHERE id = ($1)::uuid AND status = 'running' AND COALESCE(lease_owner, '') = $2
     "#;
     let sql_any = r#"
         UPDATE task_job
-        SET status='error',
-            error=$2,
-            finished_at = CURRENT_TIMESTAMP,
+        SET status = $3,
+            error = $4,
+            not_before = $5,
+            lease_owner = NULL,
+            lease_expires_at = NULL,
+            finished_at = CASE WHEN $3 = 'dead' THEN CURRENT_TIMESTAMP ELSE NULL END,
             updated_at = CURRENT_TIMESTAMP
-        WHERE id = $1
+        WHERE id = $1 AND status = 'running' AND COALESCE(lease_owner, '') = $2
     "#;
 
     let is_postgres = crate::db::database_kind_from_pool(pool) == crate::db::DatabaseKind::Postgres;
     let sql = if is_postgres { sql_pg } else { sql_any };
-    sqlx::query(sql)
+    let r = sqlx::query(sql)
         .bind(id)
+        .bind(owner.unwrap_or(""))
+        .bind(status)
         .bind(err)
+        .bind(&not_before)
         .execute(pool)
         .await
         .map_err(|e| e.to_string())?;
-    Ok(())
+    Ok((r.rows_affected() == 1).then_some(status))
 }
 
-pub async fn run_due_task_jobs(pool: &sqlx::AnyPool, max_run: i64) -> Result<i64, String> {
-    let max_run = max_run.clamp(0, 200);
-    if max_run == 0 {
-        return Ok(0);
-    }
+/// 结束某个任务仍处于 running 的执行记录（取消/租约回收时使用）。
+async fn close_running_task_runs(pool: &sqlx::AnyPool, job_id: &str, status: &str, err: &str) -> Result<(), String> {
+    sqlx::query(
+        r#"
+        UPDATE task_run
+        SET status = $2, error = $3, finished_at = CURRENT_TIMESTAMP
+        WHERE queue_type = 'task_job' AND CAST(job_id AS TEXT) = $1 AND status = 'running'
+        "#,
+    )
+    .bind(job_id)
+    .bind(status)
+    .bind(err)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
 
-    let sql = format!(
+/// 回收租约已过期（或没有租约）的 running 任务：视为一次失败，按重试策略重新排队或进入死信。
+/// 进程启动时调用一次，之后每轮调度前也会检查，以处理其他副本崩溃遗留的任务。
+pub async fn reclaim_expired_task_jobs(pool: &sqlx::AnyPool) -> Result<i64, String> {
+    let rows = sqlx::query(
         r#"
-        SELECT
-          CAST(id AS TEXT) as id,
-          task_type,
-          payload_json,
-          priority,
-          CAST(not_before AS TEXT) as not_before,
-          status,
-          attempt,
-          error,
-          created_by
+        SELECT CAST(id AS TEXT) as id, task_type, attempt, lease_owner
         FROM task_job
-        WHERE status = 'queued' AND not_before <= CURRENT_TIMESTAMP
-        ORDER BY priority DESC, not_before ASC
-        LIMIT {}
+        WHERE status = 'running'
+          AND (lease_expires_at IS NULL OR lease_expires_at < CURRENT_TIMESTAMP)
         "#,
-        max_run
-    );
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
 
-    let rows = sqlx::query(&sql)
-        .fetch_all(pool)
+    let err = "租约过期（执行进程中断），已回收";
+    let mut reclaimed = 0_i64;
+    for r in rows {
+        let id: String = r.get("id");
+        let task_type: String = r.get("task_type");
+        let owner = r.try_get::<Option<String>, _>("lease_owner").ok().flatten();
+        let attempt: i64 = r.get("attempt");
+        if fail_task_job(pool, &id, owner.as_deref(), &task_type, attempt, err)
+            .await?
+            .is_some()
+        {
+            close_running_task_runs(pool, &id, "error", err).await?;
+            reclaimed += 1;
+        }
+    }
+    Ok(reclaimed)
+}
+
+/// 取消排队中或执行中的任务；执行中的任务会在下次心跳时停止。返回 false 表示当前状态不可取消。
+pub async fn cancel_task_job(pool: &sqlx::AnyPool, id: &str) -> Result<bool, String> {
+    let sql_pg = r#"
+        UPDATE task_job
+        SET status = 'cancelled',
+            lease_owner = NULL,
+            lease_expires_at = NULL,
+            finished_at = CURRENT_TIMESTAMP,
+            updated_at = CURRENT_TIMESTAMP
+        WHERE id = ($1)::uuid AND status IN ('queued', 'running')
+    "#;
+    let sql_any = r#"
+        UPDATE task_job
+        SET status = 'cancelled',
+            lease_owner = NULL,
+            lease_expires_at = NULL,
+            finished_at = CURRENT_TIMESTAMP,
+            updated_at = CURRENT_TIMESTAMP
+        WHERE id = $1 AND status IN ('queued', 'running')
+    "#;
+
+    let is_postgres = crate::db::database_kind_from_pool(pool) == crate::db::DatabaseKind::Postgres;
+    let sql = if is_postgres { sql_pg } else { sql_any };
+    let r = sqlx::query(sql)
+        .bind(id)
+        .execute(pool)
         .await
         .map_err(|e| e.to_string())?;
+    if r.rows_affected() == 0 {
+        return Ok(false);
+    }
+    close_running_task_runs(pool, id, "cancelled", "任务已取消").await?;
+    Ok(true)
+}
+
+/// 把已结束（done/error/dead/cancelled）的任务重新排队，重试计数清零。返回 false 表示当前状态不可重排。
+pub async fn requeue_task_job(pool: &sqlx::AnyPool, id: &str) -> Result<bool, String> {
+    let sql_pg = r#"
+        UPDATE task_job
+        SET status = 'queued',
+            attempt = 0,
+            error = NULL,
+            not_before = CURRENT_TIMESTAMP,
+            started_at = NULL,
+            finished_at = NULL,
+            lease_owner = NULL,
+            lease_expires_at = NULL,
+            heartbeat_at = NULL,
+            progress_done = NULL,
+            progress_total = NULL,
+            progress_item = NULL,
+            progress_eta_seconds = NULL,
+            progress_updated_at = NULL,
+            updated_at = CURRENT_TIMESTAMP
+        WHERE id = ($1)::uuid AND status IN ('done', 'error', 'dead', 'cancelled')
+    "#;
+    let sql_any = r#"
+        UPDATE task_job
+        SET status = 'queued',
+            attempt = 0,
+            error = NULL,
+            not_before = CURRENT_TIMESTAMP,
+            started_at = NULL,
+            finished_at = NULL,
+            lease_owner = NULL,
+            lease_expires_at = NULL,
+            heartbeat_at = NULL,
+            progress_done = NULL,
+            progress_total = NULL,
+            progress_item = NULL,
+            progress_eta_seconds = NULL,
+            progress_updated_at = NULL,
+            updated_at = CURRENT_TIMESTAMP
+        WHERE id = $1 AND status IN ('done', 'error', 'dead', 'cancelled')
+    "#;
+
+    let is_postgres = crate::db::database_kind_from_pool(pool) == crate::db::DatabaseKind::Postgres;
+    let sql = if is_postgres { sql_pg } else { sql_any };
+    let r = sqlx::query(sql)
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(r.rows_affected() == 1)
+}
+
+/// 批量任务的进度上报：写回 task_job.progress_*，ETA 按已完成条目的平均耗时估算。
+/// 为避免频繁写库，除首尾外每秒最多写一次；写入失败不影响任务执行。
+pub struct TaskProgress {
+    job_id: String,
+    total: i64,
+    started: std::time::Instant,
+    last_write: Option<std::time::Instant>,
+}
+
+impl TaskProgress {
+    pub fn new(job_id: &str, total: usize) -> Self {
+        Self {
+            job_id: job_id.to_string(),
+            total: total as i64,
+            started: std::time::Instant::now(),
+            last_write: None,
+        }
+    }
+
+    /// 预计剩余秒数；尚无完成条目时无法估算。
+    pub fn eta_seconds(&self, done: i64, elapsed_seconds: f64) -> Option<i64> {
+        if done <= 0 || self.total <= 0 {
+            return None;
+        }
+        let remaining = (self.total - done).max(0) as f64;
+        Some((elapsed_seconds / done as f64 * remaining).round() as i64)
+    }
+
+    /// 上报“已完成 done 个，正在处理 item”。
+    pub async fn report(&mut self, pool: &sqlx::AnyPool, done: usize, item: Option<&str>) {
+        let done = (done as i64).min(self.total);
+        let now = std::time::Instant::now();
+        let edge = done == 0 || done >= self.total;
+        if !edge
+            && self
+                .last_write
+                .is_some_and(|t| now.duration_since(t) < std::time::Duration::from_secs(1))
+        {
+            return;
+        }
+        self.last_write = Some(now);
+
+        let eta = self.eta_seconds(done, now.duration_since(self.started).as_secs_f64());
+        if let Err(e) = set_task_job_progress(pool, &self.job_id, done, self.total, item, eta).await {
+            tracing::warn!(error = %e, job_id = %self.job_id, "task_job progress update failed");
+        }
+    }
+
+    /// 全部处理完毕。
+    pub async fn finish(&mut self, pool: &sqlx::AnyPool) {
+        let total = self.total as usize;
+        self.report(pool, total, None).await;
+    }
+}
+
+async fn set_task_job_progress(
+    pool: &sqlx::AnyPool,
+    job_id: &str,
+    done: i64,
+    total: i64,
+    item: Option<&str>,
+    eta_seconds: Option<i64>,
+) -> Result<(), String> {
+    let sql_pg = r#"
+        UPDATE task_job
+        SET progress_done = $2,
+            progress_total = $3,
+            progress_item = $4,
+            progress_eta_seconds = $5,
+            progress_updated_at = CURRENT_TIMESTAMP
+        WHERE id = ($1)::uuid AND status = 'running'
+    "#;
+    let sql_any = r#"
+        UPDATE task_job
+        SET progress_done = $2,
+            progress_total = $3,
+            progress_item = $4,
+            progress_eta_seconds = $5,
+            progress_updated_at = CURRENT_TIMESTAMP
+        WHERE id = $1 AND status = 'running'
+    "#;
+
+    let is_postgres = crate::db::database_kind_from_pool(pool) == crate::db::DatabaseKind::Postgres;
+    let sql = if is_postgres { sql_pg } else { sql_any };
+    sqlx::query(sql)
+        .bind(job_id)
+        .bind(done)
+        .bind(total)
+        .bind(item)
+        .bind(eta_seconds)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// worker 能执行的全部 task_type（周期性计划只允许这些类型）。
+pub const TASK_TYPES: &[&str] = &[
+    "signals_batch",
+    "nav_history_sync_batch",
+    "sniffer_sync",
+    "rates_risk_free_sync",
+    "forecast_model_train",
+    "ml_sector_model_train",
+    "ml_signal_outcome_resolve",
+    "index_regime_compute",
+    "fund_cluster_compute",
+    "ml_drift_monitor",
+    "fund_analysis_v2_compute",
+    "prices_refresh_batch",
+    "quant_xalpha_metrics_batch",
+    "quant_xalpha_grid_batch",
+    "quant_xalpha_scheduled_batch",
+    "quant_xalpha_qdiipredict_batch",
+];
+
+async fn exec_task_job(pool: &sqlx::AnyPool, run_id: &str, job: &TaskJobRow) -> Result<(), String> {
+    match job.task_type.as_str() {
+        "signals_batch" => exec_signals_batch(pool, run_id, job).await,
+        "nav_history_sync_batch" => exec_nav_history_sync_batch(pool, run_id, job).await,
+        "sniffer_sync" => exec_sniffer_sync(pool, run_id, job).await,
+        "rates_risk_free_sync" => exec_rates_risk_free_sync(pool, run_id, job).await,
+        "forecast_model_train" => exec_forecast_model_train(pool, run_id, job).await,
+        "ml_sector_model_train" => exec_ml_sector_model_train(pool, run_id, job).await,
+        "ml_signal_outcome_resolve" => exec_ml_signal_outcome_resolve(pool, run_id, job).await,
+        "index_regime_compute" => exec_index_regime_compute(pool, run_id, job).await,
+        "fund_cluster_compute" => exec_fund_cluster_compute(pool, run_id, job).await,
+        "ml_drift_monitor" => exec_ml_drift_monitor(pool, run_id, job).await,
+        "fund_analysis_v2_compute" => exec_fund_analysis_v2_compute(pool, run_id, job).await,
+        "prices_refresh_batch" => exec_prices_refresh_batch(pool, run_id, job).await,
+        "quant_xalpha_metrics_batch" => exec_quant_xalpha_metrics_batch(pool, run_id, job).await,
+        "quant_xalpha_grid_batch" => exec_quant_xalpha_grid_batch(pool, run_id, job).await,
+        "quant_xalpha_scheduled_batch" => exec_quant_xalpha_scheduled_batch(pool, run_id, job).await,
+        "quant_xalpha_qdiipredict_batch" => exec_quant_xalpha_qdiipredict_batch(pool, run_id, job).await,
+        _ => Err(format!("unknown task_type: {}", job.task_type)),
+    }
+}
+
+/// 执行任务并定期心跳；心跳发现任务被取消/回收时中止执行并返回 None。
+async fn exec_with_heartbeat(
+    pool: &sqlx::AnyPool,
+    run_id: &str,
+    job: &TaskJobRow,
+    owner: &str,
+    lease_seconds: i64,
+) -> Option<Result<(), String>> {
+    let exec = exec_task_job(pool, run_id, job);
+    tokio::pin!(exec);
+
+    let period = std::time::Duration::from_secs((lease_seconds / 3).max(1) as u64);
+    let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+    loop {
+        tokio::select! {
+            r = &mut exec => return Some(r),
+            _ = ticker.tick() => match heartbeat_task_job(pool, &job.id, owner, lease_seconds).await {
+                Ok(true) => {}
+                Ok(false) => return None,
+                // 心跳偶发失败不中断任务：租约时长留有余量。
+                Err(e) => tracing::warn!(error = %e, job_id = %job.id, "task_job heartbeat failed"),
+            },
+        }
+    }
+}
+
+pub async fn run_due_task_jobs(pool: &sqlx::AnyPool, max_run: i64) -> Result<i64, String> {
+    run_due_task_jobs_with_lease(pool, max_run, DEFAULT_LEASE_SECONDS).await
+}
+
+/// 顺序执行最多 max_run 个到期任务（不区分通道）；常驻进程使用 `task_worker` 的并发池。
+pub async fn run_due_task_jobs_with_lease(pool: &sqlx::AnyPool, max_run: i64, lease_seconds: i64) -> Result<i64, String> {
+    let max_run = max_run.clamp(0, 200);
+    let lease_seconds = lease_seconds.clamp(10, 24 * 60 * 60);
+    let owner = worker_id();
 
     let mut ran = 0_i64;
-    for r in rows {
-        let job = TaskJobRow {
-            id: r.get("id"),
-            task_type: r.get("task_type"),
-            payload_json: r.get("payload_json"),
-            priority: r.get("priority"),
-            not_before: r.get("not_before"),
-            status: r.get("status"),
-            attempt: r.get("attempt"),
-            error: r.try_get::<Option<String>, _>("error").ok().flatten(),
-            created_by: r.try_get::<Option<i64>, _>("created_by").ok().flatten(),
+    while ran < max_run {
+        let Some(job) = claim_next_task_job(pool, owner, lease_seconds, &ClaimFilter::default()).await? else {
+            break;
         };
+        run_claimed_task_job(pool, &job, owner, lease_seconds).await?;
+        ran += 1;
+    }
 
-        mark_task_job_running(pool, &job.id).await?;
-        let run_id = create_task_run(
-            pool,
-            "task_job",
-            &job.id,
-            &job.task_type,
-            None,
-            None,
-        )
-        .await?;
+    Ok(ran)
+}
 
-        let exec_result = match job.task_type.as_str() {
-            "signals_batch" => exec_signals_batch(pool, &run_id, &job).await,
-            "nav_history_sync_batch" => exec_nav_history_sync_batch(pool, &run_id, &job).await,
-            "sniffer_sync" => exec_sniffer_sync(pool, &run_id, &job).await,
-            "forecast_model_train" => exec_forecast_model_train(pool, &run_id, &job).await,
-            "fund_analysis_v2_compute" => exec_fund_analysis_v2_compute(pool, &run_id, &job).await,
-            "prices_refresh_batch" => exec_prices_refresh_batch(pool, &run_id, &job).await,
-            "quant_xalpha_metrics_batch" => exec_quant_xalpha_metrics_batch(pool, &run_id, &job).await,
-            "quant_xalpha_grid_batch" => exec_quant_xalpha_grid_batch(pool, &run_id, &job).await,
-            "quant_xalpha_scheduled_batch" => {
-                exec_quant_xalpha_scheduled_batch(pool, &run_id, &job).await
-            }
-            "quant_xalpha_qdiipredict_batch" => {
-                exec_quant_xalpha_qdiipredict_batch(pool, &run_id, &job).await
-            }
-            _ => Err(format!("unknown task_type: {}", job.task_type)),
-        };
+/// 执行一个已由 owner 抢占的任务，并按结果完成、重试或进入死信。
+pub async fn run_claimed_task_job(
+    pool: &sqlx::AnyPool,
+    job: &TaskJobRow,
+    owner: &str,
+    lease_seconds: i64,
+) -> Result<(), String> {
+    let attempt = job.attempt;
+    let run_id = create_task_run(
+        pool,
+        "task_job",
+        &job.id,
+        &job.task_type,
+        None,
+        None,
+    )
+    .await?;
+    if attempt > 1 {
+        let _ = append_task_log(pool, &run_id, "INFO", &format!("第 {attempt} 次尝试")).await;
+    }
 
-        match exec_result {
-            Ok(()) => {
+    match exec_with_heartbeat(pool, &run_id, job, owner, lease_seconds).await {
+        None => {
+            let _ = append_task_log(pool, &run_id, "WARN", "任务已取消或租约被回收，停止执行").await;
+            let _ = close_running_task_runs(pool, &job.id, "cancelled", "任务已取消").await;
+        }
+        Some(Ok(())) => {
+            if mark_task_job_done(pool, &job.id, owner).await? {
                 let _ = append_task_log(pool, &run_id, "INFO", "任务执行完成").await;
                 let _ = finish_task_run_ok(pool, &run_id).await;
-                mark_task_job_done(pool, &job.id).await?;
+            } else {
+                let _ = close_running_task_runs(pool, &job.id, "cancelled", "任务已取消").await;
             }
-            Err(e) => {
-                let _ = append_task_log(pool, &run_id, "ERROR", &format!("任务执行失败：{e}")).await;
-                let _ = finish_task_run_error(pool, &run_id, &e).await;
-                mark_task_job_error(pool, &job.id, &e).await?;
+        }
+        Some(Err(e)) => {
+            let _ = append_task_log(pool, &run_id, "ERROR", &format!("任务执行失败：{e}")).await;
+            let _ = finish_task_run_error(pool, &run_id, &e).await;
+            match fail_task_job(pool, &job.id, Some(owner), &job.task_type, attempt, &e).await? {
+                Some("queued") => {
+                    let delay = retry_policy(&job.task_type).backoff_seconds(attempt);
+                    let _ = append_task_log(pool, &run_id, "WARN", &format!("{delay} 秒后重试")).await;
+                }
+                Some(_) => {
+                    let _ = append_task_log(pool, &run_id, "ERROR", "重试次数已用尽，进入死信队列").await;
+                }
+                None => {}
             }
         }
-
-        ran += 1;
     }
 
-    Ok(ran)
+    Ok(())
 }
 
 fn parse_source_list(raw: &str) -> Vec<String> {
@@ -594,7 +1104,9 @@ async fn exec_prices_refresh_batch(
     let mut ok = 0_i64;
     let mut failed = 0_i64;
 
+    let mut progress = TaskProgress::new(&job.id, codes.len());
     for (idx, code) in codes.iter().enumerate() {
+        progress.report(pool, idx, Some(code.as_str())).await;
         if idx % 20 == 0 {
             let _ = append_task_log(pool, run_id, "INFO", &format!("进度 {idx}/{}", codes.len())).await;
         }
@@ -664,6 +1176,8 @@ async fn exec_prices_refresh_batch(
         tokio::time::sleep(std::time::Duration::from_millis(120)).await;
     }
 
+    progress.finish(pool).await;
+
     let _ = append_task_log(
         pool,
         run_id,
@@ -708,7 +1222,6 @@ async fn exec_nav_history_sync_batch(
     job: &TaskJobRow,
 ) -> Result<(), String> {
     use chrono::NaiveDate;
-    use sqlx::Row;
 
     let payload: Value = serde_json::from_str(&job.payload_json).map_err(|e| e.to_string())?;
     let codes = payload
@@ -787,7 +1300,9 @@ async fn exec_nav_history_sync_batch(
 
     let client = crate::eastmoney::build_client()?;
 
+    let mut progress = TaskProgress::new(&job.id, fund_codes.len());
     for (idx, fund_code) in fund_codes.iter().enumerate() {
+        progress.report(pool, idx, Some(fund_code.as_str())).await;
         if idx % 5 == 0 {
             let _ = append_task_log(
                 pool,
@@ -873,23 +1388,11 @@ async fn exec_nav_history_sync_batch(
                 )
                 .await;
 
-                let peer_rows = sqlx::query(
-                    r#"
-                    SELECT sec_code
-                    FROM fund_relate_theme
-                    WHERE fund_code = $1
-                    GROUP BY sec_code
-                    ORDER BY sec_code ASC
-                    LIMIT 2
-                    "#,
-                )
-                .bind(fund_code)
-                .fetch_all(pool)
-                .await
-                .unwrap_or_default();
+                let peer_rows = crate::ml::cluster::fund_peers(pool, fund_code, source_used, 2)
+                    .await
+                    .unwrap_or_default();
 
-                for r in peer_rows {
-                    let peer_code: String = r.get("sec_code");
+                for (peer_code, _) in peer_rows {
                     let _ = append_task_log(
                         pool,
                         run_id,
@@ -926,6 +1429,8 @@ async fn exec_nav_history_sync_batch(
         }
     }
 
+    progress.finish(pool).await;
+
     Ok(())
 }
 
@@ -954,40 +1459,396 @@ async fn exec_sniffer_sync(pool: &sqlx::AnyPool, run_id: &str, _job: &TaskJobRow
     }
 }
 
-async fn exec_forecast_model_train(pool: &sqlx::AnyPool, run_id: &str, job: &TaskJobRow) -> Result<(), String> {
-    use serde_json::Value;
-    use sqlx::Row;
-    use uuid::Uuid;
+async fn exec_rates_risk_free_sync(pool: &sqlx::AnyPool, run_id: &str, _job: &TaskJobRow) -> Result<(), String> {
+    let _ = append_task_log(pool, run_id, "INFO", "rates_risk_free_sync: start").await;
+    let got = crate::rates::treasury_3m::sync_chinabond_3m(pool).await?;
+    let _ = append_task_log(
+        pool,
+        run_id,
+        "INFO",
+        &format!(
+            "rates_risk_free_sync ok: tenor=3M rate_date={} rate_percent={:.4}",
+            got.rate_date, got.rate_percent
+        ),
+    )
+    .await;
+    Ok(())
+}
 
-    use crate::forecast::ols_sgd::{OlsModel, OlsTrainConfig, train_ols_sgd};
+/// 回填信号快照的 5T / 20T 实现结果。payload: `{"source"?, "limit"?}`。
+async fn exec_ml_signal_outcome_resolve(pool: &sqlx::AnyPool, run_id: &str, job: &TaskJobRow) -> Result<(), String> {
+    let payload: Value = serde_json::from_str(&job.payload_json).map_err(|e| e.to_string())?;
+    let source = payload
+        .get("source")
+        .and_then(|v| v.as_str())
+        .unwrap_or(crate::sources::SOURCE_TIANTIAN)
+        .trim()
+        .to_string();
+    let limit = payload.get("limit").and_then(|v| v.as_i64()).unwrap_or(20_000).clamp(1, 200_000);
+
+    let stats = crate::ml::outcomes::resolve_pending_outcomes(pool, &source, limit).await?;
+    let _ = append_task_log(
+        pool,
+        run_id,
+        "INFO",
+        &format!(
+            "ml_signal_outcome_resolve ok: scanned={} resolved_5t={} resolved_20t={} pending={}",
+            stats.scanned, stats.resolved_5t, stats.resolved_20t, stats.pending
+        ),
+    )
+    .await;
+    Ok(())
+}
 
+/// 刷新指数日线（仅 eastmoney，尽力而为）后重拟合市场状态模型并追加新日期的概率。
+///
+/// payload：`index_codes`（缺省取配置 `regime_index_codes`，逗号分隔，再缺省为上证指数）、
+/// `source_name`、`fetch`（默认 true）、`full`（清空后全部重写，默认 false）。
+async fn exec_index_regime_compute(pool: &sqlx::AnyPool, run_id: &str, job: &TaskJobRow) -> Result<(), String> {
     let payload: Value = serde_json::from_str(&job.payload_json).map_err(|e| e.to_string())?;
+    let source = payload
+        .get("source_name")
+        .and_then(|v| v.as_str())
+        .unwrap_or(crate::features::REFER_INDEX_SOURCE)
+        .trim()
+        .to_string();
+    let fetch = payload.get("fetch").and_then(|v| v.as_bool()).unwrap_or(true);
+    let full = payload.get("full").and_then(|v| v.as_bool()).unwrap_or(false);
+    let mut index_codes: Vec<String> = payload
+        .get("index_codes")
+        .and_then(|v| v.as_array())
+        .map(|a| {
+            a.iter()
+                .filter_map(|v| v.as_str())
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+    if index_codes.is_empty() {
+        index_codes = crate::config::ConfigStore::load()
+            .get_string("regime_index_codes")
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+    }
+    if index_codes.is_empty() {
+        index_codes.push(crate::features::REFER_INDEX_CODE.to_string());
+    }
+
+    let client = if fetch && source == "eastmoney" {
+        crate::eastmoney::build_client().ok()
+    } else {
+        None
+    };
+    let end_date = chrono::Utc::now().date_naive();
+    let start_date = end_date - chrono::Duration::days(5 * 365);
 
+    let mut failed = 0;
+    for code in &index_codes {
+        if let Some(client) = &client
+            && let Err(e) = crate::index_series::refresh_index_close_series(
+                pool,
+                client,
+                crate::db::database_kind_from_pool(pool),
+                code,
+                &source,
+                start_date,
+                end_date,
+            )
+            .await
+        {
+            let _ = append_task_log(pool, run_id, "WARN", &format!("[{code}] 拉取日线失败：{e}")).await;
+        }
+        match crate::ml::regime::compute_and_store(pool, code, &source, full).await {
+            Ok(stats) => {
+                let _ = append_task_log(
+                    pool,
+                    run_id,
+                    "INFO",
+                    &format!(
+                        "[{code}] index_regime ok: observations={} written={} last_date={}",
+                        stats.observations,
+                        stats.written,
+                        stats.last_date.as_deref().unwrap_or("-")
+                    ),
+                )
+                .await;
+            }
+            Err(e) => {
+                failed += 1;
+                let _ = append_task_log(pool, run_id, "WARN", &format!("[{code}] index_regime 跳过：{e}")).await;
+            }
+        }
+    }
+    if failed == index_codes.len() {
+        return Err(format!("index_regime_compute failed for all {failed} index codes"));
+    }
+    Ok(())
+}
+
+/// 按日收益相关性聚类全部基金，整体替换同类组及成员归属（见 [`crate::ml::cluster`]）。
+///
+/// payload：`source`（默认 tiantian）、`k`（缺省按轮廓系数在 `k_min..=k_max` 中选取）、
+/// `window_days`、`min_overlap`、`max_funds`、`bootstrap_rounds`。
+async fn exec_fund_cluster_compute(pool: &sqlx::AnyPool, run_id: &str, job: &TaskJobRow) -> Result<(), String> {
+    let payload: Value = serde_json::from_str(&job.payload_json).map_err(|e| e.to_string())?;
     let source = payload
         .get("source")
         .and_then(|v| v.as_str())
         .unwrap_or(crate::sources::SOURCE_TIANTIAN)
         .trim()
         .to_string();
-    let model_name = payload
-        .get("model_name")
+    let get = |key: &str| payload.get(key).and_then(|v| v.as_u64()).map(|v| v as usize);
+    let defaults = crate::ml::cluster::ClusterConfig::default();
+    let cfg = crate::ml::cluster::ClusterConfig {
+        k: get("k"),
+        k_min: get("k_min").unwrap_or(defaults.k_min),
+        k_max: get("k_max").unwrap_or(defaults.k_max),
+        window_days: get("window_days").unwrap_or(defaults.window_days).clamp(20, 1000),
+        min_overlap: get("min_overlap").unwrap_or(defaults.min_overlap).max(3),
+        max_funds: get("max_funds").unwrap_or(defaults.max_funds).clamp(2, 5000),
+        bootstrap_rounds: get("bootstrap_rounds").unwrap_or(defaults.bootstrap_rounds).min(100),
+    };
+
+    let _ = append_task_log(pool, run_id, "INFO", &format!("fund_cluster_compute: source={source} cfg={cfg:?}")).await;
+    let stats = crate::ml::cluster::compute_and_store(pool, &source, &cfg).await?;
+    let _ = append_task_log(
+        pool,
+        run_id,
+        "INFO",
+        &format!(
+            "fund_cluster ok: funds={} clusters={} mean_silhouette={:.3} mean_stability={:.3}",
+            stats.funds, stats.clusters, stats.mean_silhouette, stats.mean_stability
+        ),
+    )
+    .await;
+    Ok(())
+}
+
+async fn exec_ml_drift_monitor(pool: &sqlx::AnyPool, run_id: &str, job: &TaskJobRow) -> Result<(), String> {
+    use crate::ml::drift::{self, DriftConfig, SampleCache};
+
+    let payload: Value = serde_json::from_str(&job.payload_json).map_err(|e| e.to_string())?;
+    let source = payload
+        .get("source")
         .and_then(|v| v.as_str())
-        .unwrap_or("global_ols_v1")
+        .unwrap_or(crate::sources::SOURCE_TIANTIAN)
         .trim()
         .to_string();
-    if model_name.is_empty() {
-        return Err("missing model_name".to_string());
+    let family = payload
+        .get("family")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty());
+    let get_f64 = |key: &str| payload.get(key).and_then(|v| v.as_f64()).filter(|v| *v > 0.0);
+    let defaults = DriftConfig::default();
+    let cfg = DriftConfig {
+        psi: get_f64("psi").unwrap_or(defaults.psi),
+        ks: get_f64("ks").unwrap_or(defaults.ks),
+        brier_delta: get_f64("brier_delta").unwrap_or(defaults.brier_delta),
+        rmse_ratio: get_f64("rmse_ratio").unwrap_or(defaults.rmse_ratio),
+        max_age_days: payload
+            .get("max_age_days")
+            .and_then(|v| v.as_i64())
+            .unwrap_or(defaults.max_age_days)
+            .max(1),
+        min_samples: payload
+            .get("min_samples")
+            .and_then(|v| v.as_u64())
+            .map(|v| v as usize)
+            .unwrap_or(defaults.min_samples)
+            .max(2),
+        max_funds: payload
+            .get("max_funds")
+            .and_then(|v| v.as_u64())
+            .map(|v| v as usize)
+            .unwrap_or(defaults.max_funds)
+            .clamp(1, 10_000),
+    };
+
+    let versions = crate::ml::registry::active_versions(pool, family).await?;
+    let _ = append_task_log(
+        pool,
+        run_id,
+        "INFO",
+        &format!("ml_drift_monitor: source={source} models={} cfg={cfg:?}", versions.len()),
+    )
+    .await;
+
+    let mut progress = TaskProgress::new(&job.id, versions.len());
+    let mut cache = SampleCache::default();
+    let mut failed = 0;
+    for (i, v) in versions.iter().enumerate() {
+        progress.report(pool, i, Some(v.model_key.as_str())).await;
+        match drift::check_version(pool, v, &source, &cfg, &mut cache).await {
+            Ok(check) => {
+                let _ = append_task_log(
+                    pool,
+                    run_id,
+                    if check.reasons.is_empty() { "INFO" } else { "WARN" },
+                    &format!(
+                        "[{}/{} v{}] {} reasons={:?} task_job_id={}",
+                        v.family,
+                        v.model_key,
+                        v.version,
+                        check.decision,
+                        check.reasons,
+                        check.task_job_id.as_deref().unwrap_or("-")
+                    ),
+                )
+                .await;
+            }
+            Err(e) => {
+                failed += 1;
+                let _ = append_task_log(
+                    pool,
+                    run_id,
+                    "WARN",
+                    &format!("[{}/{} v{}] 漂移检查失败：{e}", v.family, v.model_key, v.version),
+                )
+                .await;
+            }
+        }
+    }
+    progress.finish(pool).await;
+
+    if failed > 0 && failed == versions.len() {
+        return Err(format!("all {failed} drift checks failed"));
+    }
+    Ok(())
+}
+
+async fn exec_ml_sector_model_train(pool: &sqlx::AnyPool, run_id: &str, job: &TaskJobRow) -> Result<(), String> {
+    use crate::ml::dataset::DatasetConfig;
+    use crate::ml::model::ModelKind;
+    use crate::ml::train::{MlTask, PEER_CODE_ALL, train_and_store_sector_model_with_kind};
+
+    let payload: Value = serde_json::from_str(&job.payload_json).map_err(|e| e.to_string())?;
+    let source = payload
+        .get("source")
+        .and_then(|v| v.as_str())
+        .unwrap_or(crate::sources::SOURCE_TIANTIAN)
+        .trim()
+        .to_string();
+    // 默认只重训全市场模型；板块模型在计算快照时按需训练。
+    let mut peer_codes: Vec<String> = payload
+        .get("peer_codes")
+        .and_then(|v| v.as_array())
+        .map(|a| {
+            a.iter()
+                .filter_map(|v| v.as_str())
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+    if peer_codes.is_empty() {
+        peer_codes.push(PEER_CODE_ALL.to_string());
+    }
+    let horizons: Vec<usize> = payload
+        .get("horizons")
+        .and_then(|v| v.as_array())
+        .map(|a| {
+            a.iter()
+                .filter_map(|v| v.as_i64())
+                .filter(|h| (1..=250).contains(h))
+                .map(|h| h as usize)
+                .collect()
+        })
+        .filter(|v: &Vec<usize>| !v.is_empty())
+        .unwrap_or_else(|| vec![5, 20]);
+    // 模型类型：payload.model_type 对两个任务统一生效，否则按任务取配置。
+    let model_type = match payload.get("model_type").and_then(|v| v.as_str()) {
+        Some(s) => Some(ModelKind::parse(s).ok_or_else(|| format!("unknown model_type: {s}"))?),
+        None => None,
+    };
+    let config = crate::config::ConfigStore::load();
+
+    let _ = append_task_log(
+        pool,
+        run_id,
+        "INFO",
+        &format!(
+            "ml_sector_model_train: source={source} peers={} horizons={horizons:?}",
+            peer_codes.len()
+        ),
+    )
+    .await;
+
+    let mut progress = TaskProgress::new(&job.id, peer_codes.len() * horizons.len() * 2);
+    let mut done = 0;
+    let mut failed = 0;
+    for peer_code in &peer_codes {
+        for &horizon_days in &horizons {
+            let cfg = DatasetConfig {
+                lookback_days: 252,
+                horizon_days,
+                stride_days: 5,
+            };
+            for task in [MlTask::DipBuy, MlTask::MagicRebound] {
+                progress.report(pool, done, Some(peer_code.as_str())).await;
+                let kind = model_type
+                    .unwrap_or_else(|| ModelKind::from_config(&config, task.model_type_config_key()));
+                match train_and_store_sector_model_with_kind(pool, peer_code, &source, task, &cfg, kind).await {
+                    Ok(Some(v)) => {
+                        let _ = append_task_log(
+                            pool,
+                            run_id,
+                            "INFO",
+                            &format!(
+                                "[{peer_code}] {} {horizon_days}T {} 训练完成：v{} {}",
+                                task.as_str(),
+                                kind.as_str(),
+                                v.version,
+                                v.status
+                            ),
+                        )
+                        .await;
+                    }
+                    Ok(None) => {
+                        let _ = append_task_log(
+                            pool,
+                            run_id,
+                            "INFO",
+                            &format!("[{peer_code}] {} {horizon_days}T 无样本，跳过", task.as_str()),
+                        )
+                        .await;
+                    }
+                    Err(e) => {
+                        failed += 1;
+                        let _ = append_task_log(
+                            pool,
+                            run_id,
+                            "WARN",
+                            &format!("[{peer_code}] {} {horizon_days}T 训练失败：{e}", task.as_str()),
+                        )
+                        .await;
+                    }
+                }
+                done += 1;
+            }
+        }
     }
-    let horizon = payload.get("horizon").and_then(|v| v.as_i64()).unwrap_or(60).clamp(1, 5000);
-    let lag_k = payload.get("lag_k").and_then(|v| v.as_i64()).unwrap_or(20).clamp(1, 400);
+    progress.finish(pool).await;
 
-    let _ = append_task_log(
-        pool,
-        run_id,
-        "INFO",
-        &format!("forecast_model_train: model_name={model_name} source={source} horizon={horizon} lag_k={lag_k}"),
-    )
-    .await;
+    if failed > 0 && failed == done {
+        return Err(format!("all {failed} model trainings failed"));
+    }
+    Ok(())
+}
+
+/// 从特征库构建全市场滞后收益训练样本（每只基金最近 400 个交易日，总量封顶 5 万）。
+async fn build_forecast_training_set(
+    pool: &sqlx::AnyPool,
+    run_id: &str,
+    source: &str,
+    horizon: i64,
+    lag_k: i64,
+) -> Result<crate::forecast::model::ForecastTrainingSet, String> {
+    use sqlx::Row;
 
     let _ = append_task_log(pool, run_id, "INFO", "训练全市场预测模型：开始").await;
 
@@ -997,8 +1858,7 @@ async fn exec_forecast_model_train(pool: &sqlx::AnyPool, run_id: &str, job: &Tas
         .map_err(|e| e.to_string())?;
 
     let max_samples: usize = 50_000;
-    let mut x: Vec<Vec<f64>> = Vec::new();
-    let mut y: Vec<f64> = Vec::new();
+    let mut set = crate::forecast::model::ForecastTrainingSet::default();
 
     for (idx, r) in fund_rows.iter().enumerate() {
         if idx % 200 == 0 {
@@ -1012,60 +1872,29 @@ async fn exec_forecast_model_train(pool: &sqlx::AnyPool, run_id: &str, job: &Tas
         }
 
         let code: String = r.get("fund_code");
-        let nav_rows = sqlx::query(
-            r#"
-            SELECT CAST(h.nav_date AS TEXT) as nav_date, CAST(h.unit_nav AS TEXT) as unit_nav
-            FROM fund_nav_history h
-            JOIN fund f ON f.id = h.fund_id
-            WHERE f.fund_code = $1 AND h.source_name = $2
-            ORDER BY h.nav_date DESC
-            LIMIT 400
-            "#,
+        // 滞后收益率序列读自特征库，与推理端同一口径。
+        features::store::materialize_fund(pool, code.trim(), source).await?;
+        let dated = features::store::load_dated_series(
+            pool,
+            code.trim(),
+            source,
+            features::LAGGED_RETURN_FEATURE,
+            None,
+            Some(400),
         )
-        .bind(code.trim())
-        .bind(&source)
-        .fetch_all(pool)
-        .await
-        .map_err(|e| e.to_string())?;
-
-        if nav_rows.len() < (lag_k as usize + 3) {
-            continue;
-        }
-
-        let mut navs: Vec<f64> = Vec::with_capacity(nav_rows.len());
-        for rr in nav_rows.into_iter().rev() {
-            let v: String = rr.get("unit_nav");
-            if let Ok(f) = v.trim().parse::<f64>() {
-                if f > 0.0 {
-                    navs.push(f);
-                }
-            }
-        }
-        if navs.len() < (lag_k as usize + 3) {
-            continue;
-        }
-
-        let mut rets: Vec<f64> = Vec::with_capacity(navs.len().saturating_sub(1));
-        for (a, b) in navs.iter().zip(navs.iter().skip(1)) {
-            if *a > 0.0 && *b > 0.0 {
-                rets.push((b / a).ln());
-            }
-        }
-        if rets.len() < (lag_k as usize + 2) {
+        .await?;
+        if dated.len() < (lag_k as usize + 2) {
             continue;
         }
 
-        for i in (lag_k as usize)..rets.len() {
-            if x.len() >= max_samples {
-                break;
-            }
-            let mut feat: Vec<f64> = Vec::with_capacity(lag_k as usize);
-            let start = i - lag_k as usize;
-            feat.extend_from_slice(&rets[start..i]);
-            x.push(feat);
-            y.push(rets[i]);
-        }
-        if x.len() >= max_samples {
+        let series: Vec<f64> = dated.iter().map(|(_, v)| *v).collect();
+        let (fx, fy) = features::lag_samples(&series, lag_k as usize);
+        let room = max_samples.saturating_sub(set.x.len());
+        set.x.extend(fx.into_iter().take(room));
+        set.y.extend(fy.into_iter().take(room));
+        set.dates
+            .extend(dated.into_iter().skip(lag_k as usize).map(|(d, _)| d).take(room));
+        if set.x.len() >= max_samples {
             break;
         }
     }
@@ -1074,109 +1903,75 @@ async fn exec_forecast_model_train(pool: &sqlx::AnyPool, run_id: &str, job: &Tas
         pool,
         run_id,
         "INFO",
-        &format!("训练样本构建完成：samples={} dim={} horizon={horizon}", x.len(), lag_k),
+        &format!("训练样本构建完成：samples={} dim={} horizon={horizon}", set.x.len(), lag_k),
     )
     .await;
-
-    let (model, sample_count) = if x.len() < 10 {
+    if set.x.len() < 10 {
         let _ = append_task_log(pool, run_id, "WARN", "训练样本过少，使用退化预测模型（mu=0）").await;
-        (
-            OlsModel {
-                weights: vec![0.0; lag_k as usize],
-                bias: 0.0,
-                mean: vec![0.0; lag_k as usize],
-                std: vec![1.0; lag_k as usize],
-                residual_sigma: 0.0,
-            },
-            x.len() as i64,
-        )
-    } else {
-        let cfg = OlsTrainConfig {
-            learning_rate: 0.01,
-            epochs: 3,
-            l2: 1e-4,
-        };
-        let m = train_ols_sgd(&x, &y, &cfg).ok_or_else(|| "模型训练失败（样本不足或数据异常）".to_string())?;
-        let _ = append_task_log(pool, run_id, "INFO", &format!("训练完成：residual_sigma={:.6}", m.residual_sigma)).await;
-        (m, x.len() as i64)
-    };
+    }
 
-    let id = Uuid::new_v4().to_string();
-    let weights_json = serde_json::to_string(&model.weights).map_err(|e| e.to_string())?;
-    let mean_json = serde_json::to_string(&model.mean).map_err(|e| e.to_string())?;
-    let std_json = serde_json::to_string(&model.std).map_err(|e| e.to_string())?;
+    Ok(set)
+}
 
-    let sql_pg = r#"
-        INSERT INTO forecast_model (
-          id, model_name, source, horizon, lag_k, weights_json, bias, mean_json, std_json, residual_sigma,
-          sample_count, trained_at, created_at, updated_at
-        )
-        VALUES (($1)::uuid,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,CURRENT_TIMESTAMP,CURRENT_TIMESTAMP,CURRENT_TIMESTAMP)
-        ON CONFLICT (model_name, source, horizon, lag_k) DO UPDATE SET
-          weights_json = excluded.weights_json,
-          bias = excluded.bias,
-          mean_json = excluded.mean_json,
-          std_json = excluded.std_json,
-          residual_sigma = excluded.residual_sigma,
-          sample_count = excluded.sample_count,
-          trained_at = excluded.trained_at,
-          updated_at = CURRENT_TIMESTAMP
-    "#;
-    let sql_any = r#"
-        INSERT INTO forecast_model (
-          id, model_name, source, horizon, lag_k, weights_json, bias, mean_json, std_json, residual_sigma,
-          sample_count, trained_at, created_at, updated_at
-        )
-        VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,CURRENT_TIMESTAMP,CURRENT_TIMESTAMP,CURRENT_TIMESTAMP)
-        ON CONFLICT (model_name, source, horizon, lag_k) DO UPDATE SET
-          weights_json = excluded.weights_json,
-          bias = excluded.bias,
-          mean_json = excluded.mean_json,
-          std_json = excluded.std_json,
-          residual_sigma = excluded.residual_sigma,
-          sample_count = excluded.sample_count,
-          trained_at = excluded.trained_at,
-          updated_at = CURRENT_TIMESTAMP
-    "#;
+async fn exec_forecast_model_train(pool: &sqlx::AnyPool, run_id: &str, job: &TaskJobRow) -> Result<(), String> {
+    use serde_json::Value;
 
-    if sqlx::query(sql_pg)
-        .bind(&id)
-        .bind(&model_name)
-        .bind(&source)
-        .bind(horizon)
-        .bind(lag_k)
-        .bind(&weights_json)
-        .bind(model.bias)
-        .bind(&mean_json)
-        .bind(&std_json)
-        .bind(model.residual_sigma)
-        .bind(sample_count)
-        .execute(pool)
-        .await
-        .is_err()
-    {
-        sqlx::query(sql_any)
-            .bind(&id)
-            .bind(&model_name)
-            .bind(&source)
-            .bind(horizon)
-            .bind(lag_k)
-            .bind(&weights_json)
-            .bind(model.bias)
-            .bind(&mean_json)
-            .bind(&std_json)
-            .bind(model.residual_sigma)
-            .bind(sample_count)
-            .execute(pool)
-            .await
-            .map_err(|e| e.to_string())?;
+    use crate::forecast::model::{ForecastModel, ForecastModelKey, MODEL_TYPE_CONFIG_KEY, train_and_register};
+    use crate::ml::model::ModelKind;
+
+    let payload: Value = serde_json::from_str(&job.payload_json).map_err(|e| e.to_string())?;
+
+    let source = payload
+        .get("source")
+        .and_then(|v| v.as_str())
+        .unwrap_or(crate::sources::SOURCE_TIANTIAN)
+        .trim()
+        .to_string();
+    // 模型类型：payload.model_type 优先，否则取配置。
+    let kind = match payload.get("model_type").and_then(|v| v.as_str()) {
+        Some(s) => ModelKind::parse(s).ok_or_else(|| format!("unknown model_type: {s}"))?,
+        None => ModelKind::from_config(&crate::config::ConfigStore::load(), MODEL_TYPE_CONFIG_KEY),
+    };
+    let model_name = payload
+        .get("model_name")
+        .and_then(|v| v.as_str())
+        .unwrap_or(ForecastModel::default_name(kind))
+        .trim()
+        .to_string();
+    if model_name.is_empty() {
+        return Err("missing model_name".to_string());
     }
+    let horizon = payload.get("horizon").and_then(|v| v.as_i64()).unwrap_or(60).clamp(1, 5000);
+    let lag_k = payload.get("lag_k").and_then(|v| v.as_i64()).unwrap_or(20).clamp(1, 400);
+
+    let _ = append_task_log(
+        pool,
+        run_id,
+        "INFO",
+        &format!(
+            "forecast_model_train: model_name={model_name} model_type={} source={source} horizon={horizon} lag_k={lag_k}",
+            kind.as_str()
+        ),
+    )
+    .await;
+
+    let set = build_forecast_training_set(pool, run_id, &source, horizon, lag_k).await?;
+    let key = ForecastModelKey {
+        model_name,
+        source,
+        horizon,
+        lag_k,
+    };
+    let version = train_and_register(pool, &key, kind, &set).await?;
 
     let _ = append_task_log(
         pool,
         run_id,
         "INFO",
-        &format!("写入 forecast_model 完成：samples={sample_count} dim={lag_k}"),
+        &format!(
+            "登记预测模型版本 v{}：status={} shadow={}",
+            version.version, version.status, version.shadow
+        ),
     )
     .await;
 
@@ -1192,9 +1987,12 @@ async fn exec_fund_analysis_v2_compute(
     use sqlx::Row;
     use uuid::Uuid;
 
-    use crate::forecast::ols_sgd::{OlsModel, OlsTrainConfig, train_ols_sgd};
+    use crate::forecast::model::{
+        ForecastModel, ForecastModelKey, MODEL_TYPE_CONFIG_KEY, load_serving_model, train_and_register,
+    };
+    use crate::ml::model::ModelKind;
+    use crate::ml::registry;
 
-    const MODEL_NAME: &str = "global_ols_v1";
     const FORECAST_HORIZON: i64 = 60;
     const LAG_K: i64 = 20;
 
@@ -1208,6 +2006,8 @@ async fn exec_fund_analysis_v2_compute(
     if fund_code.is_empty() {
         return Err("missing fund_code".to_string());
     }
+    let model_kind = ModelKind::from_config(&crate::config::ConfigStore::load(), MODEL_TYPE_CONFIG_KEY);
+    let model_name = ForecastModel::default_name(model_kind);
 
     let source = payload
         .get("source")
@@ -1266,6 +2066,17 @@ async fn exec_fund_analysis_v2_compute(
         .to_string();
     let refer_index_source = "eastmoney".to_string();
 
+    let mut forecast_horizons: Vec<usize> = payload
+        .get("forecast_horizons")
+        .and_then(|v| v.as_array())
+        .map(|arr| arr.iter().filter_map(|x| x.as_u64()).map(|h| h as usize).collect())
+        .unwrap_or_default();
+    forecast_horizons.retain(|h| (1..=250).contains(h));
+    if forecast_horizons.is_empty() {
+        forecast_horizons = crate::forecast::quantile::DEFAULT_HORIZONS.to_vec();
+    }
+    forecast_horizons.truncate(8);
+
     let _ = append_task_log(
         pool,
         run_id,
@@ -1305,312 +2116,57 @@ async fn exec_fund_analysis_v2_compute(
     let url_grid = format!("{quant_service_url}/api/quant/xalpha/grid");
     let url_scheduled = format!("{quant_service_url}/api/quant/xalpha/scheduled");
 
-    async fn load_forecast_model(
-        pool: &sqlx::AnyPool,
-        model_name: &str,
-        source: &str,
-        horizon: i64,
-        lag_k: i64,
-    ) -> Result<Option<(OlsModel, String)>, String> {
-        let row = sqlx::query(
-            r#"
-            SELECT
-              weights_json,
-              bias,
-              mean_json,
-              std_json,
-              residual_sigma,
-              CAST(trained_at AS TEXT) as trained_at
-            FROM forecast_model
-            WHERE model_name = $1 AND source = $2 AND horizon = $3 AND lag_k = $4
-            ORDER BY trained_at DESC
-            LIMIT 1
-            "#,
-        )
-        .bind(model_name)
-        .bind(source)
-        .bind(horizon)
-        .bind(lag_k)
-        .fetch_optional(pool)
-        .await
-        .map_err(|e| e.to_string())?;
-
-        let Some(row) = row else { return Ok(None); };
-
-        let weights_json: String = row.get("weights_json");
-        let mean_json: String = row.get("mean_json");
-        let std_json: String = row.get("std_json");
-        let weights: Vec<f64> = serde_json::from_str(&weights_json).map_err(|e| e.to_string())?;
-        let mean: Vec<f64> = serde_json::from_str(&mean_json).map_err(|e| e.to_string())?;
-        let std: Vec<f64> = serde_json::from_str(&std_json).map_err(|e| e.to_string())?;
-
-        Ok(Some((
-            OlsModel {
-                weights,
-                bias: row.get::<f64, _>("bias"),
-                mean,
-                std,
-                residual_sigma: row.get::<f64, _>("residual_sigma"),
-            },
-            row.get::<String, _>("trained_at"),
-        )))
-    }
-
-    async fn upsert_forecast_model(
-        pool: &sqlx::AnyPool,
-        model_name: &str,
-        source: &str,
-        horizon: i64,
-        lag_k: i64,
-        model: &OlsModel,
-        sample_count: i64,
-    ) -> Result<(), String> {
-        let id = Uuid::new_v4().to_string();
-        let weights_json = serde_json::to_string(&model.weights).map_err(|e| e.to_string())?;
-        let mean_json = serde_json::to_string(&model.mean).map_err(|e| e.to_string())?;
-        let std_json = serde_json::to_string(&model.std).map_err(|e| e.to_string())?;
-
-        let sql_pg = r#"
-            INSERT INTO forecast_model (
-              id, model_name, source, horizon, lag_k, weights_json, bias, mean_json, std_json, residual_sigma,
-              sample_count, trained_at, created_at, updated_at
-            )
-            VALUES (($1)::uuid,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,CURRENT_TIMESTAMP,CURRENT_TIMESTAMP,CURRENT_TIMESTAMP)
-            ON CONFLICT (model_name, source, horizon, lag_k) DO UPDATE SET
-              weights_json = excluded.weights_json,
-              bias = excluded.bias,
-              mean_json = excluded.mean_json,
-              std_json = excluded.std_json,
-              residual_sigma = excluded.residual_sigma,
-              sample_count = excluded.sample_count,
-              trained_at = excluded.trained_at,
-              updated_at = CURRENT_TIMESTAMP
-        "#;
-        let sql_any = r#"
-            INSERT INTO forecast_model (
-              id, model_name, source, horizon, lag_k, weights_json, bias, mean_json, std_json, residual_sigma,
-              sample_count, trained_at, created_at, updated_at
-            )
-            VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,CURRENT_TIMESTAMP,CURRENT_TIMESTAMP,CURRENT_TIMESTAMP)
-            ON CONFLICT (model_name, source, horizon, lag_k) DO UPDATE SET
-              weights_json = excluded.weights_json,
-              bias = excluded.bias,
-              mean_json = excluded.mean_json,
-              std_json = excluded.std_json,
-              residual_sigma = excluded.residual_sigma,
-              sample_count = excluded.sample_count,
-              trained_at = excluded.trained_at,
-              updated_at = CURRENT_TIMESTAMP
-        "#;
-
-        if sqlx::query(sql_pg)
-            .bind(&id)
-            .bind(model_name)
-            .bind(source)
-            .bind(horizon)
-            .bind(lag_k)
-            .bind(&weights_json)
-            .bind(model.bias)
-            .bind(&mean_json)
-            .bind(&std_json)
-            .bind(model.residual_sigma)
-            .bind(sample_count)
-            .execute(pool)
-            .await
-            .is_err()
-        {
-            sqlx::query(sql_any)
-                .bind(&id)
-                .bind(model_name)
-                .bind(source)
-                .bind(horizon)
-                .bind(lag_k)
-                .bind(&weights_json)
-                .bind(model.bias)
-                .bind(&mean_json)
-                .bind(&std_json)
-                .bind(model.residual_sigma)
-                .bind(sample_count)
-                .execute(pool)
-                .await
-                .map_err(|e| e.to_string())?;
-        }
-
-        Ok(())
+    fn trained_date_prefix(trained_at: &str) -> &str {
+        trained_at.get(0..10).unwrap_or("")
     }
 
-    async fn train_global_model(
-        pool: &sqlx::AnyPool,
-        run_id: &str,
-        source: &str,
-        horizon: i64,
-        lag_k: i64,
-    ) -> Result<(OlsModel, i64), String> {
-        let _ = append_task_log(pool, run_id, "INFO", "训练全市场预测模型：开始").await;
-
-        let fund_rows = sqlx::query("SELECT fund_code FROM fund ORDER BY fund_code ASC")
-            .fetch_all(pool)
-            .await
-            .map_err(|e| e.to_string())?;
-
-        let max_samples: usize = 50_000;
-        let mut x: Vec<Vec<f64>> = Vec::new();
-        let mut y: Vec<f64> = Vec::new();
-
-        for (idx, r) in fund_rows.iter().enumerate() {
-            if idx % 200 == 0 {
-                let _ = append_task_log(
-                    pool,
-                    run_id,
-                    "INFO",
-                    &format!("训练数据扫描进度 {idx}/{}", fund_rows.len()),
-                )
-                .await;
-            }
-
-            let code: String = r.get("fund_code");
-            let nav_rows = sqlx::query(
-                r#"
-                SELECT CAST(h.nav_date AS TEXT) as nav_date, CAST(h.unit_nav AS TEXT) as unit_nav
-                FROM fund_nav_history h
-                JOIN fund f ON f.id = h.fund_id
-                WHERE f.fund_code = $1 AND h.source_name = $2
-                ORDER BY h.nav_date DESC
-                LIMIT 400
-                "#,
-            )
-            .bind(code.trim())
-            .bind(source)
-            .fetch_all(pool)
-            .await
-            .map_err(|e| e.to_string())?;
-
-            if nav_rows.len() < (lag_k as usize + 3) {
-                continue;
-            }
-
-            let mut navs: Vec<f64> = Vec::with_capacity(nav_rows.len());
-            for rr in nav_rows.into_iter().rev() {
-                let v: String = rr.get("unit_nav");
-                if let Ok(f) = v.trim().parse::<f64>() {
-                    if f > 0.0 {
-                        navs.push(f);
-                    }
-                }
-            }
-            if navs.len() < (lag_k as usize + 3) {
-                continue;
-            }
-
-            let mut rets: Vec<f64> = Vec::with_capacity(navs.len().saturating_sub(1));
-            for (a, b) in navs.iter().zip(navs.iter().skip(1)) {
-                if *a > 0.0 && *b > 0.0 {
-                    rets.push((b / a).ln());
-                }
-            }
-            if rets.len() < (lag_k as usize + 2) {
-                continue;
-            }
-
-            for i in (lag_k as usize)..rets.len() {
-                if x.len() >= max_samples {
-                    break;
-                }
-                let mut feat: Vec<f64> = Vec::with_capacity(lag_k as usize);
-                let start = i - lag_k as usize;
-                feat.extend_from_slice(&rets[start..i]);
-                x.push(feat);
-                y.push(rets[i]);
-            }
-            if x.len() >= max_samples {
-                break;
-            }
-        }
-
-        let _ = append_task_log(
-            pool,
-            run_id,
-            "INFO",
-            &format!("训练样本构建完成：samples={} dim={} horizon={horizon}", x.len(), lag_k),
-        )
-        .await;
+    let today = chrono::Utc::now().date_naive().format("%Y-%m-%d").to_string();
 
-        if x.len() < 10 {
+    let key = ForecastModelKey {
+        model_name: model_name.to_string(),
+        source: source.clone(),
+        horizon: FORECAST_HORIZON,
+        lag_k: LAG_K,
+    };
+    let serving = load_serving_model(pool, &key).await?;
+    // 新鲜度取最近一次登记版本与服务表 trained_at 的较晚者：挑战者未晋升时当天不再重复训练。
+    let last_trained = registry::latest_created_at(pool, registry::FAMILY_FORECAST, &key.registry_key())
+        .await?
+        .into_iter()
+        .chain(serving.as_ref().map(|(_, t)| t.clone()))
+        .max();
+
+    let model = match serving {
+        Some((m, _)) if last_trained.as_deref().map(trained_date_prefix) == Some(today.as_str()) => m,
+        serving => {
+            let msg = match (&serving, &last_trained) {
+                (None, _) | (_, None) => "预测模型缺失，触发训练".to_string(),
+                (Some(_), Some(t)) => format!(
+                    "预测模型过期：trained_at={} today={}，触发重训",
+                    trained_date_prefix(t),
+                    today
+                ),
+            };
+            let _ = append_task_log(pool, run_id, "INFO", &msg).await;
+            let set = build_forecast_training_set(pool, run_id, &source, FORECAST_HORIZON, LAG_K).await?;
+            let version = train_and_register(pool, &key, model_kind, &set).await?;
             let _ = append_task_log(
                 pool,
                 run_id,
-                "WARN",
-                "训练样本过少，使用退化预测模型（mu=0）",
+                "INFO",
+                &format!("登记预测模型版本 v{}：status={}", version.version, version.status),
             )
             .await;
-            return Ok((
-                OlsModel {
-                    weights: vec![0.0; lag_k as usize],
-                    bias: 0.0,
-                    mean: vec![0.0; lag_k as usize],
-                    std: vec![1.0; lag_k as usize],
-                    residual_sigma: 0.0,
-                },
-                x.len() as i64,
-            ));
-        }
-
-        let cfg = OlsTrainConfig {
-            learning_rate: 0.01,
-            epochs: 3,
-            l2: 1e-4,
-        };
-        let model = train_ols_sgd(&x, &y, &cfg).ok_or_else(|| "模型训练失败（样本不足或数据异常）".to_string())?;
-
-        let _ = append_task_log(
-            pool,
-            run_id,
-            "INFO",
-            &format!("训练完成：residual_sigma={:.6}", model.residual_sigma),
-        )
-        .await;
-
-        Ok((model, x.len() as i64))
-    }
-
-    fn trained_date_prefix(trained_at: &str) -> &str {
-        trained_at.get(0..10).unwrap_or("")
-    }
-
-    let today = chrono::Utc::now().date_naive().format("%Y-%m-%d").to_string();
-
-    let model = match load_forecast_model(pool, MODEL_NAME, &source, FORECAST_HORIZON, LAG_K).await? {
-        Some((m, trained_at)) => {
-            if trained_date_prefix(&trained_at) == today {
-                m
-            } else {
-                let _ = append_task_log(
-                    pool,
-                    run_id,
-                    "INFO",
-                    &format!(
-                        "预测模型过期：trained_at={} today={}，触发重训",
-                        trained_date_prefix(&trained_at),
-                        today
-                    ),
-                )
-                .await;
-                let (m2, sample_count) =
-                    train_global_model(pool, run_id, &source, FORECAST_HORIZON, LAG_K).await?;
-                let _ = upsert_forecast_model(pool, MODEL_NAME, &source, FORECAST_HORIZON, LAG_K, &m2, sample_count).await;
-                m2
+            match load_serving_model(pool, &key).await? {
+                Some((m, _)) => m,
+                None => ForecastModel::degenerate(LAG_K as usize),
             }
         }
-        None => {
-            let _ = append_task_log(pool, run_id, "INFO", "预测模型缺失，触发训练").await;
-            let (m, sample_count) = train_global_model(pool, run_id, &source, FORECAST_HORIZON, LAG_K).await?;
-            let _ = upsert_forecast_model(pool, MODEL_NAME, &source, FORECAST_HORIZON, LAG_K, &m, sample_count).await;
-            m
-        }
     };
 
     let mut windows_out: Vec<Value> = Vec::with_capacity(windows.len());
     let mut as_of_date_overall: Option<String> = None;
+    features::store::materialize_fund(pool, fund_code.trim(), &source).await?;
 
     for window in windows {
         let rows = sqlx::query(
@@ -1680,21 +2236,17 @@ async fn exec_fund_analysis_v2_compute(
             continue;
         }
 
-        // build last LAG_K log-returns from seed navs
-        let mut rets: Vec<f64> = Vec::with_capacity(seed_navs.len().saturating_sub(1));
-        for ((_, a), (_, b)) in seed_navs.iter().zip(seed_navs.iter().skip(1)) {
-            if *a > 0.0 && *b > 0.0 {
-                rets.push((b / a).ln());
-            }
-        }
-        let mut hist: Vec<f64> = Vec::new();
-        let k = LAG_K as usize;
-        if rets.len() >= k {
-            hist.extend_from_slice(&rets[rets.len() - k..]);
-        } else {
-            hist.extend(std::iter::repeat(0.0).take(k - rets.len()));
-            hist.extend_from_slice(&rets);
-        }
+        // 最近 LAG_K 个对数收益率取自特征库（与训练同源），不足左侧补 0
+        let lagged = features::store::load_series(
+            pool,
+            fund_code.trim(),
+            &source,
+            features::LAGGED_RETURN_FEATURE,
+            as_of_date.as_deref(),
+            Some(LAG_K),
+        )
+        .await?;
+        let mut hist = features::last_lags(&lagged, LAG_K as usize);
 
         // forecast horizon=60
         let mut forecast_points: Vec<Value> = Vec::with_capacity(FORECAST_HORIZON as usize);
@@ -1703,7 +2255,7 @@ async fn exec_fund_analysis_v2_compute(
         let mut nav_max = f64::NEG_INFINITY;
         let mut nav_series_mean: Vec<f64> = Vec::with_capacity(FORECAST_HORIZON as usize);
 
-        let sigma = model.residual_sigma.max(0.0).min(0.2);
+        let sigma = model.residual_sigma().max(0.0).min(0.2);
         let z = 1.96_f64;
 
         for step in 1..=FORECAST_HORIZON {
@@ -1733,7 +2285,7 @@ async fn exec_fund_analysis_v2_compute(
             }));
 
             hist.push(mu);
-            if hist.len() > k {
+            if hist.len() > LAG_K as usize {
                 hist.remove(0);
             }
         }
@@ -1772,7 +2324,7 @@ async fn exec_fund_analysis_v2_compute(
           "nav_min": nav_min,
           "nav_max": nav_max,
           "swing_points": swings,
-          "model": { "name": MODEL_NAME, "lag_k": LAG_K, "sigma": sigma }
+          "model": { "name": model_name, "type": model.kind().as_str(), "lag_k": LAG_K, "sigma": sigma }
         });
 
         // quant-service uses forecast curve, not historical curve
@@ -2080,13 +2632,43 @@ async fn exec_fund_analysis_v2_compute(
         return Err("no eligible windows computed".to_string());
     }
 
+    // 多期限分位数预测扇形；失败不影响其余结果。
+    let forecast_fan = match crate::forecast::quantile::forecast_fan(
+        pool,
+        fund_code.trim(),
+        &source,
+        &forecast_horizons,
+        LAG_K as usize,
+        model_kind,
+    )
+    .await
+    {
+        Ok(fan) => {
+            if let Some(fan) = fan.as_ref().filter(|f| !f.skipped.is_empty()) {
+                let _ = append_task_log(
+                    pool,
+                    run_id,
+                    "WARN",
+                    &format!("[{fund_code}] 预测扇形样本不足，跳过期限：{:?}", fan.skipped),
+                )
+                .await;
+            }
+            fan
+        }
+        Err(e) => {
+            let _ = append_task_log(pool, run_id, "WARN", &format!("[{fund_code}] 预测扇形失败：{e}")).await;
+            None
+        }
+    };
+
     let result = json!({
       "fund_code": fund_code,
       "source": source,
       "profile": profile,
       "refer_index_code": refer_index_code,
       "as_of_date": as_of_date_overall,
-      "windows": windows_out
+      "windows": windows_out,
+      "forecast_fan": forecast_fan
     });
 
     let snapshot_id = Uuid::new_v4().to_string();
@@ -2184,7 +2766,9 @@ async fn exec_signals_batch(pool: &sqlx::AnyPool, run_id: &str, job: &TaskJobRow
         .execute(pool)
         .await;
 
+    let mut progress = TaskProgress::new(&job.id, fund_codes.len());
     for (idx, code) in fund_codes.iter().enumerate() {
+        progress.report(pool, idx, Some(code.as_str())).await;
         if idx % 10 == 0 {
             let _ = append_task_log(pool, run_id, "INFO", &format!("进度 {idx}/{}", fund_codes.len())).await;
         }
@@ -2216,6 +2800,7 @@ async fn exec_signals_batch(pool: &sqlx::AnyPool, run_id: &str, job: &TaskJobRow
               dip_buy_proba_20t,
               magic_rebound_proba_5t,
               magic_rebound_proba_20t,
+              explain_json,
               CAST(computed_at AS TEXT) as computed_at
             FROM fund_signal_snapshot
             WHERE fund_code = $1 AND peer_code = $2 AND CAST(as_of_date AS TEXT) = $3
@@ -2233,6 +2818,9 @@ async fn exec_signals_batch(pool: &sqlx::AnyPool, run_id: &str, job: &TaskJobRow
             continue;
         };
 
+        let explain = crate::ml::explain::SnapshotExplanations::from_json(
+            snap.try_get::<Option<String>, _>("explain_json").ok().flatten().as_deref(),
+        );
         let best_peer = serde_json::json!({
           "peer_code": crate::ml::train::PEER_CODE_ALL,
           "peer_name": "全市场",
@@ -2240,11 +2828,15 @@ async fn exec_signals_batch(pool: &sqlx::AnyPool, run_id: &str, job: &TaskJobRow
           "position_bucket": snap.try_get::<Option<String>, _>("position_bucket").ok().flatten(),
           "dip_buy": {
             "p_5t": snap.try_get::<Option<f64>, _>("dip_buy_proba_5t").ok().flatten(),
-            "p_20t": snap.try_get::<Option<f64>, _>("dip_buy_proba_20t").ok().flatten()
+            "p_20t": snap.try_get::<Option<f64>, _>("dip_buy_proba_20t").ok().flatten(),
+            "explain_5t": explain.dip_buy_5t,
+            "explain_20t": explain.dip_buy_20t
           },
           "magic_rebound": {
             "p_5t": snap.try_get::<Option<f64>, _>("magic_rebound_proba_5t").ok().flatten(),
-            "p_20t": snap.try_get::<Option<f64>, _>("magic_rebound_proba_20t").ok().flatten()
+            "p_20t": snap.try_get::<Option<f64>, _>("magic_rebound_proba_20t").ok().flatten(),
+            "explain_5t": explain.magic_rebound_5t,
+            "explain_20t": explain.magic_rebound_20t
           },
           "model_sample_size_20t": serde_json::Value::Null,
           "computed_at": snap.try_get::<Option<String>, _>("computed_at").ok().flatten()
@@ -2293,6 +2885,8 @@ async fn exec_signals_batch(pool: &sqlx::AnyPool, run_id: &str, job: &TaskJobRow
         let _ = append_task_log(pool, run_id, "INFO", &format!("[{code}] 完成")).await;
     }
 
+    progress.finish(pool).await;
+
     Ok(())
 }
 
@@ -2363,7 +2957,9 @@ async fn exec_quant_xalpha_metrics_batch(
     let mut ok = 0_i64;
     let mut failed = 0_i64;
 
+    let mut progress = TaskProgress::new(&job.id, fund_codes.len());
     for (idx, code) in fund_codes.iter().enumerate() {
+        progress.report(pool, idx, Some(code.as_str())).await;
         if idx % 20 == 0 {
             let _ = append_task_log(
                 pool,
@@ -2480,6 +3076,8 @@ async fn exec_quant_xalpha_metrics_batch(
         ok += 1;
     }
 
+    progress.finish(pool).await;
+
     let _ = append_task_log(
         pool,
         run_id,
@@ -2562,7 +3160,9 @@ async fn exec_quant_xalpha_grid_batch(
     let mut ok = 0_i64;
     let mut failed = 0_i64;
 
+    let mut progress = TaskProgress::new(&job.id, fund_codes.len());
     for (idx, code) in fund_codes.iter().enumerate() {
+        progress.report(pool, idx, Some(code.as_str())).await;
         if idx % 20 == 0 {
             let _ = append_task_log(
                 pool,
@@ -2672,6 +3272,8 @@ async fn exec_quant_xalpha_grid_batch(
         ok += 1;
     }
 
+    progress.finish(pool).await;
+
     let _ = append_task_log(
         pool,
         run_id,
@@ -2756,7 +3358,9 @@ async fn exec_quant_xalpha_scheduled_batch(
     let mut ok = 0_i64;
     let mut failed = 0_i64;
 
+    let mut progress = TaskProgress::new(&job.id, fund_codes.len());
     for (idx, code) in fund_codes.iter().enumerate() {
+        progress.report(pool, idx, Some(code.as_str())).await;
         if idx % 20 == 0 {
             let _ = append_task_log(
                 pool,
@@ -2867,6 +3471,8 @@ async fn exec_quant_xalpha_scheduled_batch(
         ok += 1;
     }
 
+    progress.finish(pool).await;
+
     let _ = append_task_log(
         pool,
         run_id,
@@ -2915,7 +3521,9 @@ async fn exec_quant_xalpha_qdiipredict_batch(
     let mut ok = 0_i64;
     let mut failed = 0_i64;
 
+    let mut progress = TaskProgress::new(&job.id, items.len());
     for (idx, it) in items.iter().enumerate() {
+        progress.report(pool, idx, it.get("fund_code").and_then(|v| v.as_str())).await;
         if idx % 20 == 0 {
             let _ = append_task_log(pool, run_id, "INFO", &format!("进度 {idx}/{}", items.len())).await;
         }
@@ -2984,6 +3592,8 @@ async fn exec_quant_xalpha_qdiipredict_batch(
         ok += 1;
     }
 
+    progress.finish(pool).await;
+
     let _ = append_task_log(
         pool,
         run_id,
@@ -0,0 +1,400 @@
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use rand::{Rng, RngCore};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::db::DatabaseKind;
+use crate::dbfmt::format_utc;
+
+/// RFC 6238 参数：SHA-1、6 位、30 秒步长（与主流验证器 App 的默认值一致）。
+pub const DIGITS: u32 = 6;
+pub const PERIOD_SECONDS: i64 = 30;
+/// 允许前后各 1 个时间步的时钟偏差。
+const SKEW_STEPS: i64 = 1;
+pub const ISSUER: &str = "Fundval";
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// RFC 4648 base32（无填充）。
+pub fn base32_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(5) * 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &b in data {
+        buffer = (buffer << 8) | b as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    out
+}
+
+/// 解码时忽略空格、填充与大小写。
+pub fn base32_decode(s: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(s.len() * 5 / 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in s.chars().filter(|c| !c.is_whitespace() && *c != '=') {
+        let v = BASE32_ALPHABET
+            .iter()
+            .position(|&a| a as char == c.to_ascii_uppercase())? as u32;
+        buffer = (buffer << 5) | v;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push(((buffer >> bits) & 0xff) as u8);
+        }
+    }
+    Some(out)
+}
+
+/// 160 位随机密钥（base32）。
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 20];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    base32_encode(&bytes)
+}
+
+/// 指定时间步的验证码（RFC 4226 动态截断）。
+pub fn code_at(secret: &[u8], step: i64) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("hmac accepts any key length");
+    mac.update(&step.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let bin = ((digest[offset] as u32 & 0x7f) << 24)
+        | ((digest[offset + 1] as u32) << 16)
+        | ((digest[offset + 2] as u32) << 8)
+        | digest[offset + 3] as u32;
+    format!(
+        "{:0width$}",
+        bin % 10_u32.pow(DIGITS),
+        width = DIGITS as usize
+    )
+}
+
+pub fn current_step(now_unix: i64) -> i64 {
+    now_unix.div_euclid(PERIOD_SECONDS)
+}
+
+/// 校验验证码；返回匹配的时间步。`after_step` 之前（含）的时间步不再接受，防止重放。
+pub fn verify_code(
+    secret_b32: &str,
+    code: &str,
+    now_unix: i64,
+    after_step: Option<i64>,
+) -> Option<i64> {
+    let code = code.trim();
+    if code.len() != DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let secret = base32_decode(secret_b32)?;
+    let step = current_step(now_unix);
+    (step - SKEW_STEPS..=step + SKEW_STEPS)
+        .filter(|s| after_step.is_none_or(|last| *s > last))
+        .find(|s| code_at(&secret, *s) == code)
+}
+
+fn uri_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// 供验证器 App 扫码的 otpauth URI。
+pub fn otpauth_uri(username: &str, secret_b32: &str) -> String {
+    format!(
+        "otpauth://totp/{issuer}:{account}?secret={secret_b32}&issuer={issuer}&algorithm=SHA1&digits={DIGITS}&period={PERIOD_SECONDS}",
+        issuer = uri_encode(ISSUER),
+        account = uri_encode(username),
+    )
+}
+
+fn is_pg(pool: &sqlx::AnyPool) -> bool {
+    crate::db::database_kind_from_pool(pool) == DatabaseKind::Postgres
+}
+
+#[derive(Debug, Clone)]
+pub struct TotpRow {
+    pub secret: String,
+    pub enabled: bool,
+    pub last_step: Option<i64>,
+}
+
+pub async fn get(pool: &sqlx::AnyPool, user_id: i64) -> Result<Option<TotpRow>, String> {
+    let row = sqlx::query(
+        r#"
+        SELECT secret, CASE WHEN enabled_at IS NULL THEN 0 ELSE 1 END as enabled, last_step
+        FROM auth_totp WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(row.map(|r| TotpRow {
+        secret: r.get("secret"),
+        enabled: r.get::<i64, _>("enabled") != 0,
+        last_step: r.try_get::<Option<i64>, _>("last_step").ok().flatten(),
+    }))
+}
+
+pub async fn is_enabled(pool: &sqlx::AnyPool, user_id: i64) -> Result<bool, String> {
+    Ok(get(pool, user_id).await?.is_some_and(|t| t.enabled))
+}
+
+/// 生成（或替换）尚未启用的密钥；已启用时不写入并返回 None。
+pub async fn begin_enrollment(
+    pool: &sqlx::AnyPool,
+    user_id: i64,
+) -> Result<Option<String>, String> {
+    let secret = generate_secret();
+    let res = sqlx::query(
+        r#"
+        INSERT INTO auth_totp (user_id, secret, enabled_at, last_step)
+        VALUES ($1, $2, NULL, NULL)
+        ON CONFLICT (user_id) DO UPDATE SET secret = $2, enabled_at = NULL, last_step = NULL
+        WHERE auth_totp.enabled_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .bind(&secret)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok((res.rows_affected() > 0).then_some(secret))
+}
+
+pub async fn mark_enabled(pool: &sqlx::AnyPool, user_id: i64) -> Result<(), String> {
+    let sql = if is_pg(pool) {
+        "UPDATE auth_totp SET enabled_at = ($2)::timestamptz WHERE user_id = $1"
+    } else {
+        "UPDATE auth_totp SET enabled_at = $2 WHERE user_id = $1"
+    };
+    sqlx::query(sql)
+        .bind(user_id)
+        .bind(format_utc(Utc::now()))
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 关闭两步验证：删除密钥与恢复码。
+pub async fn disable(pool: &sqlx::AnyPool, user_id: i64) -> Result<(), String> {
+    for sql in [
+        "DELETE FROM auth_recovery_code WHERE user_id = $1",
+        "DELETE FROM auth_totp WHERE user_id = $1",
+    ] {
+        sqlx::query(sql)
+            .bind(user_id)
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// 用验证码消费一个时间步；并发请求中只有一个能成功。
+async fn consume_step(pool: &sqlx::AnyPool, user_id: i64, step: i64) -> Result<bool, String> {
+    let r = sqlx::query(
+        "UPDATE auth_totp SET last_step = $2 WHERE user_id = $1 AND (last_step IS NULL OR last_step < $2)",
+    )
+    .bind(user_id)
+    .bind(step)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(r.rows_affected() > 0)
+}
+
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect();
+    Sha256::digest(normalized.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn generate_recovery_code() -> String {
+    const ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+    let mut rng = rand::thread_rng();
+    let mut part = || -> String {
+        (0..5)
+            .map(|_| ALPHABET[rng.gen_range(0..ALPHABET.len())] as char)
+            .collect()
+    };
+    format!("{}-{}", part(), part())
+}
+
+/// 重新生成恢复码（旧的全部作废），返回明文（仅此一次）。
+pub async fn regenerate_recovery_codes(
+    pool: &sqlx::AnyPool,
+    user_id: i64,
+) -> Result<Vec<String>, String> {
+    sqlx::query("DELETE FROM auth_recovery_code WHERE user_id = $1")
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    let sql = if is_pg(pool) {
+        "INSERT INTO auth_recovery_code (id, user_id, code_hash) VALUES (($1)::uuid, $2, $3)"
+    } else {
+        "INSERT INTO auth_recovery_code (id, user_id, code_hash) VALUES ($1, $2, $3)"
+    };
+    let mut codes = Vec::with_capacity(RECOVERY_CODE_COUNT);
+    for _ in 0..RECOVERY_CODE_COUNT {
+        let code = generate_recovery_code();
+        sqlx::query(sql)
+            .bind(Uuid::new_v4().to_string())
+            .bind(user_id)
+            .bind(hash_recovery_code(&code))
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        codes.push(code);
+    }
+    Ok(codes)
+}
+
+pub async fn remaining_recovery_codes(pool: &sqlx::AnyPool, user_id: i64) -> Result<i64, String> {
+    let row = sqlx::query(
+        "SELECT COUNT(*) as n FROM auth_recovery_code WHERE user_id = $1 AND used_at IS NULL",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(row.get::<i64, _>("n"))
+}
+
+async fn consume_recovery_code(
+    pool: &sqlx::AnyPool,
+    user_id: i64,
+    code: &str,
+) -> Result<bool, String> {
+    let sql = if is_pg(pool) {
+        "UPDATE auth_recovery_code SET used_at = ($3)::timestamptz \
+         WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL"
+    } else {
+        "UPDATE auth_recovery_code SET used_at = $3 \
+         WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL"
+    };
+    let r = sqlx::query(sql)
+        .bind(user_id)
+        .bind(hash_recovery_code(code))
+        .bind(format_utc(Utc::now()))
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(r.rows_affected() > 0)
+}
+
+/// 第二因素的校验方式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecondFactor {
+    Totp,
+    RecoveryCode,
+}
+
+/// 校验已启用的两步验证：6 位数字按 TOTP 校验，其余按恢复码校验（一次性）。
+pub async fn verify_second_factor(
+    pool: &sqlx::AnyPool,
+    user_id: i64,
+    code: &str,
+) -> Result<Option<SecondFactor>, String> {
+    let Some(row) = get(pool, user_id).await? else {
+        return Ok(None);
+    };
+    if !row.enabled {
+        return Ok(None);
+    }
+    let code = code.trim();
+    if code.len() == DIGITS as usize && code.chars().all(|c| c.is_ascii_digit()) {
+        let Some(step) = verify_code(&row.secret, code, Utc::now().timestamp(), row.last_step)
+        else {
+            return Ok(None);
+        };
+        return Ok(consume_step(pool, user_id, step)
+            .await?
+            .then_some(SecondFactor::Totp));
+    }
+    Ok(consume_recovery_code(pool, user_id, code)
+        .await?
+        .then_some(SecondFactor::RecoveryCode))
+}
+
+/// 注册确认：用尚未启用的密钥校验一次验证码，通过后启用。
+pub async fn confirm_enrollment(
+    pool: &sqlx::AnyPool,
+    user_id: i64,
+    code: &str,
+) -> Result<bool, String> {
+    let Some(row) = get(pool, user_id).await? else {
+        return Ok(false);
+    };
+    if row.enabled {
+        return Ok(false);
+    }
+    let Some(step) = verify_code(&row.secret, code, Utc::now().timestamp(), row.last_step) else {
+        return Ok(false);
+    };
+    if !consume_step(pool, user_id, step).await? {
+        return Ok(false);
+    }
+    mark_enabled(pool, user_id).await?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc6238_sha1_vectors_truncated_to_six_digits() {
+        // RFC 6238 附录 B 的 SHA-1 测试向量（8 位）取后 6 位。
+        let secret = b"12345678901234567890";
+        for (t, expected) in [
+            (59_i64, "287082"),
+            (1_111_111_109, "081804"),
+            (1_234_567_890, "005924"),
+            (2_000_000_000, "279037"),
+        ] {
+            assert_eq!(code_at(secret, current_step(t)), expected, "t={t}");
+        }
+    }
+
+    #[test]
+    fn base32_roundtrip_and_skew_window() {
+        let secret = base32_encode(b"12345678901234567890");
+        assert_eq!(secret, "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+        assert_eq!(
+            base32_decode(&secret.to_lowercase()).unwrap(),
+            b"12345678901234567890"
+        );
+
+        assert_eq!(verify_code(&secret, "287082", 59, None), Some(1));
+        assert_eq!(verify_code(&secret, "287082", 59 + 30, None), Some(1));
+        assert_eq!(verify_code(&secret, "287082", 59 + 90, None), None);
+        assert_eq!(verify_code(&secret, "287082", 59, Some(1)), None);
+        assert_eq!(verify_code(&secret, "28708", 59, None), None);
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+use serde::Serialize;
+
+use crate::index_series;
+
+/// 随二进制打包的休市日表（见 data/market_holidays.csv）。
+const BUNDLED_HOLIDAYS: &str = include_str!("../data/market_holidays.csv");
+
+/// 北京时间（+08:00），A 股/基金估值均以此判断“今天”。
+const CN_OFFSET_SECONDS: i64 = 8 * 60 * 60;
+
+/// 向前/向后查找交易日的最大步数，防止数据异常时死循环。
+const MAX_SCAN_DAYS: i64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Market {
+    Cn,
+    Hk,
+    Us,
+    /// 投资港股的 QDII：境内与香港同时开市才视为交易日。
+    QdiiHk,
+    /// 投资美股的 QDII：境内与美国同时开市才视为交易日。
+    QdiiUs,
+}
+
+impl Market {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "cn" | "a" | "ashare" | "sse" | "szse" => Some(Market::Cn),
+            "hk" | "hkex" => Some(Market::Hk),
+            "us" | "nyse" | "nasdaq" => Some(Market::Us),
+            "qdii_hk" => Some(Market::QdiiHk),
+            "qdii_us" | "qdii" => Some(Market::QdiiUs),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Market::Cn => "cn",
+            Market::Hk => "hk",
+            Market::Us => "us",
+            Market::QdiiHk => "qdii_hk",
+            Market::QdiiUs => "qdii_us",
+        }
+    }
+
+    /// 组成该日历的交易所（QDII = 境内 + 境外）。
+    fn exchanges(&self) -> &'static [&'static str] {
+        match self {
+            Market::Cn => &["cn"],
+            Market::Hk => &["hk"],
+            Market::Us => &["us"],
+            Market::QdiiHk => &["cn", "hk"],
+            Market::QdiiUs => &["cn", "us"],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct ExchangeHolidays {
+    dates: BTreeSet<NaiveDate>,
+    years: BTreeSet<i32>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TradingCalendar {
+    exchanges: HashMap<String, ExchangeHolidays>,
+}
+
+impl TradingCalendar {
+    /// 解析 `market,date,name` 格式的休市日表；`#` 开头为注释，首行表头可省略。
+    pub fn from_csv(text: &str) -> Result<Self, String> {
+        let mut exchanges: HashMap<String, ExchangeHolidays> = HashMap::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with("market,") {
+                continue;
+            }
+            let mut parts = line.splitn(3, ',');
+            let market = parts.next().unwrap_or("").trim().to_ascii_lowercase();
+            let date_raw = parts.next().unwrap_or("").trim();
+            if market.is_empty() {
+                return Err(format!("line {}: market 为空", i + 1));
+            }
+            let date = NaiveDate::parse_from_str(date_raw, "%Y-%m-%d")
+                .map_err(|e| format!("line {}: invalid date {date_raw}: {e}", i + 1))?;
+            let e = exchanges.entry(market).or_default();
+            e.dates.insert(date);
+            e.years.insert(date.year());
+        }
+        Ok(Self { exchanges })
+    }
+
+    /// 该日期是否落在休市日表的覆盖年份内（覆盖外只能按周末规则推断）。
+    pub fn is_covered(&self, market: Market, d: NaiveDate) -> bool {
+        market.exchanges().iter().all(|ex| {
+            self.exchanges
+                .get(*ex)
+                .is_some_and(|h| h.years.contains(&d.year()))
+        })
+    }
+
+    pub fn is_trading_day(&self, market: Market, d: NaiveDate) -> bool {
+        if matches!(d.weekday(), Weekday::Sat | Weekday::Sun) {
+            return false;
+        }
+        market.exchanges().iter().all(|ex| {
+            !self
+                .exchanges
+                .get(*ex)
+                .is_some_and(|h| h.dates.contains(&d))
+        })
+    }
+
+    /// d 之后（不含 d）的第一个交易日。
+    pub fn next_trading_day(&self, market: Market, d: NaiveDate) -> NaiveDate {
+        self.scan(market, d, 1)
+    }
+
+    /// d 之前（不含 d）的最近一个交易日。
+    pub fn prev_trading_day(&self, market: Market, d: NaiveDate) -> NaiveDate {
+        self.scan(market, d, -1)
+    }
+
+    /// d 当天若为交易日则返回 d，否则向前找最近的交易日。
+    pub fn last_trading_day_on_or_before(&self, market: Market, d: NaiveDate) -> NaiveDate {
+        if self.is_trading_day(market, d) {
+            d
+        } else {
+            self.prev_trading_day(market, d)
+        }
+    }
+
+    /// 从 d 起偏移 n 个交易日（n>0 向后、n<0 向前、n=0 返回 d 本身）。
+    /// d 非交易日时，+1 即下一个交易日、-1 即上一个交易日。
+    pub fn add_trading_days(&self, market: Market, d: NaiveDate, n: i64) -> NaiveDate {
+        let mut cur = d;
+        let step = n.signum();
+        for _ in 0..n.abs() {
+            cur = self.scan(market, cur, step);
+        }
+        cur
+    }
+
+    /// [start, end] 内的全部交易日（升序）。
+    pub fn trading_days_between(
+        &self,
+        market: Market,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Vec<NaiveDate> {
+        let mut out = Vec::new();
+        let mut d = start;
+        while d <= end {
+            if self.is_trading_day(market, d) {
+                out.push(d);
+            }
+            let Some(next) = d.succ_opt() else { break };
+            d = next;
+        }
+        out
+    }
+
+    fn scan(&self, market: Market, d: NaiveDate, step: i64) -> NaiveDate {
+        let mut cur = d;
+        for _ in 0..MAX_SCAN_DAYS {
+            cur += Duration::days(step);
+            if self.is_trading_day(market, cur) {
+                return cur;
+            }
+        }
+        cur
+    }
+}
+
+/// 进程内共享的日历（由打包的休市日表构建）。
+pub fn bundled() -> &'static TradingCalendar {
+    static CAL: OnceLock<TradingCalendar> = OnceLock::new();
+    CAL.get_or_init(|| {
+        TradingCalendar::from_csv(BUNDLED_HOLIDAYS).unwrap_or_else(|e| {
+            tracing::error!(error = %e, "bundled market_holidays.csv invalid; fallback to weekday-only calendar");
+            TradingCalendar::default()
+        })
+    })
+}
+
+pub fn is_trading_day(market: Market, d: NaiveDate) -> bool {
+    bundled().is_trading_day(market, d)
+}
+
+/// 北京时间下的“今天”。
+pub fn cn_today(now_utc: DateTime<Utc>) -> NaiveDate {
+    (now_utc + Duration::seconds(CN_OFFSET_SECONDS)).date_naive()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CalendarVerifyReport {
+    pub market: String,
+    pub index_code: String,
+    pub source_name: String,
+    /// 实际参与比对的区间（与指数行情有数据的区间取交集）。
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub checked_days: i64,
+    /// 日历判为交易日，但指数没有当日行情。
+    pub missing_in_index: Vec<String>,
+    /// 指数有当日行情，但日历判为休市。
+    pub unexpected_in_index: Vec<String>,
+}
+
+/// 用 index_daily_price 中某指数的实际交易日校验日历（只比对指数有数据的区间）。
+pub async fn verify_against_index(
+    pool: &sqlx::AnyPool,
+    calendar: &TradingCalendar,
+    market: Market,
+    index_code: &str,
+    source_name: &str,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<CalendarVerifyReport, String> {
+    let series =
+        index_series::load_index_close_series(pool, index_code, source_name, start, end).await?;
+    let index_days: BTreeSet<NaiveDate> = series.into_iter().map(|(d, _)| d).collect();
+
+    let mut report = CalendarVerifyReport {
+        market: market.as_str().to_string(),
+        index_code: index_code.trim().to_string(),
+        source_name: source_name.trim().to_string(),
+        start_date: None,
+        end_date: None,
+        checked_days: 0,
+        missing_in_index: Vec::new(),
+        unexpected_in_index: Vec::new(),
+    };
+    let (Some(first), Some(last)) = (index_days.first(), index_days.last()) else {
+        return Ok(report);
+    };
+    report.start_date = Some(first.to_string());
+    report.end_date = Some(last.to_string());
+
+    let mut d = *first;
+    while d <= *last {
+        report.checked_days += 1;
+        let expected = calendar.is_trading_day(market, d);
+        let actual = index_days.contains(&d);
+        if expected && !actual {
+            report.missing_in_index.push(d.to_string());
+        } else if !expected && actual {
+            report.unexpected_in_index.push(d.to_string());
+        }
+        let Some(next) = d.succ_opt() else { break };
+        d = next;
+    }
+    Ok(report)
+}
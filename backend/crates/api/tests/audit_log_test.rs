@@ -0,0 +1,423 @@
+use std::net::SocketAddr;
+
+use axum::{body::Body, extract::ConnectInfo, http::Request};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use api::state::AppState;
+
+/// (action, actor_user_id, target_id, ip)
+type AuditRow = (String, Option<i64>, Option<String>, Option<String>);
+
+struct TempDirEnv {
+    key: &'static str,
+    path: std::path::PathBuf,
+    old: Option<std::ffi::OsString>,
+}
+
+impl TempDirEnv {
+    fn new() -> Self {
+        let mut path = std::env::temp_dir();
+        path.push(format!("fundval-test-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&path).expect("create temp dir");
+
+        let key = "FUNDVAL_DATA_DIR";
+        let old = std::env::var_os(key);
+        unsafe {
+            std::env::set_var(key, &path);
+        }
+
+        Self { key, path, old }
+    }
+}
+
+impl Drop for TempDirEnv {
+    fn drop(&mut self) {
+        match self.old.take() {
+            Some(v) => unsafe {
+                std::env::set_var(self.key, v);
+            },
+            None => unsafe {
+                std::env::remove_var(self.key);
+            },
+        }
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+async fn body_json(res: axum::response::Response) -> Value {
+    let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+        .await
+        .expect("read body");
+    serde_json::from_slice(&bytes).unwrap_or(Value::Null)
+}
+
+#[tokio::test]
+async fn mutating_handlers_write_append_only_audit_events() {
+    let _env = TempDirEnv::new();
+    sqlx::any::install_default_drivers();
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+
+    sqlx::query(
+        r#"
+        INSERT INTO auth_user (id, password, is_superuser, username, is_staff, is_active)
+        VALUES (1, 'x', 0, 'admin', 1, 1), (2, 'x', 0, 'u', 0, 1)
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .expect("seed users");
+
+    let config = api::config::ConfigStore::load();
+    // 两层代理：本机 Next.js 与内网负载均衡；链路中最右侧的外部地址才是客户端。
+    config.set_string("trusted_proxies", Some("127.0.0.1,10.0.0.0/8".to_string()));
+    let jwt = api::jwt::JwtService::from_secret("test-secret");
+    let state = AppState::new(
+        Some(pool.clone()),
+        config,
+        jwt,
+        api::db::DatabaseKind::Sqlite,
+    );
+    state
+        .config()
+        .set_string("tushare_token", Some("tok-abcdef123456".to_string()));
+    let admin = state.jwt().issue_access_token("1");
+    let user = state.jwt().issue_access_token("2");
+    let app = api::app(state);
+    let call = |method: &str, uri: &str, token: &str, body: Option<Value>| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Authorization", format!("Bearer {token}"))
+            .header("Content-Type", "application/json")
+            .header("X-Forwarded-For", "203.0.113.7, 10.0.0.1")
+            .extension(ConnectInfo(
+                "127.0.0.1:40000".parse::<SocketAddr>().unwrap(),
+            ))
+            .header("User-Agent", "audit-test/1.0")
+            .body(match body {
+                Some(v) => Body::from(v.to_string()),
+                None => Body::empty(),
+            })
+            .unwrap()
+    };
+
+    let res = app
+        .clone()
+        .oneshot(call(
+            "PUT",
+            "/api/admin/crawl/config",
+            &admin,
+            Some(json!({ "crawl_run_max_jobs": 7 })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+
+    let res = app
+        .clone()
+        .oneshot(call(
+            "PUT",
+            "/api/settings/tushare_token",
+            &admin,
+            Some(json!({ "token": "new-token-987654" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+
+    let res = app
+        .clone()
+        .oneshot(call(
+            "POST",
+            "/api/accounts",
+            &user,
+            Some(json!({ "name": "主账户" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+    let account_id = body_json(res).await["id"].as_str().unwrap().to_string();
+    let res = app
+        .clone()
+        .oneshot(call(
+            "DELETE",
+            &format!("/api/accounts/{account_id}"),
+            &user,
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+
+    // 只有管理员能查询审计日志。
+    let res = app
+        .clone()
+        .oneshot(call("GET", "/api/admin/audit-events", &user, None))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 403);
+
+    let res = app
+        .clone()
+        .oneshot(call(
+            "GET",
+            "/api/admin/audit-events?action=crawl_config.update",
+            &admin,
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let items = body_json(res).await["items"].as_array().unwrap().clone();
+    assert_eq!(items.len(), 1);
+    let ev = &items[0];
+    assert_eq!(ev["actor_user_id"], 1);
+    assert_eq!(ev["target_type"], "config");
+    assert_eq!(ev["ip"], "203.0.113.7");
+    assert_eq!(ev["user_agent"], "audit-test/1.0");
+    assert_eq!(ev["before"]["crawl_run_max_jobs"], 20);
+    assert_eq!(ev["after"]["crawl_run_max_jobs"], 7);
+
+    // 令牌只以脱敏形式出现在审计记录中。
+    let res = app
+        .clone()
+        .oneshot(call(
+            "GET",
+            "/api/admin/audit-events?target_id=tushare_token",
+            &admin,
+            None,
+        ))
+        .await
+        .unwrap();
+    let text = body_json(res).await.to_string();
+    assert!(text.contains("settings.tushare_token.update"));
+    assert!(!text.contains("new-token-987654"));
+    assert!(!text.contains("tok-abcdef123456"));
+
+    let res = app
+        .clone()
+        .oneshot(call(
+            "GET",
+            "/api/admin/audit-events?action=accounts.*&actor=2",
+            &admin,
+            None,
+        ))
+        .await
+        .unwrap();
+    let items = body_json(res).await["items"].as_array().unwrap().clone();
+    let mut actions: Vec<&str> = items
+        .iter()
+        .map(|v| v["action"].as_str().unwrap())
+        .collect();
+    actions.sort();
+    assert_eq!(actions, vec!["accounts.create", "accounts.delete"]);
+    let deleted = items
+        .iter()
+        .find(|v| v["action"] == "accounts.delete")
+        .unwrap();
+    assert_eq!(deleted["target_id"], account_id);
+    assert_eq!(deleted["before"]["name"], "主账户");
+
+    for (q, n) in [
+        ("target_type=account", 2),
+        ("actor=1", 2),
+        ("since=2000-01-01&until=2999-12-31", 4),
+        ("since=2999-01-01", 0),
+        ("limit=1", 1),
+    ] {
+        let res = app
+            .clone()
+            .oneshot(call(
+                "GET",
+                &format!("/api/admin/audit-events?{q}"),
+                &admin,
+                None,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200, "{q}");
+        assert_eq!(
+            body_json(res).await["items"].as_array().unwrap().len(),
+            n,
+            "{q}"
+        );
+    }
+    let res = app
+        .clone()
+        .oneshot(call(
+            "GET",
+            "/api/admin/audit-events?since=yesterday",
+            &admin,
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    // 表只允许追加。
+    assert!(
+        sqlx::query("UPDATE audit_event SET action = 'x'")
+            .execute(&pool)
+            .await
+            .is_err()
+    );
+    assert!(
+        sqlx::query("DELETE FROM audit_event")
+            .execute(&pool)
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn registration_and_watchlist_changes_are_audited() {
+    let _env = TempDirEnv::new();
+    sqlx::any::install_default_drivers();
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+
+    sqlx::query(
+        r#"
+        INSERT INTO fund (id, fund_code, fund_name, fund_type, created_at, updated_at)
+        VALUES ('fund-1', '000001', '基金一号', '股票型', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .expect("seed fund");
+
+    let config = api::config::ConfigStore::load();
+    config.set_allow_register(true);
+    let state = AppState::new(
+        Some(pool.clone()),
+        config,
+        api::jwt::JwtService::from_secret("test-secret"),
+        api::db::DatabaseKind::Sqlite,
+    );
+    let app = api::app(state);
+    let call = |method: &str, uri: &str, token: Option<&str>, body: Option<Value>| {
+        let mut b = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Content-Type", "application/json")
+            .extension(ConnectInfo(
+                "198.51.100.4:5000".parse::<SocketAddr>().unwrap(),
+            ));
+        if let Some(t) = token {
+            b = b.header("Authorization", format!("Bearer {t}"));
+        }
+        b.body(match body {
+            Some(v) => Body::from(v.to_string()),
+            None => Body::empty(),
+        })
+        .unwrap()
+    };
+
+    let res = app
+        .clone()
+        .oneshot(call(
+            "POST",
+            "/api/users/register",
+            None,
+            Some(json!({
+                "username": "carol",
+                "password": "long-enough-pw",
+                "password_confirm": "long-enough-pw",
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+    let registered = body_json(res).await;
+    let token = registered["access_token"].as_str().unwrap().to_string();
+    let user_id = registered["user"]["id"].as_str().unwrap().to_string();
+
+    let res = app
+        .clone()
+        .oneshot(call(
+            "POST",
+            "/api/watchlists",
+            Some(&token),
+            Some(json!({ "name": "观察" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+    let wl = body_json(res).await["id"].as_str().unwrap().to_string();
+    for (method, uri, body, status) in [
+        (
+            "PATCH",
+            format!("/api/watchlists/{wl}"),
+            Some(json!({ "name": "重点" })),
+            200,
+        ),
+        (
+            "POST",
+            format!("/api/watchlists/{wl}/items"),
+            Some(json!({ "fund_code": "000001" })),
+            201,
+        ),
+        (
+            "PUT",
+            format!("/api/watchlists/{wl}/reorder"),
+            Some(json!({ "fund_codes": ["000001"] })),
+            200,
+        ),
+        (
+            "DELETE",
+            format!("/api/watchlists/{wl}/items/000001"),
+            None,
+            204,
+        ),
+        ("DELETE", format!("/api/watchlists/{wl}"), None, 204),
+    ] {
+        let res = app
+            .clone()
+            .oneshot(call(method, &uri, Some(&token), body))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), status, "{method} {uri}");
+    }
+
+    let rows: Vec<AuditRow> = sqlx::query_as(
+        "SELECT action, actor_user_id, target_id, ip FROM audit_event ORDER BY action",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    let actions: Vec<&str> = rows.iter().map(|r| r.0.as_str()).collect();
+    assert_eq!(
+        actions,
+        vec![
+            "users.register",
+            "watchlists.create",
+            "watchlists.delete",
+            "watchlists.items.add",
+            "watchlists.items.remove",
+            "watchlists.items.reorder",
+            "watchlists.update",
+        ]
+    );
+    let uid: i64 = user_id.parse().unwrap();
+    assert!(rows.iter().all(|r| r.1 == Some(uid)));
+    assert!(
+        rows[1..]
+            .iter()
+            .all(|r| r.2.as_deref() == Some(wl.as_str()))
+    );
+    // 未经受信任代理的直连请求：记录连接地址。
+    assert!(rows.iter().all(|r| r.3.as_deref() == Some("198.51.100.4")));
+}
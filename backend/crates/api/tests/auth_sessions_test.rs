@@ -0,0 +1,268 @@
+use axum::{body::Body, http::Request};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+use api::state::AppState;
+
+async fn body_json(res: axum::response::Response) -> Value {
+    let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+        .await
+        .expect("read body");
+    serde_json::from_slice(&bytes).unwrap_or(Value::Null)
+}
+
+fn call(method: &str, uri: &str, token: Option<&str>, body: Option<Value>) -> Request<Body> {
+    let mut b = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Content-Type", "application/json")
+        .header("User-Agent", "session-test/1.0");
+    if let Some(t) = token {
+        b = b.header("Authorization", format!("Bearer {t}"));
+    }
+    b.body(match body {
+        Some(v) => Body::from(v.to_string()),
+        None => Body::empty(),
+    })
+    .unwrap()
+}
+
+async fn login(app: &axum::Router, password: &str) -> (u16, Value) {
+    let res = app
+        .clone()
+        .oneshot(call(
+            "POST",
+            "/api/auth/login",
+            None,
+            Some(json!({ "username": "alice", "password": password })),
+        ))
+        .await
+        .unwrap();
+    let status = res.status().as_u16();
+    (status, body_json(res).await)
+}
+
+async fn refresh(app: &axum::Router, token: &str) -> (u16, Value) {
+    let res = app
+        .clone()
+        .oneshot(call(
+            "POST",
+            "/api/auth/refresh",
+            None,
+            Some(json!({ "refresh_token": token })),
+        ))
+        .await
+        .unwrap();
+    let status = res.status().as_u16();
+    (status, body_json(res).await)
+}
+
+async fn me_status(app: &axum::Router, token: &str) -> u16 {
+    app.clone()
+        .oneshot(call("GET", "/api/auth/me", Some(token), None))
+        .await
+        .unwrap()
+        .status()
+        .as_u16()
+}
+
+#[tokio::test]
+async fn refresh_tokens_rotate_and_sessions_can_be_revoked() {
+    sqlx::any::install_default_drivers();
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+
+    let password_hash = api::django_password::hash_password("pw12345678");
+    sqlx::query(
+        r#"
+        INSERT INTO auth_user (id, password, is_superuser, username, email, is_staff, is_active, date_joined)
+        VALUES (1, $1, 0, 'alice', '', 0, 1, CURRENT_TIMESTAMP)
+        "#,
+    )
+    .bind(&password_hash)
+    .execute(&pool)
+    .await
+    .expect("seed user");
+
+    let config = api::config::ConfigStore::load();
+    let jwt = api::jwt::JwtService::from_secret("test-secret");
+    let state = AppState::new(
+        Some(pool.clone()),
+        config,
+        jwt,
+        api::db::DatabaseKind::Sqlite,
+    );
+    let legacy = state.jwt().issue_access_token("1");
+    let app = api::app(state.clone());
+
+    // 两次登录 = 两个会话。
+    let (code, a) = login(&app, "pw12345678").await;
+    assert_eq!(code, 200);
+    let (_, b) = login(&app, "pw12345678").await;
+    let a_access = a["access_token"].as_str().unwrap().to_string();
+    let a_refresh = a["refresh_token"].as_str().unwrap().to_string();
+    let b_access = b["access_token"].as_str().unwrap().to_string();
+    let b_refresh = b["refresh_token"].as_str().unwrap().to_string();
+
+    // 轮换：新的一对令牌可用，旧刷新令牌再次出示即吊销整个会话。
+    let (code, rotated) = refresh(&app, &a_refresh).await;
+    assert_eq!(code, 200);
+    let a2_access = rotated["access_token"].as_str().unwrap().to_string();
+    let a2_refresh = rotated["refresh_token"].as_str().unwrap().to_string();
+    assert_ne!(a2_refresh, a_refresh);
+    assert_eq!(me_status(&app, &a2_access).await, 200);
+
+    let (code, v) = refresh(&app, &a_refresh).await;
+    assert_eq!(code, 400);
+    assert_eq!(v["error"], "Invalid refresh token");
+    assert_eq!(refresh(&app, &a2_refresh).await.0, 400);
+    assert_eq!(me_status(&app, &a_access).await, 401);
+    assert_eq!(me_status(&app, &a2_access).await, 401);
+    let reason: String =
+        sqlx::query_scalar("SELECT revoke_reason FROM auth_session WHERE revoked_at IS NOT NULL")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(reason, "reuse_detected");
+    let reuse_events: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM audit_event WHERE action = 'auth.session.reuse_detected' AND actor_user_id = 1",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(reuse_events, 1);
+
+    // 会话 b 不受影响；列表只含未吊销的会话并标出当前会话。
+    assert_eq!(me_status(&app, &b_access).await, 200);
+    let (_, c) = login(&app, "pw12345678").await;
+    let c_access = c["access_token"].as_str().unwrap().to_string();
+    let res = app
+        .clone()
+        .oneshot(call("GET", "/api/auth/sessions", Some(&b_access), None))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let items = body_json(res).await["items"].as_array().unwrap().clone();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items.iter().filter(|s| s["current"] == true).count(), 1);
+    assert!(items.iter().all(|s| s["user_agent"] == "session-test/1.0"));
+    let c_sid = items.iter().find(|s| s["current"] == false).unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    // 从会话 b 吊销会话 c。
+    for (sid, code) in [
+        ("not-a-uuid", 404),
+        (c_sid.as_str(), 204),
+        (c_sid.as_str(), 404),
+    ] {
+        let res = app
+            .clone()
+            .oneshot(call(
+                "DELETE",
+                &format!("/api/auth/sessions/{sid}"),
+                Some(&b_access),
+                None,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), code, "{sid}");
+    }
+    assert_eq!(me_status(&app, &c_access).await, 401);
+
+    // 退出登录只需刷新令牌（访问令牌可能已过期）。
+    let (_, d) = login(&app, "pw12345678").await;
+    let d_access = d["access_token"].as_str().unwrap().to_string();
+    let d_refresh = d["refresh_token"].as_str().unwrap().to_string();
+    let res = app
+        .clone()
+        .oneshot(call(
+            "POST",
+            "/api/auth/logout",
+            None,
+            Some(json!({ "refresh_token": d_refresh })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+    assert_eq!(me_status(&app, &d_access).await, 401);
+    assert_eq!(refresh(&app, &d_refresh).await.0, 400);
+    let res = app
+        .clone()
+        .oneshot(call("POST", "/api/auth/logout", None, Some(json!({}))))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+
+    // 修改密码：所有令牌失效，包括不绑定会话的令牌与当前请求所用的令牌。
+    let (_, e) = login(&app, "pw12345678").await;
+    let e_refresh = e["refresh_token"].as_str().unwrap().to_string();
+    assert_eq!(me_status(&app, &legacy).await, 200);
+    let res = app
+        .clone()
+        .oneshot(call(
+            "POST",
+            "/api/auth/tokens",
+            Some(&b_access),
+            Some(json!({ "name": "nightly", "scopes": ["read"] })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+    let pat = body_json(res).await["token"].as_str().unwrap().to_string();
+    assert_eq!(me_status(&app, &pat).await, 200);
+    let res = app
+        .clone()
+        .oneshot(call(
+            "PUT",
+            "/api/auth/password",
+            Some(&b_access),
+            Some(json!({ "old_password": "pw12345678", "new_password": "pw87654321" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(me_status(&app, &b_access).await, 401);
+    assert_eq!(me_status(&app, &legacy).await, 401);
+    assert_eq!(refresh(&app, &b_refresh).await.0, 400);
+    assert_eq!(me_status(&app, &pat).await, 401);
+    let live_tokens: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM auth_personal_token WHERE revoked_at IS NULL")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(live_tokens, 0);
+    assert_eq!(refresh(&app, &e_refresh).await.0, 400);
+    let active: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM auth_session WHERE revoked_at IS NULL")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(active, 0);
+
+    // 吊销记录可从库中重新加载（多副本场景）。
+    let fresh = AppState::new(
+        Some(pool.clone()),
+        api::config::ConfigStore::load(),
+        api::jwt::JwtService::from_secret("test-secret"),
+        api::db::DatabaseKind::Sqlite,
+    );
+    api::auth_session::sync_revocations(&fresh).await.unwrap();
+    let fresh_app = api::app(fresh);
+    assert_eq!(me_status(&fresh_app, &b_access).await, 401);
+    assert_eq!(me_status(&fresh_app, &legacy).await, 401);
+
+    // 新密码重新登录后正常使用。
+    let (code, f) = login(&app, "pw87654321").await;
+    assert_eq!(code, 200);
+    assert_eq!(
+        me_status(&app, f["access_token"].as_str().unwrap()).await,
+        200
+    );
+}
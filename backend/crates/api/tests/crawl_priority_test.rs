@@ -0,0 +1,281 @@
+use axum::{body::Body, http::Request};
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use serde_json::Value;
+use sqlx::Row;
+use tower::ServiceExt;
+
+use api::crawl::priority::{self, Budget, DemandSignals};
+use api::state::AppState;
+
+fn d(s: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
+
+/// 2025-06-30（周一）北京时间 22:00：当日净值应已公布。
+fn monday_evening() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 6, 30, 14, 0, 0).unwrap()
+}
+
+async fn body_json(res: axum::response::Response) -> Value {
+    let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+        .await
+        .expect("read body");
+    serde_json::from_slice(&bytes).expect("json")
+}
+
+#[test]
+fn score_combines_demand_views_staleness_and_budget() {
+    let now = monday_evening();
+    let fresh = DemandSignals {
+        latest_nav_date: Some(d("2025-06-30")),
+        ..Default::default()
+    };
+
+    // 无人关注：与全量轮询的底分一致，间隔 6 小时。
+    let s = priority::score_job("nav_history_sync", &fresh, &Budget::unlimited(), now);
+    assert_eq!(s.priority, 10);
+    assert_eq!(s.delay_seconds, 6 * 60 * 60);
+
+    // 自选 > 持有，与原先的分层优先级对齐。
+    let watched = DemandSignals {
+        watchers: 1,
+        ..fresh.clone()
+    };
+    let held = DemandSignals {
+        holders: 1,
+        ..fresh.clone()
+    };
+    let budget = Budget::unlimited();
+    assert_eq!(
+        priority::score_job("estimate_sync", &watched, &budget, now).priority,
+        100
+    );
+    let s = priority::score_job("estimate_sync", &held, &budget, now);
+    assert_eq!(s.priority, 80);
+    assert_eq!(s.delay_seconds, 5 * 60);
+
+    // 多人关注 + 最近一小时被查看。
+    let popular = DemandSignals {
+        watchers: 2,
+        holders: 3,
+        last_viewed_at: Some(now - Duration::minutes(10)),
+        ..fresh.clone()
+    };
+    let s = priority::score_job("estimate_sync", &popular, &budget, now);
+    assert_eq!(s.priority, 100 + 20 + 20);
+    let names: Vec<&str> = s.components.iter().map(|c| c.name).collect();
+    assert_eq!(names, vec!["base", "demand", "audience", "recent_view"]);
+
+    // 净值落后两个交易日（6/27、6/30）；QDII 宽限一天。
+    let stale = DemandSignals {
+        latest_nav_date: Some(d("2025-06-26")),
+        ..Default::default()
+    };
+    assert_eq!(priority::stale_trading_days(&stale, now), 2);
+    let s = priority::score_job("nav_history_sync", &stale, &budget, now);
+    assert_eq!(s.priority, 10 + 30);
+    assert!(s.components.iter().any(|c| c.name == "staleness"));
+    let qdii = DemandSignals {
+        latest_nav_date: Some(d("2025-06-27")),
+        is_qdii: true,
+        ..Default::default()
+    };
+    assert_eq!(priority::stale_trading_days(&qdii, now), 0);
+    // 估值任务不看净值滞后。
+    assert_eq!(
+        priority::score_job("estimate_sync", &stale, &budget, now).priority,
+        5
+    );
+
+    // 盘中（北京时间 10:00）还不到公布时间：预期仍是上一交易日。
+    let morning = Utc.with_ymd_and_hms(2025, 6, 30, 2, 0, 0).unwrap();
+    assert_eq!(priority::expected_nav_date(morning, false), d("2025-06-27"));
+
+    // 预算只剩 20%：间隔 ×4。
+    let tight = Budget {
+        daily_limit: 1000,
+        used: 800,
+    };
+    let s = priority::score_job("estimate_sync", &held, &tight, now);
+    assert_eq!(s.budget_factor, 4);
+    assert_eq!(s.delay_seconds, 4 * 5 * 60);
+    assert_eq!(s.priority, 80);
+}
+
+#[tokio::test]
+async fn rescore_and_admin_schedule_explain() {
+    sqlx::any::install_default_drivers();
+
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+
+    sqlx::query(
+        r#"
+        INSERT INTO auth_user (id, password, is_superuser, username, is_staff, is_active)
+        VALUES (1, 'x', 0, 'u', 0, 1), (2, 'x', 1, 'admin', 1, 1)
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .expect("seed users");
+
+    for (id, code) in [("f-a", "A"), ("f-b", "B"), ("f-c", "C"), ("f-d", "D")] {
+        sqlx::query(
+            r#"
+            INSERT INTO fund (id, fund_code, fund_name, fund_type, latest_nav_date, created_at, updated_at)
+            VALUES ($1, $2, $3, '股票型', '2099-01-01', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            "#,
+        )
+        .bind(id)
+        .bind(code)
+        .bind(format!("fund-{code}"))
+        .execute(&pool)
+        .await
+        .expect("seed fund");
+    }
+
+    // A：自选；B：持有；C：只被查看过；D：曾被自选（优先级偏高）但已无人关注。
+    sqlx::query("INSERT INTO watchlist (id, user_id, name) VALUES ('wl-1', 1, '自选')")
+        .execute(&pool)
+        .await
+        .expect("watchlist");
+    sqlx::query("INSERT INTO watchlist_item (id, watchlist_id, fund_id, \"order\") VALUES ('wli-1','wl-1','f-a',0)")
+        .execute(&pool)
+        .await
+        .expect("watchlist item");
+    sqlx::query(
+        "INSERT INTO account (id, user_id, name, is_default) VALUES ('acc-1', 1, '默认', 1)",
+    )
+    .execute(&pool)
+    .await
+    .expect("account");
+    sqlx::query(
+        "INSERT INTO position (id, account_id, fund_id, holding_share) VALUES ('pos-1','acc-1','f-b', 100)",
+    )
+    .execute(&pool)
+    .await
+    .expect("position");
+    priority::record_fund_view(&pool, "C")
+        .await
+        .expect("record view");
+    priority::record_fund_view(&pool, "C")
+        .await
+        .expect("record view again");
+    let views: i64 = sqlx::query("SELECT view_count FROM fund_view_stat WHERE fund_code = 'C'")
+        .fetch_one(&pool)
+        .await
+        .expect("view stat")
+        .get("view_count");
+    assert_eq!(views, 2);
+
+    for (id, code, prio) in [
+        ("j-a", "A", 5),
+        ("j-b", "B", 5),
+        ("j-c", "C", 5),
+        ("j-d", "D", 100),
+    ] {
+        sqlx::query(
+            r#"
+            INSERT INTO crawl_job (
+              id, job_type, fund_code, source_name, priority, not_before, status, attempt, last_ok_at, created_at, updated_at
+            ) VALUES (
+              $1, 'estimate_sync', $2, 'tiantian', $3, DATETIME(CURRENT_TIMESTAMP, '+1 day'), 'queued', 0,
+              DATETIME(CURRENT_TIMESTAMP, '-1 minute'), CURRENT_TIMESTAMP, CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .bind(id)
+        .bind(code)
+        .bind(prio)
+        .execute(&pool)
+        .await
+        .expect("seed job");
+    }
+
+    let updated = priority::rescore_jobs(&pool, "tiantian", &Budget::unlimited(), Utc::now(), 100)
+        .await
+        .expect("rescore");
+    assert_eq!(updated, 4);
+
+    let rows = sqlx::query(
+        "SELECT fund_code, priority, CAST(not_before AS TEXT) as not_before FROM crawl_job ORDER BY fund_code",
+    )
+    .fetch_all(&pool)
+    .await
+    .expect("select jobs");
+    let got: Vec<(String, i64)> = rows
+        .iter()
+        .map(|r| (r.get::<String, _>("fund_code"), r.get::<i64, _>("priority")))
+        .collect();
+    assert_eq!(
+        got,
+        vec![
+            ("A".to_string(), 100),
+            ("B".to_string(), 80),
+            ("C".to_string(), 25),
+            ("D".to_string(), 5),
+        ]
+    );
+    // 关注度上升后，下次执行从“明天”提前到 last_ok_at + 2 分钟。
+    let not_before_a: String = rows[0].get("not_before");
+    assert!(
+        !not_before_a.starts_with(
+            &(Utc::now() + Duration::days(1))
+                .format("%Y-%m-%d")
+                .to_string()
+        )
+    );
+
+    // 再跑一轮没有变化。
+    let updated = priority::rescore_jobs(&pool, "tiantian", &Budget::unlimited(), Utc::now(), 100)
+        .await
+        .expect("rescore again");
+    assert_eq!(updated, 0);
+
+    let config = api::config::ConfigStore::load();
+    let jwt = api::jwt::JwtService::from_secret("test-secret");
+    let state = AppState::new(Some(pool), config, jwt, api::db::DatabaseKind::Sqlite);
+    let user_token = state.jwt().issue_access_token("1");
+    let admin_token = state.jwt().issue_access_token("2");
+    let app = api::app(state);
+
+    let get = |uri: &str, token: &str| {
+        Request::builder()
+            .uri(uri)
+            .header("authorization", format!("Bearer {token}"))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let res = app
+        .clone()
+        .oneshot(get("/api/admin/crawl/schedule", &user_token))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 403);
+
+    let res = app
+        .clone()
+        .oneshot(get(
+            "/api/admin/crawl/schedule?fund_code=A&source=tiantian",
+            &admin_token,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let v = body_json(res).await;
+    assert_eq!(v["source"], "tiantian");
+    let jobs = v["jobs"].as_array().unwrap();
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0]["fund_code"], "A");
+    assert_eq!(jobs[0]["score"]["priority"], 100);
+    assert_eq!(jobs[0]["score"]["delay_seconds"], 120);
+    assert_eq!(jobs[0]["score"]["components"][1]["name"], "demand");
+    assert!(jobs[0]["next_due_at"].is_string());
+}
@@ -0,0 +1,227 @@
+use axum::{body::Body, http::Request};
+use chrono::{NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
+use serde_json::Value;
+use tower::ServiceExt;
+
+use api::intraday::{FundIntraday, IntradayPoint, aggregate_intraday_pnl};
+use api::state::AppState;
+
+async fn body_json(res: axum::response::Response) -> Value {
+    let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+        .await
+        .expect("read body");
+    serde_json::from_slice(&bytes).expect("json")
+}
+
+fn dt(s: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
+}
+
+fn dec(s: &str) -> Decimal {
+    s.parse().unwrap()
+}
+
+fn point(t: &str, nav: &str, growth: Option<&str>) -> IntradayPoint {
+    IntradayPoint {
+        estimate_time: dt(t),
+        estimate_nav: dec(nav),
+        estimate_growth: growth.map(dec),
+    }
+}
+
+#[test]
+fn aggregate_forward_fills_and_uses_growth_as_base() {
+    let funds = vec![
+        FundIntraday {
+            fund_code: "A".to_string(),
+            holding_share: dec("100"),
+            base_nav: None,
+            // 2.02 / (1 + 1%) = 2.00 => pnl = 100 * 0.02 = 2
+            points: vec![
+                point("2024-06-28 10:00:00", "2.02", Some("1")),
+                point("2024-06-28 11:00:00", "1.98", Some("-1")),
+            ],
+        },
+        FundIntraday {
+            fund_code: "B".to_string(),
+            holding_share: dec("10"),
+            base_nav: Some(dec("1.00")),
+            points: vec![point("2024-06-28 10:30:00", "1.10", None)],
+        },
+    ];
+
+    let got = aggregate_intraday_pnl(&funds);
+    assert_eq!(got.len(), 3);
+    assert_eq!(got[0].time, "2024-06-28T10:00:00");
+    assert_eq!(got[0].pnl, "2.00");
+    assert_eq!(got[0].estimate_value, "202.00");
+    // 10:30：A 沿用 10:00 的样本，B 首次出现
+    assert_eq!(got[1].pnl, "3.00");
+    // 11:00：A = 100 * (1.98 - 2.00) = -2，B = 1
+    assert_eq!(got[2].pnl, "-1.00");
+    assert_eq!(got[2].estimate_value, "209.00");
+}
+
+#[tokio::test]
+async fn intraday_routes_return_day_curve_account_pnl_and_prune() {
+    sqlx::any::install_default_drivers();
+
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+
+    sqlx::query(
+        r#"
+        INSERT INTO auth_user (id, password, is_superuser, username, is_staff, is_active)
+        VALUES (1, 'x', 0, 'u', 0, 1)
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .expect("seed user");
+
+    sqlx::query(
+        r#"
+        INSERT INTO fund (id, fund_code, fund_name, latest_nav, created_at, updated_at)
+        VALUES ('f1', '000001', 'F', '1.0', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .expect("seed fund");
+
+    let account_id = "11111111-1111-1111-1111-111111111111";
+    sqlx::query("INSERT INTO account (id, user_id, name, is_default) VALUES ($1, 1, 'main', 1)")
+        .bind(account_id)
+        .execute(&pool)
+        .await
+        .expect("seed account");
+    sqlx::query(
+        r#"
+        INSERT INTO position (id, account_id, fund_id, holding_share, holding_cost, holding_nav)
+        VALUES ('p1', $1, 'f1', '1000', 0, 1)
+        "#,
+    )
+    .bind(account_id)
+    .execute(&pool)
+    .await
+    .expect("seed position");
+
+    for (t, nav, growth) in [
+        ("2024-06-27 14:00:00", "0.99", "-1"),
+        ("2024-06-28 10:00:00", "1.01", "1"),
+        ("2024-06-28 14:30:00", "1.02", "2"),
+    ] {
+        api::intraday::record_sample(
+            &pool,
+            "000001",
+            "tiantian",
+            dt(t),
+            dec(nav),
+            Some(dec(growth)),
+        )
+        .await
+        .expect("record sample");
+    }
+    // 同一时间点重复抓取：覆盖而非新增
+    api::intraday::record_sample(
+        &pool,
+        "000001",
+        "tiantian",
+        dt("2024-06-28 14:30:00"),
+        dec("1.03"),
+        Some(dec("3")),
+    )
+    .await
+    .expect("record sample again");
+
+    let config = api::config::ConfigStore::load();
+    let jwt = api::jwt::JwtService::from_secret("test-secret");
+    let state = AppState::new(
+        Some(pool.clone()),
+        config,
+        jwt,
+        api::db::DatabaseKind::Sqlite,
+    );
+    let token = state.jwt().issue_access_token("1");
+    let app = api::app(state);
+
+    let get = |uri: String| {
+        Request::builder()
+            .uri(uri)
+            .header("authorization", format!("Bearer {token}"))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    // 缺省 date：返回最近有样本的一天
+    let res = app
+        .clone()
+        .oneshot(get("/api/funds/000001/estimate/intraday".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let v = body_json(res).await;
+    assert_eq!(v["date"], "2024-06-28");
+    let points = v["points"].as_array().unwrap();
+    assert_eq!(points.len(), 2);
+    assert_eq!(points[1]["time"], "2024-06-28T14:30:00");
+    assert_eq!(points[1]["estimate_nav"], "1.03");
+
+    let res = app
+        .clone()
+        .oneshot(get(
+            "/api/funds/000001/estimate/intraday?date=2024-06-27".to_string()
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let v = body_json(res).await;
+    assert_eq!(v["points"].as_array().unwrap().len(), 1);
+
+    let res = app
+        .clone()
+        .oneshot(get(
+            "/api/funds/000001/estimate/intraday?date=bad".to_string()
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    // 1000 份 × 1.03 × 3/103 = 30
+    let res = app
+        .clone()
+        .oneshot(get(format!("/api/accounts/{account_id}/estimate/intraday")))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let v = body_json(res).await;
+    assert_eq!(v["date"], "2024-06-28");
+    assert_eq!(v["covered_funds"][0], "000001");
+    let points = v["points"].as_array().unwrap();
+    assert_eq!(points.len(), 2);
+    assert_eq!(points[1]["pnl"], "30.00");
+    assert_eq!(points[1]["estimate_value"], "1030.00");
+
+    let res = app
+        .clone()
+        .oneshot(get(
+            "/api/accounts/22222222-2222-2222-2222-222222222222/estimate/intraday".to_string(),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+
+    // 保留 1 天：只留下 2024-06-28 当天的样本
+    let deleted =
+        api::intraday::prune_samples(&pool, NaiveDate::from_ymd_opt(2024, 6, 28).unwrap(), 1)
+            .await
+            .expect("prune");
+    assert_eq!(deleted, 1);
+}
@@ -0,0 +1,265 @@
+use sqlx::Row;
+
+use api::features::{self, FEATURE_SET_VERSION, REGISTRY, store};
+
+async fn setup() -> sqlx::AnyPool {
+    sqlx::any::install_default_drivers();
+
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+    pool
+}
+
+fn date(i: i64) -> String {
+    let start = chrono::NaiveDate::from_ymd_opt(2026, 1, 1).expect("date");
+    (start + chrono::Duration::days(i))
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+async fn seed_fund(pool: &sqlx::AnyPool, fund_id: &str, code: &str) {
+    sqlx::query(
+        r#"
+        INSERT INTO fund (id, fund_code, fund_name, fund_type, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        "#,
+    )
+    .bind(fund_id)
+    .bind(code)
+    .bind(format!("基金{code}"))
+    .bind("股票型")
+    .execute(pool)
+    .await
+    .expect("seed fund");
+}
+
+async fn seed_navs(pool: &sqlx::AnyPool, fund_id: &str, offset: i64, navs: &[f64]) {
+    for (i, nav) in navs.iter().enumerate() {
+        let i = offset + i as i64;
+        sqlx::query(
+            r#"
+            INSERT INTO fund_nav_history (id, source_name, fund_id, nav_date, unit_nav, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            "#,
+        )
+        .bind(format!("nav-{fund_id}-{i}"))
+        .bind("tiantian")
+        .bind(fund_id)
+        .bind(date(i))
+        .bind(format!("{nav:.4}"))
+        .execute(pool)
+        .await
+        .expect("seed nav");
+    }
+}
+
+async fn count_rows(pool: &sqlx::AnyPool, code: &str, scope: &str) -> i64 {
+    sqlx::query("SELECT COUNT(*) as n FROM fund_feature_row WHERE fund_code = $1 AND scope = $2")
+        .bind(code)
+        .bind(scope)
+        .fetch_one(pool)
+        .await
+        .expect("count rows")
+        .get::<i64, _>("n")
+}
+
+#[test]
+fn registry_names_are_unique_and_cover_model_inputs() {
+    let mut names: Vec<&str> = REGISTRY.iter().map(|d| d.name).collect();
+    names.sort();
+    names.dedup();
+    assert_eq!(names.len(), REGISTRY.len());
+
+    for name in features::ML_SIGNAL_FEATURES {
+        assert!(
+            features::def(name).is_some(),
+            "{name} missing from registry"
+        );
+    }
+    assert!(features::def(features::LAGGED_RETURN_FEATURE).is_some());
+    assert!(features::def("peer_pos_pct").is_some());
+    assert!(features::def("nope").is_none());
+}
+
+#[test]
+fn lag_samples_and_last_lags_align_with_series() {
+    let series = [0.1, 0.2, 0.3, 0.4];
+    let (x, y) = features::lag_samples(&series, 2);
+    assert_eq!(x, vec![vec![0.1, 0.2], vec![0.2, 0.3]]);
+    assert_eq!(y, vec![0.3, 0.4]);
+
+    assert_eq!(features::last_lags(&series, 2), vec![0.3, 0.4]);
+    assert_eq!(features::last_lags(&series[..1], 3), vec![0.0, 0.0, 0.1]);
+}
+
+#[tokio::test]
+async fn materialize_fund_is_incremental_and_rebuilds_on_backfill_or_version_change() {
+    let pool = setup().await;
+    seed_fund(&pool, "fund-1", "000001").await;
+    let navs: Vec<f64> = (0..40).map(|i| 1.0 + (i as f64) * 0.01).collect();
+    seed_navs(&pool, "fund-1", 10, &navs[..30]).await;
+
+    let written = store::materialize_fund(&pool, "000001", "tiantian")
+        .await
+        .expect("materialize");
+    assert_eq!(written, 30);
+
+    // 无新数据：只重写最新一行。
+    let written = store::materialize_fund(&pool, "000001", "tiantian")
+        .await
+        .expect("materialize again");
+    assert_eq!(written, 1);
+
+    seed_navs(&pool, "fund-1", 40, &navs[30..]).await;
+    let written = store::materialize_fund(&pool, "000001", "tiantian")
+        .await
+        .expect("materialize increment");
+    assert_eq!(written, 11);
+    assert_eq!(count_rows(&pool, "000001", store::FUND_SCOPE).await, 40);
+
+    let rows = store::load_rows(&pool, "000001", "tiantian", None, None, None)
+        .await
+        .expect("load rows");
+    let (last_date, last) = rows.last().expect("last row");
+    assert_eq!(last_date, &date(49));
+    let expected_ret5 = navs[39] / navs[34] - 1.0;
+    assert!((last["ret5"] - expected_ret5).abs() < 1e-9);
+    assert_eq!(last["dd_mag"], 0.0);
+    assert_eq!(last["hist_days"], 40.0);
+    assert!(last.contains_key("ret20"));
+    assert!(last.contains_key("macd_dif"));
+    assert!(!rows[0].1.contains_key("logret_1"));
+
+    // 回补更早的净值：行数对不上，整体重算。
+    seed_navs(&pool, "fund-1", 0, &[0.9; 10]).await;
+    let written = store::materialize_fund(&pool, "000001", "tiantian")
+        .await
+        .expect("materialize after backfill");
+    assert_eq!(written, 50);
+    assert_eq!(count_rows(&pool, "000001", store::FUND_SCOPE).await, 50);
+
+    // 特征集版本变化：同样整体重算。
+    sqlx::query("UPDATE fund_feature_row SET feature_set_version = $1")
+        .bind(FEATURE_SET_VERSION - 1)
+        .execute(&pool)
+        .await
+        .expect("downgrade version");
+    let written = store::materialize_fund(&pool, "000001", "tiantian")
+        .await
+        .expect("materialize after version change");
+    assert_eq!(written, 50);
+
+    let series = store::load_series(
+        &pool,
+        "000001",
+        "tiantian",
+        features::LAGGED_RETURN_FEATURE,
+        Some(&date(49)),
+        Some(5),
+    )
+    .await
+    .expect("load series");
+    assert_eq!(series.len(), 5);
+    assert!((series[4] - (navs[39] / navs[38]).ln()).abs() < 1e-6);
+}
+
+#[tokio::test]
+async fn index_excess_return_uses_reference_index() {
+    let pool = setup().await;
+    seed_fund(&pool, "fund-1", "000001").await;
+    let navs: Vec<f64> = (0..30).map(|i| 1.0 + (i as f64) * 0.01).collect();
+    seed_navs(&pool, "fund-1", 0, &navs).await;
+
+    for i in 0..30 {
+        sqlx::query(
+            r#"
+            INSERT INTO index_daily_price (id, index_code, source_name, trade_date, close, created_at, updated_at)
+            VALUES ($1,$2,$3,$4,$5,CURRENT_TIMESTAMP,CURRENT_TIMESTAMP)
+            "#,
+        )
+        .bind(format!("idx-{i}"))
+        .bind(features::REFER_INDEX_CODE)
+        .bind(features::REFER_INDEX_SOURCE)
+        .bind(date(i))
+        .bind(format!("{:.1}", 100.0 + i as f64))
+        .execute(&pool)
+        .await
+        .expect("seed index");
+    }
+
+    store::materialize_fund(&pool, "000001", "tiantian")
+        .await
+        .expect("materialize");
+    let (_, row) = store::row_on_or_before(&pool, "000001", "tiantian", None, &date(29))
+        .await
+        .expect("row")
+        .expect("row exists");
+    let expected = (navs[29] / navs[24] - 1.0) - (129.0 / 124.0 - 1.0);
+    assert!((row["idx_excess_ret5"] - expected).abs() < 1e-9);
+    assert!(row.contains_key("idx_excess_ret20"));
+}
+
+#[tokio::test]
+async fn materialize_peer_writes_cross_sectional_percentile() {
+    let pool = setup().await;
+    let flat = vec![1.0; 30];
+    let mut dipped = vec![1.0; 30];
+    dipped[29] = 0.8;
+    let mut shallow = vec![1.0; 30];
+    shallow[29] = 0.95;
+    for (id, code, navs) in [
+        ("fund-1", "000001", &flat),
+        ("fund-2", "000002", &dipped),
+        ("fund-3", "000003", &shallow),
+    ] {
+        seed_fund(&pool, id, code).await;
+        seed_navs(&pool, id, 0, navs).await;
+        sqlx::query(
+            r#"
+            INSERT INTO fund_relate_theme (fund_code, sec_code, sec_name, corr_1y, ol2top, source, fetched_at, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            "#,
+        )
+        .bind(code)
+        .bind("BK000156")
+        .bind("国防军工")
+        .bind(80.0_f64)
+        .bind(80.0_f64)
+        .bind("tiantian_h5")
+        .execute(&pool)
+        .await
+        .expect("seed relate theme");
+    }
+
+    let written = store::materialize_peer(&pool, "BK000156", "tiantian")
+        .await
+        .expect("materialize peer");
+    // 前 9 天成员历史不足 10 个点，不出分位。
+    assert_eq!(written, 3 * 21);
+
+    let pct = |code: &'static str| {
+        let pool = pool.clone();
+        async move {
+            let rows = store::load_rows(&pool, code, "tiantian", Some("BK000156"), None, Some(1))
+                .await
+                .expect("load rows");
+            rows.last()
+                .and_then(|(_, v)| v.get("peer_pos_pct").copied())
+        }
+    };
+    assert_eq!(pct("000001").await, Some(100.0));
+    assert_eq!(pct("000003").await, Some(50.0));
+    assert_eq!(pct("000002").await, Some(0.0));
+
+    // 不带板块读取时不含横截面特征。
+    let plain = store::load_rows(&pool, "000001", "tiantian", None, None, Some(1))
+        .await
+        .expect("load rows");
+    assert!(!plain[0].1.contains_key("peer_pos_pct"));
+}
@@ -67,6 +67,7 @@ async fn forecast_model_train_task_enqueues_and_trains() {
     let app = api::service(state);
 
     let res = app
+        .clone()
         .oneshot(
             Request::builder()
                 .method("POST")
@@ -122,5 +123,34 @@ async fn forecast_model_train_task_enqueues_and_trains() {
     let trained_at: String = model_row.get("trained_at");
     assert!(sample_count >= 0);
     assert!(!trained_at.trim().is_empty());
-}
 
+    // model_type=gbdt：未指定 model_name 时写入 global_gbdt_v1，weights_json 为整棵模型。
+    let res = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/forecast/model/train")
+                .header("Authorization", format!("Bearer {token}"))
+                .header("Content-Type", "application/json")
+                .body(Body::from(
+                    json!({ "source": "tiantian", "model_type": "gbdt", "lag_k": 20 }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 202);
+
+    api::tasks::run_due_task_jobs(&pool, 10)
+        .await
+        .expect("run_due_task_jobs");
+
+    let row = sqlx::query("SELECT weights_json FROM forecast_model WHERE model_name = $1")
+        .bind("global_gbdt_v1")
+        .fetch_one(&pool)
+        .await
+        .expect("gbdt forecast_model exists");
+    let weights: Value =
+        serde_json::from_str(&row.get::<String, _>("weights_json")).expect("weights json");
+    assert!(weights["trees"].is_array());
+}
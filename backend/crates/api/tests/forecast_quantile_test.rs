@@ -0,0 +1,230 @@
+use axum::{Json, Router, routing::post};
+use chrono::NaiveDate;
+use serde_json::{Value, json};
+use sqlx::Row;
+
+use api::forecast::quantile::{QuantileForecaster, horizon_samples, quantile};
+use api::ml::model::ModelKind;
+
+fn lcg(seed: u64) -> impl FnMut() -> f64 {
+    let mut s = seed;
+    move || {
+        s = s
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((s >> 11) as f64) / ((1_u64 << 53) as f64) - 0.5
+    }
+}
+
+fn dates(days: usize) -> Vec<String> {
+    let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+    (0..days)
+        .map(|i| {
+            (start + chrono::Duration::days(i as i64))
+                .format("%Y-%m-%d")
+                .to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn walk_forward_quantiles_are_ordered_and_roughly_calibrated() {
+    let mut noise = lcg(7);
+    let returns: Vec<(String, f64)> = dates(800)
+        .into_iter()
+        .map(|d| (d, 0.0003 + 0.02 * noise()))
+        .collect();
+
+    let set = horizon_samples(&returns, 20, 5);
+    assert_eq!(set.len(), 800 - 20 - 5 + 1);
+    assert_eq!(set.as_of[0], returns[19].0);
+    assert_eq!(set.target_end[0], returns[24].0);
+    let expected: f64 = returns[20..25].iter().map(|(_, v)| v).sum();
+    assert!((set.y[0] - expected).abs() < 1e-12);
+
+    let f = QuantileForecaster::fit(&set, 5, ModelKind::Linear).expect("fit");
+    let q = f.predict(&set.x[set.len() - 1]).expect("predict");
+    assert!(q[0] < q[1] && q[1] < q[2], "{q:?}");
+    let bt = &f.backtest;
+    assert!(bt.evaluated > 100, "{}", bt.evaluated);
+    let coverage = bt.coverage.expect("coverage");
+    assert!((0.65..=0.95).contains(&coverage), "{coverage}");
+    assert!(bt.mean_width.unwrap() > 0.0);
+
+    // 样本不足时不拟合。
+    let short = horizon_samples(&returns[..60], 20, 5);
+    assert!(QuantileForecaster::fit(&short, 5, ModelKind::Linear).is_none());
+
+    assert_eq!(quantile(&[1.0, 2.0, 3.0, 4.0, 5.0], 0.5), Some(3.0));
+    assert_eq!(quantile(&[0.0, 10.0], 0.1), Some(1.0));
+    assert_eq!(quantile(&[], 0.5), None);
+}
+
+async fn seed_fund(pool: &sqlx::AnyPool, n: usize, code: &str, days: usize) {
+    sqlx::query(
+        r#"
+        INSERT INTO fund (id, fund_code, fund_name, fund_type, created_at, updated_at)
+        VALUES ($1, $2, $3, '股票型', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        "#,
+    )
+    .bind(format!("fund-{n}"))
+    .bind(code)
+    .bind(format!("基金{code}"))
+    .execute(pool)
+    .await
+    .expect("seed fund");
+    // 末尾对齐：短历史基金只有最近 days 天。
+    let all = dates(400);
+    let mut noise = lcg(100 + n as u64);
+    let mut nav = 1.0_f64;
+    for (i, d) in all[all.len() - days..].iter().enumerate() {
+        nav *= 1.0 + 0.0004 + 0.02 * noise();
+        sqlx::query(
+            r#"
+            INSERT INTO fund_nav_history (id, source_name, fund_id, nav_date, unit_nav, created_at, updated_at)
+            VALUES ($1, 'tiantian', $2, $3, $4, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            "#,
+        )
+        .bind(format!("nav-{n}-{i}"))
+        .bind(format!("fund-{n}"))
+        .bind(d)
+        .bind(format!("{nav:.6}"))
+        .execute(pool)
+        .await
+        .expect("seed nav");
+    }
+    sqlx::query(
+        r#"
+        INSERT INTO fund_relate_theme (fund_code, sec_code, sec_name, corr_1y, ol2top, source, fetched_at, created_at, updated_at)
+        VALUES ($1, 'BK0001', '半导体', 0.9, 0.5, 'tiantian_h5', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        "#,
+    )
+    .bind(code)
+    .execute(pool)
+    .await
+    .expect("seed theme");
+}
+
+async fn compute_fan(pool: &sqlx::AnyPool, stub_url: &str, fund_code: &str) -> Value {
+    api::tasks::enqueue_task_job(
+        pool,
+        "fund_analysis_v2_compute",
+        &json!({
+          "fund_code": fund_code,
+          "source": "tiantian",
+          "profile": "default",
+          "windows": [60],
+          "quant_service_url": stub_url,
+        }),
+        80,
+        None,
+    )
+    .await
+    .expect("enqueue");
+    api::tasks::run_due_task_jobs(pool, 10)
+        .await
+        .expect("run tasks");
+    let row = sqlx::query(
+        "SELECT result_json FROM fund_analysis_snapshot WHERE fund_code = $1 AND source = 'tiantian'",
+    )
+    .bind(fund_code)
+    .fetch_one(pool)
+    .await
+    .expect("snapshot row");
+    let result: Value =
+        serde_json::from_str(&row.get::<String, _>("result_json")).expect("result json");
+    result["forecast_fan"].clone()
+}
+
+#[tokio::test]
+async fn analysis_v2_exposes_fund_and_peer_forecast_fans() {
+    // quant-service 桩：分析任务照常调用，这里只关注预测扇形。
+    let stub = Router::new()
+        .route(
+            "/api/quant/xalpha/metrics",
+            post(|Json(_body): Json<Value>| async move {
+                Json(json!({ "metrics": {}, "drawdown_series": [] }))
+            }),
+        )
+        .route(
+            "/api/quant/macd",
+            post(|Json(_body): Json<Value>| async move { Json(json!({ "points": [] })) }),
+        )
+        .route(
+            "/api/quant/xalpha/grid",
+            post(|Json(_body): Json<Value>| async move { Json(json!({ "actions": [] })) }),
+        )
+        .route(
+            "/api/quant/xalpha/scheduled",
+            post(|Json(_body): Json<Value>| async move { Json(json!({ "actions": [] })) }),
+        );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind stub");
+    let stub_url = format!("http://{}", listener.local_addr().expect("addr"));
+    tokio::spawn(async move {
+        let _ = axum::serve(listener, stub).await;
+    });
+
+    sqlx::any::install_default_drivers();
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+
+    seed_fund(&pool, 1, "000001", 400).await;
+    seed_fund(&pool, 2, "000002", 100).await;
+    seed_fund(&pool, 3, "000003", 400).await;
+    seed_fund(&pool, 4, "000004", 400).await;
+    sqlx::query(
+        r#"
+        INSERT INTO index_daily_price (id, index_code, source_name, trade_date, close, created_at, updated_at)
+        VALUES ('idx-0', '1.000001', 'eastmoney', '2025-02-03', '100.0', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .expect("seed index");
+
+    // 长历史：四个期限都用基金自身模型。
+    let fan = compute_fan(&pool, &stub_url, "000001").await;
+    assert_eq!(fan["lag_k"], 20);
+    assert!(fan["base_nav"].as_f64().unwrap() > 0.0);
+    let horizons = fan["horizons"].as_array().expect("horizons");
+    assert_eq!(
+        horizons
+            .iter()
+            .map(|h| h["horizon"].as_i64().unwrap())
+            .collect::<Vec<_>>(),
+        vec![5, 20, 60, 120]
+    );
+    for h in horizons {
+        assert_eq!(h["scope"], "fund");
+        assert_eq!(h["scope_code"], "000001");
+        let nav = &h["nav"];
+        assert!(nav["p10"].as_f64() < nav["p50"].as_f64());
+        assert!(nav["p50"].as_f64() < nav["p90"].as_f64());
+        assert_eq!(h["backtest"]["nominal_coverage"].as_f64(), Some(0.8));
+        let coverage = h["backtest"]["coverage"].as_f64().expect("coverage");
+        assert!((0.0..=1.0).contains(&coverage), "{coverage}");
+    }
+    // 期限越长，区间越宽。
+    let width = |h: &Value| {
+        h["log_return"]["p90"].as_f64().unwrap() - h["log_return"]["p10"].as_f64().unwrap()
+    };
+    assert!(width(&horizons[3]) > width(&horizons[0]));
+
+    // 短历史：回退到同类组合并样本。
+    let fan = compute_fan(&pool, &stub_url, "000002").await;
+    let horizons = fan["horizons"].as_array().expect("horizons");
+    assert_eq!(horizons.len(), 4);
+    for h in horizons {
+        assert_eq!(h["scope"], "peer");
+        assert_eq!(h["scope_code"], "BK0001");
+        assert!(h["sample_count"].as_i64().unwrap() > 100);
+    }
+    assert!(fan["skipped"].as_array().unwrap().is_empty());
+}
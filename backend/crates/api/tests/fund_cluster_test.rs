@@ -0,0 +1,306 @@
+use axum::{body::Body, http::Request};
+use serde_json::{Value, json};
+use sqlx::Row;
+use tower::ServiceExt;
+
+use api::ml::cluster::{ClusterConfig, cluster_panel, return_panel};
+use api::state::AppState;
+
+const GROUPS: usize = 3;
+const PER_GROUP: usize = 6;
+const DAYS: usize = 200;
+
+fn fund_code(g: usize, i: usize) -> String {
+    format!("{:06}", 100 * (g + 1) + i)
+}
+
+/// 三组基金：组内收益由同一因子驱动（相关系数约 0.9），组间因子独立。
+fn synthetic_navs() -> Vec<(String, Vec<(String, f64)>)> {
+    let mut seed = 11_u64;
+    let mut noise = || {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((seed >> 11) as f64) / ((1_u64 << 53) as f64) - 0.5
+    };
+    let end = chrono::Utc::now().date_naive();
+    let dates: Vec<String> = (0..DAYS)
+        .map(|t| {
+            (end - chrono::Duration::days((DAYS - t) as i64))
+                .format("%Y-%m-%d")
+                .to_string()
+        })
+        .collect();
+    let factors: Vec<Vec<f64>> = (0..GROUPS)
+        .map(|_| (0..DAYS).map(|_| 0.03 * noise()).collect())
+        .collect();
+    let mut out = Vec::new();
+    for (g, factor) in factors.iter().enumerate() {
+        for i in 0..PER_GROUP {
+            let mut nav = 1.0;
+            let series = dates
+                .iter()
+                .zip(factor.iter())
+                .map(|(d, f)| {
+                    nav *= 1.0 + f + 0.01 * noise();
+                    (d.clone(), nav)
+                })
+                .collect();
+            out.push((fund_code(g, i), series));
+        }
+    }
+    out
+}
+
+fn small_config() -> ClusterConfig {
+    ClusterConfig {
+        k_min: 2,
+        k_max: 6,
+        bootstrap_rounds: 5,
+        ..ClusterConfig::default()
+    }
+}
+
+#[test]
+fn k_medoids_recovers_factor_groups_with_high_stability() {
+    let navs = synthetic_navs();
+    let panel = return_panel(&navs, &small_config());
+    assert_eq!(panel.codes.len(), GROUPS * PER_GROUP);
+    assert_eq!(panel.dates.len(), DAYS - 1);
+
+    let c = cluster_panel(&panel, &small_config()).expect("cluster");
+    assert_eq!(c.k, GROUPS);
+    for g in 0..GROUPS {
+        let labels: Vec<usize> = (0..PER_GROUP)
+            .map(|i| c.labels[g * PER_GROUP + i])
+            .collect();
+        assert!(labels.iter().all(|&l| l == labels[0]), "{labels:?}");
+    }
+    assert!(c.mean_silhouette > 0.5, "{}", c.mean_silhouette);
+    assert!(c.stability.iter().all(|&s| s > 0.99), "{:?}", c.stability);
+    assert!(c.peer_code(0).starts_with("cluster:"));
+
+    // 固定 k 时照常聚类；样本不足时拒绝。
+    let fixed = ClusterConfig {
+        k: Some(2),
+        ..small_config()
+    };
+    assert_eq!(cluster_panel(&panel, &fixed).expect("fixed k").k, 2);
+    let too_many = ClusterConfig {
+        k: Some(10),
+        ..small_config()
+    };
+    assert!(cluster_panel(&panel, &too_many).is_none());
+}
+
+#[tokio::test]
+async fn cluster_task_stores_assignments_and_peers_fall_back_to_clusters() {
+    sqlx::any::install_default_drivers();
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+
+    for (n, (code, navs)) in synthetic_navs().iter().enumerate() {
+        sqlx::query(
+            r#"
+            INSERT INTO fund (id, fund_code, fund_name, fund_type, created_at, updated_at)
+            VALUES ($1, $2, $3, '股票型', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            "#,
+        )
+        .bind(format!("fund-{n}"))
+        .bind(code)
+        .bind(format!("基金{code}"))
+        .execute(&pool)
+        .await
+        .expect("seed fund");
+        for (i, (d, nav)) in navs.iter().enumerate() {
+            sqlx::query(
+                r#"
+                INSERT INTO fund_nav_history (id, source_name, fund_id, nav_date, unit_nav, created_at, updated_at)
+                VALUES ($1, 'tiantian', $2, $3, $4, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+                "#,
+            )
+            .bind(format!("nav-{n}-{i}"))
+            .bind(format!("fund-{n}"))
+            .bind(d)
+            .bind(format!("{nav:.6}"))
+            .execute(&pool)
+            .await
+            .expect("seed nav");
+        }
+    }
+    // 组 0 的第一只基金有关联板块，其余基金没有。
+    sqlx::query(
+        r#"
+        INSERT INTO fund_relate_theme (fund_code, sec_code, sec_name, corr_1y, ol2top, source, fetched_at, created_at, updated_at)
+        VALUES ('000100', 'BK0001', '半导体', 0.9, 0.5, 'tiantian_h5', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .expect("seed theme");
+
+    api::tasks::enqueue_task_job(
+        &pool,
+        "fund_cluster_compute",
+        &json!({ "k_min": 2, "k_max": 6, "bootstrap_rounds": 5 }),
+        100,
+        None,
+    )
+    .await
+    .expect("enqueue");
+    api::tasks::run_due_task_jobs(&pool, 10)
+        .await
+        .expect("run tasks");
+    let status: String =
+        sqlx::query("SELECT status FROM task_job WHERE task_type = 'fund_cluster_compute'")
+            .fetch_one(&pool)
+            .await
+            .expect("job")
+            .get("status");
+    assert_eq!(status, "done");
+
+    let clusters =
+        sqlx::query("SELECT peer_code, size, mean_stability FROM fund_cluster ORDER BY peer_code")
+            .fetch_all(&pool)
+            .await
+            .expect("clusters");
+    assert_eq!(clusters.len(), GROUPS);
+    for r in &clusters {
+        assert_eq!(r.get::<i64, _>("size"), PER_GROUP as i64);
+        assert!(r.get::<f64, _>("mean_stability") > 0.99);
+    }
+
+    // 无关联板块：回退到聚类同类组；成员即同组基金。
+    let peers = api::ml::cluster::fund_peers(&pool, "000201", "tiantian", 6)
+        .await
+        .expect("peers");
+    assert_eq!(peers.len(), 1);
+    let (peer_code, peer_name) = &peers[0];
+    assert!(peer_code.starts_with("cluster:"));
+    assert!(peer_name.contains("等6只"), "{peer_name}");
+    let members = api::features::store::peer_members(&pool, peer_code, "tiantian")
+        .await
+        .expect("members");
+    assert_eq!(
+        members,
+        (0..PER_GROUP).map(|i| fund_code(1, i)).collect::<Vec<_>>()
+    );
+
+    // 有关联板块时仍优先板块。
+    let peers = api::ml::cluster::fund_peers(&pool, "000100", "tiantian", 6)
+        .await
+        .expect("peers");
+    assert_eq!(peers, vec![("BK0001".to_string(), "半导体".to_string())]);
+
+    // 稳定性不足的归属不参与回退。
+    sqlx::query("UPDATE fund_cluster_member SET stability = 0.2 WHERE fund_code = '000301'")
+        .execute(&pool)
+        .await
+        .expect("lower stability");
+    assert!(
+        api::ml::cluster::fund_peers(&pool, "000301", "tiantian", 6)
+            .await
+            .expect("peers")
+            .is_empty()
+    );
+
+    // 性价比同类分位：无关联板块的基金用聚类同类组。
+    let config = api::config::ConfigStore::load();
+    let jwt = api::jwt::JwtService::from_secret("test-secret");
+    let state = AppState::new(
+        Some(pool.clone()),
+        config,
+        jwt,
+        api::db::DatabaseKind::Sqlite,
+    );
+    let token = state.jwt().issue_access_token("1");
+    let res = api::service(state)
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/api/funds/000201/analytics?source=tiantian")
+                .header("Authorization", format!("Bearer {token}"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+        .await
+        .expect("read body");
+    let v: Value = serde_json::from_slice(&bytes).expect("json");
+    assert_eq!(v["value_score"]["peer_kind"], "cluster");
+    assert_eq!(v["value_score"]["peer_code"], *peer_code);
+    assert_eq!(v["value_score"]["sample_size"], PER_GROUP as i64);
+
+    // 另一个数据源只有组 1、组 2 的净值：单独聚类，不覆盖 tiantian 的结果。
+    for (n, (_, navs)) in synthetic_navs().iter().enumerate().skip(PER_GROUP) {
+        for (i, (d, nav)) in navs.iter().enumerate() {
+            sqlx::query(
+                r#"
+                INSERT INTO fund_nav_history (id, source_name, fund_id, nav_date, unit_nav, created_at, updated_at)
+                VALUES ($1, 'danjuan', $2, $3, $4, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+                "#,
+            )
+            .bind(format!("nav-dj-{n}-{i}"))
+            .bind(format!("fund-{n}"))
+            .bind(d)
+            .bind(format!("{nav:.6}"))
+            .execute(&pool)
+            .await
+            .expect("seed danjuan nav");
+        }
+    }
+    let stats = api::ml::cluster::compute_and_store(&pool, "danjuan", &small_config())
+        .await
+        .expect("cluster danjuan");
+    assert_eq!(stats.clusters, GROUPS - 1);
+    for (source, clusters, members) in [
+        ("tiantian", GROUPS, GROUPS * PER_GROUP),
+        ("danjuan", GROUPS - 1, (GROUPS - 1) * PER_GROUP),
+    ] {
+        let row = sqlx::query(
+            r#"
+            SELECT
+              (SELECT COUNT(*) FROM fund_cluster WHERE source_name = $1) as clusters,
+              (SELECT COUNT(*) FROM fund_cluster_member WHERE source_name = $1) as members
+            "#,
+        )
+        .bind(source)
+        .fetch_one(&pool)
+        .await
+        .expect("counts");
+        assert_eq!(row.get::<i64, _>("clusters"), clusters as i64, "{source}");
+        assert_eq!(row.get::<i64, _>("members"), members as i64, "{source}");
+    }
+    let danjuan_peers = api::ml::cluster::fund_peers(&pool, "000301", "danjuan", 6)
+        .await
+        .expect("peers");
+    assert_eq!(danjuan_peers.len(), 1);
+    let members = api::features::store::peer_members(&pool, &danjuan_peers[0].0, "danjuan")
+        .await
+        .expect("members");
+    assert_eq!(
+        members,
+        (0..PER_GROUP).map(|i| fund_code(2, i)).collect::<Vec<_>>()
+    );
+    assert!(
+        api::ml::cluster::fund_peers(&pool, "000101", "danjuan", 6)
+            .await
+            .expect("peers")
+            .is_empty()
+    );
+    assert_eq!(
+        api::ml::cluster::fund_peers(&pool, "000101", "tiantian", 6)
+            .await
+            .expect("peers")
+            .len(),
+        1
+    );
+}
@@ -0,0 +1,255 @@
+use axum::{body::Body, http::Request};
+use chrono::NaiveDate;
+use serde_json::Value;
+use tower::ServiceExt;
+
+use api::holdings::{
+    ASSET_BOND, ASSET_STOCK, Holding, HoldingOut, HoldingsBundle, IndustryAllocation,
+    extract_apidata_content, holdings_overlap, look_through_exposure, parse_holdings_from_f10,
+    parse_industry_allocation_from_f10,
+};
+use api::state::AppState;
+
+async fn body_json(res: axum::response::Response) -> Value {
+    let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+        .await
+        .expect("read body");
+    serde_json::from_slice(&bytes).expect("json")
+}
+
+const JJCC: &str = r#"var apidata={ content:"<div class='box'><div class='boxitem w790'><h4 class='t'><label class='left'><a href='http://fund.eastmoney.com/005827.html'>易方达蓝筹精选混合</a>&nbsp;&nbsp;2024年2季度股票投资明细</label><label class='right lab2 xq505'>&nbsp;&nbsp;&nbsp;&nbsp;来源：天天基金&nbsp;&nbsp;&nbsp;&nbsp;截止至：<font class='px12'>2024-06-30</font></label></h4><table class='w782 comm tzxq'><thead><tr><th class='first'>序号</th><th>股票代码</th><th>股票名称</th><th>最新价</th><th>涨跌幅</th><th>相关资讯</th><th>占净值<br />比例</th><th class='cgs'>持股数<br />（万股）</th><th class='last ccs'>持仓市值<br />（万元）</th></tr></thead><tbody><tr><td>1</td><td><a href='//quote.eastmoney.com/unify/r/0.000858'>000858</a></td><td class='tol'><a href='//quote.eastmoney.com/unify/r/0.000858'>五粮液</a></td><td class='tor'><span id='dq000858'></span></td><td class='tor'><span id='zd000858'></span></td><td class='xglj'><a href='ccbdxq_005827_000858.html' class='red'>变动详情</a><a href='//guba.eastmoney.com/list,000858.html'>股吧</a></td><td class='tor'>9.85%</td><td class='tor'>2,100.50</td><td class='tor'>270,000.12</td></tr><tr><td>2</td><td><a href='//quote.eastmoney.com/unify/r/1.600519'>600519</a></td><td class='tol'><a href='//quote.eastmoney.com/unify/r/1.600519'>贵州茅台</a></td><td class='tor'><span id='dq600519'></span></td><td class='tor'><span id='zd600519'></span></td><td class='xglj'><a href='ccbdxq_005827_600519.html' class='red'>变动详情</a></td><td class='tor'>9.50%</td><td class='tor'>180.00</td><td class='tor'>260,000.00</td></tr></tbody></table></div></div>",arryear:[2024,2023],curyear:2024};"#;
+
+#[test]
+fn parse_jjcc_extracts_report_date_weight_and_value() {
+    let content = extract_apidata_content(JJCC).expect("content");
+    let got = parse_holdings_from_f10(&content, ASSET_STOCK);
+    assert_eq!(got.len(), 2);
+    assert_eq!(
+        got[0].report_date,
+        NaiveDate::from_ymd_opt(2024, 6, 30).unwrap()
+    );
+    assert_eq!(got[0].sec_code, "000858");
+    assert_eq!(got[0].sec_name, "五粮液");
+    assert!((got[0].weight_pct - 9.85).abs() < 1e-9);
+    assert!((got[0].shares.unwrap() - 21_005_000.0).abs() < 1e-3);
+    assert!((got[0].market_value_cny.unwrap() - 2_700_001_200.0).abs() < 1e-3);
+}
+
+#[test]
+fn parse_zqcc_and_hypz() {
+    let bonds = r#"<div class='box'><label>截止至：<font class='px12'>2024-06-30</font></label><table><thead><tr><th>序号</th><th>债券代码</th><th>债券名称</th><th>占净值比例</th><th>持仓市值（万元）</th></tr></thead><tbody><tr><td>1</td><td>019733</td><td>24国债02</td><td class='tor'>3.20%</td><td class='tor'>12,000.00</td></tr></tbody></table></div>"#;
+    let got = parse_holdings_from_f10(bonds, ASSET_BOND);
+    assert_eq!(got.len(), 1);
+    assert_eq!(got[0].asset_type, "bond");
+    assert_eq!(got[0].shares, None);
+    assert!((got[0].market_value_cny.unwrap() - 120_000_000.0).abs() < 1e-3);
+
+    let hypz = r#"<div class='box'><label>截止至：<font>2024-06-30</font></label><table><tbody><tr><td>1</td><td class='tol'>制造业</td><td><a href='#'>变动详情</a></td><td class='tor'>65.12%</td><td class='tor'>1,234.00</td></tr><tr><td></td><td>合计</td><td></td><td>90.00%</td><td>9,999.00</td></tr></tbody></table></div>"#;
+    let ind = parse_industry_allocation_from_f10(hypz);
+    assert_eq!(ind.len(), 1);
+    assert_eq!(ind[0].industry_name, "制造业");
+    assert!((ind[0].weight_pct - 65.12).abs() < 1e-9);
+}
+
+fn h(code: &str, w: f64) -> HoldingOut {
+    HoldingOut {
+        sec_code: code.to_string(),
+        sec_name: code.to_string(),
+        weight_pct: w,
+        shares: None,
+        market_value_cny: None,
+    }
+}
+
+#[test]
+fn overlap_and_exposure_math() {
+    let a = vec![h("X", 10.0), h("Y", 5.0), h("Z", 2.0)];
+    let b = vec![h("X", 4.0), h("Y", 8.0)];
+    let o = holdings_overlap("A", &a, "B", &b);
+    assert!((o.overlap_pct - 9.0).abs() < 1e-9);
+    assert_eq!(o.common.len(), 2);
+    assert_eq!(o.common[0].sec_code, "Y");
+
+    let e = look_through_exposure(&[("A".to_string(), 1000.0, a), ("B".to_string(), 1000.0, b)]);
+    // X: 1000*10% + 1000*4% = 140 => 7% of 2000
+    let x = e.iter().find(|v| v.sec_code == "X").unwrap();
+    assert!((x.exposure_value_cny - 140.0).abs() < 1e-9);
+    assert!((x.exposure_pct - 7.0).abs() < 1e-9);
+    assert_eq!(x.via_funds, vec!["A".to_string(), "B".to_string()]);
+}
+
+fn holding(date: (i32, u32, u32), code: &str, w: f64) -> Holding {
+    Holding {
+        report_date: NaiveDate::from_ymd_opt(date.0, date.1, date.2).unwrap(),
+        asset_type: ASSET_STOCK.to_string(),
+        sec_code: code.to_string(),
+        sec_name: format!("S{code}"),
+        weight_pct: w,
+        shares: None,
+        market_value_cny: None,
+    }
+}
+
+#[tokio::test]
+async fn holdings_routes_overlap_exposure_and_stock_lookup() {
+    sqlx::any::install_default_drivers();
+
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+
+    sqlx::query(
+        r#"
+        INSERT INTO auth_user (id, password, is_superuser, username, is_staff, is_active)
+        VALUES (1, 'x', 0, 'u', 0, 1)
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .expect("seed user");
+
+    for (id, code, nav) in [("f1", "000001", "2.0"), ("f2", "000002", "1.0")] {
+        sqlx::query(
+            r#"
+            INSERT INTO fund (id, fund_code, fund_name, latest_nav, created_at, updated_at)
+            VALUES ($1, $2, 'F', $3, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            "#,
+        )
+        .bind(id)
+        .bind(code)
+        .bind(nav)
+        .execute(&pool)
+        .await
+        .expect("seed fund");
+    }
+
+    let account_id = "11111111-1111-1111-1111-111111111111";
+    sqlx::query("INSERT INTO account (id, user_id, name, is_default) VALUES ($1, 1, 'main', 1)")
+        .bind(account_id)
+        .execute(&pool)
+        .await
+        .expect("seed account");
+    for (pid, fid, share) in [("p1", "f1", "500"), ("p2", "f2", "1000")] {
+        sqlx::query(
+            r#"
+            INSERT INTO position (id, account_id, fund_id, holding_share, holding_cost, holding_nav)
+            VALUES ($1, $2, $3, $4, 0, 1)
+            "#,
+        )
+        .bind(pid)
+        .bind(account_id)
+        .bind(fid)
+        .bind(share)
+        .execute(&pool)
+        .await
+        .expect("seed position");
+    }
+
+    // 000001 旧报告期的数据不应参与计算
+    let b1 = HoldingsBundle {
+        holdings: vec![
+            holding((2024, 3, 31), "600519", 50.0),
+            holding((2024, 6, 30), "600519", 10.0),
+            holding((2024, 6, 30), "000858", 5.0),
+        ],
+        industries: vec![IndustryAllocation {
+            report_date: NaiveDate::from_ymd_opt(2024, 6, 30).unwrap(),
+            industry_name: "制造业".to_string(),
+            weight_pct: 60.0,
+            market_value_cny: None,
+        }],
+    };
+    let b2 = HoldingsBundle {
+        holdings: vec![holding((2024, 6, 30), "600519", 20.0)],
+        industries: vec![],
+    };
+    api::holdings::upsert_holdings_bundle(&pool, "000001", "eastmoney", &b1)
+        .await
+        .expect("upsert 1");
+    api::holdings::upsert_holdings_bundle(&pool, "000002", "eastmoney", &b2)
+        .await
+        .expect("upsert 2");
+
+    let config = api::config::ConfigStore::load();
+    let jwt = api::jwt::JwtService::from_secret("test-secret");
+    let state = AppState::new(Some(pool), config, jwt, api::db::DatabaseKind::Sqlite);
+    let token = state.jwt().issue_access_token("1");
+    let app = api::app(state);
+
+    let get = |uri: String| {
+        Request::builder()
+            .uri(uri)
+            .header("authorization", format!("Bearer {token}"))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let res = app
+        .clone()
+        .oneshot(get("/api/funds/000001/holdings".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let v = body_json(res).await;
+    assert_eq!(v["stocks"]["report_date"], "2024-06-30");
+    assert_eq!(v["stocks"]["items"].as_array().unwrap().len(), 2);
+    assert_eq!(v["industries"]["items"][0]["industry_name"], "制造业");
+
+    let res = app
+        .clone()
+        .oneshot(get(
+            "/api/holdings/overlap?fund_a=000001&fund_b=000002".to_string()
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let v = body_json(res).await;
+    assert_eq!(v["overlap_pct"].as_f64().unwrap(), 10.0);
+
+    let res = app
+        .clone()
+        .oneshot(get(format!("/api/accounts/{account_id}/holdings/overlap")))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let v = body_json(res).await;
+    assert_eq!(v["pairs"].as_array().unwrap().len(), 1);
+
+    // 两只基金市值各 1000：600519 暴露 = 1000*10% + 1000*20% = 300 => 15%
+    let res = app
+        .clone()
+        .oneshot(get(format!("/api/accounts/{account_id}/holdings/exposure")))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let v = body_json(res).await;
+    assert_eq!(v["total_value_cny"].as_f64().unwrap(), 2000.0);
+    assert_eq!(v["stocks"][0]["sec_code"], "600519");
+    assert!((v["stocks"][0]["exposure_pct"].as_f64().unwrap() - 15.0).abs() < 1e-9);
+
+    let res = app
+        .clone()
+        .oneshot(get("/api/holdings/stocks/600519/funds".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let v = body_json(res).await;
+    let funds = v["funds"].as_array().unwrap();
+    assert_eq!(funds.len(), 2);
+    assert_eq!(funds[0]["fund_code"], "000002");
+    assert_eq!(funds[0]["in_positions"], true);
+
+    let res = app
+        .clone()
+        .oneshot(get(
+            "/api/accounts/22222222-2222-2222-2222-222222222222/holdings/exposure".to_string(),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+}
@@ -0,0 +1,296 @@
+use axum::{body::Body, http::Request};
+use serde_json::Value;
+use tower::ServiceExt;
+
+use api::fund_profile::{
+    FundProfileBundle, ManagerTenure, ScalePoint, parse_fund_base_info,
+    parse_manager_tenures_from_f10_html, parse_scale_history_from_pingzhongdata,
+    share_class_from_name,
+};
+use api::state::AppState;
+use chrono::NaiveDate;
+
+async fn body_json(res: axum::response::Response) -> Value {
+    let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+        .await
+        .expect("read body");
+    serde_json::from_slice(&bytes).expect("json")
+}
+
+#[test]
+fn share_class_ignores_etf_like_suffixes() {
+    assert_eq!(share_class_from_name("易方达蓝筹精选混合C").as_deref(), Some("C"));
+    assert_eq!(share_class_from_name("华夏成长混合A").as_deref(), Some("A"));
+    assert_eq!(share_class_from_name("华夏沪深300ETF"), None);
+    assert_eq!(share_class_from_name("广发纳斯达克100指数(QDII)"), None);
+    assert_eq!(share_class_from_name("华夏成长混合"), None);
+}
+
+#[test]
+fn parse_base_info_extracts_company_fees_and_status() {
+    let payload = r#"
+    {
+      "Datas": {
+        "FCODE": "005827",
+        "SHORTNAME": "易方达蓝筹精选混合C",
+        "ESTABDATE": "2018-09-05",
+        "JJGS": "易方达基金",
+        "JJGSID": "80000229",
+        "BENCH": "沪深300指数收益率×45%+中债总指数收益率×10%",
+        "MGREXP": "1.20%",
+        "TRUSTEXP": "0.20%",
+        "SGZT": "限大额",
+        "SHZT": "开放赎回"
+      },
+      "ErrCode": 0,
+      "Success": true
+    }
+    "#;
+
+    let p = parse_fund_base_info("005827", payload)
+        .expect("parse")
+        .expect("profile");
+    assert_eq!(p.company_id.as_deref(), Some("80000229"));
+    assert_eq!(p.company_name.as_deref(), Some("易方达基金"));
+    assert_eq!(p.inception_date, NaiveDate::from_ymd_opt(2018, 9, 5));
+    assert_eq!(p.management_fee_pct, Some(1.2));
+    assert_eq!(p.custody_fee_pct, Some(0.2));
+    assert_eq!(p.share_class.as_deref(), Some("C"));
+    assert_eq!(p.purchase_status.as_deref(), Some("限大额"));
+    assert_eq!(p.redeem_status.as_deref(), Some("开放赎回"));
+
+    assert!(
+        parse_fund_base_info("005827", r#"{"Datas":null}"#)
+            .expect("parse")
+            .is_none()
+    );
+}
+
+#[test]
+fn parse_f10_manager_table_handles_co_managers_and_current_tenure() {
+    let html = r#"
+    <label class='left'>基金经理变动一览</label>
+    <table class='w782 comm jloff'>
+      <thead><tr><th>起始期</th><th>截止期</th><th>基金经理</th><th>任职期间</th><th>任职回报</th></tr></thead>
+      <tbody>
+        <tr><td>2021-01-05</td><td>至今</td><td><a href="//fund.eastmoney.com/manager/30189741.html">张坤</a>&nbsp;&nbsp;<a href="//fund.eastmoney.com/manager/30655271.html">李四</a></td><td>3年又100天</td><td>-12.34%</td></tr>
+        <tr><td>2018-09-05</td><td>2021-01-04</td><td><a href="//fund.eastmoney.com/manager/30189741.html">张坤</a></td><td>2年又121天</td><td>150.20%</td></tr>
+      </tbody>
+    </table>
+    <table><tr><td>2000-01-01</td><td>x</td><td>y</td><td>z</td><td>1%</td></tr></table>
+    "#;
+
+    let got = parse_manager_tenures_from_f10_html(html);
+    assert_eq!(got.len(), 3);
+    assert_eq!(got[0].manager_id, "30189741");
+    assert_eq!(got[0].manager_name, "张坤");
+    assert_eq!(got[0].end_date, None);
+    assert_eq!(got[0].tenure_return_pct, Some(-12.34));
+    assert_eq!(got[1].manager_name, "李四");
+    assert_eq!(got[2].end_date, NaiveDate::from_ymd_opt(2021, 1, 4));
+}
+
+#[test]
+fn parse_pingzhongdata_scale_converts_to_cny() {
+    let js = r#"var fS_name = "x";var Data_fluctuationScale = {"categories":["2024-03-31","2024-06-30"],"series":[{"y":12.5,"mom":"-1.2%"},{"y":10.0,"mom":"-20%"}]};var Data_holderStructure = {};"#;
+    let got = parse_scale_history_from_pingzhongdata(js);
+    assert_eq!(got.len(), 2);
+    assert_eq!(got[0].report_date, NaiveDate::from_ymd_opt(2024, 3, 31).unwrap());
+    assert!((got[0].aum_cny - 1_250_000_000.0).abs() < 1e-3);
+}
+
+#[tokio::test]
+async fn fund_retrieve_includes_profile_managers_and_aum_history() {
+    sqlx::any::install_default_drivers();
+
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+
+    sqlx::query(
+        r#"
+        INSERT INTO fund (id, fund_code, fund_name, fund_type, created_at, updated_at)
+        VALUES ('f1', '005827', '易方达蓝筹精选混合C', '混合型', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .expect("seed fund");
+
+    let bundle = FundProfileBundle {
+        profile: parse_fund_base_info(
+            "005827",
+            r#"{"Datas":{"FCODE":"005827","SHORTNAME":"易方达蓝筹精选混合C","JJGS":"易方达基金","JJGSID":"80000229","ESTABDATE":"2018-09-05","MGREXP":"1.20%","TRUSTEXP":"0.20%","SGZT":"开放申购","SHZT":"开放赎回"}}"#,
+        )
+        .expect("parse"),
+        managers: vec![ManagerTenure {
+            manager_id: "30189741".to_string(),
+            manager_name: "张坤".to_string(),
+            start_date: NaiveDate::from_ymd_opt(2018, 9, 5).unwrap(),
+            end_date: Some(NaiveDate::from_ymd_opt(2018, 9, 15).unwrap()),
+            tenure_return_pct: Some(1.5),
+        }],
+        scale_history: vec![ScalePoint {
+            report_date: NaiveDate::from_ymd_opt(2024, 6, 30).unwrap(),
+            aum_cny: 1e9,
+        }],
+    };
+    api::fund_profile::upsert_fund_profile_bundle(&pool, "005827", "eastmoney", &bundle)
+        .await
+        .expect("upsert");
+    // 重复写入应为幂等 upsert
+    api::fund_profile::upsert_fund_profile_bundle(&pool, "005827", "eastmoney", &bundle)
+        .await
+        .expect("upsert again");
+
+    let config = api::config::ConfigStore::load();
+    let jwt = api::jwt::JwtService::from_secret("test-secret");
+    let state = AppState::new(Some(pool), config, jwt, api::db::DatabaseKind::Sqlite);
+    let app = api::service(state);
+
+    let res = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/funds/005827/")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let v = body_json(res).await;
+
+    assert_eq!(v["fund_code"], "005827");
+    assert_eq!(v["profile"]["company_name"], "易方达基金");
+    assert_eq!(v["profile"]["share_class"], "C");
+    assert_eq!(v["profile"]["inception_date"], "2018-09-05");
+    assert_eq!(v["profile"]["management_fee_pct"], 1.2);
+    assert_eq!(v["managers"].as_array().unwrap().len(), 1);
+    assert_eq!(v["managers"][0]["tenure_days"], 10);
+    assert_eq!(v["aum_history"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn upsert_profile_bundle_drops_tenures_missing_from_fresh_fetch() {
+    sqlx::any::install_default_drivers();
+
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+
+    let tenure = |id: &str, start: (i32, u32, u32)| ManagerTenure {
+        manager_id: id.to_string(),
+        manager_name: format!("经理{id}"),
+        start_date: NaiveDate::from_ymd_opt(start.0, start.1, start.2).unwrap(),
+        end_date: None,
+        tenure_return_pct: None,
+    };
+
+    let first = FundProfileBundle {
+        managers: vec![tenure("1", (2018, 9, 5)), tenure("2", (2020, 1, 1))],
+        ..Default::default()
+    };
+    api::fund_profile::upsert_fund_profile_bundle(&pool, "005827", "eastmoney", &first)
+        .await
+        .expect("upsert");
+    // 其他来源写入的任期不受 eastmoney 刷新影响
+    api::fund_profile::upsert_fund_profile_bundle(
+        &pool,
+        "005827",
+        "other",
+        &FundProfileBundle {
+            managers: vec![tenure("3", (2021, 3, 1))],
+            ..Default::default()
+        },
+    )
+    .await
+    .expect("upsert other source");
+
+    // 上游更正了经理 2 的任职日期，并移除了旧记录
+    let fresh = FundProfileBundle {
+        managers: vec![tenure("1", (2018, 9, 5)), tenure("2", (2020, 2, 1))],
+        ..Default::default()
+    };
+    api::fund_profile::upsert_fund_profile_bundle(&pool, "005827", "eastmoney", &fresh)
+        .await
+        .expect("upsert fresh");
+
+    let today = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+    let got: Vec<(String, String)> =
+        api::fund_profile::load_manager_tenures(&pool, "005827", today)
+            .await
+            .expect("load")
+            .into_iter()
+            .map(|m| (m.manager_id, m.start_date))
+            .collect();
+    assert_eq!(
+        got,
+        vec![
+            ("3".to_string(), "2021-03-01".to_string()),
+            ("2".to_string(), "2020-02-01".to_string()),
+            ("1".to_string(), "2018-09-05".to_string()),
+        ]
+    );
+
+    // 经理表抓取失败（为空）时不删除已有任期
+    api::fund_profile::upsert_fund_profile_bundle(
+        &pool,
+        "005827",
+        "eastmoney",
+        &FundProfileBundle::default(),
+    )
+    .await
+    .expect("upsert empty");
+    let n = api::fund_profile::load_manager_tenures(&pool, "005827", today)
+        .await
+        .expect("load")
+        .len();
+    assert_eq!(n, 3);
+}
+
+#[tokio::test]
+async fn enqueue_profile_tick_seeds_once_per_fund() {
+    sqlx::any::install_default_drivers();
+
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+
+    for (id, code) in [("f1", "000001"), ("f2", "000002")] {
+        sqlx::query(
+            r#"
+            INSERT INTO fund (id, fund_code, fund_name, created_at, updated_at)
+            VALUES ($1, $2, 'X', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            "#,
+        )
+        .bind(id)
+        .bind(code)
+        .execute(&pool)
+        .await
+        .expect("seed fund");
+    }
+
+    let n1 = api::crawl::scheduler::enqueue_profile_tick(&pool, 10, "tiantian")
+        .await
+        .expect("enqueue");
+    assert_eq!(n1, 2);
+    let n2 = api::crawl::scheduler::enqueue_profile_tick(&pool, 10, "tiantian")
+        .await
+        .expect("enqueue");
+    assert_eq!(n2, 0);
+}
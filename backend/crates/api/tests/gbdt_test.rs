@@ -0,0 +1,181 @@
+use api::forecast::model::{ForecastModel, train_forecast_model};
+use api::ml::gbdt::{GbdtObjective, GbdtTrainConfig, train_gbdt};
+use api::ml::logreg::{LogRegModel, LogRegTrainConfig, train_logreg};
+use api::ml::model::{Classifier, ModelKind};
+
+fn cfg() -> GbdtTrainConfig {
+    GbdtTrainConfig {
+        n_trees: 100,
+        learning_rate: 0.3,
+        max_depth: 3,
+        max_leaves: 8,
+        min_samples_leaf: 5,
+        max_bins: 32,
+        l2: 1.0,
+        validation_fraction: 0.0,
+        early_stopping_rounds: 0,
+    }
+}
+
+/// 确定性伪随机数（0..1）。
+fn lcg(seed: &mut u64) -> f64 {
+    *seed = seed
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+    ((*seed >> 11) as f64) / ((1_u64 << 53) as f64)
+}
+
+fn grid() -> Vec<Vec<f64>> {
+    let mut x = Vec::new();
+    for i in 0..20 {
+        for j in 0..20 {
+            x.push(vec![i as f64 / 19.0, j as f64 / 19.0]);
+        }
+    }
+    x
+}
+
+fn accuracy(p: impl Fn(&[f64]) -> f64, x: &[Vec<f64>], y: &[f64]) -> f64 {
+    let hits = x
+        .iter()
+        .zip(y)
+        .filter(|(row, yy)| (p(row) >= 0.5) == (**yy >= 0.5))
+        .count();
+    hits as f64 / x.len() as f64
+}
+
+#[test]
+fn gbdt_learns_interaction_that_logreg_cannot() {
+    let x = grid();
+    // 非对称异或：标签取决于两个特征的组合，线性边界分不开。
+    let y: Vec<f64> = x
+        .iter()
+        .map(|r| {
+            if (r[0] > 0.3) != (r[1] > 0.6) {
+                1.0
+            } else {
+                0.0
+            }
+        })
+        .collect();
+
+    let gbdt = train_gbdt(&x, &y, GbdtObjective::Logistic, &cfg()).expect("train gbdt");
+    let logreg = train_logreg(
+        &x,
+        &y,
+        &LogRegTrainConfig {
+            learning_rate: 0.5,
+            epochs: 600,
+            l2: 0.1,
+        },
+    )
+    .expect("train logreg");
+
+    let acc_gbdt = accuracy(|r| gbdt.predict_proba(r).unwrap(), &x, &y);
+    let acc_logreg = accuracy(|r| logreg.predict_proba(r).unwrap(), &x, &y);
+    assert!(acc_gbdt > 0.95, "gbdt accuracy {acc_gbdt}");
+    assert!(acc_logreg < acc_gbdt - 0.15, "logreg accuracy {acc_logreg}");
+    // max_leaves 个叶子的二叉树最多 2 * max_leaves - 1 个节点。
+    assert!(gbdt.trees.iter().all(|t| t.len() < 2 * cfg().max_leaves));
+    assert!(gbdt.predict_proba(&[0.5]).is_none());
+}
+
+#[test]
+fn gbdt_regression_fits_nonlinear_target() {
+    let x = grid();
+    let y: Vec<f64> = x.iter().map(|r| r[0] * r[1]).collect();
+    let model = train_gbdt(&x, &y, GbdtObjective::SquaredError, &cfg()).expect("train");
+    assert!(model.predict_proba(&x[0]).is_none());
+
+    let mean = y.iter().sum::<f64>() / y.len() as f64;
+    let var = y.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / y.len() as f64;
+    let mse = x
+        .iter()
+        .zip(&y)
+        .map(|(r, yy)| (model.predict_raw(r).unwrap() - yy).powi(2))
+        .sum::<f64>()
+        / y.len() as f64;
+    assert!(mse < var * 0.05, "mse {mse} var {var}");
+}
+
+#[test]
+fn gbdt_early_stopping_truncates_on_noise() {
+    let mut seed = 7;
+    let x: Vec<Vec<f64>> = (0..400)
+        .map(|_| vec![lcg(&mut seed), lcg(&mut seed)])
+        .collect();
+    let y: Vec<f64> = (0..400)
+        .map(|_| if lcg(&mut seed) > 0.5 { 1.0 } else { 0.0 })
+        .collect();
+    let cfg = GbdtTrainConfig {
+        validation_fraction: 0.25,
+        early_stopping_rounds: 5,
+        ..cfg()
+    };
+    let model = train_gbdt(&x, &y, GbdtObjective::Logistic, &cfg).expect("train");
+    assert!(
+        model.trees.len() < cfg.n_trees,
+        "trees {}",
+        model.trees.len()
+    );
+}
+
+#[test]
+fn classifier_json_is_tagged_and_reads_legacy_logreg() {
+    let x = grid();
+    let y: Vec<f64> = x
+        .iter()
+        .map(|r| if r[0] > 0.5 { 1.0 } else { 0.0 })
+        .collect();
+    let model = Classifier::Gbdt(train_gbdt(&x, &y, GbdtObjective::Logistic, &cfg()).unwrap());
+    let json = serde_json::to_string(&model).expect("serialize");
+    assert!(json.contains("\"model_type\":\"gbdt\""));
+    let back = Classifier::from_json(&json).expect("deserialize");
+    assert_eq!(back.kind(), ModelKind::Gbdt);
+    assert_eq!(
+        back.predict_proba(&[0.9, 0.1]),
+        model.predict_proba(&[0.9, 0.1])
+    );
+
+    let legacy = serde_json::to_string(&LogRegModel {
+        weights: vec![1.0, 0.0],
+        bias: 0.0,
+        mean: vec![0.0, 0.0],
+        std: vec![1.0, 1.0],
+    })
+    .unwrap();
+    let back = Classifier::from_json(&legacy).expect("legacy");
+    assert_eq!(back.kind(), ModelKind::Linear);
+    assert!(back.predict_proba(&[1.0, 0.0]).unwrap() > 0.5);
+}
+
+#[test]
+fn model_kind_parses_aliases() {
+    assert_eq!(ModelKind::parse("GBDT"), Some(ModelKind::Gbdt));
+    assert_eq!(ModelKind::parse("logreg"), Some(ModelKind::Linear));
+    assert_eq!(ModelKind::parse("ols"), Some(ModelKind::Linear));
+    assert_eq!(ModelKind::parse("forest"), None);
+}
+
+#[test]
+fn forecast_model_columns_round_trip_for_both_kinds() {
+    let x = grid();
+    let y: Vec<f64> = x.iter().map(|r| r[0] - r[1]).collect();
+    for kind in [ModelKind::Linear, ModelKind::Gbdt] {
+        let model = train_forecast_model(kind, &x, &y).expect("train");
+        assert_eq!(model.kind(), kind);
+        let cols = model.to_columns().expect("columns");
+        let back = ForecastModel::from_columns(&cols).expect("from columns");
+        assert_eq!(back.kind(), kind);
+        assert_eq!(back.predict(&x[3]), model.predict(&x[3]));
+        assert_eq!(back.residual_sigma(), model.residual_sigma());
+    }
+    assert_eq!(
+        ForecastModel::default_name(ModelKind::Linear),
+        "global_ols_v1"
+    );
+    assert_eq!(
+        ForecastModel::default_name(ModelKind::Gbdt),
+        "global_gbdt_v1"
+    );
+}
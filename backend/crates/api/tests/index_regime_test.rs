@@ -0,0 +1,347 @@
+use axum::{body::Body, http::Request};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde_json::{Value, json};
+use sqlx::Row;
+use tower::ServiceExt;
+
+use api::ml::regime::{self, Regime, RegimeModel, most_likely, observations};
+use api::sim::engine;
+use api::state::AppState;
+
+async fn setup() -> sqlx::AnyPool {
+    sqlx::any::install_default_drivers();
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+    pool
+}
+
+async fn json_body(res: axum::response::Response) -> Value {
+    let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+        .await
+        .expect("read body");
+    serde_json::from_slice(&bytes).unwrap_or(Value::Null)
+}
+
+fn date(i: usize) -> String {
+    let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+    (start + chrono::Duration::days(i as i64))
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+/// 牛（稳步上涨）→ 熊（大幅下跌、高波动）→ 震荡（低波动横盘），各 200 日。
+fn synthetic_closes(days: usize) -> Vec<(String, f64)> {
+    let mut seed = 7_u64;
+    let mut noise = || {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((seed >> 11) as f64) / ((1_u64 << 53) as f64) - 0.5
+    };
+    let mut close = 3000.0;
+    (0..days)
+        .map(|i| {
+            let r = match i / 200 {
+                0 => 0.004 + 0.01 * noise(),
+                1 => -0.005 + 0.04 * noise(),
+                _ => 0.006 * noise(),
+            };
+            close *= 1.0 + r;
+            (date(i), close)
+        })
+        .collect()
+}
+
+async fn seed_index(pool: &sqlx::AnyPool, closes: &[(String, f64)]) {
+    for (d, c) in closes {
+        sqlx::query(
+            r#"
+            INSERT INTO index_daily_price (id, index_code, source_name, trade_date, close, created_at, updated_at)
+            VALUES ($1, '1.000001', 'eastmoney', $2, $3, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            "#,
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(d)
+        .bind(format!("{c:.4}"))
+        .execute(pool)
+        .await
+        .expect("seed index");
+    }
+}
+
+#[test]
+fn hmm_separates_bull_bear_and_sideways_without_lookahead() {
+    let obs = observations(&synthetic_closes(600));
+    assert_eq!(obs.len(), 600 - regime::REGIME_WINDOW);
+    let values: Vec<[f64; 2]> = obs.iter().map(|(_, o)| *o).collect();
+    assert!(RegimeModel::fit(&values[..regime::MIN_OBSERVATIONS - 1]).is_none());
+
+    let model = RegimeModel::fit(&values).expect("fit");
+    let probas = model.filter(&values);
+    let at = |day: usize| most_likely(&probas[day - regime::REGIME_WINDOW]);
+    assert_eq!(at(150), Regime::Bull);
+    assert_eq!(at(350), Regime::Bear);
+    assert_eq!(at(550), Regime::Sideways);
+    for p in &probas {
+        assert!((p.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+    }
+
+    // 滤波概率只依赖截至当日的观测。
+    let partial = model.filter(&values[..300]);
+    assert_eq!(partial[299], probas[299]);
+    let states = model.state_summary();
+    assert_eq!(states[0]["regime"], "bull");
+    assert!(states[0]["trend_20d"].as_f64().unwrap() > states[1]["trend_20d"].as_f64().unwrap());
+}
+
+#[tokio::test]
+async fn regime_is_stored_incrementally_and_exposed_as_feature_and_route() {
+    let pool = setup().await;
+    let closes = synthetic_closes(600);
+    seed_index(&pool, &closes[..500]).await;
+
+    let config = api::config::ConfigStore::load();
+    let jwt = api::jwt::JwtService::from_secret("test-secret");
+    let state = AppState::new(
+        Some(pool.clone()),
+        config.clone(),
+        jwt,
+        api::db::DatabaseKind::Sqlite,
+    );
+    let token = state.jwt().issue_access_token("1");
+    let app = api::service(state);
+
+    // 首次请求时即时拟合。
+    let res = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/api/indexes/regime?start_date=2024-05-01&end_date=2024-06-01")
+                .header("Authorization", format!("Bearer {token}"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let v = json_body(res).await;
+    assert_eq!(v["index_code"], "1.000001");
+    assert_eq!(v["model"]["sample_count"], 480);
+    assert_eq!(v["model"]["states"].as_array().unwrap().len(), 3);
+    let points = v["points"].as_array().unwrap();
+    assert_eq!(points.len(), 32);
+    assert_eq!(points[0]["date"], "2024-05-01");
+    assert_eq!(points[0]["regime"], "bull");
+    assert_eq!(v["latest"]["date"], "2024-06-01");
+
+    let stored_before: f64 =
+        sqlx::query("SELECT p_bear FROM index_regime WHERE trade_date = '2024-08-01'")
+            .fetch_one(&pool)
+            .await
+            .expect("row")
+            .get("p_bear");
+
+    // 新日线到达后任务只追加新日期，已写入的概率不变。
+    seed_index(&pool, &closes[500..]).await;
+    api::tasks::enqueue_task_job(
+        &pool,
+        "index_regime_compute",
+        &json!({ "fetch": false }),
+        100,
+        None,
+    )
+    .await
+    .expect("enqueue");
+    api::tasks::run_due_task_jobs(&pool, 10)
+        .await
+        .expect("run tasks");
+    let status: String =
+        sqlx::query("SELECT status FROM task_job WHERE task_type = 'index_regime_compute'")
+            .fetch_one(&pool)
+            .await
+            .expect("job")
+            .get("status");
+    assert_eq!(status, "done");
+    let n: i64 = sqlx::query("SELECT COUNT(*) as n FROM index_regime")
+        .fetch_one(&pool)
+        .await
+        .unwrap()
+        .get("n");
+    assert_eq!(n, 580);
+    let stored_after: f64 =
+        sqlx::query("SELECT p_bear FROM index_regime WHERE trade_date = '2024-08-01'")
+            .fetch_one(&pool)
+            .await
+            .expect("row")
+            .get("p_bear");
+    assert_eq!(stored_before, stored_after);
+
+    let res = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/api/indexes/regime?index_code=9.999999")
+                .header("Authorization", format!("Bearer {token}"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+
+    // 特征库：基金行附带同日的状态概率；开启配置后进入板块信号模型的输入。
+    sqlx::query(
+        r#"
+        INSERT INTO fund (id, fund_code, fund_name, fund_type, created_at, updated_at)
+        VALUES ('fund-1', '000001', '基金000001', '股票型', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .expect("seed fund");
+    for i in 400..430 {
+        sqlx::query(
+            r#"
+            INSERT INTO fund_nav_history (id, source_name, fund_id, nav_date, unit_nav, created_at, updated_at)
+            VALUES ($1, 'tiantian', 'fund-1', $2, '1.0000', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            "#,
+        )
+        .bind(format!("nav-{i}"))
+        .bind(date(i))
+        .execute(&pool)
+        .await
+        .expect("seed nav");
+    }
+    api::features::store::materialize_fund(&pool, "000001", "tiantian")
+        .await
+        .expect("materialize");
+    let rows = api::features::store::load_rows(&pool, "000001", "tiantian", None, None, Some(1))
+        .await
+        .expect("rows");
+    let (d, values) = rows.last().expect("row");
+    let bear: f64 = sqlx::query("SELECT p_bear FROM index_regime WHERE trade_date = $1")
+        .bind(d)
+        .fetch_one(&pool)
+        .await
+        .expect("regime row")
+        .get("p_bear");
+    assert_eq!(values["regime_bear_p"], bear);
+    assert!(values.contains_key("regime_bull_p"));
+
+    assert!(!api::features::ml_signal_features(&config).contains(&"regime_bear_p"));
+    config.set_bool("ml_signal_regime_features", true);
+    assert!(api::features::ml_signal_features(&config).ends_with(api::features::REGIME_FEATURES));
+}
+
+#[tokio::test]
+async fn snapshot_strategy_stays_in_cash_while_bear_probability_is_high() {
+    let pool = setup().await;
+    sqlx::query(
+        r#"
+        INSERT INTO auth_user (id, password, is_superuser, username, email, is_staff, is_active, date_joined)
+        VALUES (1, 'x', 1, 'admin', 'admin@example.com', 1, 1, CURRENT_TIMESTAMP)
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .expect("seed auth_user");
+    sqlx::query(
+        r#"
+        INSERT INTO fund (id, fund_code, fund_name, fund_type, created_at, updated_at)
+        VALUES ('fund-a', 'AAA', 'A', '', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .expect("seed fund");
+
+    let dates: Vec<NaiveDate> = (1..=5)
+        .map(|d| NaiveDate::from_ymd_opt(2026, 2, d).unwrap())
+        .collect();
+    for (i, d) in dates.iter().enumerate() {
+        let ds = d.format("%Y-%m-%d").to_string();
+        sqlx::query(
+            r#"
+            INSERT INTO fund_nav_history (id, source_name, fund_id, nav_date, unit_nav, created_at, updated_at)
+            VALUES ($1, 'tiantian', 'fund-a', $2, $3, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            "#,
+        )
+        .bind(format!("nav-{i}"))
+        .bind(&ds)
+        .bind(format!("{:.2}", 1.0 + 0.02 * i as f64))
+        .execute(&pool)
+        .await
+        .expect("seed nav");
+        sqlx::query(
+            r#"
+            INSERT INTO fund_signal_snapshot (fund_code, peer_code, as_of_date, magic_rebound_proba_20t)
+            VALUES ('AAA', $1, $2, 0.9)
+            "#,
+        )
+        .bind(api::ml::train::PEER_CODE_ALL)
+        .bind(&ds)
+        .execute(&pool)
+        .await
+        .expect("seed snapshot");
+        // 前三天熊市概率高。
+        let p_bear = if i < 3 { 0.9 } else { 0.1 };
+        sqlx::query(
+            r#"
+            INSERT INTO index_regime (index_code, source_name, trade_date, regime, p_bull, p_bear, p_sideways)
+            VALUES ('1.000001', 'eastmoney', $1, 'bear', $2, $3, 0.0)
+            "#,
+        )
+        .bind(&ds)
+        .bind(1.0 - p_bear)
+        .bind(p_bear)
+        .execute(&pool)
+        .await
+        .expect("seed regime");
+    }
+
+    let run_id = engine::backtest_create_auto_topk_snapshot(
+        &pool,
+        1,
+        "regime",
+        "tiantian",
+        dates[0],
+        dates[4],
+        Decimal::from(1000),
+        0.0,
+        0.0,
+        0,
+        engine::AutoTopkSnapshotParams {
+            top_k: 1,
+            rebalance_every: 5,
+            weights: None,
+            regime_filter: Some(engine::RegimeFilter {
+                index_code: "1.000001".to_string(),
+                source_name: None,
+                max_bear_proba: 0.5,
+            }),
+        },
+    )
+    .await
+    .expect("create");
+    engine::backtest_run(&pool, &run_id).await.expect("run");
+
+    let rows = sqlx::query(
+        "SELECT positions_value FROM sim_daily_equity WHERE run_id = $1 ORDER BY date ASC",
+    )
+    .bind(&run_id)
+    .fetch_all(&pool)
+    .await
+    .expect("equity rows");
+    let positions: Vec<f64> = rows.iter().map(|r| r.get("positions_value")).collect();
+    assert_eq!(positions.len(), 5);
+    assert!(positions[..3].iter().all(|v| *v == 0.0), "{positions:?}");
+    assert!(positions[3] > 0.0, "{positions:?}");
+}
@@ -0,0 +1,468 @@
+use std::net::SocketAddr;
+
+use axum::{body::Body, extract::ConnectInfo, http::Request};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+use api::state::AppState;
+
+async fn body_json(res: axum::response::Response) -> Value {
+    let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+        .await
+        .expect("read body");
+    serde_json::from_slice(&bytes).unwrap_or(Value::Null)
+}
+
+/// 经本机反向代理转发的请求（127.0.0.1 默认受信任）。
+fn post(uri: &str, token: Option<&str>, body: Value) -> Request<Body> {
+    post_from(uri, token, body, "127.0.0.1:40000")
+}
+
+fn post_from(uri: &str, token: Option<&str>, body: Value, peer: &str) -> Request<Body> {
+    let mut builder = Request::builder()
+        .method("POST")
+        .uri(uri)
+        .header("Content-Type", "application/json")
+        .header("X-Forwarded-For", "203.0.113.7")
+        .extension(ConnectInfo(peer.parse::<SocketAddr>().unwrap()));
+    if let Some(token) = token {
+        builder = builder.header("Authorization", format!("Bearer {token}"));
+    }
+    builder.body(Body::from(body.to_string())).unwrap()
+}
+
+fn current_code(secret: &str) -> String {
+    let key = api::totp::base32_decode(secret).expect("base32 secret");
+    api::totp::code_at(
+        &key,
+        api::totp::current_step(chrono::Utc::now().timestamp()),
+    )
+}
+
+async fn setup() -> (sqlx::AnyPool, AppState) {
+    // 本文件登录次数多，降低 PBKDF2 迭代次数以免测试过慢（同一进程内各用例取值一致）。
+    unsafe {
+        std::env::set_var("DJANGO_PBKDF2_ITERATIONS", "1000");
+    }
+    sqlx::any::install_default_drivers();
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+
+    sqlx::query(
+        r#"
+        INSERT INTO auth_user (id, password, is_superuser, username, email, is_staff, is_active, date_joined)
+        VALUES (1, $1, 0, 'alice', '', 0, 1, CURRENT_TIMESTAMP)
+        "#,
+    )
+    .bind(api::django_password::hash_password("correct-horse"))
+    .execute(&pool)
+    .await
+    .expect("seed user");
+
+    let config = api::config::ConfigStore::load();
+    config.set_i64("login_max_failures_per_user", Some(3));
+    config.set_i64("login_max_failures_per_ip", Some(20));
+    config.set_i64("login_lockout_seconds", Some(900));
+    let jwt = api::jwt::JwtService::from_secret("test-secret");
+    let state = AppState::new(
+        Some(pool.clone()),
+        config,
+        jwt,
+        api::db::DatabaseKind::Sqlite,
+    );
+    (pool, state)
+}
+
+#[tokio::test]
+async fn repeated_login_failures_lock_the_account() {
+    let (pool, state) = setup().await;
+    let app = api::app(state);
+    let login = |password: &str| {
+        post(
+            "/api/auth/login",
+            None,
+            json!({ "username": "alice", "password": password }),
+        )
+    };
+
+    for _ in 0..2 {
+        let res = app.clone().oneshot(login("wrong")).await.unwrap();
+        assert_eq!(res.status(), 401);
+    }
+    // 第三次失败触发锁定；锁定期内连正确密码也不校验。
+    let res = app.clone().oneshot(login("wrong")).await.unwrap();
+    assert_eq!(res.status(), 401);
+    let res = app.clone().oneshot(login("correct-horse")).await.unwrap();
+    assert_eq!(res.status(), 429);
+    let retry: i64 = res
+        .headers()
+        .get("retry-after")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+        .expect("retry-after header");
+    assert!(retry > 0 && retry <= 900);
+    assert_eq!(body_json(res).await["retry_after"], json!(retry));
+
+    let locked: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM audit_event WHERE action = 'auth.login.locked'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(locked, 1);
+
+    // 锁定到期后可登录，且成功登录清零用户名计数。
+    sqlx::query("UPDATE auth_login_failure SET locked_until = NULL")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let res = app.clone().oneshot(login("correct-horse")).await.unwrap();
+    assert_eq!(res.status(), 200);
+    assert!(body_json(res).await["access_token"].is_string());
+    let user_rows: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM auth_login_failure WHERE key = 'user:alice'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(user_rows, 0);
+    let ip_failures: i64 =
+        sqlx::query_scalar("SELECT failures FROM auth_login_failure WHERE key = 'ip:203.0.113.7'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(ip_failures, 3);
+
+    // 不受信任的对端伪造 X-Forwarded-For：按连接地址计数。
+    let res = app
+        .clone()
+        .oneshot(post_from(
+            "/api/auth/login",
+            None,
+            json!({ "username": "alice", "password": "wrong" }),
+            "198.51.100.20:50000",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+    let spoofed: i64 = sqlx::query_scalar(
+        "SELECT failures FROM auth_login_failure WHERE key = 'ip:198.51.100.20'",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(spoofed, 1);
+}
+
+#[test]
+fn client_ip_honours_forwarded_headers_only_from_trusted_proxies() {
+    use api::client_ip::{TrustedProxies, resolve};
+    use axum::http::HeaderMap;
+
+    let trusted = TrustedProxies::parse("127.0.0.1, 10.0.0.0/8, ::1, bogus");
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "x-forwarded-for",
+        "198.51.100.1, 203.0.113.7, 10.1.2.3".parse().unwrap(),
+    );
+    let ip = |peer: &str, headers: &HeaderMap| {
+        resolve(Some(peer.parse().unwrap()), headers, &trusted).map(|ip| ip.to_string())
+    };
+
+    // 链路中最右侧的不受信任地址才是客户端；左侧地址可由客户端伪造。
+    assert_eq!(ip("127.0.0.1", &headers).as_deref(), Some("203.0.113.7"));
+    assert_eq!(
+        ip("::ffff:10.0.0.5", &headers).as_deref(),
+        Some("203.0.113.7")
+    );
+    assert_eq!(ip("192.0.2.9", &headers).as_deref(), Some("192.0.2.9"));
+
+    let mut real_ip = HeaderMap::new();
+    real_ip.insert("x-real-ip", "203.0.113.8".parse().unwrap());
+    assert_eq!(ip("::1", &real_ip).as_deref(), Some("203.0.113.8"));
+    assert_eq!(
+        ip("127.0.0.1", &HeaderMap::new()).as_deref(),
+        Some("127.0.0.1")
+    );
+    assert_eq!(resolve(None, &headers, &trusted), None);
+}
+
+#[tokio::test]
+async fn totp_enrollment_login_and_recovery_codes() {
+    let (pool, state) = setup().await;
+    let session = state.jwt().issue_access_token("1");
+    let app = api::app(state);
+
+    let res = app
+        .clone()
+        .oneshot(post("/api/auth/2fa/setup", Some(&session), json!({})))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let setup = body_json(res).await;
+    let secret = setup["secret"].as_str().unwrap().to_string();
+    assert!(
+        setup["otpauth_uri"]
+            .as_str()
+            .unwrap()
+            .starts_with("otpauth://totp/")
+    );
+
+    // 未确认前登录不受影响；错误验证码不能启用。
+    let login = || {
+        post(
+            "/api/auth/login",
+            None,
+            json!({ "username": "alice", "password": "correct-horse" }),
+        )
+    };
+    let res = app.clone().oneshot(login()).await.unwrap();
+    assert!(body_json(res).await["access_token"].is_string());
+    let res = app
+        .clone()
+        .oneshot(post(
+            "/api/auth/2fa/enable",
+            Some(&session),
+            json!({ "code": "000000x" }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    let res = app
+        .clone()
+        .oneshot(post(
+            "/api/auth/2fa/enable",
+            Some(&session),
+            json!({ "code": current_code(&secret) }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let recovery: Vec<String> = body_json(res).await["recovery_codes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| v.as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(recovery.len(), 10);
+    let res = app
+        .clone()
+        .oneshot(post("/api/auth/2fa/setup", Some(&session), json!({})))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 409);
+    // 即使绕过上面的检查，已启用的密钥也不会被覆盖。
+    assert_eq!(api::totp::begin_enrollment(&pool, 1).await.unwrap(), None);
+    // 允许同一时间步的验证码再用于登录。
+    sqlx::query("UPDATE auth_totp SET last_step = NULL")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // 密码正确只拿到中间令牌，中间令牌不能访问接口。
+    let res = app.clone().oneshot(login()).await.unwrap();
+    assert_eq!(res.status(), 200);
+    let first = body_json(res).await;
+    assert_eq!(first["mfa_required"], json!(true));
+    assert!(first.get("access_token").is_none());
+    let mfa_token = first["mfa_token"].as_str().unwrap().to_string();
+    let res = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/auth/me")
+                .header("Authorization", format!("Bearer {mfa_token}"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+
+    let second = |code: &str| {
+        post(
+            "/api/auth/login/2fa",
+            None,
+            json!({ "mfa_token": mfa_token, "code": code }),
+        )
+    };
+    let res = app
+        .clone()
+        .oneshot(post(
+            "/api/auth/login/2fa",
+            None,
+            json!({ "mfa_token": session, "code": "123456" }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    let code = current_code(&secret);
+    let res = app.clone().oneshot(second(&code)).await.unwrap();
+    assert_eq!(res.status(), 200);
+    assert!(body_json(res).await["access_token"].is_string());
+    // 同一验证码不可重放。
+    let res = app.clone().oneshot(second(&code)).await.unwrap();
+    assert_eq!(res.status(), 401);
+
+    // 恢复码只能用一次。
+    let res = app.clone().oneshot(second(&recovery[0])).await.unwrap();
+    assert_eq!(res.status(), 200);
+    let res = app.clone().oneshot(second(&recovery[0])).await.unwrap();
+    assert_eq!(res.status(), 401);
+
+    let res = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/auth/2fa")
+                .header("Authorization", format!("Bearer {session}"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = body_json(res).await;
+    assert_eq!(status["enabled"], json!(true));
+    assert_eq!(status["recovery_codes_remaining"], json!(9));
+
+    // 前面的错误验证码已计入锁定计数，清零后单独验证关闭接口。
+    sqlx::query("DELETE FROM auth_login_failure")
+        .execute(&pool)
+        .await
+        .unwrap();
+    // 关闭需要密码 + 第二因素。
+    let res = app
+        .clone()
+        .oneshot(post(
+            "/api/auth/2fa/disable",
+            Some(&session),
+            json!({ "password": "wrong", "code": recovery[1] }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    // 密码校验与登录共用防爆破计数：累计失败达到上限后直接拒绝。
+    let mut statuses = Vec::new();
+    for password in ["wrong", "wrong", "correct-horse"] {
+        let res = app
+            .clone()
+            .oneshot(post(
+                "/api/auth/2fa/disable",
+                Some(&session),
+                json!({ "password": password, "code": recovery[1] }),
+            ))
+            .await
+            .unwrap();
+        statuses.push(res.status().as_u16());
+    }
+    assert_eq!(statuses.last(), Some(&429));
+    sqlx::query("DELETE FROM auth_login_failure")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let res = app
+        .clone()
+        .oneshot(post(
+            "/api/auth/2fa/disable",
+            Some(&session),
+            json!({ "password": "correct-horse", "code": recovery[1] }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let res = app.clone().oneshot(login()).await.unwrap();
+    assert!(body_json(res).await["access_token"].is_string());
+
+    let actions: Vec<String> = sqlx::query_scalar(
+        "SELECT action FROM audit_event WHERE action LIKE 'auth.2fa.%' ORDER BY action",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        actions,
+        vec![
+            "auth.2fa.disable",
+            "auth.2fa.enable",
+            "auth.2fa.recovery_code_used"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn totp_enable_and_recovery_regeneration_are_throttled() {
+    let (pool, state) = setup().await;
+    let session = state.jwt().issue_access_token("1");
+    let app = api::app(state);
+
+    let res = app
+        .clone()
+        .oneshot(post("/api/auth/2fa/setup", Some(&session), json!({})))
+        .await
+        .unwrap();
+    let secret = body_json(res).await["secret"].as_str().unwrap().to_string();
+
+    // 错误验证码计入锁定计数，达到上限后即使验证码正确也直接拒绝。
+    let enable = |code: &str| {
+        post(
+            "/api/auth/2fa/enable",
+            Some(&session),
+            json!({ "code": code }),
+        )
+    };
+    let mut statuses = Vec::new();
+    for code in [
+        "000000".to_string(),
+        "000000".to_string(),
+        "000000".to_string(),
+        current_code(&secret),
+    ] {
+        let res = app.clone().oneshot(enable(&code)).await.unwrap();
+        statuses.push(res.status().as_u16());
+    }
+    assert_eq!(statuses, vec![400, 400, 400, 429]);
+    assert!(!api::totp::is_enabled(&pool, 1).await.unwrap());
+
+    sqlx::query("DELETE FROM auth_login_failure")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let res = app
+        .clone()
+        .oneshot(enable(&current_code(&secret)))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    sqlx::query("UPDATE auth_totp SET last_step = NULL")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let regenerate = |code: &str| {
+        post(
+            "/api/auth/2fa/recovery-codes",
+            Some(&session),
+            json!({ "code": code }),
+        )
+    };
+    let mut statuses = Vec::new();
+    for code in [
+        "000000".to_string(),
+        "000000".to_string(),
+        "000000".to_string(),
+        current_code(&secret),
+    ] {
+        let res = app.clone().oneshot(regenerate(&code)).await.unwrap();
+        statuses.push(res.status().as_u16());
+    }
+    assert_eq!(statuses, vec![400, 400, 400, 429]);
+    assert_eq!(
+        api::totp::remaining_recovery_codes(&pool, 1).await.unwrap(),
+        10
+    );
+}
@@ -0,0 +1,184 @@
+use axum::{body::Body, http::Request};
+use serde_json::json;
+use tower::ServiceExt;
+
+use api::state::AppState;
+
+async fn body_text(res: axum::response::Response) -> String {
+    let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+        .await
+        .expect("read body");
+    String::from_utf8(bytes.to_vec()).expect("utf8")
+}
+
+#[test]
+fn crawl_counter_keys_are_split_from_the_right() {
+    assert_eq!(
+        api::metrics::parse_crawl_counter_key(
+            "crawl_nav_history_sync_tiantian_err_20261018",
+            "20261018"
+        ),
+        Some((
+            "nav_history_sync".to_string(),
+            "tiantian".to_string(),
+            "err".to_string()
+        ))
+    );
+    assert_eq!(
+        api::metrics::parse_crawl_counter_key("crawl_all_danjuan_run_20261018", "20261018"),
+        Some(("all".to_string(), "danjuan".to_string(), "run".to_string()))
+    );
+    assert_eq!(
+        api::metrics::parse_crawl_counter_key("crawl_all_danjuan_run_20261017", "20261018"),
+        None
+    );
+    assert_eq!(
+        api::metrics::parse_crawl_counter_key("crawl_run_20261018", "20261018"),
+        None
+    );
+}
+
+#[tokio::test]
+async fn metrics_endpoint_is_token_gated_and_exports_queue_and_http_stats() {
+    sqlx::any::install_default_drivers();
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+
+    sqlx::query(
+        r#"
+        INSERT INTO crawl_job (id, job_type, fund_code, source_name, priority, status)
+        VALUES ('j1', 'nav_history_sync', '000001', 'tiantian', 0, 'queued'),
+               ('j2', 'nav_history_sync', '000002', 'tiantian', 0, 'queued'),
+               ('j3', 'estimate_sync', '000001', 'danjuan', 0, 'error')
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .expect("seed crawl_job");
+
+    let day = chrono::Utc::now().format("%Y%m%d").to_string();
+    for (key, value) in [
+        (format!("crawl_nav_history_sync_tiantian_run_{day}"), "7"),
+        (format!("crawl_nav_history_sync_tiantian_err_{day}"), "2"),
+        (format!("crawl_all_tiantian_run_{day}"), "30"),
+        ("crawl_all_tiantian_run_20000101".to_string(), "999"),
+    ] {
+        sqlx::query(
+            "INSERT INTO crawl_state (key, value, updated_at) VALUES ($1, $2, CURRENT_TIMESTAMP)",
+        )
+        .bind(key)
+        .bind(value)
+        .execute(&pool)
+        .await
+        .expect("seed crawl_state");
+    }
+
+    api::tasks::enqueue_task_job(&pool, "signals_batch", &json!({}), 0, None)
+        .await
+        .expect("enqueue");
+    sqlx::query("UPDATE task_job SET created_at = '2000-01-01 00:00:00'")
+        .execute(&pool)
+        .await
+        .expect("age job");
+
+    let get = |uri: &str, token: Option<&str>| {
+        let mut b = Request::builder().uri(uri);
+        if let Some(t) = token {
+            b = b.header("Authorization", format!("Bearer {t}"));
+        }
+        b.body(Body::empty()).unwrap()
+    };
+
+    // 未配置令牌：端点不开放。
+    let config = api::config::ConfigStore::load();
+    let jwt = api::jwt::JwtService::from_secret("test-secret");
+    let state = AppState::new(
+        Some(pool.clone()),
+        config,
+        jwt,
+        api::db::DatabaseKind::Sqlite,
+    );
+    let res = api::app(state.clone())
+        .oneshot(get("/metrics", None))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+
+    state.config().set_i64("crawl_daily_run_limit", Some(100));
+    state
+        .config()
+        .set_string("metrics_token", Some("s3cret".to_string()));
+    state
+        .metrics()
+        .record_source_probe("tiantian", true, Some(120));
+    let app = api::app(state);
+
+    let res = app.clone().oneshot(get("/metrics", None)).await.unwrap();
+    assert_eq!(res.status(), 401);
+    let res = app
+        .clone()
+        .oneshot(get("/metrics", Some("wrong")))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+
+    for uri in ["/api/health", "/api/health", "/api/no-such-route"] {
+        app.clone().oneshot(get(uri, None)).await.unwrap();
+    }
+
+    let res = app
+        .clone()
+        .oneshot(get("/metrics?token=s3cret", None))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert!(
+        res.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/plain; version=0.0.4")
+    );
+    let text = body_text(res).await;
+    let has = |line: &str| text.lines().any(|l| l == line);
+
+    assert!(has(
+        r#"fundval_http_requests_total{method="GET",route="/api/health",status="200"} 2"#
+    ));
+    assert!(has(
+        r#"fundval_http_request_duration_seconds_count{method="GET",route="/api/health"} 2"#
+    ));
+    assert!(text.lines().any(|l| l.starts_with(
+        r#"fundval_http_requests_total{method="GET",route="unmatched",status="404"}"#
+    )));
+    assert!(has(
+        r#"fundval_crawl_jobs{job_type="nav_history_sync",source="tiantian",status="queued"} 2"#
+    ));
+    assert!(has(
+        r#"fundval_crawl_jobs{job_type="estimate_sync",source="danjuan",status="error"} 1"#
+    ));
+    assert!(has(
+        r#"fundval_crawl_runs_today{job_type="nav_history_sync",source="tiantian",result="err"} 2"#
+    ));
+    assert!(has("fundval_crawl_daily_run_limit 100"));
+    assert!(has(r#"fundval_crawl_daily_runs{source="tiantian"} 30"#));
+    assert!(has(
+        r#"fundval_crawl_daily_limit_usage_ratio{source="tiantian"} 0.3"#
+    ));
+    assert!(has(
+        r#"fundval_task_jobs{task_type="signals_batch",status="queued"} 1"#
+    ));
+    assert!(text.lines().any(|l| {
+        l.strip_prefix(r#"fundval_task_queue_oldest_age_seconds{task_type="signals_batch"} "#)
+            .and_then(|v| v.parse::<i64>().ok())
+            .is_some_and(|age| age > 86400 * 365)
+    }));
+    assert!(has(r#"fundval_source_up{source="tiantian"} 1"#));
+    assert!(has(r#"fundval_source_latency_ms{source="tiantian"} 120"#));
+    assert!(has("fundval_db_pool_max_connections 1"));
+    assert!(text.contains("# TYPE fundval_http_request_duration_seconds histogram"));
+}
@@ -0,0 +1,351 @@
+use axum::{body::Body, http::Request};
+use serde_json::{Value, json};
+use sqlx::Row;
+use tower::ServiceExt;
+
+use api::ml::drift::{self, DriftConfig, SampleCache, ks_statistic, psi};
+use api::ml::model::ModelKind;
+use api::ml::registry::{self, NewModelVersion, ShadowReport};
+use api::state::AppState;
+
+const FUNDS: usize = 3;
+
+/// 确定性伪随机数（-0.5..0.5）。
+fn lcg(seed: &mut u64) -> f64 {
+    *seed = seed
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+    ((*seed >> 11) as f64) / ((1_u64 << 53) as f64) - 0.5
+}
+
+#[test]
+fn psi_and_ks_separate_shifted_distributions() {
+    let mut seed = 3;
+    let a: Vec<f64> = (0..2000).map(|_| lcg(&mut seed)).collect();
+    let b: Vec<f64> = (0..2000).map(|_| lcg(&mut seed)).collect();
+    let shifted: Vec<f64> = b.iter().map(|v| v + 0.3).collect();
+
+    let same_psi = psi(&a, &b, drift::PSI_BINS).unwrap();
+    let same_ks = ks_statistic(&a, &b).unwrap();
+    assert!(same_psi < 0.05, "{same_psi}");
+    assert!(same_ks < 0.06, "{same_ks}");
+    assert!(psi(&a, &shifted, drift::PSI_BINS).unwrap() > 0.25);
+    let ks = ks_statistic(&a, &shifted).unwrap();
+    assert!((ks - 0.3).abs() < 0.05, "{ks}");
+
+    // 离散特征：重复切点合并后仍可比较。
+    let flags: Vec<f64> = (0..100).map(|i| (i % 2) as f64).collect();
+    assert!(psi(&flags, &flags, drift::PSI_BINS).unwrap().abs() < 1e-12);
+    assert_eq!(psi(&[], &a, drift::PSI_BINS), None);
+    assert_eq!(ks_statistic(&a, &[]), None);
+}
+
+/// 在 `start` 之后追加 `days` 天净值，日收益波动为 `vol`。
+async fn seed_navs(pool: &sqlx::AnyPool, n: usize, start: usize, days: usize, vol: f64) {
+    let base = chrono::NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+    let mut seed = 100 + (n * 1000 + start) as u64;
+    let mut nav: f64 = match start {
+        0 => 1.0,
+        _ => sqlx::query(
+            "SELECT CAST(unit_nav AS TEXT) as unit_nav FROM fund_nav_history WHERE fund_id = $1 ORDER BY nav_date DESC LIMIT 1",
+        )
+        .bind(format!("fund-{n}"))
+        .fetch_one(pool)
+        .await
+        .expect("last nav")
+        .get::<String, _>("unit_nav")
+        .parse()
+        .unwrap(),
+    };
+    for i in start..start + days {
+        nav *= 1.0 + vol * lcg(&mut seed);
+        sqlx::query(
+            r#"
+            INSERT INTO fund_nav_history (id, source_name, fund_id, nav_date, unit_nav, created_at, updated_at)
+            VALUES ($1, 'tiantian', $2, $3, $4, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            "#,
+        )
+        .bind(format!("nav-{n}-{i}"))
+        .bind(format!("fund-{n}"))
+        .bind((base + chrono::Duration::days(i as i64)).format("%Y-%m-%d").to_string())
+        .bind(format!("{nav:.6}"))
+        .execute(pool)
+        .await
+        .expect("seed nav");
+    }
+}
+
+async fn forecast_champion(pool: &sqlx::AnyPool) -> registry::ModelVersion {
+    registry::active_versions(pool, Some(registry::FAMILY_FORECAST))
+        .await
+        .expect("active versions")
+        .into_iter()
+        .next()
+        .expect("forecast champion")
+}
+
+/// 内存库 + FUNDS 只基金各 250 天净值（日波动 2%）。
+async fn setup() -> sqlx::AnyPool {
+    sqlx::any::install_default_drivers();
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+
+    for n in 0..FUNDS {
+        sqlx::query(
+            r#"
+            INSERT INTO fund (id, fund_code, fund_name, fund_type, created_at, updated_at)
+            VALUES ($1, $2, $3, '股票型', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            "#,
+        )
+        .bind(format!("fund-{n}"))
+        .bind(format!("{:06}", n + 1))
+        .bind(format!("基金{n}"))
+        .execute(&pool)
+        .await
+        .expect("seed fund");
+        seed_navs(&pool, n, 0, 250, 0.02).await;
+    }
+    pool
+}
+
+#[tokio::test]
+async fn drift_monitor_enqueues_retrain_once_and_records_decisions() {
+    let pool = setup().await;
+
+    // 训练冠军后立即检查：没有训练截止日之后的样本，也未过期。
+    for task_type in ["forecast_model_train", "ml_drift_monitor"] {
+        api::tasks::enqueue_task_job(&pool, task_type, &json!({}), 100, None)
+            .await
+            .expect("enqueue");
+        api::tasks::run_due_task_jobs(&pool, 10)
+            .await
+            .expect("run tasks");
+    }
+    let checks = drift::list_checks(&pool, None, None, None, 10)
+        .await
+        .expect("checks");
+    assert_eq!(checks.len(), 1);
+    assert_eq!(checks[0].family, "forecast");
+    assert_eq!(checks[0].decision, "ok");
+    assert!(checks[0].reasons.is_empty());
+    assert_eq!(checks[0].report["recent_samples"], 0);
+    assert!(checks[0].task_job_id.is_none());
+
+    // 训练后波动放大五倍：标准化后的滞后收益分布明显变宽，RMSE 也远超训练残差。
+    for n in 0..FUNDS {
+        seed_navs(&pool, n, 250, 80, 0.1).await;
+    }
+    let champion = forecast_champion(&pool).await;
+    let cfg = DriftConfig::default();
+    let mut cache = SampleCache::default();
+    let first = drift::check_version(&pool, &champion, "tiantian", &cfg, &mut cache)
+        .await
+        .expect("check");
+    assert_eq!(first.decision, "retrain");
+    assert!(
+        first.reasons.contains(&"feature_psi".to_string()),
+        "{:?}",
+        first.reasons
+    );
+    assert!(first.reasons.contains(&"calibration".to_string()));
+    assert!(first.report["calibration"]["ratio"].as_f64().unwrap() > 1.5);
+    assert_eq!(first.report["features"].as_array().unwrap().len(), 20);
+    let recent_std = first.report["features"][0]["recent_std_z"]
+        .as_f64()
+        .unwrap();
+    assert!(recent_std > 3.0, "{recent_std}");
+    let job_id = first.task_job_id.clone().expect("retrain job");
+    let job = sqlx::query("SELECT task_type, payload_json, status FROM task_job WHERE id = $1")
+        .bind(&job_id)
+        .fetch_one(&pool)
+        .await
+        .expect("retrain job row");
+    assert_eq!(job.get::<String, _>("task_type"), "forecast_model_train");
+    assert_eq!(job.get::<String, _>("status"), "queued");
+    let payload: Value = serde_json::from_str(&job.get::<String, _>("payload_json")).unwrap();
+    assert_eq!(payload["model_name"], "global_ols_v1");
+    assert_eq!(payload["lag_k"], 20);
+
+    // 重训仍在排队：不重复入队，记录指向已有任务。
+    let second = drift::check_version(&pool, &champion, "tiantian", &cfg, &mut cache)
+        .await
+        .expect("check");
+    assert_eq!(second.decision, "retrain_pending");
+    assert_eq!(second.task_job_id.as_deref(), Some(job_id.as_str()));
+    let queued: i64 = sqlx::query(
+        "SELECT COUNT(*) as n FROM task_job WHERE task_type = 'forecast_model_train' AND status = 'queued'",
+    )
+    .fetch_one(&pool)
+    .await
+    .expect("count")
+    .get("n");
+    assert_eq!(queued, 1);
+
+    // 版本过期：即使没有新数据也重训。
+    sqlx::query("UPDATE ml_model_version SET created_at = '2020-01-01 00:00:00'")
+        .execute(&pool)
+        .await
+        .expect("age version");
+    let aged = forecast_champion(&pool).await;
+    let stale = drift::check_version(
+        &pool,
+        &aged,
+        "tiantian",
+        &DriftConfig {
+            min_samples: 100_000,
+            ..cfg
+        },
+        &mut SampleCache::default(),
+    )
+    .await
+    .expect("check");
+    assert_eq!(stale.reasons, vec!["stale".to_string()]);
+    assert_eq!(stale.decision, "retrain_pending");
+    assert!(stale.report["features"].as_array().unwrap().is_empty());
+
+    // 复核接口：需要模型管理权限，可按决定过滤。
+    sqlx::query(
+        r#"
+        INSERT INTO auth_user (id, password, is_superuser, username, is_staff, is_active)
+        VALUES (1, 'x', 1, 'root', 1, 1), (2, 'x', 0, 'u', 0, 1)
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .expect("seed users");
+    let state = AppState::new(
+        Some(pool.clone()),
+        api::config::ConfigStore::load(),
+        api::jwt::JwtService::from_secret("test-secret"),
+        api::db::DatabaseKind::Sqlite,
+    );
+    let root = state.jwt().issue_access_token("1");
+    let user = state.jwt().issue_access_token("2");
+    let app = api::service(state);
+    let get = |uri: &str, token: &str| {
+        Request::builder()
+            .method("GET")
+            .uri(uri)
+            .header("Authorization", format!("Bearer {token}"))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let res = app
+        .clone()
+        .oneshot(get("/api/admin/ml/drift-checks", &user))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 403);
+
+    let res = app
+        .clone()
+        .oneshot(get("/api/admin/ml/drift-checks?decision=retrain", &root))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+        .await
+        .expect("read body");
+    let v: Value = serde_json::from_slice(&bytes).expect("json");
+    let items = v["items"].as_array().expect("items");
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["task_job_id"], json!(job_id));
+    assert_eq!(
+        items[0]["report"]["retrain"]["task_type"],
+        "forecast_model_train"
+    );
+
+    let res = app
+        .oneshot(get("/api/admin/ml/drift-checks?decision=maybe", &root))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+}
+
+#[tokio::test]
+async fn held_retrain_challenger_blocks_requeue_and_resets_age() {
+    let pool = setup().await;
+    api::tasks::enqueue_task_job(&pool, "forecast_model_train", &json!({}), 100, None)
+        .await
+        .expect("enqueue");
+    api::tasks::run_due_task_jobs(&pool, 10)
+        .await
+        .expect("run tasks");
+    for n in 0..FUNDS {
+        seed_navs(&pool, n, 250, 80, 0.1).await;
+    }
+    let champion = forecast_champion(&pool).await;
+    let cfg = DriftConfig::default();
+    let first = drift::check_version(
+        &pool,
+        &champion,
+        "tiantian",
+        &cfg,
+        &mut SampleCache::default(),
+    )
+    .await
+    .expect("check");
+    assert_eq!(first.decision, "retrain");
+
+    // 重训完成，但影子样本不足：新版本保持 challenger，冠军不变。
+    sqlx::query("UPDATE task_job SET status = 'succeeded' WHERE id = $1")
+        .bind(first.task_job_id.as_deref().unwrap())
+        .execute(&pool)
+        .await
+        .expect("finish job");
+    let shadow = ShadowReport::compare("rmse", &champion, &[0.0; 3], &[0.0; 3], &[0.0; 3]);
+    let held = registry::register(
+        &pool,
+        NewModelVersion {
+            family: registry::FAMILY_FORECAST,
+            model_key: champion.model_key.clone(),
+            model_type: ModelKind::Linear,
+            feature_names: champion.feature_names.clone(),
+            payload: champion.payload.clone(),
+            metrics: champion.metrics.clone(),
+            train_start_date: champion.train_start_date.clone(),
+            train_end_date: champion.train_end_date.clone(),
+            sample_count: champion.sample_count,
+        },
+        &shadow,
+    )
+    .await
+    .expect("register");
+    assert_eq!(held.status, "challenger");
+
+    // 冠军本身很旧，但挑战者刚登记：不算陈旧；漂移仍在，但不再重复入队。
+    sqlx::query("UPDATE ml_model_version SET created_at = '2020-01-01 00:00:00' WHERE id = $1")
+        .bind(&champion.id)
+        .execute(&pool)
+        .await
+        .expect("age champion");
+    let champion = forecast_champion(&pool).await;
+    let second = drift::check_version(
+        &pool,
+        &champion,
+        "tiantian",
+        &cfg,
+        &mut SampleCache::default(),
+    )
+    .await
+    .expect("check");
+    assert_eq!(second.decision, "retrain_held");
+    assert!(second.task_job_id.is_none());
+    assert_eq!(second.report["held_challenger_id"], json!(held.id));
+    assert_eq!(second.report["age_days"], 0);
+    assert!(!second.reasons.contains(&"stale".to_string()));
+    assert!(second.reasons.contains(&"calibration".to_string()));
+    let jobs: i64 =
+        sqlx::query("SELECT COUNT(*) as n FROM task_job WHERE task_type = 'forecast_model_train'")
+            .fetch_one(&pool)
+            .await
+            .expect("count")
+            .get("n");
+    assert_eq!(jobs, 2);
+}
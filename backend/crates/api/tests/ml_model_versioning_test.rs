@@ -0,0 +1,335 @@
+use axum::{body::Body, http::Request};
+use serde_json::{Value, json};
+use sqlx::Row;
+use tower::ServiceExt;
+
+use api::forecast::model::{ForecastModelKey, ForecastTrainingSet, train_and_register};
+use api::ml::model::ModelKind;
+use api::ml::registry::{self, NewModelVersion, ShadowDecision, ShadowReport};
+use api::state::AppState;
+
+async fn setup() -> sqlx::AnyPool {
+    sqlx::any::install_default_drivers();
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+    pool
+}
+
+async fn json_body(res: axum::response::Response) -> Value {
+    let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+        .await
+        .expect("read body");
+    serde_json::from_slice(&bytes).unwrap_or(Value::Null)
+}
+
+/// 确定性伪随机数（-0.5..0.5）。
+fn lcg(seed: &mut u64) -> f64 {
+    *seed = seed
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+    ((*seed >> 11) as f64) / ((1_u64 << 53) as f64) - 0.5
+}
+
+/// y = 0.5 * x0 + 噪声；日期从 `start_day` 起每 10 个样本前进一天。
+fn extend_set(set: &mut ForecastTrainingSet, n: usize, start_day: i64, seed: &mut u64) {
+    let base = chrono::NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+    for i in 0..n {
+        let row = vec![lcg(seed), lcg(seed), lcg(seed)];
+        set.y.push(0.5 * row[0] + 0.01 * lcg(seed));
+        set.x.push(row);
+        let d = base + chrono::Duration::days(start_day + (i / 10) as i64);
+        set.dates.push(d.format("%Y-%m-%d").to_string());
+    }
+}
+
+fn key() -> ForecastModelKey {
+    ForecastModelKey {
+        model_name: "global_ols_v1".to_string(),
+        source: "tiantian".to_string(),
+        horizon: 60,
+        lag_k: 3,
+    }
+}
+
+async fn serving_sample_count(pool: &sqlx::AnyPool) -> i64 {
+    sqlx::query("SELECT sample_count FROM forecast_model WHERE model_name = 'global_ols_v1'")
+        .fetch_one(pool)
+        .await
+        .expect("forecast_model row")
+        .get("sample_count")
+}
+
+#[tokio::test]
+async fn forecast_retrain_is_versioned_promoted_and_rolled_back() {
+    let pool = setup().await;
+    let mut seed = 11;
+    let mut set = ForecastTrainingSet::default();
+    extend_set(&mut set, 1000, 0, &mut seed);
+
+    // 无冠军：首个版本直接生效并写入服务表。
+    let v1 = train_and_register(&pool, &key(), ModelKind::Linear, &set)
+        .await
+        .expect("v1");
+    assert_eq!(v1.version, 1);
+    assert_eq!(v1.status, "champion");
+    assert_eq!(v1.shadow["reason"], "no_champion");
+    assert_eq!(v1.train_start_date.as_deref(), Some("2025-01-01"));
+    assert_eq!(v1.train_end_date.as_deref(), Some("2025-04-10"));
+    assert_eq!(
+        v1.feature_set_hash,
+        registry::feature_set_hash(&v1.feature_names)
+    );
+    assert_eq!(serving_sample_count(&pool).await, 1000);
+
+    // 新数据上同配置重训：在冠军没见过的样本上影子评估，不差于冠军则晋升。
+    extend_set(&mut set, 300, 200, &mut seed);
+    let v2 = train_and_register(&pool, &key(), ModelKind::Linear, &set)
+        .await
+        .expect("v2");
+    assert_eq!(v2.version, 2);
+    assert_eq!(v2.shadow["after_date"], "2025-04-10");
+    assert_eq!(v2.shadow["samples"], 300);
+    assert_eq!(v2.status, "champion", "shadow: {}", v2.shadow);
+    assert_eq!(serving_sample_count(&pool).await, 1300);
+    let old = registry::get_version(&pool, &v1.id).await.unwrap().unwrap();
+    assert_eq!(old.status, "retired");
+
+    sqlx::query(
+        r#"
+        INSERT INTO auth_user (id, password, is_superuser, username, is_staff, is_active)
+        VALUES (1, 'x', 1, 'root', 1, 1), (2, 'x', 0, 'u', 0, 1)
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .expect("seed users");
+    let config = api::config::ConfigStore::load();
+    let jwt = api::jwt::JwtService::from_secret("test-secret");
+    let state = AppState::new(
+        Some(pool.clone()),
+        config,
+        jwt,
+        api::db::DatabaseKind::Sqlite,
+    );
+    let root = state.jwt().issue_access_token("1");
+    let user = state.jwt().issue_access_token("2");
+    let app = api::service(state);
+    let call = |method: &str, uri: &str, token: &str, body: Option<Value>| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Authorization", format!("Bearer {token}"))
+            .header("Content-Type", "application/json")
+            .body(match body {
+                Some(b) => Body::from(b.to_string()),
+                None => Body::empty(),
+            })
+            .unwrap()
+    };
+
+    let res = app
+        .clone()
+        .oneshot(call("GET", "/api/admin/ml/model-versions", &user, None))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 403);
+
+    let res = app
+        .clone()
+        .oneshot(call(
+            "GET",
+            "/api/admin/ml/model-versions?family=forecast",
+            &root,
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let items = json_body(res).await["items"].as_array().cloned().unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0]["version"], 2);
+    assert_eq!(items[0]["model_key"], "global_ols_v1/tiantian/60/3");
+    assert!(items[0].get("payload").is_none());
+
+    // 回滚到 v1：服务表恢复为 v1 的模型。
+    let rollback = json!({ "family": "forecast", "model_key": key().registry_key() });
+    let res = app
+        .clone()
+        .oneshot(call(
+            "POST",
+            "/api/admin/ml/rollback",
+            &root,
+            Some(rollback),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(json_body(res).await["version"], 1);
+    let active = registry::active_version(&pool, registry::FAMILY_FORECAST, &key().registry_key())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(active.id, v1.id);
+    assert_eq!(serving_sample_count(&pool).await, 1000);
+
+    let audit: i64 = sqlx::query(
+        "SELECT COUNT(*) as n FROM audit_event WHERE action = 'ml.model.rollback' AND target_id = $1",
+    )
+    .bind(&v1.id)
+    .fetch_one(&pool)
+    .await
+    .unwrap()
+    .get("n");
+    assert_eq!(audit, 1);
+
+    let res = app
+        .clone()
+        .oneshot(call(
+            "POST",
+            "/api/admin/ml/rollback",
+            &root,
+            Some(json!({ "family": "forecast", "model_key": "missing/x/1/1" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 409);
+}
+
+#[tokio::test]
+async fn worse_challenger_is_rejected_until_promoted_manually() {
+    let pool = setup().await;
+    let mut seed = 5;
+    let mut set = ForecastTrainingSet::default();
+    extend_set(&mut set, 500, 0, &mut seed);
+    let champion = train_and_register(&pool, &key(), ModelKind::Linear, &set)
+        .await
+        .expect("champion");
+
+    let y = vec![1.0; registry::MIN_SHADOW_SAMPLES];
+    let shadow = ShadowReport::compare(
+        "rmse",
+        &champion,
+        &y,
+        &vec![1.0; y.len()],
+        &vec![0.0; y.len()],
+    );
+    assert_eq!(shadow.decision, ShadowDecision::Reject);
+    let too_few = ShadowReport::compare("rmse", &champion, &y[..3], &y[..3], &y[..3]);
+    assert_eq!(too_few.decision, ShadowDecision::Hold);
+
+    let challenger = registry::register(
+        &pool,
+        NewModelVersion {
+            family: registry::FAMILY_FORECAST,
+            model_key: key().registry_key(),
+            model_type: ModelKind::Linear,
+            feature_names: champion.feature_names.clone(),
+            payload: json!({
+                "model_name": "global_ols_v1",
+                "source": "tiantian",
+                "horizon": 60,
+                "lag_k": 3,
+                "columns": {
+                    "weights_json": "[0.0,0.0,0.0]",
+                    "bias": 0.0,
+                    "mean_json": "[0.0,0.0,0.0]",
+                    "std_json": "[1.0,1.0,1.0]",
+                    "residual_sigma": 0.0,
+                },
+            }),
+            metrics: json!({}),
+            train_start_date: None,
+            train_end_date: None,
+            sample_count: 7,
+        },
+        &shadow,
+    )
+    .await
+    .expect("register");
+    assert_eq!(challenger.status, "rejected");
+    assert_eq!(challenger.shadow["reason"], "worse_than_champion");
+    assert_eq!(serving_sample_count(&pool).await, 500);
+
+    // 写回服务表失败时整个晋升回滚：冠军与生效指针保持不变。
+    let broken = registry::register(
+        &pool,
+        NewModelVersion {
+            family: registry::FAMILY_FORECAST,
+            model_key: key().registry_key(),
+            model_type: ModelKind::Linear,
+            feature_names: champion.feature_names.clone(),
+            payload: json!({ "model_name": "global_ols_v1", "source": "tiantian" }),
+            metrics: json!({}),
+            train_start_date: None,
+            train_end_date: None,
+            sample_count: 9,
+        },
+        &shadow,
+    )
+    .await
+    .expect("register broken");
+    assert!(registry::promote(&pool, &broken.id).await.is_err());
+    let still = registry::active_version(&pool, registry::FAMILY_FORECAST, &key().registry_key())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(still.id, champion.id);
+    assert_eq!(still.status, "champion");
+    assert_eq!(serving_sample_count(&pool).await, 500);
+
+    // 手动晋升被拒绝的版本：原冠军转为 retired，服务表随之切换。
+    let promoted = registry::promote(&pool, &challenger.id)
+        .await
+        .expect("promote");
+    assert_eq!(promoted.status, "champion");
+    assert!(promoted.promoted_at.is_some());
+    assert_eq!(serving_sample_count(&pool).await, 7);
+    let old = registry::get_version(&pool, &champion.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(old.status, "retired");
+}
+
+#[tokio::test]
+async fn consecutive_rollbacks_step_back_through_history() {
+    let pool = setup().await;
+    let mut seed = 23;
+    let mut set = ForecastTrainingSet::default();
+    let mut ids = Vec::new();
+    for (n, start_day) in [(1000, 0), (300, 200), (300, 300)] {
+        extend_set(&mut set, n, start_day, &mut seed);
+        let v = train_and_register(&pool, &key(), ModelKind::Linear, &set)
+            .await
+            .expect("train");
+        assert_eq!(v.status, "champion", "shadow: {}", v.shadow);
+        ids.push(v.id);
+    }
+
+    // v3 -> v2 -> v1：第二次回滚不会跳回刚被撤下的 v3。
+    for expected in [2, 1] {
+        let back = registry::rollback(&pool, registry::FAMILY_FORECAST, &key().registry_key())
+            .await
+            .expect("rollback")
+            .expect("target");
+        assert_eq!(back.version, expected);
+        assert_eq!(back.status, "champion");
+    }
+    assert_eq!(serving_sample_count(&pool).await, 1000);
+
+    // 已到最早版本：无处可退。
+    let none = registry::rollback(&pool, registry::FAMILY_FORECAST, &key().registry_key())
+        .await
+        .expect("rollback");
+    assert!(none.is_none());
+    let v3 = registry::get_version(&pool, &ids[2])
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(v3.status, "retired");
+}
@@ -0,0 +1,258 @@
+use axum::{body::Body, http::Request};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+use api::ml::explain::{TOP_DRIVERS, explain, permutation_shapley};
+use api::ml::gbdt::{GbdtObjective, GbdtTrainConfig, train_gbdt};
+use api::ml::logreg::LogRegModel;
+use api::ml::model::Classifier;
+use api::state::AppState;
+
+async fn body_json(res: axum::response::Response) -> Value {
+    let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+        .await
+        .expect("read body");
+    serde_json::from_slice(&bytes).expect("json")
+}
+
+fn logit(p: f64) -> f64 {
+    (p / (1.0 - p)).ln()
+}
+
+fn names(n: usize) -> Vec<String> {
+    (0..n).map(|i| format!("f{i}")).collect()
+}
+
+#[test]
+fn linear_contributions_are_exact_and_sorted() {
+    let model = Classifier::LogReg(LogRegModel {
+        weights: vec![0.5, -2.0, 0.1, 0.0, 1.0, 0.3, 0.2],
+        bias: -0.4,
+        mean: vec![1.0; 7],
+        std: vec![2.0, 1.0, 1.0, 1.0, 0.0, 1.0, 1.0],
+    });
+    let x = vec![3.0, 2.0, 1.5, 9.0, 1.5, 1.0, 2.0];
+    let e = explain(&model, &names(7), &x).expect("explain");
+
+    assert_eq!(e.method, "linear_exact");
+    assert_eq!(e.drivers.len(), TOP_DRIVERS);
+    // f1: -2 * (2-1)/1；f4 的 std 为 0，按未标准化差值计。
+    assert_eq!(e.drivers[0].feature, "f1");
+    assert!((e.drivers[0].contribution + 2.0).abs() < 1e-12);
+    assert_eq!(e.drivers[0].baseline, 1.0);
+    let f4 = e.drivers.iter().find(|d| d.feature == "f4").unwrap();
+    assert!((f4.contribution - 0.5).abs() < 1e-12);
+    assert!((e.baseline_proba - 1.0 / (1.0 + 0.4_f64.exp())).abs() < 1e-12);
+
+    let sum: f64 = e.drivers.iter().map(|d| d.contribution).sum::<f64>() + e.other_contribution;
+    assert!((logit(e.baseline_proba) + sum - logit(e.proba)).abs() < 1e-9);
+    assert!((e.proba - model.predict_proba(&x).unwrap()).abs() < 1e-12);
+    assert!(explain(&model, &names(6), &x[..6]).is_none());
+}
+
+#[test]
+fn permutation_shapley_matches_linear_and_explains_gbdt() {
+    let w = [1.0, -0.5, 2.0];
+    let f = |v: &[f64]| Some(v.iter().zip(w.iter()).map(|(a, b)| a * b).sum::<f64>());
+    let phi = permutation_shapley(f, &[1.0, 2.0, 3.0], &[0.0, 1.0, 1.0]).unwrap();
+    for (p, expected) in phi.iter().zip([1.0, -0.5, 4.0]) {
+        assert!((p - expected).abs() < 1e-12);
+    }
+
+    // 标签只取决于 f0，f1 / f2 为噪声。
+    let mut seed = 3_u64;
+    let mut rnd = || {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((seed >> 11) as f64) / ((1_u64 << 53) as f64)
+    };
+    let mut x = Vec::new();
+    let mut y = Vec::new();
+    for _ in 0..400 {
+        let row = vec![rnd(), rnd(), rnd()];
+        y.push(if row[0] > 0.6 { 1.0 } else { 0.0 });
+        x.push(row);
+    }
+    let cfg = GbdtTrainConfig {
+        n_trees: 40,
+        learning_rate: 0.3,
+        max_depth: 3,
+        max_leaves: 8,
+        min_samples_leaf: 5,
+        max_bins: 32,
+        l2: 1.0,
+        validation_fraction: 0.0,
+        early_stopping_rounds: 0,
+    };
+    let gbdt = train_gbdt(&x, &y, GbdtObjective::Logistic, &cfg).expect("train");
+    assert_eq!(gbdt.feature_means.len(), 3);
+    let model = Classifier::Gbdt(gbdt.clone());
+
+    let e = explain(&model, &names(3), &[0.95, 0.5, 0.5]).expect("explain");
+    assert_eq!(e.method, "permutation_shapley");
+    assert_eq!(e.drivers[0].feature, "f0");
+    assert!(e.drivers[0].contribution > 0.0);
+    assert!(e.proba > e.baseline_proba);
+    let sum: f64 = e.drivers.iter().map(|d| d.contribution).sum::<f64>() + e.other_contribution;
+    assert!((logit(e.baseline_proba) + sum - logit(e.proba)).abs() < 1e-9);
+
+    // 旧模型 JSON 没有特征均值：不做归因。
+    let mut legacy = serde_json::to_value(&model).unwrap();
+    legacy.as_object_mut().unwrap().remove("feature_means");
+    let legacy = Classifier::from_json(&legacy.to_string()).expect("legacy gbdt");
+    assert!(explain(&legacy, &names(3), &[0.95, 0.5, 0.5]).is_none());
+}
+
+#[tokio::test]
+async fn signal_endpoints_carry_drivers_and_peer_baseline() {
+    sqlx::any::install_default_drivers();
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+
+    sqlx::query(
+        r#"
+        INSERT INTO fund (id, fund_code, fund_name, fund_type, created_at, updated_at)
+        VALUES ('fund-1', '000001', '基金000001', '股票型', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .expect("seed fund");
+    let start = chrono::NaiveDate::from_ymd_opt(2026, 1, 1).expect("date");
+    for i in 0..60 {
+        let d = start + chrono::Duration::days(i);
+        let nav = 1.0 + 0.1 * ((i as f64) / 5.0).sin();
+        sqlx::query(
+            r#"
+            INSERT INTO fund_nav_history (id, source_name, fund_id, nav_date, unit_nav, created_at, updated_at)
+            VALUES ($1, 'tiantian', 'fund-1', $2, $3, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            "#,
+        )
+        .bind(format!("nav-{i}"))
+        .bind(d.format("%Y-%m-%d").to_string())
+        .bind(format!("{nav:.4}"))
+        .execute(&pool)
+        .await
+        .expect("seed nav");
+    }
+
+    let feature_names_json =
+        serde_json::to_string(&["dd_mag", "ret5", "ret20", "vol20"]).expect("feature json");
+    let model_json = serde_json::to_string(&LogRegModel {
+        weights: vec![3.0, 1.0, -1.0, 0.0],
+        bias: 0.2,
+        mean: vec![0.05, 0.0, 0.0, 0.01],
+        std: vec![0.05, 0.02, 0.05, 0.01],
+    })
+    .expect("model json");
+    for (task, h) in [
+        ("dip_buy", 5_i64),
+        ("dip_buy", 20),
+        ("magic_rebound", 5),
+        ("magic_rebound", 20),
+    ] {
+        sqlx::query(
+            r#"
+            INSERT INTO ml_sector_model (
+              peer_code, task, horizon_days,
+              feature_names_json, model_json, metrics_json,
+              trained_at, created_at, updated_at
+            )
+            VALUES ($1,$2,$3,$4,$5,'{}', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            "#,
+        )
+        .bind(api::ml::train::PEER_CODE_ALL)
+        .bind(task)
+        .bind(h)
+        .bind(&feature_names_json)
+        .bind(&model_json)
+        .execute(&pool)
+        .await
+        .expect("seed model");
+    }
+
+    let config = api::config::ConfigStore::load();
+    let jwt = api::jwt::JwtService::from_secret("test-secret");
+    let state = AppState::new(
+        Some(pool.clone()),
+        config,
+        jwt,
+        api::db::DatabaseKind::Sqlite,
+    );
+    let token = state.jwt().issue_access_token("1");
+    let app = api::service(state);
+
+    let res = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/api/funds/000001/signals?source=tiantian")
+                .header("Authorization", format!("Bearer {token}"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let v = body_json(res).await;
+    let dip = &v["peers"][0]["dip_buy"];
+    let e = &dip["explain_20t"];
+    assert_eq!(e["method"], "linear_exact");
+    assert_eq!(e["proba"], dip["p_20t"]);
+    assert!((e["baseline_proba"].as_f64().unwrap() - 1.0 / (1.0 + (-0.2_f64).exp())).abs() < 1e-9);
+    let drivers = e["drivers"].as_array().unwrap();
+    assert_eq!(drivers.len(), 4);
+    let dd = drivers.iter().find(|d| d["feature"] == "dd_mag").unwrap();
+    assert_eq!(dd["baseline"], 0.05);
+    let expected = 3.0 * (dd["value"].as_f64().unwrap() - 0.05) / 0.05;
+    assert!((dd["contribution"].as_f64().unwrap() - expected).abs() < 1e-9);
+    assert!(v["peers"][0]["magic_rebound"]["explain_5t"].is_object());
+
+    // 异步批量：best_peer 同样带归因。
+    let res = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/funds/signals/batch")
+                .header("Authorization", format!("Bearer {token}"))
+                .header("Content-Type", "application/json")
+                .body(Body::from(
+                    json!({ "fund_codes": ["000001"], "source": "tiantian" }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 202);
+    let task_id = body_json(res).await["task_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    api::tasks::run_due_task_jobs(&pool, 10)
+        .await
+        .expect("run task queue");
+    let res = app
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(format!("/api/funds/signals/batch_async/{task_id}"))
+                .header("Authorization", format!("Bearer {token}"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let v = body_json(res).await;
+    let best = &v["items"][0]["best_peer"];
+    assert_eq!(best["dip_buy"]["explain_20t"], *e);
+    assert!(best["magic_rebound"]["explain_5t"]["drivers"].is_array());
+}
@@ -0,0 +1,262 @@
+use axum::{body::Body, http::Request};
+use serde_json::Value;
+use sqlx::Row;
+use tower::ServiceExt;
+
+use api::ml::outcomes::resolve_pending_outcomes;
+use api::ml::signals::realized_outcome;
+use api::state::AppState;
+
+async fn json_body(res: axum::response::Response) -> Value {
+    let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+        .await
+        .expect("read body");
+    serde_json::from_slice(&bytes).unwrap_or(Value::Null)
+}
+
+#[test]
+fn realized_outcome_uses_training_thresholds() {
+    // 5T：最高涨 3%（恰好达到 5T 阈值），第 5 日回到原位。
+    let o = realized_outcome(&[1.0, 1.01, 1.03, 1.02, 1.0, 1.0], 5).expect("outcome");
+    assert!(!o.dip_buy_success);
+    assert!(o.magic_rebound);
+    // 20T 阈值更高：同样 3% 的反弹不算。
+    let mut navs = vec![1.0, 1.03];
+    navs.extend(std::iter::repeat_n(1.01, 19));
+    let o = realized_outcome(&navs, 20).expect("outcome");
+    assert!(o.dip_buy_success);
+    assert!(!o.magic_rebound);
+    assert!(realized_outcome(&navs[..10], 20).is_none());
+}
+
+#[tokio::test]
+async fn outcome_job_backfills_labels_and_report_scores_them() {
+    sqlx::any::install_default_drivers();
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+
+    // 000001 单边上涨、000002 单边下跌，各 30 个交易日。
+    for (fund_id, code, peer, step, proba) in [
+        ("fund-1", "000001", "BK1", 0.01, 0.8),
+        ("fund-2", "000002", "BK2", -0.01, 0.2),
+    ] {
+        sqlx::query(
+            r#"
+            INSERT INTO fund (id, fund_code, fund_name, fund_type, created_at, updated_at)
+            VALUES ($1, $2, $3, '股票型', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            "#,
+        )
+        .bind(fund_id)
+        .bind(code)
+        .bind(format!("测试基金-{code}"))
+        .execute(&pool)
+        .await
+        .expect("seed fund");
+        for i in 0..30 {
+            sqlx::query(
+                r#"
+                INSERT INTO fund_nav_history (id, source_name, fund_id, nav_date, unit_nav, created_at, updated_at)
+                VALUES ($1, 'tiantian', $2, $3, $4, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+                "#,
+            )
+            .bind(format!("nav-{code}-{i}"))
+            .bind(fund_id)
+            .bind(format!("2026-01-{:02}", i + 1))
+            .bind(format!("{:.4}", 1.5 + step * i as f64))
+            .execute(&pool)
+            .await
+            .expect("seed nav");
+        }
+        // 第 3 日：5T/20T 都已满；第 21 日：只满 5T；第 30 日：都未满。
+        for day in [3, 21, 30] {
+            sqlx::query(
+                r#"
+                INSERT INTO fund_signal_snapshot (
+                  fund_code, peer_code, as_of_date, position_percentile_0_100, position_bucket,
+                  dip_buy_proba_5t, dip_buy_proba_20t, magic_rebound_proba_5t, magic_rebound_proba_20t,
+                  source_name
+                )
+                VALUES ($1, $2, $3, 10.0, 'low', $4, $4, $4, $4, 'tiantian')
+                "#,
+            )
+            .bind(code)
+            .bind(peer)
+            .bind(format!("2026-01-{day:02}"))
+            .bind(proba)
+            .execute(&pool)
+            .await
+            .expect("seed snapshot");
+        }
+    }
+
+    let stats = resolve_pending_outcomes(&pool, "tiantian", 1000)
+        .await
+        .expect("resolve");
+    // 第 30 日的快照还没有任何后续净值，不进入批次。
+    assert_eq!(stats.scanned, 4);
+    assert_eq!(stats.resolved_5t, 4);
+    assert_eq!(stats.resolved_20t, 2);
+    assert_eq!(stats.pending, 2);
+
+    let row = sqlx::query(
+        r#"
+        SELECT realized_return_20t, dip_buy_outcome_20t, magic_rebound_outcome_20t
+        FROM fund_signal_snapshot WHERE fund_code = '000001' AND as_of_date = '2026-01-03'
+        "#,
+    )
+    .fetch_one(&pool)
+    .await
+    .expect("row");
+    let ret: f64 = row.get("realized_return_20t");
+    assert!((ret - 0.2 / 1.52).abs() < 1e-9, "ret {ret}");
+    assert_eq!(row.get::<i64, _>("dip_buy_outcome_20t"), 1);
+    assert_eq!(row.get::<i64, _>("magic_rebound_outcome_20t"), 1);
+    let row = sqlx::query(
+        r#"
+        SELECT dip_buy_outcome_5t, dip_buy_outcome_20t
+        FROM fund_signal_snapshot WHERE fund_code = '000002' AND as_of_date = '2026-01-21'
+        "#,
+    )
+    .fetch_one(&pool)
+    .await
+    .expect("row");
+    assert_eq!(row.get::<i64, _>("dip_buy_outcome_5t"), 0);
+    assert_eq!(
+        row.try_get::<Option<i64>, _>("dip_buy_outcome_20t")
+            .unwrap(),
+        None
+    );
+
+    // 已回填的 5T 不重复处理；只差 20T 的快照在净值满 20 个交易日前不再扫描。
+    let again = resolve_pending_outcomes(&pool, "tiantian", 1000)
+        .await
+        .expect("resolve again");
+    assert_eq!(again.scanned, 0);
+    assert_eq!(again.resolved_5t + again.resolved_20t, 0);
+
+    let config = api::config::ConfigStore::load();
+    let jwt = api::jwt::JwtService::from_secret("test-secret");
+    let state = AppState::new(
+        Some(pool.clone()),
+        config,
+        jwt,
+        api::db::DatabaseKind::Sqlite,
+    );
+    let token = state.jwt().issue_access_token("1");
+    let app = api::service(state);
+    let get = |uri: &str, token: Option<&str>| {
+        let mut b = Request::builder().method("GET").uri(uri);
+        if let Some(t) = token {
+            b = b.header("Authorization", format!("Bearer {t}"));
+        }
+        b.body(Body::empty()).unwrap()
+    };
+
+    let res = app
+        .clone()
+        .oneshot(get("/api/ml/signal-outcomes", None))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+    let res = app
+        .clone()
+        .oneshot(get(
+            "/api/ml/signal-outcomes?since=2026/01/01",
+            Some(&token),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    let res = app
+        .clone()
+        .oneshot(get("/api/ml/signal-outcomes", Some(&token)))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body = json_body(res).await;
+    let items = body["items"].as_array().unwrap();
+    assert_eq!(items.len(), 4);
+    let dip5 = items
+        .iter()
+        .find(|i| i["task"] == "dip_buy" && i["horizon_days"] == 5)
+        .unwrap();
+    assert_eq!(dip5["overall"]["n"], 4);
+    assert_eq!(dip5["overall"]["signals"], 2);
+    assert_eq!(dip5["overall"]["hit_rate"], 1.0);
+    assert_eq!(dip5["overall"]["base_rate"], 0.5);
+    assert_eq!(dip5["overall"]["auc"], 1.0);
+    assert!(dip5["overall"]["calibration"].is_array());
+    assert_eq!(dip5["by_peer"].as_array().unwrap().len(), 2);
+    assert_eq!(dip5["by_peer"][0]["peer_code"], "BK1");
+    assert_eq!(dip5["by_bucket"][0]["position_bucket"], "low");
+    assert_eq!(dip5["decay"][0]["month"], "2026-01");
+    let dip20 = items
+        .iter()
+        .find(|i| i["task"] == "dip_buy" && i["horizon_days"] == 20)
+        .unwrap();
+    assert_eq!(dip20["overall"]["n"], 2);
+
+    let res = app
+        .clone()
+        .oneshot(get(
+            "/api/ml/signal-outcomes?peer_code=BK2&until=2026-01-10",
+            Some(&token),
+        ))
+        .await
+        .unwrap();
+    let body = json_body(res).await;
+    assert_eq!(body["items"][0]["overall"]["n"], 1);
+    assert_eq!(body["items"][0]["overall"]["hit_rate"], Value::Null);
+
+    // 更早的快照永远缺净值（基金无净值、信号日早于首个净值），不能挡住后面可回填的行。
+    for (code, day) in [("000009", "2025-12-01"), ("000001", "2025-12-15")] {
+        sqlx::query(
+            r#"
+            INSERT INTO fund_signal_snapshot (
+              fund_code, peer_code, as_of_date, position_percentile_0_100, position_bucket,
+              dip_buy_proba_5t, dip_buy_proba_20t, magic_rebound_proba_5t, magic_rebound_proba_20t,
+              source_name
+            )
+            VALUES ($1, 'BK9', $2, 10.0, 'low', 0.5, 0.5, 0.5, 0.5, 'tiantian')
+            "#,
+        )
+        .bind(code)
+        .bind(day)
+        .execute(&pool)
+        .await
+        .expect("seed stuck snapshot");
+    }
+    for i in 30..45 {
+        sqlx::query(
+            r#"
+            INSERT INTO fund_nav_history (id, source_name, fund_id, nav_date, unit_nav, created_at, updated_at)
+            VALUES ($1, 'tiantian', 'fund-1', $2, $3, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            "#,
+        )
+        .bind(format!("nav-000001-{i}"))
+        .bind(format!("2026-02-{:02}", i - 29))
+        .bind(format!("{:.4}", 1.5 + 0.01 * i as f64))
+        .execute(&pool)
+        .await
+        .expect("seed later nav");
+    }
+    let window = resolve_pending_outcomes(&pool, "tiantian", 1)
+        .await
+        .expect("resolve with small window");
+    assert_eq!(window.scanned, 1);
+    assert_eq!(window.resolved_20t, 1);
+    let resolved_at: Option<String> = sqlx::query(
+        "SELECT CAST(outcome_20t_at AS TEXT) as at FROM fund_signal_snapshot WHERE fund_code = '000001' AND as_of_date = '2026-01-21'",
+    )
+    .fetch_one(&pool)
+    .await
+    .expect("row")
+    .get("at");
+    assert!(resolved_at.is_some());
+}
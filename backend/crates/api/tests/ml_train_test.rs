@@ -1,5 +1,8 @@
 use api::ml::dataset::DatasetConfig;
-use api::ml::train::{MlTask, get_sector_model, train_and_store_sector_model};
+use api::ml::model::ModelKind;
+use api::ml::train::{
+    MlTask, get_sector_model, train_and_store_sector_model, train_and_store_sector_model_with_kind,
+};
 
 #[tokio::test]
 async fn train_persists_sector_model_and_can_infer() {
@@ -106,4 +109,50 @@ async fn train_persists_sector_model_and_can_infer() {
         .predict_proba(&[0.1, 0.0, 0.0, 0.0])
         .expect("predict");
     assert!((0.0..=1.0).contains(&p));
+
+    // 指标来自按时间切分的留出集，留出集之前空出 horizon_days 个交易日。
+    let v = &rec.metrics["validation"];
+    assert_eq!(v["method"], "time_holdout");
+    assert_eq!(v["status"], "ok");
+    assert_eq!(v["embargo_days"], 5);
+    assert!(v["train_end_date"].as_str().unwrap() < v["test_start_date"].as_str().unwrap());
+    assert!(v["log_loss"].is_number());
+    assert!(v["brier"].is_number());
+    assert!(v["baseline"]["brier"].is_number());
+    assert!(v["calibration"].is_array());
+    assert_eq!(v["precision_at_k"].as_array().unwrap().len(), 3);
+
+    // 同一数据改用 GBDT：没有冠军训练截止日之后的样本，挑战者待定；手动晋升后生效，模型与指标都标明类型。
+    let challenger = train_and_store_sector_model_with_kind(
+        &pool,
+        "BK000156",
+        "tiantian",
+        MlTask::DipBuy,
+        &cfg,
+        ModelKind::Gbdt,
+    )
+    .await
+    .expect("train gbdt")
+    .expect("version");
+    assert_eq!(challenger.status, "challenger");
+    let rec = get_sector_model(&pool, "BK000156", MlTask::DipBuy, 5)
+        .await
+        .expect("get")
+        .expect("exists");
+    assert_eq!(rec.model.kind(), ModelKind::Linear);
+    api::ml::registry::promote(&pool, &challenger.id)
+        .await
+        .expect("promote");
+    let rec = get_sector_model(&pool, "BK000156", MlTask::DipBuy, 5)
+        .await
+        .expect("get")
+        .expect("exists");
+    assert_eq!(rec.model.kind(), ModelKind::Gbdt);
+    assert_eq!(rec.metrics["model_type"], "gbdt");
+    assert!(rec.metrics["train"]["n_trees"].is_number());
+    let p = rec
+        .model
+        .predict_proba(&[0.1, 0.0, 0.0, 0.0])
+        .expect("predict");
+    assert!((0.0..=1.0).contains(&p));
 }
@@ -0,0 +1,145 @@
+use axum::{body::Body, http::Request};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+use api::ml::metrics::{brier_score, calibration_buckets, log_loss, precision_at_k, roc_auc};
+use api::ml::train::time_holdout_split;
+use api::state::AppState;
+
+fn approx(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-9
+}
+
+#[test]
+fn classification_metrics_match_hand_computed_values() {
+    let y = [0.0, 0.0, 1.0, 1.0];
+    let p = [0.1, 0.4, 0.35, 0.8];
+    // 4 个正负对中 3 个排序正确。
+    assert!(approx(roc_auc(&y, &p).unwrap(), 0.75));
+    assert!(approx(roc_auc(&y, &[0.5; 4]).unwrap(), 0.5));
+    assert_eq!(roc_auc(&[1.0, 1.0], &[0.2, 0.9]), None);
+
+    let expected_ll = -((0.9_f64).ln() + (0.6_f64).ln() + (0.35_f64).ln() + (0.8_f64).ln()) / 4.0;
+    assert!(approx(log_loss(&y, &p).unwrap(), expected_ll));
+    let expected_brier = (0.01 + 0.16 + 0.4225 + 0.04) / 4.0;
+    assert!(approx(brier_score(&y, &p).unwrap(), expected_brier));
+
+    assert_eq!(precision_at_k(&y, &p, 1), Some(1.0));
+    assert_eq!(precision_at_k(&y, &p, 2), Some(0.5));
+    assert_eq!(precision_at_k(&y, &p, 10), Some(0.5));
+
+    let buckets = calibration_buckets(&y, &p, 2);
+    assert_eq!(buckets.len(), 2);
+    assert_eq!(buckets[0].count, 3);
+    assert!(approx(buckets[0].observed_rate, 1.0 / 3.0));
+    assert_eq!(buckets[1].count, 1);
+    assert!(approx(buckets[1].mean_predicted, 0.8));
+}
+
+#[test]
+fn time_split_keeps_embargo_gap_before_holdout() {
+    // 10 个交易日、每日两条样本：最后 2 日为留出集，其前 3 日丢弃。
+    let dates: Vec<String> = (1..=10)
+        .flat_map(|d| {
+            let s = format!("2026-03-{d:02}");
+            [s.clone(), s]
+        })
+        .collect();
+    let split = time_holdout_split(&dates, 0.2, 3).expect("split");
+    let days = |idx: &[usize]| {
+        let mut v: Vec<&str> = idx.iter().map(|&i| dates[i].as_str()).collect();
+        v.dedup();
+        v
+    };
+    assert_eq!(days(&split.test), vec!["2026-03-09", "2026-03-10"]);
+    assert_eq!(
+        days(&split.train),
+        vec![
+            "2026-03-01",
+            "2026-03-02",
+            "2026-03-03",
+            "2026-03-04",
+            "2026-03-05"
+        ]
+    );
+
+    assert!(time_holdout_split(&dates, 0.2, 8).is_none());
+}
+
+#[tokio::test]
+async fn ml_models_endpoint_lists_models_with_metrics() {
+    sqlx::any::install_default_drivers();
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+
+    for (peer, task) in [("BK000156", "dip_buy"), ("BK000156", "magic_rebound")] {
+        sqlx::query(
+            r#"
+            INSERT INTO ml_sector_model (peer_code, task, horizon_days, feature_names_json, model_json, metrics_json)
+            VALUES ($1, $2, 5, '["dd_mag"]', '{}', $3)
+            "#,
+        )
+        .bind(peer)
+        .bind(task)
+        .bind(json!({ "sample_size": 40, "validation": { "status": "ok", "auc": 0.61 } }).to_string())
+        .execute(&pool)
+        .await
+        .expect("seed model");
+    }
+
+    let config = api::config::ConfigStore::load();
+    let jwt = api::jwt::JwtService::from_secret("test-secret");
+    let state = AppState::new(
+        Some(pool.clone()),
+        config,
+        jwt,
+        api::db::DatabaseKind::Sqlite,
+    );
+    let token = state.jwt().issue_access_token("1");
+    let app = api::app(state);
+    let get = |uri: &str, token: Option<&str>| {
+        let mut b = Request::builder().uri(uri);
+        if let Some(t) = token {
+            b = b.header("Authorization", format!("Bearer {t}"));
+        }
+        b.body(Body::empty()).unwrap()
+    };
+
+    let res = app
+        .clone()
+        .oneshot(get("/api/ml/models", None))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+
+    let res = app
+        .clone()
+        .oneshot(get("/api/ml/models?task=dip_buy", Some(&token)))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    let items = body["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["peer_code"], "BK000156");
+    assert_eq!(items[0]["horizon_days"], 5);
+    assert_eq!(items[0]["feature_names"], json!(["dd_mag"]));
+    assert_eq!(items[0]["metrics"]["validation"]["auc"], 0.61);
+    assert!(items[0]["trained_at"].is_string());
+    assert!(items[0].get("model").is_none());
+
+    let res = app
+        .clone()
+        .oneshot(get("/api/ml/models?task=bogus", Some(&token)))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+}
@@ -0,0 +1,519 @@
+use axum::{body::Body, http::Request};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+use api::state::AppState;
+
+async fn body_json(res: axum::response::Response) -> Value {
+    let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+        .await
+        .expect("read body");
+    serde_json::from_slice(&bytes).unwrap_or(Value::Null)
+}
+
+#[tokio::test]
+async fn roles_grant_capabilities_enforced_by_extractor() {
+    sqlx::any::install_default_drivers();
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+
+    sqlx::query(
+        r#"
+        INSERT INTO auth_user (id, password, is_superuser, username, email, is_staff, is_active, date_joined)
+        VALUES (1, 'x', 1, 'root', '', 1, 1, CURRENT_TIMESTAMP),
+               (2, 'x', 0, 'staff', '', 1, 1, CURRENT_TIMESTAMP),
+               (3, 'x', 0, 'ops', '', 0, 1, CURRENT_TIMESTAMP)
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .expect("seed users");
+
+    let config = api::config::ConfigStore::load();
+    let jwt = api::jwt::JwtService::from_secret("test-secret");
+    let state = AppState::new(
+        Some(pool.clone()),
+        config,
+        jwt,
+        api::db::DatabaseKind::Sqlite,
+    );
+    let root = state.jwt().issue_access_token("1");
+    let staff = state.jwt().issue_access_token("2");
+    let ops = state.jwt().issue_access_token("3");
+    let app = api::app(state);
+    let call = |method: &str, uri: &str, token: Option<&str>, body: Option<Value>| {
+        let mut b = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Content-Type", "application/json");
+        if let Some(t) = token {
+            b = b.header("Authorization", format!("Bearer {t}"));
+        }
+        b.body(match body {
+            Some(v) => Body::from(v.to_string()),
+            None => Body::empty(),
+        })
+        .unwrap()
+    };
+    let status = |app: axum::Router, req: Request<Body>| async move {
+        app.oneshot(req).await.unwrap().status()
+    };
+
+    // is_staff 保留原有的员工能力；角色管理仍仅限超级用户。
+    assert_eq!(
+        status(
+            app.clone(),
+            call("GET", "/api/admin/crawl/config", None, None)
+        )
+        .await,
+        401
+    );
+    assert_eq!(
+        status(
+            app.clone(),
+            call("GET", "/api/admin/crawl/config", Some(&ops), None)
+        )
+        .await,
+        403
+    );
+    assert_eq!(
+        status(
+            app.clone(),
+            call("GET", "/api/admin/crawl/config", Some(&staff), None)
+        )
+        .await,
+        200
+    );
+    assert_eq!(
+        status(
+            app.clone(),
+            call("GET", "/api/admin/roles", Some(&staff), None)
+        )
+        .await,
+        403
+    );
+
+    let res = app
+        .clone()
+        .oneshot(call("GET", "/api/admin/roles", Some(&root), None))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let roles = body_json(res).await["items"].as_array().unwrap().clone();
+    let names: Vec<&str> = roles.iter().map(|r| r["name"].as_str().unwrap()).collect();
+    assert_eq!(names, vec!["admin", "analyst", "operator"]);
+    assert!(roles.iter().all(|r| r["is_system"] == true));
+
+    let res = app
+        .clone()
+        .oneshot(call("GET", "/api/admin/capabilities", Some(&root), None))
+        .await
+        .unwrap();
+    let caps = body_json(res).await["items"].clone();
+    assert!(caps.as_array().unwrap().contains(&json!("sim.unlimited")));
+
+    // 授予 operator：获得采集/任务/审计能力，但不含持仓管理。
+    let res = app
+        .clone()
+        .oneshot(call(
+            "PUT",
+            "/api/admin/users/3/roles",
+            Some(&root),
+            Some(json!({ "roles": ["operator"] })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let v = body_json(res).await;
+    assert_eq!(v["roles"], json!(["operator"]));
+    let caps: Vec<&str> = v["capabilities"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c.as_str().unwrap())
+        .collect();
+    assert!(caps.contains(&"crawl.admin"));
+    assert!(caps.contains(&"tasks.view_all"));
+    assert!(!caps.contains(&"positions.admin"));
+
+    assert_eq!(
+        status(
+            app.clone(),
+            call("GET", "/api/admin/crawl/config", Some(&ops), None)
+        )
+        .await,
+        200
+    );
+    assert_eq!(
+        status(
+            app.clone(),
+            call("GET", "/api/admin/task-schedules", Some(&ops), None)
+        )
+        .await,
+        200
+    );
+    assert_eq!(
+        status(
+            app.clone(),
+            call("POST", "/api/positions/recalculate", Some(&ops), None)
+        )
+        .await,
+        403
+    );
+
+    let res = app
+        .clone()
+        .oneshot(call("GET", "/api/auth/me", Some(&ops), None))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let me = body_json(res).await;
+    assert_eq!(me["role"], "user");
+    assert!(
+        me["capabilities"]
+            .as_array()
+            .unwrap()
+            .contains(&json!("audit.view"))
+    );
+
+    let res = app
+        .clone()
+        .oneshot(call(
+            "GET",
+            "/api/admin/audit-events?action=users.roles.update",
+            Some(&ops),
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let items = body_json(res).await["items"].as_array().unwrap().clone();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["target_id"], "3");
+    assert_eq!(items[0]["after"]["roles"], json!(["operator"]));
+
+    // 自定义角色：能力名校验、内置角色不可删除、删除后撤销授权。
+    for (body, code) in [
+        (json!({ "name": "Bad Name", "capabilities": [] }), 400),
+        (
+            json!({ "name": "sim_power", "capabilities": ["no.such"] }),
+            400,
+        ),
+        (json!({ "name": "operator", "capabilities": [] }), 409),
+        (
+            json!({ "name": "sim_power", "description": "研究员", "capabilities": ["sim.unlimited", "sim.unlimited"] }),
+            201,
+        ),
+    ] {
+        assert_eq!(
+            status(
+                app.clone(),
+                call("POST", "/api/admin/roles", Some(&root), Some(body))
+            )
+            .await,
+            code
+        );
+    }
+    let res = app
+        .clone()
+        .oneshot(call(
+            "PATCH",
+            "/api/admin/roles/sim_power",
+            Some(&root),
+            Some(json!({ "capabilities": ["sim.unlimited", "nav_history.bulk_sync"] })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(
+        body_json(res).await["capabilities"],
+        json!(["nav_history.bulk_sync", "sim.unlimited"])
+    );
+
+    assert_eq!(
+        status(
+            app.clone(),
+            call(
+                "PUT",
+                "/api/admin/users/3/roles",
+                Some(&root),
+                Some(json!({ "roles": ["missing"] })),
+            )
+        )
+        .await,
+        400
+    );
+    assert_eq!(
+        status(
+            app.clone(),
+            call(
+                "PUT",
+                "/api/admin/users/3/roles",
+                Some(&root),
+                Some(json!({ "roles": ["sim_power"] })),
+            )
+        )
+        .await,
+        200
+    );
+    assert_eq!(
+        status(
+            app.clone(),
+            call("GET", "/api/admin/crawl/config", Some(&ops), None)
+        )
+        .await,
+        403
+    );
+
+    assert_eq!(
+        status(
+            app.clone(),
+            call("DELETE", "/api/admin/roles/operator", Some(&root), None)
+        )
+        .await,
+        400
+    );
+    assert_eq!(
+        status(
+            app.clone(),
+            call("DELETE", "/api/admin/roles/sim_power", Some(&root), None)
+        )
+        .await,
+        204
+    );
+    let res = app
+        .clone()
+        .oneshot(call("GET", "/api/admin/users/3/roles", Some(&root), None))
+        .await
+        .unwrap();
+    let v = body_json(res).await;
+    assert_eq!(v["roles"], json!([]));
+    assert_eq!(v["capabilities"], json!([]));
+
+    // admin 角色的通配符展开为全部能力。
+    assert_eq!(
+        status(
+            app.clone(),
+            call(
+                "PUT",
+                "/api/admin/users/3/roles",
+                Some(&root),
+                Some(json!({ "roles": ["admin"] })),
+            )
+        )
+        .await,
+        200
+    );
+    let principal = api::permissions::load_principal(&pool, 3)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        principal.capabilities().len(),
+        api::permissions::CAPABILITIES.len()
+    );
+    assert!(principal.has("users.manage_roles"));
+    assert_eq!(
+        status(
+            app.clone(),
+            call("GET", "/api/admin/roles", Some(&ops), None)
+        )
+        .await,
+        200
+    );
+
+    // 持有角色管理能力的非超级用户不能授予 * 或 users.manage_roles（包括授予自己）。
+    assert_eq!(
+        status(
+            app.clone(),
+            call(
+                "POST",
+                "/api/admin/roles",
+                Some(&root),
+                Some(json!({ "name": "role_manager", "capabilities": ["users.manage_roles"] })),
+            )
+        )
+        .await,
+        201
+    );
+    assert_eq!(
+        status(
+            app.clone(),
+            call(
+                "PUT",
+                "/api/admin/users/3/roles",
+                Some(&root),
+                Some(json!({ "roles": ["role_manager"] })),
+            )
+        )
+        .await,
+        200
+    );
+    for (method, uri, body, code) in [
+        (
+            "POST",
+            "/api/admin/roles",
+            json!({ "name": "everything", "capabilities": ["*"] }),
+            403,
+        ),
+        (
+            "POST",
+            "/api/admin/roles",
+            json!({ "name": "managers", "capabilities": ["users.manage_roles"] }),
+            403,
+        ),
+        (
+            "POST",
+            "/api/admin/roles",
+            json!({ "name": "sim_only", "capabilities": ["sim.unlimited"] }),
+            201,
+        ),
+        (
+            "PATCH",
+            "/api/admin/roles/sim_only",
+            json!({ "capabilities": ["*"] }),
+            403,
+        ),
+        (
+            "PUT",
+            "/api/admin/users/2/roles",
+            json!({ "roles": ["admin"] }),
+            403,
+        ),
+        (
+            "PUT",
+            "/api/admin/users/3/roles",
+            json!({ "roles": ["admin"] }),
+            403,
+        ),
+        (
+            "PUT",
+            "/api/admin/users/3/roles",
+            json!({ "roles": ["role_manager", "sim_only"] }),
+            200,
+        ),
+    ] {
+        assert_eq!(
+            status(app.clone(), call(method, uri, Some(&ops), Some(body))).await,
+            code,
+            "{method} {uri}"
+        );
+    }
+    let principal = api::permissions::load_principal(&pool, 2)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!principal.has("users.manage_roles"));
+}
+
+#[tokio::test]
+async fn task_reads_are_limited_to_owner_or_view_all() {
+    sqlx::any::install_default_drivers();
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+
+    sqlx::query(
+        r#"
+        INSERT INTO auth_user (id, password, is_superuser, username, email, is_staff, is_active, date_joined)
+        VALUES (1, 'x', 1, 'root', '', 1, 1, CURRENT_TIMESTAMP),
+               (2, 'x', 0, 'owner', '', 0, 1, CURRENT_TIMESTAMP),
+               (3, 'x', 0, 'other', '', 0, 1, CURRENT_TIMESTAMP)
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .expect("seed users");
+
+    let job_id = api::tasks::enqueue_task_job(&pool, "noop", &json!({}), 10, Some(2))
+        .await
+        .expect("enqueue");
+    sqlx::query(
+        r#"
+        INSERT INTO task_run (id, queue_type, job_id, job_type, status)
+        VALUES ('run-1', 'task_job', $1, 'noop', 'running')
+        "#,
+    )
+    .bind(&job_id)
+    .execute(&pool)
+    .await
+    .expect("seed run");
+    sqlx::query(
+        "INSERT INTO task_run_log (id, run_id, level, message, seq) VALUES ('log-1', 'run-1', 'INFO', 'hello', 1)",
+    )
+    .execute(&pool)
+    .await
+    .expect("seed log");
+
+    let state = AppState::new(
+        Some(pool.clone()),
+        api::config::ConfigStore::load(),
+        api::jwt::JwtService::from_secret("test-secret"),
+        api::db::DatabaseKind::Sqlite,
+    );
+    let root = state.jwt().issue_access_token("1");
+    let owner = state.jwt().issue_access_token("2");
+    let other = state.jwt().issue_access_token("3");
+    let app = api::app(state);
+    let get = |uri: String, token: &str| {
+        Request::builder()
+            .method("GET")
+            .uri(uri)
+            .header("Authorization", format!("Bearer {token}"))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let uris = [
+        format!("/api/tasks/jobs/{job_id}/runs"),
+        format!("/api/tasks/jobs/{job_id}/logs"),
+        "/api/tasks/runs/run-1/logs".to_string(),
+        format!("/api/tasks/jobs/{job_id}/stream"),
+        "/api/tasks/runs/run-1/logs/stream".to_string(),
+    ];
+    for uri in &uris {
+        let res = app.clone().oneshot(get(uri.clone(), &other)).await.unwrap();
+        assert_eq!(res.status(), 403, "{uri}");
+    }
+    for token in [&owner, &root] {
+        for uri in &uris[..3] {
+            let res = app.clone().oneshot(get(uri.clone(), token)).await.unwrap();
+            assert_eq!(res.status(), 200, "{uri}");
+            assert_eq!(body_json(res).await.as_array().unwrap().len(), 1, "{uri}");
+        }
+    }
+    let res = app
+        .clone()
+        .oneshot(get(
+            "/api/tasks/runs/missing/logs/stream".to_string(),
+            &other,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+
+    // 概览只展示自己的任务与系统任务。
+    api::tasks::enqueue_task_job(&pool, "noop", &json!({}), 10, None)
+        .await
+        .expect("enqueue system job");
+    for (token, jobs, runs) in [(&other, 1, 0), (&owner, 2, 1), (&root, 2, 1)] {
+        let res = app
+            .clone()
+            .oneshot(get("/api/tasks/overview".to_string(), token))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+        let v = body_json(res).await;
+        assert_eq!(v["task_queue"].as_array().unwrap().len(), jobs);
+        assert_eq!(v["running"].as_array().unwrap().len(), runs);
+    }
+}
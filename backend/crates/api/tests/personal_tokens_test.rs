@@ -0,0 +1,226 @@
+use std::net::SocketAddr;
+
+use axum::{body::Body, extract::ConnectInfo, http::Request};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use api::state::AppState;
+
+async fn body_json(res: axum::response::Response) -> Value {
+    let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+        .await
+        .expect("read body");
+    serde_json::from_slice(&bytes).unwrap_or(Value::Null)
+}
+
+fn call(method: &str, uri: &str, token: &str, body: Option<Value>) -> Request<Body> {
+    Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Authorization", format!("Bearer {token}"))
+        .header("Content-Type", "application/json")
+        .header("X-Forwarded-For", "198.51.100.9")
+        .extension(ConnectInfo(
+            "127.0.0.1:40000".parse::<SocketAddr>().unwrap(),
+        ))
+        .body(match body {
+            Some(v) => Body::from(v.to_string()),
+            None => Body::empty(),
+        })
+        .unwrap()
+}
+
+#[tokio::test]
+async fn personal_tokens_authenticate_with_scopes_and_can_be_revoked() {
+    sqlx::any::install_default_drivers();
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+
+    sqlx::query(
+        r#"
+        INSERT INTO auth_user (id, password, is_superuser, username, email, is_staff, is_active, date_joined)
+        VALUES (1, 'x', 0, 'quant', '', 0, 1, CURRENT_TIMESTAMP)
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .expect("seed user");
+
+    let config = api::config::ConfigStore::load();
+    let jwt = api::jwt::JwtService::from_secret("test-secret");
+    let state = AppState::new(
+        Some(pool.clone()),
+        config,
+        jwt,
+        api::db::DatabaseKind::Sqlite,
+    );
+    let session = state.jwt().issue_access_token("1");
+    let app = api::app(state);
+    let create = |body: Value| call("POST", "/api/auth/tokens", &session, Some(body));
+
+    for body in [
+        json!({ "name": "", "scopes": ["read"] }),
+        json!({ "name": "nightly", "scopes": [] }),
+        json!({ "name": "nightly", "scopes": ["admin"] }),
+        json!({ "name": "nightly", "scopes": ["read"], "expires_in_days": 0 }),
+    ] {
+        let res = app.clone().oneshot(create(body.clone())).await.unwrap();
+        assert_eq!(res.status(), 400, "{body}");
+    }
+
+    // 明文只在创建时返回一次，库中只有摘要。
+    let res = app
+        .clone()
+        .oneshot(create(
+            json!({ "name": "nightly", "scopes": ["read", "read"], "expires_in_days": 30 }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+    let created = body_json(res).await;
+    let read_token = created["token"].as_str().unwrap().to_string();
+    let read_id = created["id"].as_str().unwrap().to_string();
+    assert!(read_token.starts_with("fvp_"));
+    assert_eq!(created["scopes"], json!(["read"]));
+    assert!(created["expires_at"].is_string());
+    assert!(read_token.starts_with(created["token_prefix"].as_str().unwrap()));
+    let stored: String = sqlx::query_scalar("SELECT token_hash FROM auth_personal_token")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(stored, api::personal_token::hash_token(&read_token));
+    assert!(!stored.contains(&read_token));
+
+    let res = app
+        .clone()
+        .oneshot(create(
+            json!({ "name": "notebook", "scopes": ["tasks", "sim"] }),
+        ))
+        .await
+        .unwrap();
+    let ops_token = body_json(res).await["token"].as_str().unwrap().to_string();
+
+    let res = app
+        .clone()
+        .oneshot(call("GET", "/api/auth/tokens", &session, None))
+        .await
+        .unwrap();
+    let listing = body_json(res).await;
+    assert_eq!(listing["items"].as_array().unwrap().len(), 2);
+    assert!(!listing.to_string().contains(&read_token));
+
+    // read：任意 GET；写操作与令牌管理被拒绝。
+    let res = app
+        .clone()
+        .oneshot(call("GET", "/api/auth/me", &read_token, None))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(body_json(res).await["username"], "quant");
+    let job = Uuid::new_v4();
+    for (method, uri, token, code) in [
+        (
+            "POST",
+            format!("/api/tasks/jobs/{job}/cancel"),
+            &read_token,
+            403,
+        ),
+        ("GET", "/api/auth/tokens".to_string(), &read_token, 403),
+        ("GET", "/api/tasks/overview".to_string(), &ops_token, 200),
+        ("GET", "/api/watchlists".to_string(), &ops_token, 403),
+        (
+            "POST",
+            format!("/api/tasks/jobs/{job}/cancel"),
+            &ops_token,
+            404,
+        ),
+        ("POST", "/api/auth/tokens".to_string(), &ops_token, 403),
+        (
+            "GET",
+            "/api/auth/me".to_string(),
+            &"fvp_unknown".to_string(),
+            401,
+        ),
+    ] {
+        let res = app
+            .clone()
+            .oneshot(call(method, &uri, token, None))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), code, "{method} {uri}");
+    }
+
+    let (last_used, ip): (Option<String>, Option<String>) =
+        sqlx::query_as("SELECT last_used_at, last_used_ip FROM auth_personal_token WHERE id = $1")
+            .bind(&read_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert!(last_used.is_some());
+    assert_eq!(ip.as_deref(), Some("198.51.100.9"));
+
+    // 过期即失效。
+    sqlx::query("UPDATE auth_personal_token SET expires_at = '2000-01-01 00:00:00' WHERE id = $1")
+        .bind(&read_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let res = app
+        .clone()
+        .oneshot(call("GET", "/api/auth/me", &read_token, None))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+
+    // 吊销：立即失效，再次吊销 404，并写入审计。
+    let res = app
+        .clone()
+        .oneshot(call("GET", "/api/auth/tokens", &session, None))
+        .await
+        .unwrap();
+    let items = body_json(res).await["items"].as_array().unwrap().clone();
+    let ops_id = items.iter().find(|t| t["name"] == "notebook").unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    for code in [204, 404] {
+        let res = app
+            .clone()
+            .oneshot(call(
+                "DELETE",
+                &format!("/api/auth/tokens/{ops_id}"),
+                &session,
+                None,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), code);
+    }
+    let res = app
+        .clone()
+        .oneshot(call("GET", "/api/tasks/overview", &ops_token, None))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+
+    let actions: Vec<String> = sqlx::query_scalar(
+        "SELECT action FROM audit_event WHERE action LIKE 'auth.token.%' ORDER BY action",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        actions,
+        vec![
+            "auth.token.create",
+            "auth.token.create",
+            "auth.token.revoke"
+        ]
+    );
+}
@@ -141,6 +141,7 @@ async fn auto_topk_snapshot_backtest_generates_equity_curve() {
             top_k: 1,
             rebalance_every: 1,
             weights: None,
+            regime_filter: None,
         },
     )
     .await
@@ -222,6 +223,7 @@ async fn auto_topk_snapshot_training_writes_rounds_and_updates_weights() {
             top_k: 1,
             rebalance_every: 2,
             weights: None,
+            regime_filter: None,
         },
     )
     .await
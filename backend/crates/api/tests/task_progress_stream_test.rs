@@ -0,0 +1,206 @@
+use axum::{body::Body, http::Request};
+use serde_json::{Value, json};
+use sqlx::Row;
+use tower::ServiceExt;
+
+use api::state::AppState;
+use api::tasks::{self, ClaimFilter, TaskProgress};
+
+async fn body_text(res: axum::response::Response) -> String {
+    let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+        .await
+        .expect("read body");
+    String::from_utf8(bytes.to_vec()).expect("utf8")
+}
+
+/// 解析 SSE 文本为 (event, id, data) 列表。
+fn parse_sse(text: &str) -> Vec<(String, Option<String>, Value)> {
+    let mut out = Vec::new();
+    for block in text.split("\n\n") {
+        let mut event = None;
+        let mut id = None;
+        let mut data = None;
+        for line in block.lines() {
+            if let Some(v) = line.strip_prefix("event: ") {
+                event = Some(v.to_string());
+            } else if let Some(v) = line.strip_prefix("id: ") {
+                id = Some(v.to_string());
+            } else if let Some(v) = line.strip_prefix("data: ") {
+                data = Some(serde_json::from_str(v).expect("event json"));
+            }
+        }
+        if let (Some(event), Some(data)) = (event, data) {
+            out.push((event, id, data));
+        }
+    }
+    out
+}
+
+#[test]
+fn progress_eta_extrapolates_average_item_time() {
+    let p = TaskProgress::new("job", 10);
+    assert_eq!(p.eta_seconds(0, 5.0), None);
+    assert_eq!(p.eta_seconds(2, 10.0), Some(40));
+    assert_eq!(p.eta_seconds(10, 30.0), Some(0));
+}
+
+#[tokio::test]
+async fn task_progress_is_reported_and_streamed_over_sse() {
+    sqlx::any::install_default_drivers();
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+
+    let config = api::config::ConfigStore::load();
+    let jwt = api::jwt::JwtService::from_secret("test-secret");
+    let state = AppState::new(
+        Some(pool.clone()),
+        config,
+        jwt,
+        api::db::DatabaseKind::Sqlite,
+    );
+    let token = state.jwt().issue_access_token("1");
+    let app = api::app(state);
+    let get = |uri: String, auth: Option<&str>| {
+        let mut b = Request::builder().uri(uri);
+        if let Some(t) = auth {
+            b = b.header("Authorization", format!("Bearer {t}"));
+        }
+        b.body(Body::empty()).unwrap()
+    };
+
+    // 执行中：进度写回 task_job，详情接口可见。
+    let live_id = tasks::enqueue_task_job(&pool, "signals_batch", &json!({}), 0, None)
+        .await
+        .expect("enqueue live");
+    let job = tasks::claim_next_task_job(&pool, "w", 60, &ClaimFilter::default())
+        .await
+        .expect("claim")
+        .expect("claimed");
+    assert_eq!(job.id, live_id);
+    let mut progress = TaskProgress::new(&job.id, 4);
+    progress.report(&pool, 1, Some("000001")).await;
+    let res = app
+        .clone()
+        .oneshot(get(format!("/api/tasks/jobs/{live_id}"), Some(&token)))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let v: Value = serde_json::from_str(&body_text(res).await).unwrap();
+    assert_eq!(v["job"]["progress"]["done"], 1);
+    assert_eq!(v["job"]["progress"]["total"], 4);
+    assert_eq!(v["job"]["progress"]["item"], "000001");
+    assert!(
+        tasks::cancel_task_job(&pool, &live_id)
+            .await
+            .expect("cancel")
+    );
+
+    // 无净值数据的基金会被跳过，任务仍正常完成。
+    let job_id = tasks::enqueue_task_job(
+        &pool,
+        "signals_batch",
+        &json!({ "fund_codes": ["000001", "000002"] }),
+        0,
+        None,
+    )
+    .await
+    .expect("enqueue");
+    tasks::run_due_task_jobs(&pool, 10).await.expect("run");
+
+    let res = app
+        .clone()
+        .oneshot(get(format!("/api/tasks/jobs/{job_id}"), Some(&token)))
+        .await
+        .unwrap();
+    let v: Value = serde_json::from_str(&body_text(res).await).unwrap();
+    assert_eq!(v["job"]["status"], "done");
+    assert_eq!(v["job"]["progress"]["done"], 2);
+    assert_eq!(v["job"]["progress"]["total"], 2);
+
+    let res = app
+        .clone()
+        .oneshot(get(format!("/api/tasks/jobs/{job_id}/stream"), None))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+    let res = app
+        .clone()
+        .oneshot(get(
+            "/api/tasks/jobs/00000000-0000-0000-0000-000000000000/stream".to_string(),
+            Some(&token),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+
+    // EventSource 通过查询参数鉴权；任务已结束，推送完日志与进度后发送 end 并关闭。
+    let res = app
+        .clone()
+        .oneshot(get(
+            format!("/api/tasks/jobs/{job_id}/stream?access_token={token}"),
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert!(
+        res.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/event-stream")
+    );
+    let events = parse_sse(&body_text(res).await);
+    let kinds: Vec<&str> = events.iter().map(|(e, _, _)| e.as_str()).collect();
+    assert!(kinds.iter().filter(|k| **k == "log").count() >= 3);
+    assert_eq!(&kinds[kinds.len() - 2..], &["progress", "end"]);
+
+    let logs: Vec<&Value> = events
+        .iter()
+        .filter(|(e, _, _)| e == "log")
+        .map(|(_, _, d)| d)
+        .collect();
+    let run_id = logs[0]["run_id"].as_str().unwrap().to_string();
+    let seqs: Vec<i64> = logs.iter().map(|d| d["seq"].as_i64().unwrap()).collect();
+    assert_eq!(seqs, (1..=seqs.len() as i64).collect::<Vec<_>>());
+    assert_eq!(events[0].1.as_deref(), Some(format!("{run_id}:1").as_str()));
+    assert!(
+        logs.iter()
+            .any(|d| d["message"] == "[000002] 跳过：无净值数据")
+    );
+    let (_, _, end) = events.last().unwrap();
+    assert_eq!(end["status"], "done");
+    assert_eq!(end["progress"]["done"], 2);
+
+    // run 日志流：按 Last-Event-ID 续传。
+    let res = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/tasks/runs/{run_id}/logs/stream"))
+                .header("Authorization", format!("Bearer {token}"))
+                .header("Last-Event-ID", format!("{run_id}:2"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let events = parse_sse(&body_text(res).await);
+    assert_eq!(events[0].2["seq"], 3);
+    assert_eq!(events.len() as i64, seqs.len() as i64 - 2 + 1);
+    let (kind, _, end) = events.last().unwrap();
+    assert_eq!(kind, "end");
+    assert_eq!(end["status"], "ok");
+
+    let n: i64 = sqlx::query("SELECT COUNT(*) as n FROM task_run_log WHERE seq IS NULL")
+        .fetch_one(&pool)
+        .await
+        .expect("count")
+        .get("n");
+    assert_eq!(n, 0);
+}
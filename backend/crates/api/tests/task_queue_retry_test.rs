@@ -0,0 +1,231 @@
+use axum::{body::Body, http::Request};
+use serde_json::{Value, json};
+use sqlx::Row;
+use tower::ServiceExt;
+
+use api::state::AppState;
+use api::tasks;
+
+async fn json_body(res: axum::response::Response) -> Value {
+    let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+        .await
+        .expect("read body");
+    serde_json::from_slice(&bytes).expect("json")
+}
+
+async fn job_state(pool: &sqlx::AnyPool, id: &str) -> (String, i64, Option<String>) {
+    let row = sqlx::query("SELECT status, attempt, lease_owner FROM task_job WHERE id = $1")
+        .bind(id)
+        .fetch_one(pool)
+        .await
+        .expect("task_job exists");
+    (
+        row.get("status"),
+        row.get("attempt"),
+        row.try_get::<Option<String>, _>("lease_owner")
+            .ok()
+            .flatten(),
+    )
+}
+
+async fn make_due(pool: &sqlx::AnyPool, id: &str) {
+    sqlx::query(
+        "UPDATE task_job SET not_before = DATETIME(CURRENT_TIMESTAMP, '-1 minute') WHERE id = $1",
+    )
+    .bind(id)
+    .execute(pool)
+    .await
+    .expect("make due");
+}
+
+#[test]
+fn retry_policy_backoff_is_exponential_and_capped() {
+    let p = tasks::retry_policy("signals_batch");
+    assert_eq!(p.max_attempts, 3);
+    assert_eq!(p.backoff_seconds(1), 30);
+    assert_eq!(p.backoff_seconds(2), 60);
+    assert_eq!(p.backoff_seconds(10), 600);
+
+    assert_eq!(tasks::retry_policy("no_such_task").max_attempts, 1);
+}
+
+#[tokio::test]
+async fn failed_jobs_retry_then_dead_letter_and_can_be_cancelled_or_requeued() {
+    sqlx::any::install_default_drivers();
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+
+    sqlx::query(
+        r#"
+        INSERT INTO auth_user (id, password, is_superuser, username, is_staff, is_active)
+        VALUES (1, 'x', 0, 'owner', 0, 1), (2, 'x', 0, 'other', 0, 1)
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .expect("seed users");
+
+    // 缺 fund_codes：每次执行都会失败。
+    let job_id = tasks::enqueue_task_job(&pool, "signals_batch", &json!({}), 10, Some(1))
+        .await
+        .expect("enqueue");
+
+    tasks::run_due_task_jobs(&pool, 10).await.expect("run #1");
+    let (status, attempt, lease) = job_state(&pool, &job_id).await;
+    assert_eq!((status.as_str(), attempt), ("queued", 1));
+    assert!(lease.is_none());
+
+    // 退避期内不会再次执行。
+    assert_eq!(tasks::run_due_task_jobs(&pool, 10).await.expect("run"), 0);
+
+    for _ in 0..2 {
+        make_due(&pool, &job_id).await;
+        tasks::run_due_task_jobs(&pool, 10).await.expect("retry");
+    }
+    let (status, attempt, _) = job_state(&pool, &job_id).await;
+    assert_eq!((status.as_str(), attempt), ("dead", 3));
+
+    // 执行中途崩溃：租约过期的 running 任务被回收为重试。
+    let stuck_id = tasks::enqueue_task_job(&pool, "sniffer_sync", &json!({}), 0, None)
+        .await
+        .expect("enqueue stuck");
+    sqlx::query(
+        r#"
+        UPDATE task_job
+        SET status = 'running', attempt = 1, lease_owner = 'gone-worker',
+            lease_expires_at = DATETIME(CURRENT_TIMESTAMP, '-1 minute')
+        WHERE id = $1
+        "#,
+    )
+    .bind(&stuck_id)
+    .execute(&pool)
+    .await
+    .expect("simulate stuck job");
+    let run_id = tasks::create_task_run(&pool, "task_job", &stuck_id, "sniffer_sync", None, None)
+        .await
+        .expect("create run");
+    // 租约仍归原 worker：心跳只对持有者生效。
+    assert!(
+        !tasks::heartbeat_task_job(&pool, &stuck_id, "someone-else", 60)
+            .await
+            .expect("heartbeat")
+    );
+
+    assert_eq!(
+        tasks::reclaim_expired_task_jobs(&pool)
+            .await
+            .expect("reclaim"),
+        1
+    );
+    let (status, attempt, lease) = job_state(&pool, &stuck_id).await;
+    assert_eq!((status.as_str(), attempt), ("queued", 1));
+    assert!(lease.is_none());
+    let run_status: String = sqlx::query("SELECT status FROM task_run WHERE id = $1")
+        .bind(&run_id)
+        .fetch_one(&pool)
+        .await
+        .expect("run")
+        .get("status");
+    assert_eq!(run_status, "error");
+
+    let config = api::config::ConfigStore::load();
+    let jwt = api::jwt::JwtService::from_secret("test-secret");
+    let state = AppState::new(
+        Some(pool.clone()),
+        config,
+        jwt,
+        api::db::DatabaseKind::Sqlite,
+    );
+    let owner = state.jwt().issue_access_token("1");
+    let other = state.jwt().issue_access_token("2");
+    let app = api::app(state);
+
+    let req = |method: &str, uri: String, token: &str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Authorization", format!("Bearer {token}"))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let res = app
+        .clone()
+        .oneshot(req("GET", "/api/tasks/dead-letter".to_string(), &owner))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let v = json_body(res).await;
+    let arr = v.as_array().expect("array");
+    assert_eq!(arr.len(), 1);
+    assert_eq!(arr[0]["id"], job_id.as_str());
+    assert_eq!(arr[0]["max_attempts"], 3);
+    assert_eq!(arr[0]["error"], "missing fund_codes");
+
+    // 非创建者且非 staff 无权操作。
+    let res = app
+        .clone()
+        .oneshot(req(
+            "POST",
+            format!("/api/tasks/jobs/{job_id}/requeue"),
+            &other,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 403);
+
+    let res = app
+        .clone()
+        .oneshot(req(
+            "POST",
+            format!("/api/tasks/jobs/{job_id}/requeue"),
+            &owner,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let v = json_body(res).await;
+    assert_eq!(v["status"], "queued");
+    assert_eq!(v["attempt"], 0);
+
+    let res = app
+        .clone()
+        .oneshot(req(
+            "POST",
+            format!("/api/tasks/jobs/{job_id}/cancel"),
+            &owner,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(json_body(res).await["status"], "cancelled");
+
+    // 已取消的任务不会再被执行，也不能重复取消。
+    assert_eq!(job_state(&pool, &job_id).await.0, "cancelled");
+    let res = app
+        .clone()
+        .oneshot(req(
+            "POST",
+            format!("/api/tasks/jobs/{job_id}/cancel"),
+            &owner,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 409);
+
+    let res = app
+        .clone()
+        .oneshot(req(
+            "POST",
+            "/api/tasks/jobs/00000000-0000-0000-0000-000000000000/cancel".to_string(),
+            &owner,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+}
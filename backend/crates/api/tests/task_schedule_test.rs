@@ -0,0 +1,320 @@
+use axum::{body::Body, http::Request};
+use chrono::{DateTime, Utc};
+use serde_json::{Value, json};
+use sqlx::Row;
+use tower::ServiceExt;
+
+use api::state::AppState;
+use api::task_schedule::{self, CronExpr, Recurrence};
+
+fn utc(s: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+}
+
+fn next(expr: &str, after: &str) -> Option<String> {
+    CronExpr::parse(expr)
+        .unwrap()
+        .next_after(utc(after))
+        .map(|t| t.to_rfc3339())
+}
+
+#[test]
+fn cron_next_run_is_computed_in_beijing_time() {
+    // 每天北京时间 03:10（= 前一天 UTC 19:10）。
+    assert_eq!(
+        next("10 3 * * *", "2026-10-18T00:00:00Z").as_deref(),
+        Some("2026-10-18T19:10:00+00:00")
+    );
+    // 恰好在触发时刻：取下一次。
+    assert_eq!(
+        next("10 3 * * *", "2026-10-18T19:10:00Z").as_deref(),
+        Some("2026-10-19T19:10:00+00:00")
+    );
+    // 工作日 18:30：周五晚上之后跳到周一。
+    assert_eq!(
+        next("30 18 * * 1-5", "2026-10-16T11:00:00Z").as_deref(),
+        Some("2026-10-19T10:30:00+00:00")
+    );
+    // 日与周同时受限时取“或”：13 号或周五。
+    assert_eq!(
+        next("0 0 13 * 5", "2026-10-16T16:00:00Z").as_deref(),
+        Some("2026-10-22T16:00:00+00:00")
+    );
+    assert_eq!(
+        next("0 0 13 * *", "2026-10-16T16:00:00Z").as_deref(),
+        Some("2026-11-12T16:00:00+00:00")
+    );
+    assert_eq!(
+        next("0 0 29 2 *", "2026-10-18T00:00:00Z").as_deref(),
+        Some("2028-02-28T16:00:00+00:00")
+    );
+    assert_eq!(
+        next("*/15 * * * *", "2026-10-18T00:07:30Z").as_deref(),
+        Some("2026-10-18T00:15:00+00:00")
+    );
+    assert_eq!(next("0 0 30 2 *", "2026-10-18T00:00:00Z"), None);
+    assert_eq!(CronExpr::parse("0 0 * * 7"), CronExpr::parse("0 0 * * 0"));
+    assert_eq!(CronExpr::parse("@daily"), CronExpr::parse("0 0 * * *"));
+
+    for bad in [
+        "60 * * * *",
+        "* * *",
+        "5-1 * * * *",
+        "*/0 * * * *",
+        "0 0 0 * *",
+    ] {
+        assert!(CronExpr::parse(bad).is_err(), "{bad}");
+    }
+
+    assert!(Recurrence::from_parts(Some("0 * * * *"), Some(600)).is_err());
+    assert!(Recurrence::from_parts(None, None).is_err());
+    assert!(Recurrence::from_parts(None, Some(10)).is_err());
+    assert_eq!(
+        Recurrence::from_parts(None, Some(600))
+            .unwrap()
+            .next_after(utc("2026-10-18T00:00:00Z"))
+            .map(|t| t.to_rfc3339())
+            .as_deref(),
+        Some("2026-10-18T00:10:00+00:00")
+    );
+}
+
+async fn body_json(res: axum::response::Response) -> Value {
+    let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+        .await
+        .expect("read body");
+    serde_json::from_slice(&bytes).unwrap_or(Value::Null)
+}
+
+#[tokio::test]
+async fn due_schedules_enqueue_task_jobs_once_and_admin_crud_works() {
+    sqlx::any::install_default_drivers();
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+
+    sqlx::query(
+        r#"
+        INSERT INTO auth_user (id, password, is_superuser, username, is_staff, is_active)
+        VALUES (1, 'x', 0, 'admin', 1, 1), (2, 'x', 0, 'u', 0, 1)
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .expect("seed users");
+
+    // 内置计划：首轮只初始化 next_run_at，不立即投递；停用的计划不参与。
+    let now = utc("2026-10-18T00:00:00Z");
+    assert_eq!(
+        task_schedule::enqueue_due_schedules(&pool, now)
+            .await
+            .expect("init"),
+        0
+    );
+    let sniffer = task_schedule::find_task_schedule_by_name(&pool, "sniffer_daily")
+        .await
+        .expect("find")
+        .expect("seeded");
+    assert_eq!(sniffer.task_type, "sniffer_sync");
+    assert_eq!(sniffer.next_run_at.as_deref(), Some("2026-10-18 19:10:00"));
+    let forecast = task_schedule::find_task_schedule_by_name(&pool, "forecast_model_weekly")
+        .await
+        .expect("find")
+        .expect("seeded");
+    assert!(!forecast.enabled);
+    assert_eq!(forecast.next_run_at, None);
+
+    // 到期（含停机错过多次）：只投递一次，并推进到下一次。
+    let later = utc("2026-10-20T20:00:00Z");
+    assert_eq!(
+        task_schedule::enqueue_due_schedules(&pool, later)
+            .await
+            .expect("enqueue"),
+        2
+    );
+    assert_eq!(
+        task_schedule::enqueue_due_schedules(&pool, later)
+            .await
+            .expect("enqueue again"),
+        0
+    );
+    let sniffer = task_schedule::get_task_schedule(&pool, &sniffer.id)
+        .await
+        .expect("get")
+        .expect("exists");
+    assert_eq!(sniffer.last_run_at.as_deref(), Some("2026-10-20 20:00:00"));
+    assert_eq!(sniffer.next_run_at.as_deref(), Some("2026-10-21 19:10:00"));
+    let job_id = sniffer.last_job_id.expect("last job");
+    let job = api::tasks::get_task_job(&pool, &job_id)
+        .await
+        .expect("job")
+        .expect("exists");
+    assert_eq!(job.task_type, "sniffer_sync");
+    assert_eq!(job.status, "queued");
+    let types: Vec<String> = sqlx::query("SELECT task_type FROM task_job ORDER BY task_type")
+        .fetch_all(&pool)
+        .await
+        .expect("jobs")
+        .iter()
+        .map(|r| r.get("task_type"))
+        .collect();
+    assert_eq!(types, vec!["rates_risk_free_sync", "sniffer_sync"]);
+
+    let config = api::config::ConfigStore::load();
+    let jwt = api::jwt::JwtService::from_secret("test-secret");
+    let state = AppState::new(
+        Some(pool.clone()),
+        config,
+        jwt,
+        api::db::DatabaseKind::Sqlite,
+    );
+    let admin = state.jwt().issue_access_token("1");
+    let user = state.jwt().issue_access_token("2");
+    let app = api::app(state);
+    let call = |method: &str, uri: String, token: &str, body: Option<Value>| {
+        let b = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Authorization", format!("Bearer {token}"))
+            .header("Content-Type", "application/json");
+        b.body(match body {
+            Some(v) => Body::from(v.to_string()),
+            None => Body::empty(),
+        })
+        .unwrap()
+    };
+
+    let res = app
+        .clone()
+        .oneshot(call("GET", "/api/admin/task-schedules".into(), &user, None))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 403);
+
+    let res = app
+        .clone()
+        .oneshot(call(
+            "GET",
+            "/api/admin/task-schedules".into(),
+            &admin,
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(body_json(res).await["items"].as_array().unwrap().len(), 8);
+
+    for bad in [
+        json!({ "name": "x", "task_type": "sniffer_sync", "cron_expr": "0 * * * *", "interval_seconds": 600 }),
+        json!({ "name": "x", "task_type": "no_such_task", "interval_seconds": 600 }),
+        json!({ "name": "x", "task_type": "sniffer_sync", "cron_expr": "bad" }),
+        json!({ "name": "x", "task_type": "sniffer_sync", "payload": [1], "interval_seconds": 600 }),
+    ] {
+        let res = app
+            .clone()
+            .oneshot(call(
+                "POST",
+                "/api/admin/task-schedules".into(),
+                &admin,
+                Some(bad),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 400);
+    }
+
+    let res = app
+        .clone()
+        .oneshot(call(
+            "POST",
+            "/api/admin/task-schedules".into(),
+            &admin,
+            Some(json!({
+                "name": "signals_hourly",
+                "task_type": "signals_batch",
+                "payload": { "fund_codes": ["000001"] },
+                "interval_seconds": 3600,
+                "priority": 3
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+    let created = body_json(res).await;
+    let id = created["id"].as_str().unwrap().to_string();
+    assert_eq!(created["enabled"], true);
+    assert_eq!(created["payload"]["fund_codes"][0], "000001");
+    assert_eq!(created["created_by"], 1);
+    assert!(created["next_run_at"].is_string());
+
+    let res = app
+        .clone()
+        .oneshot(call(
+            "POST",
+            "/api/admin/task-schedules".into(),
+            &admin,
+            Some(json!({ "name": "signals_hourly", "task_type": "signals_batch", "interval_seconds": 600 })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 409);
+
+    let res = app
+        .clone()
+        .oneshot(call(
+            "PATCH",
+            format!("/api/admin/task-schedules/{id}"),
+            &admin,
+            Some(json!({ "enabled": false })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let v = body_json(res).await;
+    assert_eq!(v["enabled"], false);
+    assert!(v["next_run_at"].is_null());
+
+    let res = app
+        .clone()
+        .oneshot(call(
+            "PATCH",
+            format!("/api/admin/task-schedules/{id}"),
+            &admin,
+            Some(json!({ "enabled": true, "cron_expr": "0 9 * * 1-5" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let v = body_json(res).await;
+    assert_eq!(v["cron_expr"], "0 9 * * 1-5");
+    assert!(v["interval_seconds"].is_null());
+    assert!(v["next_run_at"].is_string());
+    assert_eq!(v["priority"], 3);
+
+    let res = app
+        .clone()
+        .oneshot(call(
+            "DELETE",
+            format!("/api/admin/task-schedules/{id}"),
+            &admin,
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+    let res = app
+        .clone()
+        .oneshot(call(
+            "GET",
+            format!("/api/admin/task-schedules/{id}"),
+            &admin,
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+}
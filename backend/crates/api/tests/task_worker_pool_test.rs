@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+
+use serde_json::json;
+use sqlx::Row;
+
+use api::task_worker::{self, ConcurrencyLimits, RunningCounts, TaskWorkerPool};
+use api::tasks::{self, ClaimFilter};
+
+fn limits() -> ConcurrencyLimits {
+    ConcurrencyLimits {
+        crawl: 1,
+        compute: 2,
+        per_type: task_worker::parse_per_type_limits(
+            "signals_batch=1, bad, x=abc,forecast_model_train=0",
+        ),
+    }
+}
+
+#[test]
+fn claim_filter_respects_lane_and_type_limits() {
+    let limits = limits();
+    assert_eq!(
+        limits.per_type,
+        HashMap::from([
+            ("signals_batch".to_string(), 1),
+            ("forecast_model_train".to_string(), 0),
+        ])
+    );
+
+    // 上限为 0 的类型在本副本永不执行。
+    let idle = RunningCounts::default();
+    assert_eq!(
+        task_worker::claim_filter(&limits, &idle),
+        Some(ClaimFilter {
+            only: vec![],
+            exclude: vec!["forecast_model_train".to_string()],
+        })
+    );
+
+    // 抓取通道已满：只排除抓取类任务。
+    let crawl_busy = RunningCounts {
+        crawl: 1,
+        per_type: HashMap::from([("sniffer_sync".to_string(), 1)]),
+        ..Default::default()
+    };
+    let f = task_worker::claim_filter(&limits, &crawl_busy).unwrap();
+    assert!(f.only.is_empty());
+    assert!(f.exclude.contains(&"nav_history_sync_batch".to_string()));
+    assert!(!f.exclude.contains(&"signals_batch".to_string()));
+
+    // 计算通道已满：只抢抓取类任务。
+    let compute_busy = RunningCounts {
+        compute: 2,
+        per_type: HashMap::from([
+            ("signals_batch".to_string(), 1),
+            ("quant_xalpha_grid_batch".to_string(), 1),
+        ]),
+        ..Default::default()
+    };
+    let f = task_worker::claim_filter(&limits, &compute_busy).unwrap();
+    assert_eq!(
+        f.only,
+        tasks::CRAWL_LANE_TASK_TYPES
+            .iter()
+            .map(|t| t.to_string())
+            .collect::<Vec<_>>()
+    );
+
+    let all_busy = RunningCounts {
+        crawl: 1,
+        ..compute_busy
+    };
+    assert_eq!(task_worker::claim_filter(&limits, &all_busy), None);
+
+    assert_eq!(
+        tasks::task_lane("prices_refresh_batch"),
+        tasks::TaskLane::Crawl
+    );
+    assert_eq!(tasks::task_lane("whatever"), tasks::TaskLane::Compute);
+}
+
+#[tokio::test]
+async fn pool_claims_within_limits_and_isolates_lanes() {
+    sqlx::any::install_default_drivers();
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+
+    // 缺 fund_codes 的任务执行即失败，不会访问外部服务。
+    for _ in 0..3 {
+        tasks::enqueue_task_job(&pool, "signals_batch", &json!({}), 10, None)
+            .await
+            .expect("enqueue signals");
+    }
+    tasks::enqueue_task_job(&pool, "quant_xalpha_metrics_batch", &json!({}), 5, None)
+        .await
+        .expect("enqueue quant");
+    tasks::enqueue_task_job(&pool, "nav_history_sync_batch", &json!({}), 1, None)
+        .await
+        .expect("enqueue nav");
+
+    assert!(
+        tasks::claim_next_task_job(
+            &pool,
+            "w",
+            60,
+            &ClaimFilter {
+                only: vec!["no_such_task".to_string()],
+                exclude: vec![],
+            },
+        )
+        .await
+        .expect("claim")
+        .is_none()
+    );
+
+    let workers = TaskWorkerPool::new(pool.clone());
+    assert_eq!(workers.fill(&limits(), 0, 60).await.expect("fill"), 0);
+
+    // signals_batch 限 1 个；计算通道的第二个名额给 quant；抓取通道独立再派发 1 个。
+    assert_eq!(workers.fill(&limits(), 10, 60).await.expect("fill"), 3);
+
+    for _ in 0..250 {
+        if workers.running() == RunningCounts::default() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert_eq!(workers.running(), RunningCounts::default());
+
+    let rows = sqlx::query("SELECT task_type, attempt FROM task_job ORDER BY task_type, attempt")
+        .fetch_all(&pool)
+        .await
+        .expect("select jobs");
+    let got: Vec<(String, i64)> = rows
+        .iter()
+        .map(|r| (r.get::<String, _>("task_type"), r.get::<i64, _>("attempt")))
+        .collect();
+    assert_eq!(
+        got,
+        vec![
+            ("nav_history_sync_batch".to_string(), 1),
+            ("quant_xalpha_metrics_batch".to_string(), 1),
+            ("signals_batch".to_string(), 0),
+            ("signals_batch".to_string(), 0),
+            ("signals_batch".to_string(), 1),
+        ]
+    );
+}
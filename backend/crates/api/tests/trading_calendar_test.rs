@@ -0,0 +1,201 @@
+use axum::{body::Body, http::Request};
+use chrono::NaiveDate;
+use serde_json::Value;
+use tower::ServiceExt;
+
+use api::state::AppState;
+use api::trading_calendar::{self, Market, TradingCalendar};
+
+fn d(s: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
+
+async fn body_json(res: axum::response::Response) -> Value {
+    let bytes = axum::body::to_bytes(res.into_body(), usize::MAX)
+        .await
+        .expect("read body");
+    serde_json::from_slice(&bytes).expect("json")
+}
+
+#[test]
+fn bundled_calendar_handles_holidays_weekends_and_qdii() {
+    let cal = trading_calendar::bundled();
+
+    // 2025 春节：1/28 ~ 2/4 休市，2/5 复市
+    assert!(!cal.is_trading_day(Market::Cn, d("2025-01-28")));
+    assert!(!cal.is_trading_day(Market::Cn, d("2025-02-01")));
+    assert!(cal.is_trading_day(Market::Cn, d("2025-02-05")));
+    assert_eq!(
+        cal.next_trading_day(Market::Cn, d("2025-01-27")),
+        d("2025-02-05")
+    );
+    assert_eq!(
+        cal.prev_trading_day(Market::Cn, d("2025-02-05")),
+        d("2025-01-27")
+    );
+    assert_eq!(
+        cal.last_trading_day_on_or_before(Market::Cn, d("2025-02-02")),
+        d("2025-01-27")
+    );
+
+    // 调休补班的周末不开市
+    assert!(!cal.is_trading_day(Market::Cn, d("2025-01-26")));
+
+    // 美股感恩节：A 股开市但 QDII(美) 不视为交易日
+    assert!(cal.is_trading_day(Market::Cn, d("2025-11-27")));
+    assert!(!cal.is_trading_day(Market::Us, d("2025-11-27")));
+    assert!(!cal.is_trading_day(Market::QdiiUs, d("2025-11-27")));
+    assert!(cal.is_trading_day(Market::QdiiHk, d("2025-11-27")));
+
+    // n 个交易日偏移：非交易日起点 +1 即下一个交易日
+    assert_eq!(
+        cal.add_trading_days(Market::Cn, d("2025-02-01"), 1),
+        d("2025-02-05")
+    );
+    assert_eq!(
+        cal.add_trading_days(Market::Cn, d("2025-02-05"), -2),
+        d("2025-01-24")
+    );
+    assert_eq!(
+        cal.add_trading_days(Market::Cn, d("2025-02-05"), 0),
+        d("2025-02-05")
+    );
+
+    let days = cal.trading_days_between(Market::Cn, d("2025-09-29"), d("2025-10-10"));
+    assert_eq!(
+        days,
+        vec![
+            d("2025-09-29"),
+            d("2025-09-30"),
+            d("2025-10-09"),
+            d("2025-10-10")
+        ]
+    );
+}
+
+#[test]
+fn csv_parsing_tracks_coverage_and_rejects_bad_dates() {
+    let cal = TradingCalendar::from_csv("# comment\nmarket,date,name\ncn,2030-01-01,元旦\n")
+        .expect("parse");
+    assert!(cal.is_covered(Market::Cn, d("2030-06-01")));
+    assert!(!cal.is_covered(Market::Cn, d("2031-06-01")));
+    assert!(!cal.is_covered(Market::QdiiUs, d("2030-06-01")));
+    assert!(!cal.is_trading_day(Market::Cn, d("2030-01-01")));
+
+    assert!(TradingCalendar::from_csv("cn,2030-13-01,bad\n").is_err());
+}
+
+#[tokio::test]
+async fn verify_against_index_and_calendar_routes() {
+    sqlx::any::install_default_drivers();
+
+    let pool = sqlx::any::AnyPoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("connect sqlite in-memory");
+
+    let migrator = sqlx::migrate!("../../migrations/sqlite");
+    migrator.run(&pool).await.expect("migrate");
+
+    sqlx::query(
+        r#"
+        INSERT INTO auth_user (id, password, is_superuser, username, is_staff, is_active)
+        VALUES (1, 'x', 0, 'u', 0, 1)
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .expect("seed user");
+
+    // 2025-09-29 ~ 2025-10-10：缺 10-10 的行情，且 10-08（休市）意外出现
+    for (i, day) in ["2025-09-29", "2025-09-30", "2025-10-08", "2025-10-09"]
+        .iter()
+        .enumerate()
+    {
+        sqlx::query(
+            r#"
+            INSERT INTO index_daily_price (id, index_code, source_name, trade_date, close)
+            VALUES ($1, '1.000300', 'eastmoney', $2, '4000')
+            "#,
+        )
+        .bind(format!("p{i}"))
+        .bind(*day)
+        .execute(&pool)
+        .await
+        .expect("seed index");
+    }
+
+    let report = trading_calendar::verify_against_index(
+        &pool,
+        trading_calendar::bundled(),
+        Market::Cn,
+        "1.000300",
+        "eastmoney",
+        d("2025-09-01"),
+        d("2025-10-31"),
+    )
+    .await
+    .expect("verify");
+    assert_eq!(report.start_date.as_deref(), Some("2025-09-29"));
+    assert_eq!(report.end_date.as_deref(), Some("2025-10-09"));
+    assert!(report.missing_in_index.is_empty());
+    assert_eq!(report.unexpected_in_index, vec!["2025-10-08".to_string()]);
+
+    let config = api::config::ConfigStore::load();
+    let jwt = api::jwt::JwtService::from_secret("test-secret");
+    let state = AppState::new(Some(pool), config, jwt, api::db::DatabaseKind::Sqlite);
+    let token = state.jwt().issue_access_token("1");
+    let app = api::app(state);
+
+    let get = |uri: &str| {
+        Request::builder()
+            .uri(uri)
+            .header("authorization", format!("Bearer {token}"))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let res = app
+        .clone()
+        .oneshot(get("/api/calendar/cn/day?date=2025-10-01"))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let v = body_json(res).await;
+    assert_eq!(v["is_trading_day"], false);
+    assert_eq!(v["next_trading_day"], "2025-10-09");
+    assert_eq!(v["covered"], true);
+
+    let res = app
+        .clone()
+        .oneshot(get("/api/calendar/cn/offset?date=2025-09-30&n=2"))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(body_json(res).await["result"], "2025-10-10");
+
+    let res = app
+        .clone()
+        .oneshot(get("/api/calendar/us/days?start=2025-07-01&end=2025-07-07"))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    // 7/4 独立日休市：7/1、7/2、7/3、7/7
+    assert_eq!(body_json(res).await["count"], 4);
+
+    let res = app
+        .clone()
+        .oneshot(get("/api/calendar/mars/day"))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+
+    let res = app
+        .clone()
+        .oneshot(get("/api/calendar/verify?start=2025-09-01&end=2025-10-31"))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(body_json(res).await["unexpected_in_index"][0], "2025-10-08");
+}
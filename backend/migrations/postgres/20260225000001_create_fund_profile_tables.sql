@@ -0,0 +1,53 @@
+-- Fund master data: company / profile / manager tenure / AUM history (Postgres flavor)
+
+CREATE TABLE IF NOT EXISTS fund_company (
+  company_id TEXT PRIMARY KEY,
+  company_name TEXT NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS fund_profile (
+  fund_code TEXT PRIMARY KEY,
+  company_id TEXT NULL,
+  inception_date DATE NULL,
+  benchmark TEXT NULL,
+  management_fee_pct DOUBLE PRECISION NULL,
+  custody_fee_pct DOUBLE PRECISION NULL,
+  share_class TEXT NULL,
+  purchase_status TEXT NULL,
+  redeem_status TEXT NULL,
+  source TEXT NOT NULL,
+  fetched_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS fund_profile_company_id_idx ON fund_profile(company_id);
+
+CREATE TABLE IF NOT EXISTS fund_manager_tenure (
+  fund_code TEXT NOT NULL,
+  manager_id TEXT NOT NULL,
+  manager_name TEXT NOT NULL,
+  start_date DATE NOT NULL,
+  end_date DATE NULL,
+  tenure_return_pct DOUBLE PRECISION NULL,
+  source TEXT NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+  PRIMARY KEY (fund_code, manager_id, start_date)
+);
+
+CREATE INDEX IF NOT EXISTS fund_manager_tenure_manager_id_idx ON fund_manager_tenure(manager_id);
+
+CREATE TABLE IF NOT EXISTS fund_scale_history (
+  fund_code TEXT NOT NULL,
+  report_date DATE NOT NULL,
+  aum_cny DOUBLE PRECISION NOT NULL,
+  source TEXT NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+  PRIMARY KEY (fund_code, report_date, source)
+);
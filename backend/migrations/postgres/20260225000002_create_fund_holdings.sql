@@ -0,0 +1,33 @@
+-- Quarterly disclosed top holdings + industry allocation (Postgres flavor)
+
+CREATE TABLE IF NOT EXISTS fund_holding (
+  fund_code TEXT NOT NULL,
+  report_date DATE NOT NULL,
+  asset_type TEXT NOT NULL,
+  sec_code TEXT NOT NULL,
+  sec_name TEXT NOT NULL,
+  weight_pct DOUBLE PRECISION NOT NULL,
+  shares DOUBLE PRECISION NULL,
+  market_value_cny DOUBLE PRECISION NULL,
+  source TEXT NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+  PRIMARY KEY (fund_code, report_date, asset_type, sec_code, source)
+);
+
+CREATE INDEX IF NOT EXISTS fund_holding_sec_code_idx ON fund_holding(sec_code, report_date);
+CREATE INDEX IF NOT EXISTS fund_holding_fund_date_idx ON fund_holding(fund_code, asset_type, report_date);
+
+CREATE TABLE IF NOT EXISTS fund_industry_allocation (
+  fund_code TEXT NOT NULL,
+  report_date DATE NOT NULL,
+  industry_name TEXT NOT NULL,
+  weight_pct DOUBLE PRECISION NOT NULL,
+  market_value_cny DOUBLE PRECISION NULL,
+  source TEXT NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+  PRIMARY KEY (fund_code, report_date, industry_name, source)
+);
@@ -0,0 +1,15 @@
+-- Intraday estimate samples (Postgres flavor)
+-- estimate_time 为数据源给出的本地时间（如 fundgz gztime），不带时区。
+
+CREATE TABLE IF NOT EXISTS fund_estimate_intraday (
+  fund_code TEXT NOT NULL,
+  source_name TEXT NOT NULL,
+  estimate_time TIMESTAMP NOT NULL,
+  estimate_nav NUMERIC(10, 4) NOT NULL,
+  estimate_growth NUMERIC(10, 4) NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+  PRIMARY KEY (fund_code, source_name, estimate_time)
+);
+
+CREATE INDEX IF NOT EXISTS fund_estimate_intraday_time_idx ON fund_estimate_intraday(estimate_time);
@@ -0,0 +1,10 @@
+-- Fund API access stats (Postgres flavor)
+-- 基金详情/估值接口被访问时累加，供抓取优先级评分参考“最近是否有人在看”。
+
+CREATE TABLE IF NOT EXISTS fund_view_stat (
+  fund_code TEXT PRIMARY KEY,
+  view_count BIGINT NOT NULL DEFAULT 0,
+  last_viewed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS fund_view_stat_last_viewed_idx ON fund_view_stat(last_viewed_at);
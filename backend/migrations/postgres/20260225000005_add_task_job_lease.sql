@@ -0,0 +1,9 @@
+-- Task queue leases (Postgres flavor)
+-- task_job.status 新增：cancelled（手动取消）| dead（重试耗尽，进入死信）；task_run.status 新增 cancelled。
+-- running 任务需持有租约并定期心跳；租约过期（进程崩溃等）由 worker 回收并按重试策略处理。
+
+ALTER TABLE task_job ADD COLUMN IF NOT EXISTS lease_owner TEXT NULL;
+ALTER TABLE task_job ADD COLUMN IF NOT EXISTS lease_expires_at TIMESTAMPTZ NULL;
+ALTER TABLE task_job ADD COLUMN IF NOT EXISTS heartbeat_at TIMESTAMPTZ NULL;
+
+CREATE INDEX IF NOT EXISTS task_job_lease_idx ON task_job(status, lease_expires_at);
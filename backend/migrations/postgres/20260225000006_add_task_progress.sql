@@ -0,0 +1,13 @@
+-- Task progress + log sequence (Postgres flavor)
+-- 批量任务在执行中上报进度（已完成/总数/当前条目/预计剩余秒数），写回 task_job。
+-- task_run_log.seq：同一 run 内单调递增的序号，供 SSE 流按游标增量推送日志。
+
+ALTER TABLE task_job ADD COLUMN IF NOT EXISTS progress_done INTEGER NULL;
+ALTER TABLE task_job ADD COLUMN IF NOT EXISTS progress_total INTEGER NULL;
+ALTER TABLE task_job ADD COLUMN IF NOT EXISTS progress_item TEXT NULL;
+ALTER TABLE task_job ADD COLUMN IF NOT EXISTS progress_eta_seconds INTEGER NULL;
+ALTER TABLE task_job ADD COLUMN IF NOT EXISTS progress_updated_at TIMESTAMPTZ NULL;
+
+ALTER TABLE task_run_log ADD COLUMN IF NOT EXISTS seq INTEGER NULL;
+
+CREATE INDEX IF NOT EXISTS task_run_log_seq_idx ON task_run_log(run_id, seq);
@@ -0,0 +1,33 @@
+-- Audit log (Postgres flavor)
+-- 管理操作与数据变更的审计记录：谁（actor）在何时从哪里（ip）对什么（target）做了什么（action），
+-- 以及变更前后的快照。只允许追加：触发器拒绝 UPDATE/DELETE。
+-- actor_user_id 不设外键：用户被删除后审计记录仍需保留。
+
+CREATE TABLE IF NOT EXISTS audit_event (
+  id UUID PRIMARY KEY,
+  actor_user_id BIGINT NULL,
+  action TEXT NOT NULL,
+  target_type TEXT NOT NULL,
+  target_id TEXT NULL,
+  before_json TEXT NULL,
+  after_json TEXT NULL,
+  ip TEXT NULL,
+  user_agent TEXT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS audit_event_created_idx ON audit_event(created_at DESC);
+CREATE INDEX IF NOT EXISTS audit_event_actor_idx ON audit_event(actor_user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS audit_event_action_idx ON audit_event(action, created_at DESC);
+CREATE INDEX IF NOT EXISTS audit_event_target_idx ON audit_event(target_type, target_id);
+
+CREATE OR REPLACE FUNCTION audit_event_append_only() RETURNS trigger AS $$
+BEGIN
+  RAISE EXCEPTION 'audit_event is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS audit_event_no_modify ON audit_event;
+CREATE TRIGGER audit_event_no_modify
+BEFORE UPDATE OR DELETE ON audit_event
+FOR EACH ROW EXECUTE FUNCTION audit_event_append_only();
@@ -0,0 +1,23 @@
+-- Refresh-token sessions (Postgres flavor)
+-- 每次登录产生一个会话（刷新令牌家族）。刷新令牌每用一次就轮换，refresh_jti 只记录当前有效的那一枚；
+-- 出示同一会话中已被轮换掉的令牌视为泄露重放，整个会话被吊销。
+-- 访问令牌携带会话 id（sid），会话吊销后其访问令牌随之失效。
+
+CREATE TABLE IF NOT EXISTS auth_session (
+  id UUID PRIMARY KEY,
+  user_id BIGINT NOT NULL REFERENCES auth_user(id) ON DELETE CASCADE,
+  refresh_jti VARCHAR(64) NOT NULL,
+  ip VARCHAR(64) NULL,
+  user_agent TEXT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  last_used_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  expires_at TIMESTAMPTZ NOT NULL,
+  revoked_at TIMESTAMPTZ NULL,
+  revoke_reason VARCHAR(32) NULL
+);
+
+CREATE INDEX IF NOT EXISTS auth_session_user_idx ON auth_session(user_id, revoked_at);
+CREATE INDEX IF NOT EXISTS auth_session_revoked_idx ON auth_session(revoked_at);
+
+-- 修改密码时写入：不晚于该时间签发的、不绑定会话的访问令牌一律无效（会话令牌随会话吊销）。
+ALTER TABLE auth_user ADD COLUMN IF NOT EXISTS tokens_valid_after TIMESTAMPTZ NULL;
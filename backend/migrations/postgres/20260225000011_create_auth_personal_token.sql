@@ -0,0 +1,19 @@
+-- Personal access tokens (Postgres flavor)
+-- 供脚本/外部集成使用的长期令牌：明文只在创建时返回一次，库中只存 SHA-256 摘要。
+-- scopes 为逗号分隔的作用域（read / tasks / sim），由路由中间件按请求方法与路径校验。
+
+CREATE TABLE IF NOT EXISTS auth_personal_token (
+  id UUID PRIMARY KEY,
+  user_id BIGINT NOT NULL REFERENCES auth_user(id) ON DELETE CASCADE,
+  name VARCHAR(100) NOT NULL,
+  token_prefix VARCHAR(16) NOT NULL,
+  token_hash VARCHAR(64) NOT NULL UNIQUE,
+  scopes VARCHAR(100) NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  expires_at TIMESTAMPTZ NULL,
+  last_used_at TIMESTAMPTZ NULL,
+  last_used_ip VARCHAR(64) NULL,
+  revoked_at TIMESTAMPTZ NULL
+);
+
+CREATE INDEX IF NOT EXISTS auth_personal_token_user_idx ON auth_personal_token(user_id, revoked_at);
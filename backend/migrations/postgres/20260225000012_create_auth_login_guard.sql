@@ -0,0 +1,31 @@
+-- Login throttling and TOTP two-factor (Postgres flavor)
+-- auth_login_failure：按用户名（user:<name>）与来源 IP（ip:<addr>）累计失败次数；
+-- locked_until 同时承载渐进延迟与锁定，未到时间的登录请求直接拒绝（不做密码校验）。
+
+CREATE TABLE IF NOT EXISTS auth_login_failure (
+  key VARCHAR(200) PRIMARY KEY,
+  failures INTEGER NOT NULL DEFAULT 0,
+  last_failed_at TIMESTAMPTZ NOT NULL,
+  locked_until TIMESTAMPTZ NULL
+);
+
+-- 每个用户至多一条 TOTP 密钥；enabled_at 为空表示已生成但尚未确认（注册中）。
+-- last_step 记录最近一次被接受的时间步，防止同一验证码重放。
+CREATE TABLE IF NOT EXISTS auth_totp (
+  user_id BIGINT PRIMARY KEY REFERENCES auth_user(id) ON DELETE CASCADE,
+  secret VARCHAR(64) NOT NULL,
+  enabled_at TIMESTAMPTZ NULL,
+  last_step BIGINT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- 恢复码只存 SHA-256 摘要，每个只能使用一次。
+CREATE TABLE IF NOT EXISTS auth_recovery_code (
+  id UUID PRIMARY KEY,
+  user_id BIGINT NOT NULL REFERENCES auth_user(id) ON DELETE CASCADE,
+  code_hash VARCHAR(64) NOT NULL,
+  used_at TIMESTAMPTZ NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS auth_recovery_code_user_idx ON auth_recovery_code(user_id, used_at);
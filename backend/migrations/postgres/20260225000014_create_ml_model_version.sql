@@ -0,0 +1,37 @@
+-- 模型版本库：每次训练产生一个不可变版本（板块信号模型 / 全市场净值预测模型）。
+-- family='sector' 时 model_key 为 "{peer_code}/{task}/{horizon_days}"；
+-- family='forecast' 时为 "{model_name}/{source}/{horizon}/{lag_k}"。
+-- payload_json 自带定位服务表所需的键与模型本体；晋升/回滚时写回 ml_sector_model / forecast_model。
+-- status：challenger（待定）、champion（当前生效）、retired（曾生效）、rejected（影子评估不如冠军）。
+CREATE TABLE IF NOT EXISTS ml_model_version (
+  id UUID PRIMARY KEY,
+  family TEXT NOT NULL,
+  model_key TEXT NOT NULL,
+  version INTEGER NOT NULL,
+  model_type TEXT NOT NULL,
+  feature_names_json TEXT NOT NULL,
+  feature_set_hash TEXT NOT NULL,
+  payload_json TEXT NOT NULL,
+  metrics_json TEXT NOT NULL,
+  train_start_date DATE NULL,
+  train_end_date DATE NULL,
+  sample_count BIGINT NOT NULL DEFAULT 0,
+  status TEXT NOT NULL,
+  shadow_json TEXT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  promoted_at TIMESTAMPTZ NULL,
+
+  CONSTRAINT ml_model_version_unique UNIQUE (family, model_key, version)
+);
+
+CREATE INDEX IF NOT EXISTS ml_model_version_key_idx ON ml_model_version(family, model_key, status);
+
+-- 生效指针：每个 (family, model_key) 至多一个冠军版本。
+CREATE TABLE IF NOT EXISTS ml_model_active (
+  family TEXT NOT NULL,
+  model_key TEXT NOT NULL,
+  version_id UUID NOT NULL REFERENCES ml_model_version(id),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+  PRIMARY KEY (family, model_key)
+);
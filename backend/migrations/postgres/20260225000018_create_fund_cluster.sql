@@ -0,0 +1,29 @@
+-- 按日收益相关性聚类得到的基金同类组（k-medoids）；每次运行整体替换。
+-- peer_code 形如 `cluster:<代表基金代码>`，代表基金不变时同类组代码与已训练的板块模型保持不变。
+CREATE TABLE IF NOT EXISTS fund_cluster (
+  peer_code TEXT PRIMARY KEY,
+  peer_name TEXT NOT NULL,
+  medoid_fund_code TEXT NOT NULL,
+  source_name TEXT NOT NULL,
+  size BIGINT NOT NULL,
+  mean_stability DOUBLE PRECISION NOT NULL,
+  mean_silhouette DOUBLE PRECISION NOT NULL,
+  computed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- 每只基金的聚类归属；stability 为子样本重聚类时同组成员的平均 Jaccard 相似度（0..1）。
+CREATE TABLE IF NOT EXISTS fund_cluster_member (
+  fund_code TEXT PRIMARY KEY,
+  peer_code TEXT NOT NULL,
+  stability DOUBLE PRECISION NOT NULL,
+  silhouette DOUBLE PRECISION NOT NULL,
+  computed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS fund_cluster_member_peer_code_idx ON fund_cluster_member(peer_code);
+
+-- 每周重聚类一次；默认关闭，由管理员按需启用。
+INSERT INTO task_schedule (id, name, task_type, payload_json, cron_expr, interval_seconds, priority, enabled)
+VALUES
+  ('5b0c7d1e-6f0a-4c39-9a51-3f1d2b7e0007', 'fund_cluster_weekly', 'fund_cluster_compute', '{}', '0 21 * * 6', NULL, 0, FALSE)
+ON CONFLICT (name) DO NOTHING;
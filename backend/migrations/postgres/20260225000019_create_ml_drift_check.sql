@@ -0,0 +1,22 @@
+-- 漂移监控记录：每次检查一个冠军版本写一行，供人工复核重训决定。
+-- decision：ok（未越阈值）/ retrain（已入队重训）/ retrain_pending（已有同参数重训在排队或运行）。
+CREATE TABLE IF NOT EXISTS ml_drift_check (
+  id UUID PRIMARY KEY,
+  family TEXT NOT NULL,
+  model_key TEXT NOT NULL,
+  model_version_id UUID NOT NULL,
+  version BIGINT NOT NULL,
+  decision TEXT NOT NULL,
+  reasons_json TEXT NOT NULL,
+  report_json TEXT NOT NULL,
+  task_job_id UUID NULL,
+  checked_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS ml_drift_check_model_idx ON ml_drift_check(family, model_key, checked_at);
+
+-- 每天检查一次；默认关闭，由管理员按需启用。
+INSERT INTO task_schedule (id, name, task_type, payload_json, cron_expr, interval_seconds, priority, enabled)
+VALUES
+  ('5b0c7d1e-6f0a-4c39-9a51-3f1d2b7e0008', 'ml_drift_monitor_daily', 'ml_drift_monitor', '{}', '0 22 * * *', NULL, 0, FALSE)
+ON CONFLICT (name) DO NOTHING;
@@ -0,0 +1,33 @@
+-- 聚类同类组按数据源分别保存：不同数据源各自重聚类、互不覆盖（Postgres flavor）
+-- fund_cluster 主键改为 (source_name, peer_code)；fund_cluster_member 增加 source_name，主键改为 (source_name, fund_code)。
+
+ALTER TABLE fund_cluster_member
+  ADD COLUMN IF NOT EXISTS source_name TEXT NULL;
+
+-- 旧成员行的数据源取自所属同类组；找不到同类组的孤立行直接删除。
+UPDATE fund_cluster_member m
+SET source_name = c.source_name
+FROM fund_cluster c
+WHERE c.peer_code = m.peer_code AND m.source_name IS NULL;
+
+DELETE FROM fund_cluster_member WHERE source_name IS NULL;
+
+ALTER TABLE fund_cluster_member
+  ALTER COLUMN source_name SET NOT NULL;
+
+ALTER TABLE fund_cluster_member
+  DROP CONSTRAINT IF EXISTS fund_cluster_member_pkey;
+
+ALTER TABLE fund_cluster_member
+  ADD CONSTRAINT fund_cluster_member_pkey PRIMARY KEY (source_name, fund_code);
+
+ALTER TABLE fund_cluster
+  DROP CONSTRAINT IF EXISTS fund_cluster_pkey;
+
+ALTER TABLE fund_cluster
+  ADD CONSTRAINT fund_cluster_pkey PRIMARY KEY (source_name, peer_code);
+
+DROP INDEX IF EXISTS fund_cluster_member_peer_code_idx;
+
+CREATE INDEX IF NOT EXISTS fund_cluster_member_source_peer_code_idx
+  ON fund_cluster_member(source_name, peer_code);
@@ -0,0 +1,53 @@
+-- Fund master data: company / profile / manager tenure / AUM history (SQLite flavor)
+
+CREATE TABLE IF NOT EXISTS fund_company (
+  company_id TEXT PRIMARY KEY,
+  company_name TEXT NOT NULL,
+  created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS fund_profile (
+  fund_code TEXT PRIMARY KEY,
+  company_id TEXT NULL,
+  inception_date TEXT NULL,
+  benchmark TEXT NULL,
+  management_fee_pct REAL NULL,
+  custody_fee_pct REAL NULL,
+  share_class TEXT NULL,
+  purchase_status TEXT NULL,
+  redeem_status TEXT NULL,
+  source TEXT NOT NULL,
+  fetched_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS fund_profile_company_id_idx ON fund_profile(company_id);
+
+CREATE TABLE IF NOT EXISTS fund_manager_tenure (
+  fund_code TEXT NOT NULL,
+  manager_id TEXT NOT NULL,
+  manager_name TEXT NOT NULL,
+  start_date TEXT NOT NULL,
+  end_date TEXT NULL,
+  tenure_return_pct REAL NULL,
+  source TEXT NOT NULL,
+  created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+  PRIMARY KEY (fund_code, manager_id, start_date)
+);
+
+CREATE INDEX IF NOT EXISTS fund_manager_tenure_manager_id_idx ON fund_manager_tenure(manager_id);
+
+CREATE TABLE IF NOT EXISTS fund_scale_history (
+  fund_code TEXT NOT NULL,
+  report_date TEXT NOT NULL,
+  aum_cny REAL NOT NULL,
+  source TEXT NOT NULL,
+  created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+  PRIMARY KEY (fund_code, report_date, source)
+);
@@ -0,0 +1,15 @@
+-- Intraday estimate samples (SQLite flavor)
+-- estimate_time 为数据源给出的本地时间（如 fundgz gztime），格式 YYYY-MM-DD HH:MM:SS。
+
+CREATE TABLE IF NOT EXISTS fund_estimate_intraday (
+  fund_code TEXT NOT NULL,
+  source_name TEXT NOT NULL,
+  estimate_time TEXT NOT NULL,
+  estimate_nav NUMERIC NOT NULL,
+  estimate_growth NUMERIC NULL,
+  created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+  PRIMARY KEY (fund_code, source_name, estimate_time)
+);
+
+CREATE INDEX IF NOT EXISTS fund_estimate_intraday_time_idx ON fund_estimate_intraday(estimate_time);
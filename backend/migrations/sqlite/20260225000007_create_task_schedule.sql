@@ -0,0 +1,31 @@
+-- Recurring task schedules (SQLite flavor)
+-- 周期性任务：按 cron 表达式（北京时间）或固定间隔向 task_job 队列投递任务。
+-- cron_expr 与 interval_seconds 二选一；next_run_at 为 NULL 时由调度器初始化。
+
+CREATE TABLE IF NOT EXISTS task_schedule (
+  id TEXT PRIMARY KEY,
+  name TEXT NOT NULL UNIQUE,
+  task_type TEXT NOT NULL,
+  payload_json TEXT NOT NULL DEFAULT '{}',
+  cron_expr TEXT NULL,
+  interval_seconds INTEGER NULL,
+  priority INTEGER NOT NULL DEFAULT 0,
+  enabled INTEGER NOT NULL DEFAULT 1,
+  last_run_at TEXT NULL,
+  next_run_at TEXT NULL,
+  last_job_id TEXT NULL,
+  created_by INTEGER NULL REFERENCES auth_user(id) ON DELETE SET NULL,
+  created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  CONSTRAINT task_schedule_one_trigger CHECK ((cron_expr IS NULL) <> (interval_seconds IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS task_schedule_due_idx ON task_schedule(enabled, next_run_at);
+
+-- 内置计划：原先硬编码/手动触发的周期性工作。训练类默认关闭，由管理员按需启用。
+INSERT OR IGNORE INTO task_schedule (id, name, task_type, payload_json, cron_expr, interval_seconds, priority, enabled)
+VALUES
+  ('5b0c7d1e-6f0a-4c39-9a51-3f1d2b7e0001', 'sniffer_daily', 'sniffer_sync', '{}', '10 3 * * *', NULL, 0, 1),
+  ('5b0c7d1e-6f0a-4c39-9a51-3f1d2b7e0002', 'rates_risk_free_daily', 'rates_risk_free_sync', '{}', '30 18 * * 1-5', NULL, 0, 1),
+  ('5b0c7d1e-6f0a-4c39-9a51-3f1d2b7e0003', 'forecast_model_weekly', 'forecast_model_train', '{}', '0 4 * * 6', NULL, 0, 0),
+  ('5b0c7d1e-6f0a-4c39-9a51-3f1d2b7e0004', 'ml_sector_model_weekly', 'ml_sector_model_train', '{}', '0 5 * * 6', NULL, 0, 0);
@@ -0,0 +1,34 @@
+-- Audit log (SQLite flavor)
+-- 管理操作与数据变更的审计记录：谁（actor）在何时从哪里（ip）对什么（target）做了什么（action），
+-- 以及变更前后的快照。只允许追加：触发器拒绝 UPDATE/DELETE。
+-- actor_user_id 不设外键：用户被删除后审计记录仍需保留。
+
+CREATE TABLE IF NOT EXISTS audit_event (
+  id TEXT PRIMARY KEY,
+  actor_user_id INTEGER NULL,
+  action TEXT NOT NULL,
+  target_type TEXT NOT NULL,
+  target_id TEXT NULL,
+  before_json TEXT NULL,
+  after_json TEXT NULL,
+  ip TEXT NULL,
+  user_agent TEXT NULL,
+  created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS audit_event_created_idx ON audit_event(created_at);
+CREATE INDEX IF NOT EXISTS audit_event_actor_idx ON audit_event(actor_user_id, created_at);
+CREATE INDEX IF NOT EXISTS audit_event_action_idx ON audit_event(action, created_at);
+CREATE INDEX IF NOT EXISTS audit_event_target_idx ON audit_event(target_type, target_id);
+
+CREATE TRIGGER IF NOT EXISTS audit_event_no_update
+BEFORE UPDATE ON audit_event
+BEGIN
+  SELECT RAISE(ABORT, 'audit_event is append-only');
+END;
+
+CREATE TRIGGER IF NOT EXISTS audit_event_no_delete
+BEFORE DELETE ON audit_event
+BEGIN
+  SELECT RAISE(ABORT, 'audit_event is append-only');
+END;
@@ -0,0 +1,45 @@
+-- Roles and capabilities (SQLite flavor)
+-- 角色 = 一组具名能力（如 crawl.admin）；用户可持有多个角色。
+-- is_superuser 拥有全部能力，is_staff 拥有内置的员工能力集（见 permissions.rs），角色在此基础上追加。
+
+CREATE TABLE IF NOT EXISTS auth_role (
+  name TEXT PRIMARY KEY,
+  description TEXT NOT NULL DEFAULT '',
+  is_system INTEGER NOT NULL DEFAULT 0,
+  created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS auth_role_capability (
+  role_name TEXT NOT NULL REFERENCES auth_role(name) ON DELETE CASCADE,
+  capability TEXT NOT NULL,
+  PRIMARY KEY (role_name, capability)
+);
+
+CREATE TABLE IF NOT EXISTS auth_user_role (
+  user_id INTEGER NOT NULL REFERENCES auth_user(id) ON DELETE CASCADE,
+  role_name TEXT NOT NULL REFERENCES auth_role(name) ON DELETE CASCADE,
+  granted_by INTEGER NULL REFERENCES auth_user(id) ON DELETE SET NULL,
+  created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (user_id, role_name)
+);
+
+CREATE INDEX IF NOT EXISTS auth_user_role_role_idx ON auth_user_role(role_name);
+
+-- 内置角色：admin 拥有全部能力（*）；operator 负责数据采集与任务运维；analyst 面向重度研究用户。
+INSERT OR IGNORE INTO auth_role (name, description, is_system)
+VALUES
+  ('admin', '全部能力', 1),
+  ('operator', '数据采集、数据源与任务运维', 1),
+  ('analyst', '批量同步与不限量模拟', 1);
+
+INSERT OR IGNORE INTO auth_role_capability (role_name, capability)
+VALUES
+  ('admin', '*'),
+  ('operator', 'crawl.admin'),
+  ('operator', 'sources.configure'),
+  ('operator', 'tasks.view_all'),
+  ('operator', 'tasks.schedule'),
+  ('operator', 'audit.view'),
+  ('analyst', 'nav_history.bulk_sync'),
+  ('analyst', 'sim.unlimited');
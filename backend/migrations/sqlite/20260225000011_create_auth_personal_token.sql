@@ -0,0 +1,19 @@
+-- Personal access tokens (SQLite flavor)
+-- 供脚本/外部集成使用的长期令牌：明文只在创建时返回一次，库中只存 SHA-256 摘要。
+-- scopes 为逗号分隔的作用域（read / tasks / sim），由路由中间件按请求方法与路径校验。
+
+CREATE TABLE IF NOT EXISTS auth_personal_token (
+  id TEXT PRIMARY KEY,
+  user_id INTEGER NOT NULL REFERENCES auth_user(id) ON DELETE CASCADE,
+  name TEXT NOT NULL,
+  token_prefix TEXT NOT NULL,
+  token_hash TEXT NOT NULL UNIQUE,
+  scopes TEXT NOT NULL,
+  created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  expires_at TEXT NULL,
+  last_used_at TEXT NULL,
+  last_used_ip TEXT NULL,
+  revoked_at TEXT NULL
+);
+
+CREATE INDEX IF NOT EXISTS auth_personal_token_user_idx ON auth_personal_token(user_id, revoked_at);
@@ -0,0 +1,15 @@
+-- 特征库：每只基金每个交易日一行，values_json 为 {特征名: 数值}。
+-- scope='' 为基金自身特征（净值/指数相对/MACD）；scope=板块代码 为同板块横截面特征。
+CREATE TABLE IF NOT EXISTS fund_feature_row (
+  fund_code TEXT NOT NULL,
+  source_name TEXT NOT NULL,
+  scope TEXT NOT NULL DEFAULT '',
+  as_of_date DATE NOT NULL,
+  feature_set_version INTEGER NOT NULL,
+  values_json TEXT NOT NULL,
+  computed_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+  PRIMARY KEY (fund_code, source_name, scope, as_of_date)
+);
+
+CREATE INDEX IF NOT EXISTS fund_feature_row_scope_date_idx ON fund_feature_row(scope, source_name, as_of_date);
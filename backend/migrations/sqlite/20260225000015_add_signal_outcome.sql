@@ -0,0 +1,21 @@
+-- 信号事后结果：信号日之后满 5T / 20T 个交易日时回填实现收益与标签（与训练标签同口径），
+-- 与预测概率存在同一行，便于统计线上命中率、校准与衰减。
+ALTER TABLE fund_signal_snapshot ADD COLUMN source_name TEXT NULL;
+ALTER TABLE fund_signal_snapshot ADD COLUMN realized_return_5t REAL NULL;
+ALTER TABLE fund_signal_snapshot ADD COLUMN realized_max_return_5t REAL NULL;
+ALTER TABLE fund_signal_snapshot ADD COLUMN dip_buy_outcome_5t INTEGER NULL;
+ALTER TABLE fund_signal_snapshot ADD COLUMN magic_rebound_outcome_5t INTEGER NULL;
+ALTER TABLE fund_signal_snapshot ADD COLUMN outcome_5t_at DATETIME NULL;
+ALTER TABLE fund_signal_snapshot ADD COLUMN realized_return_20t REAL NULL;
+ALTER TABLE fund_signal_snapshot ADD COLUMN realized_max_return_20t REAL NULL;
+ALTER TABLE fund_signal_snapshot ADD COLUMN dip_buy_outcome_20t INTEGER NULL;
+ALTER TABLE fund_signal_snapshot ADD COLUMN magic_rebound_outcome_20t INTEGER NULL;
+ALTER TABLE fund_signal_snapshot ADD COLUMN outcome_20t_at DATETIME NULL;
+
+CREATE INDEX IF NOT EXISTS fund_signal_snapshot_outcome_pending_idx
+  ON fund_signal_snapshot(outcome_20t_at, as_of_date);
+
+-- 每个交易日收盘后回填；默认关闭，由管理员按需启用。
+INSERT OR IGNORE INTO task_schedule (id, name, task_type, payload_json, cron_expr, interval_seconds, priority, enabled)
+VALUES
+  ('5b0c7d1e-6f0a-4c39-9a51-3f1d2b7e0005', 'ml_signal_outcome_daily', 'ml_signal_outcome_resolve', '{}', '0 21 * * 1-5', NULL, 0, 0);
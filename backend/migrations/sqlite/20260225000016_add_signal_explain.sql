@@ -0,0 +1,2 @@
+-- 信号归因：四个概率各自的主要驱动特征与板块基准（SnapshotExplanations 的 JSON）。
+ALTER TABLE fund_signal_snapshot ADD COLUMN explain_json TEXT NULL;
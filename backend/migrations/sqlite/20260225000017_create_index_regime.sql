@@ -0,0 +1,30 @@
+-- 指数市场状态（牛 / 熊 / 震荡）：隐马尔可夫模型对每个交易日的滤波概率（只用截至当日的数据）。
+CREATE TABLE IF NOT EXISTS index_regime (
+  index_code TEXT NOT NULL,
+  source_name TEXT NOT NULL,
+  trade_date TEXT NOT NULL,
+  regime TEXT NOT NULL,
+  p_bull REAL NOT NULL,
+  p_bear REAL NOT NULL,
+  p_sideways REAL NOT NULL,
+  computed_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+  PRIMARY KEY (index_code, source_name, trade_date)
+);
+
+-- 每个指数最近一次拟合的模型参数。
+CREATE TABLE IF NOT EXISTS index_regime_model (
+  index_code TEXT NOT NULL,
+  source_name TEXT NOT NULL,
+  model_json TEXT NOT NULL,
+  sample_count INTEGER NOT NULL,
+  log_likelihood REAL NOT NULL,
+  fitted_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+  PRIMARY KEY (index_code, source_name)
+);
+
+-- 每个交易日收盘后重拟合并追加新日期；默认关闭，由管理员按需启用。
+INSERT OR IGNORE INTO task_schedule (id, name, task_type, payload_json, cron_expr, interval_seconds, priority, enabled)
+VALUES
+  ('5b0c7d1e-6f0a-4c39-9a51-3f1d2b7e0006', 'index_regime_daily', 'index_regime_compute', '{}', '30 20 * * 1-5', NULL, 0, 0);
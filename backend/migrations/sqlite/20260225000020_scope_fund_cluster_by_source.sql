@@ -0,0 +1,53 @@
+-- 聚类同类组按数据源分别保存：不同数据源各自重聚类、互不覆盖（SQLite flavor）
+-- fund_cluster 主键改为 (source_name, peer_code)；fund_cluster_member 增加 source_name，主键改为 (source_name, fund_code)。
+
+PRAGMA foreign_keys=off;
+
+ALTER TABLE fund_cluster RENAME TO fund_cluster_old;
+ALTER TABLE fund_cluster_member RENAME TO fund_cluster_member_old;
+DROP INDEX IF EXISTS fund_cluster_member_peer_code_idx;
+
+CREATE TABLE fund_cluster (
+  source_name TEXT NOT NULL,
+  peer_code TEXT NOT NULL,
+  peer_name TEXT NOT NULL,
+  medoid_fund_code TEXT NOT NULL,
+  size INTEGER NOT NULL,
+  mean_stability REAL NOT NULL,
+  mean_silhouette REAL NOT NULL,
+  computed_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+  PRIMARY KEY (source_name, peer_code)
+);
+
+CREATE TABLE fund_cluster_member (
+  source_name TEXT NOT NULL,
+  fund_code TEXT NOT NULL,
+  peer_code TEXT NOT NULL,
+  stability REAL NOT NULL,
+  silhouette REAL NOT NULL,
+  computed_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+  PRIMARY KEY (source_name, fund_code)
+);
+
+INSERT INTO fund_cluster (
+  source_name, peer_code, peer_name, medoid_fund_code, size, mean_stability, mean_silhouette, computed_at
+)
+SELECT
+  source_name, peer_code, peer_name, medoid_fund_code, size, mean_stability, mean_silhouette, computed_at
+FROM fund_cluster_old;
+
+-- 旧成员行的数据源取自所属同类组。
+INSERT INTO fund_cluster_member (source_name, fund_code, peer_code, stability, silhouette, computed_at)
+SELECT c.source_name, m.fund_code, m.peer_code, m.stability, m.silhouette, m.computed_at
+FROM fund_cluster_member_old m
+JOIN fund_cluster_old c ON c.peer_code = m.peer_code;
+
+DROP TABLE fund_cluster_member_old;
+DROP TABLE fund_cluster_old;
+
+CREATE INDEX IF NOT EXISTS fund_cluster_member_source_peer_code_idx
+  ON fund_cluster_member(source_name, peer_code);
+
+PRAGMA foreign_keys=on;
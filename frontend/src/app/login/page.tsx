@@ -6,7 +6,7 @@ import { CloudServerOutlined, LockOutlined, LoginOutlined, UserOutlined } from "
 import Link from "next/link";
 import { useRouter } from "next/navigation";
 import { PublicShell } from "../../components/PublicShell";
-import { login } from "../../lib/api";
+import { login, loginTwoFactor } from "../../lib/api";
 import { setToken } from "../../lib/auth";
 import { useAuth } from "../../contexts/AuthContext";
 import { isAuthenticated } from "../../lib/auth";
@@ -22,6 +22,7 @@ export default function LoginPage() {
   const [checking, setChecking] = useState(true);
   const [healthError, setHealthError] = useState<string | null>(null);
   const [checkNonce, setCheckNonce] = useState(0);
+  const [mfaToken, setMfaToken] = useState<string | null>(null);
   const { login: authLogin } = useAuth();
 
   useEffect(() => {
@@ -56,19 +57,52 @@ export default function LoginPage() {
     };
   }, [router, checkNonce]);
 
+  const finishLogin = (data: any, fallbackName?: string) => {
+    const { access_token, refresh_token, user } = data;
+    setToken(access_token, refresh_token);
+    authLogin(user);
+    message.success(`欢迎回来，${user?.username ?? fallbackName ?? ""}！`);
+    router.push("/dashboard");
+  };
+
+  const loginErrorMessage = (error: any) => {
+    const data = error?.response?.data;
+    if (error?.response?.status === 429 && data?.retry_after) {
+      return `尝试次数过多，请 ${data.retry_after} 秒后再试`;
+    }
+    return data?.error || "登录失败";
+  };
+
   const onFinish = async (values: LoginValues) => {
     setLoading(true);
     try {
       const response = await login(values.username, values.password);
-      const { access_token, refresh_token, user } = response.data as any;
-
-      setToken(access_token, refresh_token);
-      authLogin(user);
-      message.success(`欢迎回来，${user?.username ?? values.username}！`);
+      const data = response.data as any;
+      if (data?.mfa_required) {
+        setMfaToken(data.mfa_token);
+        return;
+      }
+      finishLogin(data, values.username);
+    } catch (error: any) {
+      message.error(loginErrorMessage(error));
+    } finally {
+      setLoading(false);
+    }
+  };
 
-      router.push("/dashboard");
+  const onFinishTwoFactor = async (values: { code: string }) => {
+    if (!mfaToken) return;
+    setLoading(true);
+    try {
+      const response = await loginTwoFactor(mfaToken, values.code.trim());
+      finishLogin(response.data);
     } catch (error: any) {
-      message.error(error?.response?.data?.error || "登录失败");
+      if (error?.response?.data?.error === "Invalid mfa token") {
+        setMfaToken(null);
+        message.error("验证已超时，请重新登录");
+      } else {
+        message.error(loginErrorMessage(error));
+      }
     } finally {
       setLoading(false);
     }
@@ -113,6 +147,28 @@ export default function LoginPage() {
             </Button>,
           ]}
         />
+      ) : mfaToken ? (
+        <Form name="login-2fa" onFinish={onFinishTwoFactor} autoComplete="off" layout="vertical" size="large">
+          <Form.Item
+            name="code"
+            extra="输入身份验证器中的 6 位验证码，或一个恢复码"
+            rules={[{ required: true, message: "请输入验证码" }]}
+          >
+            <Input prefix={<LockOutlined style={{ color: "rgba(0,0,0,.25)" }} />} placeholder="验证码" autoFocus />
+          </Form.Item>
+
+          <Form.Item style={{ marginBottom: 16 }}>
+            <Button type="primary" htmlType="submit" loading={loading} block size="large" icon={<LoginOutlined />}>
+              验证
+            </Button>
+          </Form.Item>
+
+          <div style={{ textAlign: "center" }}>
+            <Button type="link" onClick={() => setMfaToken(null)}>
+              返回重新登录
+            </Button>
+          </div>
+        </Form>
       ) : (
         <Form name="login" onFinish={onFinish} autoComplete="off" layout="vertical" size="large">
           <Form.Item name="username" rules={[{ required: true, message: "请输入用户名" }]}>
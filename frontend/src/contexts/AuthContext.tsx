@@ -1,7 +1,8 @@
 "use client";
 
 import React, { createContext, useContext, useEffect, useMemo, useState } from "react";
-import { getUser, isAuthenticated, logout as clearAuthStorage, setUser as persistUser } from "../lib/auth";
+import { logoutSession } from "../lib/api";
+import { getToken, getUser, isAuthenticated, logout as clearAuthStorage, setUser as persistUser } from "../lib/auth";
 
 type AuthContextValue = {
   user: any | null;
@@ -36,6 +37,9 @@ export function AuthProvider({ children }: { children: React.ReactNode }) {
         setAuthed(true);
       },
       logout: () => {
+        // 通知服务端吊销当前会话；失败不影响本地退出。
+        const { refreshToken } = getToken();
+        if (refreshToken) logoutSession(refreshToken).catch(() => undefined);
         setUser(null);
         setAuthed(false);
         clearAuthStorage();
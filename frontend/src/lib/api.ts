@@ -25,6 +25,38 @@ export const refreshToken = (refreshTokenValue: string) =>
 
 export const getCurrentUser = () => api.get("/auth/me");
 
+export const logoutSession = (refreshTokenValue: string) =>
+  publicApi.post("/auth/logout", { refresh_token: refreshTokenValue });
+
+export const listSessions = () => api.get("/auth/sessions");
+
+export const revokeSession = (sessionId: string) => api.delete(`/auth/sessions/${encodeURIComponent(sessionId)}`);
+
+export const revokeOtherSessions = () => api.delete("/auth/sessions");
+
+// personal access tokens
+export const listPersonalTokens = () => api.get("/auth/tokens");
+
+export const createPersonalToken = (payload: { name: string; scopes: string[]; expires_in_days?: number }) =>
+  api.post("/auth/tokens", payload);
+
+export const revokePersonalToken = (tokenId: string) => api.delete(`/auth/tokens/${encodeURIComponent(tokenId)}`);
+
+// two-factor authentication
+export const loginTwoFactor = (mfaToken: string, code: string) =>
+  publicApi.post("/auth/login/2fa", { mfa_token: mfaToken, code });
+
+export const getTwoFactorStatus = () => api.get("/auth/2fa");
+
+export const setupTwoFactor = () => api.post("/auth/2fa/setup");
+
+export const enableTwoFactor = (code: string) => api.post("/auth/2fa/enable", { code });
+
+export const disableTwoFactor = (password: string, code: string) =>
+  api.post("/auth/2fa/disable", { password, code });
+
+export const regenerateRecoveryCodes = (code: string) => api.post("/auth/2fa/recovery-codes", { code });
+
 export const changePassword = (oldPassword: string, newPassword: string) =>
   api.put("/auth/password", { old_password: oldPassword, new_password: newPassword });
 
@@ -128,6 +160,9 @@ export const getBatchFundSignalsPage = (
   params?: { page?: number; page_size?: number }
 ) => api.get(`/funds/signals/batch_async/${encodeURIComponent(taskId)}`, { params });
 
+export const listMlModels = (params?: { peer_code?: string; task?: "dip_buy" | "magic_rebound" }) =>
+  api.get("/ml/models", { params });
+
 // tasks
 export const getTasksOverview = (params?: { queued_limit?: number; running_limit?: number; recent_limit?: number }) =>
   api.get("/tasks/overview", { params });
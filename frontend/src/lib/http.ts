@@ -50,8 +50,10 @@ api.interceptors.response.use(
         const resp = await publicApi.post("/auth/refresh", { refresh_token: refreshToken });
         const accessToken = resp.data?.access_token as string | undefined;
         if (!accessToken) throw new Error("refresh response missing access_token");
+        // 刷新令牌每次使用后轮换，旧令牌再次使用会导致整个会话被吊销。
+        const rotated = (resp.data?.refresh_token as string | undefined) ?? refreshToken;
 
-        setToken(accessToken, refreshToken);
+        setToken(accessToken, rotated);
         originalRequest.headers = originalRequest.headers ?? {};
         originalRequest.headers.Authorization = `Bearer ${accessToken}`;
         return api(originalRequest);